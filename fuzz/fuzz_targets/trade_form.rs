@@ -0,0 +1,18 @@
+//! Feeds arbitrary bytes into `TradeForm` deserialization and the trade validation path.
+//!
+//! Any form that passes `TradeForm::validate` must be convertible into a `Trade` without panicking.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use trade_management_system::services::trade::{fill_optional_fields, TradeForm};
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(form) = serde_json::from_slice::<TradeForm>(data) {
+        if form.validate().is_ok() {
+            let trade = fill_optional_fields(&form);
+            let _ = trade.calculate_trade_pnl();
+            let _ = trade.calculate_slippage();
+        }
+    }
+});
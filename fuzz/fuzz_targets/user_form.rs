@@ -0,0 +1,11 @@
+//! Feeds arbitrary bytes into `UserForm` and `LoginForm` deserialization.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use trade_management_system::services::user::{LoginForm, UserForm};
+
+fuzz_target!(|data: &[u8]| {
+    let _ = serde_json::from_slice::<UserForm>(data);
+    let _ = serde_json::from_slice::<LoginForm>(data);
+});
@@ -0,0 +1,5 @@
+-- This file should undo anything in `up.sql`
+DROP TABLE IF EXISTS `wallet_transfer_approvals`;
+DROP TABLE IF EXISTS `wallet_transfers`;
+DROP TABLE IF EXISTS `wallet_approvers`;
+DROP TABLE IF EXISTS `wallet_approval_policies`;
@@ -0,0 +1,41 @@
+-- Your SQL goes here
+CREATE TABLE IF NOT EXISTS wallet_approval_policies (
+    wallet_id CHARACTER(36) PRIMARY KEY NOT NULL,
+    threshold REAL NOT NULL,
+    required_approvals INTEGER NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (wallet_id) REFERENCES wallet(id)
+);
+
+CREATE TABLE IF NOT EXISTS wallet_approvers (
+    id CHARACTER(36) PRIMARY KEY NOT NULL,
+    wallet_id CHARACTER(36) NOT NULL,
+    user_id CHARACTER(36) NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (wallet_id) REFERENCES wallet(id),
+    FOREIGN KEY (user_id) REFERENCES users(id),
+    UNIQUE (wallet_id, user_id)
+);
+
+CREATE TABLE IF NOT EXISTS wallet_transfers (
+    id CHARACTER(36) PRIMARY KEY NOT NULL,
+    wallet_id CHARACTER(36) NOT NULL,
+    requested_by CHARACTER(36) NOT NULL,
+    amount REAL NOT NULL,
+    status VARCHAR(20) NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (wallet_id) REFERENCES wallet(id),
+    FOREIGN KEY (requested_by) REFERENCES users(id)
+);
+
+CREATE TABLE IF NOT EXISTS wallet_transfer_approvals (
+    id CHARACTER(36) PRIMARY KEY NOT NULL,
+    transfer_id CHARACTER(36) NOT NULL,
+    user_id CHARACTER(36) NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (transfer_id) REFERENCES wallet_transfers(id),
+    FOREIGN KEY (user_id) REFERENCES users(id),
+    UNIQUE (transfer_id, user_id)
+);
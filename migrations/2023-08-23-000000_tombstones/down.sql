@@ -0,0 +1,3 @@
+-- This file should undo anything in `up.sql`
+DROP INDEX IF EXISTS `tombstones_user_deleted_at`;
+DROP TABLE IF EXISTS `tombstones`;
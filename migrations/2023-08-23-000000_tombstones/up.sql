@@ -0,0 +1,10 @@
+-- Your SQL goes here
+CREATE TABLE IF NOT EXISTS tombstones (
+    id CHARACTER(36) PRIMARY KEY NOT NULL,
+    entity VARCHAR(20) NOT NULL,
+    entity_id CHARACTER(36) NOT NULL,
+    user_id CHARACTER(36) NOT NULL,
+    deleted_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS tombstones_user_deleted_at ON tombstones (user_id, deleted_at);
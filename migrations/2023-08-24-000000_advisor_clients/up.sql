@@ -0,0 +1,10 @@
+-- Your SQL goes here
+CREATE TABLE IF NOT EXISTS advisor_clients (
+    id CHARACTER(36) PRIMARY KEY NOT NULL,
+    advisor_id CHARACTER(36) NOT NULL,
+    client_id CHARACTER(36) NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (advisor_id) REFERENCES users(id),
+    FOREIGN KEY (client_id) REFERENCES users(id),
+    UNIQUE (advisor_id, client_id)
+);
@@ -0,0 +1,4 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE trades DROP COLUMN entered_by;
+DROP INDEX IF EXISTS `trade_delegations_owner_delegate`;
+DROP TABLE IF EXISTS `trade_delegations`;
@@ -0,0 +1,15 @@
+-- Your SQL goes here
+CREATE TABLE IF NOT EXISTS trade_delegations (
+    id CHARACTER(36) PRIMARY KEY NOT NULL,
+    owner_id CHARACTER(36) NOT NULL,
+    delegate_id CHARACTER(36) NOT NULL,
+    scope VARCHAR(20) NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    revoked_at TIMESTAMP,
+    FOREIGN KEY (owner_id) REFERENCES users(id),
+    FOREIGN KEY (delegate_id) REFERENCES users(id)
+);
+
+CREATE INDEX IF NOT EXISTS trade_delegations_owner_delegate ON trade_delegations (owner_id, delegate_id);
+
+ALTER TABLE trades ADD COLUMN entered_by CHARACTER(36);
@@ -0,0 +1,3 @@
+-- This file should undo anything in `up.sql`
+DROP INDEX IF EXISTS `trade_requests_owner_status`;
+DROP TABLE IF EXISTS `trade_requests`;
@@ -0,0 +1,16 @@
+-- Your SQL goes here
+CREATE TABLE IF NOT EXISTS trade_requests (
+    id CHARACTER(36) PRIMARY KEY NOT NULL,
+    owner_id CHARACTER(36) NOT NULL,
+    requested_by CHARACTER(36) NOT NULL,
+    payload TEXT NOT NULL,
+    status VARCHAR(20) NOT NULL,
+    trade_id CHARACTER(36),
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (owner_id) REFERENCES users(id),
+    FOREIGN KEY (requested_by) REFERENCES users(id),
+    FOREIGN KEY (trade_id) REFERENCES trades(id)
+);
+
+CREATE INDEX IF NOT EXISTS trade_requests_owner_status ON trade_requests (owner_id, status);
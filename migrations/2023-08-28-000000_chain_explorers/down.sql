@@ -0,0 +1,3 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE trades DROP COLUMN tx_hash;
+DROP TABLE IF EXISTS `chain_explorers`;
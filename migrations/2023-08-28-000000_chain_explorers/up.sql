@@ -0,0 +1,8 @@
+-- Your SQL goes here
+CREATE TABLE IF NOT EXISTS chain_explorers (
+    chain VARCHAR(20) PRIMARY KEY NOT NULL,
+    tx_url_template TEXT NOT NULL,
+    updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+ALTER TABLE trades ADD COLUMN tx_hash VARCHAR(66);
@@ -0,0 +1,11 @@
+-- Your SQL goes here
+CREATE TABLE IF NOT EXISTS positions (
+    user_id CHARACTER(36) NOT NULL,
+    asset VARCHAR(5) NOT NULL,
+    quantity REAL NOT NULL,
+    average_entry_price REAL NOT NULL,
+    realized_pnl REAL NOT NULL,
+    last_price REAL NOT NULL,
+    updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (user_id, asset)
+);
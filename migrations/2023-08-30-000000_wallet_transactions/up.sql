@@ -0,0 +1,19 @@
+-- Your SQL goes here
+CREATE TABLE IF NOT EXISTS wallet_transactions (
+    id CHARACTER(36) PRIMARY KEY NOT NULL,
+    wallet_id CHARACTER(36) NOT NULL,
+    kind VARCHAR(20) NOT NULL,
+    amount REAL NOT NULL,
+    balance_after REAL NOT NULL,
+    reference VARCHAR(255),
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (wallet_id) REFERENCES wallet(id)
+);
+
+CREATE INDEX IF NOT EXISTS wallet_transactions_wallet_created ON wallet_transactions (wallet_id, created_at);
+
+-- Open the ledger of existing wallets with their current balance.
+INSERT INTO wallet_transactions (id, wallet_id, kind, amount, balance_after, reference, created_at)
+SELECT lower(hex(randomblob(16))), id, 'Adjustment', balance, balance, 'Opening balance', CURRENT_TIMESTAMP
+FROM wallet
+WHERE balance <> 0;
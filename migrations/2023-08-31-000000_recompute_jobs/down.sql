@@ -0,0 +1,4 @@
+-- This file should undo anything in `up.sql`
+DROP TABLE IF EXISTS `recompute_jobs`;
+DROP TABLE IF EXISTS `daily_snapshots`;
+DROP TABLE IF EXISTS `audit_log`;
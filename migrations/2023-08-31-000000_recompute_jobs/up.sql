@@ -0,0 +1,36 @@
+-- Your SQL goes here
+CREATE TABLE IF NOT EXISTS audit_log (
+    id CHARACTER(36) PRIMARY KEY NOT NULL,
+    actor_id CHARACTER(36) NOT NULL,
+    action VARCHAR(50) NOT NULL,
+    target_id VARCHAR(255) NOT NULL,
+    details TEXT NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS audit_log_created ON audit_log (created_at);
+
+CREATE TABLE IF NOT EXISTS daily_snapshots (
+    user_id CHARACTER(36) NOT NULL,
+    date DATE NOT NULL,
+    trade_count INTEGER NOT NULL,
+    volume REAL NOT NULL,
+    fees REAL NOT NULL,
+    pnl REAL NOT NULL,
+    updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (user_id, date)
+);
+
+CREATE TABLE IF NOT EXISTS recompute_jobs (
+    id CHARACTER(36) PRIMARY KEY NOT NULL,
+    what VARCHAR(20) NOT NULL,
+    range_start TIMESTAMP,
+    range_end TIMESTAMP,
+    status VARCHAR(20) NOT NULL,
+    total INTEGER NOT NULL,
+    processed INTEGER NOT NULL,
+    error TEXT,
+    requested_by CHARACTER(36) NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
@@ -0,0 +1,16 @@
+-- Your SQL goes here
+CREATE TABLE IF NOT EXISTS fee_schedules (
+    id CHARACTER(36) PRIMARY KEY NOT NULL,
+    execution_rate REAL NOT NULL,
+    transaction_rate REAL NOT NULL,
+    effective_from TIMESTAMP NOT NULL,
+    effective_to TIMESTAMP,
+    created_by CHARACTER(36) NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS fee_schedules_effective_from ON fee_schedules (effective_from);
+
+-- The rates that applied before schedules were versioned cover every earlier trade.
+INSERT INTO fee_schedules (id, execution_rate, transaction_rate, effective_from, effective_to, created_by)
+VALUES ('00000000-0000-0000-0000-000000000000', 0.003, 0.005, '1970-01-01 00:00:00', NULL, 'system');
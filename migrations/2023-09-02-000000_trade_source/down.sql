@@ -0,0 +1,3 @@
+-- This file should undo anything in `up.sql`
+DROP INDEX IF EXISTS trades_user_source;
+ALTER TABLE trades DROP COLUMN source;
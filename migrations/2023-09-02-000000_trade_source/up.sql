@@ -0,0 +1,4 @@
+-- Your SQL goes here
+ALTER TABLE trades ADD COLUMN source VARCHAR(100) NOT NULL DEFAULT 'manual';
+
+CREATE INDEX IF NOT EXISTS trades_user_source ON trades (user_id, source);
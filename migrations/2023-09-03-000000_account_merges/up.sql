@@ -0,0 +1,11 @@
+-- Your SQL goes here
+CREATE TABLE IF NOT EXISTS account_merges (
+    id CHARACTER(36) PRIMARY KEY NOT NULL,
+    source_user_id CHARACTER(36) NOT NULL,
+    target_user_id CHARACTER(36) NOT NULL,
+    mapping TEXT NOT NULL,
+    merged_by CHARACTER(36) NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS account_merges_target ON account_merges (target_user_id);
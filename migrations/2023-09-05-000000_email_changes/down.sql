@@ -0,0 +1,3 @@
+-- This file should undo anything in `up.sql`
+DROP TABLE IF EXISTS `outbound_emails`;
+DROP TABLE IF EXISTS `email_changes`;
@@ -0,0 +1,21 @@
+-- Your SQL goes here
+CREATE TABLE IF NOT EXISTS user_settings (
+    user_id CHARACTER(36) PRIMARY KEY NOT NULL,
+    confirm_new_devices BOOLEAN NOT NULL DEFAULT 0,
+    updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (user_id) REFERENCES users(id)
+);
+
+CREATE TABLE IF NOT EXISTS known_devices (
+    id CHARACTER(36) PRIMARY KEY NOT NULL,
+    user_id CHARACTER(36) NOT NULL,
+    fingerprint CHARACTER(64) NOT NULL,
+    ip_address VARCHAR(255) NOT NULL,
+    user_agent TEXT NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    last_seen_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    confirmed_at TIMESTAMP,
+    FOREIGN KEY (user_id) REFERENCES users(id)
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS known_devices_user_fingerprint ON known_devices (user_id, fingerprint);
@@ -0,0 +1,4 @@
+-- This file should undo anything in `up.sql`
+DROP INDEX IF EXISTS trade_audit_trade_created;
+DROP TABLE IF EXISTS trade_audit;
+ALTER TABLE trades DROP COLUMN deleted_at;
@@ -0,0 +1,14 @@
+-- Your SQL goes here
+ALTER TABLE trades ADD COLUMN deleted_at TIMESTAMP;
+
+CREATE TABLE IF NOT EXISTS trade_audit (
+    id CHARACTER(36) PRIMARY KEY NOT NULL,
+    trade_id CHARACTER(36) NOT NULL,
+    action VARCHAR(20) NOT NULL,
+    actor_id CHARACTER(36) NOT NULL,
+    previous TEXT,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (trade_id) REFERENCES trades(id)
+);
+
+CREATE INDEX IF NOT EXISTS trade_audit_trade_created ON trade_audit (trade_id, created_at);
@@ -0,0 +1,3 @@
+-- This file should undo anything in `up.sql`
+DROP INDEX IF EXISTS login_attempts_email_created;
+DROP TABLE IF EXISTS login_attempts;
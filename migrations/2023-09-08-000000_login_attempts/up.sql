@@ -0,0 +1,10 @@
+-- Your SQL goes here
+CREATE TABLE IF NOT EXISTS login_attempts (
+    id CHARACTER(36) PRIMARY KEY NOT NULL,
+    email VARCHAR(255) NOT NULL,
+    user_id CHARACTER(36),
+    succeeded BOOLEAN NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS login_attempts_email_created ON login_attempts (email, created_at);
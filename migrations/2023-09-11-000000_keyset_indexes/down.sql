@@ -0,0 +1,4 @@
+-- This file should undo anything in `up.sql`
+DROP INDEX IF EXISTS wallet_transactions_wallet_created_id;
+DROP INDEX IF EXISTS trades_user_created_id;
+DROP INDEX IF EXISTS trades_created_id;
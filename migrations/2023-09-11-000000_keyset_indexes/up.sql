@@ -0,0 +1,5 @@
+-- Your SQL goes here
+-- Keyset pagination of trades and wallet ledgers continues after a (created_at, id) pair.
+CREATE INDEX IF NOT EXISTS trades_created_id ON trades (created_at, id);
+CREATE INDEX IF NOT EXISTS trades_user_created_id ON trades (user_id, created_at, id);
+CREATE INDEX IF NOT EXISTS wallet_transactions_wallet_created_id ON wallet_transactions (wallet_id, created_at, id);
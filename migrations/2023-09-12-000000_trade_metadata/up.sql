@@ -0,0 +1,3 @@
+-- Your SQL goes here
+-- A JSON object of string values, e.g. {"exchange_order_id": "8123", "client_tag": "grid-bot"}.
+ALTER TABLE trades ADD COLUMN metadata TEXT CHECK (metadata IS NULL OR json_type(metadata) = 'object');
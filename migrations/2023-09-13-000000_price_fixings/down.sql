@@ -0,0 +1,3 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE daily_snapshots DROP COLUMN fixing_id;
+DROP TABLE IF EXISTS `price_fixings`;
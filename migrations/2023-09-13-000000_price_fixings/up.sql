@@ -0,0 +1,18 @@
+-- Your SQL goes here
+CREATE TABLE IF NOT EXISTS price_fixings (
+    id CHARACTER(36) PRIMARY KEY NOT NULL,
+    source TEXT NOT NULL,
+    fixing_time TIME NOT NULL,
+    effective_from TIMESTAMP NOT NULL,
+    effective_to TIMESTAMP,
+    created_by CHARACTER(36) NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS price_fixings_effective_from ON price_fixings (effective_from);
+
+-- Days snapshotted before fixings were configurable were fixed at the defaults.
+INSERT INTO price_fixings (id, source, fixing_time, effective_from, effective_to, created_by)
+VALUES ('00000000-0000-0000-0000-000000000000', 'coingecko', '16:00:00', '1970-01-01 00:00:00', NULL, 'system');
+
+ALTER TABLE daily_snapshots ADD COLUMN fixing_id CHARACTER(36) NOT NULL DEFAULT '00000000-0000-0000-0000-000000000000';
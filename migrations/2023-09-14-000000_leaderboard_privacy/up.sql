@@ -0,0 +1,2 @@
+-- Your SQL goes here
+ALTER TABLE user_settings ADD COLUMN hide_from_leaderboard BOOLEAN NOT NULL DEFAULT 0;
@@ -0,0 +1,15 @@
+-- Your SQL goes here
+CREATE TABLE IF NOT EXISTS user_wallets (
+    wallet_id CHARACTER(36) PRIMARY KEY NOT NULL,
+    user_id CHARACTER(36) NOT NULL,
+    name VARCHAR(64) NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (wallet_id) REFERENCES wallet(id),
+    FOREIGN KEY (user_id) REFERENCES users(id)
+);
+
+CREATE INDEX IF NOT EXISTS user_wallets_user_id ON user_wallets (user_id);
+
+-- The wallet created at signup becomes each user's first named wallet.
+INSERT OR IGNORE INTO user_wallets (wallet_id, user_id, name, created_at)
+SELECT wallet_id, id, 'Main', created_at FROM users;
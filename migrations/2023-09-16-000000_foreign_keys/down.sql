@@ -0,0 +1,42 @@
+-- This file should undo anything in `up.sql`
+CREATE TABLE positions_old (
+    user_id CHARACTER(36) NOT NULL,
+    asset VARCHAR(5) NOT NULL,
+    quantity REAL NOT NULL,
+    average_entry_price REAL NOT NULL,
+    realized_pnl REAL NOT NULL,
+    last_price REAL NOT NULL,
+    updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (user_id, asset)
+);
+INSERT INTO positions_old SELECT * FROM positions;
+DROP TABLE positions;
+ALTER TABLE positions_old RENAME TO positions;
+
+CREATE TABLE daily_snapshots_old (
+    user_id CHARACTER(36) NOT NULL,
+    date DATE NOT NULL,
+    trade_count INTEGER NOT NULL,
+    volume REAL NOT NULL,
+    fees REAL NOT NULL,
+    pnl REAL NOT NULL,
+    updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    fixing_id CHARACTER(36) NOT NULL DEFAULT '00000000-0000-0000-0000-000000000000',
+    PRIMARY KEY (user_id, date)
+);
+INSERT INTO daily_snapshots_old SELECT * FROM daily_snapshots;
+DROP TABLE daily_snapshots;
+ALTER TABLE daily_snapshots_old RENAME TO daily_snapshots;
+
+CREATE TABLE tombstones_old (
+    id CHARACTER(36) PRIMARY KEY NOT NULL,
+    entity VARCHAR(20) NOT NULL,
+    entity_id CHARACTER(36) NOT NULL,
+    user_id CHARACTER(36) NOT NULL,
+    deleted_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+INSERT INTO tombstones_old SELECT * FROM tombstones;
+DROP TABLE tombstones;
+ALTER TABLE tombstones_old RENAME TO tombstones;
+
+CREATE INDEX IF NOT EXISTS tombstones_user_deleted_at ON tombstones (user_id, deleted_at);
@@ -0,0 +1,56 @@
+-- Your SQL goes here
+-- SQLite cannot add constraints to existing tables, so the tables created without foreign keys are rebuilt with them.
+-- Rows of users that no longer exist are dropped: positions and snapshots are derived from the trades, and tombstones
+-- are only read by their user.
+
+CREATE TABLE positions_new (
+    user_id CHARACTER(36) NOT NULL,
+    asset VARCHAR(5) NOT NULL,
+    quantity REAL NOT NULL,
+    average_entry_price REAL NOT NULL,
+    realized_pnl REAL NOT NULL,
+    last_price REAL NOT NULL,
+    updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (user_id, asset),
+    FOREIGN KEY (user_id) REFERENCES users(id)
+);
+INSERT INTO positions_new (user_id, asset, quantity, average_entry_price, realized_pnl, last_price, updated_at)
+SELECT user_id, asset, quantity, average_entry_price, realized_pnl, last_price, updated_at FROM positions
+WHERE user_id IN (SELECT id FROM users);
+DROP TABLE positions;
+ALTER TABLE positions_new RENAME TO positions;
+
+CREATE TABLE daily_snapshots_new (
+    user_id CHARACTER(36) NOT NULL,
+    date DATE NOT NULL,
+    trade_count INTEGER NOT NULL,
+    volume REAL NOT NULL,
+    fees REAL NOT NULL,
+    pnl REAL NOT NULL,
+    updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    fixing_id CHARACTER(36) NOT NULL DEFAULT '00000000-0000-0000-0000-000000000000',
+    PRIMARY KEY (user_id, date),
+    FOREIGN KEY (user_id) REFERENCES users(id),
+    FOREIGN KEY (fixing_id) REFERENCES price_fixings(id)
+);
+INSERT INTO daily_snapshots_new (user_id, date, trade_count, volume, fees, pnl, updated_at, fixing_id)
+SELECT user_id, date, trade_count, volume, fees, pnl, updated_at, fixing_id FROM daily_snapshots
+WHERE user_id IN (SELECT id FROM users) AND fixing_id IN (SELECT id FROM price_fixings);
+DROP TABLE daily_snapshots;
+ALTER TABLE daily_snapshots_new RENAME TO daily_snapshots;
+
+CREATE TABLE tombstones_new (
+    id CHARACTER(36) PRIMARY KEY NOT NULL,
+    entity VARCHAR(20) NOT NULL,
+    entity_id CHARACTER(36) NOT NULL,
+    user_id CHARACTER(36) NOT NULL,
+    deleted_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (user_id) REFERENCES users(id)
+);
+INSERT INTO tombstones_new (id, entity, entity_id, user_id, deleted_at)
+SELECT id, entity, entity_id, user_id, deleted_at FROM tombstones
+WHERE user_id IN (SELECT id FROM users);
+DROP TABLE tombstones;
+ALTER TABLE tombstones_new RENAME TO tombstones;
+
+CREATE INDEX IF NOT EXISTS tombstones_user_deleted_at ON tombstones (user_id, deleted_at);
@@ -0,0 +1,127 @@
+//! This module resolves the application's secrets without requiring them to be set in the process environment.
+//!
+//! `secret(name)` looks a secret up, in order:
+//!
+//! 1. in the environment variable `name` itself (e.g. `JWT_SECRET`), as before;
+//! 2. in the file named by `name_FILE` (e.g. `JWT_SECRET_FILE=/run/secrets/jwt`), the convention used by Docker and
+//!    Kubernetes secret mounts. One trailing newline is stripped. Files are read once and kept in memory, so rotating a
+//!    mounted secret takes a restart;
+//! 3. in HashiCorp Vault, when `VAULT_ADDR` and `VAULT_SECRET_PATH` are set. The secret at that path (e.g.
+//!    `secret/data/trade-management` for a KV v2 engine) is read once by `load`, with the token from `VAULT_TOKEN` (or
+//!    `VAULT_TOKEN_FILE`), and each of its string keys is a secret (`{"JWT_SECRET": "...", "DATABASE_URL": "..."}`).
+//!
+//! This covers every secret the application reads: `JWT_SECRET`, `DATABASE_URL`, the `*_SECRET` keys of the signed
+//! tokens and links, `INBOUND_EMAIL_SECRET`, and credentials such as `SMTP_PASSWORD` for mail integrations. Secrets
+//! resolved from files or Vault are never copied into the environment, so they do not show up in environment listings
+//! of the process (`/proc/<pid>/environ`, `ps e`).
+//!
+//! `load` is called once at startup. It reads every `*_FILE` variable and the Vault secret up front, so a missing
+//! file or an unreachable Vault stops the server from starting instead of failing the first request that needs it.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use crate::config;
+//!
+//! config::load()?;
+//! let secret = config::secret("JWT_SECRET").expect("JWT_SECRET must be set");
+//! ```
+
+use std::collections::BTreeMap;
+use std::sync::{Mutex, OnceLock};
+
+use dotenv::dotenv;
+
+// Non-secret settings: bind address, pool size, token lifetimes, logging and CORS.
+pub mod settings;
+
+pub const FILE_SUFFIX: &str = "_FILE";
+
+// Secrets read from `*_FILE` files, by name.
+static FILES: Mutex<BTreeMap<String, String>> = Mutex::new(BTreeMap::new());
+
+// Secrets read from Vault by `load`, by name.
+static VAULT: OnceLock<BTreeMap<String, String>> = OnceLock::new();
+
+pub fn read_secret_file(path: &str) -> Result<String, String> {
+    let contents = std::fs::read_to_string(path).map_err(|err| format!("{} cannot be read: {}", path, err))?;
+    let contents = contents.strip_suffix('\n').unwrap_or(&contents);
+    Ok(contents.strip_suffix('\r').unwrap_or(contents).to_string())
+}
+
+fn from_file(name: &str) -> Result<Option<String>, String> {
+    let mut files = FILES.lock().unwrap();
+    if let Some(value) = files.get(name) {
+        return Ok(Some(value.clone()));
+    }
+    let path = match std::env::var(format!("{}{}", name, FILE_SUFFIX)) {
+        Ok(path) if !path.is_empty() => path,
+        _ => return Ok(None),
+    };
+    let value = read_secret_file(&path)?;
+    files.insert(name.to_string(), value.clone());
+    Ok(Some(value))
+}
+
+pub fn secret(name: &str) -> Option<String> {
+    if let Ok(value) = std::env::var(name) {
+        if !value.is_empty() {
+            return Some(value);
+        }
+    }
+    match from_file(name) {
+        Ok(Some(value)) => return Some(value),
+        Ok(None) => (),
+        Err(err) => log::error!("{}{}: {}", name, FILE_SUFFIX, err),
+    }
+    VAULT.get().and_then(|secrets| secrets.get(name).cloned())
+}
+
+// The string values of a Vault read response; KV v2 engines nest them one level deeper than KV v1.
+pub fn vault_secrets(response: &serde_json::Value) -> BTreeMap<String, String> {
+    let data = &response["data"];
+    let data = if data["data"].is_object() && data["metadata"].is_object() { &data["data"] } else { data };
+    data.as_object()
+        .map(|values| {
+            values
+                .iter()
+                .filter_map(|(key, value)| value.as_str().map(|value| (key.clone(), value.to_string())))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn read_vault(addr: &str, path: &str) -> Result<BTreeMap<String, String>, String> {
+    let token = secret("VAULT_TOKEN").ok_or("VAULT_TOKEN or VAULT_TOKEN_FILE must be set to read from Vault")?;
+    let url = format!("{}/v1/{}", addr.trim_end_matches('/'), path.trim_start_matches('/'));
+    let response: serde_json::Value = ureq::get(&url)
+        .set("X-Vault-Token", &token)
+        .call()
+        .map_err(|err| format!("Vault request to {} failed: {}", url, err))?
+        .into_json()
+        .map_err(|err| format!("Vault response from {} is not JSON: {}", url, err))?;
+    Ok(vault_secrets(&response))
+}
+
+pub fn load() -> Result<(), String> {
+    dotenv().ok();
+
+    let names: Vec<String> = std::env::vars()
+        .filter_map(|(key, _)| key.strip_suffix(FILE_SUFFIX).map(str::to_string))
+        .collect();
+    for name in names {
+        from_file(&name).map_err(|err| format!("{}{}: {}", name, FILE_SUFFIX, err))?;
+    }
+
+    if let (Ok(addr), Ok(path)) = (std::env::var("VAULT_ADDR"), std::env::var("VAULT_SECRET_PATH")) {
+        let secrets = read_vault(&addr, &path)?;
+        log::info!("Loaded {} secret(s) from Vault", secrets.len());
+        VAULT.set(secrets).map_err(|_| "Vault secrets were already loaded".to_string())?;
+    }
+
+    Ok(())
+}
+
+// Import settings tests (only included in test builds)
+#[cfg(test)]
+mod settings_test;
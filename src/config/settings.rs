@@ -0,0 +1,223 @@
+//! This module defines the non-secret settings of the application: where the server listens and how many workers,
+//! connections and threads it runs with, the database pool, token lifetimes, account lockout, logging and CORS.
+//!
+//! Settings are read once, in order of increasing precedence, from:
+//!
+//! 1. the built-in defaults (`Settings::default`), which match the values previously hard-coded in `main`;
+//! 2. a TOML file, `settings.toml` in the working directory or the path in `CONFIG_FILE`. The file is optional;
+//!    see `settings.example.toml` for every key;
+//! 3. environment variables prefixed with `APP_`, with `__` between nested keys: `APP_SERVER__PORT=8080`,
+//!    `APP_DATABASE__POOL_SIZE=20`, `APP_CORS__ALLOWED_ORIGINS=['https://app.example.com']`.
+//!
+//! Secrets are not settings: `JWT_SECRET` and friends are still resolved by `config::secret`. The database URL may be
+//! given as `database.url`; when it is not, `DATABASE_URL` is looked up as a secret, as before.
+//!
+//! `load` is called by `main` before anything else and fails on a malformed file or value. `get` returns the loaded
+//! settings, or the defaults (with environment overrides) when `load` was never called, as in tests.
+//!
+//! The worker count and the blocking thread pool size default to values derived from the machine, like Actix's own
+//! defaults; `ServerSettings::workers` and `ServerSettings::blocking_threads` resolve them, and `main` logs the
+//! effective values at startup.
+//!
+//! `server.read_only` turns an instance into a read-only replica for dashboard traffic (see `services::replica`).
+//! `database.auto_migrate` applies pending database migrations at startup (see `db::establish_connection`).
+//!
+//! # Examples
+//!
+//! ```rust
+//! use crate::config::settings;
+//!
+//! let settings = settings::load()?;
+//! println!("Listening on {}:{}", settings.server.host, settings.server.port);
+//!
+//! let minutes = settings::get().jwt.access_token_minutes;
+//! ```
+
+use std::path::Path;
+use std::sync::OnceLock;
+
+use figment::providers::{Env, Format, Serialized, Toml};
+use actix_web::http::{Method, Uri};
+use figment::Figment;
+use serde::{Deserialize, Serialize};
+
+use crate::db::models::refresh_token::REFRESH_TOKEN_DAYS;
+use crate::services::jwt::ACCESS_TOKEN_MINUTES;
+
+pub const DEFAULT_FILE: &str = "settings.toml";
+pub const ENV_PREFIX: &str = "APP_";
+
+static SETTINGS: OnceLock<Settings> = OnceLock::new();
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ServerSettings {
+    pub host: String,
+    pub port: u16,
+    // Largest accepted JSON body, in bytes.
+    pub json_limit: usize,
+    // HTTP worker threads. One per available CPU when not set.
+    pub workers: Option<usize>,
+    // Concurrent connections per worker; a worker stops accepting connections at the limit.
+    pub max_connections: usize,
+    // Time a client has to send the request head before getting a `408`, in milliseconds. `0` disables the timeout.
+    pub client_timeout_ms: u64,
+    // Threads per worker for blocking work (`web::block`). 512 shared between the workers when not set.
+    pub blocking_threads: Option<usize>,
+    // Run as a read-only replica: only the read routes of `services::replica` are served, from a database opened
+    // read-only, and nothing is written at startup.
+    pub read_only: bool,
+}
+
+impl ServerSettings {
+    pub fn workers(&self) -> usize {
+        self.workers.unwrap_or_else(|| std::thread::available_parallelism().map_or(1, |count| count.get()))
+    }
+
+    pub fn blocking_threads(&self) -> usize {
+        self.blocking_threads.unwrap_or_else(|| (512 / self.workers()).max(1))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DatabaseSettings {
+    pub url: Option<String>,
+    pub pool_size: u32,
+    // Apply pending migrations at startup. Turn off when they are run separately before each deployment.
+    pub auto_migrate: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JwtSettings {
+    pub access_token_minutes: i64,
+    pub refresh_token_days: i64,
+    // Allowed clock skew when checking the `exp` and `nbf` claims of access tokens, in seconds.
+    pub leeway_seconds: u64,
+    // `iss` and `aud` of the access tokens issued and accepted. Give each environment its own so tokens do not carry
+    // over from one to another.
+    pub issuer: String,
+    pub audience: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LoginSettings {
+    // Consecutive failed logins for an email after which it is locked.
+    pub max_failures: u32,
+    pub lockout_minutes: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LogSettings {
+    // A `tracing_subscriber::EnvFilter` directive such as `info` or `info,trade_management_system=debug`. `RUST_LOG`
+    // takes precedence when set.
+    pub level: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CorsSettings {
+    // Exact origins (`https://app.example.com`) allowed to call the API from a browser. Empty disables cross-origin
+    // requests.
+    pub allowed_origins: Vec<String>,
+    pub allowed_methods: Vec<String>,
+    pub allowed_headers: Vec<String>,
+    // How long browsers may cache a preflight response, in seconds.
+    pub max_age_seconds: usize,
+}
+
+impl Default for CorsSettings {
+    fn default() -> Self {
+        CorsSettings {
+            allowed_origins: Vec::new(),
+            allowed_methods: ["GET", "POST", "PUT", "DELETE"].map(String::from).to_vec(),
+            allowed_headers: ["Authorization", "Content-Type", "Accept"].map(String::from).to_vec(),
+            max_age_seconds: 3600,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Settings {
+    pub server: ServerSettings,
+    pub database: DatabaseSettings,
+    pub jwt: JwtSettings,
+    pub login: LoginSettings,
+    pub log: LogSettings,
+    pub cors: CorsSettings,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            server: ServerSettings {
+                host: "127.0.0.1".to_string(),
+                port: 9000,
+                json_limit: 4096,
+                workers: None,
+                max_connections: 25_000,
+                client_timeout_ms: 5000,
+                blocking_threads: None,
+                read_only: false,
+            },
+            database: DatabaseSettings { url: None, pool_size: 10, auto_migrate: true },
+            jwt: JwtSettings {
+                access_token_minutes: ACCESS_TOKEN_MINUTES,
+                refresh_token_days: REFRESH_TOKEN_DAYS,
+                leeway_seconds: 60,
+                issuer: "trade-management-system".to_string(),
+                audience: "trade-management-system".to_string(),
+            },
+            login: LoginSettings { max_failures: 5, lockout_minutes: 15 },
+            log: LogSettings { level: "debug".to_string() },
+            cors: CorsSettings::default(),
+        }
+    }
+}
+
+impl Settings {
+    // The defaults, overridden by the file at `path` (when it exists) and by the variables starting with `env_prefix`.
+    pub fn from_sources(path: &Path, env_prefix: &str) -> Result<Self, String> {
+        let settings: Settings = Figment::from(Serialized::defaults(Settings::default()))
+            .merge(Toml::file(path))
+            .merge(Env::prefixed(env_prefix).split("__"))
+            .extract()
+            .map_err(|err| format!("Invalid settings: {}", err))?;
+
+        if settings.server.workers == Some(0) || settings.server.max_connections == 0 || settings.server.blocking_threads == Some(0) {
+            return Err("Invalid settings: server.workers, server.max_connections and server.blocking_threads must be at least 1".to_string());
+        }
+        if settings.database.pool_size == 0 {
+            return Err("Invalid settings: database.pool_size must be at least 1".to_string());
+        }
+        if settings.jwt.access_token_minutes <= 0 || settings.jwt.refresh_token_days <= 0 {
+            return Err("Invalid settings: token lifetimes must be positive".to_string());
+        }
+        if settings.login.max_failures == 0 || settings.login.lockout_minutes <= 0 {
+            return Err("Invalid settings: login.max_failures and login.lockout_minutes must be positive".to_string());
+        }
+        if settings.jwt.issuer.is_empty() || settings.jwt.audience.is_empty() {
+            return Err("Invalid settings: jwt.issuer and jwt.audience must not be empty".to_string());
+        }
+        // `actix_cors` only reports these when the server starts, as a panic.
+        if let Some(origin) = settings.cors.allowed_origins.iter().find(|origin| origin.as_str() == "*" || origin.parse::<Uri>().is_err()) {
+            return Err(format!("Invalid settings: cors.allowed_origins has an invalid origin {:?}", origin));
+        }
+        if let Some(method) = settings.cors.allowed_methods.iter().find(|method| Method::from_bytes(method.as_bytes()).is_err()) {
+            return Err(format!("Invalid settings: cors.allowed_methods has an invalid method {:?}", method));
+        }
+        Ok(settings)
+    }
+
+    pub fn from_env() -> Result<Self, String> {
+        dotenv::dotenv().ok();
+        let path = std::env::var("CONFIG_FILE").unwrap_or_else(|_| DEFAULT_FILE.to_string());
+        Self::from_sources(Path::new(&path), ENV_PREFIX)
+    }
+}
+
+pub fn load() -> Result<&'static Settings, String> {
+    let settings = Settings::from_env()?;
+    Ok(SETTINGS.get_or_init(|| settings))
+}
+
+pub fn get() -> &'static Settings {
+    SETTINGS.get_or_init(|| Settings::from_env().unwrap_or_default())
+}
@@ -0,0 +1,102 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use uuid::Uuid;
+
+use super::settings::Settings;
+
+// Each test uses its own variable prefix so parallel tests do not see each other's overrides.
+fn prefix() -> String {
+    format!("TEST_{}_", Uuid::new_v4().simple()).to_uppercase()
+}
+
+fn write_file(contents: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("settings-{}.toml", Uuid::new_v4().simple()));
+    fs::write(&path, contents).unwrap();
+    path
+}
+
+#[test]
+fn test_defaults_without_file() {
+    let settings = Settings::from_sources(Path::new("/nonexistent/settings.toml"), &prefix()).unwrap();
+
+    assert_eq!(settings, Settings::default());
+    assert_eq!(settings.server.host, "127.0.0.1");
+    assert_eq!(settings.server.port, 9000);
+    assert_eq!(settings.server.json_limit, 4096);
+    assert_eq!(settings.jwt.access_token_minutes, 15);
+}
+
+#[test]
+fn test_file_overrides_defaults() {
+    let path = write_file(
+        "[server]\nport = 8080\n\n[database]\nurl = \"other.db\"\npool_size = 4\n\n[cors]\nallowed_origins = [\"https://app.example.com\"]\n",
+    );
+    let settings = Settings::from_sources(&path, &prefix()).unwrap();
+    fs::remove_file(&path).unwrap();
+
+    assert_eq!(settings.server.port, 8080);
+    // Keys missing from the file keep their defaults.
+    assert_eq!(settings.server.host, "127.0.0.1");
+    assert_eq!(settings.database.url.as_deref(), Some("other.db"));
+    assert_eq!(settings.database.pool_size, 4);
+    assert!(settings.database.auto_migrate);
+    assert_eq!(settings.cors.allowed_origins, vec!["https://app.example.com".to_string()]);
+}
+
+#[test]
+fn test_env_overrides_file() {
+    let prefix = prefix();
+    let path = write_file("[server]\nport = 8080\nhost = \"0.0.0.0\"\n");
+    std::env::set_var(format!("{}SERVER__PORT", prefix), "7000");
+    std::env::set_var(format!("{}JWT__ACCESS_TOKEN_MINUTES", prefix), "5");
+    std::env::set_var(format!("{}JWT__LEEWAY_SECONDS", prefix), "120");
+    let settings = Settings::from_sources(&path, &prefix).unwrap();
+    fs::remove_file(&path).unwrap();
+
+    assert_eq!(settings.server.port, 7000);
+    assert_eq!(settings.server.host, "0.0.0.0");
+    assert_eq!(settings.jwt.access_token_minutes, 5);
+    assert_eq!(settings.jwt.leeway_seconds, 120);
+}
+
+#[test]
+fn test_invalid_settings_are_rejected() {
+    let path = write_file("[server]\nport = \"not a port\"\n");
+    assert!(Settings::from_sources(&path, &prefix()).is_err());
+    fs::remove_file(&path).unwrap();
+
+    let path = write_file("[server]\nworkers = 0\n");
+    assert!(Settings::from_sources(&path, &prefix()).is_err());
+    fs::remove_file(&path).unwrap();
+
+    let path = write_file("[database]\npool_size = 0\n");
+    assert!(Settings::from_sources(&path, &prefix()).is_err());
+    fs::remove_file(&path).unwrap();
+
+    let path = write_file("[cors]\nallowed_origins = [\"*\"]\n");
+    assert!(Settings::from_sources(&path, &prefix()).is_err());
+    fs::remove_file(&path).unwrap();
+
+    let path = write_file("[cors]\nallowed_origins = [\"*\"]\n");
+    assert!(Settings::from_sources(&path, &prefix()).is_err());
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_runtime_tuning() {
+    let defaults = Settings::from_sources(Path::new("/nonexistent/settings.toml"), &prefix()).unwrap().server;
+    assert!(defaults.workers() >= 1);
+    assert_eq!(defaults.blocking_threads(), (512 / defaults.workers()).max(1));
+    assert_eq!(defaults.client_timeout_ms, 5000);
+
+    let prefix = prefix();
+    std::env::set_var(format!("{}SERVER__WORKERS", prefix), "8");
+    std::env::set_var(format!("{}SERVER__MAX_CONNECTIONS", prefix), "1000");
+    let server = Settings::from_sources(Path::new("/nonexistent/settings.toml"), &prefix).unwrap().server;
+
+    assert_eq!(server.workers(), 8);
+    assert_eq!(server.max_connections, 1000);
+    // The blocking pool follows the worker count unless it is set.
+    assert_eq!(server.blocking_threads(), 64);
+}
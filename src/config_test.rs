@@ -0,0 +1,32 @@
+use std::io::Write;
+
+use super::config::{read_secret_file, secret, vault_secrets};
+
+#[test]
+fn secrets_are_read_from_files() {
+    let path = std::env::temp_dir().join(format!("config-test-{}", uuid::Uuid::new_v4().simple()));
+    std::fs::File::create(&path).unwrap().write_all(b"s3cr3t\n").unwrap();
+
+    assert_eq!(read_secret_file(path.to_str().unwrap()).unwrap(), "s3cr3t");
+    std::env::set_var("CONFIG_TEST_PASSWORD_FILE", &path);
+    assert_eq!(secret("CONFIG_TEST_PASSWORD"), Some("s3cr3t".to_string()));
+
+    std::env::set_var("CONFIG_TEST_PASSWORD", "from-env");
+    assert_eq!(secret("CONFIG_TEST_PASSWORD"), Some("from-env".to_string()));
+
+    std::fs::remove_file(&path).unwrap();
+    assert!(read_secret_file(path.to_str().unwrap()).is_err());
+    assert_eq!(secret("CONFIG_TEST_MISSING"), None);
+}
+
+#[test]
+fn vault_responses_of_both_kv_versions_are_read() {
+    let v1 = serde_json::json!({"data": {"JWT_SECRET": "jwt", "PORT": 8080}});
+    let v2 = serde_json::json!({"data": {"data": {"JWT_SECRET": "jwt"}, "metadata": {"version": 3}}});
+
+    for response in [v1, v2] {
+        let secrets = vault_secrets(&response);
+        assert_eq!(secrets.len(), 1);
+        assert_eq!(secrets["JWT_SECRET"], "jwt");
+    }
+}
@@ -16,54 +16,253 @@
 //! // Initialize the database connection pool.
 //! let pool: DbPool = establish_connection();
 //!
-//! // ... other database operations ...
+//! // Query from a handler without blocking the worker.
+//! let wallets = db::run(&pool, move |conn| Ok(Wallet::list(conn)?)).await?;
 //! ```
 //!
+//! Every pooled connection is configured with an SQLite `busy_timeout` (`DB_BUSY_TIMEOUT_MS`, default 5000) so that
+//! concurrent writers wait for the lock instead of failing immediately. Write paths additionally go through
+//! `retry::retry_on_busy` and report persistent lock contention as `error::DbError::Busy`.
+//!
+//! SQLite only enforces foreign keys on connections that ask for it, so every pooled connection also sets
+//! `PRAGMA foreign_keys`. A write that references a missing row, or deletes a row others still refer to, fails with a
+//! foreign key violation, which handlers answer with `409 Conflict` (see `error::DbError`).
+//!
+//! A read-only replica (`server.read_only`) opens the database with `mode=ro` (`read_only_url`) and additionally sets
+//! `PRAGMA query_only` on every connection, so any write fails with an error instead of reaching the file.
+//!
+//! Diesel's SQLite calls block, so handlers never query on the actix worker itself: `run` checks a connection out of
+//! the pool and calls the given closure with it on the worker's blocking thread pool (`web::block`, sized by
+//! `server.blocking_threads`), and the handler awaits the result. The closure returns `Result<T, AppError>` and gets
+//! everything it needs by value, since the request itself stays on the worker. A pool that has no connection to spare
+//! within its timeout is reported as `AppError::Busy`, like lock contention.
+//!
+//! Outside tests, `establish_connection` applies the pending migrations before handing out the pool and logs the ones
+//! it applied, unless `database.auto_migrate` is off: migrations are then expected to be run separately (e.g. with
+//! `diesel migration run`) and the pending ones are logged as a warning. Read-only replicas never migrate.
+//! `migration_status` lists the applied and pending migrations (see `services::migrations`).
+//!
+//! In test builds every pool is backed by its own uniquely named in-memory database, so tests stay isolated
+//! from each other while still sharing data across connections of the same pool. See the `fixtures` module. The
+//! outputs of the trade analytics are pinned by golden files (see the `golden` module and `testdata/golden`).
+//!
 //! # Note
+//! The pool holds up to `database.pool_size` connections (see `config::settings`), and the database is `database.url`
+//! when set, otherwise the `DATABASE_URL` secret.
+//!
 //! Make sure to configure your environment variables (e.g., `DATABASE_URL`) to ensure proper database connection setup and migration execution.
 
+use std::collections::HashMap;
 use std::env;
 use std::error::Error;
+use actix_web::web;
+use diesel::migration::MigrationSource;
+use diesel::sqlite::Sqlite;
 use diesel_migrations::MigrationHarness;
+use serde::Serialize;
 use dotenv::dotenv;
-use diesel::r2d2::{ConnectionManager, Pool};
+use diesel::connection::SimpleConnection;
+use diesel::r2d2::{ConnectionManager, CustomizeConnection, Pool};
 use diesel::sqlite::SqliteConnection;
+use uuid::Uuid;
+
+use crate::config::{self, settings};
+use crate::error::AppError;
 
+pub mod error;
 pub mod models;
+pub mod retry;
 pub mod schema;
 
+// Import test fixtures (only included in test builds)
+#[cfg(test)]
+pub mod fixtures;
+
+// Import fixture tests (only included in test builds)
+#[cfg(test)]
+mod fixtures_test;
+
+// Import golden-file datasets and assertions (only included in test builds)
+#[cfg(test)]
+pub mod golden;
+
+// Import golden-file analytics tests (only included in test builds)
+#[cfg(test)]
+mod golden_test;
+
+// Import retry tests (only included in test builds)
+#[cfg(test)]
+mod retry_test;
+
+// Import read-only pool tests (only included in test builds)
+#[cfg(test)]
+mod read_only_test;
+
+// Import foreign key tests (only included in test builds)
+#[cfg(test)]
+mod foreign_keys_test;
+
+// Import migration tests (only included in test builds)
+#[cfg(test)]
+mod migrations_test;
+
+// Import blocking query tests (only included in test builds)
+#[cfg(test)]
+mod run_test;
+
 pub type DbPool = Pool<ConnectionManager<SqliteConnection>>;
 
 pub const MIGRATIONS: diesel_migrations::EmbeddedMigrations = diesel_migrations::embed_migrations!("migrations");
 
+/// Applies per-connection SQLite settings when the pool opens a new connection.
+#[derive(Debug)]
+struct ConnectionOptions {
+    busy_timeout_ms: u64,
+    read_only: bool,
+}
+
+impl CustomizeConnection<SqliteConnection, diesel::r2d2::Error> for ConnectionOptions {
+    fn on_acquire(&self, conn: &mut SqliteConnection) -> Result<(), diesel::r2d2::Error> {
+        conn.batch_execute(&format!("PRAGMA busy_timeout = {}; PRAGMA foreign_keys = ON;", self.busy_timeout_ms))
+            .map_err(diesel::r2d2::Error::QueryError)?;
+        if self.read_only {
+            conn.batch_execute("PRAGMA query_only = 1;").map_err(diesel::r2d2::Error::QueryError)?;
+        }
+        Ok(())
+    }
+}
+
+fn connection_options(read_only: bool) -> ConnectionOptions {
+    let busy_timeout_ms = env::var("DB_BUSY_TIMEOUT_MS")
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(5000);
+
+    ConnectionOptions { busy_timeout_ms, read_only }
+}
+
+// The URI opening the database at `database_url` (a path or a `file:` URI) read-only.
+pub fn read_only_url(database_url: &str) -> String {
+    let uri = if database_url.starts_with("file:") { database_url.to_string() } else { format!("file:{}", database_url) };
+    let separator = if uri.contains('?') { '&' } else { '?' };
+    format!("{}{}mode=ro", uri, separator)
+}
+
+fn read_only_pool(database_url: &str, pool_size: u32) -> DbPool {
+    Pool::builder()
+        .max_size(pool_size)
+        .connection_customizer(Box::new(connection_options(true)))
+        .build(ConnectionManager::<SqliteConnection>::new(read_only_url(database_url)))
+        .expect("Failed to create DB pool.")
+}
+
 pub fn establish_connection() -> DbPool {
     dotenv().ok();
 
     if cfg!(test) {
-        let manager = ConnectionManager::<SqliteConnection>::new(":memory:");
-        let pool = Pool::builder().build(manager).expect("Failed to create DB pool.");
+        // Every pool gets its own named in-memory database. The shared cache lets all pooled
+        // connections see the same data, while the unique name keeps parallel tests isolated.
+        let database_url = format!("file:test-{}?mode=memory&cache=shared", Uuid::new_v4().simple());
+        let manager = ConnectionManager::<SqliteConnection>::new(database_url);
+        let pool = Pool::builder()
+            .connection_customizer(Box::new(connection_options(false)))
+            .build(manager)
+            .expect("Failed to create DB pool.");
         let mut conn = pool.get().expect("Failed to get a connection from the pool");
         
         run_migrations(&mut conn).expect("Failed to run migrations");
         pool
     } else {
     
-        let database_url = env::var("DATABASE_URL").expect("DATABASE_URL must be set");
+        let database = &settings::get().database;
+        let database_url = database.url.clone()
+            .or_else(|| config::secret("DATABASE_URL"))
+            .expect("DATABASE_URL must be set");
+        if settings::get().server.read_only {
+            return read_only_pool(&database_url, database.pool_size);
+        }
         let manager = ConnectionManager::<SqliteConnection>::new(database_url);
         
-        let pool = Pool::builder().build(manager).expect("Failed to create DB pool.");
+        let pool = Pool::builder()
+            .max_size(database.pool_size)
+            .connection_customizer(Box::new(connection_options(false)))
+            .build(manager)
+            .expect("Failed to create DB pool.");
+        let mut conn = pool.get().expect("Failed to get a connection from the pool");
+        if database.auto_migrate {
+            let applied = run_migrations(&mut conn).expect("Failed to run migrations");
+            if !applied.is_empty() {
+                log::info!("Applied {} database migration(s): {}", applied.len(), applied.join(", "));
+            }
+        } else {
+            let pending = migration_status(&mut conn).expect("Failed to read the applied migrations").pending;
+            if !pending.is_empty() {
+                log::warn!("database.auto_migrate is off and {} migration(s) are pending: {}", pending.len(), pending.join(", "));
+            }
+        }
+        drop(conn);
         pool
     }
 }
 
-fn run_migrations(connection: &mut SqliteConnection) -> Result<(), Box<dyn Error + Send + Sync + 'static>> {
+// Runs `f` with a pooled connection on the blocking thread pool. See the module documentation.
+pub async fn run<T, F>(pool: &DbPool, f: F) -> Result<T, AppError>
+where
+    F: FnOnce(&mut SqliteConnection) -> Result<T, AppError> + Send + 'static,
+    T: Send + 'static,
+{
+    let pool = pool.clone();
+    web::block(move || {
+        let mut conn = pool.get().map_err(|_| AppError::Busy)?;
+        f(&mut conn)
+    })
+    .await
+    .map_err(|err| AppError::Internal(format!("Database task failed: {}", err)))?
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+pub struct MigrationStatus {
+    pub applied: Vec<String>,
+    pub pending: Vec<String>,
+}
+
+// Runs the pending migrations and returns their names.
+fn run_migrations(connection: &mut SqliteConnection) -> Result<Vec<String>, Box<dyn Error + Send + Sync + 'static>> {
+    let names = migration_names()?;
 
     // This will run the necessary migrations.
     //
     // See the documentation for `MigrationHarness` for
     // all available methods.
-    connection.run_pending_migrations(MIGRATIONS)?;
+    let applied = connection.run_pending_migrations(MIGRATIONS)?;
+
+    Ok(applied.into_iter().map(|version| named(&names, version.to_string())).collect())
+}
+
+// The embedded migrations by version, e.g. "20230916000000" => "2023-09-16-000000_foreign_keys".
+fn migration_names() -> Result<HashMap<String, String>, Box<dyn Error + Send + Sync + 'static>> {
+    Ok(MigrationSource::<Sqlite>::migrations(&MIGRATIONS)?
+        .iter()
+        .map(|migration| (migration.name().version().to_string(), migration.name().to_string()))
+        .collect())
+}
+
+// Migrations applied by a newer build are not embedded in this one and keep their bare version.
+fn named(names: &HashMap<String, String>, version: String) -> String {
+    names.get(&version).cloned().unwrap_or(version)
+}
+
+// The applied migrations, oldest first, and the embedded ones not applied yet.
+pub fn migration_status(connection: &mut SqliteConnection) -> Result<MigrationStatus, Box<dyn Error + Send + Sync + 'static>> {
+    let names = migration_names()?;
+    let mut applied = connection.applied_migrations()?.into_iter().map(|version| version.to_string()).collect::<Vec<_>>();
+    applied.sort();
+    let pending = connection
+        .pending_migrations(MIGRATIONS)?
+        .iter()
+        .map(|migration| migration.name().to_string())
+        .collect();
 
-    Ok(())
+    Ok(MigrationStatus { applied: applied.into_iter().map(|version| named(&names, version)).collect(), pending })
 }
 
@@ -0,0 +1,74 @@
+//! This module defines the error type returned by database write paths.
+//!
+//! `DbError` separates transient lock contention (`Busy`), which clients may retry, from every other database failure
+//! (`Query`). It implements `ResponseError` by converting into `crate::error::AppError`, so handlers can turn it
+//! straight into an HTTP response: `Busy` becomes `503 Service Unavailable` with a `Retry-After` header, a missing
+//! row `404 Not Found`, a constraint violation `409 Conflict` and anything else `500 Internal Server Error`.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use actix_web::ResponseError;
+//! use crate::db::error::DbError;
+//!
+//! match Wallet::create(conn) {
+//!     Ok(Some(wallet)) => HttpResponse::Ok().json(wallet),
+//!     Ok(None) => HttpResponse::InternalServerError().json("Failed to create wallet"),
+//!     Err(err) => err.error_response(),
+//! }
+//! ```
+
+use std::fmt;
+
+use actix_web::http::StatusCode;
+use actix_web::{HttpResponse, ResponseError};
+use diesel::result::{DatabaseErrorKind, Error};
+
+use crate::error::AppError;
+
+#[derive(Debug)]
+pub enum DbError {
+    Busy,
+    Query(Error),
+}
+
+pub fn is_busy(err: &Error) -> bool {
+    match err {
+        Error::DatabaseError(DatabaseErrorKind::Unknown, info) => {
+            let message = info.message();
+            message.contains("database is locked") || message.contains("database table is locked")
+        }
+        _ => false,
+    }
+}
+
+impl From<Error> for DbError {
+    fn from(err: Error) -> Self {
+        if is_busy(&err) {
+            DbError::Busy
+        } else {
+            DbError::Query(err)
+        }
+    }
+}
+
+impl fmt::Display for DbError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DbError::Busy => write!(f, "Database is busy, please retry later"),
+            DbError::Query(err) => write!(f, "Database error: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for DbError {}
+
+impl ResponseError for DbError {
+    fn status_code(&self) -> StatusCode {
+        AppError::from(self).status_code()
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        AppError::from(self).error_response()
+    }
+}
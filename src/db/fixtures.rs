@@ -0,0 +1,111 @@
+//! This module provides database fixtures for tests.
+//!
+//! Each call to `test_pool` creates a fresh, fully migrated database that no other test can see, so tests
+//! can run in parallel without sharing rows. Pooled connections obtained from the same pool all point at
+//! the same database, which keeps multi-connection code paths testable.
+//!
+//! `with_rollback` runs a closure inside a transaction that is always rolled back, leaving the database
+//! untouched for the next assertion in the same test.
+//!
+//! `funded_wallet` creates a wallet holding `FUNDED_BALANCE`, enough for tests to settle any trade they create.
+//!
+//! `user` creates a user named `name` (`{name}@example.com`, password `TEST_PASSWORD`) on a funded wallet. `trade`
+//! builds a 1 ETH market buy at 100 on Ethereum for a user and wallet at `TRADE_TIMESTAMP`; tests override the
+//! fields they care about with struct update syntax, and `create_trade` stores the form as `Trade::create` would.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use crate::db::fixtures::{test_connection, with_rollback};
+//!
+//! let conn = &mut test_connection();
+//!
+//! with_rollback(conn, |conn| {
+//!     let wallet = Wallet::create(conn).unwrap().unwrap();
+//!     assert!(Wallet::find_by_id(conn, wallet.id).is_some());
+//! });
+//!
+//! let alice = user(conn, "alice");
+//! let sell = create_trade(conn, &TradeForm { trade_type: "MarketSell".to_string(), ..trade(&alice.id, &alice.wallet_id) });
+//! ```
+
+use diesel::r2d2::{ConnectionManager, PooledConnection};
+use diesel::result::Error;
+use diesel::sqlite::SqliteConnection;
+use diesel::Connection;
+
+use crate::services::trade::{fill_optional_fields, TradeForm};
+use super::models::trade::Trade;
+use super::models::user::User;
+use super::models::wallet::Wallet;
+use super::models::wallet_transaction::WalletTransaction;
+use super::{establish_connection, DbPool};
+
+pub const FUNDED_BALANCE: f32 = 1_000_000.0;
+pub const TEST_PASSWORD: &str = "test_password";
+// 2023-08-14 08:00:00 UTC.
+pub const TRADE_TIMESTAMP: i64 = 1_692_000_000;
+
+pub type TestConnection = PooledConnection<ConnectionManager<SqliteConnection>>;
+
+pub fn test_pool() -> DbPool {
+    establish_connection()
+}
+
+pub fn test_connection() -> TestConnection {
+    test_pool().get().expect("Failed to get a connection from the pool")
+}
+
+pub fn funded_wallet(conn: &mut SqliteConnection) -> Wallet {
+    let wallet = Wallet::create(conn).unwrap().unwrap();
+    WalletTransaction::deposit(conn, wallet.id.clone(), FUNDED_BALANCE, None).unwrap();
+    Wallet::find_by_id(conn, wallet.id).unwrap().unwrap()
+}
+
+pub fn user(conn: &mut SqliteConnection, name: &str) -> User {
+    let wallet = funded_wallet(conn);
+    let (user, errors) = User::create(conn, name.to_string(), format!("{}@example.com", name), wallet.id, TEST_PASSWORD.to_string()).unwrap();
+    user.unwrap_or_else(|| panic!("Failed to create user {}: {:?}", name, errors))
+}
+
+pub fn trade(user_id: &str, wallet_id: &str) -> TradeForm {
+    TradeForm {
+        user_id: user_id.to_string(),
+        wallet_id: wallet_id.to_string(),
+        amount: 100.0,
+        chain: "Ethereum".to_string(),
+        trade_type: "MarketBuy".to_string(),
+        asset: "ETH".to_string(),
+        before_price: None,
+        execution_price: Some(100.0),
+        final_price: Some(100.0),
+        traded_amount: Some(1.0),
+        timestamp: Some(TRADE_TIMESTAMP),
+        entered_by: None,
+        tx_hash: None,
+        source: None,
+        metadata: None,
+    }
+}
+
+pub fn create_trade(conn: &mut SqliteConnection, form: &TradeForm) -> Trade {
+    let (trade, errors) = Trade::create(conn, &mut fill_optional_fields(form)).unwrap();
+    trade.unwrap_or_else(|| panic!("Failed to create trade: {:?}", errors))
+}
+
+pub fn with_rollback<T, F>(conn: &mut SqliteConnection, f: F) -> T
+where
+    F: FnOnce(&mut SqliteConnection) -> T,
+{
+    let mut output = None;
+    let result = conn.transaction::<(), Error, _>(|conn| {
+        output = Some(f(conn));
+        Err(Error::RollbackTransaction)
+    });
+
+    match result {
+        Err(Error::RollbackTransaction) => output.expect("fixture closure did not run"),
+        Err(err) => panic!("Failed to roll back test transaction: {}", err),
+        Ok(()) => unreachable!(),
+    }
+}
@@ -0,0 +1,38 @@
+use super::fixtures::{test_connection, test_pool, with_rollback};
+use super::models::wallet::Wallet;
+
+#[test]
+fn pools_are_isolated() {
+    let conn = &mut test_connection();
+    let other_conn = &mut test_connection();
+
+    let wallet = Wallet::create(conn).unwrap().unwrap();
+
+    assert!(Wallet::find_by_id(conn, wallet.id.clone()).unwrap().is_some());
+    assert!(Wallet::find_by_id(other_conn, wallet.id).unwrap().is_none());
+}
+
+#[test]
+fn pooled_connections_share_database() {
+    let pool = test_pool();
+    let conn = &mut pool.get().unwrap();
+    let other_conn = &mut pool.get().unwrap();
+
+    let wallet = Wallet::create(conn).unwrap().unwrap();
+
+    assert!(Wallet::find_by_id(other_conn, wallet.id).unwrap().is_some());
+}
+
+#[test]
+fn rollback_discards_changes() {
+    let conn = &mut test_connection();
+
+    let wallet_id = with_rollback(conn, |conn| {
+        let wallet = Wallet::create(conn).unwrap().unwrap();
+        assert!(Wallet::find_by_id(conn, wallet.id.clone()).unwrap().is_some());
+        wallet.id
+    });
+
+    assert!(Wallet::find_by_id(conn, wallet_id).unwrap().is_none());
+    assert!(Wallet::list(conn).unwrap().is_empty());
+}
@@ -0,0 +1,41 @@
+use actix_web::http::StatusCode;
+use actix_web::ResponseError;
+use diesel::prelude::*;
+
+use crate::services::trade::fill_optional_fields;
+use super::error::DbError;
+use super::fixtures::{funded_wallet, test_connection, trade, user};
+use super::models::trade::Trade;
+use super::models::user::User;
+use super::models::user_settings::UserSettings;
+use super::schema::trades;
+
+#[test]
+fn trades_of_missing_users_are_conflicts() {
+    let conn = &mut test_connection();
+    let wallet = funded_wallet(conn);
+
+    let mut orphan = fill_optional_fields(&trade("missing", &wallet.id));
+
+    // `Trade::create` refuses the wallet before the insert; the constraint still guards direct writes.
+    let (created, errors) = Trade::create(conn, &mut orphan).unwrap();
+    assert!(created.is_none() && errors.is_some());
+    let err = DbError::from(diesel::insert_into(trades::table).values(&orphan).execute(conn).unwrap_err());
+    assert_eq!(err.status_code(), StatusCode::CONFLICT);
+    assert_eq!(Trade::list(conn).unwrap().len(), 0);
+}
+
+#[test]
+fn users_are_only_deleted_once_nothing_refers_to_them() {
+    let conn = &mut test_connection();
+    let (idle, trader) = (user(conn, "idle"), user(conn, "trader"));
+    UserSettings::update(conn, idle.id.clone(), true, false).unwrap();
+    Trade::create(conn, &mut fill_optional_fields(&trade(&trader.id, &trader.wallet_id))).unwrap();
+
+    assert!(User::delete(conn, idle.id.clone()).unwrap());
+    assert!(User::find_by_id(conn, idle.id).unwrap().is_none());
+
+    let err = User::delete(conn, trader.id.clone()).unwrap_err();
+    assert_eq!(err.status_code(), StatusCode::CONFLICT);
+    assert!(User::find_by_id(conn, trader.id).unwrap().is_some());
+}
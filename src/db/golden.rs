@@ -0,0 +1,128 @@
+//! This module provides golden-file tests for the trade analytics.
+//!
+//! A dataset is a canned list of trades in `testdata/golden/{dataset}/trades.json`, together with the date range the
+//! analytics are run over. `load` creates a user with a funded wallet in the given database and enters every trade
+//! through `Trade::create`, exactly as the API would. `Dataset::assert_golden` then compares an analytics result,
+//! serialized as pretty-printed JSON, byte for byte with the approved output in `testdata/golden/{dataset}/{output}.json`.
+//!
+//! The generated user ID is replaced by `<user_id>` before comparing, so outputs that name the trader stay stable.
+//!
+//! When a change to the math is intended, re-approve the outputs by running the tests with `UPDATE_GOLDEN=1` and
+//! review the diff of `testdata/golden` like any other change:
+//!
+//! ```sh
+//! UPDATE_GOLDEN=1 cargo test golden
+//! ```
+//!
+//! # Examples
+//!
+//! ```rust
+//! use crate::db::golden;
+//!
+//! let conn = &mut test_connection();
+//! let dataset = golden::load(conn, "round_trips");
+//! let fees = Trade::cumulative_fees(conn, dataset.start_date.clone(), dataset.end_date.clone(), dataset.user_id.clone(), &[])?;
+//! dataset.assert_golden("cumulative_fees", &fees);
+//! ```
+
+use std::fs;
+use std::path::PathBuf;
+
+use diesel::sqlite::SqliteConnection;
+use serde::{Deserialize, Serialize};
+
+use super::fixtures::funded_wallet;
+use super::models::trade::Trade;
+use super::models::user::User;
+use crate::services::trade::{fill_optional_fields, TradeForm};
+
+pub const GOLDEN_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/testdata/golden");
+
+#[derive(Deserialize)]
+struct GoldenTrade {
+    chain: String,
+    trade_type: String,
+    asset: String,
+    amount: f32,
+    before_price: Option<f32>,
+    execution_price: Option<f32>,
+    final_price: Option<f32>,
+    traded_amount: Option<f32>,
+    timestamp: i64,
+}
+
+#[derive(Deserialize)]
+struct GoldenFile {
+    start_date: String,
+    end_date: String,
+    trades: Vec<GoldenTrade>,
+}
+
+pub struct Dataset {
+    pub name: String,
+    pub user_id: String,
+    pub start_date: String,
+    pub end_date: String,
+}
+
+fn path(dataset: &str, file: &str) -> PathBuf {
+    PathBuf::from(GOLDEN_DIR).join(dataset).join(format!("{}.json", file))
+}
+
+pub fn load(conn: &mut SqliteConnection, name: &str) -> Dataset {
+    let file = fs::read_to_string(path(name, "trades")).unwrap_or_else(|err| panic!("Error reading golden dataset {}: {}", name, err));
+    let file: GoldenFile = serde_json::from_str(&file).unwrap_or_else(|err| panic!("Invalid golden dataset {}: {}", name, err));
+
+    let wallet = funded_wallet(conn);
+    let (user, _err) = User::create(conn, "golden".to_string(), format!("{}@golden.example.com", name), wallet.id.clone(), "golden_password".to_string()).unwrap();
+    let user = user.unwrap();
+
+    for (index, trade) in file.trades.into_iter().enumerate() {
+        let form = TradeForm {
+            user_id: user.id.clone(),
+            wallet_id: wallet.id.clone(),
+            amount: trade.amount,
+            chain: trade.chain,
+            trade_type: trade.trade_type,
+            asset: trade.asset,
+            before_price: trade.before_price,
+            execution_price: trade.execution_price,
+            final_price: trade.final_price,
+            traded_amount: trade.traded_amount,
+            timestamp: Some(trade.timestamp),
+            entered_by: None,
+            tx_hash: None,
+            source: None,
+            metadata: None,
+        };
+        match Trade::create(conn, &mut fill_optional_fields(&form)).unwrap() {
+            (Some(_), None) => (),
+            (_, errors) => panic!("Trade {} of golden dataset {} was rejected: {:?}", index, name, errors),
+        }
+    }
+
+    Dataset { name: name.to_string(), user_id: user.id, start_date: file.start_date, end_date: file.end_date }
+}
+
+impl Dataset {
+    pub fn assert_golden<T: Serialize>(&self, output: &str, value: &T) {
+        let actual = serde_json::to_string_pretty(value).unwrap().replace(&self.user_id, "<user_id>") + "\n";
+        let path = path(&self.name, output);
+
+        if std::env::var("UPDATE_GOLDEN").is_ok_and(|update| update == "1") {
+            fs::write(&path, &actual).unwrap_or_else(|err| panic!("Error writing {}: {}", path.display(), err));
+            return;
+        }
+
+        let expected = fs::read_to_string(&path)
+            .unwrap_or_else(|err| panic!("Error reading {} ({}); run with UPDATE_GOLDEN=1 to create it", path.display(), err));
+        assert!(
+            actual == expected,
+            "{} differs from the approved output {}; run with UPDATE_GOLDEN=1 to approve it\n--- expected\n{}\n--- actual\n{}",
+            output,
+            path.display(),
+            expected,
+            actual
+        );
+    }
+}
@@ -0,0 +1,30 @@
+use crate::db::fixtures::test_connection;
+use super::golden::{self, Dataset};
+use super::models::trade::Trade;
+use super::fixtures::TestConnection;
+
+fn assert_analytics(conn: &mut TestConnection, dataset: &Dataset) {
+    let (start, end, user_id) = (dataset.start_date.clone(), dataset.end_date.clone(), dataset.user_id.clone());
+
+    dataset.assert_golden("profit_loss", &Trade::profit_loss(conn, start.clone(), end.clone(), user_id.clone(), None, None, &[]).unwrap());
+    dataset.assert_golden("profit_loss_by_asset", &Trade::profit_loss_by_asset(conn, start.clone(), end.clone(), user_id.clone(), None, None, &[]).unwrap());
+    dataset.assert_golden("profit_loss_fifo", &Trade::profit_loss_fifo(conn, start.clone(), end.clone(), user_id.clone(), None, &[]).unwrap());
+    dataset.assert_golden("slippage", &Trade::get_slippage_bt_dates(conn, start.clone(), end.clone(), user_id.clone(), &[]).unwrap());
+    dataset.assert_golden("execution_quality", &Trade::execution_quality(conn, start.clone(), end.clone(), user_id.clone(), &[]).unwrap());
+    dataset.assert_golden("cumulative_fees", &Trade::cumulative_fees(conn, start.clone(), end.clone(), user_id.clone(), &[]).unwrap());
+    dataset.assert_golden("fee_breakdown", &Trade::fee_breakdown(conn, start, end, user_id, &[]).unwrap());
+}
+
+#[test]
+fn golden_round_trips() {
+    let conn = &mut test_connection();
+    let dataset = golden::load(conn, "round_trips");
+    assert_analytics(conn, &dataset);
+}
+
+#[test]
+fn golden_shorts() {
+    let conn = &mut test_connection();
+    let dataset = golden::load(conn, "shorts");
+    assert_analytics(conn, &dataset);
+}
@@ -0,0 +1,38 @@
+use diesel::migration::MigrationSource;
+use diesel::prelude::*;
+use diesel::sqlite::Sqlite;
+use uuid::Uuid;
+
+use super::fixtures::test_connection;
+use super::{migration_status, run_migrations, MIGRATIONS};
+
+#[test]
+fn pending_migrations_are_applied_in_order() {
+    let path = std::env::temp_dir().join(format!("migrations-{}.db", Uuid::new_v4().simple()));
+    let conn = &mut SqliteConnection::establish(path.to_str().unwrap()).unwrap();
+
+    let before = migration_status(conn).unwrap();
+    assert!(before.applied.is_empty());
+    assert_eq!(before.pending.first().map(String::as_str), Some("2023-08-13-014914_create"));
+
+    let applied = run_migrations(conn).unwrap();
+    assert_eq!(applied, before.pending);
+
+    let after = migration_status(conn).unwrap();
+    assert_eq!(after.applied, applied);
+    assert!(after.pending.is_empty());
+    assert!(run_migrations(conn).unwrap().is_empty());
+
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_databases_are_fully_migrated() {
+    let conn = &mut test_connection();
+    let status = migration_status(conn).unwrap();
+
+    let embedded = MigrationSource::<Sqlite>::migrations(&MIGRATIONS).unwrap();
+    assert!(status.pending.is_empty());
+    assert_eq!(status.applied.last(), embedded.last().map(|migration| migration.name().to_string()).as_ref());
+    assert_eq!(status.applied.len(), embedded.len());
+}
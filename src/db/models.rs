@@ -9,9 +9,59 @@
 //! - [`user`](user/index.html): Contains the `User` data model and related methods.
 //! - [`trade`](trade/index.html): Contains the `Trade` data model and related methods.
 //! - [`wallet`](wallet/index.html): Contains the `Wallet` data model and related methods.
+//! - [`transfer`](transfer/index.html): Contains wallet transfers and their multi-signature approval models.
+//! - [`email_review`](email_review/index.html): Contains the review queue for emailed trade confirmations.
+//! - [`summary`](summary/index.html): Contains the aggregated home-screen summary.
+//! - [`tombstone`](tombstone/index.html): Contains deletion records used by client sync.
+//! - [`advisor`](advisor/index.html): Contains advisor-to-client links and the consolidated client metrics.
+//! - [`delegation`](delegation/index.html): Contains the grants allowing a user to enter trades on someone else's behalf.
+//! - [`refresh_token`](refresh_token/index.html): Contains the refresh tokens of login sessions.
+//! - [`trade_request`](trade_request/index.html): Contains the inbox of trades proposed by assistants.
+//! - [`chain_explorer`](chain_explorer/index.html): Contains the block explorer templates configured per chain.
+//! - [`position`](position/index.html): Contains the per-asset positions derived from a user's trades.
+//! - [`wallet_transaction`](wallet_transaction/index.html): Contains the wallet ledger of credits and debits.
+//! - [`audit`](audit/index.html): Contains the audit log of administrative actions.
+//! - [`snapshot`](snapshot/index.html): Contains the daily per-user trade totals.
+//! - [`recompute`](recompute/index.html): Contains the admin jobs that re-derive stored values.
+//! - [`fee_schedule`](fee_schedule/index.html): Contains the fee rates in force over time.
+//! - [`fee_override`](fee_override/index.html): Contains the per-user and per-venue fee rates layered on the schedule.
+//! - [`price_fixing`](price_fixing/index.html): Contains the end-of-day price source and fixing time in force over time.
+//! - [`leaderboard`](leaderboard/index.html): Contains the rankings of traders by P&L and volume.
+//! - [`user_wallet`](user_wallet/index.html): Contains the named wallets each user owns.
+//! - [`account_merge`](account_merge/index.html): Contains the merges of duplicate user accounts.
+//! - [`outbound_email`](outbound_email/index.html): Contains the outbox of emails sent to users.
+//! - [`email_change`](email_change/index.html): Contains the verified email address change flow.
+//! - [`user_settings`](user_settings/index.html): Contains the per-user settings.
+//! - [`device`](device/index.html): Contains the devices users log in from, used to detect new devices.
+//! - [`trade_audit`](trade_audit/index.html): Contains the audit trail of changes made to trades.
+//! - [`login_attempt`](login_attempt/index.html): Contains the login attempts used to lock accounts after repeated failures.
+//! - [`password_reset`](password_reset/index.html): Contains the single-use tokens for resetting a forgotten password.
 //! - [`user_test`](user_test/index.html): Contains unit tests for the `User` data model.
 //! - [`trade_test`](trade_test/index.html): Contains unit tests for the `Trade` data model.
 //! - [`wallet_test`](wallet_test/index.html): Contains unit tests for the `Wallet` data model.
+//! - [`transfer_test`](transfer_test/index.html): Contains unit tests for the transfer approval flow.
+//! - [`email_review_test`](email_review_test/index.html): Contains unit tests for the email review queue.
+//! - [`summary_test`](summary_test/index.html): Contains unit tests for the home-screen summary.
+//! - [`tombstone_test`](tombstone_test/index.html): Contains unit tests for deletion tracking.
+//! - [`advisor_test`](advisor_test/index.html): Contains unit tests for advisor links and client metrics.
+//! - [`delegation_test`](delegation_test/index.html): Contains unit tests for delegated trade entry permissions.
+//! - [`refresh_token_test`](refresh_token_test/index.html): Contains unit tests for refresh token rotation and revocation.
+//! - [`trade_request_test`](trade_request_test/index.html): Contains unit tests for the trade request inbox.
+//! - [`chain_explorer_test`](chain_explorer_test/index.html): Contains unit tests for the chain explorer overrides.
+//! - [`position_test`](position_test/index.html): Contains unit tests for position tracking.
+//! - [`wallet_transaction_test`](wallet_transaction_test/index.html): Contains unit tests for the wallet ledger.
+//! - [`recompute_test`](recompute_test/index.html): Contains unit tests for recompute jobs and daily snapshots.
+//! - [`fee_schedule_test`](fee_schedule_test/index.html): Contains unit tests for fee schedule versioning.
+//! - [`account_merge_test`](account_merge_test/index.html): Contains unit tests for account merges.
+//! - [`email_change_test`](email_change_test/index.html): Contains unit tests for email address changes.
+//! - [`device_test`](device_test/index.html): Contains unit tests for new-device detection.
+//! - [`trade_audit_test`](trade_audit_test/index.html): Contains unit tests for trade soft deletes and their audit trail.
+//! - [`login_attempt_test`](login_attempt_test/index.html): Contains unit tests for account lockout.
+//! - [`password_reset_test`](password_reset_test/index.html): Contains unit tests for password resets.
+//! - [`fee_override_test`](fee_override_test/index.html): Contains unit tests for per-user fee overrides.
+//! - [`price_fixing_test`](price_fixing_test/index.html): Contains unit tests for price fixings and the snapshots fixed with them.
+//! - [`leaderboard_test`](leaderboard_test/index.html): Contains unit tests for the leaderboard rankings.
+//! - [`user_wallet_test`](user_wallet_test/index.html): Contains unit tests for named wallets and their ownership.
 //!
 //! # Examples
 //!
@@ -41,6 +91,187 @@ pub mod trade;
 // Import wallet data model
 pub mod wallet;
 
+// Import wallet transfer and approval data models
+pub mod transfer;
+
+// Import emailed trade confirmation review queue model
+pub mod email_review;
+
+// Import home-screen summary aggregates
+pub mod summary;
+
+// Import deletion records for client sync
+pub mod tombstone;
+
+// Import advisor client links and consolidated metrics
+pub mod advisor;
+
+// Import delegated trade entry permissions
+pub mod delegation;
+
+// Import login session refresh tokens
+pub mod refresh_token;
+
+// Import trade request inbox model
+pub mod trade_request;
+
+// Import chain registry explorer overrides
+pub mod chain_explorer;
+
+// Import positions derived from trades
+pub mod position;
+
+// Import wallet ledger model
+pub mod wallet_transaction;
+
+// Import audit log model
+pub mod audit;
+
+// Import daily trade snapshots
+pub mod snapshot;
+
+// Import admin recompute jobs
+pub mod recompute;
+
+// Import versioned fee schedules
+pub mod fee_schedule;
+
+// Import per-user fee overrides
+pub mod fee_override;
+
+// Import versioned end-of-day price fixings
+pub mod price_fixing;
+
+// Import the trader leaderboard
+pub mod leaderboard;
+
+// Import the named wallets of users
+pub mod user_wallet;
+
+// Import account merges
+pub mod account_merge;
+
+// Import the outbound email outbox
+pub mod outbound_email;
+
+// Import email address changes
+pub mod email_change;
+
+// Import user settings
+pub mod user_settings;
+
+// Import known login devices
+pub mod device;
+
+// Import the trade audit trail
+pub mod trade_audit;
+
+// Import login attempts and account lockout
+pub mod login_attempt;
+
+// Import password reset tokens
+pub mod password_reset;
+
+// Import user tests (only included in test builds)
+#[cfg(test)]
+mod user_test;
+
 // Import trade tests (only included in test builds)
 #[cfg(test)]
 mod trade_test;
+
+// Import wallet tests (only included in test builds)
+#[cfg(test)]
+mod wallet_test;
+
+// Import transfer tests (only included in test builds)
+#[cfg(test)]
+mod transfer_test;
+
+// Import email review tests (only included in test builds)
+#[cfg(test)]
+mod email_review_test;
+
+// Import summary tests (only included in test builds)
+#[cfg(test)]
+mod summary_test;
+
+// Import tombstone tests (only included in test builds)
+#[cfg(test)]
+mod tombstone_test;
+
+// Import advisor tests (only included in test builds)
+#[cfg(test)]
+mod advisor_test;
+
+// Import delegation tests (only included in test builds)
+#[cfg(test)]
+mod delegation_test;
+
+// Import refresh token tests (only included in test builds)
+#[cfg(test)]
+mod refresh_token_test;
+
+// Import trade request tests (only included in test builds)
+#[cfg(test)]
+mod trade_request_test;
+
+// Import chain explorer tests (only included in test builds)
+#[cfg(test)]
+mod chain_explorer_test;
+
+// Import position tests (only included in test builds)
+#[cfg(test)]
+mod position_test;
+
+// Import wallet ledger tests (only included in test builds)
+#[cfg(test)]
+mod wallet_transaction_test;
+
+// Import recompute job tests (only included in test builds)
+#[cfg(test)]
+mod recompute_test;
+
+// Import fee schedule tests (only included in test builds)
+#[cfg(test)]
+mod fee_schedule_test;
+
+// Import account merge tests (only included in test builds)
+#[cfg(test)]
+mod account_merge_test;
+
+// Import email change tests (only included in test builds)
+#[cfg(test)]
+mod email_change_test;
+
+// Import device tests (only included in test builds)
+#[cfg(test)]
+mod device_test;
+
+// Import trade audit tests (only included in test builds)
+#[cfg(test)]
+mod trade_audit_test;
+
+// Import login attempt tests (only included in test builds)
+#[cfg(test)]
+mod login_attempt_test;
+
+// Import password reset tests (only included in test builds)
+#[cfg(test)]
+mod password_reset_test;
+
+// Import fee override tests (only included in test builds)
+#[cfg(test)]
+mod fee_override_test;
+
+// Import price fixing tests (only included in test builds)
+#[cfg(test)]
+mod price_fixing_test;
+
+// Import leaderboard tests (only included in test builds)
+#[cfg(test)]
+mod leaderboard_test;
+
+// Import user wallet tests (only included in test builds)
+#[cfg(test)]
+mod user_wallet_test;
@@ -0,0 +1,319 @@
+//! This module defines account merges, which fold a duplicate user account into the account that survives it.
+//!
+//! `AccountMerge::merge` runs in one transaction and:
+//!
+//! - reassigns every row referring to the source user (trades, email reviews, tombstones, delegations, trade requests,
+//!   advisor links, wallet approvers, transfers, transfer approvals and known login devices) to the target user;
+//! - reassigns the source wallet's trades, ledger entries and transfers to the target wallet and adds the source
+//!   balance to it, so the target's ledger still sums to its balance. The source wallet's approval policy and
+//!   approvers move along when the target wallet has no policy of its own and are dropped otherwise;
+//! - moves the source user's other named wallets (`user_wallet`) to the target user, names included, and drops the
+//!   link of the source signup wallet along with the wallet;
+//! - drops delegations and advisor links between the two accounts, which would point an account at itself, and ends
+//!   the source user's sessions (refresh tokens), pending password resets and email changes. The source user's
+//!   settings are dropped in favour of the target's, as are source login devices the target already knows and the
+//!   source user's negotiated fee rates (`fee_override`), which the merged trades keep but new trades of the target
+//!   are not charged with;
+//! - deletes the source user and wallet, and rebuilds the target's positions (`position`) and daily snapshots
+//!   (`snapshot`) from the merged trades.
+//!
+//! The merge is stored with a `MergeMapping` listing the source user and wallet, the IDs of every reassigned row per
+//! table and column, and the full rows that were dropped, which is enough to undo the merge by hand. The password hash
+//! of the source user is not kept. Each merge is recorded in the audit log (`audit`) as `merge_accounts`.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use crate::models::account_merge::AccountMerge;
+//!
+//! let (merge, errors) = AccountMerge::merge(&mut connection, "duplicate_user_id".to_string(), "user_id".to_string(), "admin_id".to_string())?;
+//! let mapping = merge.unwrap().mapping();
+//! ```
+//!
+//! # Note
+//! This module assumes the availability of a database connection (`SqliteConnection` in this case) for merge data retrieval and manipulation.
+
+use uuid::Uuid;
+use serde::{Serialize, Deserialize};
+use diesel::prelude::*;
+use diesel::sql_types::Text;
+
+use super::super::error::DbError;
+use super::super::retry::retry_on_busy;
+use super::super::schema::{account_merges, advisor_clients, daily_snapshots, email_changes, fee_overrides, known_devices, password_resets, positions, refresh_tokens, trade_delegations, trades, user_settings, user_wallets, users, wallet, wallet_approval_policies, wallet_approvers};
+use super::advisor::AdvisorClient;
+use super::audit::AuditEntry;
+use super::delegation::TradeDelegation;
+use super::device::KnownDevice;
+use super::email_change::EmailChange;
+use super::fee_override::FeeOverride;
+use super::position::Position;
+use super::snapshot::DailySnapshot;
+use super::transfer::{ApprovalPolicy, Approver};
+use super::user::User;
+use super::user_settings::UserSettings;
+use super::user_wallet::UserWallet;
+use super::wallet::Wallet;
+
+// Columns holding a user ID, reassigned to the target user.
+const USER_COLUMNS: [(&str, &str); 13] = [
+    ("trades", "user_id"),
+    ("email_trade_reviews", "user_id"),
+    ("tombstones", "user_id"),
+    ("trade_delegations", "owner_id"),
+    ("trade_delegations", "delegate_id"),
+    ("trade_requests", "owner_id"),
+    ("trade_requests", "requested_by"),
+    ("advisor_clients", "advisor_id"),
+    ("advisor_clients", "client_id"),
+    ("wallet_approvers", "user_id"),
+    ("wallet_transfers", "requested_by"),
+    ("wallet_transfer_approvals", "user_id"),
+    ("known_devices", "user_id"),
+];
+
+// Columns holding a wallet ID, reassigned to the target user's wallet.
+const WALLET_COLUMNS: [(&str, &str); 3] = [
+    ("trades", "wallet_id"),
+    ("wallet_transactions", "wallet_id"),
+    ("wallet_transfers", "wallet_id"),
+];
+
+#[derive(Debug, Deserialize, Serialize, Queryable, Insertable)]
+#[diesel(table_name = crate::db::schema::account_merges)]
+pub struct AccountMerge {
+    pub id: String,
+    pub source_user_id: String,
+    pub target_user_id: String,
+    pub mapping: String,
+    pub merged_by: String,
+    pub created_at: chrono::NaiveDateTime,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct MovedRows {
+    pub table: String,
+    pub column: String,
+    pub ids: Vec<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct RemovedRows {
+    pub table: String,
+    pub rows: Vec<serde_json::Value>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct MergeMapping {
+    pub source_user: serde_json::Value,
+    pub source_wallet: Option<Wallet>,
+    pub target_wallet_id: String,
+    pub moved_balance: f32,
+    pub moved: Vec<MovedRows>,
+    pub removed: Vec<RemovedRows>,
+    pub ended_sessions: usize,
+}
+
+#[derive(QueryableByName)]
+struct IdRow {
+    #[diesel(sql_type = Text)]
+    id: String,
+}
+
+fn reassign(conn: &mut SqliteConnection, table: &str, column: &str, from: &str, to: &str) -> QueryResult<MovedRows> {
+    let ids = diesel::sql_query(format!("SELECT id FROM {} WHERE {} = ?", table, column))
+        .bind::<Text, _>(from)
+        .load::<IdRow>(conn)?
+        .into_iter()
+        .map(|row| row.id)
+        .collect();
+    diesel::sql_query(format!("UPDATE {} SET {} = ? WHERE {} = ?", table, column, column))
+        .bind::<Text, _>(to)
+        .bind::<Text, _>(from)
+        .execute(conn)?;
+
+    Ok(MovedRows { table: table.to_string(), column: column.to_string(), ids })
+}
+
+fn removed<T: Serialize>(table: &str, rows: &[T]) -> RemovedRows {
+    RemovedRows {
+        table: table.to_string(),
+        rows: rows.iter().map(|row| serde_json::to_value(row).expect("rows serialize")).collect(),
+    }
+}
+
+impl AccountMerge {
+    pub fn find_by_id(conn: &mut SqliteConnection, id: String) -> Result<Option<Self>, DbError> {
+        Ok(account_merges::table
+            .find(id)
+            .first::<AccountMerge>(conn)
+            .optional()?)
+    }
+
+    pub fn list(conn: &mut SqliteConnection) -> Result<Vec<Self>, DbError> {
+        Ok(account_merges::table
+            .order(account_merges::created_at.desc())
+            .load::<AccountMerge>(conn)?)
+    }
+
+    pub fn mapping(&self) -> MergeMapping {
+        serde_json::from_str(&self.mapping).expect("stored merge mapping is valid")
+    }
+
+    pub fn merge(conn: &mut SqliteConnection, source_user_id: String, target_user_id: String, merged_by: String) -> Result<(Option<Self>, Option<String>), DbError> {
+        if source_user_id == target_user_id {
+            return Ok((None, Some("Cannot merge an account into itself".to_string())));
+        }
+
+        let source = match User::find_by_id(conn, source_user_id)? {
+            Some(source) => source,
+            None => return Ok((None, Some("Source user does not exist".to_string()))),
+        };
+        let target = match User::find_by_id(conn, target_user_id)? {
+            Some(target) => target,
+            None => return Ok((None, Some("Target user does not exist".to_string()))),
+        };
+
+        let merge = retry_on_busy(|| conn.transaction(|conn| Self::apply(conn, &source, &target, &merged_by)))?;
+
+        let assets = trades::table
+            .filter(trades::user_id.eq(&target.id))
+            .select(trades::asset)
+            .distinct()
+            .load::<String>(conn)?;
+        for asset in assets {
+            Position::recompute(conn, &target.id, &asset)?;
+        }
+
+        Ok((Some(merge), None))
+    }
+
+    fn apply(conn: &mut SqliteConnection, source: &User, target: &User, merged_by: &str) -> QueryResult<Self> {
+        let mut moved = Vec::new();
+        let mut dropped = Vec::new();
+        let (s, t) = (&source.id, &target.id);
+
+        let delegations = trade_delegations::table
+            .filter(
+                trade_delegations::owner_id.eq(s).and(trade_delegations::delegate_id.eq(t))
+                    .or(trade_delegations::owner_id.eq(t).and(trade_delegations::delegate_id.eq(s))),
+            )
+            .load::<TradeDelegation>(conn)?;
+        let links = advisor_clients::table
+            .filter(
+                advisor_clients::advisor_id.eq(s).and(advisor_clients::client_id.eq(t))
+                    .or(advisor_clients::advisor_id.eq(t).and(advisor_clients::client_id.eq(s))),
+            )
+            .load::<AdvisorClient>(conn)?;
+        diesel::delete(trade_delegations::table.filter(trade_delegations::id.eq_any(delegations.iter().map(|row| &row.id)))).execute(conn)?;
+        diesel::delete(advisor_clients::table.filter(advisor_clients::id.eq_any(links.iter().map(|row| &row.id)))).execute(conn)?;
+        dropped.push(removed("trade_delegations", &delegations));
+        dropped.push(removed("advisor_clients", &links));
+
+        let settings = user_settings::table.find(s).load::<UserSettings>(conn)?;
+        let target_fingerprints = known_devices::table.filter(known_devices::user_id.eq(t)).select(known_devices::fingerprint).load::<String>(conn)?;
+        let devices = known_devices::table
+            .filter(known_devices::user_id.eq(s))
+            .filter(known_devices::fingerprint.eq_any(target_fingerprints))
+            .load::<KnownDevice>(conn)?;
+        diesel::delete(user_settings::table.find(s)).execute(conn)?;
+        diesel::delete(known_devices::table.filter(known_devices::id.eq_any(devices.iter().map(|row| &row.id)))).execute(conn)?;
+        dropped.push(removed("user_settings", &settings));
+        dropped.push(removed("known_devices", &devices));
+
+        let source_wallet = wallet::table.find(&source.wallet_id).first::<Wallet>(conn).optional()?;
+        let source_policy = wallet_approval_policies::table.find(&source.wallet_id).first::<ApprovalPolicy>(conn).optional()?;
+        if let Some(policy) = source_policy {
+            let target_has_policy = wallet_approval_policies::table.find(&target.wallet_id).first::<ApprovalPolicy>(conn).optional()?.is_some();
+            if target_has_policy {
+                let approvers = wallet_approvers::table.filter(wallet_approvers::wallet_id.eq(&source.wallet_id)).load::<Approver>(conn)?;
+                diesel::delete(wallet_approvers::table.filter(wallet_approvers::wallet_id.eq(&source.wallet_id))).execute(conn)?;
+                diesel::delete(wallet_approval_policies::table.find(&source.wallet_id)).execute(conn)?;
+                dropped.push(removed("wallet_approval_policies", &[policy]));
+                dropped.push(removed("wallet_approvers", &approvers));
+            } else {
+                diesel::update(wallet_approval_policies::table.find(&source.wallet_id))
+                    .set(wallet_approval_policies::wallet_id.eq(&target.wallet_id))
+                    .execute(conn)?;
+                moved.push(MovedRows { table: "wallet_approval_policies".to_string(), column: "wallet_id".to_string(), ids: vec![source.wallet_id.clone()] });
+                moved.push(reassign(conn, "wallet_approvers", "wallet_id", &source.wallet_id, &target.wallet_id)?);
+            }
+        }
+
+        let ended_sessions = diesel::delete(refresh_tokens::table.filter(refresh_tokens::user_id.eq(s))).execute(conn)?;
+        diesel::delete(password_resets::table.filter(password_resets::user_id.eq(s))).execute(conn)?;
+        let email_changes = email_changes::table.filter(email_changes::user_id.eq(s)).load::<EmailChange>(conn)?;
+        diesel::delete(email_changes::table.filter(email_changes::user_id.eq(s))).execute(conn)?;
+        dropped.push(removed("email_changes", &email_changes));
+        let overrides = FeeOverride::list_for_user(conn, s)?;
+        diesel::delete(fee_overrides::table.filter(fee_overrides::user_id.eq(s))).execute(conn)?;
+        dropped.push(removed("fee_overrides", &overrides));
+        let snapshot_dates = daily_snapshots::table
+            .filter(daily_snapshots::user_id.eq(s))
+            .select(daily_snapshots::date)
+            .load::<chrono::NaiveDate>(conn)?;
+        diesel::delete(daily_snapshots::table.filter(daily_snapshots::user_id.eq(s))).execute(conn)?;
+        diesel::delete(positions::table.filter(positions::user_id.eq(s))).execute(conn)?;
+
+        // The source's signup wallet goes away below; their other named wallets move to the target.
+        let source_link = user_wallets::table.find(&source.wallet_id).filter(user_wallets::user_id.eq(s));
+        let source_links = source_link.load::<UserWallet>(conn)?;
+        diesel::delete(source_link).execute(conn)?;
+        dropped.push(removed("user_wallets", &source_links));
+        let named_wallets = user_wallets::table.filter(user_wallets::user_id.eq(s)).select(user_wallets::wallet_id).load::<String>(conn)?;
+        diesel::update(user_wallets::table.filter(user_wallets::user_id.eq(s))).set(user_wallets::user_id.eq(t)).execute(conn)?;
+        moved.push(MovedRows { table: "user_wallets".to_string(), column: "user_id".to_string(), ids: named_wallets });
+
+        for (table, column) in USER_COLUMNS {
+            moved.push(reassign(conn, table, column, &source.id, &target.id)?);
+        }
+        for (table, column) in WALLET_COLUMNS {
+            moved.push(reassign(conn, table, column, &source.wallet_id, &target.wallet_id)?);
+        }
+        moved.retain(|rows| !rows.ids.is_empty());
+        dropped.retain(|rows| !rows.rows.is_empty());
+
+        let moved_balance = source_wallet.as_ref().map_or(0.0, |source_wallet| source_wallet.balance);
+        diesel::update(wallet::table.find(&target.wallet_id))
+            .set((wallet::balance.eq(wallet::balance + moved_balance), wallet::updated_at.eq(chrono::Local::now().naive_local())))
+            .execute(conn)?;
+        diesel::delete(users::table.find(&source.id)).execute(conn)?;
+        diesel::delete(wallet::table.find(&source.wallet_id)).execute(conn)?;
+
+        for date in snapshot_dates {
+            DailySnapshot::rebuild(conn, &target.id, date)?;
+        }
+
+        let mapping = MergeMapping {
+            source_user: serde_json::json!({
+                "id": source.id,
+                "name": source.name,
+                "email": source.email,
+                "wallet_id": source.wallet_id,
+                "created_at": source.created_at,
+                "updated_at": source.updated_at,
+            }),
+            source_wallet,
+            target_wallet_id: target.wallet_id.clone(),
+            moved_balance,
+            moved,
+            removed: dropped,
+            ended_sessions,
+        };
+        let merge = AccountMerge {
+            id: Uuid::new_v4().as_hyphenated().to_string(),
+            source_user_id: source.id.clone(),
+            target_user_id: target.id.clone(),
+            mapping: serde_json::to_string(&mapping).expect("merge mapping serializes"),
+            merged_by: merged_by.to_string(),
+            created_at: chrono::Local::now().naive_local(),
+        };
+        diesel::insert_into(account_merges::table).values(&merge).execute(conn)?;
+        AuditEntry::record(conn, merged_by, "merge_accounts", &merge.id, serde_json::json!({
+            "source_user_id": merge.source_user_id,
+            "target_user_id": merge.target_user_id,
+        }))?;
+
+        Ok(merge)
+    }
+}
@@ -0,0 +1,84 @@
+use diesel::prelude::*;
+
+use crate::db::fixtures::{self, test_connection, trade, user};
+use crate::db::schema::{trade_delegations, wallet_transactions};
+use crate::services::trade::TradeForm;
+use super::account_merge::AccountMerge;
+use super::audit::AuditEntry;
+use super::delegation::{DelegationScope, TradeDelegation};
+use super::position::Position;
+use super::refresh_token::RefreshToken;
+use super::trade::Trade;
+use super::user::User;
+use super::user_wallet::UserWallet;
+use super::wallet::Wallet;
+
+fn create_trade(conn: &mut SqliteConnection, user: &User, traded_amount: f32) -> Trade {
+    let form = TradeForm { amount: 100.0 * traded_amount, traded_amount: Some(traded_amount), ..trade(&user.id, &user.wallet_id) };
+    fixtures::create_trade(conn, &form)
+}
+
+#[test]
+fn merge_moves_trades_and_ledger_to_the_target() {
+    let conn = &mut test_connection();
+    let source = user(conn, "duplicate");
+    let target = user(conn, "user");
+    let moved = create_trade(conn, &source, 1.0);
+    create_trade(conn, &target, 2.0);
+    TradeDelegation::grant(conn, source.id.clone(), target.id.clone(), DelegationScope::CREATE.to_string()).unwrap();
+    RefreshToken::issue(conn, source.id.clone()).unwrap();
+    let savings = UserWallet::create(conn, &source.id, "Savings").unwrap().0.unwrap();
+    let expected_balance = Wallet::find_by_id(conn, source.wallet_id.clone()).unwrap().unwrap().balance
+        + Wallet::find_by_id(conn, target.wallet_id.clone()).unwrap().unwrap().balance;
+
+    let (merge, errors) = AccountMerge::merge(conn, source.id.clone(), target.id.clone(), "admin".to_string()).unwrap();
+    assert!(errors.is_none());
+    let merge = merge.unwrap();
+
+    assert!(User::find_by_id(conn, source.id.clone()).unwrap().is_none());
+    assert!(Wallet::find_by_id(conn, source.wallet_id.clone()).unwrap().is_none());
+    let trade = Trade::find_by_id(conn, moved.id.clone()).unwrap().unwrap();
+    assert_eq!((trade.user_id.as_str(), trade.wallet_id.as_str()), (target.id.as_str(), target.wallet_id.as_str()));
+    assert_eq!(Position::find(conn, target.id.clone(), "ETH".to_string()).unwrap().unwrap().quantity, 3.0);
+    assert!(Position::find(conn, source.id.clone(), "ETH".to_string()).unwrap().is_none());
+
+    let balance = Wallet::find_by_id(conn, target.wallet_id.clone()).unwrap().unwrap().balance;
+    let ledger = wallet_transactions::table
+        .filter(wallet_transactions::wallet_id.eq(&target.wallet_id))
+        .select(wallet_transactions::amount)
+        .load::<f32>(conn)
+        .unwrap();
+    assert_eq!(balance, expected_balance);
+    assert!((ledger.iter().sum::<f32>() - balance).abs() < 1.0);
+    assert_eq!(trade_delegations::table.count().get_result::<i64>(conn).unwrap(), 0);
+    let names: Vec<String> = UserWallet::list_for_user(conn, &target.id).unwrap().into_iter().map(|wallet| wallet.name).collect();
+    assert_eq!(names, vec!["Main", "Savings"]);
+    assert!(UserWallet::owns(conn, &target.id, &savings.id).unwrap());
+
+    let mapping = merge.mapping();
+    assert_eq!(mapping.source_user["email"], "duplicate@example.com");
+    assert!(mapping.source_user.get("password").is_none());
+    assert_eq!(mapping.ended_sessions, 1);
+    let trade_rows = mapping.moved.iter().find(|rows| rows.table == "trades" && rows.column == "user_id").unwrap();
+    assert_eq!(trade_rows.ids, vec![moved.id]);
+    assert_eq!(mapping.removed[0].table, "trade_delegations");
+
+    let audit = AuditEntry::list(conn, 10, 0).unwrap();
+    assert_eq!((audit[0].action.as_str(), &audit[0].target_id), ("merge_accounts", &merge.id));
+    assert_eq!(AccountMerge::list(conn).unwrap().len(), 1);
+}
+
+#[test]
+fn merge_rejects_missing_or_identical_accounts() {
+    let conn = &mut test_connection();
+    let user = user(conn, "user");
+
+    let (merge, errors) = AccountMerge::merge(conn, user.id.clone(), user.id.clone(), "admin".to_string()).unwrap();
+    assert!(merge.is_none());
+    assert_eq!(errors, Some("Cannot merge an account into itself".to_string()));
+    let (_, errors) = AccountMerge::merge(conn, "missing".to_string(), user.id.clone(), "admin".to_string()).unwrap();
+    assert_eq!(errors, Some("Source user does not exist".to_string()));
+    let (_, errors) = AccountMerge::merge(conn, user.id, "missing".to_string(), "admin".to_string()).unwrap();
+    assert_eq!(errors, Some("Target user does not exist".to_string()));
+    assert!(AccountMerge::list(conn).unwrap().is_empty());
+}
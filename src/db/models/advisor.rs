@@ -0,0 +1,169 @@
+//! This module links advisors to the client accounts they may view and aggregates metrics across those clients.
+//!
+//! An `AdvisorClient` row grants an advisor read-only access to one client. Granting is idempotent and an advisor
+//! cannot be linked to their own account. `ClientMetrics::for_advisor` returns, for every linked client, the number
+//! of trades, the P&L (same formula as `Trade::calculate_trade_pnl`, evaluated in SQL), the exposure (gross traded
+//! notional, `|execution_price * traded_amount|`) and the fees paid within a date range. Clients without trades in the
+//! range are still listed, with zero totals.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use crate::models::advisor::{AdvisorClient, ClientMetrics};
+//!
+//! AdvisorClient::grant(&mut connection, "advisor_id".to_string(), "client_id".to_string())?;
+//!
+//! let metrics = ClientMetrics::for_advisor(&mut connection, "advisor_id".to_string(), "start_date".to_string(), "end_date".to_string())?;
+//! let total_fees: f32 = metrics.iter().map(|client| client.fees).sum();
+//! ```
+//!
+//! # Note
+//! This module assumes the availability of a database connection (`SqliteConnection` in this case) for advisor data retrieval and manipulation.
+
+use uuid::Uuid;
+use serde::{Serialize, Deserialize};
+use diesel::prelude::*;
+use diesel::sql_types::{BigInt, Double, Nullable, Text};
+
+use super::super::error::DbError;
+use super::super::retry::retry_on_busy;
+use super::super::schema::advisor_clients;
+use super::summary::TRADE_PNL_SQL;
+use super::user::User;
+
+#[derive(Debug, Deserialize, Serialize, Queryable, Insertable)]
+#[diesel(table_name = crate::db::schema::advisor_clients)]
+pub struct AdvisorClient {
+    pub id: String,
+    pub advisor_id: String,
+    pub client_id: String,
+    pub created_at: chrono::NaiveDateTime,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ClientMetrics {
+    pub client_id: String,
+    pub name: String,
+    pub trade_count: i64,
+    pub pnl: f32,
+    pub exposure: f32,
+    pub fees: f32,
+}
+
+#[derive(QueryableByName)]
+struct MetricsRow {
+    #[diesel(sql_type = Text)]
+    client_id: String,
+    #[diesel(sql_type = Text)]
+    name: String,
+    #[diesel(sql_type = BigInt)]
+    trade_count: i64,
+    #[diesel(sql_type = Nullable<Double>)]
+    pnl: Option<f64>,
+    #[diesel(sql_type = Nullable<Double>)]
+    exposure: Option<f64>,
+    #[diesel(sql_type = Nullable<Double>)]
+    fees: Option<f64>,
+}
+
+impl AdvisorClient {
+    pub fn find(conn: &mut SqliteConnection, advisor_id: String, client_id: String) -> Result<Option<Self>, DbError> {
+        Ok(advisor_clients::table
+            .filter(advisor_clients::advisor_id.eq(advisor_id))
+            .filter(advisor_clients::client_id.eq(client_id))
+            .first::<AdvisorClient>(conn)
+            .optional()?)
+    }
+
+    pub fn list_for_advisor(conn: &mut SqliteConnection, advisor_id: String) -> Result<Vec<Self>, DbError> {
+        Ok(advisor_clients::table
+            .filter(advisor_clients::advisor_id.eq(advisor_id))
+            .order(advisor_clients::created_at.asc())
+            .load::<AdvisorClient>(conn)?)
+    }
+
+    pub fn grant(conn: &mut SqliteConnection, advisor_id: String, client_id: String) -> Result<(Option<Self>, Option<String>), DbError> {
+        if advisor_id == client_id {
+            return Ok((None, Some("Advisor cannot be their own client".to_string())));
+        }
+
+        if User::find_by_id(conn, advisor_id.clone())?.is_none() {
+            return Ok((None, Some("Advisor does not exist".to_string())));
+        }
+
+        if User::find_by_id(conn, client_id.clone())?.is_none() {
+            return Ok((None, Some("Client does not exist".to_string())));
+        }
+
+        if let Some(existing) = Self::find(conn, advisor_id.clone(), client_id.clone())? {
+            return Ok((Some(existing), None));
+        }
+
+        let link = AdvisorClient {
+            id: Uuid::new_v4().as_hyphenated().to_string(),
+            advisor_id: advisor_id.clone(),
+            client_id: client_id.clone(),
+            created_at: chrono::Local::now().naive_local(),
+        };
+        retry_on_busy(|| {
+            diesel::insert_into(advisor_clients::table)
+                .values(&link)
+                .execute(conn)
+        })?;
+
+        Ok((Self::find(conn, advisor_id, client_id)?, None))
+    }
+
+    pub fn revoke(conn: &mut SqliteConnection, advisor_id: String, client_id: String) -> Result<bool, DbError> {
+        let deleted = retry_on_busy(|| {
+            diesel::delete(
+                advisor_clients::table
+                    .filter(advisor_clients::advisor_id.eq(advisor_id.clone()))
+                    .filter(advisor_clients::client_id.eq(client_id.clone())),
+            )
+            .execute(conn)
+        })?;
+
+        Ok(deleted > 0)
+    }
+}
+
+impl ClientMetrics {
+    pub fn for_advisor(conn: &mut SqliteConnection, advisor_id: String, start_date: String, end_date: String) -> Result<Vec<Self>, DbError> {
+        let rows = diesel::sql_query(format!(
+            "SELECT users.id AS client_id, users.name AS name, COUNT(trades.id) AS trade_count, \
+                SUM({}) AS pnl, \
+                SUM(ABS(trades.execution_price * trades.traded_amount)) AS exposure, \
+                SUM(trades.execution_fee + trades.transaction_fee) AS fees \
+            FROM advisor_clients \
+            INNER JOIN users ON users.id = advisor_clients.client_id \
+            LEFT JOIN trades ON trades.user_id = users.id AND trades.deleted_at IS NULL AND trades.created_at >= ? AND trades.created_at <= ? \
+            WHERE advisor_clients.advisor_id = ? \
+            GROUP BY users.id, users.name \
+            ORDER BY users.name, users.id",
+            TRADE_PNL_SQL
+        ))
+        .bind::<Text, _>(start_date)
+        .bind::<Text, _>(end_date)
+        .bind::<Text, _>(advisor_id)
+        .load::<MetricsRow>(conn)?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| ClientMetrics {
+                client_id: row.client_id,
+                name: row.name,
+                trade_count: row.trade_count,
+                pnl: (row.pnl.unwrap_or(0.0) as f32).round(),
+                exposure: (row.exposure.unwrap_or(0.0) as f32).round(),
+                fees: (row.fees.unwrap_or(0.0) as f32).round(),
+            })
+            .collect())
+    }
+
+    pub fn for_client(conn: &mut SqliteConnection, advisor_id: String, client_id: String, start_date: String, end_date: String) -> Result<Option<Self>, DbError> {
+        Ok(Self::for_advisor(conn, advisor_id, start_date, end_date)?
+            .into_iter()
+            .find(|metrics| metrics.client_id == client_id))
+    }
+}
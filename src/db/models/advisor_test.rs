@@ -0,0 +1,74 @@
+use diesel::SqliteConnection;
+
+use crate::db::fixtures::{self, test_connection, trade, user};
+use crate::services::trade::TradeForm;
+use super::advisor::{AdvisorClient, ClientMetrics};
+use super::trade::Trade;
+use super::user::User;
+
+fn create_trade(conn: &mut SqliteConnection, user_id: &str, wallet_id: &str, timestamp: i64) -> Trade {
+    let form = TradeForm {
+        amount: 2.0,
+        trade_type: "LimitBuy".to_string(),
+        before_price: Some(10.0),
+        execution_price: Some(10.0),
+        final_price: Some(12.0),
+        traded_amount: Some(2.0),
+        timestamp: Some(timestamp),
+        ..trade(user_id, wallet_id)
+    };
+    fixtures::create_trade(conn, &form)
+}
+
+#[test]
+fn grants_are_validated_and_idempotent() {
+    let conn = &mut test_connection();
+    let advisor_id = user(conn, "advisor").id;
+    let client_id = user(conn, "client").id;
+
+    let (link, errors) = AdvisorClient::grant(conn, advisor_id.clone(), client_id.clone()).unwrap();
+    assert!(errors.is_none());
+    let link = link.unwrap();
+
+    let (again, _) = AdvisorClient::grant(conn, advisor_id.clone(), client_id.clone()).unwrap();
+    assert_eq!(again.unwrap().id, link.id);
+    assert_eq!(AdvisorClient::list_for_advisor(conn, advisor_id.clone()).unwrap().len(), 1);
+
+    let (_, errors) = AdvisorClient::grant(conn, advisor_id.clone(), advisor_id.clone()).unwrap();
+    assert_eq!(errors, Some("Advisor cannot be their own client".to_string()));
+    let (_, errors) = AdvisorClient::grant(conn, advisor_id.clone(), "missing".to_string()).unwrap();
+    assert_eq!(errors, Some("Client does not exist".to_string()));
+
+    assert!(AdvisorClient::revoke(conn, advisor_id.clone(), client_id.clone()).unwrap());
+    assert!(!AdvisorClient::revoke(conn, advisor_id.clone(), client_id).unwrap());
+    assert!(AdvisorClient::list_for_advisor(conn, advisor_id).unwrap().is_empty());
+}
+
+#[test]
+fn aggregates_metrics_per_linked_client() {
+    let conn = &mut test_connection();
+    let advisor_id = user(conn, "advisor").id;
+    let User { id: active_id, wallet_id: active_wallet, .. } = user(conn, "active");
+    let idle_id = user(conn, "idle").id;
+    let User { id: other_id, wallet_id: other_wallet, .. } = user(conn, "other");
+    AdvisorClient::grant(conn, advisor_id.clone(), active_id.clone()).unwrap();
+    AdvisorClient::grant(conn, advisor_id.clone(), idle_id.clone()).unwrap();
+
+    // 2023-08-01 and 2023-07-01 (UTC); only the first falls in the range below.
+    let trade = create_trade(conn, &active_id, &active_wallet, 1690891200);
+    create_trade(conn, &active_id, &active_wallet, 1688212800);
+    create_trade(conn, &other_id, &other_wallet, 1690891200);
+
+    let metrics = ClientMetrics::for_advisor(conn, advisor_id.clone(), "2023-08-01 00:00:00".to_string(), "2023-08-31 23:59:59".to_string()).unwrap();
+
+    assert_eq!(metrics.len(), 2);
+    let active = metrics.iter().find(|client| client.client_id == active_id).unwrap();
+    assert_eq!(active.trade_count, 1);
+    assert_eq!(active.pnl, trade.calculate_trade_pnl().round());
+    assert_eq!(active.exposure, (trade.execution_price * trade.traded_amount).round());
+    assert_eq!(active.fees, (trade.execution_fee + trade.transaction_fee).round());
+    let idle = metrics.iter().find(|client| client.client_id == idle_id).unwrap();
+    assert_eq!((idle.trade_count, idle.pnl, idle.fees), (0, 0.0, 0.0));
+
+    assert!(ClientMetrics::for_client(conn, advisor_id, other_id, "2023-08-01 00:00:00".to_string(), "2023-08-31 23:59:59".to_string()).unwrap().is_none());
+}
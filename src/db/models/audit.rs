@@ -0,0 +1,77 @@
+//! This module defines the audit log of administrative actions.
+//!
+//! An `AuditEntry` records who (`actor_id`) did what (`action`, e.g. `recompute`) to which object (`target_id`), with
+//! free-form JSON `details`. Entries are only ever appended. `record` takes the connection of an open transaction, so an
+//! entry can be written atomically with the change it describes.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use crate::models::audit::AuditEntry;
+//!
+//! AuditEntry::record(&mut connection, "admin_id", "recompute", &job.id, serde_json::json!({"what": "fees"}))?;
+//!
+//! let recent = AuditEntry::list(&mut connection, 50, 0)?;
+//!
+//! // The whole log, oldest first, a page at a time (see `services::audit_export`).
+//! let first = AuditEntry::export_page(&mut connection, None, 500)?;
+//! ```
+//!
+//! # Note
+//! This module assumes the availability of a database connection (`SqliteConnection` in this case) for audit data retrieval and manipulation.
+
+use uuid::Uuid;
+use serde::{Serialize, Deserialize};
+use diesel::prelude::*;
+
+use super::super::error::DbError;
+use super::super::schema::audit_log;
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize, Queryable, Insertable)]
+#[diesel(table_name = crate::db::schema::audit_log)]
+pub struct AuditEntry {
+    pub id: String,
+    pub actor_id: String,
+    pub action: String,
+    pub target_id: String,
+    pub details: String,
+    pub created_at: chrono::NaiveDateTime,
+}
+
+impl AuditEntry {
+    pub fn list(conn: &mut SqliteConnection, limit: i64, offset: i64) -> Result<Vec<Self>, DbError> {
+        Ok(audit_log::table
+            .order(audit_log::created_at.desc())
+            .limit(limit)
+            .offset(offset)
+            .load::<AuditEntry>(conn)?)
+    }
+
+    // Entries after `after` (a `created_at` and `id`), oldest first; ties on `created_at` are ordered by ID.
+    pub fn export_page(conn: &mut SqliteConnection, after: Option<&(chrono::NaiveDateTime, String)>, limit: i64) -> Result<Vec<Self>, DbError> {
+        let mut query = audit_log::table.into_boxed();
+        if let Some((created_at, id)) = after {
+            query = query.filter(audit_log::created_at.gt(*created_at).or(audit_log::created_at.eq(*created_at).and(audit_log::id.gt(id.clone()))));
+        }
+        Ok(query
+            .order((audit_log::created_at.asc(), audit_log::id.asc()))
+            .limit(limit)
+            .load::<AuditEntry>(conn)?)
+    }
+
+    pub fn record(conn: &mut SqliteConnection, actor_id: &str, action: &str, target_id: &str, details: serde_json::Value) -> QueryResult<Self> {
+        let entry = AuditEntry {
+            id: Uuid::new_v4().as_hyphenated().to_string(),
+            actor_id: actor_id.to_string(),
+            action: action.to_string(),
+            target_id: target_id.to_string(),
+            details: details.to_string(),
+            created_at: chrono::Local::now().naive_local(),
+        };
+        diesel::insert_into(audit_log::table)
+            .values(&entry)
+            .execute(conn)?;
+
+        Ok(entry)
+    }
+}
@@ -0,0 +1,82 @@
+//! This module defines the chain registry overrides for block explorer links.
+//!
+//! Every supported chain has a built-in transaction URL template (see `services::metadata`). A `ChainExplorer` row
+//! replaces that template for one chain, so operators can switch explorers without a release. Templates must be
+//! `https://` URLs containing the `{hash}` placeholder. Removing the row restores the built-in template.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use crate::models::chain_explorer::ChainExplorer;
+//!
+//! ChainExplorer::set(&mut connection, "Polygon".to_string(), "https://polygon.blockscout.com/tx/{hash}".to_string())?;
+//!
+//! let overrides = ChainExplorer::list(&mut connection)?;
+//!
+//! ChainExplorer::reset(&mut connection, "Polygon".to_string())?;
+//! ```
+//!
+//! # Note
+//! This module assumes the availability of a database connection (`SqliteConnection` in this case) for registry data retrieval and manipulation.
+
+use serde::{Serialize, Deserialize};
+use diesel::prelude::*;
+
+use super::super::error::DbError;
+use super::super::retry::retry_on_busy;
+use super::super::schema::chain_explorers;
+use super::trade::Chain;
+
+#[derive(Debug, Deserialize, Serialize, Queryable, Insertable)]
+#[diesel(table_name = crate::db::schema::chain_explorers)]
+pub struct ChainExplorer {
+    pub chain: String,
+    pub tx_url_template: String,
+    pub updated_at: chrono::NaiveDateTime,
+}
+
+impl ChainExplorer {
+    pub fn find(conn: &mut SqliteConnection, chain: String) -> Result<Option<Self>, DbError> {
+        Ok(chain_explorers::table
+            .find(chain)
+            .first::<ChainExplorer>(conn)
+            .optional()?)
+    }
+
+    pub fn list(conn: &mut SqliteConnection) -> Result<Vec<Self>, DbError> {
+        Ok(chain_explorers::table
+            .order(chain_explorers::chain.asc())
+            .load::<ChainExplorer>(conn)?)
+    }
+
+    pub fn set(conn: &mut SqliteConnection, chain: String, tx_url_template: String) -> Result<(Option<Self>, Option<String>), DbError> {
+        if !Chain::is_valid(&chain) {
+            return Ok((None, Some("Unknown chain".to_string())));
+        }
+
+        if !tx_url_template.starts_with("https://") || !tx_url_template.contains("{hash}") {
+            return Ok((None, Some("Template must be an https:// URL containing {hash}".to_string())));
+        }
+
+        let explorer = ChainExplorer {
+            chain: chain.clone(),
+            tx_url_template,
+            updated_at: chrono::Local::now().naive_local(),
+        };
+        retry_on_busy(|| {
+            diesel::replace_into(chain_explorers::table)
+                .values(&explorer)
+                .execute(conn)
+        })?;
+
+        Ok((Self::find(conn, chain)?, None))
+    }
+
+    pub fn reset(conn: &mut SqliteConnection, chain: String) -> Result<bool, DbError> {
+        let deleted = retry_on_busy(|| {
+            diesel::delete(chain_explorers::table.find(chain.clone())).execute(conn)
+        })?;
+
+        Ok(deleted > 0)
+    }
+}
@@ -0,0 +1,24 @@
+use crate::db::fixtures::test_connection;
+use super::chain_explorer::ChainExplorer;
+
+#[test]
+fn overrides_are_validated_and_resettable() {
+    let conn = &mut test_connection();
+
+    let (explorer, errors) = ChainExplorer::set(conn, "Polygon".to_string(), "https://polygon.blockscout.com/tx/{hash}".to_string()).unwrap();
+    assert!(errors.is_none());
+    assert_eq!(explorer.unwrap().tx_url_template, "https://polygon.blockscout.com/tx/{hash}");
+
+    let (_, errors) = ChainExplorer::set(conn, "Polygon".to_string(), "https://polygonscan.com/tx/{hash}".to_string()).unwrap();
+    assert!(errors.is_none());
+    assert_eq!(ChainExplorer::list(conn).unwrap().len(), 1);
+
+    let (_, errors) = ChainExplorer::set(conn, "Solana".to_string(), "https://solscan.io/tx/{hash}".to_string()).unwrap();
+    assert_eq!(errors, Some("Unknown chain".to_string()));
+    let (_, errors) = ChainExplorer::set(conn, "Polygon".to_string(), "http://polygonscan.com/tx/".to_string()).unwrap();
+    assert!(errors.is_some());
+
+    assert!(ChainExplorer::reset(conn, "Polygon".to_string()).unwrap());
+    assert!(!ChainExplorer::reset(conn, "Polygon".to_string()).unwrap());
+    assert!(ChainExplorer::list(conn).unwrap().is_empty());
+}
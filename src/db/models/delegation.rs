@@ -0,0 +1,141 @@
+//! This module defines delegated trade entry: a user (the owner) may allow another user (the delegate) to create,
+//! update or delete trades on their behalf.
+//!
+//! A `TradeDelegation` row grants one delegate one scope (`DelegationScope::CREATE`, `UPDATE` or `DELETE`) on the
+//! owner's trades, or `PROPOSE`, which only lets the delegate send trades to the owner's inbox for acceptance (see
+//! `trade_request`). Granting is idempotent and a user cannot delegate to themselves. Revoking stamps `revoked_at`
+//! instead of deleting the row, so past grants remain visible for auditing; a later grant creates a new row.
+//!
+//! `TradeDelegation::allows` is the permission check used by the trade service: owners may always act on their own
+//! trades, anyone else needs an active grant for the requested scope. Trades entered by a delegate record the
+//! delegate in `Trade::entered_by` next to the owner in `Trade::user_id`.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use crate::models::delegation::{DelegationScope, TradeDelegation};
+//!
+//! TradeDelegation::grant(&mut connection, "owner_id".to_string(), "assistant_id".to_string(), DelegationScope::CREATE.to_string())?;
+//!
+//! if TradeDelegation::allows(&mut connection, "owner_id", "assistant_id", DelegationScope::CREATE)? {
+//!     // ... create the trade for the owner ...
+//! }
+//! ```
+//!
+//! # Note
+//! This module assumes the availability of a database connection (`SqliteConnection` in this case) for delegation data retrieval and manipulation.
+
+use uuid::Uuid;
+use serde::{Serialize, Deserialize};
+use diesel::prelude::*;
+
+use super::super::error::DbError;
+use super::super::retry::retry_on_busy;
+use super::super::schema::trade_delegations;
+use super::user::User;
+
+#[derive(Debug, Deserialize, Serialize, Queryable, Insertable)]
+#[diesel(table_name = crate::db::schema::trade_delegations)]
+pub struct TradeDelegation {
+    pub id: String,
+    pub owner_id: String,
+    pub delegate_id: String,
+    pub scope: String,
+    pub created_at: chrono::NaiveDateTime,
+    pub revoked_at: Option<chrono::NaiveDateTime>,
+}
+
+pub struct DelegationScope;
+
+impl DelegationScope {
+    pub const CREATE: &'static str = "create";
+    pub const UPDATE: &'static str = "update";
+    pub const DELETE: &'static str = "delete";
+    pub const PROPOSE: &'static str = "propose";
+
+    pub fn is_valid(scope: &str) -> bool {
+        [Self::CREATE, Self::UPDATE, Self::DELETE, Self::PROPOSE].contains(&scope)
+    }
+}
+
+impl TradeDelegation {
+    pub fn find_active(conn: &mut SqliteConnection, owner_id: &str, delegate_id: &str, scope: &str) -> Result<Option<Self>, DbError> {
+        Ok(trade_delegations::table
+            .filter(trade_delegations::owner_id.eq(owner_id))
+            .filter(trade_delegations::delegate_id.eq(delegate_id))
+            .filter(trade_delegations::scope.eq(scope))
+            .filter(trade_delegations::revoked_at.is_null())
+            .first::<TradeDelegation>(conn)
+            .optional()?)
+    }
+
+    pub fn list_for_owner(conn: &mut SqliteConnection, owner_id: String) -> Result<Vec<Self>, DbError> {
+        Ok(trade_delegations::table
+            .filter(trade_delegations::owner_id.eq(owner_id))
+            .filter(trade_delegations::revoked_at.is_null())
+            .order(trade_delegations::created_at.asc())
+            .load::<TradeDelegation>(conn)?)
+    }
+
+    pub fn allows(conn: &mut SqliteConnection, owner_id: &str, actor_id: &str, scope: &str) -> Result<bool, DbError> {
+        if owner_id == actor_id {
+            return Ok(true);
+        }
+
+        Ok(Self::find_active(conn, owner_id, actor_id, scope)?.is_some())
+    }
+
+    pub fn grant(conn: &mut SqliteConnection, owner_id: String, delegate_id: String, scope: String) -> Result<(Option<Self>, Option<String>), DbError> {
+        if !DelegationScope::is_valid(&scope) {
+            return Ok((None, Some("Scope must be one of create, update, delete or propose".to_string())));
+        }
+
+        if owner_id == delegate_id {
+            return Ok((None, Some("Users cannot delegate to themselves".to_string())));
+        }
+
+        if User::find_by_id(conn, owner_id.clone())?.is_none() {
+            return Ok((None, Some("Owner does not exist".to_string())));
+        }
+
+        if User::find_by_id(conn, delegate_id.clone())?.is_none() {
+            return Ok((None, Some("Delegate does not exist".to_string())));
+        }
+
+        if let Some(existing) = Self::find_active(conn, &owner_id, &delegate_id, &scope)? {
+            return Ok((Some(existing), None));
+        }
+
+        let delegation = TradeDelegation {
+            id: Uuid::new_v4().as_hyphenated().to_string(),
+            owner_id: owner_id.clone(),
+            delegate_id: delegate_id.clone(),
+            scope: scope.clone(),
+            created_at: chrono::Local::now().naive_local(),
+            revoked_at: None,
+        };
+        retry_on_busy(|| {
+            diesel::insert_into(trade_delegations::table)
+                .values(&delegation)
+                .execute(conn)
+        })?;
+
+        Ok((Self::find_active(conn, &owner_id, &delegate_id, &scope)?, None))
+    }
+
+    pub fn revoke(conn: &mut SqliteConnection, owner_id: String, delegate_id: String, scope: String) -> Result<bool, DbError> {
+        let revoked = retry_on_busy(|| {
+            diesel::update(
+                trade_delegations::table
+                    .filter(trade_delegations::owner_id.eq(owner_id.clone()))
+                    .filter(trade_delegations::delegate_id.eq(delegate_id.clone()))
+                    .filter(trade_delegations::scope.eq(scope.clone()))
+                    .filter(trade_delegations::revoked_at.is_null()),
+            )
+            .set(trade_delegations::revoked_at.eq(chrono::Local::now().naive_local()))
+            .execute(conn)
+        })?;
+
+        Ok(revoked > 0)
+    }
+}
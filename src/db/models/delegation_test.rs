@@ -0,0 +1,50 @@
+use crate::db::fixtures::{test_connection, trade, user};
+use crate::services::trade::fill_optional_fields;
+use super::delegation::{DelegationScope, TradeDelegation};
+use super::trade::Trade;
+use super::user::User;
+
+#[test]
+fn grants_are_scoped_and_revocable() {
+    let conn = &mut test_connection();
+    let owner_id = user(conn, "owner").id;
+    let assistant_id = user(conn, "assistant").id;
+
+    assert!(TradeDelegation::allows(conn, &owner_id, &owner_id, DelegationScope::DELETE).unwrap());
+    assert!(!TradeDelegation::allows(conn, &owner_id, &assistant_id, DelegationScope::CREATE).unwrap());
+
+    let (delegation, errors) = TradeDelegation::grant(conn, owner_id.clone(), assistant_id.clone(), DelegationScope::CREATE.to_string()).unwrap();
+    assert!(errors.is_none());
+    let (again, _) = TradeDelegation::grant(conn, owner_id.clone(), assistant_id.clone(), DelegationScope::CREATE.to_string()).unwrap();
+    assert_eq!(again.unwrap().id, delegation.unwrap().id);
+
+    assert!(TradeDelegation::allows(conn, &owner_id, &assistant_id, DelegationScope::CREATE).unwrap());
+    assert!(!TradeDelegation::allows(conn, &owner_id, &assistant_id, DelegationScope::DELETE).unwrap());
+    assert!(!TradeDelegation::allows(conn, &assistant_id, &owner_id, DelegationScope::CREATE).unwrap());
+
+    let (_, errors) = TradeDelegation::grant(conn, owner_id.clone(), owner_id.clone(), DelegationScope::CREATE.to_string()).unwrap();
+    assert_eq!(errors, Some("Users cannot delegate to themselves".to_string()));
+    let (_, errors) = TradeDelegation::grant(conn, owner_id.clone(), assistant_id.clone(), "admin".to_string()).unwrap();
+    assert_eq!(errors, Some("Scope must be one of create, update, delete or propose".to_string()));
+
+    assert!(TradeDelegation::revoke(conn, owner_id.clone(), assistant_id.clone(), DelegationScope::CREATE.to_string()).unwrap());
+    assert!(!TradeDelegation::revoke(conn, owner_id.clone(), assistant_id.clone(), DelegationScope::CREATE.to_string()).unwrap());
+    assert!(!TradeDelegation::allows(conn, &owner_id, &assistant_id, DelegationScope::CREATE).unwrap());
+    assert!(TradeDelegation::list_for_owner(conn, owner_id).unwrap().is_empty());
+}
+
+#[test]
+fn trades_record_owner_and_actor() {
+    let conn = &mut test_connection();
+    let User { id: owner_id, wallet_id, .. } = user(conn, "owner");
+    let assistant_id = user(conn, "assistant").id;
+
+    let mut form = trade(&owner_id, &wallet_id);
+    let own = Trade::create(conn, &mut fill_optional_fields(&form)).unwrap().0.unwrap();
+    assert_eq!(own.entered_by, Some(owner_id.clone()));
+
+    form.entered_by = Some(assistant_id.clone());
+    let delegated = Trade::create(conn, &mut fill_optional_fields(&form)).unwrap().0.unwrap();
+    assert_eq!(delegated.user_id, owner_id);
+    assert_eq!(delegated.entered_by, Some(assistant_id));
+}
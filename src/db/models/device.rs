@@ -0,0 +1,177 @@
+//! This module defines the devices users log in from, used to detect logins from unseen devices.
+//!
+//! A device is identified by a fingerprint: a SHA-256 hash of the client's `User-Agent` and IP address, so a known
+//! browser on a new network counts as a new device. `KnownDevice::check_in` runs on every successful password check:
+//!
+//! - a confirmed device is trusted and its `last_seen_at` is updated;
+//! - the first device of a user is trusted without notice, since there is nothing to compare it with;
+//! - any other device triggers a security email (`outbound_email`) to the account's address. If the user enabled
+//!   `confirm_new_devices` (see `user_settings`), the device is stored unconfirmed and the email carries a signed
+//!   confirmation token; the login only completes once `KnownDevice::confirm` is called with it. Otherwise the device
+//!   is trusted right away.
+//!
+//! The confirmation token is the device ID followed by an HMAC-SHA256 over the device and the time of the login
+//! attempt, keyed with `DEVICE_CONFIRMATION_SECRET` (falling back to `JWT_SECRET`). It is valid for
+//! `DEVICE_CONFIRMATION_MINUTES`, and a new login attempt from the same device replaces it.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use crate::models::device::{DeviceCheck, KnownDevice};
+//!
+//! match KnownDevice::check_in(&mut connection, &user, "Mozilla/5.0 ...", "203.0.113.7")? {
+//!     DeviceCheck::Trusted(_) => { /* issue tokens */ }
+//!     DeviceCheck::NeedsConfirmation => { /* ask the user to check their email */ }
+//! }
+//! ```
+//!
+//! # Note
+//! This module assumes the availability of a database connection (`SqliteConnection` in this case) for device data retrieval and manipulation.
+
+use uuid::Uuid;
+use hmac::{Hmac, Mac};
+use sha2::{Digest, Sha256};
+use serde::{Serialize, Deserialize};
+use diesel::prelude::*;
+
+use crate::config;
+use super::super::error::DbError;
+use super::super::retry::retry_on_busy;
+use super::super::schema::known_devices;
+use super::outbound_email::OutboundEmail;
+use super::user::User;
+use super::user_settings::UserSettings;
+
+type HmacSha256 = Hmac<Sha256>;
+
+pub const DEVICE_CONFIRMATION_MINUTES: i64 = 30;
+
+#[derive(Debug, Deserialize, Serialize, Queryable, Insertable)]
+#[diesel(table_name = crate::db::schema::known_devices)]
+pub struct KnownDevice {
+    pub id: String,
+    pub user_id: String,
+    pub fingerprint: String,
+    pub ip_address: String,
+    pub user_agent: String,
+    pub created_at: chrono::NaiveDateTime,
+    pub last_seen_at: chrono::NaiveDateTime,
+    pub confirmed_at: Option<chrono::NaiveDateTime>,
+}
+
+#[derive(Debug)]
+pub enum DeviceCheck {
+    Trusted(KnownDevice),
+    NeedsConfirmation,
+}
+
+fn secret() -> String {
+    config::secret("DEVICE_CONFIRMATION_SECRET")
+        .or_else(|| config::secret("JWT_SECRET"))
+        .expect("DEVICE_CONFIRMATION_SECRET or JWT_SECRET must be set")
+}
+
+impl KnownDevice {
+    pub fn fingerprint(user_agent: &str, ip_address: &str) -> String {
+        hex::encode(Sha256::digest(format!("{}\n{}", user_agent, ip_address).as_bytes()))
+    }
+
+    pub fn list_for_user(conn: &mut SqliteConnection, user_id: String) -> Result<Vec<Self>, DbError> {
+        Ok(known_devices::table
+            .filter(known_devices::user_id.eq(user_id))
+            .order(known_devices::last_seen_at.desc())
+            .load::<KnownDevice>(conn)?)
+    }
+
+    fn mac(&self) -> HmacSha256 {
+        let mut mac = HmacSha256::new_from_slice(secret().as_bytes()).expect("HMAC accepts keys of any size");
+        mac.update(format!("{}\n{}\n{}\n{}", self.id, self.user_id, self.fingerprint, self.last_seen_at.format("%Y-%m-%d %H:%M:%S")).as_bytes());
+        mac
+    }
+
+    // The confirmation token; only ever sent to the account's email address.
+    pub fn token(&self) -> String {
+        format!("{}.{}", self.id, hex::encode(self.mac().finalize().into_bytes()))
+    }
+
+    pub fn check_in(conn: &mut SqliteConnection, user: &User, user_agent: &str, ip_address: &str) -> Result<DeviceCheck, DbError> {
+        let fingerprint = Self::fingerprint(user_agent, ip_address);
+        let now = chrono::Local::now().naive_local();
+
+        retry_on_busy(|| {
+            conn.transaction(|conn| {
+                let existing = known_devices::table
+                    .filter(known_devices::user_id.eq(&user.id))
+                    .filter(known_devices::fingerprint.eq(&fingerprint))
+                    .first::<KnownDevice>(conn)
+                    .optional()?;
+                let first_device = existing.is_none() && known_devices::table
+                    .filter(known_devices::user_id.eq(&user.id))
+                    .count()
+                    .get_result::<i64>(conn)? == 0;
+
+                let mut device = existing.unwrap_or_else(|| KnownDevice {
+                    id: Uuid::new_v4().as_hyphenated().to_string(),
+                    user_id: user.id.clone(),
+                    fingerprint: fingerprint.clone(),
+                    ip_address: ip_address.to_string(),
+                    user_agent: user_agent.to_string(),
+                    created_at: now,
+                    last_seen_at: now,
+                    confirmed_at: None,
+                });
+                device.last_seen_at = now;
+                if device.confirmed_at.is_some() || first_device {
+                    device.confirmed_at = device.confirmed_at.or(Some(now));
+                    diesel::replace_into(known_devices::table).values(&device).execute(conn)?;
+                    return Ok(DeviceCheck::Trusted(device));
+                }
+
+                let confirm = UserSettings::for_user(conn, user.id.clone())?.confirm_new_devices;
+                let mut body = format!(
+                    "Hi {},\n\nYour account was signed in to from a new device.\n\nDevice: {}\nIP address: {}\nTime: {}\n\n",
+                    user.name, user_agent, ip_address, now.format("%Y-%m-%d %H:%M:%S")
+                );
+                if confirm {
+                    body.push_str(&format!(
+                        "Confirm this login with the token below. It expires in {} minutes.\n\n{}\n\nIf this was not you, \
+                        change your password.",
+                        DEVICE_CONFIRMATION_MINUTES, device.token()
+                    ));
+                } else {
+                    device.confirmed_at = Some(now);
+                    body.push_str("If this was not you, change your password.");
+                }
+                diesel::replace_into(known_devices::table).values(&device).execute(conn)?;
+                OutboundEmail::queue(conn, &user.email, "New sign-in to your account", body)?;
+
+                Ok(if confirm { DeviceCheck::NeedsConfirmation } else { DeviceCheck::Trusted(device) })
+            })
+        })
+    }
+
+    pub fn confirm(conn: &mut SqliteConnection, token: &str) -> Result<Option<Self>, DbError> {
+        let (id, signature) = match token.split_once('.') {
+            Some((id, signature)) => (id, hex::decode(signature).unwrap_or_default()),
+            None => return Ok(None),
+        };
+        let mut device = match known_devices::table.find(id).first::<KnownDevice>(conn).optional()? {
+            Some(device) if device.mac().verify_slice(&signature).is_ok() => device,
+            _ => return Ok(None),
+        };
+
+        let now = chrono::Local::now().naive_local();
+        if device.confirmed_at.is_some() || device.last_seen_at + chrono::Duration::minutes(DEVICE_CONFIRMATION_MINUTES) <= now {
+            return Ok(None);
+        }
+
+        device.confirmed_at = Some(now);
+        retry_on_busy(|| {
+            diesel::update(known_devices::table.find(&device.id))
+                .set(known_devices::confirmed_at.eq(now))
+                .execute(conn)
+        })?;
+
+        Ok(Some(device))
+    }
+}
@@ -0,0 +1,51 @@
+use crate::db::fixtures::{test_connection, user};
+use super::device::{DeviceCheck, KnownDevice};
+use super::outbound_email::OutboundEmail;
+use super::user_settings::UserSettings;
+
+const BROWSER: &str = "Mozilla/5.0 (X11; Linux x86_64)";
+const PHONE: &str = "Mozilla/5.0 (iPhone; CPU iPhone OS 16_0 like Mac OS X)";
+
+#[test]
+fn new_devices_are_notified() {
+    let conn = &mut test_connection();
+    std::env::set_var("JWT_SECRET", "test_secret");
+    let user = user(conn, "device");
+
+    assert!(matches!(KnownDevice::check_in(conn, &user, BROWSER, "203.0.113.7").unwrap(), DeviceCheck::Trusted(_)));
+    assert!(matches!(KnownDevice::check_in(conn, &user, BROWSER, "203.0.113.7").unwrap(), DeviceCheck::Trusted(_)));
+    assert!(OutboundEmail::pending(conn, 10).unwrap().is_empty());
+
+    assert!(matches!(KnownDevice::check_in(conn, &user, BROWSER, "198.51.100.20").unwrap(), DeviceCheck::Trusted(_)));
+    let outbox = OutboundEmail::pending(conn, 10).unwrap();
+    assert_eq!(outbox.len(), 1);
+    assert_eq!((outbox[0].recipient.as_str(), outbox[0].subject.as_str()), ("device@example.com", "New sign-in to your account"));
+    assert!(outbox[0].body.contains("198.51.100.20"));
+
+    let devices = KnownDevice::list_for_user(conn, user.id.clone()).unwrap();
+    assert_eq!(devices.len(), 2);
+    assert!(devices.iter().all(|device| device.confirmed_at.is_some()));
+}
+
+#[test]
+fn new_devices_need_confirmation_when_enabled() {
+    let conn = &mut test_connection();
+    std::env::set_var("JWT_SECRET", "test_secret");
+    let user = user(conn, "device");
+    KnownDevice::check_in(conn, &user, BROWSER, "203.0.113.7").unwrap();
+    let (settings, errors) = UserSettings::update(conn, user.id.clone(), true, false).unwrap();
+    assert!(errors.is_none() && settings.unwrap().confirm_new_devices);
+
+    assert!(matches!(KnownDevice::check_in(conn, &user, PHONE, "198.51.100.20").unwrap(), DeviceCheck::NeedsConfirmation));
+    let device = KnownDevice::list_for_user(conn, user.id.clone()).unwrap().into_iter().find(|device| device.user_agent == PHONE).unwrap();
+    assert!(device.confirmed_at.is_none());
+    assert!(OutboundEmail::pending(conn, 10).unwrap()[0].body.contains(&device.token()));
+
+    let forged = format!("{}.{}", device.id, "00".repeat(32));
+    assert!(KnownDevice::confirm(conn, &forged).unwrap().is_none());
+
+    let confirmed = KnownDevice::confirm(conn, &device.token()).unwrap().unwrap();
+    assert_eq!(confirmed.user_id, user.id);
+    assert!(KnownDevice::confirm(conn, &device.token()).unwrap().is_none());
+    assert!(matches!(KnownDevice::check_in(conn, &user, PHONE, "198.51.100.20").unwrap(), DeviceCheck::Trusted(_)));
+}
@@ -0,0 +1,200 @@
+//! This module defines the flow for changing a user's email address.
+//!
+//! The address of an account is never overwritten directly. `EmailChange::request` records the requested address as a
+//! pending change and queues two emails (`outbound_email`): one to the new address carrying a signed confirmation
+//! token, and one telling the current address that a change was requested. The current address stays the login and
+//! the inbound-mail sender of the account until the change is confirmed, so a mistyped or hijacked address never locks
+//! the owner out.
+//!
+//! `EmailChange::confirm` takes the token from the confirmation email. The token is the change ID followed by an
+//! HMAC-SHA256 over the change, keyed with `EMAIL_CHANGE_SECRET` (falling back to `JWT_SECRET`); only its holder can
+//! confirm, and it is only valid for `EMAIL_CHANGE_HOURS` and for the latest change requested by the user. Confirming
+//! switches the address and notifies the previous one.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use crate::models::email_change::EmailChange;
+//!
+//! let (change, errors) = EmailChange::request(&mut connection, "user_id".to_string(), "new@example.com".to_string())?;
+//!
+//! // Later, with the token from the confirmation email.
+//! let (user, errors) = EmailChange::confirm(&mut connection, &token)?;
+//! ```
+//!
+//! # Note
+//! This module assumes the availability of a database connection (`SqliteConnection` in this case) for email change data retrieval and manipulation.
+
+use uuid::Uuid;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use serde::{Serialize, Deserialize};
+use diesel::prelude::*;
+
+use crate::config;
+use super::super::error::DbError;
+use super::super::retry::retry_on_busy;
+use super::super::schema::{email_changes, users};
+use super::outbound_email::OutboundEmail;
+use super::user::{User, EMAIL_EXISTS};
+
+type HmacSha256 = Hmac<Sha256>;
+
+pub const EMAIL_CHANGE_HOURS: i64 = 24;
+
+pub const INVALID_TOKEN: &str = "Invalid or expired confirmation token";
+
+#[derive(Debug, Deserialize, Serialize, Queryable, Insertable)]
+#[diesel(table_name = crate::db::schema::email_changes)]
+pub struct EmailChange {
+    pub id: String,
+    pub user_id: String,
+    pub old_email: String,
+    pub new_email: String,
+    pub created_at: chrono::NaiveDateTime,
+    pub expires_at: chrono::NaiveDateTime,
+    pub confirmed_at: Option<chrono::NaiveDateTime>,
+    pub cancelled_at: Option<chrono::NaiveDateTime>,
+}
+
+fn secret() -> String {
+    config::secret("EMAIL_CHANGE_SECRET")
+        .or_else(|| config::secret("JWT_SECRET"))
+        .expect("EMAIL_CHANGE_SECRET or JWT_SECRET must be set")
+}
+
+impl EmailChange {
+    pub fn find_by_id(conn: &mut SqliteConnection, id: String) -> Result<Option<Self>, DbError> {
+        Ok(email_changes::table
+            .find(id)
+            .first::<EmailChange>(conn)
+            .optional()?)
+    }
+
+    pub fn find_pending(conn: &mut SqliteConnection, user_id: String) -> Result<Option<Self>, DbError> {
+        Ok(email_changes::table
+            .filter(email_changes::user_id.eq(user_id))
+            .filter(email_changes::confirmed_at.is_null())
+            .filter(email_changes::cancelled_at.is_null())
+            .filter(email_changes::expires_at.gt(chrono::Local::now().naive_local()))
+            .first::<EmailChange>(conn)
+            .optional()?)
+    }
+
+    fn mac(&self) -> HmacSha256 {
+        let mut mac = HmacSha256::new_from_slice(secret().as_bytes()).expect("HMAC accepts keys of any size");
+        mac.update(format!("{}\n{}\n{}\n{}", self.id, self.user_id, self.new_email, self.expires_at.format("%Y-%m-%d %H:%M:%S")).as_bytes());
+        mac
+    }
+
+    // The confirmation token; only ever sent to the new address.
+    pub fn token(&self) -> String {
+        format!("{}.{}", self.id, hex::encode(self.mac().finalize().into_bytes()))
+    }
+
+    pub fn request(conn: &mut SqliteConnection, user_id: String, new_email: String) -> Result<(Option<Self>, Option<String>), DbError> {
+        let new_email = new_email.trim().to_string();
+        if new_email.is_empty() || !new_email.contains('@') {
+            return Ok((None, Some("Invalid email address".to_string())));
+        }
+
+        let user = match User::find_by_id(conn, user_id)? {
+            Some(user) => user,
+            None => return Ok((None, Some("User does not exist".to_string()))),
+        };
+        if user.email == new_email {
+            return Ok((None, Some("The new email is the current one".to_string())));
+        }
+        if User::find_by_email(conn, new_email.clone())?.is_some() {
+            return Ok((None, Some(EMAIL_EXISTS.to_string())));
+        }
+
+        let now = chrono::Local::now().naive_local();
+        let change = EmailChange {
+            id: Uuid::new_v4().as_hyphenated().to_string(),
+            user_id: user.id.clone(),
+            old_email: user.email.clone(),
+            new_email,
+            created_at: now,
+            expires_at: now + chrono::Duration::hours(EMAIL_CHANGE_HOURS),
+            confirmed_at: None,
+            cancelled_at: None,
+        };
+
+        retry_on_busy(|| {
+            conn.transaction(|conn| {
+                Self::cancel_pending(conn, &change.user_id)?;
+                diesel::insert_into(email_changes::table).values(&change).execute(conn)?;
+                OutboundEmail::queue(conn, &change.new_email, "Confirm your new email address", format!(
+                    "Hi {},\n\nConfirm that you want to use this address for your account with the token below. It \
+                    expires in {} hours.\n\n{}\n\nIf you did not ask for this, ignore this email.",
+                    user.name, EMAIL_CHANGE_HOURS, change.token()
+                ))?;
+                OutboundEmail::queue(conn, &change.old_email, "Your email address is being changed", format!(
+                    "Hi {},\n\nA change of your account's email address to {} was requested. This address stays \
+                    active until the new one is confirmed. If you did not ask for this, cancel the change and \
+                    change your password.",
+                    user.name, change.new_email
+                ))
+            })
+        })?;
+
+        Ok((Self::find_by_id(conn, change.id)?, None))
+    }
+
+    pub fn confirm(conn: &mut SqliteConnection, token: &str) -> Result<(Option<User>, Option<String>), DbError> {
+        let (id, signature) = match token.split_once('.') {
+            Some((id, signature)) => (id, hex::decode(signature).unwrap_or_default()),
+            None => return Ok((None, Some(INVALID_TOKEN.to_string()))),
+        };
+        let change = match Self::find_by_id(conn, id.to_string())? {
+            Some(change) if change.mac().verify_slice(&signature).is_ok() => change,
+            _ => return Ok((None, Some(INVALID_TOKEN.to_string()))),
+        };
+
+        let now = chrono::Local::now().naive_local();
+        if change.confirmed_at.is_some() || change.cancelled_at.is_some() || change.expires_at <= now {
+            return Ok((None, Some(INVALID_TOKEN.to_string())));
+        }
+        if User::find_by_email(conn, change.new_email.clone())?.is_some() {
+            return Ok((None, Some(EMAIL_EXISTS.to_string())));
+        }
+        let user = match User::find_by_id(conn, change.user_id.clone())? {
+            Some(user) => user,
+            None => return Ok((None, Some("User does not exist".to_string()))),
+        };
+
+        retry_on_busy(|| {
+            conn.transaction(|conn| {
+                diesel::update(users::table.find(&user.id))
+                    .set((users::email.eq(&change.new_email), users::updated_at.eq(now)))
+                    .execute(conn)?;
+                diesel::update(email_changes::table.find(&change.id))
+                    .set(email_changes::confirmed_at.eq(now))
+                    .execute(conn)?;
+                OutboundEmail::queue(conn, &user.email, "Your email address was changed", format!(
+                    "Hi {},\n\nYour account's email address was changed to {}. This address no longer signs you in.",
+                    user.name, change.new_email
+                ))
+            })
+        })?;
+
+        Ok((User::find_by_id(conn, user.id)?, None))
+    }
+
+    pub fn cancel(conn: &mut SqliteConnection, user_id: String) -> Result<bool, DbError> {
+        let cancelled = retry_on_busy(|| Self::cancel_pending(conn, &user_id))?;
+        Ok(cancelled > 0)
+    }
+
+    fn cancel_pending(conn: &mut SqliteConnection, user_id: &str) -> QueryResult<usize> {
+        diesel::update(
+            email_changes::table
+                .filter(email_changes::user_id.eq(user_id))
+                .filter(email_changes::confirmed_at.is_null())
+                .filter(email_changes::cancelled_at.is_null()),
+        )
+        .set(email_changes::cancelled_at.eq(chrono::Local::now().naive_local()))
+        .execute(conn)
+    }
+}
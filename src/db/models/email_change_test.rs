@@ -0,0 +1,61 @@
+use crate::db::fixtures::{self, test_connection, user, TEST_PASSWORD};
+use super::email_change::{EmailChange, INVALID_TOKEN};
+use super::outbound_email::OutboundEmail;
+use super::user::{User, EMAIL_EXISTS};
+
+#[test]
+fn email_changes_only_after_confirmation() {
+    let conn = &mut test_connection();
+    std::env::set_var("JWT_SECRET", "test_secret");
+    let user = user(conn, "old");
+
+    let (change, errors) = EmailChange::request(conn, user.id.clone(), "new@example.com".to_string()).unwrap();
+    assert!(errors.is_none());
+    let change = change.unwrap();
+    assert_eq!(User::find_by_id(conn, user.id.clone()).unwrap().unwrap().email, "old@example.com");
+
+    let outbox = OutboundEmail::pending(conn, 10).unwrap();
+    assert_eq!(outbox.iter().map(|email| email.recipient.as_str()).collect::<Vec<_>>(), vec!["new@example.com", "old@example.com"]);
+    assert!(outbox[0].body.contains(&change.token()));
+    assert!(!outbox[1].body.contains(&change.token()));
+
+    let forged = format!("{}.{}", change.id, "00".repeat(32));
+    assert_eq!(EmailChange::confirm(conn, &forged).unwrap().1, Some(INVALID_TOKEN.to_string()));
+
+    let (updated, errors) = EmailChange::confirm(conn, &change.token()).unwrap();
+    assert!(errors.is_none());
+    assert_eq!(updated.unwrap().email, "new@example.com");
+    assert!(User::login(conn, "old@example.com".to_string(), TEST_PASSWORD.to_string()).unwrap().user().is_none());
+
+    let notice = OutboundEmail::pending(conn, 10).unwrap().pop().unwrap();
+    assert_eq!((notice.recipient.as_str(), notice.subject.as_str()), ("old@example.com", "Your email address was changed"));
+    assert!(OutboundEmail::mark_sent(conn, notice.id).unwrap());
+    assert_eq!(OutboundEmail::pending(conn, 10).unwrap().len(), 2);
+
+    assert_eq!(EmailChange::confirm(conn, &change.token()).unwrap().1, Some(INVALID_TOKEN.to_string()));
+}
+
+#[test]
+fn superseded_and_conflicting_changes_are_rejected() {
+    let conn = &mut test_connection();
+    std::env::set_var("JWT_SECRET", "test_secret");
+    let user = user(conn, "old");
+    fixtures::user(conn, "taken");
+
+    let (_, errors) = EmailChange::request(conn, user.id.clone(), "taken@example.com".to_string()).unwrap();
+    assert_eq!(errors, Some(EMAIL_EXISTS.to_string()));
+    let (_, errors) = EmailChange::request(conn, user.id.clone(), "not an email".to_string()).unwrap();
+    assert_eq!(errors, Some("Invalid email address".to_string()));
+
+    let (first, _) = EmailChange::request(conn, user.id.clone(), "first@example.com".to_string()).unwrap();
+    let (second, _) = EmailChange::request(conn, user.id.clone(), "second@example.com".to_string()).unwrap();
+    let (first, second) = (first.unwrap(), second.unwrap());
+    assert_eq!(EmailChange::confirm(conn, &first.token()).unwrap().1, Some(INVALID_TOKEN.to_string()));
+    assert_eq!(EmailChange::find_pending(conn, user.id.clone()).unwrap().unwrap().id, second.id);
+
+    fixtures::user(conn, "second");
+    assert_eq!(EmailChange::confirm(conn, &second.token()).unwrap().1, Some(EMAIL_EXISTS.to_string()));
+
+    assert!(EmailChange::cancel(conn, user.id.clone()).unwrap());
+    assert!(EmailChange::find_pending(conn, user.id).unwrap().is_none());
+}
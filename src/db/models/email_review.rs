@@ -0,0 +1,119 @@
+//! This module defines the review queue for trade confirmation emails that could not be turned into trades automatically.
+//!
+//! The inbound email gateway (`services::email_in`) stores an `EmailReview` whenever a confirmation email from a known
+//! user is ambiguous: a required field is missing, conflicting or not recognised. The entry keeps the original message,
+//! the partially parsed fields (as JSON) and the reason it needs review. The owner then accepts it, which records the
+//! trade that was created from it, or rejects it.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use crate::models::email_review::{EmailReview, ReviewStatus};
+//!
+//! // Queue an ambiguous confirmation.
+//! let review = EmailReview::create(&mut connection, "user_id".to_string(), sender, subject, body, parsed_json, "Missing asset".to_string())?;
+//!
+//! // List what is waiting for the user.
+//! let pending = EmailReview::list_pending(&mut connection, "user_id".to_string())?;
+//!
+//! // Mark it as accepted once the trade has been created.
+//! EmailReview::resolve(&mut connection, review.id, ReviewStatus::ACCEPTED, Some(trade.id))?;
+//! ```
+//!
+//! # Note
+//! This module assumes the availability of a database connection (`SqliteConnection` in this case) for review data retrieval and manipulation.
+
+use uuid::Uuid;
+use serde::{Serialize, Deserialize};
+use diesel::prelude::*;
+
+use super::super::error::DbError;
+use super::super::retry::retry_on_busy;
+use super::super::schema::email_trade_reviews;
+
+#[derive(Debug, Deserialize, Serialize, Queryable, Insertable)]
+#[diesel(table_name = crate::db::schema::email_trade_reviews)]
+pub struct EmailReview {
+    pub id: String,
+    pub user_id: String,
+    pub sender: String,
+    pub subject: String,
+    pub body: String,
+    pub parsed: String,
+    pub reason: String,
+    pub status: String,
+    pub trade_id: Option<String>,
+    pub created_at: chrono::NaiveDateTime,
+    pub updated_at: chrono::NaiveDateTime,
+}
+
+pub struct ReviewStatus;
+
+impl ReviewStatus {
+    pub const PENDING: &'static str = "Pending";
+    pub const ACCEPTED: &'static str = "Accepted";
+    pub const REJECTED: &'static str = "Rejected";
+}
+
+impl EmailReview {
+    pub fn find_by_id(conn: &mut SqliteConnection, id: String) -> Result<Option<Self>, DbError> {
+        Ok(email_trade_reviews::table
+            .find(id)
+            .first::<EmailReview>(conn)
+            .optional()?)
+    }
+
+    pub fn list_pending(conn: &mut SqliteConnection, user_id: String) -> Result<Vec<Self>, DbError> {
+        Ok(email_trade_reviews::table
+            .filter(email_trade_reviews::user_id.eq(user_id))
+            .filter(email_trade_reviews::status.eq(ReviewStatus::PENDING))
+            .order(email_trade_reviews::created_at.asc())
+            .load::<EmailReview>(conn)?)
+    }
+
+    pub fn create(conn: &mut SqliteConnection, user_id: String, sender: String, subject: String, body: String, parsed: String, reason: String) -> Result<Option<Self>, DbError> {
+        let now = chrono::Local::now().naive_local();
+        let review = EmailReview {
+            id: Uuid::new_v4().as_hyphenated().to_string(),
+            user_id,
+            sender,
+            subject,
+            body,
+            parsed,
+            reason,
+            status: ReviewStatus::PENDING.to_string(),
+            trade_id: None,
+            created_at: now,
+            updated_at: now,
+        };
+
+        retry_on_busy(|| {
+            diesel::insert_into(email_trade_reviews::table)
+                .values(&review)
+                .execute(conn)
+        })?;
+
+        Self::find_by_id(conn, review.id)
+    }
+
+    pub fn resolve(conn: &mut SqliteConnection, id: String, status: &str, trade_id: Option<String>) -> Result<Option<Self>, DbError> {
+        let updated = retry_on_busy(|| {
+            diesel::update(
+                email_trade_reviews::table
+                    .find(id.clone())
+                    .filter(email_trade_reviews::status.eq(ReviewStatus::PENDING)),
+            )
+            .set((
+                email_trade_reviews::status.eq(status),
+                email_trade_reviews::trade_id.eq(trade_id.clone()),
+                email_trade_reviews::updated_at.eq(chrono::Local::now().naive_local()),
+            ))
+            .execute(conn)
+        })?;
+
+        if updated == 0 {
+            return Ok(None);
+        }
+        Self::find_by_id(conn, id)
+    }
+}
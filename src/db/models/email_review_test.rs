@@ -0,0 +1,36 @@
+use diesel::SqliteConnection;
+
+use crate::db::fixtures::{test_connection, user};
+use super::email_review::{EmailReview, ReviewStatus};
+
+fn create_review(conn: &mut SqliteConnection, user_id: String) -> EmailReview {
+    EmailReview::create(conn, user_id, "trader@example.com".to_string(), "Fill".to_string(), "Side: Buy".to_string(), "{}".to_string(), "Missing quantity".to_string())
+        .unwrap()
+        .unwrap()
+}
+
+#[test]
+fn lists_only_pending_reviews() {
+    let conn = &mut test_connection();
+    let user_id = user(conn, "trader").id;
+    let first = create_review(conn, user_id.clone());
+    create_review(conn, user_id.clone());
+
+    EmailReview::resolve(conn, first.id, ReviewStatus::REJECTED, None).unwrap();
+
+    let pending = EmailReview::list_pending(conn, user_id).unwrap();
+    assert_eq!(pending.len(), 1);
+    assert_eq!(pending[0].status, ReviewStatus::PENDING);
+}
+
+#[test]
+fn resolved_reviews_cannot_be_resolved_again() {
+    let conn = &mut test_connection();
+    let user_id = user(conn, "trader").id;
+    let review = create_review(conn, user_id);
+
+    let rejected = EmailReview::resolve(conn, review.id.clone(), ReviewStatus::REJECTED, None).unwrap();
+    assert_eq!(rejected.unwrap().status, ReviewStatus::REJECTED);
+
+    assert!(EmailReview::resolve(conn, review.id, ReviewStatus::ACCEPTED, None).unwrap().is_none());
+}
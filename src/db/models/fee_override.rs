@@ -0,0 +1,194 @@
+//! This module defines the fee rates negotiated by single users, layered on top of the global fee schedule.
+//!
+//! A `FeeOverride` replaces the rates of the fee schedule (`fee_schedule`) for one user, either on every venue
+//! (`venue` is `None`) or on one venue only, where the venue of a trade is derived from its source by
+//! `TradeSource::venue` (`binance` for `import:binance`, `onchain`, `api_key` or `manual`). Like schedules, overrides
+//! are versioned: each one is in force from `effective_from` up to (but excluding) `effective_to`, so a trade keeps
+//! the rates that applied at its execution time (`created_at`) when it is recomputed.
+//!
+//! The rates of a trade are those of the override for its user and venue in force at its execution time, or else of
+//! the user's override for every venue, or else of the schedule in force (see `FeeSchedule::fees_for`). `set` appends
+//! a version for a user and venue: it must start after the latest one for the same pair and closes the open one.
+//! `remove` closes the open version, after which the user pays the schedule again. Both are recorded in the audit log
+//! (`audit`) in the same transaction.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use crate::models::fee_override::FeeOverride;
+//!
+//! // Cheaper trades on Binance for one user, from now on.
+//! let now = chrono::Local::now().naive_local();
+//! let (fee_override, errors) = FeeOverride::set(&mut connection, "user_id", Some("binance".to_string()), 0.001, 0.002, now, "admin_id".to_string())?;
+//!
+//! let fee_override = FeeOverride::in_force_at(&mut connection, "user_id", "binance", trade.created_at)?;
+//! ```
+//!
+//! # Note
+//! This module assumes the availability of a database connection (`SqliteConnection` in this case) for override data retrieval and manipulation.
+
+use uuid::Uuid;
+use serde::{Serialize, Deserialize};
+use diesel::prelude::*;
+
+use super::super::error::DbError;
+use super::super::retry::retry_on_busy;
+use super::super::schema::fee_overrides;
+use super::audit::AuditEntry;
+use super::user::User;
+
+#[derive(Debug, Clone, Deserialize, Serialize, Queryable, Insertable)]
+#[diesel(table_name = crate::db::schema::fee_overrides)]
+pub struct FeeOverride {
+    pub id: String,
+    pub user_id: String,
+    pub venue: Option<String>,
+    pub execution_rate: f32,
+    pub transaction_rate: f32,
+    pub effective_from: chrono::NaiveDateTime,
+    pub effective_to: Option<chrono::NaiveDateTime>,
+    pub created_by: String,
+    pub created_at: chrono::NaiveDateTime,
+}
+
+fn normalize(venue: Option<String>) -> Option<String> {
+    venue.map(|venue| venue.trim().to_lowercase()).filter(|venue| !venue.is_empty())
+}
+
+impl FeeOverride {
+    pub fn find_by_id(conn: &mut SqliteConnection, id: String) -> Result<Option<Self>, DbError> {
+        Ok(fee_overrides::table
+            .find(id)
+            .first::<FeeOverride>(conn)
+            .optional()?)
+    }
+
+    // Every version for the user, newest first.
+    pub fn list_for_user(conn: &mut SqliteConnection, user_id: &str) -> QueryResult<Vec<Self>> {
+        fee_overrides::table
+            .filter(fee_overrides::user_id.eq(user_id))
+            .order(fee_overrides::effective_from.desc())
+            .load::<FeeOverride>(conn)
+    }
+
+    pub fn is_in_force_at(&self, at: chrono::NaiveDateTime) -> bool {
+        self.effective_from <= at && self.effective_to.is_none_or(|effective_to| effective_to > at)
+    }
+
+    // The override that charges a trade on `venue` at `at` among the overrides of its user: the one for the venue,
+    // else the one for every venue.
+    pub fn covering<'a>(overrides: &'a [Self], venue: &str, at: chrono::NaiveDateTime) -> Option<&'a Self> {
+        let in_force = |venue: Option<&str>| {
+            overrides
+                .iter()
+                .filter(|fee_override| fee_override.venue.as_deref() == venue && fee_override.is_in_force_at(at))
+                .max_by_key(|fee_override| fee_override.effective_from)
+        };
+        in_force(Some(venue)).or_else(|| in_force(None))
+    }
+
+    pub fn in_force_at(conn: &mut SqliteConnection, user_id: &str, venue: &str, at: chrono::NaiveDateTime) -> QueryResult<Option<Self>> {
+        let overrides = Self::list_for_user(conn, user_id)?;
+        Ok(Self::covering(&overrides, venue, at).cloned())
+    }
+
+    pub fn set(
+        conn: &mut SqliteConnection,
+        user_id: &str,
+        venue: Option<String>,
+        execution_rate: f32,
+        transaction_rate: f32,
+        effective_from: chrono::NaiveDateTime,
+        created_by: String,
+    ) -> Result<(Option<Self>, Option<String>), DbError> {
+        if ![execution_rate, transaction_rate].iter().all(|rate| rate.is_finite() && (0.0..=1.0).contains(rate)) {
+            return Ok((None, Some("Rates must be between 0 and 1".to_string())));
+        }
+        if User::find_by_id(conn, user_id.to_string())?.is_none() {
+            return Ok((None, Some("User not found".to_string())));
+        }
+        let venue = normalize(venue);
+
+        let latest = fee_overrides::table
+            .filter(fee_overrides::user_id.eq(user_id))
+            .filter(fee_overrides::venue.is(&venue))
+            .select(diesel::dsl::max(fee_overrides::effective_from))
+            .first::<Option<chrono::NaiveDateTime>>(conn)?;
+        if latest.is_some_and(|latest| effective_from <= latest) {
+            return Ok((None, Some("A new override must take effect after the latest one".to_string())));
+        }
+
+        let fee_override = FeeOverride {
+            id: Uuid::new_v4().as_hyphenated().to_string(),
+            user_id: user_id.to_string(),
+            venue,
+            execution_rate,
+            transaction_rate,
+            effective_from,
+            effective_to: None,
+            created_by,
+            created_at: chrono::Local::now().naive_local(),
+        };
+        let details = serde_json::json!({
+            "user_id": fee_override.user_id,
+            "venue": fee_override.venue,
+            "execution_rate": fee_override.execution_rate,
+            "transaction_rate": fee_override.transaction_rate,
+            "effective_from": fee_override.effective_from,
+        });
+
+        retry_on_busy(|| {
+            conn.transaction(|conn| {
+                diesel::update(
+                    fee_overrides::table
+                        .filter(fee_overrides::user_id.eq(&fee_override.user_id))
+                        .filter(fee_overrides::venue.is(&fee_override.venue))
+                        .filter(fee_overrides::effective_to.is_null()),
+                )
+                .set(fee_overrides::effective_to.eq(fee_override.effective_from))
+                .execute(conn)?;
+                diesel::insert_into(fee_overrides::table).values(&fee_override).execute(conn)?;
+                AuditEntry::record(conn, &fee_override.created_by, "fee_override", &fee_override.id, details.clone())
+            })
+        })?;
+
+        Ok((Self::find_by_id(conn, fee_override.id)?, None))
+    }
+
+    // Ends the open override for the user and venue at `at`; `false` when there was none.
+    pub fn remove(
+        conn: &mut SqliteConnection,
+        user_id: &str,
+        venue: Option<String>,
+        at: chrono::NaiveDateTime,
+        removed_by: &str,
+    ) -> Result<bool, DbError> {
+        let venue = normalize(venue);
+        let open = fee_overrides::table
+            .filter(fee_overrides::user_id.eq(user_id))
+            .filter(fee_overrides::venue.is(&venue))
+            .filter(fee_overrides::effective_to.is_null())
+            .first::<FeeOverride>(conn)
+            .optional()?;
+        let open = match open {
+            Some(open) => open,
+            None => return Ok(false),
+        };
+        let details = serde_json::json!({
+            "user_id": open.user_id,
+            "venue": open.venue,
+            "effective_to": at.max(open.effective_from),
+        });
+
+        retry_on_busy(|| {
+            conn.transaction(|conn| {
+                diesel::update(fee_overrides::table.find(&open.id))
+                    .set(fee_overrides::effective_to.eq(at.max(open.effective_from)))
+                    .execute(conn)?;
+                AuditEntry::record(conn, removed_by, "fee_override_removed", &open.id, details.clone())
+            })
+        })?;
+
+        Ok(true)
+    }
+}
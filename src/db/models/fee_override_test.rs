@@ -0,0 +1,95 @@
+use diesel::prelude::*;
+
+use crate::db::fixtures::{test_connection, trade, user};
+use crate::db::schema::trades;
+use crate::services::trade::{fill_optional_fields, TradeForm};
+use crate::utils::date::timestamp_to_naive_date_time;
+use super::fee_override::FeeOverride;
+use super::fee_schedule::FeeSchedule;
+use super::recompute::{RecomputeJob, RecomputeTarget};
+use super::trade::Trade;
+use super::user::User;
+
+fn create_trade(conn: &mut SqliteConnection, user: &User, source: &str, timestamp: i64) -> Trade {
+    let form = TradeForm { amount: 200.0, traded_amount: Some(2.0), timestamp: Some(timestamp), source: Some(source.to_string()), ..trade(&user.id, &user.wallet_id) };
+    let mut trade = fill_optional_fields(&form);
+    FeeSchedule::apply(conn, &mut trade).unwrap();
+    Trade::create(conn, &mut trade).unwrap().0.unwrap()
+}
+
+#[test]
+fn venue_overrides_take_precedence_over_user_overrides_and_the_schedule() {
+    let conn = &mut test_connection();
+    let user = user(conn, "overrides");
+    let start = timestamp_to_naive_date_time(1_692_050_000).unwrap();
+
+    let (_, errors) = FeeOverride::set(conn, &user.id, None, 0.001, 0.0, start, "admin".to_string()).unwrap();
+    assert!(errors.is_none());
+    let (fee_override, _) = FeeOverride::set(conn, &user.id, Some(" Binance ".to_string()), 0.002, 0.0, start, "admin".to_string()).unwrap();
+    assert_eq!(fee_override.unwrap().venue, Some("binance".to_string()));
+
+    let before = create_trade(conn, &user, "manual", 1_692_000_000);
+    let manual = create_trade(conn, &user, "manual", 1_692_100_000);
+    let binance = create_trade(conn, &user, "import:binance", 1_692_100_000);
+    let kraken = create_trade(conn, &user, "import:kraken", 1_692_100_000);
+    assert_eq!((before.execution_fee, before.transaction_fee), FeeSchedule::default_fees(100.0, 2.0));
+    assert_eq!((manual.execution_fee, manual.transaction_fee), (0.2, 0.0));
+    assert_eq!((binance.execution_fee, binance.transaction_fee), (0.4, 0.0));
+    assert_eq!((kraken.execution_fee, kraken.transaction_fee), (0.2, 0.0));
+
+    let breakdown = Trade::fee_breakdown(conn, "2023-01-01".to_string(), "2024-01-01".to_string(), user.id.clone(), &[]).unwrap();
+    assert_eq!(breakdown.len(), 1);
+    assert_eq!((breakdown[0].trades, breakdown[0].overridden_trades), (4, 3));
+}
+
+#[test]
+fn removed_overrides_keep_charging_the_trades_they_covered() {
+    let conn = &mut test_connection();
+    let user = user(conn, "overrides");
+    let start = timestamp_to_naive_date_time(1_692_050_000).unwrap();
+    let end = timestamp_to_naive_date_time(1_692_150_000).unwrap();
+
+    FeeOverride::set(conn, &user.id, None, 0.001, 0.0, start, "admin".to_string()).unwrap();
+    assert!(FeeOverride::remove(conn, &user.id, None, end, "admin").unwrap());
+    assert!(!FeeOverride::remove(conn, &user.id, None, end, "admin").unwrap());
+    assert!(!FeeOverride::remove(conn, &user.id, Some("binance".to_string()), end, "admin").unwrap());
+
+    let covered = create_trade(conn, &user, "manual", 1_692_100_000);
+    let after = create_trade(conn, &user, "manual", 1_692_200_000);
+    assert_eq!(covered.execution_fee, 0.2);
+    assert_eq!((after.execution_fee, after.transaction_fee), FeeSchedule::default_fees(100.0, 2.0));
+
+    diesel::update(trades::table)
+        .set((trades::execution_fee.eq(0.0), trades::transaction_fee.eq(0.0)))
+        .execute(conn)
+        .unwrap();
+    let (job, _) = RecomputeJob::create(conn, RecomputeTarget::FEES.to_string(), None, "admin".to_string()).unwrap();
+    RecomputeJob::run(conn, job.unwrap().id).unwrap();
+    assert_eq!(Trade::find_by_id(conn, covered.id).unwrap().unwrap().execution_fee, 0.2);
+    assert_eq!(Trade::find_by_id(conn, after.id).unwrap().unwrap().execution_fee, after.execution_fee);
+}
+
+#[test]
+fn overrides_only_append_after_the_latest_version_of_their_venue() {
+    let conn = &mut test_connection();
+    let user = user(conn, "overrides");
+    let start = timestamp_to_naive_date_time(1_692_050_000).unwrap();
+    FeeOverride::set(conn, &user.id, None, 0.001, 0.0, start, "admin".to_string()).unwrap();
+
+    let (fee_override, errors) = FeeOverride::set(conn, &user.id, None, 0.002, 0.0, start, "admin".to_string()).unwrap();
+    assert!(fee_override.is_none());
+    assert_eq!(errors, Some("A new override must take effect after the latest one".to_string()));
+    let (_, errors) = FeeOverride::set(conn, &user.id, Some("binance".to_string()), 0.002, 0.0, start, "admin".to_string()).unwrap();
+    assert!(errors.is_none());
+    let (_, errors) = FeeOverride::set(conn, &user.id, None, 0.0, -0.1, start + chrono::Duration::days(1), "admin".to_string()).unwrap();
+    assert_eq!(errors, Some("Rates must be between 0 and 1".to_string()));
+    let (_, errors) = FeeOverride::set(conn, "missing", None, 0.001, 0.0, start, "admin".to_string()).unwrap();
+    assert_eq!(errors, Some("User not found".to_string()));
+
+    let (_, errors) = FeeOverride::set(conn, &user.id, None, 0.002, 0.0, start + chrono::Duration::days(1), "admin".to_string()).unwrap();
+    assert!(errors.is_none());
+    let overrides = FeeOverride::list_for_user(conn, &user.id).unwrap();
+    assert_eq!(overrides.len(), 3);
+    let first = overrides.iter().find(|fee_override| fee_override.execution_rate == 0.001).unwrap();
+    assert_eq!(first.effective_to, Some(start + chrono::Duration::days(1)));
+}
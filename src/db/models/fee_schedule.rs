@@ -0,0 +1,160 @@
+//! This module defines the versioned fee schedule used to charge trades.
+//!
+//! A `FeeSchedule` holds the rates in force from `effective_from` up to (but excluding) `effective_to`; the latest
+//! schedule is open-ended. The execution fee is `execution_rate` of the trade value (`execution_price * traded_amount`)
+//! and the transaction fee is `transaction_rate` of the execution price.
+//!
+//! Trades are charged with the schedule in force at their execution time (`created_at`), both when they are created
+//! and when an admin recompute job (`recompute`) re-derives their fees, so changing the rates never rewrites the fees
+//! of older trades. `create` appends a new version: it must start after every existing version, closes the open one
+//! and is recorded in the audit log (`audit`) in the same transaction. When no schedule covers a time the built-in
+//! `DEFAULT_EXECUTION_RATE` and `DEFAULT_TRANSACTION_RATE` apply.
+//!
+//! Users with negotiated rates have fee overrides (`fee_override`) that take precedence over the schedule for their
+//! trades, on one venue or on all of them. `fees_for` charges a trade with the rates of its user, which is what trade
+//! creation and recompute jobs use; `fees_at` only looks at the schedule.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use crate::models::fee_schedule::FeeSchedule;
+//!
+//! let (schedule, errors) = FeeSchedule::create(&mut connection, 0.002, 0.004, effective_from, "admin_id".to_string())?;
+//!
+//! let (execution_fee, transaction_fee) = FeeSchedule::fees_at(&mut connection, trade.created_at, 100.0, 2.0)?;
+//! let (execution_fee, transaction_fee) = FeeSchedule::fees_for(&mut connection, &trade)?;
+//! ```
+//!
+//! # Note
+//! This module assumes the availability of a database connection (`SqliteConnection` in this case) for schedule data retrieval and manipulation.
+
+use uuid::Uuid;
+use serde::{Serialize, Deserialize};
+use diesel::prelude::*;
+
+use super::super::error::DbError;
+use super::super::retry::retry_on_busy;
+use super::super::schema::fee_schedules;
+use super::audit::AuditEntry;
+use super::fee_override::FeeOverride;
+use super::trade::{Trade, TradeSource};
+
+pub const DEFAULT_EXECUTION_RATE: f32 = 0.003;
+pub const DEFAULT_TRANSACTION_RATE: f32 = 0.005;
+
+#[derive(Debug, Deserialize, Serialize, Queryable, Insertable)]
+#[diesel(table_name = crate::db::schema::fee_schedules)]
+pub struct FeeSchedule {
+    pub id: String,
+    pub execution_rate: f32,
+    pub transaction_rate: f32,
+    pub effective_from: chrono::NaiveDateTime,
+    pub effective_to: Option<chrono::NaiveDateTime>,
+    pub created_by: String,
+    pub created_at: chrono::NaiveDateTime,
+}
+
+impl FeeSchedule {
+    pub fn find_by_id(conn: &mut SqliteConnection, id: String) -> Result<Option<Self>, DbError> {
+        Ok(fee_schedules::table
+            .find(id)
+            .first::<FeeSchedule>(conn)
+            .optional()?)
+    }
+
+    pub fn list(conn: &mut SqliteConnection) -> Result<Vec<Self>, DbError> {
+        Ok(fee_schedules::table
+            .order(fee_schedules::effective_from.desc())
+            .load::<FeeSchedule>(conn)?)
+    }
+
+    pub fn in_force_at(conn: &mut SqliteConnection, at: chrono::NaiveDateTime) -> QueryResult<Option<Self>> {
+        fee_schedules::table
+            .filter(fee_schedules::effective_from.le(at))
+            .filter(fee_schedules::effective_to.is_null().or(fee_schedules::effective_to.gt(at)))
+            .order(fee_schedules::effective_from.desc())
+            .first::<FeeSchedule>(conn)
+            .optional()
+    }
+
+    pub fn fees(&self, execution_price: f32, traded_amount: f32) -> (f32, f32) {
+        compute_fees(self.execution_rate, self.transaction_rate, execution_price, traded_amount)
+    }
+
+    pub fn default_fees(execution_price: f32, traded_amount: f32) -> (f32, f32) {
+        compute_fees(DEFAULT_EXECUTION_RATE, DEFAULT_TRANSACTION_RATE, execution_price, traded_amount)
+    }
+
+    pub fn fees_at(conn: &mut SqliteConnection, at: chrono::NaiveDateTime, execution_price: f32, traded_amount: f32) -> QueryResult<(f32, f32)> {
+        Ok(match Self::in_force_at(conn, at)? {
+            Some(schedule) => schedule.fees(execution_price, traded_amount),
+            None => Self::default_fees(execution_price, traded_amount),
+        })
+    }
+
+    // The fees of a trade with the rates of its user's override in force at its execution time, if any.
+    pub fn fees_for(conn: &mut SqliteConnection, trade: &Trade) -> QueryResult<(f32, f32)> {
+        match FeeOverride::in_force_at(conn, &trade.user_id, TradeSource::venue(&trade.source), trade.created_at)? {
+            Some(fee_override) => Ok(compute_fees(fee_override.execution_rate, fee_override.transaction_rate, trade.execution_price, trade.traded_amount)),
+            None => Self::fees_at(conn, trade.created_at, trade.execution_price, trade.traded_amount),
+        }
+    }
+
+    // Charges a trade that is about to be created with the rates in force at its execution time.
+    pub fn apply(conn: &mut SqliteConnection, trade: &mut Trade) -> QueryResult<()> {
+        let (execution_fee, transaction_fee) = Self::fees_for(conn, trade)?;
+        trade.execution_fee = execution_fee;
+        trade.transaction_fee = transaction_fee;
+        Ok(())
+    }
+
+    pub fn create(
+        conn: &mut SqliteConnection,
+        execution_rate: f32,
+        transaction_rate: f32,
+        effective_from: chrono::NaiveDateTime,
+        created_by: String,
+    ) -> Result<(Option<Self>, Option<String>), DbError> {
+        if ![execution_rate, transaction_rate].iter().all(|rate| rate.is_finite() && (0.0..=1.0).contains(rate)) {
+            return Ok((None, Some("Rates must be between 0 and 1".to_string())));
+        }
+
+        let latest = fee_schedules::table
+            .select(diesel::dsl::max(fee_schedules::effective_from))
+            .first::<Option<chrono::NaiveDateTime>>(conn)?;
+        if latest.is_some_and(|latest| effective_from <= latest) {
+            return Ok((None, Some("A new schedule must take effect after the latest one".to_string())));
+        }
+
+        let schedule = FeeSchedule {
+            id: Uuid::new_v4().as_hyphenated().to_string(),
+            execution_rate,
+            transaction_rate,
+            effective_from,
+            effective_to: None,
+            created_by,
+            created_at: chrono::Local::now().naive_local(),
+        };
+        let details = serde_json::json!({
+            "execution_rate": schedule.execution_rate,
+            "transaction_rate": schedule.transaction_rate,
+            "effective_from": schedule.effective_from,
+        });
+
+        retry_on_busy(|| {
+            conn.transaction(|conn| {
+                diesel::update(fee_schedules::table.filter(fee_schedules::effective_to.is_null()))
+                    .set(fee_schedules::effective_to.eq(schedule.effective_from))
+                    .execute(conn)?;
+                diesel::insert_into(fee_schedules::table).values(&schedule).execute(conn)?;
+                AuditEntry::record(conn, &schedule.created_by, "fee_schedule", &schedule.id, details.clone())
+            })
+        })?;
+
+        Ok((Self::find_by_id(conn, schedule.id)?, None))
+    }
+}
+
+fn compute_fees(execution_rate: f32, transaction_rate: f32, execution_price: f32, traded_amount: f32) -> (f32, f32) {
+    (execution_price * traded_amount * execution_rate, execution_price * transaction_rate)
+}
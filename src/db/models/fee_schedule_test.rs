@@ -0,0 +1,60 @@
+use diesel::prelude::*;
+
+use crate::db::fixtures::{test_connection, trade, user};
+use crate::db::schema::trades;
+use crate::services::trade::{fill_optional_fields, TradeForm};
+use crate::utils::date::timestamp_to_naive_date_time;
+use super::fee_schedule::FeeSchedule;
+use super::recompute::{RecomputeJob, RecomputeTarget};
+use super::trade::Trade;
+use super::user::User;
+
+fn create_trade(conn: &mut SqliteConnection, user: &User, timestamp: i64) -> Trade {
+    let form = TradeForm { amount: 200.0, traded_amount: Some(2.0), timestamp: Some(timestamp), ..trade(&user.id, &user.wallet_id) };
+    let mut trade = fill_optional_fields(&form);
+    FeeSchedule::apply(conn, &mut trade).unwrap();
+    Trade::create(conn, &mut trade).unwrap().0.unwrap()
+}
+
+#[test]
+fn trades_are_charged_with_the_schedule_in_force() {
+    let conn = &mut test_connection();
+    let user = user(conn, "fees");
+
+    let change = timestamp_to_naive_date_time(1_692_050_000).unwrap();
+    let (schedule, errors) = FeeSchedule::create(conn, 0.01, 0.0, change, "admin".to_string()).unwrap();
+    assert!(errors.is_none());
+    assert_eq!(schedule.unwrap().effective_to, None);
+
+    let before = create_trade(conn, &user, 1_692_000_000);
+    let after = create_trade(conn, &user, 1_692_100_000);
+    assert_eq!((before.execution_fee, before.transaction_fee), FeeSchedule::default_fees(100.0, 2.0));
+    assert_eq!((after.execution_fee, after.transaction_fee), (2.0, 0.0));
+
+    let schedules = FeeSchedule::list(conn).unwrap();
+    assert_eq!(schedules.len(), 2);
+    assert_eq!(schedules[1].effective_to, Some(change));
+
+    diesel::update(trades::table)
+        .set((trades::execution_fee.eq(0.0), trades::transaction_fee.eq(0.0)))
+        .execute(conn)
+        .unwrap();
+    let (job, _) = RecomputeJob::create(conn, RecomputeTarget::FEES.to_string(), None, "admin".to_string()).unwrap();
+    RecomputeJob::run(conn, job.unwrap().id).unwrap();
+    assert_eq!(Trade::find_by_id(conn, before.id).unwrap().unwrap().execution_fee, before.execution_fee);
+    assert_eq!(Trade::find_by_id(conn, after.id).unwrap().unwrap().execution_fee, 2.0);
+}
+
+#[test]
+fn schedules_only_append_after_the_latest_version() {
+    let conn = &mut test_connection();
+    let change = timestamp_to_naive_date_time(1_692_050_000).unwrap();
+    FeeSchedule::create(conn, 0.01, 0.0, change, "admin".to_string()).unwrap();
+
+    let (schedule, errors) = FeeSchedule::create(conn, 0.02, 0.0, change, "admin".to_string()).unwrap();
+    assert!(schedule.is_none());
+    assert_eq!(errors, Some("A new schedule must take effect after the latest one".to_string()));
+    let (_, errors) = FeeSchedule::create(conn, 1.5, 0.0, change + chrono::Duration::days(1), "admin".to_string()).unwrap();
+    assert_eq!(errors, Some("Rates must be between 0 and 1".to_string()));
+    assert_eq!(FeeSchedule::list(conn).unwrap().len(), 2);
+}
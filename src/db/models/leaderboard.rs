@@ -0,0 +1,95 @@
+//! This module ranks traders against each other for the public leaderboard.
+//!
+//! `Leaderboard::top` returns the top traders of a period twice: by realized P&L (`by_pnl`) and by traded volume
+//! (`by_volume`). Both rankings are computed by one grouped query each over the trades table, so the cost does not grow
+//! with the number of trades loaded per user:
+//!
+//! - `pnl`: the summed per-trade P&L of the trades executed in the period, net of fees, with the same formula as
+//!   `Trade::calculate_trade_pnl` (see `summary::TRADE_PNL_SQL`);
+//! - `volume`: the notional traded (`traded_amount * execution_price`);
+//! - `trades`: the number of trades executed in the period.
+//!
+//! Deleted and simulated trades (`source = simulation`) are left out, and so are users who opted out with the
+//! `hide_from_leaderboard` setting (see `user_settings`). Ties are broken by user ID so the order is stable. Entries
+//! carry the user's display name, never their email.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use crate::models::leaderboard::Leaderboard;
+//!
+//! let since = chrono::Utc::now().naive_utc() - chrono::Duration::days(7);
+//! let leaderboard = Leaderboard::top(&mut connection, Some(since), 10)?;
+//! println!("Top trader this week: {:?}", leaderboard.by_pnl.first());
+//! ```
+//!
+//! # Note
+//! This module assumes the availability of a database connection (`SqliteConnection` in this case) for leaderboard data retrieval.
+
+use serde::{Serialize, Deserialize};
+use diesel::prelude::*;
+use diesel::sql_types::{BigInt, Double, Nullable, Text, Timestamp};
+
+use super::trade::TradeSource;
+use super::summary::TRADE_PNL_SQL;
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, QueryableByName)]
+pub struct LeaderboardEntry {
+    #[diesel(sql_type = BigInt)]
+    pub rank: i64,
+    #[diesel(sql_type = Text)]
+    pub user_id: String,
+    #[diesel(sql_type = Text)]
+    pub name: String,
+    #[diesel(sql_type = Double)]
+    pub pnl: f64,
+    #[diesel(sql_type = Double)]
+    pub volume: f64,
+    #[diesel(sql_type = BigInt)]
+    pub trades: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Leaderboard {
+    pub by_pnl: Vec<LeaderboardEntry>,
+    pub by_volume: Vec<LeaderboardEntry>,
+}
+
+// The top `limit` traders ordered by `column` of the per-user totals.
+fn ranking(conn: &mut SqliteConnection, since: Option<chrono::NaiveDateTime>, limit: i64, column: &str) -> QueryResult<Vec<LeaderboardEntry>> {
+    diesel::sql_query(format!(
+        "SELECT ROW_NUMBER() OVER (ORDER BY totals.{column} DESC, totals.user_id) AS rank, \
+            totals.user_id, users.name, totals.pnl, totals.volume, totals.trades \
+        FROM (\
+            SELECT user_id, \
+                CAST(SUM({pnl}) AS REAL) AS pnl, \
+                CAST(SUM(traded_amount * execution_price) AS REAL) AS volume, \
+                COUNT(*) AS trades \
+            FROM trades \
+            WHERE deleted_at IS NULL AND source != ? AND (? IS NULL OR created_at >= ?) \
+            GROUP BY user_id\
+        ) AS totals \
+        JOIN users ON users.id = totals.user_id \
+        LEFT JOIN user_settings ON user_settings.user_id = totals.user_id \
+        WHERE COALESCE(user_settings.hide_from_leaderboard, 0) = 0 \
+        ORDER BY rank \
+        LIMIT ?",
+        column = column,
+        pnl = TRADE_PNL_SQL,
+    ))
+    .bind::<Text, _>(TradeSource::SIMULATION)
+    .bind::<Nullable<Timestamp>, _>(since)
+    .bind::<Nullable<Timestamp>, _>(since)
+    .bind::<BigInt, _>(limit)
+    .load::<LeaderboardEntry>(conn)
+}
+
+impl Leaderboard {
+    // The top `limit` traders since `since`, or of all time without it.
+    pub fn top(conn: &mut SqliteConnection, since: Option<chrono::NaiveDateTime>, limit: i64) -> QueryResult<Self> {
+        Ok(Leaderboard {
+            by_pnl: ranking(conn, since, limit, "pnl")?,
+            by_volume: ranking(conn, since, limit, "volume")?,
+        })
+    }
+}
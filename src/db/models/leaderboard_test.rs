@@ -0,0 +1,54 @@
+use diesel::prelude::*;
+
+use crate::db::fixtures::{self, test_connection, trade, user};
+use crate::services::trade::TradeForm;
+use super::leaderboard::Leaderboard;
+use super::trade::{Trade, TradeSource};
+use super::user::User;
+use super::user_settings::UserSettings;
+
+fn create_trade(conn: &mut SqliteConnection, user: &User, final_price: f32, traded_amount: f32, days_ago: i64, source: &str) -> Trade {
+    let created_at = chrono::Utc::now().naive_utc() - chrono::Duration::days(days_ago);
+    let form = TradeForm {
+        final_price: Some(final_price),
+        traded_amount: Some(traded_amount),
+        timestamp: Some(created_at.and_utc().timestamp()),
+        source: Some(source.to_string()),
+        ..trade(&user.id, &user.wallet_id)
+    };
+    fixtures::create_trade(conn, &form)
+}
+
+#[test]
+fn traders_are_ranked_by_pnl_and_volume_within_the_period() {
+    let conn = &mut test_connection();
+    let (alice, bob, carol) = (user(conn, "alice"), user(conn, "bob"), user(conn, "carol"));
+
+    let alice_trade = create_trade(conn, &alice, 150.0, 2.0, 1, TradeSource::MANUAL);
+    create_trade(conn, &alice, 1_000.0, 50.0, 1, TradeSource::SIMULATION);
+    let bob_trade = create_trade(conn, &bob, 90.0, 10.0, 2, TradeSource::MANUAL);
+    let bob_old_trade = create_trade(conn, &bob, 500.0, 10.0, 60, TradeSource::MANUAL);
+    create_trade(conn, &carol, 300.0, 1.0, 3, TradeSource::MANUAL);
+
+    // Carol opts out.
+    UserSettings::update(conn, carol.id.clone(), false, true).unwrap();
+
+    let since = chrono::Utc::now().naive_utc() - chrono::Duration::days(30);
+    let leaderboard = Leaderboard::top(conn, Some(since), 10).unwrap();
+    let by_pnl: Vec<(i64, &str)> = leaderboard.by_pnl.iter().map(|entry| (entry.rank, entry.name.as_str())).collect();
+    let by_volume: Vec<(i64, &str)> = leaderboard.by_volume.iter().map(|entry| (entry.rank, entry.name.as_str())).collect();
+    assert_eq!(by_pnl, vec![(1, "alice"), (2, "bob")]);
+    assert_eq!(by_volume, vec![(1, "bob"), (2, "alice")]);
+
+    let alice_entry = &leaderboard.by_pnl[0];
+    assert_eq!(alice_entry.trades, 1);
+    assert!((alice_entry.pnl - alice_trade.calculate_trade_pnl() as f64).abs() < 1e-3);
+    assert!((alice_entry.volume - 200.0).abs() < 1e-3);
+
+    let all_time = Leaderboard::top(conn, None, 1).unwrap();
+    assert_eq!(all_time.by_pnl.len(), 1);
+    let bob_entry = &all_time.by_pnl[0];
+    assert_eq!((bob_entry.user_id.as_str(), bob_entry.trades), (bob.id.as_str(), 2));
+    let bob_pnl = bob_trade.calculate_trade_pnl() + bob_old_trade.calculate_trade_pnl();
+    assert!((bob_entry.pnl - bob_pnl as f64).abs() < 1e-2);
+}
@@ -0,0 +1,101 @@
+//! This module defines the login attempts recorded to lock accounts out after repeated failures.
+//!
+//! `User::login` records every password check as a `LoginAttempt`, successful or not, keyed by the email it was made
+//! for (trimmed and lowercased). Unknown emails are tracked exactly like registered ones, so the lockout does not
+//! reveal which emails have an account.
+//!
+//! An email is locked once it has `login.max_failures` failed attempts (see `config::settings`) since its last
+//! successful login, all within the last `login.lockout_minutes`. It stays locked until the oldest of those failures
+//! is `login.lockout_minutes` old; while it is locked, passwords are not checked and attempts are not recorded, so
+//! retrying does not extend the lock. `lock_state` reports either the lock's end or the failures left before it.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use crate::models::login_attempt::{LockState, LoginAttempt};
+//!
+//! let now = chrono::Local::now().naive_local();
+//! match LoginAttempt::lock_state(&mut connection, "john@example.com", now)? {
+//!     LockState::Locked { until } => println!("Locked until {}", until),
+//!     LockState::Open { attempts_left } => println!("{} attempt(s) left", attempts_left),
+//! }
+//! ```
+//!
+//! # Note
+//! This module assumes the availability of a database connection (`SqliteConnection` in this case) for login attempt data retrieval and manipulation.
+
+use uuid::Uuid;
+use serde::{Serialize, Deserialize};
+use diesel::prelude::*;
+
+use crate::config::settings;
+use super::super::error::DbError;
+use super::super::retry::retry_on_busy;
+use super::super::schema::login_attempts;
+
+#[derive(Debug, Deserialize, Serialize, Queryable, Insertable)]
+#[diesel(table_name = crate::db::schema::login_attempts)]
+pub struct LoginAttempt {
+    pub id: String,
+    pub email: String,
+    pub user_id: Option<String>,
+    pub succeeded: bool,
+    pub created_at: chrono::NaiveDateTime,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum LockState {
+    Open { attempts_left: u32 },
+    Locked { until: chrono::NaiveDateTime },
+}
+
+fn normalize(email: &str) -> String {
+    email.trim().to_lowercase()
+}
+
+impl LoginAttempt {
+    pub fn record(conn: &mut SqliteConnection, email: &str, user_id: Option<&str>, succeeded: bool) -> Result<Self, DbError> {
+        let attempt = LoginAttempt {
+            id: Uuid::new_v4().as_hyphenated().to_string(),
+            email: normalize(email),
+            user_id: user_id.map(str::to_string),
+            succeeded,
+            created_at: chrono::Local::now().naive_local(),
+        };
+        retry_on_busy(|| {
+            diesel::insert_into(login_attempts::table)
+                .values(&attempt)
+                .execute(conn)
+        })?;
+
+        Ok(attempt)
+    }
+
+    pub fn lock_state(conn: &mut SqliteConnection, email: &str, now: chrono::NaiveDateTime) -> Result<LockState, DbError> {
+        let login = &settings::get().login;
+        let email = normalize(email);
+        let lockout = chrono::Duration::minutes(login.lockout_minutes);
+
+        let last_success = login_attempts::table
+            .filter(login_attempts::email.eq(&email))
+            .filter(login_attempts::succeeded.eq(true))
+            .select(diesel::dsl::max(login_attempts::created_at))
+            .first::<Option<chrono::NaiveDateTime>>(conn)?;
+        let since = last_success.map_or(now - lockout, |last_success| last_success.max(now - lockout));
+
+        // The most recent failures that count towards the lock, newest first.
+        let failures = login_attempts::table
+            .filter(login_attempts::email.eq(&email))
+            .filter(login_attempts::succeeded.eq(false))
+            .filter(login_attempts::created_at.gt(since))
+            .order(login_attempts::created_at.desc())
+            .select(login_attempts::created_at)
+            .limit(login.max_failures as i64)
+            .load::<chrono::NaiveDateTime>(conn)?;
+
+        match failures.get(login.max_failures as usize - 1) {
+            Some(oldest) => Ok(LockState::Locked { until: *oldest + lockout }),
+            None => Ok(LockState::Open { attempts_left: login.max_failures - failures.len() as u32 }),
+        }
+    }
+}
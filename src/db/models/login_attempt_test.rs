@@ -0,0 +1,62 @@
+use diesel::SqliteConnection;
+
+use crate::db::fixtures::{test_connection, user, TEST_PASSWORD};
+use super::login_attempt::{LockState, LoginAttempt};
+use super::user::{LoginOutcome, User};
+
+fn login(conn: &mut SqliteConnection, email: &str, password: &str) -> LoginOutcome {
+    User::login(conn, email.to_string(), password.to_string()).unwrap()
+}
+
+#[test]
+fn locks_after_consecutive_failures() {
+    let conn = &mut test_connection();
+    user(conn, "lockout");
+
+    for attempts_left in (1..5).rev() {
+        assert!(matches!(login(conn, "lockout@example.com", "wrong_password"), LoginOutcome::Failed { attempts_left: left } if left == attempts_left));
+    }
+    let now = chrono::Local::now().naive_local();
+    let LoginOutcome::Locked { until } = login(conn, "lockout@example.com", "wrong_password") else {
+        panic!("the fifth failure should lock the account");
+    };
+    assert!(until > now + chrono::Duration::minutes(14) && until <= now + chrono::Duration::minutes(16));
+
+    // The right password is not even checked while locked, and retrying does not extend the lock.
+    assert!(matches!(login(conn, "lockout@example.com", TEST_PASSWORD), LoginOutcome::Locked { until: retried } if retried == until));
+}
+
+#[test]
+fn success_resets_the_failure_count() {
+    let conn = &mut test_connection();
+    user(conn, "lockout");
+
+    for _ in 0..4 {
+        login(conn, "lockout@example.com", "wrong_password");
+    }
+    assert!(matches!(login(conn, "lockout@example.com", TEST_PASSWORD), LoginOutcome::Success(_)));
+    assert!(matches!(login(conn, "lockout@example.com", "wrong_password"), LoginOutcome::Failed { attempts_left: 4 }));
+}
+
+#[test]
+fn unknown_emails_are_tracked_like_registered_ones() {
+    let conn = &mut test_connection();
+
+    for _ in 0..5 {
+        login(conn, "Nobody@Example.com ", TEST_PASSWORD);
+    }
+    let now = chrono::Local::now().naive_local();
+    assert!(matches!(LoginAttempt::lock_state(conn, "nobody@example.com", now).unwrap(), LockState::Locked { .. }));
+}
+
+#[test]
+fn locks_expire() {
+    let conn = &mut test_connection();
+    user(conn, "lockout");
+
+    for _ in 0..5 {
+        login(conn, "lockout@example.com", "wrong_password");
+    }
+    let later = chrono::Local::now().naive_local() + chrono::Duration::minutes(16);
+    assert_eq!(LoginAttempt::lock_state(conn, "lockout@example.com", later).unwrap(), LockState::Open { attempts_left: 5 });
+}
@@ -0,0 +1,74 @@
+//! This module defines the outbox of emails sent to users.
+//!
+//! Flows that need to reach a user by email (such as confirming an email address change, see `email_change`) queue an
+//! `OutboundEmail` in the same transaction as the change that triggers it, so a message is never sent for a change
+//! that was rolled back, nor lost for one that was committed. A delivery worker reads the unsent messages oldest first
+//! with `OutboundEmail::pending` and marks each one with `OutboundEmail::mark_sent` once the mail provider accepted it.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use crate::models::outbound_email::OutboundEmail;
+//!
+//! OutboundEmail::queue(&mut connection, "user@example.com", "Subject", "Body".to_string())?;
+//!
+//! for email in OutboundEmail::pending(&mut connection, 50)? {
+//!     // ... hand the message to the mail provider ...
+//!     OutboundEmail::mark_sent(&mut connection, email.id)?;
+//! }
+//! ```
+//!
+//! # Note
+//! This module assumes the availability of a database connection (`SqliteConnection` in this case) for outbox data retrieval and manipulation.
+
+use uuid::Uuid;
+use serde::{Serialize, Deserialize};
+use diesel::prelude::*;
+
+use super::super::error::DbError;
+use super::super::retry::retry_on_busy;
+use super::super::schema::outbound_emails;
+
+#[derive(Debug, Deserialize, Serialize, Queryable, Insertable)]
+#[diesel(table_name = crate::db::schema::outbound_emails)]
+pub struct OutboundEmail {
+    pub id: String,
+    pub recipient: String,
+    pub subject: String,
+    pub body: String,
+    pub created_at: chrono::NaiveDateTime,
+    pub sent_at: Option<chrono::NaiveDateTime>,
+}
+
+impl OutboundEmail {
+    pub fn pending(conn: &mut SqliteConnection, limit: i64) -> Result<Vec<Self>, DbError> {
+        Ok(outbound_emails::table
+            .filter(outbound_emails::sent_at.is_null())
+            .order(outbound_emails::created_at.asc())
+            .limit(limit)
+            .load::<OutboundEmail>(conn)?)
+    }
+
+    // Queues a message; meant to run inside the transaction of the change it reports.
+    pub fn queue(conn: &mut SqliteConnection, recipient: &str, subject: &str, body: String) -> QueryResult<Self> {
+        let email = OutboundEmail {
+            id: Uuid::new_v4().as_hyphenated().to_string(),
+            recipient: recipient.to_string(),
+            subject: subject.to_string(),
+            body,
+            created_at: chrono::Local::now().naive_local(),
+            sent_at: None,
+        };
+        diesel::insert_into(outbound_emails::table).values(&email).execute(conn)?;
+        Ok(email)
+    }
+
+    pub fn mark_sent(conn: &mut SqliteConnection, id: String) -> Result<bool, DbError> {
+        let updated = retry_on_busy(|| {
+            diesel::update(outbound_emails::table.find(&id).filter(outbound_emails::sent_at.is_null()))
+                .set(outbound_emails::sent_at.eq(chrono::Local::now().naive_local()))
+                .execute(conn)
+        })?;
+        Ok(updated > 0)
+    }
+}
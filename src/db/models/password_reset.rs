@@ -0,0 +1,154 @@
+//! This module defines the single-use tokens that reset a forgotten password.
+//!
+//! `PasswordReset::request` looks the account up by email and, if there is one, issues a random token valid for
+//! `PASSWORD_RESET_MINUTES` and queues it in an email to the account's address (`outbound_email`). Requesting again
+//! invalidates the tokens issued before. Only a SHA-256 hash of each token is stored, as for refresh tokens, so a
+//! database leak does not expose usable tokens. An unknown email issues nothing but is not an error, so callers can
+//! answer every request the same way and not reveal which emails are registered.
+//!
+//! `PasswordReset::reset` takes the token and the new password. An unknown, expired or already used token fails with
+//! `INVALID_TOKEN`. Otherwise, in one transaction, the token is marked used, the password is replaced by its bcrypt
+//! hash, every session of the user is ended (refresh tokens are revoked) and the account's address is told that the
+//! password changed.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use crate::models::password_reset::PasswordReset;
+//!
+//! // The token is only ever sent to the account's email address.
+//! PasswordReset::request(&mut connection, "john@example.com".to_string())?;
+//!
+//! // Later, with the token from the email.
+//! let (user, errors) = PasswordReset::reset(&mut connection, &token, "new_password".to_string())?;
+//! ```
+//!
+//! # Note
+//! This module assumes the availability of a database connection (`SqliteConnection` in this case) for password reset data retrieval and manipulation.
+
+use uuid::Uuid;
+use rand::Rng;
+use serde::{Serialize, Deserialize};
+use sha2::{Digest, Sha256};
+use diesel::prelude::*;
+
+use super::super::error::DbError;
+use super::super::retry::retry_on_busy;
+use super::super::schema::{password_resets, users};
+use super::outbound_email::OutboundEmail;
+use super::refresh_token::RefreshToken;
+use super::user::User;
+
+pub const PASSWORD_RESET_MINUTES: i64 = 60;
+
+pub const INVALID_TOKEN: &str = "Invalid or expired reset token";
+
+#[derive(Debug, Deserialize, Serialize, Queryable, Insertable)]
+#[diesel(table_name = crate::db::schema::password_resets)]
+pub struct PasswordReset {
+    pub id: String,
+    pub user_id: String,
+    #[serde(skip_serializing)]
+    pub token_hash: String,
+    pub created_at: chrono::NaiveDateTime,
+    pub expires_at: chrono::NaiveDateTime,
+    pub used_at: Option<chrono::NaiveDateTime>,
+}
+
+fn hash(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
+
+impl PasswordReset {
+    // The issued token, or `None` when no account has the email.
+    pub fn request(conn: &mut SqliteConnection, email: String) -> Result<Option<String>, DbError> {
+        let user = match User::find_by_email(conn, email.trim().to_string())? {
+            Some(user) => user,
+            None => return Ok(None),
+        };
+
+        let token = hex::encode(rand::thread_rng().gen::<[u8; 32]>());
+        let now = chrono::Local::now().naive_local();
+        let reset = PasswordReset {
+            id: Uuid::new_v4().as_hyphenated().to_string(),
+            user_id: user.id.clone(),
+            token_hash: hash(&token),
+            created_at: now,
+            expires_at: now + chrono::Duration::minutes(PASSWORD_RESET_MINUTES),
+            used_at: None,
+        };
+
+        retry_on_busy(|| {
+            conn.transaction(|conn| {
+                diesel::update(
+                    password_resets::table
+                        .filter(password_resets::user_id.eq(&user.id))
+                        .filter(password_resets::used_at.is_null()),
+                )
+                .set(password_resets::used_at.eq(now))
+                .execute(conn)?;
+                diesel::insert_into(password_resets::table).values(&reset).execute(conn)?;
+                OutboundEmail::queue(conn, &user.email, "Reset your password", format!(
+                    "Hi {},\n\nSet a new password for your account with the token below. It expires in {} minutes and \
+                    can be used once.\n\n{}\n\nIf you did not ask for this, ignore this email; your password stays \
+                    the same.",
+                    user.name, PASSWORD_RESET_MINUTES, token
+                ))
+            })
+        })?;
+
+        Ok(Some(token))
+    }
+
+    pub fn find_active(conn: &mut SqliteConnection, token: &str) -> Result<Option<Self>, DbError> {
+        Ok(password_resets::table
+            .filter(password_resets::token_hash.eq(hash(token)))
+            .filter(password_resets::used_at.is_null())
+            .filter(password_resets::expires_at.gt(chrono::Local::now().naive_local()))
+            .first::<PasswordReset>(conn)
+            .optional()?)
+    }
+
+    pub fn reset(conn: &mut SqliteConnection, token: &str, password: String) -> Result<(Option<User>, Option<String>), DbError> {
+        if password.is_empty() {
+            return Ok((None, Some("Missing required fields".to_string())));
+        }
+        let reset = match Self::find_active(conn, token)? {
+            Some(reset) => reset,
+            None => return Ok((None, Some(INVALID_TOKEN.to_string()))),
+        };
+        let user = match User::find_by_id(conn, reset.user_id.clone())? {
+            Some(user) => user,
+            None => return Ok((None, Some(INVALID_TOKEN.to_string()))),
+        };
+
+        let hashed_password = bcrypt::hash(password, bcrypt::DEFAULT_COST).unwrap();
+        let now = chrono::Local::now().naive_local();
+        let applied = retry_on_busy(|| {
+            conn.transaction(|conn| {
+                // Another request used the same token first.
+                let claimed = diesel::update(password_resets::table.find(&reset.id).filter(password_resets::used_at.is_null()))
+                    .set(password_resets::used_at.eq(now))
+                    .execute(conn)?;
+                if claimed == 0 {
+                    return Ok(false);
+                }
+                diesel::update(users::table.find(&user.id))
+                    .set((users::password.eq(&hashed_password), users::updated_at.eq(now)))
+                    .execute(conn)?;
+                RefreshToken::revoke_all(conn, &user.id)?;
+                OutboundEmail::queue(conn, &user.email, "Your password was changed", format!(
+                    "Hi {},\n\nThe password of your account was reset and you were logged out everywhere. If you did \
+                    not do this, reset your password again right away.",
+                    user.name
+                ))?;
+                Ok(true)
+            })
+        })?;
+
+        if !applied {
+            return Ok((None, Some(INVALID_TOKEN.to_string())));
+        }
+        Ok((User::find_by_id(conn, user.id)?, None))
+    }
+}
@@ -0,0 +1,53 @@
+use crate::db::fixtures::{test_connection, user, TEST_PASSWORD};
+use super::outbound_email::OutboundEmail;
+use super::password_reset::{PasswordReset, INVALID_TOKEN};
+use super::refresh_token::RefreshToken;
+use super::user::{User, LoginOutcome};
+
+#[test]
+fn resets_the_password_once() {
+    let conn = &mut test_connection();
+    let user = user(conn, "reset");
+    let (session, _) = RefreshToken::issue(conn, user.id.clone()).unwrap();
+
+    let token = PasswordReset::request(conn, "reset@example.com".to_string()).unwrap().unwrap();
+    let outbox = OutboundEmail::pending(conn, 10).unwrap();
+    assert_eq!(outbox.len(), 1);
+    assert_eq!(outbox[0].recipient, "reset@example.com");
+    assert!(outbox[0].body.contains(&token));
+
+    let (reset, errors) = PasswordReset::reset(conn, &token, "new_password".to_string()).unwrap();
+    assert_eq!((reset.map(|user| user.id), errors), (Some(user.id), None));
+    assert!(matches!(User::login(conn, "reset@example.com".to_string(), "new_password".to_string()).unwrap(), LoginOutcome::Success(_)));
+    assert!(User::login(conn, "reset@example.com".to_string(), TEST_PASSWORD.to_string()).unwrap().user().is_none());
+    // Every session ended and the owner was told.
+    assert!(RefreshToken::find_active(conn, &session).unwrap().is_none());
+    assert_eq!(OutboundEmail::pending(conn, 10).unwrap().len(), 2);
+
+    let (reused, errors) = PasswordReset::reset(conn, &token, "another_password".to_string()).unwrap();
+    assert!(reused.is_none());
+    assert_eq!(errors.as_deref(), Some(INVALID_TOKEN));
+}
+
+#[test]
+fn a_new_request_invalidates_older_tokens() {
+    let conn = &mut test_connection();
+    user(conn, "reset");
+
+    let first = PasswordReset::request(conn, "reset@example.com".to_string()).unwrap().unwrap();
+    let second = PasswordReset::request(conn, "reset@example.com".to_string()).unwrap().unwrap();
+
+    assert!(PasswordReset::find_active(conn, &first).unwrap().is_none());
+    assert!(PasswordReset::find_active(conn, &second).unwrap().is_some());
+}
+
+#[test]
+fn unknown_emails_issue_nothing() {
+    let conn = &mut test_connection();
+
+    assert_eq!(PasswordReset::request(conn, "nobody@example.com".to_string()).unwrap(), None);
+    assert!(OutboundEmail::pending(conn, 10).unwrap().is_empty());
+    let (user, errors) = PasswordReset::reset(conn, "not-a-token", "new_password".to_string()).unwrap();
+    assert!(user.is_none());
+    assert_eq!(errors.as_deref(), Some(INVALID_TOKEN));
+}
@@ -0,0 +1,144 @@
+//! This module defines the positions derived from a user's trades, one per asset.
+//!
+//! A `Position` is never edited directly. `Trade::create`, `Trade::update` and `Trade::delete` call `recompute`,
+//! which replays the user's trades of that asset in execution order (`created_at`) and stores the result, so the row
+//! always matches the trade history no matter which trade changed. Buys (`LimitBuy`, `MarketBuy`) add
+//! `traded_amount` at `execution_price` and sells (`LimitSell`, `MarketSell`) remove it; a sell larger than the
+//! holding leaves a short (negative) position. Adding to a position moves `average_entry_price` to the weighted
+//! average, while reducing it realizes `(price - average_entry_price) * closed quantity` in `realized_pnl` and keeps the
+//! average. Fees are not included.
+//!
+//! `last_price` is the mark used for `unrealized_pnl`: the `final_price` of the most recent trade, or its
+//! `execution_price` when no final price was recorded. When the last trade of an asset is deleted the position row is
+//! removed.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use crate::models::position::Position;
+//!
+//! // Rebuild the ETH position of a user after one of their trades changed.
+//! Position::recompute(&mut connection, "user_id", "ETH")?;
+//!
+//! // List the open and closed positions of a user.
+//! for position in Position::list_for_user(&mut connection, "user_id".to_string())? {
+//!     println!("{} {} @ {} ({})", position.asset, position.quantity, position.average_entry_price, position.unrealized_pnl());
+//! }
+//! ```
+//!
+//! # Note
+//! This module assumes the availability of a database connection (`SqliteConnection` in this case) for position data retrieval and manipulation.
+
+use serde::{Serialize, Deserialize};
+use diesel::prelude::*;
+
+use super::super::error::DbError;
+use super::super::retry::retry_on_busy;
+use super::super::schema::{positions, trades};
+use super::trade::Trade;
+
+// Quantities below this are treated as a closed position, absorbing `f32` rounding.
+const QUANTITY_EPSILON: f32 = 1e-6;
+
+#[derive(Debug, Deserialize, Serialize, Queryable, Insertable)]
+#[diesel(table_name = crate::db::schema::positions)]
+pub struct Position {
+    pub user_id: String,
+    pub asset: String,
+    pub quantity: f32,
+    pub average_entry_price: f32,
+    pub realized_pnl: f32,
+    pub last_price: f32,
+    pub updated_at: chrono::NaiveDateTime,
+}
+
+impl Position {
+    pub fn find(conn: &mut SqliteConnection, user_id: String, asset: String) -> Result<Option<Self>, DbError> {
+        Ok(positions::table
+            .find((user_id, asset))
+            .first::<Position>(conn)
+            .optional()?)
+    }
+
+    pub fn list_for_user(conn: &mut SqliteConnection, user_id: String) -> Result<Vec<Self>, DbError> {
+        Ok(positions::table
+            .filter(positions::user_id.eq(user_id))
+            .order(positions::asset.asc())
+            .load::<Position>(conn)?)
+    }
+
+    pub fn unrealized_pnl(&self) -> f32 {
+        (self.last_price - self.average_entry_price) * self.quantity
+    }
+
+    /// Folds trades of a single user and asset, oldest first, into a position. Returns `None` for an empty slice.
+    pub fn from_trades(trades: &[Trade]) -> Option<Self> {
+        let first = trades.first()?;
+        let mut position = Position {
+            user_id: first.user_id.clone(),
+            asset: first.asset.clone(),
+            quantity: 0.0,
+            average_entry_price: 0.0,
+            realized_pnl: 0.0,
+            last_price: 0.0,
+            updated_at: chrono::Local::now().naive_local(),
+        };
+
+        for trade in trades {
+            let price = trade.execution_price;
+            let size = match trade.trade_type.as_str() {
+                "LimitBuy" | "MarketBuy" => trade.traded_amount,
+                "LimitSell" | "MarketSell" => -trade.traded_amount,
+                _ => 0.0,
+            };
+            position.last_price = if trade.final_price > 0.0 { trade.final_price } else { price };
+
+            if size == 0.0 {
+                continue;
+            }
+
+            let quantity = position.quantity + size;
+            if position.quantity == 0.0 || position.quantity.signum() == size.signum() {
+                position.average_entry_price =
+                    (position.average_entry_price * position.quantity.abs() + price * size.abs()) / quantity.abs();
+            } else {
+                let closed = size.abs().min(position.quantity.abs());
+                position.realized_pnl += (price - position.average_entry_price) * closed * position.quantity.signum();
+                if quantity.abs() < QUANTITY_EPSILON {
+                    position.average_entry_price = 0.0;
+                } else if quantity.signum() != position.quantity.signum() {
+                    position.average_entry_price = price;
+                }
+            }
+            position.quantity = if quantity.abs() < QUANTITY_EPSILON { 0.0 } else { quantity };
+        }
+
+        Some(position)
+    }
+
+    pub fn recompute(conn: &mut SqliteConnection, user_id: &str, asset: &str) -> Result<Option<Self>, DbError> {
+        let history = trades::table
+            .filter(trades::user_id.eq(user_id))
+            .filter(trades::asset.eq(asset))
+            .filter(trades::deleted_at.is_null())
+            .order((trades::created_at.asc(), trades::id.asc()))
+            .load::<Trade>(conn)?;
+
+        match Self::from_trades(&history) {
+            Some(position) => {
+                retry_on_busy(|| {
+                    diesel::replace_into(positions::table)
+                        .values(&position)
+                        .execute(conn)
+                })?;
+                Ok(Some(position))
+            }
+            None => {
+                retry_on_busy(|| {
+                    diesel::delete(positions::table.find((user_id, asset))).execute(conn)
+                })?;
+                Ok(None)
+            }
+        }
+    }
+}
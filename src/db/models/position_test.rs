@@ -0,0 +1,69 @@
+use crate::db::fixtures::{test_connection, trade, user};
+use crate::services::trade::{fill_optional_fields, TradeForm};
+use super::position::Position;
+use super::trade::Trade;
+use super::user::User;
+
+fn form(user_id: &str, wallet_id: &str, trade_type: &str, asset: &str, price: f32, quantity: f32, timestamp: i64) -> TradeForm {
+    TradeForm {
+        amount: price * quantity,
+        trade_type: trade_type.to_string(),
+        asset: asset.to_string(),
+        execution_price: Some(price),
+        final_price: None,
+        traded_amount: Some(quantity),
+        timestamp: Some(timestamp),
+        ..trade(user_id, wallet_id)
+    }
+}
+
+#[test]
+fn positions_follow_trade_history() {
+    let conn = &mut test_connection();
+    let User { id: user_id, wallet_id, .. } = user(conn, "trader");
+
+    Trade::create(conn, &mut fill_optional_fields(&form(&user_id, &wallet_id, "MarketBuy", "ETH", 100.0, 2.0, 1_692_000_000))).unwrap();
+    let second = Trade::create(conn, &mut fill_optional_fields(&form(&user_id, &wallet_id, "LimitBuy", "ETH", 200.0, 2.0, 1_692_000_100))).unwrap().0.unwrap();
+    let position = Position::find(conn, user_id.clone(), "ETH".to_string()).unwrap().unwrap();
+    assert_eq!(position.quantity, 4.0);
+    assert_eq!(position.average_entry_price, 150.0);
+    assert_eq!(position.unrealized_pnl(), 200.0);
+
+    let sell = Trade::create(conn, &mut fill_optional_fields(&form(&user_id, &wallet_id, "MarketSell", "ETH", 180.0, 1.0, 1_692_000_200))).unwrap().0.unwrap();
+    let position = Position::find(conn, user_id.clone(), "ETH".to_string()).unwrap().unwrap();
+    assert_eq!(position.quantity, 3.0);
+    assert_eq!(position.average_entry_price, 150.0);
+    assert_eq!(position.realized_pnl, 30.0);
+    assert_eq!(position.last_price, 180.0);
+
+    let mut moved = fill_optional_fields(&form(&user_id, &wallet_id, "LimitBuy", "BTC", 200.0, 2.0, 1_692_000_100));
+    Trade::update(conn, second.id, &mut moved, &user_id).unwrap();
+    let position = Position::find(conn, user_id.clone(), "ETH".to_string()).unwrap().unwrap();
+    assert_eq!(position.quantity, 1.0);
+    assert_eq!(position.realized_pnl, 80.0);
+    assert_eq!(Position::find(conn, user_id.clone(), "BTC".to_string()).unwrap().unwrap().quantity, 2.0);
+
+    assert_eq!(Trade::delete(conn, sell.id, &user_id).unwrap(), (true, None));
+    assert_eq!(Position::find(conn, user_id.clone(), "ETH".to_string()).unwrap().unwrap().quantity, 2.0);
+    assert_eq!(Position::list_for_user(conn, user_id).unwrap().len(), 2);
+}
+
+#[test]
+fn closing_and_flipping_reset_the_entry_price() {
+    let conn = &mut test_connection();
+    let User { id: user_id, wallet_id, .. } = user(conn, "trader");
+
+    let buy = Trade::create(conn, &mut fill_optional_fields(&form(&user_id, &wallet_id, "MarketBuy", "BTC", 10.0, 1.0, 1_692_000_000))).unwrap().0.unwrap();
+    Trade::create(conn, &mut fill_optional_fields(&form(&user_id, &wallet_id, "MarketSell", "BTC", 12.0, 3.0, 1_692_000_100))).unwrap();
+    let position = Position::find(conn, user_id.clone(), "BTC".to_string()).unwrap().unwrap();
+    assert_eq!(position.quantity, -2.0);
+    assert_eq!(position.average_entry_price, 12.0);
+    assert_eq!(position.realized_pnl, 2.0);
+
+    let trades = Trade::search(conn, &Default::default(), Default::default(), 10, 0).unwrap();
+    for trade in trades {
+        Trade::delete(conn, trade.id, &user_id).unwrap();
+    }
+    assert!(Trade::find_by_id(conn, buy.id).unwrap().is_none());
+    assert!(Position::find(conn, user_id, "BTC".to_string()).unwrap().is_none());
+}
@@ -0,0 +1,138 @@
+//! This module defines the versioned end-of-day price fixing used for daily snapshots and reporting conversions.
+//!
+//! A `PriceFixing` names the `source` of the end-of-day prices and the `fixing_time` (UTC) at which a day's prices are
+//! taken, in force from `effective_from` up to (but excluding) `effective_to`; the latest fixing is open-ended. The
+//! sources are:
+//!
+//! - `coingecko`: the price feed of `services::prices`, sampled at the fixing time.
+//! - `last_trade`: the execution price of the asset's last trade at or before the fixing time.
+//!
+//! A day is fixed with the fixing in force at its start (`for_date`), so a change never applies halfway through a day,
+//! and `fixed_at` is the moment its prices are taken. Every `DailySnapshot` records the `fixing_id` it was built with,
+//! which keeps old days traceable after the fixing changes: rebuilding a day (e.g. by a recompute job) uses the fixing
+//! that was in force on that day, not the current one. Days snapshotted before fixings were configurable refer to the
+//! built-in fixing (`DEFAULT_FIXING_ID`: `coingecko` at `16:00`).
+//!
+//! Like fee schedules, `create` appends a version that must start after every existing one, closes the open one and is
+//! recorded in the audit log (`audit`) in the same transaction. `list` returns the full history.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use crate::models::price_fixing::PriceFixing;
+//!
+//! let fixing_time = chrono::NaiveTime::from_hms_opt(16, 0, 0).unwrap();
+//! let (fixing, errors) = PriceFixing::create(&mut connection, "last_trade".to_string(), fixing_time, effective_from, "admin_id".to_string())?;
+//!
+//! let fixing = PriceFixing::for_date(&mut connection, day)?;
+//! println!("{} prices are fixed at {}", fixing.source, fixing.fixed_at(day));
+//! ```
+//!
+//! # Note
+//! This module assumes the availability of a database connection (`SqliteConnection` in this case) for fixing data retrieval and manipulation.
+
+use uuid::Uuid;
+use serde::{Serialize, Deserialize};
+use diesel::prelude::*;
+
+use super::super::error::DbError;
+use super::super::retry::retry_on_busy;
+use super::super::schema::price_fixings;
+use super::audit::AuditEntry;
+
+pub const DEFAULT_FIXING_ID: &str = "00000000-0000-0000-0000-000000000000";
+pub const SOURCES: [&str; 2] = ["coingecko", "last_trade"];
+
+#[derive(Debug, Deserialize, Serialize, Queryable, Insertable)]
+#[diesel(table_name = crate::db::schema::price_fixings)]
+pub struct PriceFixing {
+    pub id: String,
+    pub source: String,
+    pub fixing_time: chrono::NaiveTime,
+    pub effective_from: chrono::NaiveDateTime,
+    pub effective_to: Option<chrono::NaiveDateTime>,
+    pub created_by: String,
+    pub created_at: chrono::NaiveDateTime,
+}
+
+impl PriceFixing {
+    pub fn find_by_id(conn: &mut SqliteConnection, id: String) -> Result<Option<Self>, DbError> {
+        Ok(price_fixings::table
+            .find(id)
+            .first::<PriceFixing>(conn)
+            .optional()?)
+    }
+
+    pub fn list(conn: &mut SqliteConnection) -> Result<Vec<Self>, DbError> {
+        Ok(price_fixings::table
+            .order(price_fixings::effective_from.desc())
+            .load::<PriceFixing>(conn)?)
+    }
+
+    pub fn in_force_at(conn: &mut SqliteConnection, at: chrono::NaiveDateTime) -> QueryResult<Option<Self>> {
+        price_fixings::table
+            .filter(price_fixings::effective_from.le(at))
+            .filter(price_fixings::effective_to.is_null().or(price_fixings::effective_to.gt(at)))
+            .order(price_fixings::effective_from.desc())
+            .first::<PriceFixing>(conn)
+            .optional()
+    }
+
+    // The fixing of a day, falling back to the built-in one.
+    pub fn for_date(conn: &mut SqliteConnection, date: chrono::NaiveDate) -> QueryResult<Self> {
+        match Self::in_force_at(conn, date.and_time(chrono::NaiveTime::MIN))? {
+            Some(fixing) => Ok(fixing),
+            None => price_fixings::table.find(DEFAULT_FIXING_ID).first::<PriceFixing>(conn),
+        }
+    }
+
+    pub fn fixed_at(&self, date: chrono::NaiveDate) -> chrono::NaiveDateTime {
+        date.and_time(self.fixing_time)
+    }
+
+    pub fn create(
+        conn: &mut SqliteConnection,
+        source: String,
+        fixing_time: chrono::NaiveTime,
+        effective_from: chrono::NaiveDateTime,
+        created_by: String,
+    ) -> Result<(Option<Self>, Option<String>), DbError> {
+        if !SOURCES.contains(&source.as_str()) {
+            return Ok((None, Some(format!("Unknown price source, expected one of: {}", SOURCES.join(", ")))));
+        }
+
+        let latest = price_fixings::table
+            .select(diesel::dsl::max(price_fixings::effective_from))
+            .first::<Option<chrono::NaiveDateTime>>(conn)?;
+        if latest.is_some_and(|latest| effective_from <= latest) {
+            return Ok((None, Some("A new fixing must take effect after the latest one".to_string())));
+        }
+
+        let fixing = PriceFixing {
+            id: Uuid::new_v4().as_hyphenated().to_string(),
+            source,
+            fixing_time,
+            effective_from,
+            effective_to: None,
+            created_by,
+            created_at: chrono::Local::now().naive_local(),
+        };
+        let details = serde_json::json!({
+            "source": fixing.source,
+            "fixing_time": fixing.fixing_time,
+            "effective_from": fixing.effective_from,
+        });
+
+        retry_on_busy(|| {
+            conn.transaction(|conn| {
+                diesel::update(price_fixings::table.filter(price_fixings::effective_to.is_null()))
+                    .set(price_fixings::effective_to.eq(fixing.effective_from))
+                    .execute(conn)?;
+                diesel::insert_into(price_fixings::table).values(&fixing).execute(conn)?;
+                AuditEntry::record(conn, &fixing.created_by, "price_fixing", &fixing.id, details.clone())
+            })
+        })?;
+
+        Ok((Self::find_by_id(conn, fixing.id)?, None))
+    }
+}
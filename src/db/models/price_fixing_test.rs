@@ -0,0 +1,52 @@
+use crate::db::fixtures::{create_trade, test_connection, trade, user};
+use crate::services::trade::TradeForm;
+use super::price_fixing::{PriceFixing, DEFAULT_FIXING_ID};
+use super::snapshot::DailySnapshot;
+
+#[test]
+fn fixings_are_versioned_and_validated() {
+    let conn = &mut test_connection();
+    let four_pm = chrono::NaiveTime::from_hms_opt(16, 0, 0).unwrap();
+    let change = chrono::NaiveDate::from_ymd_opt(2023, 9, 1).unwrap().and_time(chrono::NaiveTime::MIN);
+
+    let (fixing, errors) = PriceFixing::create(conn, "bloomberg".to_string(), four_pm, change, "admin".to_string()).unwrap();
+    assert!(fixing.is_none());
+    assert_eq!(errors.unwrap(), "Unknown price source, expected one of: coingecko, last_trade");
+
+    let noon = chrono::NaiveTime::from_hms_opt(12, 0, 0).unwrap();
+    let (fixing, errors) = PriceFixing::create(conn, "last_trade".to_string(), noon, change, "admin".to_string()).unwrap();
+    assert!(errors.is_none());
+    let fixing = fixing.unwrap();
+    assert_eq!((fixing.fixing_time, fixing.effective_to), (noon, None));
+    assert_eq!(fixing.fixed_at(change.date()), change.date().and_time(noon));
+
+    let (_, errors) = PriceFixing::create(conn, "coingecko".to_string(), four_pm, change, "admin".to_string()).unwrap();
+    assert_eq!(errors.unwrap(), "A new fixing must take effect after the latest one");
+
+    let history = PriceFixing::list(conn).unwrap();
+    let history: Vec<(&str, &str, Option<chrono::NaiveDateTime>)> =
+        history.iter().map(|fixing| (fixing.id.as_str(), fixing.source.as_str(), fixing.effective_to)).collect();
+    assert_eq!(history, vec![(fixing.id.as_str(), "last_trade", None), (DEFAULT_FIXING_ID, "coingecko", Some(change))]);
+}
+
+#[test]
+fn snapshots_record_the_fixing_in_force_on_their_day() {
+    let conn = &mut test_connection();
+    let user = user(conn, "fixing");
+
+    // The fixing changes in the middle of the second day, so it only applies from the third one.
+    let day = |day: u32| chrono::NaiveDate::from_ymd_opt(2023, 9, day).unwrap();
+    let change = day(2).and_hms_opt(12, 0, 0).unwrap();
+    let (fixing, _) = PriceFixing::create(conn, "last_trade".to_string(), chrono::NaiveTime::MIN, change, "admin".to_string()).unwrap();
+    let fixing = fixing.unwrap();
+
+    for date in [day(1), day(2), day(3)] {
+        let timestamp = date.and_hms_opt(18, 0, 0).unwrap().and_utc().timestamp();
+        create_trade(conn, &TradeForm { timestamp: Some(timestamp), ..trade(&user.id, &user.wallet_id) });
+        DailySnapshot::rebuild(conn, &user.id, date).unwrap();
+    }
+
+    let snapshots = DailySnapshot::list_for_user(conn, user.id.clone(), day(1), day(3)).unwrap();
+    let fixings: Vec<&str> = snapshots.iter().map(|snapshot| snapshot.fixing_id.as_str()).collect();
+    assert_eq!(fixings, vec![DEFAULT_FIXING_ID, DEFAULT_FIXING_ID, fixing.id.as_str()]);
+}
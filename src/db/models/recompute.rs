@@ -0,0 +1,266 @@
+//! This module defines admin recompute jobs, which re-derive stored values from the trades table.
+//!
+//! After a fee or P&L formula changes, values stored earlier are stale. A `RecomputeJob` re-derives one kind of value
+//! (`RecomputeTarget`) for the trades created within an optional range:
+//!
+//! - `fees`: `execution_fee` and `transaction_fee` of every trade, from the fee schedule (`fee_schedule`), or the
+//!   user's fee override (`fee_override`), in force at its execution time.
+//! - `positions`: every position (`position`) of a user and asset with a trade in the range. Positions are rebuilt
+//!   from the full trade history, not only the range.
+//! - `snapshots`: every daily snapshot (`snapshot`) of a user and day with a trade in the range.
+//!
+//! `create` stores the job as `Pending` and appends an audit entry (`audit`) naming the admin who requested it, in one
+//! transaction. `run` then works through the affected records in batches of `BATCH_SIZE`, committing each batch and
+//! updating `processed` out of `total` so progress can be polled. A failed batch stops the job as `Failed` with the
+//! error; batches already committed stay applied, and running the same recompute again is safe. A job that cannot run
+//! at all, for instance when no connection is available, is marked `Failed` with `fail`.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use crate::models::recompute::{RecomputeJob, RecomputeTarget};
+//!
+//! let (job, errors) = RecomputeJob::create(&mut connection, RecomputeTarget::FEES.to_string(), Some((start, end)), "admin_id".to_string())?;
+//! let job = RecomputeJob::run(&mut connection, job.unwrap().id)?;
+//! println!("{} of {} trades", job.unwrap().processed, job.total);
+//! ```
+//!
+//! # Note
+//! This module assumes the availability of a database connection (`SqliteConnection` in this case) for job data retrieval and manipulation.
+
+use uuid::Uuid;
+use serde::{Serialize, Deserialize};
+use diesel::prelude::*;
+
+use super::super::error::DbError;
+use super::super::retry::retry_on_busy;
+use super::super::schema::{recompute_jobs, trades};
+use super::audit::AuditEntry;
+use super::fee_schedule::FeeSchedule;
+use super::position::Position;
+use super::snapshot::DailySnapshot;
+use super::trade::Trade;
+
+pub const BATCH_SIZE: usize = 200;
+
+#[derive(Debug, Deserialize, Serialize, Queryable, Insertable)]
+#[diesel(table_name = crate::db::schema::recompute_jobs)]
+pub struct RecomputeJob {
+    pub id: String,
+    pub what: String,
+    pub range_start: Option<chrono::NaiveDateTime>,
+    pub range_end: Option<chrono::NaiveDateTime>,
+    pub status: String,
+    pub total: i32,
+    pub processed: i32,
+    pub error: Option<String>,
+    pub requested_by: String,
+    pub created_at: chrono::NaiveDateTime,
+    pub updated_at: chrono::NaiveDateTime,
+}
+
+pub struct RecomputeTarget;
+
+impl RecomputeTarget {
+    pub const FEES: &'static str = "fees";
+    pub const SNAPSHOTS: &'static str = "snapshots";
+    pub const POSITIONS: &'static str = "positions";
+
+    pub fn is_valid(what: &str) -> bool {
+        matches!(what, Self::FEES | Self::SNAPSHOTS | Self::POSITIONS)
+    }
+}
+
+pub struct JobStatus;
+
+impl JobStatus {
+    pub const PENDING: &'static str = "Pending";
+    pub const RUNNING: &'static str = "Running";
+    pub const COMPLETED: &'static str = "Completed";
+    pub const FAILED: &'static str = "Failed";
+}
+
+impl RecomputeJob {
+    pub fn find_by_id(conn: &mut SqliteConnection, id: String) -> Result<Option<Self>, DbError> {
+        Ok(recompute_jobs::table
+            .find(id)
+            .first::<RecomputeJob>(conn)
+            .optional()?)
+    }
+
+    pub fn list(conn: &mut SqliteConnection, limit: i64) -> Result<Vec<Self>, DbError> {
+        Ok(recompute_jobs::table
+            .order(recompute_jobs::created_at.desc())
+            .limit(limit)
+            .load::<RecomputeJob>(conn)?)
+    }
+
+    pub fn create(
+        conn: &mut SqliteConnection,
+        what: String,
+        range: Option<(chrono::NaiveDateTime, chrono::NaiveDateTime)>,
+        requested_by: String,
+    ) -> Result<(Option<Self>, Option<String>), DbError> {
+        if !RecomputeTarget::is_valid(&what) {
+            return Ok((None, Some("what must be one of fees, snapshots or positions".to_string())));
+        }
+
+        let now = chrono::Local::now().naive_local();
+        let job = RecomputeJob {
+            id: Uuid::new_v4().as_hyphenated().to_string(),
+            what,
+            range_start: range.map(|(start, _)| start),
+            range_end: range.map(|(_, end)| end),
+            status: JobStatus::PENDING.to_string(),
+            total: 0,
+            processed: 0,
+            error: None,
+            requested_by,
+            created_at: now,
+            updated_at: now,
+        };
+        let details = serde_json::json!({
+            "what": job.what,
+            "range_start": job.range_start,
+            "range_end": job.range_end,
+        });
+
+        retry_on_busy(|| {
+            conn.transaction(|conn| {
+                diesel::insert_into(recompute_jobs::table).values(&job).execute(conn)?;
+                AuditEntry::record(conn, &job.requested_by, "recompute", &job.id, details.clone())
+            })
+        })?;
+
+        Ok((Self::find_by_id(conn, job.id)?, None))
+    }
+
+    fn set_progress(conn: &mut SqliteConnection, id: &str, status: &str, total: i32, processed: i32, error: Option<String>) -> Result<(), DbError> {
+        retry_on_busy(|| {
+            diesel::update(recompute_jobs::table.find(id))
+                .set((
+                    recompute_jobs::status.eq(status),
+                    recompute_jobs::total.eq(total),
+                    recompute_jobs::processed.eq(processed),
+                    recompute_jobs::error.eq(error.clone()),
+                    recompute_jobs::updated_at.eq(chrono::Local::now().naive_local()),
+                ))
+                .execute(conn)
+        })?;
+        Ok(())
+    }
+
+    // Marks a job that could not run to the end as `Failed`, unless `run` already did with the failing batch's error.
+    pub fn fail(conn: &mut SqliteConnection, id: &str, error: String) -> Result<(), DbError> {
+        retry_on_busy(|| {
+            diesel::update(recompute_jobs::table.find(id).filter(recompute_jobs::status.ne(JobStatus::FAILED)))
+                .set((
+                    recompute_jobs::status.eq(JobStatus::FAILED),
+                    recompute_jobs::error.eq(Some(error.clone())),
+                    recompute_jobs::updated_at.eq(chrono::Local::now().naive_local()),
+                ))
+                .execute(conn)
+        })?;
+        Ok(())
+    }
+
+    fn trades_in_range(&self) -> trades::BoxedQuery<'static, diesel::sqlite::Sqlite> {
+        let mut query = trades::table.filter(trades::deleted_at.is_null()).into_boxed();
+        if let Some(start) = self.range_start {
+            query = query.filter(trades::created_at.ge(start));
+        }
+        if let Some(end) = self.range_end {
+            query = query.filter(trades::created_at.le(end));
+        }
+        query
+    }
+
+    // The records to recompute, as pairs: (trade ID, "") for fees, (user ID, asset) for positions and
+    // (user ID, day) for snapshots.
+    fn keys(&self, conn: &mut SqliteConnection) -> Result<Vec<(String, String)>, DbError> {
+        match self.what.as_str() {
+            RecomputeTarget::FEES => Ok(self
+                .trades_in_range()
+                .select(trades::id)
+                .order(trades::id.asc())
+                .load::<String>(conn)?
+                .into_iter()
+                .map(|id| (id, String::new()))
+                .collect()),
+            RecomputeTarget::POSITIONS => Ok(self
+                .trades_in_range()
+                .select((trades::user_id, trades::asset))
+                .distinct()
+                .order((trades::user_id.asc(), trades::asset.asc()))
+                .load::<(String, String)>(conn)?),
+            _ => Ok(self
+                .trades_in_range()
+                .select((trades::user_id, trades::created_at))
+                .load::<(String, chrono::NaiveDateTime)>(conn)?
+                .into_iter()
+                .map(|(user_id, created_at)| (user_id, created_at.date().format("%Y-%m-%d").to_string()))
+                .collect::<std::collections::BTreeSet<_>>()
+                .into_iter()
+                .collect()),
+        }
+    }
+
+    fn recompute_batch(&self, conn: &mut SqliteConnection, batch: &[(String, String)]) -> Result<(), DbError> {
+        match self.what.as_str() {
+            RecomputeTarget::FEES => retry_on_busy(|| {
+                conn.transaction(|conn| {
+                    let ids: Vec<&String> = batch.iter().map(|(id, _)| id).collect();
+                    for trade in trades::table.filter(trades::id.eq_any(ids)).load::<Trade>(conn)? {
+                        let (execution_fee, transaction_fee) = FeeSchedule::fees_for(conn, &trade)?;
+                        if (execution_fee, transaction_fee) != (trade.execution_fee, trade.transaction_fee) {
+                            diesel::update(trades::table.find(&trade.id))
+                                .set((trades::execution_fee.eq(execution_fee), trades::transaction_fee.eq(transaction_fee)))
+                                .execute(conn)?;
+                        }
+                    }
+                    Ok(())
+                })
+            }),
+            RecomputeTarget::POSITIONS => {
+                for (user_id, asset) in batch {
+                    Position::recompute(conn, user_id, asset)?;
+                }
+                Ok(())
+            }
+            _ => retry_on_busy(|| {
+                conn.transaction(|conn| {
+                    for (user_id, day) in batch {
+                        if let Ok(date) = chrono::NaiveDate::parse_from_str(day, "%Y-%m-%d") {
+                            DailySnapshot::rebuild(conn, user_id, date)?;
+                        }
+                    }
+                    Ok(())
+                })
+            }),
+        }
+    }
+
+    pub fn run(conn: &mut SqliteConnection, id: String) -> Result<Option<Self>, DbError> {
+        let job = match Self::find_by_id(conn, id.clone())? {
+            Some(job) => job,
+            None => return Ok(None),
+        };
+
+        let keys = job.keys(conn)?;
+        let total = keys.len() as i32;
+        let mut processed = 0;
+        Self::set_progress(conn, &id, JobStatus::RUNNING, total, processed, None)?;
+
+        for batch in keys.chunks(BATCH_SIZE) {
+            if let Err(err) = job.recompute_batch(conn, batch) {
+                Self::set_progress(conn, &id, JobStatus::FAILED, total, processed, Some(err.to_string()))?;
+                return Err(err);
+            }
+            processed += batch.len() as i32;
+            Self::set_progress(conn, &id, JobStatus::RUNNING, total, processed, None)?;
+        }
+
+        Self::set_progress(conn, &id, JobStatus::COMPLETED, total, processed, None)?;
+        Self::find_by_id(conn, id)
+    }
+}
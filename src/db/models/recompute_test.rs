@@ -0,0 +1,118 @@
+use diesel::prelude::*;
+
+use crate::db::fixtures::{self, test_connection, trade, user};
+use crate::db::schema::{positions, trades};
+use crate::services::trade::TradeForm;
+use super::audit::AuditEntry;
+use super::fee_schedule::FeeSchedule;
+use super::position::Position;
+use super::recompute::{JobStatus, RecomputeJob, RecomputeTarget};
+use super::snapshot::DailySnapshot;
+use super::trade::Trade;
+use super::user::User;
+
+fn create_trades(conn: &mut SqliteConnection) -> (String, Vec<Trade>) {
+    let user = user(conn, "recompute");
+
+    let trades = [(100.0, 1_692_000_000), (200.0, 1_692_003_600), (300.0, 1_692_100_000)]
+        .iter()
+        .map(|(price, timestamp)| create_trade(conn, &user, *price, *timestamp))
+        .collect();
+    (user.id, trades)
+}
+
+fn create_trade(conn: &mut SqliteConnection, user: &User, price: f32, timestamp: i64) -> Trade {
+    let form = TradeForm { amount: price, execution_price: Some(price), final_price: Some(price), timestamp: Some(timestamp), ..trade(&user.id, &user.wallet_id) };
+    fixtures::create_trade(conn, &form)
+}
+
+#[test]
+fn fees_job_rewrites_stale_fees_within_the_range() {
+    let conn = &mut test_connection();
+    let (_, created) = create_trades(conn);
+    diesel::update(trades::table)
+        .set((trades::execution_fee.eq(0.0), trades::transaction_fee.eq(0.0)))
+        .execute(conn)
+        .unwrap();
+
+    let start = created[0].created_at;
+    let end = created[1].created_at;
+    let (job, errors) = RecomputeJob::create(conn, RecomputeTarget::FEES.to_string(), Some((start, end)), "admin".to_string()).unwrap();
+    assert!(errors.is_none());
+    let job = RecomputeJob::run(conn, job.unwrap().id).unwrap().unwrap();
+    assert_eq!((job.status.as_str(), job.total, job.processed), (JobStatus::COMPLETED, 2, 2));
+
+    let first = Trade::find_by_id(conn, created[0].id.clone()).unwrap().unwrap();
+    assert_eq!((first.execution_fee, first.transaction_fee), FeeSchedule::default_fees(100.0, 1.0));
+    let outside = Trade::find_by_id(conn, created[2].id.clone()).unwrap().unwrap();
+    assert_eq!(outside.execution_fee, 0.0);
+
+    let audit = AuditEntry::list(conn, 10, 0).unwrap();
+    assert_eq!(audit.len(), 1);
+    assert_eq!((audit[0].actor_id.as_str(), audit[0].action.as_str(), &audit[0].target_id), ("admin", "recompute", &job.id));
+}
+
+#[test]
+fn snapshot_and_position_jobs_rebuild_rows() {
+    let conn = &mut test_connection();
+    let (user_id, created) = create_trades(conn);
+    diesel::delete(positions::table).execute(conn).unwrap();
+
+    let (job, _) = RecomputeJob::create(conn, RecomputeTarget::POSITIONS.to_string(), None, "admin".to_string()).unwrap();
+    RecomputeJob::run(conn, job.unwrap().id).unwrap();
+    assert_eq!(Position::find(conn, user_id.clone(), "ETH".to_string()).unwrap().unwrap().quantity, 3.0);
+
+    let (job, _) = RecomputeJob::create(conn, RecomputeTarget::SNAPSHOTS.to_string(), None, "admin".to_string()).unwrap();
+    let job = RecomputeJob::run(conn, job.unwrap().id).unwrap().unwrap();
+    assert_eq!(job.total, 2);
+
+    let start = created[0].created_at.date();
+    let snapshots = DailySnapshot::list_for_user(conn, user_id, start, start + chrono::Duration::days(7)).unwrap();
+    assert_eq!(snapshots.iter().map(|snapshot| snapshot.trade_count).collect::<Vec<_>>(), vec![2, 1]);
+    assert_eq!(snapshots[0].volume, 300.0);
+
+    let (job, errors) = RecomputeJob::create(conn, "balances".to_string(), None, "admin".to_string()).unwrap();
+    assert!(job.is_none());
+    assert!(errors.is_some());
+    assert_eq!(RecomputeJob::list(conn, 10).unwrap().len(), 2);
+}
+
+#[test]
+fn backdated_trades_refresh_materialized_snapshots() {
+    let conn = &mut test_connection();
+    let (user_id, created) = create_trades(conn);
+    let user = User::find_by_id(conn, user_id.clone()).unwrap().unwrap();
+    let (job, _) = RecomputeJob::create(conn, RecomputeTarget::SNAPSHOTS.to_string(), None, "admin".to_string()).unwrap();
+    RecomputeJob::run(conn, job.unwrap().id).unwrap();
+
+    let backdated = create_trade(conn, &user, 50.0, 1_691_660_000);
+    create_trade(conn, &user, 400.0, 1_692_010_000);
+    let later = create_trade(conn, &user, 500.0, 1_692_520_000);
+
+    let start = backdated.created_at.date();
+    let counts = |conn: &mut SqliteConnection| {
+        DailySnapshot::list_for_user(conn, user_id.clone(), start, later.created_at.date())
+            .unwrap()
+            .iter()
+            .map(|snapshot| (snapshot.date.to_string(), snapshot.trade_count))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(counts(conn), vec![("2023-08-10".to_string(), 1), ("2023-08-14".to_string(), 3), ("2023-08-15".to_string(), 1)]);
+
+    assert_eq!(Trade::delete(conn, backdated.id, &user_id).unwrap(), (true, None));
+    Trade::delete(conn, created[0].id.clone(), &user_id).unwrap();
+    assert_eq!(counts(conn), vec![("2023-08-14".to_string(), 2), ("2023-08-15".to_string(), 1)]);
+}
+
+#[test]
+fn jobs_that_cannot_run_are_marked_failed_once() {
+    let conn = &mut test_connection();
+    let (job, _) = RecomputeJob::create(conn, RecomputeTarget::FEES.to_string(), None, "admin".to_string()).unwrap();
+    let id = job.unwrap().id;
+
+    RecomputeJob::fail(conn, &id, "No connection available".to_string()).unwrap();
+    RecomputeJob::fail(conn, &id, "Later error".to_string()).unwrap();
+    let job = RecomputeJob::find_by_id(conn, id).unwrap().unwrap();
+    assert_eq!(job.status, JobStatus::FAILED);
+    assert_eq!(job.error.as_deref(), Some("No connection available"));
+}
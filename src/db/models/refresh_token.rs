@@ -0,0 +1,127 @@
+//! This module defines the refresh tokens that keep a login session alive.
+//!
+//! Access tokens (JWTs, see `services::jwt`) are short-lived; a client exchanges its refresh token for a new access
+//! token instead of logging in again. Only a SHA-256 hash of each refresh token is stored, so a database leak does not
+//! expose usable tokens. Refresh tokens are rotated: `RefreshToken::rotate` revokes the presented token and issues a
+//! new one, so each token can be used once. Revoking a token (on logout) ends the session, and changing or resetting
+//! the password ends every session of the user (`revoke_all`).
+//!
+//! # Examples
+//!
+//! ```rust
+//! use crate::models::refresh_token::RefreshToken;
+//!
+//! // Issue a refresh token at login; only the returned plain token is ever sent to the client.
+//! let (token, _record) = RefreshToken::issue(&mut connection, "user_id".to_string())?;
+//!
+//! // Exchange it for a new one.
+//! if let Some((next_token, record)) = RefreshToken::rotate(&mut connection, &token)? {
+//!     println!("Session of {} refreshed", record.user_id);
+//! }
+//!
+//! // Log out.
+//! RefreshToken::revoke(&mut connection, &next_token)?;
+//! ```
+//!
+//! # Note
+//! This module assumes the availability of a database connection (`SqliteConnection` in this case) for refresh token retrieval and manipulation.
+
+use uuid::Uuid;
+use rand::Rng;
+use serde::{Serialize, Deserialize};
+use sha2::{Digest, Sha256};
+use diesel::prelude::*;
+
+use super::super::error::DbError;
+use crate::config::settings;
+use super::super::retry::retry_on_busy;
+use super::super::schema::refresh_tokens;
+
+// Default refresh token lifetime; see `jwt.refresh_token_days` in the settings.
+pub const REFRESH_TOKEN_DAYS: i64 = 30;
+
+#[derive(Debug, Deserialize, Serialize, Queryable, Insertable)]
+#[diesel(table_name = crate::db::schema::refresh_tokens)]
+pub struct RefreshToken {
+    pub id: String,
+    pub user_id: String,
+    #[serde(skip_serializing)]
+    pub token_hash: String,
+    pub created_at: chrono::NaiveDateTime,
+    pub expires_at: chrono::NaiveDateTime,
+    pub revoked_at: Option<chrono::NaiveDateTime>,
+}
+
+fn hash(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
+
+impl RefreshToken {
+    pub fn issue(conn: &mut SqliteConnection, user_id: String) -> Result<(String, Self), DbError> {
+        let token = hex::encode(rand::thread_rng().gen::<[u8; 32]>());
+        let now = chrono::Local::now().naive_local();
+        let record = RefreshToken {
+            id: Uuid::new_v4().as_hyphenated().to_string(),
+            user_id,
+            token_hash: hash(&token),
+            created_at: now,
+            expires_at: now + chrono::Duration::days(settings::get().jwt.refresh_token_days),
+            revoked_at: None,
+        };
+        retry_on_busy(|| {
+            diesel::insert_into(refresh_tokens::table)
+                .values(&record)
+                .execute(conn)
+        })?;
+
+        Ok((token, record))
+    }
+
+    pub fn find_active(conn: &mut SqliteConnection, token: &str) -> Result<Option<Self>, DbError> {
+        Ok(refresh_tokens::table
+            .filter(refresh_tokens::token_hash.eq(hash(token)))
+            .filter(refresh_tokens::revoked_at.is_null())
+            .filter(refresh_tokens::expires_at.gt(chrono::Local::now().naive_local()))
+            .first::<RefreshToken>(conn)
+            .optional()?)
+    }
+
+    pub fn rotate(conn: &mut SqliteConnection, token: &str) -> Result<Option<(String, Self)>, DbError> {
+        let current = match Self::find_active(conn, token)? {
+            Some(current) => current,
+            None => return Ok(None),
+        };
+
+        if !Self::revoke(conn, token)? {
+            // Another request rotated the same token first.
+            return Ok(None);
+        }
+
+        Self::issue(conn, current.user_id).map(Some)
+    }
+
+    pub fn revoke(conn: &mut SqliteConnection, token: &str) -> Result<bool, DbError> {
+        let revoked = retry_on_busy(|| {
+            diesel::update(
+                refresh_tokens::table
+                    .filter(refresh_tokens::token_hash.eq(hash(token)))
+                    .filter(refresh_tokens::revoked_at.is_null()),
+            )
+            .set(refresh_tokens::revoked_at.eq(chrono::Local::now().naive_local()))
+            .execute(conn)
+        })?;
+
+        Ok(revoked > 0)
+    }
+
+    // Ends every session of a user, after a password change. Must run inside the transaction making the change.
+    pub fn revoke_all(conn: &mut SqliteConnection, user_id: &str) -> QueryResult<usize> {
+        diesel::update(
+            refresh_tokens::table
+                .filter(refresh_tokens::user_id.eq(user_id))
+                .filter(refresh_tokens::revoked_at.is_null()),
+        )
+        .set(refresh_tokens::revoked_at.eq(chrono::Local::now().naive_local()))
+        .execute(conn)
+    }
+}
@@ -0,0 +1,40 @@
+use diesel::prelude::*;
+
+use crate::db::fixtures::{test_connection, user};
+use crate::db::schema::refresh_tokens;
+use super::refresh_token::RefreshToken;
+
+#[test]
+fn refresh_tokens_rotate_once() {
+    let conn = &mut test_connection();
+    let user_id = user(conn, "session").id;
+
+    let (token, record) = RefreshToken::issue(conn, user_id.clone()).unwrap();
+    assert_ne!(record.token_hash, token);
+
+    let (next, rotated) = RefreshToken::rotate(conn, &token).unwrap().unwrap();
+    assert_eq!(rotated.user_id, user_id);
+    assert_ne!(next, token);
+
+    assert!(RefreshToken::rotate(conn, &token).unwrap().is_none());
+    assert!(RefreshToken::find_active(conn, &next).unwrap().is_some());
+}
+
+#[test]
+fn revoked_and_expired_tokens_are_rejected() {
+    let conn = &mut test_connection();
+    let user_id = user(conn, "session").id;
+
+    let (token, _) = RefreshToken::issue(conn, user_id.clone()).unwrap();
+    assert!(RefreshToken::revoke(conn, &token).unwrap());
+    assert!(!RefreshToken::revoke(conn, &token).unwrap());
+    assert!(RefreshToken::rotate(conn, &token).unwrap().is_none());
+
+    let (token, record) = RefreshToken::issue(conn, user_id).unwrap();
+    diesel::update(refresh_tokens::table.find(record.id))
+        .set(refresh_tokens::expires_at.eq(chrono::Local::now().naive_local() - chrono::Duration::minutes(1)))
+        .execute(conn)
+        .unwrap();
+    assert!(RefreshToken::find_active(conn, &token).unwrap().is_none());
+    assert!(RefreshToken::rotate(conn, &token).unwrap().is_none());
+}
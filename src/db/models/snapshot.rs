@@ -0,0 +1,121 @@
+//! This module defines daily snapshots: per-user totals of the trades executed on one calendar day.
+//!
+//! A `DailySnapshot` stores the number of trades, the traded volume (`traded_amount * execution_price`), the fees and
+//! the P&L of a user's trades whose `created_at` falls on `date`, so reports over long periods can read one row per day
+//! instead of every trade. P&L uses the same formula as `Trade::calculate_trade_pnl` (see `summary::TRADE_PNL_SQL`).
+//!
+//! `rebuild` recomputes one day from the trades table and removes the row when the day has no trades left. Snapshots
+//! are materialized by the admin recompute job (`recompute`). Each one records the end-of-day price fixing in force on
+//! its day (`fixing_id`, see `price_fixing`), so reports can tell which source and fixing time its figures go with.
+//!
+//! Once a user's snapshots cover a day, writing a trade on that day would leave its snapshot stale. This is typical
+//! of backfills, where trades are inserted with historical timestamps. `Trade::create`, `update` and `delete` therefore
+//! call `invalidate` for the trade's day, which rebuilds it when the user has a snapshot on that day or later. Days
+//! past the latest snapshot are not materialized yet and are left to the recompute job. Positions are rebuilt from
+//! the full history on every trade write (see `position`), so backdated trades need nothing more there.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use crate::models::snapshot::DailySnapshot;
+//!
+//! let day = chrono::NaiveDate::from_ymd_opt(2023, 8, 1).unwrap();
+//! DailySnapshot::rebuild(&mut connection, "user_id", day)?;
+//!
+//! let august = DailySnapshot::list_for_user(&mut connection, "user_id".to_string(), start, end)?;
+//! ```
+//!
+//! # Note
+//! This module assumes the availability of a database connection (`SqliteConnection` in this case) for snapshot data retrieval and manipulation.
+
+use serde::{Serialize, Deserialize};
+use diesel::prelude::*;
+use diesel::sql_types::{BigInt, Double, Text};
+
+use super::super::error::DbError;
+use super::super::schema::daily_snapshots;
+use super::price_fixing::PriceFixing;
+use super::summary::TRADE_PNL_SQL;
+
+#[derive(Debug, Deserialize, Serialize, Queryable, Insertable)]
+#[diesel(table_name = crate::db::schema::daily_snapshots)]
+pub struct DailySnapshot {
+    pub user_id: String,
+    pub date: chrono::NaiveDate,
+    pub trade_count: i32,
+    pub volume: f32,
+    pub fees: f32,
+    pub pnl: f32,
+    pub updated_at: chrono::NaiveDateTime,
+    pub fixing_id: String,
+}
+
+#[derive(QueryableByName)]
+struct TotalsRow {
+    #[diesel(sql_type = BigInt)]
+    trade_count: i64,
+    #[diesel(sql_type = Double)]
+    volume: f64,
+    #[diesel(sql_type = Double)]
+    fees: f64,
+    #[diesel(sql_type = Double)]
+    pnl: f64,
+}
+
+impl DailySnapshot {
+    pub fn list_for_user(conn: &mut SqliteConnection, user_id: String, start: chrono::NaiveDate, end: chrono::NaiveDate) -> Result<Vec<Self>, DbError> {
+        Ok(daily_snapshots::table
+            .filter(daily_snapshots::user_id.eq(user_id))
+            .filter(daily_snapshots::date.ge(start))
+            .filter(daily_snapshots::date.le(end))
+            .order(daily_snapshots::date.asc())
+            .load::<DailySnapshot>(conn)?)
+    }
+
+    pub fn invalidate(conn: &mut SqliteConnection, user_id: &str, date: chrono::NaiveDate) -> QueryResult<Option<Self>> {
+        let materialized = daily_snapshots::table
+            .filter(daily_snapshots::user_id.eq(user_id))
+            .filter(daily_snapshots::date.ge(date))
+            .count()
+            .get_result::<i64>(conn)?;
+        if materialized == 0 {
+            return Ok(None);
+        }
+        Self::rebuild(conn, user_id, date)
+    }
+
+    pub fn rebuild(conn: &mut SqliteConnection, user_id: &str, date: chrono::NaiveDate) -> QueryResult<Option<Self>> {
+        let totals = diesel::sql_query(format!(
+            "SELECT COUNT(*) AS trade_count, \
+                CAST(COALESCE(SUM(traded_amount * execution_price), 0) AS REAL) AS volume, \
+                CAST(COALESCE(SUM(execution_fee + transaction_fee), 0) AS REAL) AS fees, \
+                CAST(COALESCE(SUM({}), 0) AS REAL) AS pnl \
+            FROM trades WHERE user_id = ? AND deleted_at IS NULL AND date(created_at) = ?",
+            TRADE_PNL_SQL
+        ))
+        .bind::<Text, _>(user_id)
+        .bind::<Text, _>(date.format("%Y-%m-%d").to_string())
+        .get_result::<TotalsRow>(conn)?;
+
+        if totals.trade_count == 0 {
+            diesel::delete(daily_snapshots::table.find((user_id, date))).execute(conn)?;
+            return Ok(None);
+        }
+
+        let snapshot = DailySnapshot {
+            user_id: user_id.to_string(),
+            date,
+            trade_count: totals.trade_count as i32,
+            volume: totals.volume as f32,
+            fees: totals.fees as f32,
+            pnl: totals.pnl as f32,
+            updated_at: chrono::Local::now().naive_local(),
+            fixing_id: PriceFixing::for_date(conn, date)?.id,
+        };
+        diesel::replace_into(daily_snapshots::table)
+            .values(&snapshot)
+            .execute(conn)?;
+
+        Ok(Some(snapshot))
+    }
+}
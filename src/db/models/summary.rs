@@ -0,0 +1,126 @@
+//! This module assembles the compact home-screen summary served to mobile clients.
+//!
+//! `Summary::for_user` gathers everything a home screen needs with a handful of aggregate queries instead of one
+//! request per widget: the P&L of trades within a period (usually today), the number of open positions, the balance and
+//! trade count of each of the user's wallets (`UserWallet::list_for_user`, oldest first), the latest trades and the
+//! number of items waiting for the user's attention.
+//!
+//! P&L uses the same per-trade formula as `Trade::calculate_trade_pnl`, evaluated in SQL. A position counts as open
+//! when the bought quantity of an asset exceeds the sold quantity. Items needing attention are pending
+//! emailed trade confirmations; there is no alerting subsystem yet.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use crate::models::summary::Summary;
+//!
+//! let summary = Summary::for_user(&mut connection, "user_id".to_string(), "2023-08-01 00:00:00".to_string(), "2023-08-01 23:59:59".to_string())?;
+//! println!("Today's P&L: {}", summary.today_pnl);
+//! ```
+//!
+//! # Note
+//! This module assumes the availability of a database connection (`SqliteConnection` in this case) for summary data retrieval.
+
+use serde::{Serialize, Deserialize};
+use diesel::prelude::*;
+use diesel::sql_types::{BigInt, Double, Nullable, Text};
+
+use super::super::error::DbError;
+use super::super::schema::{email_trade_reviews, trades};
+use super::email_review::ReviewStatus;
+use super::trade::Trade;
+use super::user_wallet::UserWallet;
+
+pub const TRADE_PNL_SQL: &str = "(CASE \
+    WHEN trade_type IN ('LimitBuy', 'MarketBuy') THEN final_price - execution_price \
+    WHEN trade_type IN ('LimitSell', 'MarketSell') THEN final_price - before_price \
+    ELSE 0 END) * traded_amount - execution_fee - transaction_fee";
+
+const RECENT_TRADES: i64 = 5;
+
+#[derive(Serialize, Deserialize)]
+pub struct WalletTotals {
+    pub wallet_id: String,
+    pub name: String,
+    pub balance: f32,
+    pub trade_count: i64,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Summary {
+    pub user_id: String,
+    pub today_pnl: f32,
+    pub open_positions: i64,
+    pub wallets: Vec<WalletTotals>,
+    pub recent_trades: Vec<Trade>,
+    pub pending_reviews: i64,
+}
+
+#[derive(QueryableByName)]
+struct PnlRow {
+    #[diesel(sql_type = Nullable<Double>)]
+    pnl: Option<f64>,
+}
+
+#[derive(QueryableByName)]
+struct CountRow {
+    #[diesel(sql_type = BigInt)]
+    count: i64,
+}
+
+impl Summary {
+    pub fn for_user(conn: &mut SqliteConnection, user_id: String, start_date: String, end_date: String) -> Result<Self, DbError> {
+        let today_pnl = diesel::sql_query(format!(
+            "SELECT SUM({}) AS pnl FROM trades WHERE user_id = ? AND deleted_at IS NULL AND created_at >= ? AND created_at <= ?",
+            TRADE_PNL_SQL
+        ))
+        .bind::<Text, _>(user_id.clone())
+        .bind::<Text, _>(start_date)
+        .bind::<Text, _>(end_date)
+        .get_result::<PnlRow>(conn)?
+        .pnl
+        .unwrap_or(0.0);
+
+        let open_positions = diesel::sql_query(
+            "SELECT COUNT(*) AS count FROM (\
+                SELECT asset FROM trades WHERE user_id = ? AND deleted_at IS NULL GROUP BY asset \
+                HAVING SUM(CASE WHEN trade_type IN ('LimitBuy', 'MarketBuy') THEN traded_amount ELSE -traded_amount END) > 0\
+            )",
+        )
+        .bind::<Text, _>(user_id.clone())
+        .get_result::<CountRow>(conn)?
+        .count;
+
+        let mut wallets = Vec::new();
+        for wallet in UserWallet::list_for_user(conn, &user_id)? {
+            let trade_count = trades::table
+                .filter(trades::wallet_id.eq(wallet.id.clone()))
+                .filter(trades::deleted_at.is_null())
+                .count()
+                .get_result::<i64>(conn)?;
+            wallets.push(WalletTotals { wallet_id: wallet.id, name: wallet.name, balance: wallet.balance, trade_count });
+        }
+
+        let recent_trades = trades::table
+            .filter(trades::user_id.eq(user_id.clone()))
+            .filter(trades::deleted_at.is_null())
+            .order(trades::created_at.desc())
+            .limit(RECENT_TRADES)
+            .load::<Trade>(conn)?;
+
+        let pending_reviews = email_trade_reviews::table
+            .filter(email_trade_reviews::user_id.eq(user_id.clone()))
+            .filter(email_trade_reviews::status.eq(ReviewStatus::PENDING))
+            .count()
+            .get_result::<i64>(conn)?;
+
+        Ok(Summary {
+            user_id,
+            today_pnl: (today_pnl as f32).round(),
+            open_positions,
+            wallets,
+            recent_trades,
+            pending_reviews,
+        })
+    }
+}
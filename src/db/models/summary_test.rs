@@ -0,0 +1,67 @@
+use diesel::SqliteConnection;
+
+use crate::db::fixtures::{self, test_connection, trade, user};
+use crate::services::trade::TradeForm;
+use super::summary::Summary;
+use super::trade::Trade;
+use super::user_wallet::UserWallet;
+use super::wallet::Wallet;
+
+fn create_user(conn: &mut SqliteConnection) -> (String, String) {
+    let user = user(conn, "summary");
+    Wallet::update_balance(conn, user.wallet_id.clone(), 250.0).unwrap();
+    (user.id, user.wallet_id)
+}
+
+fn create_trade(conn: &mut SqliteConnection, user_id: &str, wallet_id: &str, trade_type: &str, asset: &str, timestamp: i64) -> Trade {
+    let form = TradeForm {
+        amount: 2.0,
+        trade_type: trade_type.to_string(),
+        asset: asset.to_string(),
+        before_price: Some(10.0),
+        execution_price: Some(10.0),
+        final_price: Some(12.0),
+        traded_amount: Some(2.0),
+        timestamp: Some(timestamp),
+        ..trade(user_id, wallet_id)
+    };
+    fixtures::create_trade(conn, &form)
+}
+
+#[test]
+fn summarizes_user_activity() {
+    let conn = &mut test_connection();
+    let (user_id, wallet_id) = create_user(conn);
+    UserWallet::create(conn, &user_id, "Savings").unwrap();
+    // 2023-08-01 and 2023-07-31 (UTC).
+    let today = create_trade(conn, &user_id, &wallet_id, "LimitBuy", "ETH", 1690891200);
+    let buy = create_trade(conn, &user_id, &wallet_id, "LimitBuy", "BTC", 1690804800);
+    let sell = create_trade(conn, &user_id, &wallet_id, "MarketSell", "BTC", 1690804900);
+    let fees: f32 = [&today, &buy, &sell].iter().map(|trade| trade.execution_fee + trade.transaction_fee).sum();
+
+    let summary = Summary::for_user(conn, user_id, "2023-08-01 00:00:00".to_string(), "2023-08-01 23:59:59".to_string()).unwrap();
+
+    assert_eq!(summary.today_pnl, today.calculate_trade_pnl().round());
+    assert_eq!(summary.open_positions, 1);
+    let wallet = &summary.wallets[0];
+    // Two buys and one sell of 20.0 each, settled against the wallet.
+    assert!((wallet.balance - (250.0 - 20.0 - fees)).abs() < 1e-3);
+    assert_eq!(wallet.trade_count, 3);
+    let savings = &summary.wallets[1];
+    assert_eq!((savings.name.as_str(), savings.balance, savings.trade_count), ("Savings", 0.0, 0));
+    assert_eq!(summary.recent_trades.len(), 3);
+    assert_eq!(summary.recent_trades[0].id, today.id);
+    assert_eq!(summary.pending_reviews, 0);
+}
+
+#[test]
+fn empty_summary_for_new_user() {
+    let conn = &mut test_connection();
+    let (user_id, _wallet_id) = create_user(conn);
+
+    let summary = Summary::for_user(conn, user_id, "2023-08-01 00:00:00".to_string(), "2023-08-01 23:59:59".to_string()).unwrap();
+
+    assert_eq!(summary.today_pnl, 0.0);
+    assert_eq!(summary.open_positions, 0);
+    assert!(summary.recent_trades.is_empty());
+}
@@ -0,0 +1,68 @@
+//! This module records deletions so offline clients can remove entities they have cached.
+//!
+//! A `Tombstone` is written in the same transaction as the delete it describes and is kept after the row itself is
+//! gone (or, for trades, soft-deleted). `Tombstone::since` returns the tombstones of a user newer than a sync cursor.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use crate::models::tombstone::{Tombstone, Entity};
+//!
+//! conn.transaction(|conn| {
+//!     diesel::delete(trades::table.find(id.clone())).execute(conn)?;
+//!     Tombstone::record(conn, Entity::TRADE, id, user_id)
+//! })?;
+//!
+//! let deleted = Tombstone::since(&mut connection, "user_id".to_string(), Some(cursor))?;
+//! ```
+//!
+//! # Note
+//! This module assumes the availability of a database connection (`SqliteConnection` in this case) for tombstone data retrieval and manipulation.
+
+use uuid::Uuid;
+use serde::{Serialize, Deserialize};
+use diesel::prelude::*;
+
+use super::super::error::DbError;
+use super::super::schema::tombstones;
+
+#[derive(Debug, Deserialize, Serialize, Queryable, Insertable)]
+#[diesel(table_name = crate::db::schema::tombstones)]
+pub struct Tombstone {
+    pub id: String,
+    pub entity: String,
+    pub entity_id: String,
+    pub user_id: String,
+    pub deleted_at: chrono::NaiveDateTime,
+}
+
+pub struct Entity;
+
+impl Entity {
+    pub const TRADE: &'static str = "trade";
+}
+
+impl Tombstone {
+    pub fn record(conn: &mut SqliteConnection, entity: &str, entity_id: String, user_id: String) -> QueryResult<usize> {
+        diesel::insert_into(tombstones::table)
+            .values(&Tombstone {
+                id: Uuid::new_v4().as_hyphenated().to_string(),
+                entity: entity.to_string(),
+                entity_id,
+                user_id,
+                deleted_at: chrono::Local::now().naive_local(),
+            })
+            .execute(conn)
+    }
+
+    pub fn since(conn: &mut SqliteConnection, user_id: String, since: Option<chrono::NaiveDateTime>) -> Result<Vec<Self>, DbError> {
+        let mut query = tombstones::table
+            .filter(tombstones::user_id.eq(user_id))
+            .order(tombstones::deleted_at.asc())
+            .into_boxed();
+        if let Some(since) = since {
+            query = query.filter(tombstones::deleted_at.gt(since));
+        }
+        Ok(query.load::<Tombstone>(conn)?)
+    }
+}
@@ -0,0 +1,20 @@
+use crate::db::fixtures::{create_trade, test_connection, trade, user};
+use super::tombstone::{Entity, Tombstone};
+use super::trade::Trade;
+
+#[test]
+fn deleting_a_trade_leaves_a_tombstone() {
+    let conn = &mut test_connection();
+    let user = user(conn, "sync");
+    let trade = create_trade(conn, &trade(&user.id, &user.wallet_id));
+    let cursor = trade.updated_at;
+
+    assert!(Trade::changed_since(conn, user.id.clone(), Some(cursor)).unwrap().is_empty());
+    assert_eq!(Trade::delete(conn, trade.id.clone(), &trade.user_id).unwrap(), (true, None));
+
+    let tombstones = Tombstone::since(conn, user.id.clone(), Some(cursor)).unwrap();
+    assert_eq!(tombstones.len(), 1);
+    assert_eq!(tombstones[0].entity, Entity::TRADE);
+    assert_eq!(tombstones[0].entity_id, trade.id);
+    assert!(Tombstone::since(conn, user.id, Some(tombstones[0].deleted_at)).unwrap().is_empty());
+}
@@ -8,7 +8,44 @@
 //! and validating the integrity of trade attributes like trade chain, trade type, and asset.
 //! 
 //! Additionally, it offers utilities for categorizing trade statistics by various dimensions like asset or trade type,
-//! as well as methods for retrieving and manipulating trade records in the database.
+//! as well as methods for retrieving and manipulating trade records in the database. Database failures are returned as
+//! `DbError` rather than panicking. Creating, updating or deleting a trade also rebuilds the affected `Position` (see
+//! `position`).
+//!
+//! The wallet of a new trade must be one of its user's wallets (`user_wallet`); whichever path creates the trade, it
+//! is rejected otherwise. Creating a trade settles it against its wallet in the same transaction: a buy debits the traded value
+//! (`traded_amount * execution_price`) and a sell credits it, and the execution and transaction fees are debited, each
+//! as a wallet ledger entry (`wallet_transaction`) referencing the trade. A trade the wallet cannot pay for is rejected
+//! with a message stating the shortfall, and nothing is written. Updating a trade posts the difference in its
+//! settlement, and deleting it posts the reversal, in the same transaction as the change; when that would overdraw
+//! the wallet the change is rejected the same way.
+//!
+//! Every trade records the entry path it came from in `source` (`TradeSource`): `manual`, `simulation`, or a kind
+//! qualified by its origin such as `import:binance`, `onchain:arbitrum` or `api_key:{id}`. `TradeFilter::source` and
+//! `Trade::other_sources` narrow listings and aggregates to one source, or to every source of a kind when given just
+//! `import`, `onchain` or `api_key`.
+//!
+//! `Trade::profit_loss` aggregates in SQL, grouping the per-trade P&L (`summary::TRADE_PNL_SQL`) by the day of
+//! `created_at`, so only one row per day leaves the database. The `(user_id, created_at)` index keeps these range
+//! queries fast on large trade tables.
+//!
+//! That per-trade P&L compares each trade with its own prices only. `Trade::profit_loss_fifo` instead tracks cost
+//! basis: `CostBasis` keeps the open lots of an asset in FIFO order, so a sell realizes `(sell price - lot price)` on
+//! the oldest lots it closes, and what remains open is marked at the latest trade price (`mark`) as unrealized P&L. Results
+//! are reported per day and asset, as of the last trade of that day. Shorts are lots with a negative quantity. Fees
+//! are not included, as in `position`.
+//!
+//! `Trade::holdings_conflict` checks a new trade against the history of its wallet and asset, replayed in time order:
+//! it reports the first sell the wallet could not have covered with what it held at that moment. A backdated sell can
+//! be covered itself and still leave a later sell short, which usually points to a data-entry mistake.
+//!
+//! Trades may carry `metadata`: a JSON object of string values set by importers and API clients, such as the exchange
+//! order ID, a client tag or a bot run ID (`TradeMetadata`). `TradeFilter::metadata` matches trades having every given
+//! key and value, compared in SQL with the JSON1 `json_extract` function.
+//!
+//! Deleting a trade is a soft delete: `deleted_at` is set and the row is left out of every listing, lookup and
+//! aggregate, but kept so its history stays complete (`Trade::find_including_deleted`). Every create, update and
+//! delete is recorded in the `trade_audit` trail with the acting user and, for updates and deletes, the previous values.
 //! 
 //! # Examples
 //! 
@@ -16,54 +53,94 @@
 //! use crate::models::trade::{Trade, DailyProfitLoss, CumulativeFeesResponse, SlippageByTrader};
 //!
 //! // List all trades in the database
-//! let trades = Trade::list(&mut connection);
+//! let trades = Trade::list(&mut connection)?;
+//!
+//! // List one page of a user's ETH trades, newest first
+//! let filter = TradeFilter { user_id: Some("user_id".to_string()), asset: Some("ETH".to_string()), ..Default::default() };
+//! let page = Trade::search(&mut connection, &filter, TradeSort::default(), 50, 0)?;
+//! let total = Trade::count(&mut connection, &filter)?;
 //!
 //! // Find a trade by ID
-//! if let Some(trade) = Trade::find_by_id(&mut connection, "trade_id".to_string()) {
+//! if let Ok(Some(trade)) = Trade::find_by_id(&mut connection, "trade_id".to_string()) {
 //!     println!("Found trade: {:?}", trade);
 //! }
 //!
 //! // Create a new trade
-//! let mut new_trade = Trade::create(&mut connection, &mut Trade { /* trade attributes */ });
-//! if let Some(new_trade) = new_trade {
-//!     println!("Created new trade: {:?}", new_trade);
+//! let new_trade = Trade::create(&mut connection, &mut Trade { /* trade attributes */ });
+//! match new_trade {
+//!     Ok((Some(new_trade), None)) => println!("Created new trade: {:?}", new_trade),
+//!     Ok((_, errors)) => println!("Rejected: {:?}", errors),
+//!     Err(err) => println!("Database error: {}", err),
 //! }
 //!
 //! // Update trade information
-//! if let Some(updated_trade) = Trade::update(&mut connection, "trade_id".to_string(), &mut Trade { /* updated trade attributes */ }) {
+//! if let Ok((Some(updated_trade), None)) = Trade::update(&mut connection, "trade_id".to_string(), &mut Trade { /* updated trade attributes */ }, "actor_id") {
 //!     println!("Updated trade: {:?}", updated_trade);
 //! }
 //!
-//! // Delete a trade
-//! if Trade::delete(&mut connection, "trade_id".to_string()) {
+//! // Delete a trade (soft delete, kept for its history)
+//! if let Ok((true, None)) = Trade::delete(&mut connection, "trade_id".to_string(), "actor_id") {
 //!     println!("Trade deleted");
 //! }
 //!
 //! // Calculate cumulative fees for a specific date range and user
-//! let cumulative_fees = Trade::cumulative_fees(&mut connection, "start_date".to_string(), "end_date".to_string(), "user_id".to_string());
+//! let cumulative_fees = Trade::cumulative_fees(&mut connection, "start_date".to_string(), "end_date".to_string(), "user_id".to_string(), &[])?;
 //! println!("Cumulative fees: {:?}", cumulative_fees);
 //!
 //! // Calculate daily profit/loss for a specific date range, user, and optionally by asset or trade type
-//! let profit_loss = Trade::profit_loss(&mut connection, "start_date".to_string(), "end_date".to_string(), "user_id".to_string(), Some("asset".to_string()), None);
+//! let profit_loss = Trade::profit_loss(&mut connection, "start_date".to_string(), "end_date".to_string(), "user_id".to_string(), Some("asset".to_string()), None, &[])?;
 //! println!("Daily profit/loss: {:?}", profit_loss);
 //!
 //! // Calculate slippage statistics for a specific date range and user
-//! let slippage_stats = Trade::get_slippage_bt_dates(&mut connection, "start_date".to_string(), "end_date".to_string(), "user_id".to_string());
+//! let slippage_stats = Trade::get_slippage_bt_dates(&mut connection, "start_date".to_string(), "end_date".to_string(), "user_id".to_string(), &[])?;
 //! println!("Slippage statistics: {:?}", slippage_stats);
+//!
+//! // Slippage distribution (p50/p90/p99) per asset, streamed through quantile sketches
+//! let execution_quality = Trade::execution_quality(&mut connection, "start_date".to_string(), "end_date".to_string(), "user_id".to_string(), &[])?;
+//!
+//! // Volume, P&L, fees and slippage of every trader, per day, asset and chain
+//! let activity = Trade::platform_activity(&mut connection, "start_date".to_string(), "end_date".to_string())?;
+//!
+//! // Leave trades beyond 3 standard deviations of P&L or slippage out of an aggregate
+//! let excluded = Trade::outliers(&mut connection, "start_date".to_string(), "end_date".to_string(), "user_id".to_string(), 3.0)?;
+//! let fees = Trade::cumulative_fees(&mut connection, "start_date".to_string(), "end_date".to_string(), "user_id".to_string(), &excluded)?;
+//!
+//! // Group trades into 4 clusters by size, outcome and time of day
+//! let clusters = Trade::clusters(&mut connection, "start_date".to_string(), "end_date".to_string(), "user_id".to_string(), 4)?;
 //! ```
 //!
 //! # Note
 //! This module assumes the availability of a database connection (`SqliteConnection` in this case) for trade data retrieval and manipulation.
 
 
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+
+use chrono::Timelike;
+
 use uuid::Uuid;
 use serde::{Serialize, Deserialize};
+use utoipa::ToSchema;
+use diesel::connection::DefaultLoadingMode;
 use diesel::prelude::*;
+use diesel::sqlite::Sqlite;
+use diesel::sql_types::{Double, Text};
 
+use super::super::error::DbError;
+use super::super::retry::retry_on_busy;
 use super::super::schema::{*, self};
 use super::super::schema::trades::dsl::trades as trades_dsl;
+use super::fee_override::FeeOverride;
+use super::position::Position;
+use super::snapshot::DailySnapshot;
+use super::summary::TRADE_PNL_SQL;
+use super::tombstone::{Entity, Tombstone};
+use super::trade_audit::{TradeAction, TradeAudit};
+use super::user_wallet::UserWallet;
+use super::wallet_transaction::{TransactionKind, WalletTransaction};
+use crate::utils::kmeans::{kmeans, standardize};
+use crate::utils::quantile::QuantileSketch;
 
-#[derive(Debug, Deserialize, Serialize, Queryable, Insertable)]
+#[derive(Debug, Clone, Deserialize, Serialize, Queryable, Insertable, ToSchema)]
 #[diesel(table_name = crate::db::schema::trades)]
 pub struct Trade {
     pub id: String,
@@ -81,21 +158,144 @@ pub struct Trade {
     pub transaction_fee: f32,
     pub created_at: chrono::NaiveDateTime,
     pub updated_at: chrono::NaiveDateTime,
+    pub entered_by: Option<String>,
+    pub tx_hash: Option<String>,
+    pub source: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deleted_at: Option<chrono::NaiveDateTime>,
+    // A JSON object of string values, see `TradeMetadata`.
+    #[serde(default)]
+    pub metadata: Option<String>,
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct TradeFilter {
+    pub user_id: Option<String>,
+    pub wallet_id: Option<String>,
+    pub asset: Option<String>,
+    pub chain: Option<String>,
+    pub trade_type: Option<String>,
+    pub start_date: Option<String>,
+    pub end_date: Option<String>,
+    pub source: Option<String>,
+    // Key and value pairs that must all be in the trade's metadata.
+    pub metadata: Vec<(String, String)>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize, PartialEq)]
+pub struct TradeTotals {
+    pub trades: i64,
+    pub volume: f32,
+    pub fees: f32,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum TradeSortField {
+    #[default]
+    CreatedAt,
+    Amount,
+    Asset,
+    // The trade's own P&L (`summary::TRADE_PNL_SQL`).
+    Pnl,
+}
+
+// Listing order; ties are broken by `created_at` and `id` in the same direction so pages stay stable.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct TradeSort {
+    pub field: TradeSortField,
+    pub ascending: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, ToSchema)]
 pub struct DailyProfitLoss {
     pub date: String,
     pub profit: f32,
     pub loss: f32,
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(QueryableByName)]
+struct DailyProfitLossRow {
+    #[diesel(sql_type = Text)]
+    date: String,
+    #[diesel(sql_type = Double)]
+    profit: f64,
+    #[diesel(sql_type = Double)]
+    loss: f64,
+}
+
+#[derive(QueryableByName)]
+struct AssetProfitLossRow {
+    #[diesel(sql_type = Text)]
+    date: String,
+    #[diesel(sql_type = Text)]
+    asset: String,
+    #[diesel(sql_type = Double)]
+    profit: f64,
+    #[diesel(sql_type = Double)]
+    loss: f64,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, ToSchema)]
+pub struct AssetProfitLoss {
+    pub asset: String,
+    pub profit: f32,
+    pub loss: f32,
+}
+
+#[derive(Serialize, Deserialize, Debug, ToSchema)]
+pub struct DailyProfitLossBreakdown {
+    pub date: String,
+    pub profit: f32,
+    pub loss: f32,
+    pub assets: Vec<AssetProfitLoss>,
+}
+
+impl DailyProfitLossBreakdown {
+    // One row per day and asset, for CSV.
+    pub fn flatten(days: Vec<Self>) -> Vec<DailyProfitLossByAsset> {
+        days.into_iter()
+            .flat_map(|day| {
+                let date = day.date;
+                day.assets.into_iter().map(move |asset| DailyProfitLossByAsset {
+                    date: date.clone(),
+                    profit: asset.profit,
+                    loss: asset.loss,
+                    asset: asset.asset,
+                })
+            })
+            .collect()
+    }
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
 pub struct CumulativeFeesResponse {
     pub trader_id: String,
     pub cumulative_fees: f32,
 }
 
+#[derive(Serialize, Deserialize, Debug, PartialEq, ToSchema)]
+pub struct FeeBreakdown {
+    pub asset: String,
+    pub chain: String,
+    pub trade_type: String,
+    pub trades: usize,
+    // Trades charged with the user's negotiated rates (`fee_override`) rather than the fee schedule.
+    pub overridden_trades: usize,
+    pub execution_fees: f32,
+    pub transaction_fees: f32,
+    pub total_fees: f32,
+}
+
+#[derive(Serialize, Deserialize, Debug, ToSchema)]
+pub struct DailyCostBasisPnl {
+    pub date: String,
+    pub asset: String,
+    pub realized: f32,
+    pub unrealized: f32,
+    pub open_quantity: f32,
+    pub mark: f32,
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct DailyProfitLossByAsset {
     pub date: String,
@@ -112,7 +312,7 @@ pub struct DailyProfitLossByTradeType {
     pub trade_type: String,
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, ToSchema)]
 pub struct SlippageByTrader {
     pub trader_id: String,
     pub total_slippage: f32,
@@ -121,193 +321,851 @@ pub struct SlippageByTrader {
     pub average_slippage_cost_percent: f32    
 }
 
+// Trading across every trader on one day, for one asset on one chain.
+#[derive(Serialize, Deserialize, Debug, PartialEq, ToSchema)]
+pub struct PlatformActivity {
+    pub date: String,
+    pub asset: String,
+    pub chain: String,
+    pub trades: usize,
+    pub traders: usize,
+    // Notional traded, `traded_amount * execution_price`.
+    pub volume: f32,
+    pub pnl: f32,
+    pub fees: f32,
+    // Of the trades whose slippage is defined (non-zero `before_price` and `traded_amount`).
+    pub average_slippage: Option<f32>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct ExecutionQuality {
+    pub asset: String,
+    pub trades: usize,
+    pub slippage_p50: Option<f32>,
+    pub slippage_p90: Option<f32>,
+    pub slippage_p99: Option<f32>,
+    pub slippage_cost_percent_p50: Option<f32>,
+    pub slippage_cost_percent_p90: Option<f32>,
+    pub slippage_cost_percent_p99: Option<f32>,
+}
+
+#[derive(Serialize, Deserialize, Debug, ToSchema)]
+pub struct TradeCluster {
+    pub label: String,
+    pub trades: usize,
+    pub average_notional: f32,
+    pub average_pnl: f32,
+    pub win_rate: f32,
+    pub typical_hour: u32,
+    pub trade_ids: Vec<String>,
+}
+
+// Quantities below this are treated as fully closed, absorbing `f32` rounding.
+const LOT_EPSILON: f32 = 1e-6;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Lot {
+    pub quantity: f32,
+    pub price: f32,
+}
+
+// The open lots of one asset, oldest first. All lots are on the same side: negative quantities are shorts.
+#[derive(Debug, Default)]
+pub struct CostBasis {
+    lots: VecDeque<Lot>,
+    pub mark: f32,
+}
+
+impl CostBasis {
+    pub fn lots(&self) -> &VecDeque<Lot> {
+        &self.lots
+    }
+
+    pub fn quantity(&self) -> f32 {
+        self.lots.iter().map(|lot| lot.quantity).sum()
+    }
+
+    pub fn unrealized(&self) -> f32 {
+        self.lots.iter().map(|lot| (self.mark - lot.price) * lot.quantity).sum()
+    }
+
+    // Fills `size` (positive to buy, negative to sell) at `price`, closing the oldest opposite lots first and opening
+    // a lot with whatever is left. Returns the P&L realized by the closed quantity.
+    pub fn fill(&mut self, size: f32, price: f32) -> f32 {
+        let mut remaining = size;
+        let mut realized = 0.0;
+        while remaining.abs() >= LOT_EPSILON {
+            match self.lots.front_mut() {
+                Some(lot) if lot.quantity.signum() != remaining.signum() => {
+                    let closed = remaining.abs().min(lot.quantity.abs());
+                    realized += (price - lot.price) * closed * lot.quantity.signum();
+                    lot.quantity -= closed * lot.quantity.signum();
+                    remaining -= closed * remaining.signum();
+                    if lot.quantity.abs() < LOT_EPSILON {
+                        self.lots.pop_front();
+                    }
+                }
+                _ => {
+                    self.lots.push_back(Lot { quantity: remaining, price });
+                    remaining = 0.0;
+                }
+            }
+        }
+        realized
+    }
+
+    // Fills a trade at its `execution_price` and marks the book at its `final_price` (or the execution price when no
+    // final price was recorded), as `Position` does.
+    pub fn apply(&mut self, trade: &Trade) -> f32 {
+        let size = match trade.trade_type.as_str() {
+            "LimitBuy" | "MarketBuy" => trade.traded_amount,
+            "LimitSell" | "MarketSell" => -trade.traded_amount,
+            _ => 0.0,
+        };
+        self.mark = if trade.final_price > 0.0 { trade.final_price } else { trade.execution_price };
+        self.fill(size, trade.execution_price)
+    }
+}
+
+struct Distribution {
+    trades: usize,
+    slippage: [QuantileSketch; 3],
+    slippage_cost_percent: [QuantileSketch; 3],
+}
+
+impl Distribution {
+    fn new() -> Self {
+        let sketches = || [QuantileSketch::new(0.5), QuantileSketch::new(0.9), QuantileSketch::new(0.99)];
+        Distribution { trades: 0, slippage: sketches(), slippage_cost_percent: sketches() }
+    }
+}
+
 pub struct Chain;
 pub struct TradeType;
 pub struct Asset;
+pub struct TradeSource;
+pub struct TradeMetadata;
 
 impl Chain {
+    pub const ALL: [&'static str; 4] = ["Ethereum", "Arbitrum", "Optimism", "Polygon"];
+
     pub fn is_valid(chain: &str) -> bool {
-        match chain {
-            "Ethereum" => true,
-            "Arbitrum" => true,
-            "Optimism" => true,
-            "Polygon" => true,
-            _ => false,
-        }
-    }    
+        Self::ALL.contains(&chain)
+    }
 }
 
 impl TradeType {
     pub fn is_valid(tradetype: &str) -> bool {
-        match tradetype {
-            "LimitBuy" => true,
-            "LimitSell" => true,
-            "MarketBuy" => true,
-            "MarketSell" => true,
-            _ => false,
-        }
+        matches!(tradetype, "LimitBuy" | "LimitSell" | "MarketBuy" | "MarketSell")
     }
 }
 
 impl Asset {
+    pub const ALL: [&'static str; 5] = ["BTC", "ETH", "XRP", "XLM", "DOGE"];
+
     pub fn is_valid(asset: &str) -> bool {
-        match asset {
-            "BTC" => true,
-            "ETH" => true,
-            "XRP" => true,
-            "XLM" => true,
-            "DOGE" => true,
-            _ => false,
+        Self::ALL.contains(&asset)
+    }
+}
+
+impl TradeSource {
+    pub const MANUAL: &'static str = "manual";
+    pub const SIMULATION: &'static str = "simulation";
+    // Sources qualified by a provider, chain or key ID, e.g. `import:binance`, `onchain:arbitrum` or `api_key:{id}`.
+    pub const KINDS: [&'static str; 3] = ["import", "onchain", "api_key"];
+
+    pub fn import(provider: &str) -> String {
+        format!("import:{}", provider)
+    }
+
+    // Where a trade was executed: the provider of an import (`binance`), or else the kind of source (`onchain`,
+    // `api_key`, `manual`).
+    pub fn venue(source: &str) -> &str {
+        match source.split_once(':') {
+            Some(("import", provider)) => provider,
+            Some((kind, _)) => kind,
+            None => source,
+        }
+    }
+
+    pub fn is_valid(source: &str) -> bool {
+        if source == Self::MANUAL || source == Self::SIMULATION {
+            return true;
+        }
+        match source.split_once(':') {
+            Some((kind, detail)) => Self::KINDS.contains(&kind) && !detail.is_empty() && source.len() <= 100,
+            None => false,
+        }
+    }
+
+    // Matches a source exactly, or every source of a kind when given just the kind (`import` matches `import:binance`).
+    fn matching(source: String) -> Box<dyn BoxableExpression<trades::table, Sqlite, SqlType = diesel::sql_types::Bool>> {
+        if Self::KINDS.contains(&source.as_str()) {
+            Box::new(trades::source.like(format!("{}:%", source)))
+        } else {
+            Box::new(trades::source.eq(source))
+        }
+    }
+}
+
+impl TradeSort {
+    pub const FIELDS: [&'static str; 4] = ["created_at", "amount", "asset", "pnl"];
+
+    // Newest first unless asked otherwise.
+    pub fn parse(sort: Option<&str>, order: Option<&str>) -> Result<Self, String> {
+        let field = match sort.unwrap_or("created_at") {
+            "created_at" => TradeSortField::CreatedAt,
+            "amount" => TradeSortField::Amount,
+            "asset" => TradeSortField::Asset,
+            "pnl" => TradeSortField::Pnl,
+            other => return Err(format!("Unknown sort '{}', expected one of {}", other, Self::FIELDS.join(", "))),
+        };
+        let ascending = match order.unwrap_or("desc") {
+            "asc" => true,
+            "desc" => false,
+            other => return Err(format!("Unknown order '{}', expected asc or desc", other)),
+        };
+        Ok(TradeSort { field, ascending })
+    }
+
+    fn apply(&self, query: trades::BoxedQuery<'static, Sqlite>) -> trades::BoxedQuery<'static, Sqlite> {
+        let pnl = || diesel::dsl::sql::<Double>(TRADE_PNL_SQL);
+        let query = match (self.field, self.ascending) {
+            (TradeSortField::CreatedAt, _) => query,
+            (TradeSortField::Amount, true) => query.order(trades::amount.asc()),
+            (TradeSortField::Amount, false) => query.order(trades::amount.desc()),
+            (TradeSortField::Asset, true) => query.order(trades::asset.asc()),
+            (TradeSortField::Asset, false) => query.order(trades::asset.desc()),
+            (TradeSortField::Pnl, true) => query.order(pnl().asc()),
+            (TradeSortField::Pnl, false) => query.order(pnl().desc()),
+        };
+        if self.ascending {
+            query.then_order_by((trades::created_at.asc(), trades::id.asc()))
+        } else {
+            query.then_order_by((trades::created_at.desc(), trades::id.desc()))
         }
     }
 }
 
+impl TradeMetadata {
+    pub const MAX_KEYS: usize = 20;
+    pub const MAX_KEY_LENGTH: usize = 64;
+    pub const MAX_VALUE_LENGTH: usize = 256;
+
+    // Keys are kept to characters that need no quoting in a JSON path or a query parameter.
+    pub fn is_valid_key(key: &str) -> bool {
+        !key.is_empty()
+            && key.len() <= Self::MAX_KEY_LENGTH
+            && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+    }
+
+    pub fn validate(metadata: &BTreeMap<String, String>) -> Result<(), String> {
+        if metadata.len() > Self::MAX_KEYS {
+            return Err(format!("Metadata can have at most {} keys", Self::MAX_KEYS));
+        }
+        if let Some(key) = metadata.keys().find(|key| !Self::is_valid_key(key)) {
+            return Err(format!("Invalid metadata key '{}': use up to {} letters, digits, '_' or '-'", key, Self::MAX_KEY_LENGTH));
+        }
+        if metadata.values().any(|value| value.chars().count() > Self::MAX_VALUE_LENGTH) {
+            return Err(format!("Metadata values can have at most {} characters", Self::MAX_VALUE_LENGTH));
+        }
+        Ok(())
+    }
+
+    pub fn to_json(metadata: &BTreeMap<String, String>) -> String {
+        serde_json::to_string(metadata).expect("string maps always serialize")
+    }
+
+    pub fn parse(metadata: Option<&str>) -> BTreeMap<String, String> {
+        metadata.and_then(|metadata| serde_json::from_str(metadata).ok()).unwrap_or_default()
+    }
+
+    // `json_extract` takes the key as a bound JSON path, so keys and values never end up in the SQL text.
+    fn matching(key: String, value: String) -> Box<dyn BoxableExpression<trades::table, Sqlite, SqlType = diesel::sql_types::Bool>> {
+        Box::new(
+            diesel::dsl::sql::<diesel::sql_types::Bool>("json_extract(trades.metadata, ")
+                .bind::<Text, _>(format!("$.\"{}\"", key))
+                .sql(") = ")
+                .bind::<Text, _>(value),
+        )
+    }
+}
+
 impl Trade {
     
 
-    pub fn list(conn: &mut SqliteConnection) -> Vec<Self> {
-        trades_dsl
+    pub fn list(conn: &mut SqliteConnection) -> Result<Vec<Self>, DbError> {
+        Ok(trades_dsl
+            .filter(trades::deleted_at.is_null())
             .order(trades::id.desc())
-            .load::<Trade>(conn)
-            .expect("Error loading wallets")
+            .load::<Trade>(conn)?)
     }
 
-    pub fn find_by_id(conn: &mut SqliteConnection, id: String) -> Option<Self> {
-        if let Ok(record) = trades_dsl
+    fn filtered(filter: &TradeFilter) -> trades::BoxedQuery<'static, Sqlite> {
+        let mut query = trades_dsl.filter(trades::deleted_at.is_null()).into_boxed();
+        if let Some(user_id) = filter.user_id.clone() {
+            query = query.filter(trades::user_id.eq(user_id));
+        }
+        if let Some(wallet_id) = filter.wallet_id.clone() {
+            query = query.filter(trades::wallet_id.eq(wallet_id));
+        }
+        if let Some(asset) = filter.asset.clone() {
+            query = query.filter(trades::asset.eq(asset));
+        }
+        if let Some(chain) = filter.chain.clone() {
+            query = query.filter(trades::chain.eq(chain));
+        }
+        if let Some(trade_type) = filter.trade_type.clone() {
+            query = query.filter(trades::trade_type.eq(trade_type));
+        }
+        if let Some(start_date) = filter.start_date.clone() {
+            query = query.filter(trades::created_at.ge(start_date));
+        }
+        if let Some(end_date) = filter.end_date.clone() {
+            query = query.filter(trades::created_at.le(end_date));
+        }
+        if let Some(source) = filter.source.clone() {
+            query = query.filter(TradeSource::matching(source));
+        }
+        for (key, value) in filter.metadata.iter().cloned() {
+            query = query.filter(TradeMetadata::matching(key, value));
+        }
+        query
+    }
+
+    // The trades of a user within a range that did not come from `source`, to leave out of an aggregate.
+    pub fn other_sources(conn: &mut SqliteConnection, start_date: String, end_date: String, user_id: String, source: String) -> Result<Vec<String>, DbError> {
+        Ok(trades_dsl
+            .filter(trades::user_id.eq(user_id))
+            .filter(trades::deleted_at.is_null())
+            .filter(trades::created_at.ge(start_date))
+            .filter(trades::created_at.le(end_date))
+            .filter(diesel::dsl::not(TradeSource::matching(source)))
+            .select(trades::id)
+            .load::<String>(conn)?)
+    }
+
+    pub fn search(conn: &mut SqliteConnection, filter: &TradeFilter, sort: TradeSort, limit: i64, offset: i64) -> Result<Vec<Self>, DbError> {
+        Ok(sort
+            .apply(Self::filtered(filter))
+            .limit(limit)
+            .offset(offset)
+            .load::<Trade>(conn)?)
+    }
+
+    // Like `search`, but continuing after the `(created_at, id)` of the previous page's last trade instead of skipping
+    // `offset` rows.
+    pub fn search_before(conn: &mut SqliteConnection, filter: &TradeFilter, before: Option<&(chrono::NaiveDateTime, String)>, limit: i64) -> Result<Vec<Self>, DbError> {
+        let mut query = Self::filtered(filter);
+        if let Some((created_at, id)) = before {
+            query = query.filter(trades::created_at.lt(*created_at).or(trades::created_at.eq(*created_at).and(trades::id.lt(id.clone()))));
+        }
+        Ok(query
+            .order((trades::created_at.desc(), trades::id.desc()))
+            .limit(limit)
+            .load::<Trade>(conn)?)
+    }
+
+    // A page of a user's trades in creation order, continuing after the `(created_at, id)` of the previous page's last trade.
+    pub fn export_page(conn: &mut SqliteConnection, user_id: &str, after: Option<&(chrono::NaiveDateTime, String)>, limit: i64) -> Result<Vec<Self>, DbError> {
+        let mut query = trades_dsl
+            .filter(trades::user_id.eq(user_id.to_string()))
+            .filter(trades::deleted_at.is_null())
+            .into_boxed();
+        if let Some((created_at, id)) = after {
+            query = query.filter(trades::created_at.gt(*created_at).or(trades::created_at.eq(*created_at).and(trades::id.gt(id.clone()))));
+        }
+        Ok(query
+            .order((trades::created_at.asc(), trades::id.asc()))
+            .limit(limit)
+            .load::<Trade>(conn)?)
+    }
+
+    pub fn count(conn: &mut SqliteConnection, filter: &TradeFilter) -> Result<i64, DbError> {
+        Ok(Self::filtered(filter)
+            .count()
+            .get_result::<i64>(conn)?)
+    }
+
+    // The number, notional and fees of every trade matching the filter, in one aggregate query.
+    pub fn totals(conn: &mut SqliteConnection, filter: &TradeFilter) -> Result<TradeTotals, DbError> {
+        let (trades, volume, fees) = Self::filtered(filter)
+            .select((
+                diesel::dsl::count_star(),
+                diesel::dsl::sql::<Double>("CAST(COALESCE(SUM(traded_amount * execution_price), 0) AS REAL)"),
+                diesel::dsl::sql::<Double>("CAST(COALESCE(SUM(execution_fee + transaction_fee), 0) AS REAL)"),
+            ))
+            .get_result::<(i64, f64, f64)>(conn)?;
+        Ok(TradeTotals { trades, volume: volume as f32, fees: fees as f32 })
+    }
+
+    pub fn find_by_id(conn: &mut SqliteConnection, id: String) -> Result<Option<Self>, DbError> {
+        Ok(trades_dsl
             .find(id)
-            .get_result::<Trade>(conn) {
-            Some(record)
-            } else {
-                None
-            }
+            .filter(trades::deleted_at.is_null())
+            .get_result::<Trade>(conn)
+            .optional()?)
     }
 
-    pub fn create(conn: &mut SqliteConnection, trade: &mut Self) -> Option<Self> {
-        trade.id = Uuid::new_v4().as_hyphenated().to_string();
+    // Like `find_by_id`, but also finds soft-deleted trades.
+    pub fn find_including_deleted(conn: &mut SqliteConnection, id: String) -> Result<Option<Self>, DbError> {
+        Ok(trades_dsl
+            .find(id)
+            .get_result::<Trade>(conn)
+            .optional()?)
+    }
+
+    pub fn create(conn: &mut SqliteConnection, trade: &mut Self) -> Result<(Option<Self>, Option<String>), DbError> {
+        if trade.id.is_empty() {
+            trade.id = Uuid::new_v4().as_hyphenated().to_string();
+        }
         
         if trade.chain.is_empty() || trade.trade_type.is_empty() || trade.asset.is_empty() {
-            return None;
+            return Ok((None, Some("Invalid chain, trade type or asset".to_string())));
         }
         
         if !Chain::is_valid(&trade.chain) || !TradeType::is_valid(&trade.trade_type) || !Asset::is_valid(&trade.asset) {
-            return None;
+            return Ok((None, Some("Invalid chain, trade type or asset".to_string())));
+        }
+
+        if !TradeSource::is_valid(&trade.source) {
+            return Ok((None, Some("Invalid trade source".to_string())));
+        }
+
+        if !UserWallet::owns(conn, &trade.user_id, &trade.wallet_id)? {
+            return Ok((None, Some("Wallet does not belong to this user".to_string())));
         }
                 
-        diesel::insert_into(trades_dsl)
-            .values(&*trade)
-            .execute(conn)
-            .expect("Error saving new trade");
+        let rejected = retry_on_busy(|| {
+            conn.transaction(|conn| {
+                if let Some(rejected) = Self::check_funds(conn, trade)? {
+                    return Ok(Some(rejected));
+                }
+                diesel::insert_into(trades_dsl)
+                    .values(&*trade)
+                    .execute(conn)?;
+                Self::settle(conn, trade)?;
+                TradeAudit::record(conn, &trade.id, TradeAction::CREATE, trade.entered_by.as_deref().unwrap_or(&trade.user_id), None)?;
+                DailySnapshot::invalidate(conn, &trade.user_id, trade.created_at.date())?;
+                Ok(None)
+            })
+        })?;
+        if rejected.is_some() {
+            return Ok((None, rejected));
+        }
+        Position::recompute(conn, &trade.user_id, &trade.asset)?;
         
-        Self::find_by_id(conn, trade.id.clone())
+        Ok((Self::find_by_id(conn, trade.id.clone())?, None))
     }
 
-    pub fn update(conn: &mut SqliteConnection, id: String, trade: &mut Trade) -> Option<Self> {
-        if trade.chain.is_empty() || trade.trade_type.is_empty() || trade.asset.is_empty() {
-            return None;
-        }
-
-        diesel::update(trades_dsl.find(id.clone()))
-            .set((
-                schema::trades::amount.eq(trade.amount.clone()),
-                schema::trades::chain.eq(trade.chain.clone()),
-                schema::trades::trade_type.eq(trade.trade_type.clone()),
-                schema::trades::asset.eq(trade.asset.clone()),
-                schema::trades::before_price.eq(trade.before_price.clone()),
-                schema::trades::execution_price.eq(trade.execution_price.clone()),
-                schema::trades::final_price.eq(trade.final_price.clone()),
-                schema::trades::traded_amount.eq(trade.traded_amount.clone()),
-                schema::trades::updated_at.eq(chrono::Local::now().naive_local())))
-            .execute(conn)
-            .expect("Error updating trade");
-        
-        Self::find_by_id(conn, id)
+    // The wallet movements of a trade: the signed traded value and the (positive) fees debited on top.
+    fn settlement(&self) -> (f32, f32) {
+        let value = self.traded_amount * self.execution_price;
+        let value = if matches!(self.trade_type.as_str(), "LimitBuy" | "MarketBuy") { -value } else { value };
+        (value, self.execution_fee + self.transaction_fee)
+    }
+
+    fn check_funds(conn: &mut SqliteConnection, trade: &Self) -> QueryResult<Option<String>> {
+        let balance = match wallet::table
+            .find(&trade.wallet_id)
+            .select(wallet::balance)
+            .first::<f32>(conn)
+            .optional()?
+        {
+            Some(balance) => balance,
+            None => return Ok(Some("Wallet does not exist".to_string())),
+        };
+
+        let (value, fees) = trade.settlement();
+        let required = fees - value;
+        if balance < required {
+            return Ok(Some(format!(
+                "Insufficient balance: the trade needs {:.2} including {:.2} in fees, but the wallet holds {:.2}",
+                required, fees, balance
+            )));
+        }
+        Ok(None)
+    }
+
+    // Buys add to the wallet's holdings of the asset and sells remove from them.
+    fn signed_size(&self) -> f32 {
+        match self.trade_type.as_str() {
+            "LimitBuy" | "MarketBuy" => self.traded_amount,
+            "LimitSell" | "MarketSell" => -self.traded_amount,
+            _ => 0.0,
+        }
+    }
+
+    pub fn holdings_conflict(conn: &mut SqliteConnection, trade: &Self) -> Result<Option<String>, DbError> {
+        let history = trades_dsl
+            .filter(trades::wallet_id.eq(&trade.wallet_id))
+            .filter(trades::asset.eq(&trade.asset))
+            .filter(trades::id.ne(&trade.id))
+            .filter(trades::deleted_at.is_null())
+            .order((trades::created_at.asc(), trades::id.asc()))
+            .load::<Trade>(conn)?;
+        let mut sequence: Vec<&Trade> = history.iter().collect();
+        sequence.insert(history.partition_point(|existing| existing.created_at <= trade.created_at), trade);
+
+        let mut held = 0.0;
+        for entry in sequence {
+            let size = entry.signed_size();
+            if size < 0.0 && held + size < -LOT_EPSILON {
+                let sell = if std::ptr::eq(entry, trade) { "this trade".to_string() } else { format!("trade {}", entry.id) };
+                return Ok(Some(format!(
+                    "The wallet held {} {} on {}, not enough for the sell of {} in {}",
+                    held, trade.asset, entry.created_at, -size, sell
+                )));
+            }
+            held += size;
+        }
+        Ok(None)
     }
 
-    pub fn delete(conn: &mut SqliteConnection, id: String) -> bool {
-        diesel::delete(trades_dsl.find(id.clone()))
-            .execute(conn)
-            .expect("Error deleting trade");
+    fn settle(conn: &mut SqliteConnection, trade: &Self) -> QueryResult<()> {
+        let (value, fees) = trade.settlement();
+        for (kind, amount) in [(TransactionKind::TRADE_SETTLEMENT, value), (TransactionKind::FEE, -fees)] {
+            if amount != 0.0 && WalletTransaction::post(conn, &trade.wallet_id, kind, amount, Some(trade.id.clone()))?.is_none() {
+                return Err(diesel::result::Error::RollbackTransaction);
+            }
+        }
+        Ok(())
+    }
+
+    // Posts the difference between a trade's settlement before and after a change, or reverses it entirely when
+    // the trade is deleted (`current` is `None`). Credits are posted before debits, so only a net debit can overdraw
+    // the wallet, and that is refused with a message before anything is posted.
+    fn resettle(conn: &mut SqliteConnection, previous: &Self, current: Option<&Self>) -> QueryResult<Option<String>> {
+        let (previous_value, previous_fees) = previous.settlement();
+        let (value, fees) = current.map(Self::settlement).unwrap_or((0.0, 0.0));
+        let mut entries = [
+            (TransactionKind::TRADE_SETTLEMENT, value - previous_value),
+            (TransactionKind::FEE, previous_fees - fees),
+        ];
+        entries.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+        let net: f32 = entries.iter().map(|(_, amount)| amount).sum();
+        if net < 0.0 {
+            let balance = wallet::table.find(&previous.wallet_id).select(wallet::balance).first::<f32>(conn)?;
+            if balance + net < 0.0 {
+                return Ok(Some(format!(
+                    "Insufficient balance: the change debits {:.2} from the wallet, which holds {:.2}",
+                    -net, balance
+                )));
+            }
+        }
+        for (kind, amount) in entries {
+            if amount != 0.0 && WalletTransaction::post(conn, &previous.wallet_id, kind, amount, Some(previous.id.clone()))?.is_none() {
+                return Err(diesel::result::Error::RollbackTransaction);
+            }
+        }
+        Ok(None)
+    }
+
+    // `(None, None)` when no trade has the ID, and `(None, Some(error))` when the wallet cannot cover the change; the
+    // fields are validated by the caller (`TradeForm::validate`).
+    pub fn update(conn: &mut SqliteConnection, id: String, trade: &mut Trade, actor_id: &str) -> Result<(Option<Self>, Option<String>), DbError> {
+        let outcome = retry_on_busy(|| {
+            conn.transaction(|conn| {
+                let previous = match trades_dsl
+                    .find(id.clone())
+                    .filter(trades::deleted_at.is_null())
+                    .first::<Trade>(conn)
+                    .optional()?
+                {
+                    Some(previous) => previous,
+                    None => return Ok(None),
+                };
+                let updated = Trade {
+                    trade_type: trade.trade_type.clone(),
+                    execution_price: trade.execution_price,
+                    traded_amount: trade.traded_amount,
+                    ..previous.clone()
+                };
+                if let Some(rejected) = Self::resettle(conn, &previous, Some(&updated))? {
+                    return Ok(Some((previous, Some(rejected))));
+                }
+
+                diesel::update(trades_dsl.find(id.clone()))
+                    .set((
+                        schema::trades::amount.eq(trade.amount),
+                        schema::trades::chain.eq(trade.chain.clone()),
+                        schema::trades::trade_type.eq(trade.trade_type.clone()),
+                        schema::trades::asset.eq(trade.asset.clone()),
+                        schema::trades::before_price.eq(trade.before_price),
+                        schema::trades::execution_price.eq(trade.execution_price),
+                        schema::trades::final_price.eq(trade.final_price),
+                        schema::trades::traded_amount.eq(trade.traded_amount),
+                        schema::trades::tx_hash.eq(trade.tx_hash.clone()),
+                        // Forms without metadata keep the trade's.
+                        schema::trades::metadata.eq(trade.metadata.clone().or_else(|| previous.metadata.clone())),
+                        schema::trades::updated_at.eq(chrono::Local::now().naive_local())))
+                    .execute(conn)?;
+                TradeAudit::record(conn, &id, TradeAction::UPDATE, actor_id, Some(&previous))?;
+                DailySnapshot::invalidate(conn, &previous.user_id, previous.created_at.date())?;
+                Ok(Some((previous, None)))
+            })
+        })?;
+        let previous = match outcome {
+            Some((previous, None)) => previous,
+            Some((_, rejected)) => return Ok((None, rejected)),
+            None => return Ok((None, None)),
+        };
+        Position::recompute(conn, &previous.user_id, &previous.asset)?;
+        if previous.asset != trade.asset {
+            Position::recompute(conn, &previous.user_id, &trade.asset)?;
+        }
         
-        Self::find_by_id(conn, id).is_none()
+        Ok((Self::find_by_id(conn, id)?, None))
     }
 
-    fn get_dates_by_asset(conn: &mut SqliteConnection,start_date: String, end_date: String, user_id: String, asset: String) -> Vec<Self> {
-        trades_dsl
+    // Soft delete: the row is kept with `deleted_at` set, for the audit trail, and left out of every query. The
+    // trade's settlement is reversed; `(false, None)` when no trade has the ID, and `(false, Some(error))` when the
+    // wallet cannot cover the reversal of a sell.
+    pub fn delete(conn: &mut SqliteConnection, id: String, actor_id: &str) -> Result<(bool, Option<String>), DbError> {
+        let outcome = retry_on_busy(|| {
+            conn.transaction(|conn| {
+                let trade = match trades_dsl
+                    .find(id.clone())
+                    .filter(trades::deleted_at.is_null())
+                    .first::<Trade>(conn)
+                    .optional()?
+                {
+                    Some(trade) => trade,
+                    None => return Ok(None),
+                };
+                if let Some(rejected) = Self::resettle(conn, &trade, None)? {
+                    return Ok(Some((trade, Some(rejected))));
+                }
+
+                let now = chrono::Local::now().naive_local();
+                diesel::update(trades_dsl.find(id.clone()))
+                    .set((trades::deleted_at.eq(now), trades::updated_at.eq(now)))
+                    .execute(conn)?;
+                Tombstone::record(conn, Entity::TRADE, id.clone(), trade.user_id.clone())?;
+                TradeAudit::record(conn, &id, TradeAction::DELETE, actor_id, Some(&trade))?;
+                DailySnapshot::invalidate(conn, &trade.user_id, trade.created_at.date())?;
+                Ok(Some((trade, None)))
+            })
+        })?;
+        match outcome {
+            Some((trade, None)) => {
+                Position::recompute(conn, &trade.user_id, &trade.asset)?;
+                Ok((true, None))
+            }
+            Some((_, rejected)) => Ok((false, rejected)),
+            None => Ok((false, None)),
+        }
+    }
+
+    pub fn changed_since(conn: &mut SqliteConnection, user_id: String, since: Option<chrono::NaiveDateTime>) -> Result<Vec<Self>, DbError> {
+        let mut query = trades_dsl
             .filter(trades::user_id.eq(user_id))
-            .filter(trades::created_at.ge(start_date))
-            .filter(trades::created_at.le(end_date))
-            .filter(trades::asset.eq(asset))
-            .load::<Trade>(conn)
-            .expect("Error loading trades")
+            .filter(trades::deleted_at.is_null())
+            .order(trades::updated_at.asc())
+            .into_boxed();
+        if let Some(since) = since {
+            query = query.filter(trades::updated_at.gt(since));
+        }
+        Ok(query.load::<Trade>(conn)?)
     }
 
-    fn get_dates_by_trade(conn: &mut SqliteConnection, start_date: String, end_date: String, user_id: String, tradetype: String) -> Vec<Self> {
-        trades_dsl
+    // Every trade of a user, oldest first, to replay into holdings.
+    pub fn history_for_user(conn: &mut SqliteConnection, user_id: String) -> Result<Vec<Self>, DbError> {
+        Ok(trades_dsl
             .filter(trades::user_id.eq(user_id))
-            .filter(trades::created_at.ge(start_date))
-            .filter(trades::created_at.le(end_date))
-            .filter(trades::trade_type.eq(tradetype))
-            .load::<Trade>(conn)
-            .expect("Error loading trades")
+            .filter(trades::deleted_at.is_null())
+            .order((trades::created_at.asc(), trades::id.asc()))
+            .load::<Trade>(conn)?)
     }
 
-    fn get_bt_dates(conn: &mut SqliteConnection,start_date: String, end_date: String, user_id: String) -> Vec<Self> {
-        trades_dsl
+    pub fn get_bt_dates(conn: &mut SqliteConnection, start_date: String, end_date: String, user_id: String, excluded: &[String]) -> Result<Vec<Self>, DbError> {
+        Ok(trades_dsl
             .filter(trades::user_id.eq(user_id))
+            .filter(trades::deleted_at.is_null())
+            .filter(trades::id.ne_all(excluded))
             .filter(trades::created_at.ge(start_date))
             .filter(trades::created_at.le(end_date))
-            .load::<Trade>(conn)
-            .expect("Error loading trades")
+            .load::<Trade>(conn)?)
     }
     
-    pub fn cumulative_fees(conn: &mut SqliteConnection, start_date: String, end_date: String, user_id: String) -> CumulativeFeesResponse {
-        let trades: Vec<Trade> = Self::get_bt_dates(conn, start_date, end_date, user_id.clone());
+    pub fn cumulative_fees(conn: &mut SqliteConnection, start_date: String, end_date: String, user_id: String, excluded: &[String]) -> Result<CumulativeFeesResponse, DbError> {
+        let trades: Vec<Trade> = Self::get_bt_dates(conn, start_date, end_date, user_id.clone(), excluded)?;
         
         let mut fees = 0.0;
         for trade in trades.iter() {
             fees += trade.execution_fee + trade.transaction_fee;
         }
 
-        CumulativeFeesResponse { trader_id: user_id, cumulative_fees: fees.round() }
+        Ok(CumulativeFeesResponse { trader_id: user_id, cumulative_fees: fees.round() })
     }
 
-    pub fn profit_loss(conn: &mut SqliteConnection, start_date: String, end_date: String, user_id: String, asset: Option<String>, tradetype: Option<String>) -> Vec<DailyProfitLoss> {
-        let trades: Vec<Trade>;
-        if asset.is_some() {
-            trades = Self::get_dates_by_asset(conn, start_date, end_date, user_id, asset.unwrap());
-        } else if tradetype.is_some() {
-            trades = Self::get_dates_by_trade(conn, start_date, end_date, user_id, tradetype.unwrap());
-        } else {
-            trades = Self::get_bt_dates(conn, start_date, end_date, user_id);
+    // The fees of `cumulative_fees` per asset, chain and trade type, most expensive first.
+    pub fn fee_breakdown(conn: &mut SqliteConnection, start_date: String, end_date: String, user_id: String, excluded: &[String]) -> Result<Vec<FeeBreakdown>, DbError> {
+        let overrides = FeeOverride::list_for_user(conn, &user_id)?;
+        let mut groups: BTreeMap<(String, String, String), FeeBreakdown> = BTreeMap::new();
+        for trade in Self::get_bt_dates(conn, start_date, end_date, user_id, excluded)? {
+            let group = groups
+                .entry((trade.asset.clone(), trade.chain.clone(), trade.trade_type.clone()))
+                .or_insert_with(|| FeeBreakdown {
+                    asset: trade.asset.clone(),
+                    chain: trade.chain.clone(),
+                    trade_type: trade.trade_type.clone(),
+                    trades: 0,
+                    overridden_trades: 0,
+                    execution_fees: 0.0,
+                    transaction_fees: 0.0,
+                    total_fees: 0.0,
+                });
+            group.trades += 1;
+            if FeeOverride::covering(&overrides, TradeSource::venue(&trade.source), trade.created_at).is_some() {
+                group.overridden_trades += 1;
+            }
+            group.execution_fees += trade.execution_fee;
+            group.transaction_fees += trade.transaction_fee;
+            group.total_fees += trade.execution_fee + trade.transaction_fee;
         }
-        
-        let mut daily_profit_loss: Vec<DailyProfitLoss> = Vec::new();
-        let mut dates: Vec<String> = Vec::new();
-        for trade in trades.iter() {
-            if !dates.contains(&trade.created_at.date().to_string()) {
-                dates.push(trade.created_at.date().to_string());
+
+        let mut breakdown: Vec<FeeBreakdown> = groups.into_values().collect();
+        breakdown.sort_by(|a, b| b.total_fees.total_cmp(&a.total_fees));
+        Ok(breakdown)
+    }
+
+    // An asset filter, or else a trade type filter, for `daily_pnl_rows`.
+    fn pnl_filter(asset: Option<String>, tradetype: Option<String>) -> Option<(&'static str, String)> {
+        asset.map(|asset| ("asset = ?", asset)).or(tradetype.map(|tradetype| ("trade_type = ?", tradetype)))
+    }
+
+    // Profit and loss per day, and per asset within the day when `by_asset`. Rows are not rounded.
+    fn daily_pnl_rows<Row: QueryableByName<Sqlite> + 'static>(
+        conn: &mut SqliteConnection,
+        start_date: String,
+        end_date: String,
+        user_id: String,
+        filter: Option<(&str, String)>,
+        excluded: &[String],
+        by_asset: bool,
+    ) -> Result<Vec<Row>, DbError> {
+        let mut conditions = vec!["user_id = ?", "deleted_at IS NULL", "created_at >= ?", "created_at <= ?"];
+        if let Some((condition, _)) = &filter {
+            conditions.push(condition);
+        }
+        let placeholders = vec!["?"; excluded.len()].join(", ");
+        let not_excluded = format!("id NOT IN ({})", placeholders);
+        if !excluded.is_empty() {
+            conditions.push(&not_excluded);
+        }
+        let group = if by_asset { "date(created_at), asset" } else { "date(created_at)" };
+
+        let mut query = diesel::sql_query(format!(
+            "SELECT date(created_at) AS date, {}\
+                CAST(SUM(CASE WHEN pnl > 0 THEN pnl ELSE 0 END) AS REAL) AS profit, \
+                CAST(SUM(CASE WHEN pnl > 0 THEN 0 ELSE pnl END) AS REAL) AS loss \
+            FROM (SELECT created_at, asset, {} AS pnl FROM trades WHERE {}) \
+            GROUP BY {} \
+            ORDER BY {}",
+            if by_asset { "asset, " } else { "" },
+            TRADE_PNL_SQL,
+            conditions.join(" AND "),
+            group,
+            group
+        ))
+        .into_boxed::<Sqlite>()
+        .bind::<Text, _>(user_id)
+        .bind::<Text, _>(start_date)
+        .bind::<Text, _>(end_date);
+        if let Some((_, value)) = filter {
+            query = query.bind::<Text, _>(value);
+        }
+        for id in excluded {
+            query = query.bind::<Text, _>(id.clone());
+        }
+
+        Ok(query.load::<Row>(conn)?)
+    }
+
+    pub fn profit_loss(conn: &mut SqliteConnection, start_date: String, end_date: String, user_id: String, asset: Option<String>, tradetype: Option<String>, excluded: &[String]) -> Result<Vec<DailyProfitLoss>, DbError> {
+        Ok(Self::daily_pnl_rows::<DailyProfitLossRow>(conn, start_date, end_date, user_id, Self::pnl_filter(asset, tradetype), excluded, false)?
+            .into_iter()
+            .map(|row| DailyProfitLoss {
+                date: row.date,
+                profit: (row.profit as f32).round(),
+                loss: (row.loss as f32).round(),
+            })
+            .collect())
+    }
+
+    // The days of `profit_loss`, each with the profit and loss of every asset traded that day. Day totals are summed
+    // before rounding, so they match `profit_loss`.
+    pub fn profit_loss_by_asset(conn: &mut SqliteConnection, start_date: String, end_date: String, user_id: String, asset: Option<String>, tradetype: Option<String>, excluded: &[String]) -> Result<Vec<DailyProfitLossBreakdown>, DbError> {
+        let rows = Self::daily_pnl_rows::<AssetProfitLossRow>(conn, start_date, end_date, user_id, Self::pnl_filter(asset, tradetype), excluded, true)?;
+
+        let mut days: Vec<(String, f64, f64, Vec<AssetProfitLoss>)> = Vec::new();
+        for row in rows {
+            if days.last().is_none_or(|(date, ..)| *date != row.date) {
+                days.push((row.date.clone(), 0.0, 0.0, Vec::new()));
             }
-        };
-        for date in dates {
-            let mut profit = 0.0;
-            let mut loss = 0.0;
-            for trade in trades.iter() {
-                if trade.created_at.date().to_string() == date {
-                    let pnl = trade.calculate_trade_pnl();
-                    if pnl > 0.0 {
-                        profit += pnl;
-                    } else {
-                        loss += pnl;
-                    }
-                }
+            let day = days.last_mut().unwrap();
+            day.1 += row.profit;
+            day.2 += row.loss;
+            day.3.push(AssetProfitLoss { asset: row.asset, profit: (row.profit as f32).round(), loss: (row.loss as f32).round() });
+        }
+
+        Ok(days
+            .into_iter()
+            .map(|(date, profit, loss, assets)| DailyProfitLossBreakdown {
+                date,
+                profit: (profit as f32).round(),
+                loss: (loss as f32).round(),
+                assets,
+            })
+            .collect())
+    }
+
+    // Realized and unrealized P&L per day and asset under FIFO cost basis. Lots opened before `start_date` are replayed
+    // from the start of the history so sells in the range close them at their actual cost.
+    pub fn profit_loss_fifo(conn: &mut SqliteConnection, start_date: String, end_date: String, user_id: String, asset: Option<String>, excluded: &[String]) -> Result<Vec<DailyCostBasisPnl>, DbError> {
+        let mut query = trades_dsl
+            .filter(trades::user_id.eq(user_id))
+            .filter(trades::deleted_at.is_null())
+            .filter(trades::created_at.le(end_date))
+            .into_boxed();
+        if let Some(asset) = asset {
+            query = query.filter(trades::asset.eq(asset));
+        }
+        if !excluded.is_empty() {
+            query = query.filter(diesel::dsl::not(trades::id.eq_any(excluded.to_vec())));
+        }
+
+        let mut books: BTreeMap<String, CostBasis> = BTreeMap::new();
+        let mut days: BTreeMap<(String, String), DailyCostBasisPnl> = BTreeMap::new();
+        for trade in query.order((trades::created_at.asc(), trades::id.asc())).load_iter::<Trade, DefaultLoadingMode>(conn)? {
+            let trade = trade?;
+            let book = books.entry(trade.asset.clone()).or_default();
+            let realized = book.apply(&trade);
+            if trade.created_at.format("%Y-%m-%d %H:%M:%S%.f").to_string() < start_date {
+                continue;
             }
-            daily_profit_loss.push(DailyProfitLoss {
-                date: date,
-                profit: profit.round(),
-                loss: loss.round(),
+
+            let date = trade.created_at.date().to_string();
+            let day = days.entry((date.clone(), trade.asset.clone())).or_insert_with(|| DailyCostBasisPnl {
+                date,
+                asset: trade.asset.clone(),
+                realized: 0.0,
+                unrealized: 0.0,
+                open_quantity: 0.0,
+                mark: 0.0,
             });
+            day.realized += realized;
+            day.unrealized = book.unrealized();
+            day.open_quantity = book.quantity();
+            day.mark = book.mark;
         }
-        daily_profit_loss
+
+        Ok(days
+            .into_values()
+            .map(|day| DailyCostBasisPnl { realized: day.realized.round(), unrealized: day.unrealized.round(), ..day })
+            .collect())
     }
 
     pub fn calculate_trade_pnl(&self) -> f32{
@@ -324,8 +1182,8 @@ impl Trade {
         pnl * self.traded_amount - self.execution_fee - self.transaction_fee
     }
 
-    pub fn get_slippage_bt_dates(conn: &mut SqliteConnection, start_date: String, end_date: String, user_id: String) -> SlippageByTrader {
-        let trades = Trade::get_bt_dates(conn, start_date, end_date, user_id.clone());
+    pub fn get_slippage_bt_dates(conn: &mut SqliteConnection, start_date: String, end_date: String, user_id: String, excluded: &[String]) -> Result<SlippageByTrader, DbError> {
+        let trades = Trade::get_bt_dates(conn, start_date, end_date, user_id.clone(), excluded)?;
         
         let mut total_slippage = 0.0;
         let mut total_slippage_cost_percent = 0.0;
@@ -339,14 +1197,188 @@ impl Trade {
         let average_slippage = total_slippage / trades.len() as f32;
         let average_slippage_cost_percent = total_slippage_cost_percent / trades.len() as f32;
         
-        SlippageByTrader {
+        Ok(SlippageByTrader {
             trader_id: user_id,
             total_slippage: total_slippage.round(),
             average_slippage: average_slippage.round(),
             total_slippage_cost_percent: total_slippage_cost_percent.round(),
             average_slippage_cost_percent: average_slippage_cost_percent.round(),
+        })
+
+    }
+
+    // Every trader's trades between the dates per day, asset and chain, oldest first. Simulations are left out.
+    pub fn platform_activity(conn: &mut SqliteConnection, start_date: String, end_date: String) -> Result<Vec<PlatformActivity>, DbError> {
+        // Per (date, asset, chain): the totals, the distinct traders and the slippages to average.
+        type Group = (PlatformActivity, BTreeSet<String>, Vec<f32>);
+        let mut groups: BTreeMap<(String, String, String), Group> = BTreeMap::new();
+
+        let rows = trades_dsl
+            .filter(trades::deleted_at.is_null())
+            .filter(trades::source.ne(TradeSource::SIMULATION))
+            .filter(trades::created_at.ge(start_date))
+            .filter(trades::created_at.le(end_date))
+            .load_iter::<Trade, DefaultLoadingMode>(conn)?;
+        for trade in rows {
+            let trade = trade?;
+            let date = trade.created_at.date().to_string();
+            let (activity, traders, slippages) = groups
+                .entry((date.clone(), trade.asset.clone(), trade.chain.clone()))
+                .or_insert_with(|| {
+                    let activity = PlatformActivity {
+                        date,
+                        asset: trade.asset.clone(),
+                        chain: trade.chain.clone(),
+                        trades: 0,
+                        traders: 0,
+                        volume: 0.0,
+                        pnl: 0.0,
+                        fees: 0.0,
+                        average_slippage: None,
+                    };
+                    (activity, Default::default(), Vec::new())
+                });
+            activity.trades += 1;
+            activity.volume += trade.traded_amount * trade.execution_price;
+            activity.pnl += trade.calculate_trade_pnl();
+            activity.fees += trade.execution_fee + trade.transaction_fee;
+            let (slippage, _) = trade.calculate_slippage();
+            if slippage.is_finite() && trade.before_price > 0.0 {
+                slippages.push(slippage);
+            }
+            traders.insert(trade.user_id);
+        }
+
+        Ok(groups
+            .into_values()
+            .map(|(mut activity, traders, slippages)| {
+                activity.traders = traders.len();
+                if !slippages.is_empty() {
+                    activity.average_slippage = Some(slippages.iter().sum::<f32>() / slippages.len() as f32);
+                }
+                activity
+            })
+            .collect())
+    }
+
+    pub fn execution_quality(conn: &mut SqliteConnection, start_date: String, end_date: String, user_id: String, excluded: &[String]) -> Result<Vec<ExecutionQuality>, DbError> {
+        let mut distributions: BTreeMap<String, Distribution> = BTreeMap::new();
+
+        let rows = trades_dsl
+            .filter(trades::user_id.eq(user_id))
+            .filter(trades::deleted_at.is_null())
+            .filter(trades::id.ne_all(excluded))
+            .filter(trades::created_at.ge(start_date))
+            .filter(trades::created_at.le(end_date))
+            .load_iter::<Trade, DefaultLoadingMode>(conn)?;
+        for trade in rows {
+            let trade = trade?;
+            let (slippage, slippage_cost_percent) = trade.calculate_slippage();
+            let distribution = distributions.entry(trade.asset).or_insert_with(Distribution::new);
+            distribution.trades += 1;
+            distribution.slippage.iter_mut().for_each(|sketch| sketch.observe(slippage as f64));
+            distribution.slippage_cost_percent.iter_mut().for_each(|sketch| sketch.observe(slippage_cost_percent as f64));
+        }
+
+        let estimate = |sketch: &QuantileSketch| sketch.quantile().map(|value| value as f32);
+        Ok(distributions
+            .into_iter()
+            .map(|(asset, distribution)| ExecutionQuality {
+                asset,
+                trades: distribution.trades,
+                slippage_p50: estimate(&distribution.slippage[0]),
+                slippage_p90: estimate(&distribution.slippage[1]),
+                slippage_p99: estimate(&distribution.slippage[2]),
+                slippage_cost_percent_p50: estimate(&distribution.slippage_cost_percent[0]),
+                slippage_cost_percent_p90: estimate(&distribution.slippage_cost_percent[1]),
+                slippage_cost_percent_p99: estimate(&distribution.slippage_cost_percent[2]),
+            })
+            .collect())
+    }
+
+    pub fn clusters(conn: &mut SqliteConnection, start_date: String, end_date: String, user_id: String, k: usize) -> Result<Vec<TradeCluster>, DbError> {
+        let trades = Self::get_bt_dates(conn, start_date, end_date, user_id, &[])?;
+        if trades.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        // Hours are placed on a circle so that 23:00 and 01:00 end up close together.
+        let angle = |trade: &Trade| trade.created_at.hour() as f64 / 24.0 * std::f64::consts::TAU;
+        let mut features: Vec<Vec<f64>> = trades
+            .iter()
+            .map(|trade| {
+                let notional = (trade.execution_price * trade.traded_amount).abs() as f64;
+                vec![notional.ln_1p(), trade.calculate_trade_pnl() as f64, angle(trade).sin(), angle(trade).cos()]
+            })
+            .collect();
+        standardize(&mut features);
+        let assignments = kmeans(&features, k, 100).assignments;
+
+        let notional = |trade: &Trade| trade.execution_price * trade.traded_amount;
+        let overall_notional = trades.iter().map(notional).sum::<f32>() / trades.len() as f32;
+
+        let mut groups: BTreeMap<usize, Vec<&Trade>> = BTreeMap::new();
+        for (trade, cluster) in trades.iter().zip(assignments) {
+            groups.entry(cluster).or_default().push(trade);
         }
 
+        let mut clusters: Vec<TradeCluster> = groups
+            .into_values()
+            .map(|members| {
+                let count = members.len() as f32;
+                let average_notional = members.iter().map(|trade| notional(trade)).sum::<f32>() / count;
+                let average_pnl = members.iter().map(|trade| trade.calculate_trade_pnl()).sum::<f32>() / count;
+                let wins = members.iter().filter(|trade| trade.calculate_trade_pnl() > 0.0).count() as f32;
+                let (sin, cos) = members
+                    .iter()
+                    .fold((0.0, 0.0), |(sin, cos), trade| (sin + angle(trade).sin(), cos + angle(trade).cos()));
+                let typical_hour = (sin.atan2(cos).rem_euclid(std::f64::consts::TAU) / std::f64::consts::TAU * 24.0).round() as u32 % 24;
+
+                let size = if average_notional >= overall_notional { "large" } else { "small" };
+                let outcome = if average_pnl >= 0.0 { "winning" } else { "losing" };
+                let time = match typical_hour {
+                    6..=11 => "morning",
+                    12..=17 => "afternoon",
+                    18..=21 => "evening",
+                    _ => "late-night",
+                };
+
+                TradeCluster {
+                    label: format!("{} {} {} trades", size, outcome, time),
+                    trades: members.len(),
+                    average_notional: average_notional.round(),
+                    average_pnl: average_pnl.round(),
+                    win_rate: wins / count,
+                    typical_hour,
+                    trade_ids: members.iter().map(|trade| trade.id.clone()).collect(),
+                }
+            })
+            .collect();
+        clusters.sort_by_key(|cluster| std::cmp::Reverse(cluster.trades));
+        Ok(clusters)
+    }
+
+    pub fn outliers(conn: &mut SqliteConnection, start_date: String, end_date: String, user_id: String, deviations: f32) -> Result<Vec<String>, DbError> {
+        let trades = Self::get_bt_dates(conn, start_date, end_date, user_id, &[])?;
+
+        // Mean and standard deviation over the finite values only; a zero amount or price yields a NaN slippage.
+        let bounds = |values: &[f32]| {
+            let finite: Vec<f32> = values.iter().cloned().filter(|value| value.is_finite()).collect();
+            let mean = finite.iter().sum::<f32>() / finite.len() as f32;
+            let deviation = (finite.iter().map(|value| (value - mean).powi(2)).sum::<f32>() / finite.len() as f32).sqrt();
+            (mean, deviation * deviations)
+        };
+        let pnl: Vec<f32> = trades.iter().map(|trade| trade.calculate_trade_pnl()).collect();
+        let slippage: Vec<f32> = trades.iter().map(|trade| trade.calculate_slippage().0).collect();
+        let (pnl_mean, pnl_limit) = bounds(&pnl);
+        let (slippage_mean, slippage_limit) = bounds(&slippage);
+
+        Ok(trades
+            .iter()
+            .enumerate()
+            .filter(|(index, _)| (pnl[*index] - pnl_mean).abs() > pnl_limit || (slippage[*index] - slippage_mean).abs() > slippage_limit)
+            .map(|(_, trade)| trade.id.clone())
+            .collect())
     }
 
     pub fn calculate_slippage(&self) -> (f32, f32) {
@@ -0,0 +1,80 @@
+//! This module defines the audit trail of changes made to trades.
+//!
+//! A `TradeAudit` row is written in the same transaction as every create, update and delete of a trade. It records the
+//! `action` (`TradeAction`), the user who made the change (`actor_id`, from the JWT of the request, or the trade's
+//! owner for changes made without one) and, for updates and deletes, the full trade as it was before the change
+//! (`previous`, serialized as JSON). Rows are only ever appended, and deleted trades are kept (see `Trade::delete`), so
+//! the trail of a trade can always be replayed.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use crate::models::trade_audit::{TradeAudit, TradeAction};
+//!
+//! conn.transaction(|conn| {
+//!     diesel::update(trades::table.find(&trade.id)).set(trades::amount.eq(10.0)).execute(conn)?;
+//!     TradeAudit::record(conn, &trade.id, TradeAction::UPDATE, "user_id", Some(&trade))
+//! })?;
+//!
+//! let history = TradeAudit::history(&mut connection, "trade_id")?;
+//! ```
+//!
+//! # Note
+//! This module assumes the availability of a database connection (`SqliteConnection` in this case) for audit data retrieval and manipulation.
+
+use uuid::Uuid;
+use serde::{Serialize, Deserialize};
+use diesel::prelude::*;
+
+use super::super::error::DbError;
+use super::super::schema::trade_audit;
+use super::trade::Trade;
+
+#[derive(Debug, Deserialize, Serialize, Queryable, Insertable)]
+#[diesel(table_name = crate::db::schema::trade_audit)]
+pub struct TradeAudit {
+    pub id: String,
+    pub trade_id: String,
+    pub action: String,
+    pub actor_id: String,
+    pub previous: Option<String>,
+    pub created_at: chrono::NaiveDateTime,
+}
+
+pub struct TradeAction;
+
+impl TradeAction {
+    pub const CREATE: &'static str = "create";
+    pub const UPDATE: &'static str = "update";
+    pub const DELETE: &'static str = "delete";
+}
+
+impl TradeAudit {
+    pub fn record(conn: &mut SqliteConnection, trade_id: &str, action: &str, actor_id: &str, previous: Option<&Trade>) -> QueryResult<Self> {
+        let previous = previous
+            .map(serde_json::to_string)
+            .transpose()
+            .map_err(|err| diesel::result::Error::SerializationError(Box::new(err)))?;
+        let entry = TradeAudit {
+            id: Uuid::new_v4().as_hyphenated().to_string(),
+            trade_id: trade_id.to_string(),
+            action: action.to_string(),
+            actor_id: actor_id.to_string(),
+            previous,
+            created_at: chrono::Local::now().naive_local(),
+        };
+        diesel::insert_into(trade_audit::table)
+            .values(&entry)
+            .execute(conn)?;
+
+        Ok(entry)
+    }
+
+    // Oldest first.
+    pub fn history(conn: &mut SqliteConnection, trade_id: &str) -> Result<Vec<Self>, DbError> {
+        Ok(trade_audit::table
+            .filter(trade_audit::trade_id.eq(trade_id))
+            .order((trade_audit::created_at.asc(), trade_audit::id.asc()))
+            .load::<TradeAudit>(conn)?)
+    }
+}
@@ -0,0 +1,49 @@
+use crate::db::fixtures::{test_connection, trade, user};
+use crate::services::trade::{fill_optional_fields, TradeForm};
+use super::trade::Trade;
+use super::trade_audit::{TradeAction, TradeAudit};
+
+fn form(user_id: &str, wallet_id: &str, traded_amount: f32) -> TradeForm {
+    TradeForm { traded_amount: Some(traded_amount), ..trade(user_id, wallet_id) }
+}
+
+#[test]
+fn changes_are_recorded_with_previous_values() {
+    let conn = &mut test_connection();
+    let user = user(conn, "audit");
+
+    let trade = Trade::create(conn, &mut fill_optional_fields(&form(&user.id, &user.wallet_id, 1.0))).unwrap().0.unwrap();
+    Trade::update(conn, trade.id.clone(), &mut fill_optional_fields(&form(&user.id, &user.wallet_id, 2.0)), "admin_id").unwrap().0.unwrap();
+    assert_eq!(Trade::delete(conn, trade.id.clone(), &user.id).unwrap(), (true, None));
+    assert_eq!(Trade::delete(conn, trade.id.clone(), &user.id).unwrap(), (false, None));
+
+    let history = TradeAudit::history(conn, &trade.id).unwrap();
+    let actions: Vec<(&str, &str)> = history.iter().map(|entry| (entry.action.as_str(), entry.actor_id.as_str())).collect();
+    assert_eq!(actions, vec![(TradeAction::CREATE, user.id.as_str()), (TradeAction::UPDATE, "admin_id"), (TradeAction::DELETE, user.id.as_str())]);
+    assert!(history[0].previous.is_none());
+
+    let before_update: Trade = serde_json::from_str(history[1].previous.as_deref().unwrap()).unwrap();
+    let before_delete: Trade = serde_json::from_str(history[2].previous.as_deref().unwrap()).unwrap();
+    assert_eq!((before_update.traded_amount, before_delete.traded_amount), (1.0, 2.0));
+}
+
+#[test]
+fn deleted_trades_are_kept_but_hidden() {
+    let conn = &mut test_connection();
+    let user = user(conn, "soft");
+
+    let kept = Trade::create(conn, &mut fill_optional_fields(&form(&user.id, &user.wallet_id, 1.0))).unwrap().0.unwrap();
+    let deleted = Trade::create(conn, &mut fill_optional_fields(&form(&user.id, &user.wallet_id, 3.0))).unwrap().0.unwrap();
+    assert_eq!(Trade::delete(conn, deleted.id.clone(), &user.id).unwrap(), (true, None));
+
+    assert!(Trade::find_by_id(conn, deleted.id.clone()).unwrap().is_none());
+    assert!(Trade::find_including_deleted(conn, deleted.id.clone()).unwrap().unwrap().deleted_at.is_some());
+    assert!(Trade::update(conn, deleted.id.clone(), &mut fill_optional_fields(&form(&user.id, &user.wallet_id, 5.0)), &user.id).unwrap().0.is_none());
+
+    let listed: Vec<String> = Trade::list(conn).unwrap().into_iter().map(|trade| trade.id).collect();
+    assert_eq!(listed, vec![kept.id.clone()]);
+    let start = (kept.created_at - chrono::Duration::days(1)).to_string();
+    let end = (kept.created_at + chrono::Duration::days(1)).to_string();
+    let in_range: Vec<String> = Trade::get_bt_dates(conn, start, end, user.id, &[]).unwrap().into_iter().map(|trade| trade.id).collect();
+    assert_eq!(in_range, vec![kept.id]);
+}
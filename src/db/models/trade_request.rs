@@ -0,0 +1,113 @@
+//! This module defines the trade request inbox: trades an assistant proposes for an owner to accept or reject.
+//!
+//! An assistant holding a `propose` delegation (see `delegation`) submits a trade form on the owner's behalf. Instead
+//! of creating the trade, a `TradeRequest` is queued with the form (as JSON) in the owner's inbox. Accepting it creates
+//! the trade, recording the assistant in `Trade::entered_by`, and stores the ID of the created trade; rejecting it
+//! only changes the status. Resolved requests stay in the table as history.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use crate::models::trade_request::{RequestStatus, TradeRequest};
+//!
+//! // Queue a proposal from an assistant.
+//! let request = TradeRequest::create(&mut connection, "owner_id".to_string(), "assistant_id".to_string(), payload_json)?;
+//!
+//! // List what is waiting for the owner.
+//! let pending = TradeRequest::list_pending(&mut connection, "owner_id".to_string())?;
+//!
+//! // Mark it as accepted once the trade has been created.
+//! TradeRequest::resolve(&mut connection, request.id, RequestStatus::ACCEPTED, Some(trade.id))?;
+//! ```
+//!
+//! # Note
+//! This module assumes the availability of a database connection (`SqliteConnection` in this case) for inbox data retrieval and manipulation.
+
+use uuid::Uuid;
+use serde::{Serialize, Deserialize};
+use diesel::prelude::*;
+
+use super::super::error::DbError;
+use super::super::retry::retry_on_busy;
+use super::super::schema::trade_requests;
+
+#[derive(Debug, Deserialize, Serialize, Queryable, Insertable)]
+#[diesel(table_name = crate::db::schema::trade_requests)]
+pub struct TradeRequest {
+    pub id: String,
+    pub owner_id: String,
+    pub requested_by: String,
+    pub payload: String,
+    pub status: String,
+    pub trade_id: Option<String>,
+    pub created_at: chrono::NaiveDateTime,
+    pub updated_at: chrono::NaiveDateTime,
+}
+
+pub struct RequestStatus;
+
+impl RequestStatus {
+    pub const PENDING: &'static str = "Pending";
+    pub const ACCEPTED: &'static str = "Accepted";
+    pub const REJECTED: &'static str = "Rejected";
+}
+
+impl TradeRequest {
+    pub fn find_by_id(conn: &mut SqliteConnection, id: String) -> Result<Option<Self>, DbError> {
+        Ok(trade_requests::table
+            .find(id)
+            .first::<TradeRequest>(conn)
+            .optional()?)
+    }
+
+    pub fn list_pending(conn: &mut SqliteConnection, owner_id: String) -> Result<Vec<Self>, DbError> {
+        Ok(trade_requests::table
+            .filter(trade_requests::owner_id.eq(owner_id))
+            .filter(trade_requests::status.eq(RequestStatus::PENDING))
+            .order(trade_requests::created_at.asc())
+            .load::<TradeRequest>(conn)?)
+    }
+
+    pub fn create(conn: &mut SqliteConnection, owner_id: String, requested_by: String, payload: String) -> Result<Option<Self>, DbError> {
+        let now = chrono::Local::now().naive_local();
+        let request = TradeRequest {
+            id: Uuid::new_v4().as_hyphenated().to_string(),
+            owner_id,
+            requested_by,
+            payload,
+            status: RequestStatus::PENDING.to_string(),
+            trade_id: None,
+            created_at: now,
+            updated_at: now,
+        };
+
+        retry_on_busy(|| {
+            diesel::insert_into(trade_requests::table)
+                .values(&request)
+                .execute(conn)
+        })?;
+
+        Self::find_by_id(conn, request.id)
+    }
+
+    pub fn resolve(conn: &mut SqliteConnection, id: String, status: &str, trade_id: Option<String>) -> Result<Option<Self>, DbError> {
+        let updated = retry_on_busy(|| {
+            diesel::update(
+                trade_requests::table
+                    .find(id.clone())
+                    .filter(trade_requests::status.eq(RequestStatus::PENDING)),
+            )
+            .set((
+                trade_requests::status.eq(status),
+                trade_requests::trade_id.eq(trade_id.clone()),
+                trade_requests::updated_at.eq(chrono::Local::now().naive_local()),
+            ))
+            .execute(conn)
+        })?;
+
+        if updated == 0 {
+            return Ok(None);
+        }
+        Self::find_by_id(conn, id)
+    }
+}
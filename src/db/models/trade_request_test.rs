@@ -0,0 +1,20 @@
+use crate::db::fixtures::{test_connection, user};
+use super::trade_request::{RequestStatus, TradeRequest};
+
+#[test]
+fn pending_requests_are_resolved_once() {
+    let conn = &mut test_connection();
+    let owner_id = user(conn, "owner").id;
+    let assistant_id = user(conn, "assistant").id;
+
+    let first = TradeRequest::create(conn, owner_id.clone(), assistant_id.clone(), "{}".to_string()).unwrap().unwrap();
+    TradeRequest::create(conn, owner_id.clone(), assistant_id.clone(), "{}".to_string()).unwrap();
+    assert_eq!(first.status, RequestStatus::PENDING);
+    assert_eq!(TradeRequest::list_pending(conn, owner_id.clone()).unwrap().len(), 2);
+    assert!(TradeRequest::list_pending(conn, assistant_id).unwrap().is_empty());
+
+    let rejected = TradeRequest::resolve(conn, first.id.clone(), RequestStatus::REJECTED, None).unwrap().unwrap();
+    assert_eq!(rejected.status, RequestStatus::REJECTED);
+    assert!(TradeRequest::resolve(conn, first.id, RequestStatus::ACCEPTED, None).unwrap().is_none());
+    assert_eq!(TradeRequest::list_pending(conn, owner_id).unwrap().len(), 1);
+}
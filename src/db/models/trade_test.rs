@@ -1,41 +1,24 @@
-use diesel::SqliteConnection;
-use r2d2::PooledConnection;
 use rand::Rng;
 
-use crate::db::establish_connection;
+use crate::db::fixtures::{test_connection, trade, user, TestConnection};
 use crate::services::trade::{TradeForm, fill_optional_fields};
-use super::trade::Trade;
-use super::wallet::Wallet;
+use super::trade::{AssetProfitLoss, CostBasis, Lot, Trade, TradeFilter, TradeSort, TradeTotals};
 use super::user::User;
+use super::wallet_transaction::WalletTransaction;
 
-fn get_connection() -> PooledConnection<diesel::r2d2::ConnectionManager<diesel::SqliteConnection>> {
-    let pool = establish_connection();
-    pool.get().unwrap()
+fn get_connection() -> TestConnection {
+    test_connection()
 }
 
-fn create_wallet(conn: &mut SqliteConnection) -> String {
-    let wallet = Wallet::create(conn).unwrap();
-    wallet.id
-}
-
-fn create_user(conn: &mut SqliteConnection) -> (String, String) {
-    let name = "test_user".to_string();
-    let email = "test_email".to_string();
-    let password = "test_password".to_string();
-    let wallet_id = create_wallet(conn);
-
-    let (user, _err) = User::create(conn, name, email, wallet_id, password);
-    
-    let user = user.unwrap();
-    (user.id, user.wallet_id)
+// Each day is rounded on its own, so the sum may drift from the rounded total by up to one per day.
+fn assert_daily_total(actual: f32, expected: f32, days: usize) {
+    assert!((actual - expected).abs() <= days as f32, "{} is not within {} of {}", actual, days, expected);
 }
 
 fn gen_rand_trade(user_id: String, wallet_id: String) -> Trade {
     let mut rng = rand::thread_rng();
 
     let trade_form = TradeForm {
-        user_id: user_id,
-        wallet_id: wallet_id,
         trade_type: if rng.gen() {
             if rng.gen() {
                 "LimitBuy".to_string()
@@ -65,6 +48,7 @@ fn gen_rand_trade(user_id: String, wallet_id: String) -> Trade {
         final_price: Some(rng.gen_range(1.0..100.0)),
         traded_amount: Some(rng.gen_range(1.0..100.0)),
         timestamp: Some(rng.gen_range(1641045600..1672418400)),
+        ..trade(&user_id, &wallet_id)
     };
 
     fill_optional_fields(&trade_form)
@@ -73,10 +57,10 @@ fn gen_rand_trade(user_id: String, wallet_id: String) -> Trade {
 #[test]
 fn create_trade() {
     let conn = &mut get_connection();
-    let (user_id, wallet_id) = create_user(conn);
+    let User { id: user_id, wallet_id, .. } = user(conn, "trader");
     let mut new_trade = gen_rand_trade(user_id, wallet_id);
     
-    let trade = Trade::create(conn, &mut new_trade);
+    let (trade, _errors) = Trade::create(conn, &mut new_trade).unwrap();
     let trade = trade.unwrap();
 
     assert_eq!(trade.user_id, new_trade.user_id);
@@ -97,49 +81,57 @@ fn create_trade() {
 #[test]
 fn cumulative_fees() {
     let conn = &mut get_connection();
-    let (user_id, wallet_id) = create_user(conn);
+    let User { id: user_id, wallet_id, .. } = user(conn, "trader");
 
     for _ in 0..10 {
         let mut new_trade = gen_rand_trade(user_id.clone(), wallet_id.clone());
-        Trade::create(conn, &mut new_trade).unwrap();
+        Trade::create(conn, &mut new_trade).unwrap().0.unwrap();
     }
     
-    let _result = Trade::profit_loss(conn, "2022-01-01".to_string(), "2023-01-08".to_string(), user_id.clone(), None, None);
-    assert!(_result.len() > 0);
+    let _result = Trade::profit_loss(conn, "2022-01-01".to_string(), "2023-01-08".to_string(), user_id.clone(), None, None, &[]).unwrap();
+    assert!(!_result.is_empty());
 }
 
 #[test]
 fn cumulative_fees_by_asset() {
     let conn = &mut get_connection();
-    let (user_id, wallet_id) = create_user(conn);
+    let User { id: user_id, wallet_id, .. } = user(conn, "trader");
 
-    for _ in 0..10 {
+    for index in 0..10 {
         let mut new_trade = gen_rand_trade(user_id.clone(), wallet_id.clone());
-        Trade::create(conn, &mut new_trade).unwrap();
+        // At least one trade matches the filter, whatever the others are.
+        if index == 0 {
+            new_trade.asset = "ETH".to_string();
+        }
+        Trade::create(conn, &mut new_trade).unwrap().0.unwrap();
     }
     
-    let _result = Trade::profit_loss(conn, "2022-01-01".to_string(), "2023-01-08".to_string(), user_id.clone(), Some("ETH".to_string()), None);
-    assert!(_result.len() > 0);
+    let _result = Trade::profit_loss(conn, "2022-01-01".to_string(), "2023-01-08".to_string(), user_id.clone(), Some("ETH".to_string()), None, &[]).unwrap();
+    assert!(!_result.is_empty());
 }
 
 #[test]
 fn cumulative_fees_by_trade_type() {
     let conn = &mut get_connection();
-    let (user_id, wallet_id) = create_user(conn);
+    let User { id: user_id, wallet_id, .. } = user(conn, "trader");
 
-    for _ in 0..10 {
+    for index in 0..10 {
         let mut new_trade = gen_rand_trade(user_id.clone(), wallet_id.clone());
-        Trade::create(conn, &mut new_trade).unwrap();
+        // At least one trade matches the filter, whatever the others are.
+        if index == 0 {
+            new_trade.trade_type = "LimitBuy".to_string();
+        }
+        Trade::create(conn, &mut new_trade).unwrap().0.unwrap();
     }
     
-    let _result = Trade::profit_loss(conn, "2022-01-01".to_string(), "2023-01-08".to_string(), user_id.clone(), None, Some("LimitBuy".to_string()));
-    assert!(_result.len() > 0);
+    let _result = Trade::profit_loss(conn, "2022-01-01".to_string(), "2023-01-08".to_string(), user_id.clone(), None, Some("LimitBuy".to_string()), &[]).unwrap();
+    assert!(!_result.is_empty());
 }
 
 #[test]
 fn test_profit_loss_with_asset() {
     let conn = &mut get_connection();
-    let (user_id, wallet_id) = create_user(conn);
+    let User { id: user_id, wallet_id, .. } = user(conn, "trader");
     
     let mut expected_profit_value_for_asset = 0.0;
     let mut expected_loss_value_for_asset = 0.0;
@@ -147,7 +139,7 @@ fn test_profit_loss_with_asset() {
     for _ in 0..5 {
         let mut new_trade = gen_rand_trade(user_id.clone(), wallet_id.clone());
         new_trade.asset = "ETH".to_string();
-        let trade = Trade::create(conn, &mut new_trade).unwrap();
+        let trade = Trade::create(conn, &mut new_trade).unwrap().0.unwrap();
         let pnl = trade.calculate_trade_pnl();
         if pnl > 0.0 {
             expected_profit_value_for_asset += pnl;
@@ -163,7 +155,7 @@ fn test_profit_loss_with_asset() {
     for _ in 0..3 {
         let mut new_trade = gen_rand_trade(user_id.clone(), wallet_id.clone());
         new_trade.asset = "XRP".to_string();
-        let trade = Trade::create(conn, &mut new_trade).unwrap();
+        let trade = Trade::create(conn, &mut new_trade).unwrap().0.unwrap();
         let pnl = trade.calculate_trade_pnl();
         if pnl > 0.0 {
             expected_profit_value_for_other_asset += pnl;
@@ -172,7 +164,7 @@ fn test_profit_loss_with_asset() {
         }
     }
     
-    let result = Trade::profit_loss(conn, "2022-01-01".to_string(), "2023-01-08".to_string(), user_id.clone(), Some("ETH".to_string()), None);
+    let result = Trade::profit_loss(conn, "2022-01-01".to_string(), "2023-01-08".to_string(), user_id.clone(), Some("ETH".to_string()), None, &[]).unwrap();
     
     assert!(!result.is_empty());
 
@@ -183,11 +175,11 @@ fn test_profit_loss_with_asset() {
         loss += trade.loss;
     }
 
-    assert_eq!(profit, expected_profit_value_for_asset.round());
-    assert_eq!(loss, expected_loss_value_for_asset.round());
+    assert_daily_total(profit, expected_profit_value_for_asset, result.len());
+    assert_daily_total(loss, expected_loss_value_for_asset, result.len());
     
 
-    let result = Trade::profit_loss(conn, "2022-01-01".to_string(), "2023-01-08".to_string(), user_id.clone(), Some("XRP".to_string()), None);
+    let result = Trade::profit_loss(conn, "2022-01-01".to_string(), "2023-01-08".to_string(), user_id.clone(), Some("XRP".to_string()), None, &[]).unwrap();
     
     let mut profit = 0.0;
     let mut loss = 0.0;
@@ -199,15 +191,15 @@ fn test_profit_loss_with_asset() {
     assert!(!result.is_empty());
 
     // Example: Assert the profit and loss values for the first entry (you should adjust these values)
-    assert_eq!(profit, expected_profit_value_for_other_asset.round());
-    assert_eq!(loss, expected_loss_value_for_other_asset.round());
+    assert_daily_total(profit, expected_profit_value_for_other_asset, result.len());
+    assert_daily_total(loss, expected_loss_value_for_other_asset, result.len());
 
 }
 
 #[test]
 fn test_profit_loss_with_tradetype() {
     let conn = &mut get_connection();
-    let (user_id, wallet_id) = create_user(conn);
+    let User { id: user_id, wallet_id, .. } = user(conn, "trader");
     
     let mut expected_profit_value_for_trade_type = 0.0;
     let mut expected_loss_value_for_trade_type = 0.0;
@@ -215,7 +207,7 @@ fn test_profit_loss_with_tradetype() {
     for _ in 0..5 {
         let mut new_trade = gen_rand_trade(user_id.clone(), wallet_id.clone());
         new_trade.trade_type = "LimitBuy".to_string();
-        let trade = Trade::create(conn, &mut new_trade).unwrap();
+        let trade = Trade::create(conn, &mut new_trade).unwrap().0.unwrap();
         let pnl = trade.calculate_trade_pnl();
         if pnl > 0.0 {
             expected_profit_value_for_trade_type += pnl;
@@ -224,7 +216,7 @@ fn test_profit_loss_with_tradetype() {
         }
     }
     
-    let result = Trade::profit_loss(conn, "2022-01-01".to_string(), "2023-01-08".to_string(), user_id.clone(), None, Some("LimitBuy".to_string()));
+    let result = Trade::profit_loss(conn, "2022-01-01".to_string(), "2023-01-08".to_string(), user_id.clone(), None, Some("LimitBuy".to_string()), &[]).unwrap();
     
     assert!(!result.is_empty());
 
@@ -235,14 +227,14 @@ fn test_profit_loss_with_tradetype() {
         loss += trade.loss;
     }
 
-    assert_eq!(profit, expected_profit_value_for_trade_type.round());
-    assert_eq!(loss, expected_loss_value_for_trade_type.round());
+    assert_daily_total(profit, expected_profit_value_for_trade_type, result.len());
+    assert_daily_total(loss, expected_loss_value_for_trade_type, result.len());
 }
 
 #[test]
 fn test_profit_loss_without_asset_and_tradetype() {
     let conn = &mut get_connection();
-    let (user_id, wallet_id) = create_user(conn);
+    let User { id: user_id, wallet_id, .. } = user(conn, "trader");
     
     let mut expected_profit_value = 0.0;
     let mut expected_loss_value = 0.0;
@@ -250,7 +242,7 @@ fn test_profit_loss_without_asset_and_tradetype() {
     for _ in 0..5 {
         let mut new_trade = gen_rand_trade(user_id.clone(), wallet_id.clone());
         
-        let trade = Trade::create(conn, &mut new_trade).unwrap();
+        let trade = Trade::create(conn, &mut new_trade).unwrap().0.unwrap();
         let pnl = trade.calculate_trade_pnl();
         if pnl > 0.0 {
             expected_profit_value += pnl;
@@ -259,7 +251,7 @@ fn test_profit_loss_without_asset_and_tradetype() {
         }
     }
     
-    let result = Trade::profit_loss(conn, "2022-01-01".to_string(), "2023-01-08".to_string(), user_id.clone(), None, None);
+    let result = Trade::profit_loss(conn, "2022-01-01".to_string(), "2023-01-08".to_string(), user_id.clone(), None, None, &[]).unwrap();
     
     assert!(!result.is_empty());
 
@@ -270,27 +262,27 @@ fn test_profit_loss_without_asset_and_tradetype() {
         loss += trade.loss;
     }
 
-    assert_eq!(profit, expected_profit_value.round());
-    assert_eq!(loss, expected_loss_value.round());
+    assert_daily_total(profit, expected_profit_value, result.len());
+    assert_daily_total(loss, expected_loss_value, result.len());
 }
 
 #[test]
     fn test_get_slippage_bt_dates() {
         let conn = &mut get_connection();
-        let (user_id, wallet_id) = create_user(conn);
+        let User { id: user_id, wallet_id, .. } = user(conn, "trader");
         
         let mut expected_total_slippage = 0.0;
         let mut expected_total_slippage_cost_percent = 0.0;
         let mut trades = 0;
         for _ in 0..5 {
             let mut new_trade = gen_rand_trade(user_id.clone(), wallet_id.clone());    
-            let (slippage, slippage_cost_percent) = Trade::create(conn, &mut new_trade).unwrap().calculate_slippage();
+            let (slippage, slippage_cost_percent) = Trade::create(conn, &mut new_trade).unwrap().0.unwrap().calculate_slippage();
             expected_total_slippage += slippage;
             expected_total_slippage_cost_percent += slippage_cost_percent;
             trades += 1;
         }        
         
-        let result = Trade::get_slippage_bt_dates(conn, "2022-01-01".to_string(), "2023-01-08".to_string(), user_id.clone());
+        let result = Trade::get_slippage_bt_dates(conn, "2022-01-01".to_string(), "2023-01-08".to_string(), user_id.clone(), &[]).unwrap();
         
         let expected_average_slippage = expected_total_slippage / trades as f32;
         let expected_average_slippage_cost_percent = expected_total_slippage_cost_percent / trades as f32;
@@ -301,4 +293,443 @@ fn test_profit_loss_without_asset_and_tradetype() {
         assert_eq!(result.average_slippage, expected_average_slippage.round());
         assert_eq!(result.total_slippage_cost_percent, expected_total_slippage_cost_percent.round());
         assert_eq!(result.average_slippage_cost_percent, expected_average_slippage_cost_percent.round());
-    }
\ No newline at end of file
+    }
+#[test]
+fn test_execution_quality_by_asset() {
+    let conn = &mut get_connection();
+    let User { id: user_id, wallet_id, .. } = user(conn, "trader");
+
+    let mut slippages: Vec<(String, f32)> = Vec::new();
+    for _ in 0..20 {
+        let mut new_trade = gen_rand_trade(user_id.clone(), wallet_id.clone());
+        let trade = Trade::create(conn, &mut new_trade).unwrap().0.unwrap();
+        slippages.push((trade.asset.clone(), trade.calculate_slippage().0));
+    }
+
+    let result = Trade::execution_quality(conn, "2022-01-01".to_string(), "2023-01-08".to_string(), user_id, &[]).unwrap();
+
+    assert_eq!(result.iter().map(|quality| quality.trades).sum::<usize>(), 20);
+    for quality in result {
+        let asset_slippages: Vec<f32> = slippages.iter().filter(|(asset, _)| *asset == quality.asset).map(|(_, slippage)| *slippage).collect();
+        let min = asset_slippages.iter().cloned().fold(f32::INFINITY, f32::min);
+        let max = asset_slippages.iter().cloned().fold(f32::NEG_INFINITY, f32::max);
+        let (p50, p99) = (quality.slippage_p50.unwrap(), quality.slippage_p99.unwrap());
+
+        assert_eq!(quality.trades, asset_slippages.len());
+        assert!(min <= p50 && p50 <= p99 && p99 <= max);
+    }
+}
+
+#[test]
+fn test_search_filters_and_paginates() {
+    let conn = &mut get_connection();
+    let User { id: user_id, wallet_id, .. } = user(conn, "trader");
+    let User { id: other_user_id, wallet_id: other_wallet_id, .. } = user(conn, "other_user");
+
+    let mut trades = Vec::new();
+    for _ in 0..12 {
+        let mut new_trade = gen_rand_trade(user_id.clone(), wallet_id.clone());
+        trades.push(Trade::create(conn, &mut new_trade).unwrap().0.unwrap());
+    }
+    let mut other_trade = gen_rand_trade(other_user_id, other_wallet_id);
+    Trade::create(conn, &mut other_trade).unwrap();
+
+    let filter = TradeFilter { user_id: Some(user_id.clone()), ..Default::default() };
+    assert_eq!(Trade::count(conn, &filter).unwrap(), 12);
+
+    let first_page = Trade::search(conn, &filter, TradeSort::default(), 5, 0).unwrap();
+    let last_page = Trade::search(conn, &filter, TradeSort::default(), 5, 10).unwrap();
+    assert_eq!(first_page.len(), 5);
+    assert_eq!(last_page.len(), 2);
+    assert!(first_page.windows(2).all(|pair| pair[0].created_at >= pair[1].created_at));
+    assert!(first_page.iter().all(|trade| trade.user_id == user_id));
+
+    let eth = TradeFilter { asset: Some("ETH".to_string()), chain: Some("Arbitrum".to_string()), ..filter.clone() };
+    let expected = trades.iter().filter(|trade| trade.asset == "ETH" && trade.chain == "Arbitrum").count();
+    assert_eq!(Trade::search(conn, &eth, TradeSort::default(), 100, 0).unwrap().len(), expected);
+
+    let cutoff = trades[0].created_at;
+    let since = TradeFilter { start_date: Some(cutoff.format("%Y-%m-%d %H:%M:%S%.f").to_string()), ..filter };
+    let expected = trades.iter().filter(|trade| trade.created_at >= cutoff).count() as i64;
+    assert_eq!(Trade::count(conn, &since).unwrap(), expected);
+}
+
+#[test]
+fn test_source_filters_listing_and_aggregates() {
+    let conn = &mut get_connection();
+    let User { id: user_id, wallet_id, .. } = user(conn, "trader");
+
+    let mut imported = Vec::new();
+    for (index, source) in ["manual", "import:binance", "import:kraken", "simulation"].iter().enumerate() {
+        let mut new_trade = gen_rand_trade(user_id.clone(), wallet_id.clone());
+        new_trade.source = source.to_string();
+        new_trade.created_at = chrono::NaiveDate::from_ymd_opt(2022, 6, 1 + index as u32).unwrap().and_hms_opt(12, 0, 0).unwrap();
+        let trade = Trade::create(conn, &mut new_trade).unwrap().0.unwrap();
+        if source.starts_with("import:") {
+            imported.push(trade);
+        }
+    }
+    let mut invalid = gen_rand_trade(user_id.clone(), wallet_id.clone());
+    invalid.source = "robot".to_string();
+    assert_eq!(Trade::create(conn, &mut invalid).unwrap().1, Some("Invalid trade source".to_string()));
+
+    let filter = TradeFilter { user_id: Some(user_id.clone()), source: Some("import".to_string()), ..Default::default() };
+    assert_eq!(Trade::count(conn, &filter).unwrap(), 2);
+    let binance = TradeFilter { source: Some("import:binance".to_string()), ..filter };
+    assert_eq!(Trade::search(conn, &binance, TradeSort::default(), 10, 0).unwrap()[0].id, imported[0].id);
+
+    let others = Trade::other_sources(conn, "2022-01-01".to_string(), "2023-01-01".to_string(), user_id.clone(), "import".to_string()).unwrap();
+    assert_eq!(others.len(), 2);
+    let fees = Trade::cumulative_fees(conn, "2022-01-01".to_string(), "2023-01-01".to_string(), user_id, &others).unwrap();
+    let expected: f32 = imported.iter().map(|trade| trade.execution_fee + trade.transaction_fee).sum();
+    assert_eq!(fees.cumulative_fees, expected.round());
+}
+
+#[test]
+fn test_clusters_partition_trades() {
+    let conn = &mut get_connection();
+    let User { id: user_id, wallet_id, .. } = user(conn, "trader");
+
+    for _ in 0..15 {
+        let mut new_trade = gen_rand_trade(user_id.clone(), wallet_id.clone());
+        Trade::create(conn, &mut new_trade).unwrap();
+    }
+
+    let clusters = Trade::clusters(conn, "2022-01-01".to_string(), "2023-01-08".to_string(), user_id.clone(), 3).unwrap();
+
+    assert!(!clusters.is_empty() && clusters.len() <= 3);
+    assert_eq!(clusters.iter().map(|cluster| cluster.trades).sum::<usize>(), 15);
+    for cluster in &clusters {
+        assert_eq!(cluster.trade_ids.len(), cluster.trades);
+        assert!((0.0..=1.0).contains(&cluster.win_rate));
+        assert!(cluster.typical_hour < 24);
+        assert!(cluster.label.ends_with(" trades"));
+    }
+
+    assert!(Trade::clusters(conn, "2022-01-01".to_string(), "2023-01-08".to_string(), "missing".to_string(), 3).unwrap().is_empty());
+}
+
+#[test]
+fn test_outliers_are_excluded_from_aggregates() {
+    let conn = &mut get_connection();
+    let User { id: user_id, wallet_id, .. } = user(conn, "trader");
+
+    for _ in 0..20 {
+        let mut new_trade = gen_rand_trade(user_id.clone(), wallet_id.clone());
+        Trade::create(conn, &mut new_trade).unwrap();
+    }
+    let mut fat_finger = gen_rand_trade(user_id.clone(), wallet_id.clone());
+    fat_finger.before_price = 100.0;
+    fat_finger.execution_price = 100.0;
+    fat_finger.final_price = 1_000.0;
+    fat_finger.traded_amount = 100_000.0;
+    fat_finger.execution_fee = 5_000.0;
+    // Fund a fat-fingered buy of 100,000 units.
+    WalletTransaction::deposit(conn, wallet_id.clone(), 10_000_000.0, None).unwrap();
+    let fat_finger = Trade::create(conn, &mut fat_finger).unwrap().0.unwrap();
+
+    let excluded = Trade::outliers(conn, "2022-01-01".to_string(), "2023-01-08".to_string(), user_id.clone(), 2.0).unwrap();
+    assert!(excluded.contains(&fat_finger.id));
+
+    let all = Trade::cumulative_fees(conn, "2022-01-01".to_string(), "2023-01-08".to_string(), user_id.clone(), &[]).unwrap();
+    let filtered = Trade::cumulative_fees(conn, "2022-01-01".to_string(), "2023-01-08".to_string(), user_id.clone(), std::slice::from_ref(&fat_finger.id)).unwrap();
+    assert!(all.cumulative_fees - filtered.cumulative_fees >= 5_000.0);
+
+    let quality = Trade::execution_quality(conn, "2022-01-01".to_string(), "2023-01-08".to_string(), user_id, &excluded).unwrap();
+    assert_eq!(quality.iter().map(|quality| quality.trades).sum::<usize>(), 21 - excluded.len());
+}
+
+#[test]
+fn test_export_pages_cover_every_trade_once() {
+    let conn = &mut get_connection();
+    let User { id: user_id, wallet_id, .. } = user(conn, "trader");
+
+    let created_at = chrono::NaiveDate::from_ymd_opt(2022, 6, 1).unwrap().and_hms_opt(12, 0, 0).unwrap();
+    let mut ids = Vec::new();
+    for index in 0..7 {
+        let mut new_trade = gen_rand_trade(user_id.clone(), wallet_id.clone());
+        // Pairs of trades share a timestamp, so pages must also continue by ID.
+        new_trade.created_at = created_at + chrono::Duration::hours(index / 2);
+        ids.push(Trade::create(conn, &mut new_trade).unwrap().0.unwrap().id);
+    }
+
+    let mut exported = Vec::new();
+    let mut after = None;
+    loop {
+        let page = Trade::export_page(conn, &user_id, after.as_ref(), 3).unwrap();
+        assert!(page.len() <= 3);
+        let Some(last) = page.last() else { break };
+        after = Some((last.created_at, last.id.clone()));
+        exported.extend(page.into_iter().map(|trade| trade.id));
+    }
+
+    let mut sorted = exported.clone();
+    sorted.sort();
+    sorted.dedup();
+    ids.sort();
+    assert_eq!(sorted, ids);
+    assert_eq!(exported.len(), 7);
+    assert!(Trade::export_page(conn, "someone_else", None, 3).unwrap().is_empty());
+}
+
+#[test]
+fn test_cost_basis_closes_oldest_lots_first() {
+    let mut book = CostBasis::default();
+    assert_eq!(book.fill(2.0, 100.0), 0.0);
+    assert_eq!(book.fill(2.0, 200.0), 0.0);
+
+    // Sells 3: both lots at 100, then one at 200.
+    assert_eq!(book.fill(-3.0, 250.0), 2.0 * 150.0 + 50.0);
+    assert_eq!(book.lots().iter().copied().collect::<Vec<_>>(), vec![Lot { quantity: 1.0, price: 200.0 }]);
+
+    // Selling past the holding opens a short at the sell price.
+    assert_eq!(book.fill(-2.0, 300.0), 100.0);
+    assert_eq!(book.lots().iter().copied().collect::<Vec<_>>(), vec![Lot { quantity: -1.0, price: 300.0 }]);
+    book.mark = 280.0;
+    assert_eq!(book.unrealized(), 20.0);
+    assert_eq!(book.fill(1.0, 250.0), 50.0);
+    assert!(book.lots().is_empty());
+}
+
+#[test]
+fn test_profit_loss_fifo_splits_realized_and_unrealized() {
+    let conn = &mut get_connection();
+    let User { id: user_id, wallet_id, .. } = user(conn, "trader");
+
+    let day = |day: u32| chrono::NaiveDate::from_ymd_opt(2022, 6, day).unwrap().and_hms_opt(12, 0, 0).unwrap();
+    for (created_at, trade_type, amount, price, asset) in [
+        (day(1), "MarketBuy", 2.0, 100.0, "ETH"),
+        (day(2), "MarketBuy", 2.0, 200.0, "ETH"),
+        (day(3), "MarketSell", 3.0, 250.0, "ETH"),
+        (day(3), "MarketBuy", 1.0, 50.0, "BTC"),
+    ] {
+        let mut new_trade = gen_rand_trade(user_id.clone(), wallet_id.clone());
+        new_trade.created_at = created_at;
+        new_trade.trade_type = trade_type.to_string();
+        new_trade.asset = asset.to_string();
+        new_trade.traded_amount = amount;
+        new_trade.execution_price = price;
+        new_trade.final_price = price;
+        Trade::create(conn, &mut new_trade).unwrap().0.unwrap();
+    }
+
+    // The range starts after the first buy, whose lot is still closed at its cost.
+    let days = Trade::profit_loss_fifo(conn, "2022-06-02".to_string(), "2022-06-30".to_string(), user_id.clone(), None, &[]).unwrap();
+    let rows: Vec<_> = days.iter().map(|day| (day.date.as_str(), day.asset.as_str(), day.realized, day.unrealized, day.open_quantity)).collect();
+    assert_eq!(rows, vec![
+        ("2022-06-02", "ETH", 0.0, 200.0, 4.0),
+        ("2022-06-03", "BTC", 0.0, 0.0, 1.0),
+        ("2022-06-03", "ETH", 350.0, 50.0, 1.0),
+    ]);
+
+    let eth = Trade::profit_loss_fifo(conn, "2022-06-01".to_string(), "2022-06-30".to_string(), user_id, Some("ETH".to_string()), &[]).unwrap();
+    assert_eq!(eth.len(), 3);
+    assert!(eth.iter().all(|day| day.asset == "ETH"));
+}
+
+#[test]
+fn test_profit_loss_by_asset_splits_each_day() {
+    let conn = &mut get_connection();
+    let User { id: user_id, wallet_id, .. } = user(conn, "trader");
+
+    let day = |day: u32| chrono::NaiveDate::from_ymd_opt(2022, 7, day).unwrap().and_hms_opt(12, 0, 0).unwrap();
+    for (created_at, asset, amount, execution_price, final_price) in [
+        (day(1), "ETH", 2.0, 100.0, 110.0),
+        (day(1), "BTC", 1.0, 50.0, 40.0),
+        (day(1), "ETH", 1.0, 100.0, 95.0),
+        (day(2), "BTC", 1.0, 10.0, 13.0),
+    ] {
+        let mut new_trade = gen_rand_trade(user_id.clone(), wallet_id.clone());
+        new_trade.created_at = created_at;
+        new_trade.trade_type = "MarketBuy".to_string();
+        new_trade.asset = asset.to_string();
+        new_trade.traded_amount = amount;
+        new_trade.execution_price = execution_price;
+        new_trade.final_price = final_price;
+        new_trade.execution_fee = 0.0;
+        new_trade.transaction_fee = 0.0;
+        Trade::create(conn, &mut new_trade).unwrap().0.unwrap();
+    }
+
+    let (start, end) = ("2022-07-01".to_string(), "2022-07-31".to_string());
+    let days = Trade::profit_loss_by_asset(conn, start.clone(), end.clone(), user_id.clone(), None, None, &[]).unwrap();
+    let asset = |asset: &str, profit, loss| AssetProfitLoss { asset: asset.to_string(), profit, loss };
+    assert_eq!(days.len(), 2);
+    assert_eq!((days[0].date.as_str(), days[0].profit, days[0].loss), ("2022-07-01", 20.0, -15.0));
+    assert_eq!(days[0].assets, vec![asset("BTC", 0.0, -10.0), asset("ETH", 20.0, -5.0)]);
+    assert_eq!(days[1].assets, vec![asset("BTC", 3.0, 0.0)]);
+
+    let totals = Trade::profit_loss(conn, start, end, user_id, None, None, &[]).unwrap();
+    assert!(totals.iter().zip(&days).all(|(total, day)| (total.date.as_str(), total.profit, total.loss) == (day.date.as_str(), day.profit, day.loss)));
+}
+
+#[test]
+fn test_fee_breakdown_groups_by_asset_chain_and_type() {
+    let conn = &mut get_connection();
+    let User { id: user_id, wallet_id, .. } = user(conn, "trader");
+
+    let mut created = Vec::new();
+    for (asset, chain, trade_type) in [("ETH", "Ethereum", "MarketBuy"), ("ETH", "Ethereum", "MarketBuy"), ("ETH", "Arbitrum", "MarketBuy"), ("BTC", "Ethereum", "LimitSell")] {
+        let mut new_trade = gen_rand_trade(user_id.clone(), wallet_id.clone());
+        new_trade.asset = asset.to_string();
+        new_trade.chain = chain.to_string();
+        new_trade.trade_type = trade_type.to_string();
+        created.push(Trade::create(conn, &mut new_trade).unwrap().0.unwrap());
+    }
+
+    let breakdown = Trade::fee_breakdown(conn, "2022-01-01".to_string(), "2023-01-01".to_string(), user_id.clone(), &[]).unwrap();
+    assert_eq!(breakdown.len(), 3);
+    assert!(breakdown.windows(2).all(|pair| pair[0].total_fees >= pair[1].total_fees));
+
+    let pair = breakdown.iter().find(|group| group.asset == "ETH" && group.chain == "Ethereum").unwrap();
+    assert_eq!(pair.trades, 2);
+    assert_eq!(pair.execution_fees, created[0].execution_fee + created[1].execution_fee);
+    assert_eq!(pair.transaction_fees, created[0].transaction_fee + created[1].transaction_fee);
+
+    let total: f32 = breakdown.iter().map(|group| group.total_fees).sum();
+    let cumulative = Trade::cumulative_fees(conn, "2022-01-01".to_string(), "2023-01-01".to_string(), user_id, &[]).unwrap();
+    assert_eq!(total.round(), cumulative.cumulative_fees);
+}
+
+#[test]
+fn test_holdings_conflict_replays_wallet_history() {
+    let conn = &mut get_connection();
+    let User { id: user_id, wallet_id, .. } = user(conn, "trader");
+
+    let day = |day: u32| chrono::NaiveDate::from_ymd_opt(2022, 6, day).unwrap().and_hms_opt(12, 0, 0).unwrap();
+    let trade = |created_at, trade_type: &str, amount| {
+        let mut new_trade = gen_rand_trade(user_id.clone(), wallet_id.clone());
+        new_trade.created_at = created_at;
+        new_trade.trade_type = trade_type.to_string();
+        new_trade.asset = "ETH".to_string();
+        new_trade.traded_amount = amount;
+        new_trade.id = String::new();
+        new_trade
+    };
+    Trade::create(conn, &mut trade(day(1), "MarketBuy", 2.0)).unwrap().0.unwrap();
+    let later_sell = Trade::create(conn, &mut trade(day(5), "MarketSell", 2.0)).unwrap().0.unwrap();
+
+    assert_eq!(
+        Trade::holdings_conflict(conn, &trade(day(3), "MarketSell", 1.0)).unwrap(),
+        Some(format!("The wallet held 1 ETH on 2022-06-05 12:00:00, not enough for the sell of 2 in trade {}", later_sell.id))
+    );
+    assert_eq!(
+        Trade::holdings_conflict(conn, &trade(day(6), "LimitSell", 0.5)).unwrap(),
+        Some("The wallet held 0 ETH on 2022-06-06 12:00:00, not enough for the sell of 0.5 in this trade".to_string())
+    );
+    assert_eq!(Trade::holdings_conflict(conn, &trade(day(3), "MarketBuy", 1.0)).unwrap(), None);
+
+    Trade::create(conn, &mut trade(day(2), "MarketBuy", 1.0)).unwrap().0.unwrap();
+    assert_eq!(Trade::holdings_conflict(conn, &trade(day(3), "MarketSell", 1.0)).unwrap(), None);
+}
+
+#[test]
+fn metadata_filters_match_every_given_pair() {
+    let conn = &mut get_connection();
+    let User { id: user_id, wallet_id, .. } = user(conn, "trader");
+    let tags = [("8123", "grid-bot"), ("8124", "grid-bot"), ("8125", "manual-hedge")];
+    for (order_id, client_tag) in tags {
+        let mut trade = gen_rand_trade(user_id.clone(), wallet_id.clone());
+        trade.metadata = Some(serde_json::json!({"exchange_order_id": order_id, "client_tag": client_tag}).to_string());
+        Trade::create(conn, &mut trade).unwrap().0.unwrap();
+    }
+    Trade::create(conn, &mut gen_rand_trade(user_id.clone(), wallet_id)).unwrap().0.unwrap();
+
+    let filter = |pairs: &[(&str, &str)]| TradeFilter {
+        user_id: Some(user_id.clone()),
+        metadata: pairs.iter().map(|(key, value)| (key.to_string(), value.to_string())).collect(),
+        ..Default::default()
+    };
+    assert_eq!(Trade::count(conn, &filter(&[("client_tag", "grid-bot")])).unwrap(), 2);
+    assert_eq!(Trade::count(conn, &filter(&[("client_tag", "grid-bot"), ("exchange_order_id", "8124")])).unwrap(), 1);
+    assert_eq!(Trade::count(conn, &filter(&[("exchange_order_id", "' OR 1=1 --")])).unwrap(), 0);
+    assert_eq!(Trade::count(conn, &filter(&[])).unwrap(), 4);
+}
+
+#[test]
+fn search_sorts_by_the_requested_column() {
+    let conn = &mut get_connection();
+    let User { id: user_id, wallet_id, .. } = user(conn, "trader");
+    for _ in 0..8 {
+        Trade::create(conn, &mut gen_rand_trade(user_id.clone(), wallet_id.clone())).unwrap().0.unwrap();
+    }
+    let filter = TradeFilter { user_id: Some(user_id), ..Default::default() };
+    let mut sorted = |sort: Option<&str>, order: Option<&str>| Trade::search(conn, &filter, TradeSort::parse(sort, order).unwrap(), 100, 0).unwrap();
+
+    let amounts: Vec<f32> = sorted(Some("amount"), Some("asc")).iter().map(|trade| trade.amount).collect();
+    assert!(amounts.windows(2).all(|pair| pair[0] <= pair[1]));
+    let assets: Vec<String> = sorted(Some("asset"), None).into_iter().map(|trade| trade.asset).collect();
+    assert!(assets.windows(2).all(|pair| pair[0] >= pair[1]));
+    let created: Vec<_> = sorted(None, None).iter().map(|trade| trade.created_at).collect();
+    assert!(created.windows(2).all(|pair| pair[0] >= pair[1]));
+
+    let pnl = |trade: &Trade| {
+        let gain = if trade.trade_type.ends_with("Buy") { trade.final_price - trade.execution_price } else { trade.final_price - trade.before_price };
+        gain * trade.traded_amount - trade.execution_fee - trade.transaction_fee
+    };
+    let pnls: Vec<f32> = sorted(Some("pnl"), Some("desc")).iter().map(pnl).collect();
+    assert!(pnls.windows(2).all(|pair| pair[0] >= pair[1] - 0.01));
+
+    assert!(TradeSort::parse(Some("amount; DROP TABLE trades"), None).is_err());
+    assert!(TradeSort::parse(None, Some("sideways")).is_err());
+}
+
+#[test]
+fn platform_activity_aggregates_every_trader_per_day_asset_and_chain() {
+    let conn = &mut get_connection();
+    let User { id: first, wallet_id: first_wallet, .. } = user(conn, "trader");
+    let User { id: second, wallet_id: second_wallet, .. } = user(conn, "second");
+
+    let june = |day: u32| chrono::NaiveDate::from_ymd_opt(2022, 6, day).unwrap().and_hms_opt(12, 0, 0).unwrap();
+    let mut created = Vec::new();
+    for (user_id, wallet_id, day, asset, source) in [
+        (&first, &first_wallet, 1, "ETH", "manual"),
+        (&second, &second_wallet, 1, "ETH", "import:binance"),
+        (&first, &first_wallet, 1, "BTC", "manual"),
+        (&first, &first_wallet, 2, "ETH", "manual"),
+        (&second, &second_wallet, 1, "ETH", "simulation"),
+    ] {
+        let mut trade = gen_rand_trade(user_id.clone(), wallet_id.clone());
+        (trade.asset, trade.chain, trade.source, trade.created_at) = (asset.to_string(), "Ethereum".to_string(), source.to_string(), june(day));
+        created.push(Trade::create(conn, &mut trade).unwrap().0.unwrap());
+    }
+
+    let activity = Trade::platform_activity(conn, "2022-06-01".to_string(), "2022-06-30".to_string()).unwrap();
+    let groups: Vec<(&str, &str, usize, usize)> = activity.iter().map(|row| (row.date.as_str(), row.asset.as_str(), row.trades, row.traders)).collect();
+    assert_eq!(groups, vec![("2022-06-01", "BTC", 1, 1), ("2022-06-01", "ETH", 2, 2), ("2022-06-02", "ETH", 1, 1)]);
+
+    let eth = &activity[1];
+    let expected_volume: f32 = created[..2].iter().map(|trade| trade.traded_amount * trade.execution_price).sum();
+    let expected_fees: f32 = created[..2].iter().map(|trade| trade.execution_fee + trade.transaction_fee).sum();
+    assert!((eth.volume - expected_volume).abs() < 1e-2);
+    assert!((eth.fees - expected_fees).abs() < 1e-3);
+    assert!(eth.average_slippage.is_some());
+}
+
+#[test]
+fn totals_cover_every_trade_of_a_wallet() {
+    let conn = &mut get_connection();
+    let User { id: user_id, wallet_id, .. } = user(conn, "trader");
+    let other_user = user(conn, "other_user");
+
+    let mut trades = Vec::new();
+    for _ in 0..6 {
+        trades.push(Trade::create(conn, &mut gen_rand_trade(user_id.clone(), wallet_id.clone())).unwrap().0.unwrap());
+    }
+    Trade::create(conn, &mut gen_rand_trade(other_user.id, other_user.wallet_id)).unwrap();
+
+    let filter = TradeFilter { wallet_id: Some(wallet_id.clone()), ..Default::default() };
+    let page = Trade::search(conn, &filter, TradeSort::default(), 4, 0).unwrap();
+    assert_eq!(page.len(), 4);
+    assert!(page.iter().all(|trade| trade.wallet_id == wallet_id));
+
+    let totals = Trade::totals(conn, &filter).unwrap();
+    let volume: f32 = trades.iter().map(|trade| trade.traded_amount * trade.execution_price).sum();
+    let fees: f32 = trades.iter().map(|trade| trade.execution_fee + trade.transaction_fee).sum();
+    assert_eq!(totals.trades, 6);
+    assert!((totals.volume - volume).abs() <= volume.abs() * 1e-4);
+    assert!((totals.fees - fees).abs() <= fees.abs() * 1e-4);
+
+    let nothing = TradeFilter { start_date: Some("2999-01-01".to_string()), ..filter };
+    assert_eq!(Trade::totals(conn, &nothing).unwrap(), TradeTotals::default());
+}
@@ -0,0 +1,326 @@
+//! This module defines wallet transfers and the multi-signature approval flow that guards large transfers.
+//!
+//! A wallet can be configured with an `ApprovalPolicy`: transfers above `threshold` need `required_approvals`
+//! approvals (M) from the wallet's designated `Approver`s (N). Transfers at or below the threshold, or from wallets
+//! without a policy, are executed immediately. Larger transfers are stored as `Pending`, collect `TransferApproval`s,
+//! and are executed automatically once the quorum is met. The requester of a transfer never counts towards its
+//! quorum, even when they are one of the approvers.
+//!
+//! Executing a transfer withdraws its amount from the wallet balance, recorded in the wallet ledger as a withdrawal
+//! referencing the transfer (see `wallet_transaction`). A transfer that would overdraw the wallet is marked `Rejected`
+//! instead. Approvals and executions run in one transaction that claims the transfer while it is still `Pending`, so
+//! concurrent approvals cannot execute it twice.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use crate::models::transfer::{ApprovalPolicy, Transfer};
+//!
+//! // Require 2 of 3 approvers for transfers above 1000.
+//! ApprovalPolicy::set(&mut connection, "wallet_id".to_string(), 1000.0, 2, vec![alice, bob, carol]);
+//!
+//! // Request a transfer; it stays pending until approved.
+//! if let Ok((Some(transfer), None)) = Transfer::request(&mut connection, "wallet_id".to_string(), "user_id".to_string(), 5000.0) {
+//!     // Each approver signs off; the second approval executes the transfer.
+//!     Transfer::approve(&mut connection, transfer.id.clone(), alice);
+//!     Transfer::approve(&mut connection, transfer.id, bob);
+//! }
+//! ```
+//!
+//! # Note
+//! This module assumes the availability of a database connection (`SqliteConnection` in this case) for transfer data retrieval and manipulation.
+
+use uuid::Uuid;
+use serde::{Serialize, Deserialize};
+use diesel::prelude::*;
+
+use super::super::error::DbError;
+use super::super::retry::retry_on_busy;
+use super::super::schema::{
+    wallet_approval_policies, wallet_approvers, wallet_transfer_approvals, wallet_transfers,
+};
+use super::user::User;
+use super::wallet::Wallet;
+use super::wallet_transaction::{TransactionKind, WalletTransaction};
+
+pub const NOT_AN_APPROVER: &str = "User is not an approver for this wallet";
+
+#[derive(Debug, Deserialize, Serialize, Queryable, Insertable)]
+#[diesel(table_name = crate::db::schema::wallet_approval_policies)]
+pub struct ApprovalPolicy {
+    pub wallet_id: String,
+    pub threshold: f32,
+    pub required_approvals: i32,
+    pub created_at: chrono::NaiveDateTime,
+    pub updated_at: chrono::NaiveDateTime,
+}
+
+#[derive(Debug, Deserialize, Serialize, Queryable, Insertable)]
+#[diesel(table_name = crate::db::schema::wallet_approvers)]
+pub struct Approver {
+    pub id: String,
+    pub wallet_id: String,
+    pub user_id: String,
+    pub created_at: chrono::NaiveDateTime,
+}
+
+#[derive(Debug, Deserialize, Serialize, Queryable, Insertable)]
+#[diesel(table_name = crate::db::schema::wallet_transfers)]
+pub struct Transfer {
+    pub id: String,
+    pub wallet_id: String,
+    pub requested_by: String,
+    pub amount: f32,
+    pub status: String,
+    pub created_at: chrono::NaiveDateTime,
+    pub updated_at: chrono::NaiveDateTime,
+}
+
+#[derive(Debug, Deserialize, Serialize, Queryable, Insertable)]
+#[diesel(table_name = crate::db::schema::wallet_transfer_approvals)]
+pub struct TransferApproval {
+    pub id: String,
+    pub transfer_id: String,
+    pub user_id: String,
+    pub created_at: chrono::NaiveDateTime,
+}
+
+pub struct TransferStatus;
+
+impl TransferStatus {
+    pub const PENDING: &'static str = "Pending";
+    pub const EXECUTED: &'static str = "Executed";
+    pub const REJECTED: &'static str = "Rejected";
+}
+
+impl ApprovalPolicy {
+    pub fn find_by_wallet(conn: &mut SqliteConnection, wallet_id: String) -> Result<Option<Self>, DbError> {
+        Ok(wallet_approval_policies::table
+            .find(wallet_id)
+            .first::<ApprovalPolicy>(conn)
+            .optional()?)
+    }
+
+    pub fn set(conn: &mut SqliteConnection, wallet_id: String, threshold: f32, required_approvals: i32, mut approvers: Vec<String>) -> Result<(Option<Self>, Option<String>), DbError> {
+        approvers.sort();
+        approvers.dedup();
+
+        if !threshold.is_finite() || threshold < 0.0 {
+            return Ok((None, Some("Threshold must be a non-negative number".to_string())));
+        }
+
+        if required_approvals < 1 || required_approvals as usize > approvers.len() {
+            return Ok((None, Some("Required approvals must be between 1 and the number of approvers".to_string())));
+        }
+
+        if Wallet::find_by_id(conn, wallet_id.clone())?.is_none() {
+            return Ok((None, Some("Wallet does not exist".to_string())));
+        }
+
+        for user_id in &approvers {
+            if User::find_by_id(conn, user_id.clone())?.is_none() {
+                return Ok((None, Some("Approver does not exist".to_string())));
+            }
+        }
+
+        let now = chrono::Local::now().naive_local();
+        let policy = ApprovalPolicy {
+            wallet_id: wallet_id.clone(),
+            threshold,
+            required_approvals,
+            created_at: now,
+            updated_at: now,
+        };
+        let approvers: Vec<Approver> = approvers
+            .into_iter()
+            .map(|user_id| Approver {
+                id: Uuid::new_v4().as_hyphenated().to_string(),
+                wallet_id: wallet_id.clone(),
+                user_id,
+                created_at: now,
+            })
+            .collect();
+
+        retry_on_busy(|| {
+            conn.transaction(|conn| {
+                diesel::replace_into(wallet_approval_policies::table)
+                    .values(&policy)
+                    .execute(conn)?;
+                diesel::delete(wallet_approvers::table.filter(wallet_approvers::wallet_id.eq(wallet_id.clone())))
+                    .execute(conn)?;
+                diesel::insert_into(wallet_approvers::table)
+                    .values(&approvers)
+                    .execute(conn)
+            })
+        })?;
+
+        Ok((Self::find_by_wallet(conn, wallet_id)?, None))
+    }
+
+    pub fn requires_approval(&self, amount: f32) -> bool {
+        amount > self.threshold
+    }
+}
+
+impl Approver {
+    pub fn list_for_wallet(conn: &mut SqliteConnection, wallet_id: String) -> Result<Vec<Self>, DbError> {
+        Ok(wallet_approvers::table
+            .filter(wallet_approvers::wallet_id.eq(wallet_id))
+            .load::<Approver>(conn)?)
+    }
+}
+
+impl Transfer {
+    pub fn find_by_id(conn: &mut SqliteConnection, id: String) -> Result<Option<Self>, DbError> {
+        Ok(wallet_transfers::table
+            .find(id)
+            .first::<Transfer>(conn)
+            .optional()?)
+    }
+
+    pub fn approvals(conn: &mut SqliteConnection, id: String) -> Result<Vec<TransferApproval>, DbError> {
+        Ok(wallet_transfer_approvals::table
+            .filter(wallet_transfer_approvals::transfer_id.eq(id))
+            .load::<TransferApproval>(conn)?)
+    }
+
+    pub fn request(conn: &mut SqliteConnection, wallet_id: String, requested_by: String, amount: f32) -> Result<(Option<Self>, Option<String>), DbError> {
+        if !amount.is_finite() || amount <= 0.0 {
+            return Ok((None, Some("Amount must be a positive number".to_string())));
+        }
+
+        if Wallet::find_by_id(conn, wallet_id.clone())?.is_none() {
+            return Ok((None, Some("Wallet does not exist".to_string())));
+        }
+
+        if User::find_by_id(conn, requested_by.clone())?.is_none() {
+            return Ok((None, Some("User does not exist".to_string())));
+        }
+
+        let now = chrono::Local::now().naive_local();
+        let transfer = Transfer {
+            id: Uuid::new_v4().as_hyphenated().to_string(),
+            wallet_id: wallet_id.clone(),
+            requested_by,
+            amount,
+            status: TransferStatus::PENDING.to_string(),
+            created_at: now,
+            updated_at: now,
+        };
+
+        retry_on_busy(|| {
+            diesel::insert_into(wallet_transfers::table)
+                .values(&transfer)
+                .execute(conn)
+        })?;
+
+        let needs_approval = match ApprovalPolicy::find_by_wallet(conn, wallet_id)? {
+            Some(policy) => policy.requires_approval(amount),
+            None => false,
+        };
+        if !needs_approval {
+            retry_on_busy(|| conn.transaction(|conn| Self::execute(conn, &transfer.id)))?;
+        }
+
+        Ok((Self::find_by_id(conn, transfer.id)?, None))
+    }
+
+    pub fn approve(conn: &mut SqliteConnection, id: String, user_id: String) -> Result<(Option<Self>, Option<String>), DbError> {
+        // The checks, the approval and the execution share one transaction so concurrent approvals cannot both
+        // reach the quorum on a stale count.
+        let rejection = retry_on_busy(|| {
+            conn.transaction(|conn| {
+                let transfer = match wallet_transfers::table.find(id.clone()).first::<Transfer>(conn).optional()? {
+                    Some(transfer) => transfer,
+                    None => return Ok(Some("Transfer does not exist".to_string())),
+                };
+
+                if transfer.status != TransferStatus::PENDING {
+                    return Ok(Some("Transfer is not pending".to_string()));
+                }
+
+                let is_approver = wallet_approvers::table
+                    .filter(wallet_approvers::wallet_id.eq(transfer.wallet_id.clone()))
+                    .filter(wallet_approvers::user_id.eq(user_id.clone()))
+                    .count()
+                    .get_result::<i64>(conn)? > 0;
+                if !is_approver {
+                    return Ok(Some(NOT_AN_APPROVER.to_string()));
+                }
+
+                if transfer.requested_by == user_id {
+                    return Ok(Some("Transfers cannot be approved by their requester".to_string()));
+                }
+
+                let approvals = wallet_transfer_approvals::table
+                    .filter(wallet_transfer_approvals::transfer_id.eq(id.clone()))
+                    .select(wallet_transfer_approvals::user_id)
+                    .load::<String>(conn)?;
+                if approvals.contains(&user_id) {
+                    return Ok(Some("Transfer already approved by this user".to_string()));
+                }
+
+                diesel::insert_into(wallet_transfer_approvals::table)
+                    .values(&TransferApproval {
+                        id: Uuid::new_v4().as_hyphenated().to_string(),
+                        transfer_id: id.clone(),
+                        user_id: user_id.clone(),
+                        created_at: chrono::Local::now().naive_local(),
+                    })
+                    .execute(conn)?;
+
+                let required_approvals = wallet_approval_policies::table
+                    .find(transfer.wallet_id.clone())
+                    .first::<ApprovalPolicy>(conn)
+                    .optional()?
+                    .map(|policy| policy.required_approvals as usize)
+                    .unwrap_or(1);
+                if approvals.len() + 1 >= required_approvals {
+                    Self::execute(conn, &id)?;
+                }
+
+                Ok(None)
+            })
+        })?;
+
+        match rejection {
+            Some(error) => Ok((None, Some(error))),
+            None => Ok((Self::find_by_id(conn, id)?, None)),
+        }
+    }
+
+    /// Claims a pending transfer and withdraws its amount. Must run inside a transaction; a transfer that is no
+    /// longer pending is left untouched, so it is never debited twice.
+    fn execute(conn: &mut SqliteConnection, id: &str) -> QueryResult<()> {
+        let now = chrono::Local::now().naive_local();
+        let claimed = diesel::update(
+            wallet_transfers::table
+                .filter(wallet_transfers::id.eq(id))
+                .filter(wallet_transfers::status.eq(TransferStatus::PENDING)),
+        )
+        .set((
+            wallet_transfers::status.eq(TransferStatus::EXECUTED),
+            wallet_transfers::updated_at.eq(now),
+        ))
+        .execute(conn)?;
+        if claimed != 1 {
+            return Ok(());
+        }
+
+        let transfer = wallet_transfers::table.find(id).first::<Transfer>(conn)?;
+        let withdrawal = WalletTransaction::post(
+            conn,
+            &transfer.wallet_id,
+            TransactionKind::WITHDRAWAL,
+            -transfer.amount,
+            Some(transfer.id.clone()),
+        )?;
+        if withdrawal.is_none() {
+            diesel::update(wallet_transfers::table.find(id))
+                .set(wallet_transfers::status.eq(TransferStatus::REJECTED))
+                .execute(conn)?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,104 @@
+use diesel::SqliteConnection;
+
+use crate::db::fixtures::{test_connection, user};
+use super::transfer::{ApprovalPolicy, Transfer, TransferStatus};
+use super::wallet::Wallet;
+
+fn create_wallet(conn: &mut SqliteConnection, balance: f32) -> String {
+    let wallet = Wallet::create(conn).unwrap().unwrap();
+    Wallet::update_balance(conn, wallet.id.clone(), balance).unwrap();
+    wallet.id
+}
+
+fn balance(conn: &mut SqliteConnection, wallet_id: String) -> f32 {
+    Wallet::find_by_id(conn, wallet_id).unwrap().unwrap().balance
+}
+
+#[test]
+fn small_transfer_executes_immediately() {
+    let conn = &mut test_connection();
+    let wallet_id = create_wallet(conn, 100.0);
+    let approvers = vec![user(conn, "a").id, user(conn, "b").id];
+    let requester = user(conn, "requester").id;
+    ApprovalPolicy::set(conn, wallet_id.clone(), 50.0, 2, approvers).unwrap();
+
+    let (transfer, _err) = Transfer::request(conn, wallet_id.clone(), requester, 40.0).unwrap();
+
+    assert_eq!(transfer.unwrap().status, TransferStatus::EXECUTED);
+    assert_eq!(balance(conn, wallet_id), 60.0);
+}
+
+#[test]
+fn large_transfer_executes_after_quorum() {
+    let conn = &mut test_connection();
+    let wallet_id = create_wallet(conn, 100.0);
+    let approvers = vec![user(conn, "a").id, user(conn, "b").id, user(conn, "c").id];
+    let requester = user(conn, "requester").id;
+    ApprovalPolicy::set(conn, wallet_id.clone(), 50.0, 2, approvers.clone()).unwrap();
+
+    let (transfer, _err) = Transfer::request(conn, wallet_id.clone(), requester.clone(), 80.0).unwrap();
+    let transfer = transfer.unwrap();
+    assert_eq!(transfer.status, TransferStatus::PENDING);
+
+    let (_transfer, err) = Transfer::approve(conn, transfer.id.clone(), requester).unwrap();
+    assert!(err.is_some());
+
+    let (pending, _err) = Transfer::approve(conn, transfer.id.clone(), approvers[0].clone()).unwrap();
+    assert_eq!(pending.unwrap().status, TransferStatus::PENDING);
+    assert_eq!(balance(conn, wallet_id.clone()), 100.0);
+
+    let (_transfer, err) = Transfer::approve(conn, transfer.id.clone(), approvers[0].clone()).unwrap();
+    assert!(err.is_some());
+
+    let (executed, _err) = Transfer::approve(conn, transfer.id, approvers[1].clone()).unwrap();
+    assert_eq!(executed.unwrap().status, TransferStatus::EXECUTED);
+    assert_eq!(balance(conn, wallet_id), 20.0);
+}
+
+#[test]
+fn overdrawing_transfer_is_rejected() {
+    let conn = &mut test_connection();
+    let wallet_id = create_wallet(conn, 10.0);
+    let requester = user(conn, "requester").id;
+
+    let (transfer, _err) = Transfer::request(conn, wallet_id.clone(), requester, 20.0).unwrap();
+
+    assert_eq!(transfer.unwrap().status, TransferStatus::REJECTED);
+    assert_eq!(balance(conn, wallet_id), 10.0);
+}
+
+#[test]
+fn policy_requires_valid_quorum() {
+    let conn = &mut test_connection();
+    let wallet_id = create_wallet(conn, 0.0);
+    let approver = user(conn, "a").id;
+
+    let (policy, err) = ApprovalPolicy::set(conn, wallet_id, 50.0, 2, vec![approver.clone(), approver]).unwrap();
+
+    assert!(policy.is_none());
+    assert!(err.is_some());
+}
+
+#[test]
+fn requesters_cannot_approve_their_own_transfer() {
+    let conn = &mut test_connection();
+    let wallet_id = create_wallet(conn, 100.0);
+    let requester = user(conn, "requester").id;
+    let approver = user(conn, "a").id;
+    ApprovalPolicy::set(conn, wallet_id.clone(), 50.0, 1, vec![requester.clone(), approver.clone()]).unwrap();
+
+    let (transfer, _err) = Transfer::request(conn, wallet_id.clone(), requester.clone(), 80.0).unwrap();
+    let transfer = transfer.unwrap();
+
+    let (refused, err) = Transfer::approve(conn, transfer.id.clone(), requester).unwrap();
+    assert!(refused.is_none());
+    assert!(err.is_some());
+    assert_eq!(balance(conn, wallet_id.clone()), 100.0);
+
+    let (executed, _err) = Transfer::approve(conn, transfer.id.clone(), approver.clone()).unwrap();
+    assert_eq!(executed.unwrap().status, TransferStatus::EXECUTED);
+
+    let (_transfer, err) = Transfer::approve(conn, transfer.id, approver).unwrap();
+    assert!(err.is_some());
+    assert_eq!(balance(conn, wallet_id), 20.0);
+}
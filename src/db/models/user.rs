@@ -1,11 +1,34 @@
 //! This module contains the definition and implementation of the `User` struct.
 //!
 //! The `User` struct represents a user in the application. It stores information such as
-//! user ID, name, email, password, wallet ID, and timestamps for creation and update.
+//! user ID, name, email, password, wallet ID, and timestamps for creation and update. The password is stored as a
+//! bcrypt hash and never serialized.
 //!
 //! The module provides various methods for interacting with user data, including listing users,
 //! finding users by ID or email, creating new users, updating user information, deleting users,
-//! and handling user login.
+//! and checking login credentials (`login` returns the user; tokens are issued by `services::auth`). Database failures are returned as `DbError` rather than panicking; a missing user is
+//! `Ok(None)`.
+//!
+//! `login` takes the same time whether or not the email is registered: when no user has the email, the password is
+//! still checked with bcrypt against a dummy hash of the same cost, and both failures return `Ok(None)`. The login
+//! route answers both with the same `401 Invalid email or password`, so neither the timing nor the response tells an
+//! attacker which emails are registered.
+//!
+//! Every check is recorded as a `LoginAttempt`, and an email with too many failures in a row is locked for a while (see
+//! `login_attempt`): `login` returns `LoginOutcome::Locked` without checking the password, and `LoginOutcome::Failed`
+//! tells how many attempts are left before the lock.
+//!
+//! `update` only changes the fields given in `UserChanges`. A new password needs the current one and is only hashed
+//! and stored when it differs from it; every session of the user is ended (refresh tokens are revoked) and the
+//! account's address is told about the change. A new email is not written
+//! directly: it starts a verified change (`email_change`), and the address stays the same until it is confirmed.
+//!
+//! `create` links the signup wallet to the user as their `Main` wallet; further named wallets are opened with
+//! `UserWallet::create` (see `user_wallet`). `delete` drops the user's wallet links, sessions, settings, login devices,
+//! pending password resets and email changes, and the positions and snapshots derived from their trades, but keeps the
+//! wallets themselves. A user with trades or any other history still referring to them cannot be deleted: the foreign
+//! keys reject it as a `DbError`, answered with `409 Conflict`, and the accounts of duplicate users are merged instead
+//! (see `account_merge`).
 //! 
 //! # Examples
 //! 
@@ -13,99 +36,144 @@
 //! use crate::models::user::User;
 //!
 //! // List all users in the database
-//! let users = User::list(&mut connection);
+//! let users = User::list(&mut connection)?;
 //!
 //! // Find a user by ID
-//! if let Some(user) = User::find_by_id(&mut connection, "user_id".to_string()) {
+//! if let Ok(Some(user)) = User::find_by_id(&mut connection, "user_id".to_string()) {
 //!     println!("Found user: {:?}", user);
 //! }
 //!
 //! // Create a new user
-//! if let Some(new_user) = User::create(&mut connection, "John Doe".to_string(), "john@example.com".to_string(), "wallet_id".to_string(), "password123".to_string()) {
+//! if let Ok((Some(new_user), None)) = User::create(&mut connection, "John Doe".to_string(), "john@example.com".to_string(), "wallet_id".to_string(), "password123".to_string()) {
 //!     println!("Created new user: {:?}", new_user);
 //! }
 //!
-//! // Update user information
-//! if let Some(updated_user) = User::update(&mut connection, "user_id".to_string(), "New Name".to_string(), "newemail@example.com".to_string(), "new_wallet_id".to_string(), "new_password123".to_string()) {
-//!     println!("Updated user: {:?}", updated_user);
+//! // Update some of the user's information
+//! let changes = UserChanges { name: Some("New Name".to_string()), ..Default::default() };
+//! if let Ok((Some(update), None)) = User::update(&mut connection, "user_id".to_string(), changes) {
+//!     println!("Updated user: {:?}", update.user);
 //! }
 //!
 //! // Delete a user
-//! if User::delete(&mut connection, "user_id".to_string()) {
+//! if let Ok(true) = User::delete(&mut connection, "user_id".to_string()) {
 //!     println!("User deleted");
 //! }
 //!
 //! // User login
-//! if let Some(jwt_token) = User::login(&mut connection, "john@example.com".to_string(), "password123".to_string()) {
-//!     println!("User logged in. JWT token: {}", jwt_token);
+//! if let Ok(LoginOutcome::Success(user)) = User::login(&mut connection, "john@example.com".to_string(), "password123".to_string()) {
+//!     println!("User logged in: {}", user.id);
 //! }
 //! ```
 //! 
 use uuid::Uuid;
 use serde::{Serialize, Deserialize};
+use utoipa::ToSchema;
 use diesel::prelude::*;
 
-use crate::services::jwt::create_jwt;
-
-use super::super::schema::{*, self};
+use super::super::error::DbError;
+use super::super::retry::retry_on_busy;
+use super::super::schema::*;
 use super::super::schema::users::dsl::users as users_dsl;
+use super::email_change::EmailChange;
+use super::login_attempt::{LockState, LoginAttempt};
+use super::outbound_email::OutboundEmail;
+use super::refresh_token::RefreshToken;
+use super::user_wallet::{UserWallet, PRIMARY_WALLET_NAME};
 use super::wallet::Wallet;
 
-#[derive(Debug, Deserialize, Serialize, Queryable, Insertable)]
+pub const EMAIL_EXISTS: &str = "Email already exists";
+
+pub const USER_NOT_FOUND: &str = "User not found";
+
+pub const WRONG_PASSWORD: &str = "Current password is incorrect";
+
+// A bcrypt hash at `bcrypt::DEFAULT_COST` that no password is checked against for real; see `User::login`.
+const DUMMY_HASH: &str = "$2b$12$SWzthykKNQhMa2utvnRI0.Qm3lh3ro0mVL0GdR6dSC5l/Y/h.KthK";
+
+#[derive(Debug)]
+pub enum LoginOutcome {
+    Success(User),
+    Failed { attempts_left: u32 },
+    Locked { until: chrono::NaiveDateTime },
+}
+
+impl LoginOutcome {
+    pub fn user(self) -> Option<User> {
+        match self {
+            LoginOutcome::Success(user) => Some(user),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Queryable, Insertable, ToSchema)]
 #[diesel(table_name = crate::db::schema::users)]
 pub struct User {
     pub id: String,
     pub name: String,
     pub email: String,
+    // The bcrypt hash; never sent back to clients.
+    #[serde(skip_serializing)]
+    #[schema(write_only)]
     pub password: String,
     pub wallet_id: String,
     pub created_at: chrono::NaiveDateTime,
     pub updated_at: chrono::NaiveDateTime,
 }
 
+// The fields to change in `User::update`; missing ones are kept.
+#[derive(Debug, Default, Deserialize, Serialize, ToSchema)]
+pub struct UserChanges {
+    pub name: Option<String>,
+    pub email: Option<String>,
+    pub password: Option<String>,
+    // Required to change the password.
+    pub current_password: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub struct UserUpdate {
+    pub user: User,
+    // The new address waiting to be confirmed, when the email was changed (see `email_change`).
+    pub pending_email: Option<String>,
+}
+
 impl User {
-    pub fn list(conn: &mut SqliteConnection) -> Vec<Self> {
-        users_dsl
+    pub fn list(conn: &mut SqliteConnection) -> Result<Vec<Self>, DbError> {
+        Ok(users_dsl
             .order(users::id.desc())
-            .load::<User>(conn)
-            .expect("Error loading users")
+            .load::<User>(conn)?)
     }
 
-    pub fn find_by_id(conn: &mut SqliteConnection, id: String) -> Option<Self> {
-        if let Ok(record) = users_dsl
+    pub fn find_by_id(conn: &mut SqliteConnection, id: String) -> Result<Option<Self>, DbError> {
+        Ok(users_dsl
             .find(id)
-            .get_result::<User>(conn) {
-            Some(record)
-            } else {
-                None
-            }
+            .get_result::<User>(conn)
+            .optional()?)
     }
 
-    pub fn find_by_email(conn: &mut SqliteConnection, email: String) -> Option<Self> {
-        if let Ok(record) = users_dsl
+    pub fn find_by_email(conn: &mut SqliteConnection, email: String) -> Result<Option<Self>, DbError> {
+        Ok(users_dsl
             .filter(users::email.eq(email))
-            .get_result::<User>(conn) {
-            Some(record)
-            } else {
-                None
-            }
+            .get_result::<User>(conn)
+            .optional()?)
     }
 
-    pub fn create(conn: &mut SqliteConnection, name: String, email: String, wallet_id: String, password: String) -> (Option<Self>, Option<String>) {
+    pub fn create(conn: &mut SqliteConnection, name: String, email: String, wallet_id: String, password: String) -> Result<(Option<Self>, Option<String>), DbError> {
         let new_id = Uuid::new_v4().as_hyphenated().to_string();
 
         if email.is_empty() || password.is_empty() || name.is_empty() || wallet_id.is_empty() {
-            return (None, Some("Missing required fields".to_string()));
+            return Ok((None, Some("Missing required fields".to_string())));
         }
         
         
-        if Self::find_by_email(conn, email.clone()).is_some() {
-            return (None, Some("Email already exists".to_string()));
+        if Self::find_by_email(conn, email.clone())?.is_some() {
+            return Ok((None, Some(EMAIL_EXISTS.to_string())));
         }
         
         
-        if Wallet::find_by_id(conn, wallet_id.clone()).is_none() {
-            return (None, Some("Wallet does not exist".to_string()));
+        if Wallet::find_by_id(conn, wallet_id.clone())?.is_none() {
+            return Ok((None, Some("Wallet does not exist".to_string())));
         }
         
         let hashed_password = bcrypt::hash(password, bcrypt::DEFAULT_COST).unwrap();
@@ -113,79 +181,136 @@ impl User {
 
         let new_user = Self::new_user_struct(new_id, name, email, wallet_id, hashed_password);
 
-        diesel::insert_into(users_dsl)
-            .values(&new_user)
-            .execute(conn)
-            .expect("Error saving new user");
+        retry_on_busy(|| {
+            conn.transaction(|conn| {
+                diesel::insert_into(users_dsl)
+                    .values(&new_user)
+                    .execute(conn)?;
+                UserWallet::link(conn, &new_user.id, &new_user.wallet_id, PRIMARY_WALLET_NAME)
+            })
+        })?;
         
-        (Self::find_by_id(conn, new_user.id), None)
+        Ok((Self::find_by_id(conn, new_user.id)?, None))
     }
 
     fn new_user_struct(id: String, name: String, email: String, wallet_id: String, password: String) -> Self {
         Self {
-            id: id,
-            name: name,
-            email: email,
-            password: password,
-            wallet_id: wallet_id,
+            id,
+            name,
+            email,
+            password,
+            wallet_id,
             created_at: chrono::Local::now().naive_local(),
             updated_at: chrono::Local::now().naive_local(),
         }
     }
 
-    pub fn update(conn: &mut SqliteConnection, id: String, name: String, email: String, wallet: String, password: String) -> Option<Self> {
-        if let Ok(record) = users_dsl
-            .find(id)
-            .get_result::<User>(conn) {
-            let updated_user = Self::update_user_struct(record, name, email, wallet, password);
-            diesel::update(users_dsl.find(updated_user.id.clone()))
-                .set((schema::users::name.eq(updated_user.name.clone()),
-                    schema::users::email.eq(updated_user.email.clone()),
-                    schema::users::wallet_id.eq(updated_user.wallet_id.clone()),                    
-                    schema::users::password.eq(bcrypt::hash(updated_user.password.clone(), bcrypt::DEFAULT_COST).unwrap()),
-                    schema::users::updated_at.eq(chrono::Local::now().naive_local())))
-                .execute(conn)
-                .expect("Error updating user");
-            Some(updated_user)
-            } else {
-                None
+    // Applies the fields present in `changes` and leaves the others as they are. See the module documentation.
+    pub fn update(conn: &mut SqliteConnection, id: String, changes: UserChanges) -> Result<(Option<UserUpdate>, Option<String>), DbError> {
+        let user = match Self::find_by_id(conn, id)? {
+            Some(user) => user,
+            None => return Ok((None, Some(USER_NOT_FOUND.to_string()))),
+        };
+
+        let name = match changes.name.map(|name| name.trim().to_string()) {
+            Some(name) if name.is_empty() => return Ok((None, Some("Name must not be empty".to_string()))),
+            name => name.filter(|name| *name != user.name),
+        };
+        let hashed_password = match changes.password {
+            Some(password) if password.is_empty() => return Ok((None, Some("Password must not be empty".to_string()))),
+            Some(password) => {
+                let current_password = changes.current_password.unwrap_or_default();
+                if !bcrypt::verify(&current_password, &user.password).unwrap_or(false) {
+                    return Ok((None, Some(WRONG_PASSWORD.to_string())));
+                }
+                (password != current_password).then(|| bcrypt::hash(password, bcrypt::DEFAULT_COST).unwrap())
             }
-    }
+            None => None,
+        };
 
-    fn update_user_struct(mut user: Self, name: String, email: String, wallet: String, password: String) -> Self {
-        user.name = name;
-        user.email = email;
-        user.wallet_id = wallet;
-        user.password = password;
-        user.updated_at = chrono::Local::now().naive_local();
-        user
+        // Requested last, so a rejected name or password does not leave a pending change behind.
+        let pending_email = match changes.email.map(|email| email.trim().to_string()).filter(|email| *email != user.email) {
+            Some(email) => match EmailChange::request(conn, user.id.clone(), email)? {
+                (Some(change), None) => Some(change.new_email),
+                (_, errors) => return Ok((None, errors)),
+            },
+            None => None,
+        };
+
+        if name.is_some() || hashed_password.is_some() {
+            retry_on_busy(|| {
+                conn.transaction(|conn| {
+                    diesel::update(users_dsl.find(&user.id))
+                        .set((
+                            name.as_ref().map(|name| users::name.eq(name)),
+                            hashed_password.as_ref().map(|password| users::password.eq(password)),
+                            users::updated_at.eq(chrono::Local::now().naive_local()),
+                        ))
+                        .execute(conn)?;
+                    if hashed_password.is_some() {
+                        RefreshToken::revoke_all(conn, &user.id)?;
+                        OutboundEmail::queue(conn, &user.email, "Your password was changed", format!(
+                            "Hi {},\n\nThe password of your account was changed and you were logged out everywhere. If \
+                            you did not do this, reset your password right away.",
+                            name.as_ref().unwrap_or(&user.name)
+                        ))?;
+                    }
+                    Ok::<_, diesel::result::Error>(())
+                })
+            })?;
+        }
+
+        match Self::find_by_id(conn, user.id)? {
+            Some(user) => Ok((Some(UserUpdate { user, pending_email }), None)),
+            None => Ok((None, Some(USER_NOT_FOUND.to_string()))),
+        }
     }
 
-    pub fn delete(conn: &mut SqliteConnection, id: String) -> bool {
-        if let Ok(_record) = users_dsl
-            .find(id.clone())
-            .get_result::<User>(conn) {
-            diesel::delete(users_dsl.find(id))
-                .execute(conn)
-                .expect("Error deleting user");
-            true
+    pub fn delete(conn: &mut SqliteConnection, id: String) -> Result<bool, DbError> {
+        if Self::find_by_id(conn, id.clone())?.is_some() {
+            retry_on_busy(|| {
+                conn.transaction(|conn| {
+                    diesel::delete(user_wallets::table.filter(user_wallets::user_id.eq(&id))).execute(conn)?;
+                    diesel::delete(user_settings::table.find(&id)).execute(conn)?;
+                    diesel::delete(known_devices::table.filter(known_devices::user_id.eq(&id))).execute(conn)?;
+                    diesel::delete(refresh_tokens::table.filter(refresh_tokens::user_id.eq(&id))).execute(conn)?;
+                    diesel::delete(password_resets::table.filter(password_resets::user_id.eq(&id))).execute(conn)?;
+                    diesel::delete(email_changes::table.filter(email_changes::user_id.eq(&id))).execute(conn)?;
+                    diesel::delete(positions::table.filter(positions::user_id.eq(&id))).execute(conn)?;
+                    diesel::delete(daily_snapshots::table.filter(daily_snapshots::user_id.eq(&id))).execute(conn)?;
+                    diesel::delete(users_dsl.find(id.clone()))
+                        .execute(conn)
+                })
+            })?;
+            Ok(true)
             } else {
-                false
+                Ok(false)
             }
     }
 
-    pub fn login(conn: &mut SqliteConnection, email: String, password: String) -> Option<String> {
-        if let Ok(record) = users_dsl
-            .filter(users::email.eq(email))
-            .get_result::<User>(conn) {
-                if bcrypt::verify(password, &record.password).unwrap() {
-                    Some(create_jwt(record.id).unwrap())
-                } else {
-                    None
+    pub fn login(conn: &mut SqliteConnection, email: String, password: String) -> Result<LoginOutcome, DbError> {
+        if let LockState::Locked { until } = LoginAttempt::lock_state(conn, &email, chrono::Local::now().naive_local())? {
+            return Ok(LoginOutcome::Locked { until });
+        }
+
+        let record = Self::find_by_email(conn, email.clone())?;
+        // Unknown emails are verified against a dummy hash of the same cost, so they take as long as a wrong password.
+        let hash = record.as_ref().map_or(DUMMY_HASH, |record| record.password.as_str());
+        let verified = bcrypt::verify(password, hash).unwrap_or(false);
+
+        match record.filter(|_| verified) {
+            Some(user) => {
+                LoginAttempt::record(conn, &email, Some(&user.id), true)?;
+                Ok(LoginOutcome::Success(user))
+            }
+            None => {
+                LoginAttempt::record(conn, &email, None, false)?;
+                match LoginAttempt::lock_state(conn, &email, chrono::Local::now().naive_local())? {
+                    LockState::Locked { until } => Ok(LoginOutcome::Locked { until }),
+                    LockState::Open { attempts_left } => Ok(LoginOutcome::Failed { attempts_left }),
                 }
-            } else {
-                None
             }
+        }
     }
 
 }
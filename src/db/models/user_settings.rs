@@ -0,0 +1,78 @@
+//! This module defines the per-user settings.
+//!
+//! A user without a stored row has the default settings, so `UserSettings::for_user` always returns a value and a row is
+//! only written once a user changes something. The settings are:
+//!
+//! - `confirm_new_devices`: When set, a login from a device the user has not logged in from before only completes
+//!   once it is confirmed from the account's email address (see `device`). Off by default; such logins are then
+//!   only notified.
+//! - `hide_from_leaderboard`: When set, the user is left out of the public leaderboard (`leaderboard`). Off by
+//!   default, so users appear there until they opt out.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use crate::models::user_settings::UserSettings;
+//!
+//! let (settings, errors) = UserSettings::update(&mut connection, "user_id".to_string(), true, false)?;
+//! assert!(UserSettings::for_user(&mut connection, "user_id".to_string())?.confirm_new_devices);
+//! ```
+//!
+//! # Note
+//! This module assumes the availability of a database connection (`SqliteConnection` in this case) for settings data retrieval and manipulation.
+
+use serde::{Serialize, Deserialize};
+use diesel::prelude::*;
+
+use super::super::error::DbError;
+use super::super::retry::retry_on_busy;
+use super::super::schema::user_settings;
+use super::user::User;
+
+#[derive(Debug, Deserialize, Serialize, Queryable, Insertable)]
+#[diesel(table_name = crate::db::schema::user_settings)]
+pub struct UserSettings {
+    pub user_id: String,
+    pub confirm_new_devices: bool,
+    pub updated_at: chrono::NaiveDateTime,
+    pub hide_from_leaderboard: bool,
+}
+
+impl UserSettings {
+    fn defaults(user_id: String) -> Self {
+        UserSettings {
+            user_id,
+            confirm_new_devices: false,
+            updated_at: chrono::Local::now().naive_local(),
+            hide_from_leaderboard: false,
+        }
+    }
+
+    pub fn for_user(conn: &mut SqliteConnection, user_id: String) -> QueryResult<Self> {
+        Ok(user_settings::table
+            .find(&user_id)
+            .first::<UserSettings>(conn)
+            .optional()?
+            .unwrap_or_else(|| Self::defaults(user_id)))
+    }
+
+    pub fn update(
+        conn: &mut SqliteConnection,
+        user_id: String,
+        confirm_new_devices: bool,
+        hide_from_leaderboard: bool,
+    ) -> Result<(Option<Self>, Option<String>), DbError> {
+        if User::find_by_id(conn, user_id.clone())?.is_none() {
+            return Ok((None, Some("User does not exist".to_string())));
+        }
+
+        let settings = UserSettings { confirm_new_devices, hide_from_leaderboard, ..Self::defaults(user_id) };
+        retry_on_busy(|| {
+            diesel::replace_into(user_settings::table)
+                .values(&settings)
+                .execute(conn)
+        })?;
+
+        Ok((Some(settings), None))
+    }
+}
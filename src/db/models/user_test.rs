@@ -0,0 +1,82 @@
+use std::time::{Duration, Instant};
+
+use crate::db::fixtures::test_connection;
+use super::refresh_token::RefreshToken;
+use super::user::{User, UserChanges, EMAIL_EXISTS, WRONG_PASSWORD};
+use super::wallet::Wallet;
+
+fn timed<T>(f: impl FnOnce() -> T) -> (T, Duration) {
+    let start = Instant::now();
+    let result = f();
+    (result, start.elapsed())
+}
+
+#[test]
+fn login_does_not_reveal_registered_emails() {
+    let conn = &mut test_connection();
+    let wallet = Wallet::create(conn).unwrap().unwrap();
+    User::create(conn, "test_user".to_string(), "login@example.com".to_string(), wallet.id, "test_password".to_string()).unwrap();
+
+    let user = User::login(conn, "login@example.com".to_string(), "test_password".to_string()).unwrap().user();
+    assert_eq!(user.unwrap().email, "login@example.com");
+
+    let (wrong_password, known) = timed(|| User::login(conn, "login@example.com".to_string(), "wrong_password".to_string()).unwrap().user());
+    let (unknown_email, unknown) = timed(|| User::login(conn, "nobody@example.com".to_string(), "test_password".to_string()).unwrap().user());
+    assert!(wrong_password.is_none() && unknown_email.is_none());
+    // Both paths run one bcrypt verification at the same cost.
+    assert!(unknown * 2 > known, "unknown email took {:?}, wrong password {:?}", unknown, known);
+}
+
+#[test]
+fn update_only_changes_the_given_fields() {
+    let conn = &mut test_connection();
+    let wallet = Wallet::create(conn).unwrap().unwrap();
+    let (user, _) = User::create(conn, "test_user".to_string(), "update@example.com".to_string(), wallet.id, "test_password".to_string()).unwrap();
+    let user = user.unwrap();
+
+    let changes = UserChanges { name: Some("Renamed".to_string()), ..Default::default() };
+    let (update, errors) = User::update(conn, user.id.clone(), changes).unwrap();
+    assert!(errors.is_none());
+    let update = update.unwrap();
+    assert_eq!(update.user.name, "Renamed");
+    assert_eq!((update.user.email.as_str(), update.user.password.as_str()), (user.email.as_str(), user.password.as_str()));
+    assert_eq!(update.pending_email, None);
+
+    // The address only changes once the new one is confirmed.
+    let changes = UserChanges { email: Some("new@example.com".to_string()), ..Default::default() };
+    let update = User::update(conn, user.id.clone(), changes).unwrap().0.unwrap();
+    assert_eq!(update.user.email, "update@example.com");
+    assert_eq!(update.pending_email.as_deref(), Some("new@example.com"));
+
+    let wallet = Wallet::create(conn).unwrap().unwrap();
+    User::create(conn, "other_user".to_string(), "taken@example.com".to_string(), wallet.id, "test_password".to_string()).unwrap();
+    let changes = UserChanges { email: Some("taken@example.com".to_string()), ..Default::default() };
+    assert_eq!(User::update(conn, user.id.clone(), changes).unwrap().1, Some(EMAIL_EXISTS.to_string()));
+}
+
+#[test]
+fn update_needs_the_current_password_to_change_it() {
+    let conn = &mut test_connection();
+    let wallet = Wallet::create(conn).unwrap().unwrap();
+    let (user, _) = User::create(conn, "test_user".to_string(), "password@example.com".to_string(), wallet.id, "test_password".to_string()).unwrap();
+    let user = user.unwrap();
+
+    let changes = UserChanges { name: Some("Renamed".to_string()), password: Some("new_password".to_string()), ..Default::default() };
+    assert_eq!(User::update(conn, user.id.clone(), changes).unwrap().1, Some(WRONG_PASSWORD.to_string()));
+    // Nothing is applied when the password is rejected.
+    assert_eq!(User::find_by_id(conn, user.id.clone()).unwrap().unwrap().name, "test_user");
+
+    // The same password is not hashed again.
+    let changes = UserChanges { password: Some("test_password".to_string()), current_password: Some("test_password".to_string()), ..Default::default() };
+    let (session, _) = RefreshToken::issue(conn, user.id.clone()).unwrap();
+    let update = User::update(conn, user.id.clone(), changes).unwrap().0.unwrap();
+    assert_eq!(update.user.password, user.password);
+    assert!(RefreshToken::find_active(conn, &session).unwrap().is_some());
+
+    // A new password ends every session, and the hash is never serialized.
+    let changes = UserChanges { password: Some("new_password".to_string()), current_password: Some("test_password".to_string()), ..Default::default() };
+    let update = User::update(conn, user.id.clone(), changes).unwrap().0.unwrap();
+    assert!(User::login(conn, "password@example.com".to_string(), "new_password".to_string()).unwrap().user().is_some());
+    assert!(RefreshToken::find_active(conn, &session).unwrap().is_none());
+    assert!(serde_json::to_value(&update.user).unwrap().get("password").is_none());
+}
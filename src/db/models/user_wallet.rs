@@ -0,0 +1,157 @@
+//! This module defines the named wallets a user owns.
+//!
+//! Every user gets a wallet at signup (`users.wallet_id`), which `User::create` links to them as their `Main` wallet,
+//! and may open more with `UserWallet::create`. Each wallet is created empty and carries a name of its owner's
+//! choosing: names are trimmed, at most `MAX_NAME_LENGTH` characters long and unique per user regardless of case. A
+//! wallet belongs to one user, and `owns` tells whether trades may be posted to a wallet on a user's behalf. The
+//! signup wallet always belongs to its user, including the few wallets shared by several users at signup, which only
+//! the first of them has linked.
+//!
+//! `list_for_user` returns a user's wallets oldest first, with their hash and balance; `primary` marks the signup
+//! wallet. Users who signed up before wallets were named had their signup wallet linked as `Main` by the migration.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use crate::models::user_wallet::UserWallet;
+//!
+//! let (wallet, errors) = UserWallet::create(&mut connection, "user_id", "Savings")?;
+//! let wallets = UserWallet::list_for_user(&mut connection, "user_id")?;
+//! assert!(UserWallet::owns(&mut connection, "user_id", &wallet.unwrap().id)?);
+//! ```
+//!
+//! # Note
+//! This module assumes the availability of a database connection (`SqliteConnection` in this case) for wallet data retrieval and manipulation.
+
+use uuid::Uuid;
+use serde::{Serialize, Deserialize};
+use diesel::prelude::*;
+
+use super::super::error::DbError;
+use super::super::retry::retry_on_busy;
+use super::super::schema::{user_wallets, users, wallet};
+use super::user::{User, USER_NOT_FOUND};
+use super::wallet::Wallet;
+use crate::utils::hash::new_hash;
+
+pub const PRIMARY_WALLET_NAME: &str = "Main";
+pub const MAX_NAME_LENGTH: usize = 64;
+
+#[derive(Debug, Deserialize, Serialize, Queryable, Insertable)]
+#[diesel(table_name = crate::db::schema::user_wallets)]
+pub struct UserWallet {
+    pub wallet_id: String,
+    pub user_id: String,
+    pub name: String,
+    pub created_at: chrono::NaiveDateTime,
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+pub struct NamedWallet {
+    pub id: String,
+    pub name: String,
+    pub hash: String,
+    pub balance: f32,
+    pub primary: bool,
+    pub created_at: chrono::NaiveDateTime,
+}
+
+impl NamedWallet {
+    fn new(link: UserWallet, wallet: Wallet, primary_id: Option<&str>) -> Self {
+        NamedWallet {
+            primary: primary_id == Some(wallet.id.as_str()),
+            id: wallet.id,
+            name: link.name,
+            hash: wallet.hash,
+            balance: wallet.balance,
+            created_at: link.created_at,
+        }
+    }
+}
+
+impl UserWallet {
+    // Links a wallet to a user. A wallet already linked to someone stays theirs.
+    pub fn link(conn: &mut SqliteConnection, user_id: &str, wallet_id: &str, name: &str) -> QueryResult<usize> {
+        diesel::insert_or_ignore_into(user_wallets::table)
+            .values(&UserWallet {
+                wallet_id: wallet_id.to_string(),
+                user_id: user_id.to_string(),
+                name: name.to_string(),
+                created_at: chrono::Local::now().naive_local(),
+            })
+            .execute(conn)
+    }
+
+    fn primary_wallet_id(conn: &mut SqliteConnection, user_id: &str) -> QueryResult<Option<String>> {
+        users::table.find(user_id).select(users::wallet_id).first::<String>(conn).optional()
+    }
+
+    pub fn owns(conn: &mut SqliteConnection, user_id: &str, wallet_id: &str) -> Result<bool, DbError> {
+        if Self::primary_wallet_id(conn, user_id)?.as_deref() == Some(wallet_id) {
+            return Ok(true);
+        }
+        let linked = user_wallets::table
+            .find(wallet_id)
+            .filter(user_wallets::user_id.eq(user_id))
+            .count()
+            .get_result::<i64>(conn)?;
+        Ok(linked > 0)
+    }
+
+    pub fn list_for_user(conn: &mut SqliteConnection, user_id: &str) -> Result<Vec<NamedWallet>, DbError> {
+        let primary_id = Self::primary_wallet_id(conn, user_id)?;
+        let wallets = user_wallets::table
+            .inner_join(wallet::table)
+            .filter(user_wallets::user_id.eq(user_id))
+            .order((user_wallets::created_at.asc(), user_wallets::wallet_id.asc()))
+            .load::<(UserWallet, Wallet)>(conn)?;
+        Ok(wallets
+            .into_iter()
+            .map(|(link, wallet)| NamedWallet::new(link, wallet, primary_id.as_deref()))
+            .collect())
+    }
+
+    pub fn create(conn: &mut SqliteConnection, user_id: &str, name: &str) -> Result<(Option<NamedWallet>, Option<String>), DbError> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Ok((None, Some("Wallet name is required".to_string())));
+        }
+        if name.chars().count() > MAX_NAME_LENGTH {
+            return Ok((None, Some(format!("Wallet names are at most {} characters long", MAX_NAME_LENGTH))));
+        }
+        if User::find_by_id(conn, user_id.to_string())?.is_none() {
+            return Ok((None, Some(USER_NOT_FOUND.to_string())));
+        }
+        let existing = Self::list_for_user(conn, user_id)?;
+        if existing.iter().any(|wallet| wallet.name.to_lowercase() == name.to_lowercase()) {
+            return Ok((None, Some(format!("A wallet named {} already exists", name))));
+        }
+
+        let now = chrono::Local::now().naive_local();
+        let new_wallet = Wallet {
+            id: Uuid::new_v4().as_hyphenated().to_string(),
+            hash: new_hash(),
+            balance: 0.0,
+            created_at: now,
+            updated_at: now,
+        };
+        let link = UserWallet {
+            wallet_id: new_wallet.id.clone(),
+            user_id: user_id.to_string(),
+            name: name.to_string(),
+            created_at: now,
+        };
+        retry_on_busy(|| {
+            conn.transaction(|conn| {
+                diesel::insert_into(wallet::table).values(&new_wallet).execute(conn)?;
+                diesel::insert_into(user_wallets::table).values(&link).execute(conn)
+            })
+        })?;
+
+        let created = user_wallets::table
+            .find(&new_wallet.id)
+            .inner_join(wallet::table)
+            .first::<(UserWallet, Wallet)>(conn)?;
+        Ok((Some(NamedWallet::new(created.0, created.1, None)), None))
+    }
+}
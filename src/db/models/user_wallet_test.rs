@@ -0,0 +1,46 @@
+use diesel::prelude::*;
+
+use crate::db::fixtures::{test_connection, user};
+use crate::db::schema::user_wallets;
+use super::user::User;
+use super::user_wallet::{UserWallet, PRIMARY_WALLET_NAME};
+
+#[test]
+fn users_open_named_wallets_next_to_their_signup_wallet() {
+    let conn = &mut test_connection();
+    let (alice, bob) = (user(conn, "alice"), user(conn, "bob"));
+
+    let (savings, err) = UserWallet::create(conn, &alice.id, "  Savings ").unwrap();
+    assert_eq!(err, None);
+    let savings = savings.unwrap();
+    assert_eq!((savings.name.as_str(), savings.balance, savings.primary), ("Savings", 0.0, false));
+
+    let wallets = UserWallet::list_for_user(conn, &alice.id).unwrap();
+    let names: Vec<(&str, bool)> = wallets.iter().map(|wallet| (wallet.name.as_str(), wallet.primary)).collect();
+    assert_eq!(names, vec![(PRIMARY_WALLET_NAME, true), ("Savings", false)]);
+    assert_eq!(wallets[0].id, alice.wallet_id);
+
+    assert!(UserWallet::owns(conn, &alice.id, &alice.wallet_id).unwrap());
+    assert!(UserWallet::owns(conn, &alice.id, &savings.id).unwrap());
+    assert!(!UserWallet::owns(conn, &bob.id, &savings.id).unwrap());
+    assert!(!UserWallet::owns(conn, &alice.id, &bob.wallet_id).unwrap());
+}
+
+#[test]
+fn wallet_names_are_required_and_unique_per_user() {
+    let conn = &mut test_connection();
+    let (alice, bob) = (user(conn, "alice"), user(conn, "bob"));
+
+    assert!(UserWallet::create(conn, &alice.id, "   ").unwrap().1.is_some());
+    assert!(UserWallet::create(conn, &alice.id, &"x".repeat(65)).unwrap().1.is_some());
+    assert!(UserWallet::create(conn, &alice.id, "main").unwrap().1.is_some());
+    assert!(UserWallet::create(conn, "missing", "Savings").unwrap().1.is_some());
+    assert!(UserWallet::create(conn, &bob.id, "Savings").unwrap().0.is_some());
+    assert!(UserWallet::create(conn, &alice.id, "Savings").unwrap().0.is_some());
+
+    // Deleting a user drops their links; the wallets stay.
+    assert!(User::delete(conn, alice.id.clone()).unwrap());
+    let links = user_wallets::table.filter(user_wallets::user_id.eq(&alice.id)).count().get_result::<i64>(conn).unwrap();
+    assert_eq!(links, 0);
+    assert_eq!(UserWallet::list_for_user(conn, &bob.id).unwrap().len(), 2);
+}
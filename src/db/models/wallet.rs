@@ -5,6 +5,11 @@
 //! 
 //! The module provides methods for retrieving wallet data from the database, creating new wallets, and updating wallet balances.
 //! Additionally, it includes utility methods for generating a new wallet hash and creating a new wallet struct.
+//! Balance changes go through the wallet ledger (see `wallet_transaction`); `update_balance` records the difference as
+//! an adjustment.
+//! Database failures are returned as `DbError` rather than panicking; a missing wallet is `Ok(None)`.
+//! `regenerate_hash` replaces a wallet's hash, for wallets created before hashes were drawn from OS entropy, and records
+//! the previous hash in the audit log.
 //! 
 //! # Examples
 //! 
@@ -12,27 +17,30 @@
 //! use crate::models::wallet::Wallet;
 //!
 //! // List all wallets in the database
-//! let wallets = Wallet::list(&mut connection);
+//! let wallets = Wallet::list(&mut connection)?;
 //!
 //! // Find a wallet by ID
-//! if let Some(wallet) = Wallet::find_by_id(&mut connection, "wallet_id".to_string()) {
+//! if let Ok(Some(wallet)) = Wallet::find_by_id(&mut connection, "wallet_id".to_string()) {
 //!     println!("Found wallet: {:?}", wallet);
 //! }
 //!
 //! // Find a wallet by hash
-//! if let Some(wallet) = Wallet::find_by_hash(&mut connection, "wallet_hash".to_string()) {
+//! if let Ok(Some(wallet)) = Wallet::find_by_hash(&mut connection, "wallet_hash".to_string()) {
 //!     println!("Found wallet: {:?}", wallet);
 //! }
 //!
 //! // Create a new wallet
-//! if let Some(new_wallet) = Wallet::create(&mut connection) {
+//! if let Ok(Some(new_wallet)) = Wallet::create(&mut connection) {
 //!     println!("Created new wallet: {:?}", new_wallet);
 //! }
 //!
 //! // Update wallet balance
-//! if let Some(updated_wallet) = Wallet::update_balance(&mut connection, "wallet_id".to_string(), 100.0) {
+//! if let Ok(Some(updated_wallet)) = Wallet::update_balance(&mut connection, "wallet_id".to_string(), 100.0) {
 //!     println!("Updated wallet balance: {:?}", updated_wallet);
 //! }
+//!
+//! // Give a wallet a new hash
+//! let regenerated = Wallet::regenerate_hash(&mut connection, "wallet_id".to_string(), "admin_id")?;
 //! ```
 //!
 //! # Note
@@ -42,6 +50,8 @@ use uuid::Uuid;
 use serde::{Serialize, Deserialize};
 use diesel::prelude::*;
 
+use super::super::error::DbError;
+use super::super::retry::retry_on_busy;
 use super::super::schema::wallet;
 use super::super::schema::wallet::dsl::{
     id as id_dsl,
@@ -50,6 +60,8 @@ use super::super::schema::wallet::dsl::{
     hash as hash_dsl,
 };
 
+use super::audit::AuditEntry;
+use super::wallet_transaction::{TransactionKind, WalletTransaction};
 use crate::utils::hash::new_hash;
 
 #[derive(Debug, Deserialize, Serialize, Queryable, Insertable)]
@@ -63,72 +75,83 @@ pub struct Wallet {
 }
 
 impl Wallet {
-    pub fn list(conn: &mut SqliteConnection) -> Vec<Self> {
-        wallet_dsl
+    pub fn list(conn: &mut SqliteConnection) -> Result<Vec<Self>, DbError> {
+        Ok(wallet_dsl
             .order(wallet::id.desc())
-            .load::<Wallet>(conn)
-            .expect("Error loading wallets")
+            .load::<Wallet>(conn)?)
     }
     
-    pub fn find_by_id(conn: &mut SqliteConnection, id: String) -> Option<Self> {
-        
-        let wallet = wallet_dsl
-            .filter(id_dsl.eq(id.clone()))
+    pub fn find_by_id(conn: &mut SqliteConnection, id: String) -> Result<Option<Self>, DbError> {
+        Ok(wallet_dsl
+            .filter(id_dsl.eq(id))
             .first::<Wallet>(conn)
-            .optional()
-            .expect("Error loading wallet");
-        
-        match wallet {
-            Some(wallet) => Some(wallet),
-            None => None,
-        }
+            .optional()?)
     }
 
-    pub fn find_by_hash(conn: &mut SqliteConnection, hash: String) -> Option<Self> {
-        let wallet = wallet_dsl
+    pub fn find_by_hash(conn: &mut SqliteConnection, hash: String) -> Result<Option<Self>, DbError> {
+        Ok(wallet_dsl
             .filter(hash_dsl.eq(hash))
             .first::<Wallet>(conn)
-            .optional()
-            .expect("Error loading wallet");
-
-        match wallet {
-            Some(wallet) => Some(wallet),
-            None => None,
-        }
+            .optional()?)
     }
 
-    pub fn create(conn: &mut SqliteConnection) -> Option<Self> {
+    pub fn create(conn: &mut SqliteConnection) -> Result<Option<Self>, DbError> {
         let new_id = Uuid::new_v4().as_hyphenated().to_string();
         let new_hash = new_hash();
         let new_wallet = Self::new_wallet_struct(new_id, new_hash.clone(), 0.0);
 
-        diesel::insert_into(wallet_dsl)
-            .values(&new_wallet)
-            .execute(conn)
-            .expect("Error saving new wallet");
+        retry_on_busy(|| {
+            diesel::insert_into(wallet_dsl)
+                .values(&new_wallet)
+                .execute(conn)
+        })?;
         
         Self::find_by_hash(conn, new_hash)
     }
 
+    pub fn regenerate_hash(conn: &mut SqliteConnection, id: String, actor_id: &str) -> Result<Option<Self>, DbError> {
+        let previous = match Self::find_by_id(conn, id.clone())? {
+            Some(previous) => previous,
+            None => return Ok(None),
+        };
+        let hash = new_hash();
+
+        retry_on_busy(|| {
+            conn.transaction(|conn| {
+                diesel::update(wallet_dsl.find(id.clone()))
+                    .set((hash_dsl.eq(hash.clone()), wallet::updated_at.eq(chrono::Local::now().naive_local())))
+                    .execute(conn)?;
+                AuditEntry::record(conn, actor_id, "regenerate_wallet_hash", &id, serde_json::json!({"previous_hash": previous.hash}))
+            })
+        })?;
+
+        Self::find_by_id(conn, id)
+    }
+
     fn new_wallet_struct(id: String, hash: String, balance: f32) -> Self {
         Self {
-            id: id,
-            hash: hash,
-            balance: balance,
+            id,
+            hash,
+            balance,
             created_at: chrono::Local::now().naive_local(),
             updated_at: chrono::Local::now().naive_local(),
         }
     }
 
-    pub fn update_balance(conn: &mut SqliteConnection, id: String, balance: f32) -> Option<Self> {
-        if let Some(mut _wallet) = Self::find_by_id(conn, id.clone()) {
-            diesel::update(wallet_dsl.find(id.clone()))
-                .set(balance_dsl.eq(balance))
-                .execute(conn)
-                .expect("Error updating wallet");
+    pub fn update_balance(conn: &mut SqliteConnection, id: String, balance: f32) -> Result<Option<Self>, DbError> {
+        if let Some(current) = Self::find_by_id(conn, id.clone())? {
+            retry_on_busy(|| {
+                conn.transaction(|conn| {
+                    WalletTransaction::post(conn, &id, TransactionKind::ADJUSTMENT, balance - current.balance, None)?;
+                    // Set the exact balance rather than the sum, which may differ in the last bit.
+                    diesel::update(wallet_dsl.find(id.clone()))
+                        .set((balance_dsl.eq(balance), wallet::updated_at.eq(chrono::Local::now().naive_local())))
+                        .execute(conn)
+                })
+            })?;
             Self::find_by_id(conn, id)
         } else {
-            None
+            Ok(None)
         }
     }
 }
@@ -0,0 +1,20 @@
+use crate::db::fixtures::test_connection;
+use super::audit::AuditEntry;
+use super::wallet::Wallet;
+
+#[test]
+fn regenerating_a_hash_is_audited() {
+    let conn = &mut test_connection();
+    let wallet = Wallet::create(conn).unwrap().unwrap();
+    assert_eq!(wallet.hash.len(), 64);
+
+    let regenerated = Wallet::regenerate_hash(conn, wallet.id.clone(), "admin_id").unwrap().unwrap();
+    assert_ne!(regenerated.hash, wallet.hash);
+    assert!(Wallet::find_by_hash(conn, wallet.hash.clone()).unwrap().is_none());
+
+    let audit = AuditEntry::list(conn, 10, 0).unwrap();
+    assert_eq!((audit[0].actor_id.as_str(), audit[0].action.as_str(), audit[0].target_id.as_str()), ("admin_id", "regenerate_wallet_hash", wallet.id.as_str()));
+    assert_eq!(serde_json::from_str::<serde_json::Value>(&audit[0].details).unwrap()["previous_hash"], wallet.hash);
+
+    assert!(Wallet::regenerate_hash(conn, "missing".to_string(), "admin_id").unwrap().is_none());
+}
@@ -0,0 +1,162 @@
+//! This module defines the wallet ledger: one `WalletTransaction` for every credit or debit of a wallet balance.
+//!
+//! Each entry records its kind (`TransactionKind`), the signed amount (credits positive, debits negative), the balance
+//! right after it and an optional free-form reference (a transfer ID, a trade ID, a bank reference...). `post` changes
+//! the wallet balance and appends the entry in the same database transaction, so the sum of a wallet's entries always
+//! equals its balance. A debit that would overdraw the wallet is refused, except for adjustments.
+//!
+//! Executed transfers are recorded as withdrawals referencing the transfer, created trades as a trade settlement and a
+//! fee referencing the trade, and `Wallet::update_balance` records the difference as an adjustment. Existing balances are opened with an adjustment when the ledger is introduced.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use crate::models::wallet_transaction::{TransactionKind, WalletTransaction};
+//!
+//! // Credit a deposit and debit a withdrawal.
+//! WalletTransaction::deposit(&mut connection, "wallet_id".to_string(), 100.0, Some("wire 1234".to_string()))?;
+//! WalletTransaction::withdraw(&mut connection, "wallet_id".to_string(), 40.0, None)?;
+//!
+//! // Inside an existing transaction, post any kind of entry.
+//! WalletTransaction::post(conn, "wallet_id", TransactionKind::FEE, -1.5, Some("trade_id".to_string()))?;
+//!
+//! // Newest entries first.
+//! let history = WalletTransaction::list_for_wallet(&mut connection, "wallet_id".to_string(), 50, 0)?;
+//!
+//! // The next entries, continuing after the last one of the previous page.
+//! let last = history.last().unwrap();
+//! let older = WalletTransaction::list_before(&mut connection, "wallet_id".to_string(), Some(&(last.created_at, last.id.clone())), 50)?;
+//! ```
+//!
+//! # Note
+//! This module assumes the availability of a database connection (`SqliteConnection` in this case) for ledger data retrieval and manipulation.
+
+use uuid::Uuid;
+use serde::{Serialize, Deserialize};
+use diesel::prelude::*;
+
+use super::super::error::DbError;
+use super::super::retry::retry_on_busy;
+use super::super::schema::{wallet, wallet_transactions};
+
+#[derive(Debug, Deserialize, Serialize, Queryable, Insertable)]
+#[diesel(table_name = crate::db::schema::wallet_transactions)]
+pub struct WalletTransaction {
+    pub id: String,
+    pub wallet_id: String,
+    pub kind: String,
+    pub amount: f32,
+    pub balance_after: f32,
+    pub reference: Option<String>,
+    pub created_at: chrono::NaiveDateTime,
+}
+
+pub struct TransactionKind;
+
+impl TransactionKind {
+    pub const DEPOSIT: &'static str = "Deposit";
+    pub const WITHDRAWAL: &'static str = "Withdrawal";
+    pub const TRADE_SETTLEMENT: &'static str = "TradeSettlement";
+    pub const FEE: &'static str = "Fee";
+    pub const ADJUSTMENT: &'static str = "Adjustment";
+}
+
+impl WalletTransaction {
+    pub fn list_for_wallet(conn: &mut SqliteConnection, wallet_id: String, limit: i64, offset: i64) -> Result<Vec<Self>, DbError> {
+        Ok(wallet_transactions::table
+            .filter(wallet_transactions::wallet_id.eq(wallet_id))
+            .order(wallet_transactions::created_at.desc())
+            .limit(limit)
+            .offset(offset)
+            .load::<WalletTransaction>(conn)?)
+    }
+
+    // Newest first, continuing after the `(created_at, id)` of the previous page's last entry.
+    pub fn list_before(conn: &mut SqliteConnection, wallet_id: String, before: Option<&(chrono::NaiveDateTime, String)>, limit: i64) -> Result<Vec<Self>, DbError> {
+        let mut query = wallet_transactions::table
+            .filter(wallet_transactions::wallet_id.eq(wallet_id))
+            .into_boxed();
+        if let Some((created_at, id)) = before {
+            query = query.filter(
+                wallet_transactions::created_at
+                    .lt(*created_at)
+                    .or(wallet_transactions::created_at.eq(*created_at).and(wallet_transactions::id.lt(id.clone()))),
+            );
+        }
+        Ok(query
+            .order((wallet_transactions::created_at.desc(), wallet_transactions::id.desc()))
+            .limit(limit)
+            .load::<WalletTransaction>(conn)?)
+    }
+
+    // Applies `amount` to the wallet balance and appends the entry. Must run inside a transaction; returns `None`
+    // without changing anything when a debit other than an adjustment would overdraw the wallet or the wallet does
+    // not exist.
+    pub fn post(conn: &mut SqliteConnection, wallet_id: &str, kind: &str, amount: f32, reference: Option<String>) -> QueryResult<Option<Self>> {
+        let balance = match wallet::table
+            .find(wallet_id)
+            .select(wallet::balance)
+            .first::<f32>(conn)
+            .optional()?
+        {
+            Some(balance) => balance,
+            None => return Ok(None),
+        };
+
+        let balance_after = balance + amount;
+        if amount < 0.0 && balance_after < 0.0 && kind != TransactionKind::ADJUSTMENT {
+            return Ok(None);
+        }
+
+        let now = chrono::Local::now().naive_local();
+        diesel::update(wallet::table.find(wallet_id))
+            .set((wallet::balance.eq(balance_after), wallet::updated_at.eq(now)))
+            .execute(conn)?;
+
+        let entry = WalletTransaction {
+            id: Uuid::new_v4().as_hyphenated().to_string(),
+            wallet_id: wallet_id.to_string(),
+            kind: kind.to_string(),
+            amount,
+            balance_after,
+            reference,
+            created_at: now,
+        };
+        diesel::insert_into(wallet_transactions::table)
+            .values(&entry)
+            .execute(conn)?;
+
+        Ok(Some(entry))
+    }
+
+    fn apply(conn: &mut SqliteConnection, wallet_id: String, kind: &str, amount: f32, reference: Option<String>) -> Result<(Option<Self>, Option<String>), DbError> {
+        if !amount.is_finite() || amount <= 0.0 {
+            return Ok((None, Some("Amount must be a positive number".to_string())));
+        }
+
+        if reference.as_ref().is_some_and(|reference| reference.len() > 255) {
+            return Ok((None, Some("Reference must be at most 255 characters".to_string())));
+        }
+
+        let signed = if kind == TransactionKind::DEPOSIT { amount } else { -amount };
+        let entry = retry_on_busy(|| {
+            conn.transaction(|conn| Self::post(conn, &wallet_id, kind, signed, reference.clone()))
+        })?;
+
+        match entry {
+            Some(entry) => Ok((Some(entry), None)),
+            None if wallet::table.find(&wallet_id).count().get_result::<i64>(conn)? == 0 => {
+                Ok((None, Some("Wallet does not exist".to_string())))
+            }
+            None => Ok((None, Some("Insufficient balance".to_string()))),
+        }
+    }
+
+    pub fn deposit(conn: &mut SqliteConnection, wallet_id: String, amount: f32, reference: Option<String>) -> Result<(Option<Self>, Option<String>), DbError> {
+        Self::apply(conn, wallet_id, TransactionKind::DEPOSIT, amount, reference)
+    }
+
+    pub fn withdraw(conn: &mut SqliteConnection, wallet_id: String, amount: f32, reference: Option<String>) -> Result<(Option<Self>, Option<String>), DbError> {
+        Self::apply(conn, wallet_id, TransactionKind::WITHDRAWAL, amount, reference)
+    }
+}
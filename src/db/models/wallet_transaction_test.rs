@@ -0,0 +1,132 @@
+use diesel::SqliteConnection;
+
+use crate::db::fixtures::{test_connection, trade};
+use crate::services::trade::{fill_optional_fields, TradeForm};
+use super::trade::Trade;
+use super::transfer::Transfer;
+use super::user::User;
+use super::wallet::Wallet;
+use super::wallet_transaction::{TransactionKind, WalletTransaction};
+
+fn create_user(conn: &mut SqliteConnection) -> (String, String) {
+    let wallet = Wallet::create(conn).unwrap().unwrap();
+    let (user, _err) = User::create(conn, "test_user".to_string(), "ledger@example.com".to_string(), wallet.id, "test_password".to_string()).unwrap();
+    let user = user.unwrap();
+    (user.id, user.wallet_id)
+}
+
+#[test]
+fn ledger_tracks_running_balance() {
+    let conn = &mut test_connection();
+    let (user_id, wallet_id) = create_user(conn);
+
+    let (entry, errors) = WalletTransaction::deposit(conn, wallet_id.clone(), 100.0, Some("wire 1234".to_string())).unwrap();
+    assert!(errors.is_none());
+    assert_eq!(entry.unwrap().balance_after, 100.0);
+
+    let (entry, _) = WalletTransaction::withdraw(conn, wallet_id.clone(), 30.0, None).unwrap();
+    let entry = entry.unwrap();
+    assert_eq!((entry.kind.as_str(), entry.amount, entry.balance_after), (TransactionKind::WITHDRAWAL, -30.0, 70.0));
+
+    let (transfer, _) = Transfer::request(conn, wallet_id.clone(), user_id, 20.0).unwrap();
+    Wallet::update_balance(conn, wallet_id.clone(), 75.0).unwrap();
+
+    let history = WalletTransaction::list_for_wallet(conn, wallet_id.clone(), 10, 0).unwrap();
+    assert_eq!(history.len(), 4);
+    assert_eq!(history[0].kind, TransactionKind::ADJUSTMENT);
+    assert_eq!(history[0].amount, 25.0);
+    assert_eq!(history[1].reference, Some(transfer.unwrap().id));
+    assert_eq!(history.iter().map(|entry| entry.amount).sum::<f32>(), 75.0);
+    assert_eq!(Wallet::find_by_id(conn, wallet_id).unwrap().unwrap().balance, 75.0);
+}
+
+#[test]
+fn invalid_and_overdrawing_entries_are_refused() {
+    let conn = &mut test_connection();
+    let (_, wallet_id) = create_user(conn);
+    WalletTransaction::deposit(conn, wallet_id.clone(), 10.0, None).unwrap();
+
+    let (entry, errors) = WalletTransaction::withdraw(conn, wallet_id.clone(), 10.5, None).unwrap();
+    assert!(entry.is_none());
+    assert_eq!(errors, Some("Insufficient balance".to_string()));
+    let (_, errors) = WalletTransaction::deposit(conn, wallet_id.clone(), -5.0, None).unwrap();
+    assert_eq!(errors, Some("Amount must be a positive number".to_string()));
+    let (_, errors) = WalletTransaction::deposit(conn, "missing".to_string(), 5.0, None).unwrap();
+    assert_eq!(errors, Some("Wallet does not exist".to_string()));
+
+    assert_eq!(WalletTransaction::list_for_wallet(conn, wallet_id.clone(), 10, 0).unwrap().len(), 1);
+    assert_eq!(Wallet::find_by_id(conn, wallet_id).unwrap().unwrap().balance, 10.0);
+}
+
+#[test]
+fn trades_settle_against_the_wallet() {
+    let conn = &mut test_connection();
+    let (user_id, wallet_id) = create_user(conn);
+    WalletTransaction::deposit(conn, wallet_id.clone(), 250.0, None).unwrap();
+
+    let form = |trade_type: &str, quantity: f32| TradeForm {
+        amount: 100.0 * quantity,
+        trade_type: trade_type.to_string(),
+        final_price: None,
+        traded_amount: Some(quantity),
+        timestamp: None,
+        ..trade(&user_id, &wallet_id)
+    };
+
+    let (trade, errors) = Trade::create(conn, &mut fill_optional_fields(&form("MarketBuy", 2.0))).unwrap();
+    assert!(errors.is_none());
+    let trade = trade.unwrap();
+    let fees = trade.execution_fee + trade.transaction_fee;
+    let balance = 250.0 - 200.0 - fees;
+    assert_eq!(Wallet::find_by_id(conn, wallet_id.clone()).unwrap().unwrap().balance, balance);
+
+    let history = WalletTransaction::list_for_wallet(conn, wallet_id.clone(), 10, 0).unwrap();
+    assert_eq!(history[0].kind, TransactionKind::FEE);
+    assert_eq!(history[1].kind, TransactionKind::TRADE_SETTLEMENT);
+    assert_eq!(history[1].amount, -200.0);
+    assert_eq!(history[0].reference, Some(trade.id.clone()));
+
+    let (trade, errors) = Trade::create(conn, &mut fill_optional_fields(&form("LimitBuy", 1.0))).unwrap();
+    assert!(trade.is_none());
+    assert!(errors.unwrap().starts_with("Insufficient balance"));
+    assert_eq!(Trade::list(conn).unwrap().len(), 1);
+    assert_eq!(Wallet::find_by_id(conn, wallet_id.clone()).unwrap().unwrap().balance, balance);
+
+    let (trade, _) = Trade::create(conn, &mut fill_optional_fields(&form("MarketSell", 1.0))).unwrap();
+    let trade = trade.unwrap();
+    let balance = balance + 100.0 - trade.execution_fee - trade.transaction_fee;
+    assert_eq!(Wallet::find_by_id(conn, wallet_id).unwrap().unwrap().balance, balance);
+}
+
+#[test]
+fn updates_and_deletes_resettle_the_wallet() {
+    let conn = &mut test_connection();
+    let (user_id, wallet_id) = create_user(conn);
+    WalletTransaction::deposit(conn, wallet_id.clone(), 250.0, None).unwrap();
+    let balance = |conn: &mut SqliteConnection| Wallet::find_by_id(conn, wallet_id.clone()).unwrap().unwrap().balance;
+    let form = |quantity: f32| TradeForm { amount: 100.0 * quantity, traded_amount: Some(quantity), ..trade(&user_id, &wallet_id) };
+
+    let trade = Trade::create(conn, &mut fill_optional_fields(&form(1.0))).unwrap().0.unwrap();
+    let fees = trade.execution_fee + trade.transaction_fee;
+    assert!((balance(conn) - (150.0 - fees)).abs() < 1e-3);
+
+    let (updated, errors) = Trade::update(conn, trade.id.clone(), &mut fill_optional_fields(&form(2.0)), &user_id).unwrap();
+    assert!(errors.is_none());
+    assert_eq!(updated.unwrap().traded_amount, 2.0);
+    assert!((balance(conn) - (50.0 - fees)).abs() < 1e-3);
+    let history = WalletTransaction::list_for_wallet(conn, wallet_id.clone(), 10, 0).unwrap();
+    assert_eq!((history[0].kind.as_str(), history[0].amount), (TransactionKind::TRADE_SETTLEMENT, -100.0));
+    assert_eq!(history[0].reference, Some(trade.id.clone()));
+
+    let (updated, errors) = Trade::update(conn, trade.id.clone(), &mut fill_optional_fields(&form(3.0)), &user_id).unwrap();
+    assert!(updated.is_none());
+    assert!(errors.unwrap().starts_with("Insufficient balance"));
+    assert_eq!(Trade::find_by_id(conn, trade.id.clone()).unwrap().unwrap().traded_amount, 2.0);
+    assert!((balance(conn) - (50.0 - fees)).abs() < 1e-3);
+
+    assert_eq!(Trade::delete(conn, trade.id.clone(), &user_id).unwrap(), (true, None));
+    assert!((balance(conn) - 250.0).abs() < 1e-3);
+    let history = WalletTransaction::list_for_wallet(conn, wallet_id.clone(), 10, 0).unwrap();
+    let net: f32 = history.iter().filter(|entry| entry.reference.as_deref() == Some(trade.id.as_str())).map(|entry| entry.amount).sum();
+    assert!(net.abs() < 1e-3);
+}
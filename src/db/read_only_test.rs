@@ -0,0 +1,30 @@
+use diesel::prelude::*;
+use uuid::Uuid;
+
+use super::models::wallet::Wallet;
+use super::schema::wallet;
+use super::{read_only_pool, read_only_url, run_migrations};
+
+#[test]
+fn read_only_urls_keep_existing_parameters() {
+    assert_eq!(read_only_url("trades.db"), "file:trades.db?mode=ro");
+    assert_eq!(read_only_url("file:/data/trades.db"), "file:/data/trades.db?mode=ro");
+    assert_eq!(read_only_url("file:/data/trades.db?cache=shared"), "file:/data/trades.db?cache=shared&mode=ro");
+}
+
+#[test]
+fn read_only_pools_read_but_never_write() {
+    let path = std::env::temp_dir().join(format!("replica-{}.db", Uuid::new_v4().simple()));
+    let path = path.to_str().unwrap().to_string();
+    let writer = &mut SqliteConnection::establish(&path).unwrap();
+    run_migrations(writer).unwrap();
+    let created = Wallet::create(writer).unwrap().unwrap();
+
+    let replica = &mut read_only_pool(&path, 2).get().unwrap();
+    assert_eq!(Wallet::find_by_id(replica, created.id.clone()).unwrap().map(|found| found.id), Some(created.id.clone()));
+    assert!(Wallet::create(replica).is_err());
+    assert!(diesel::delete(wallet::table).execute(replica).is_err());
+    assert_eq!(wallet::table.count().get_result::<i64>(replica).unwrap(), 1);
+
+    std::fs::remove_file(&path).unwrap();
+}
@@ -0,0 +1,52 @@
+//! This module provides a retry helper for database writes that hit transient SQLite lock errors.
+//!
+//! SQLite returns `SQLITE_BUSY` ("database is locked") when another connection holds the write lock for longer than
+//! the configured `busy_timeout`. `retry_on_busy` re-runs the write with exponential backoff plus random jitter, so
+//! concurrent writers do not retry in lockstep. Any other error is returned immediately, and a write that is still
+//! busy after the last attempt is reported as `DbError::Busy`.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use crate::db::retry::retry_on_busy;
+//!
+//! retry_on_busy(|| {
+//!     diesel::insert_into(trades_dsl)
+//!         .values(&trade)
+//!         .execute(conn)
+//! })?;
+//! ```
+
+use std::thread::sleep;
+use std::time::Duration;
+
+use diesel::result::QueryResult;
+use rand::Rng;
+
+use super::error::{is_busy, DbError};
+
+const MAX_ATTEMPTS: u32 = 5;
+const BASE_DELAY_MS: u64 = 10;
+
+pub fn backoff_delay(attempt: u32) -> Duration {
+    let base = BASE_DELAY_MS * 2u64.pow(attempt);
+    let jitter = rand::thread_rng().gen_range(0..=base);
+    Duration::from_millis(base + jitter)
+}
+
+pub fn retry_on_busy<T, F>(mut f: F) -> Result<T, DbError>
+where
+    F: FnMut() -> QueryResult<T>,
+{
+    let mut attempt = 0;
+    loop {
+        match f() {
+            Ok(value) => return Ok(value),
+            Err(err) if is_busy(&err) && attempt + 1 < MAX_ATTEMPTS => {
+                sleep(backoff_delay(attempt));
+                attempt += 1;
+            }
+            Err(err) => return Err(err.into()),
+        }
+    }
+}
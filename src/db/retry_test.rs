@@ -0,0 +1,46 @@
+use std::cell::Cell;
+
+use diesel::result::{DatabaseErrorKind, Error};
+
+use super::error::DbError;
+use super::retry::retry_on_busy;
+
+fn busy_error() -> Error {
+    Error::DatabaseError(DatabaseErrorKind::Unknown, Box::new("database is locked".to_string()))
+}
+
+#[test]
+fn retries_until_success() {
+    let attempts = Cell::new(0);
+
+    let result = retry_on_busy(|| {
+        attempts.set(attempts.get() + 1);
+        if attempts.get() < 3 {
+            Err(busy_error())
+        } else {
+            Ok(attempts.get())
+        }
+    });
+
+    assert_eq!(result.unwrap(), 3);
+}
+
+#[test]
+fn persistent_busy_becomes_busy_error() {
+    let result: Result<(), DbError> = retry_on_busy(|| Err(busy_error()));
+
+    assert!(matches!(result, Err(DbError::Busy)));
+}
+
+#[test]
+fn other_errors_are_not_retried() {
+    let attempts = Cell::new(0);
+
+    let result: Result<(), DbError> = retry_on_busy(|| {
+        attempts.set(attempts.get() + 1);
+        Err(Error::NotFound)
+    });
+
+    assert!(matches!(result, Err(DbError::Query(Error::NotFound))));
+    assert_eq!(attempts.get(), 1);
+}
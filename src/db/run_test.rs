@@ -0,0 +1,35 @@
+use std::time::Duration;
+
+use actix_web::http::StatusCode;
+use actix_web::ResponseError;
+use diesel::r2d2::{ConnectionManager, Pool};
+use diesel::sqlite::SqliteConnection;
+
+use crate::error::AppError;
+use super::fixtures::{funded_wallet, test_pool};
+use super::models::wallet::Wallet;
+use super::run;
+
+#[actix_web::test]
+async fn closures_return_their_result_to_the_handler() {
+    let pool = test_pool();
+    let wallet = run(&pool, |conn| Ok(funded_wallet(conn))).await.unwrap();
+    let found = run(&pool, move |conn| Ok(Wallet::find_by_id(conn, wallet.id)?)).await.unwrap();
+    assert!(found.is_some());
+
+    let err = run(&pool, |_| Err::<(), _>(AppError::NotFound("Wallet not found".to_string()))).await.unwrap_err();
+    assert_eq!(err.status_code(), StatusCode::NOT_FOUND);
+}
+
+#[actix_web::test]
+async fn an_exhausted_pool_is_busy() {
+    let pool = Pool::builder()
+        .max_size(1)
+        .connection_timeout(Duration::from_millis(50))
+        .build(ConnectionManager::<SqliteConnection>::new(":memory:"))
+        .unwrap();
+    let _held = pool.get().unwrap();
+
+    let err = run(&pool, |_| Ok(())).await.unwrap_err();
+    assert_eq!(err.status_code(), StatusCode::SERVICE_UNAVAILABLE);
+}
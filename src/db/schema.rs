@@ -1,8 +1,19 @@
 //! This module defines the Diesel schema for the database tables used in the application.
 //!
-//! It includes the Diesel table definitions for the `trades`, `users`, and `wallet` tables.
-//! These tables represent different aspects of the application's data, including trade activities,
-//! user information, and wallet details.
+//! It includes the Diesel table definitions for the `trades`, `users`, and `wallet` tables, along with the
+//! `wallet_transactions` ledger, the wallet approval tables (`wallet_approval_policies`, `wallet_approvers`,
+//! `wallet_transfers` and `wallet_transfer_approvals`), the `email_trade_reviews` queue, `tombstones` for deleted
+//! entities, the `advisor_clients` links, `trade_delegations`, `trade_requests`, `refresh_tokens`, `chain_explorers`,
+//! `positions`, `daily_snapshots`, `recompute_jobs`, `fee_schedules`, `fee_overrides`, `price_fixings`, `account_merges`, `email_changes`, the
+//! `outbound_emails` outbox, `user_settings`, the `user_wallets` links, `known_devices`, `login_attempts`, `password_resets`, the `audit_log` and the `trade_audit` trail. These
+//! tables represent different aspects of the application's data, including trade activities, user information, wallet details and their
+//! credit/debit history, multi-signature transfer approvals, emailed trade confirmations awaiting review, deletions for
+//! client sync, the client accounts an advisor may view, the users allowed to enter or propose trades on someone else's
+//! behalf, the refresh tokens of login sessions, the block explorer link templates configured per chain, the per-asset
+//! positions and daily totals derived from each user's trades, the progress of admin recompute jobs, the fee rates in
+//! force over time and the rates negotiated by single users, the end-of-day price fixings, merged duplicate accounts, pending email address changes, emails waiting to be delivered, each
+//! user's preferences, the named wallets each user owns, the devices users have logged in from, every login attempt, password reset tokens, a record of administrative actions and the history of
+//! every change to a trade.
 //!
 //! Additionally, this module establishes relationships between tables using the `joinable!` macros,
 //! enabling convenient queries involving multiple tables.
@@ -15,6 +26,254 @@
 
 // @generated automatically by Diesel CLI.
 
+diesel::table! {
+    account_merges (id) {
+        id -> Text,
+        source_user_id -> Text,
+        target_user_id -> Text,
+        mapping -> Text,
+        merged_by -> Text,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    advisor_clients (id) {
+        id -> Text,
+        advisor_id -> Text,
+        client_id -> Text,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    audit_log (id) {
+        id -> Text,
+        actor_id -> Text,
+        action -> Text,
+        target_id -> Text,
+        details -> Text,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    chain_explorers (chain) {
+        chain -> Text,
+        tx_url_template -> Text,
+        updated_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    daily_snapshots (user_id, date) {
+        user_id -> Text,
+        date -> Date,
+        trade_count -> Integer,
+        volume -> Float,
+        fees -> Float,
+        pnl -> Float,
+        updated_at -> Timestamp,
+        fixing_id -> Text,
+    }
+}
+
+diesel::table! {
+    email_changes (id) {
+        id -> Text,
+        user_id -> Text,
+        old_email -> Text,
+        new_email -> Text,
+        created_at -> Timestamp,
+        expires_at -> Timestamp,
+        confirmed_at -> Nullable<Timestamp>,
+        cancelled_at -> Nullable<Timestamp>,
+    }
+}
+
+diesel::table! {
+    email_trade_reviews (id) {
+        id -> Text,
+        user_id -> Text,
+        sender -> Text,
+        subject -> Text,
+        body -> Text,
+        parsed -> Text,
+        reason -> Text,
+        status -> Text,
+        trade_id -> Nullable<Text>,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    fee_schedules (id) {
+        id -> Text,
+        execution_rate -> Float,
+        transaction_rate -> Float,
+        effective_from -> Timestamp,
+        effective_to -> Nullable<Timestamp>,
+        created_by -> Text,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    price_fixings (id) {
+        id -> Text,
+        source -> Text,
+        fixing_time -> Time,
+        effective_from -> Timestamp,
+        effective_to -> Nullable<Timestamp>,
+        created_by -> Text,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    fee_overrides (id) {
+        id -> Text,
+        user_id -> Text,
+        venue -> Nullable<Text>,
+        execution_rate -> Float,
+        transaction_rate -> Float,
+        effective_from -> Timestamp,
+        effective_to -> Nullable<Timestamp>,
+        created_by -> Text,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    known_devices (id) {
+        id -> Text,
+        user_id -> Text,
+        fingerprint -> Text,
+        ip_address -> Text,
+        user_agent -> Text,
+        created_at -> Timestamp,
+        last_seen_at -> Timestamp,
+        confirmed_at -> Nullable<Timestamp>,
+    }
+}
+
+diesel::table! {
+    login_attempts (id) {
+        id -> Text,
+        email -> Text,
+        user_id -> Nullable<Text>,
+        succeeded -> Bool,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    outbound_emails (id) {
+        id -> Text,
+        recipient -> Text,
+        subject -> Text,
+        body -> Text,
+        created_at -> Timestamp,
+        sent_at -> Nullable<Timestamp>,
+    }
+}
+
+diesel::table! {
+    password_resets (id) {
+        id -> Text,
+        user_id -> Text,
+        token_hash -> Text,
+        created_at -> Timestamp,
+        expires_at -> Timestamp,
+        used_at -> Nullable<Timestamp>,
+    }
+}
+
+diesel::table! {
+    positions (user_id, asset) {
+        user_id -> Text,
+        asset -> Text,
+        quantity -> Float,
+        average_entry_price -> Float,
+        realized_pnl -> Float,
+        last_price -> Float,
+        updated_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    recompute_jobs (id) {
+        id -> Text,
+        what -> Text,
+        range_start -> Nullable<Timestamp>,
+        range_end -> Nullable<Timestamp>,
+        status -> Text,
+        total -> Integer,
+        processed -> Integer,
+        error -> Nullable<Text>,
+        requested_by -> Text,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    refresh_tokens (id) {
+        id -> Text,
+        user_id -> Text,
+        token_hash -> Text,
+        created_at -> Timestamp,
+        expires_at -> Timestamp,
+        revoked_at -> Nullable<Timestamp>,
+    }
+}
+
+diesel::table! {
+    tombstones (id) {
+        id -> Text,
+        entity -> Text,
+        entity_id -> Text,
+        user_id -> Text,
+        deleted_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    trade_audit (id) {
+        id -> Text,
+        trade_id -> Text,
+        action -> Text,
+        actor_id -> Text,
+        previous -> Nullable<Text>,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    trade_delegations (id) {
+        id -> Text,
+        owner_id -> Text,
+        delegate_id -> Text,
+        scope -> Text,
+        created_at -> Timestamp,
+        revoked_at -> Nullable<Timestamp>,
+    }
+}
+
+diesel::table! {
+    trade_requests (id) {
+        id -> Text,
+        owner_id -> Text,
+        requested_by -> Text,
+        payload -> Text,
+        status -> Text,
+        trade_id -> Nullable<Text>,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+    }
+}
+
 diesel::table! {
     trades (id) {
         id -> Text,
@@ -32,6 +291,29 @@ diesel::table! {
         transaction_fee -> Float,
         created_at -> Timestamp,
         updated_at -> Timestamp,
+        entered_by -> Nullable<Text>,
+        tx_hash -> Nullable<Text>,
+        source -> Text,
+        deleted_at -> Nullable<Timestamp>,
+        metadata -> Nullable<Text>,
+    }
+}
+
+diesel::table! {
+    user_settings (user_id) {
+        user_id -> Text,
+        confirm_new_devices -> Bool,
+        updated_at -> Timestamp,
+        hide_from_leaderboard -> Bool,
+    }
+}
+
+diesel::table! {
+    user_wallets (wallet_id) {
+        wallet_id -> Text,
+        user_id -> Text,
+        name -> Text,
+        created_at -> Timestamp,
     }
 }
 
@@ -57,12 +339,115 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    wallet_approval_policies (wallet_id) {
+        wallet_id -> Text,
+        threshold -> Float,
+        required_approvals -> Integer,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    wallet_approvers (id) {
+        id -> Text,
+        wallet_id -> Text,
+        user_id -> Text,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    wallet_transactions (id) {
+        id -> Text,
+        wallet_id -> Text,
+        kind -> Text,
+        amount -> Float,
+        balance_after -> Float,
+        reference -> Nullable<Text>,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    wallet_transfer_approvals (id) {
+        id -> Text,
+        transfer_id -> Text,
+        user_id -> Text,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    wallet_transfers (id) {
+        id -> Text,
+        wallet_id -> Text,
+        requested_by -> Text,
+        amount -> Float,
+        status -> Text,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+    }
+}
+
+diesel::joinable!(advisor_clients -> users (client_id));
+diesel::joinable!(daily_snapshots -> users (user_id));
+diesel::joinable!(email_trade_reviews -> trades (trade_id));
+diesel::joinable!(email_trade_reviews -> users (user_id));
+diesel::joinable!(fee_overrides -> users (user_id));
+diesel::joinable!(positions -> users (user_id));
+diesel::joinable!(password_resets -> users (user_id));
+diesel::joinable!(refresh_tokens -> users (user_id));
+diesel::joinable!(tombstones -> users (user_id));
+diesel::joinable!(trade_audit -> trades (trade_id));
+diesel::joinable!(trade_delegations -> users (owner_id));
+diesel::joinable!(trade_requests -> trades (trade_id));
+diesel::joinable!(trade_requests -> users (owner_id));
 diesel::joinable!(trades -> users (user_id));
 diesel::joinable!(trades -> wallet (wallet_id));
+diesel::joinable!(user_wallets -> users (user_id));
+diesel::joinable!(user_wallets -> wallet (wallet_id));
 diesel::joinable!(users -> wallet (wallet_id));
+diesel::joinable!(wallet_approval_policies -> wallet (wallet_id));
+diesel::joinable!(wallet_approvers -> users (user_id));
+diesel::joinable!(wallet_approvers -> wallet (wallet_id));
+diesel::joinable!(wallet_transactions -> wallet (wallet_id));
+diesel::joinable!(wallet_transfer_approvals -> users (user_id));
+diesel::joinable!(wallet_transfer_approvals -> wallet_transfers (transfer_id));
+diesel::joinable!(wallet_transfers -> users (requested_by));
+diesel::joinable!(wallet_transfers -> wallet (wallet_id));
 
 diesel::allow_tables_to_appear_in_same_query!(
+    account_merges,
+    advisor_clients,
+    audit_log,
+    chain_explorers,
+    daily_snapshots,
+    email_changes,
+    email_trade_reviews,
+    fee_overrides,
+    fee_schedules,
+    known_devices,
+    login_attempts,
+    outbound_emails,
+    password_resets,
+    positions,
+    price_fixings,
+    recompute_jobs,
+    refresh_tokens,
+    tombstones,
+    trade_audit,
+    trade_delegations,
+    trade_requests,
     trades,
+    user_settings,
+    user_wallets,
     users,
     wallet,
+    wallet_approval_policies,
+    wallet_approvers,
+    wallet_transactions,
+    wallet_transfer_approvals,
+    wallet_transfers,
 );
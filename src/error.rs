@@ -0,0 +1,174 @@
+//! This module defines `AppError`, the error type handlers turn into HTTP error responses.
+//!
+//! Every variant maps to one status code and a stable, machine-readable `code`, and is rendered as a JSON body of the
+//! form `{"code": "not_found", "message": "Trade not found", "request_id": "..."}`. `request_id` is the ID of the request
+//! being handled (see `middleware::request_log`), to correlate a reported error with the server logs; it is left out
+//! when there is no request:
+//!
+//! - `Validation`: `400 Bad Request`, code `validation_error`.
+//! - `Unauthorized`: `401 Unauthorized`, code `unauthorized`.
+//! - `Forbidden`: `403 Forbidden`, code `forbidden`.
+//! - `NotFound`: `404 Not Found`, code `not_found`.
+//! - `Conflict`: `409 Conflict`, code `conflict`.
+//! - `Locked`: `423 Locked` with a `Retry-After` header, code `account_locked`, for logins to a locked account.
+//! - `Busy`: `503 Service Unavailable` with a `Retry-After` header, code `database_busy`.
+//! - `Internal`: `500 Internal Server Error`, code `internal_error`. The underlying error is logged, not returned.
+//!
+//! Database errors convert into `AppError` through `From<DbError>`: lock contention becomes `Busy`, a missing row
+//! becomes `NotFound`, unique and foreign key violations become `Conflict`, and anything else is `Internal`.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use actix_web::ResponseError;
+//! use crate::error::AppError;
+//!
+//! match Trade::find_by_id(conn, trade_id) {
+//!     Ok(Some(trade)) => HttpResponse::Ok().json(trade),
+//!     Ok(None) => AppError::NotFound("Trade not found".to_string()).error_response(),
+//!     Err(err) => AppError::from(err).error_response(),
+//! }
+//! ```
+
+use std::fmt;
+
+use actix_web::http::header::RETRY_AFTER;
+use actix_web::http::StatusCode;
+use actix_web::{HttpResponse, ResponseError};
+use diesel::result::{DatabaseErrorKind, Error};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::db::error::DbError;
+use crate::middleware::request_log::current_request_id;
+
+#[derive(Debug, PartialEq)]
+pub enum AppError {
+    Validation(String),
+    Unauthorized(String),
+    Forbidden(String),
+    NotFound(String),
+    Conflict(String),
+    // Until when.
+    Locked(chrono::NaiveDateTime),
+    Busy,
+    Internal(String),
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, ToSchema)]
+pub struct ErrorBody {
+    pub code: String,
+    pub message: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
+}
+
+impl AppError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            AppError::Validation(_) => "validation_error",
+            AppError::Unauthorized(_) => "unauthorized",
+            AppError::Forbidden(_) => "forbidden",
+            AppError::NotFound(_) => "not_found",
+            AppError::Conflict(_) => "conflict",
+            AppError::Locked(_) => "account_locked",
+            AppError::Busy => "database_busy",
+            AppError::Internal(_) => "internal_error",
+        }
+    }
+
+    pub fn body(&self) -> ErrorBody {
+        let message = match self {
+            AppError::Internal(_) => "Internal server error".to_string(),
+            _ => self.to_string(),
+        };
+        ErrorBody { code: self.code().to_string(), message, request_id: current_request_id() }
+    }
+}
+
+// Whole seconds from now until `until`, at least 1.
+fn retry_after_seconds(until: chrono::NaiveDateTime) -> i64 {
+    (until - chrono::Local::now().naive_local()).num_seconds().max(1)
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AppError::Validation(message)
+            | AppError::Unauthorized(message)
+            | AppError::Forbidden(message)
+            | AppError::NotFound(message)
+            | AppError::Conflict(message)
+            | AppError::Internal(message) => write!(f, "{}", message),
+            AppError::Locked(until) => write!(
+                f,
+                "Too many failed login attempts; try again in {} minute(s)",
+                (retry_after_seconds(*until) + 59) / 60
+            ),
+            AppError::Busy => write!(f, "Database is busy, please retry later"),
+        }
+    }
+}
+
+impl std::error::Error for AppError {}
+
+impl From<&DbError> for AppError {
+    fn from(err: &DbError) -> Self {
+        match err {
+            DbError::Busy => AppError::Busy,
+            DbError::Query(Error::NotFound) => AppError::NotFound("Record not found".to_string()),
+            DbError::Query(Error::DatabaseError(DatabaseErrorKind::UniqueViolation, _)) => {
+                AppError::Conflict("Record already exists".to_string())
+            }
+            DbError::Query(Error::DatabaseError(DatabaseErrorKind::ForeignKeyViolation, _)) => {
+                AppError::Conflict("Record references missing or dependent data".to_string())
+            }
+            DbError::Query(err) => AppError::Internal(format!("Database error: {}", err)),
+        }
+    }
+}
+
+impl From<DbError> for AppError {
+    fn from(err: DbError) -> Self {
+        AppError::from(&err)
+    }
+}
+
+impl From<Error> for AppError {
+    fn from(err: Error) -> Self {
+        AppError::from(DbError::from(err))
+    }
+}
+
+impl ResponseError for AppError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            AppError::Validation(_) => StatusCode::BAD_REQUEST,
+            AppError::Unauthorized(_) => StatusCode::UNAUTHORIZED,
+            AppError::Forbidden(_) => StatusCode::FORBIDDEN,
+            AppError::NotFound(_) => StatusCode::NOT_FOUND,
+            AppError::Conflict(_) => StatusCode::CONFLICT,
+            AppError::Locked(_) => StatusCode::LOCKED,
+            AppError::Busy => StatusCode::SERVICE_UNAVAILABLE,
+            AppError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        if let AppError::Internal(message) = self {
+            log::error!("{}", message);
+        }
+
+        let mut response = HttpResponse::build(self.status_code());
+        match self {
+            AppError::Busy => {
+                response.insert_header((RETRY_AFTER, "1"));
+            }
+            AppError::Locked(until) => {
+                response.insert_header((RETRY_AFTER, retry_after_seconds(*until).to_string()));
+            }
+            _ => (),
+        }
+        response.json(self.body())
+    }
+}
@@ -0,0 +1,53 @@
+use actix_web::http::header::RETRY_AFTER;
+use actix_web::http::StatusCode;
+use actix_web::ResponseError;
+use diesel::result::{DatabaseErrorKind, Error};
+
+use crate::db::error::DbError;
+use crate::error::{AppError, ErrorBody};
+
+fn violation(kind: DatabaseErrorKind) -> DbError {
+    DbError::Query(Error::DatabaseError(kind, Box::new("constraint failed".to_string())))
+}
+
+#[test]
+fn maps_variants_to_status_codes() {
+    let cases = [
+        (AppError::Validation("bad".to_string()), StatusCode::BAD_REQUEST, "validation_error"),
+        (AppError::Unauthorized("no".to_string()), StatusCode::UNAUTHORIZED, "unauthorized"),
+        (AppError::Forbidden("denied".to_string()), StatusCode::FORBIDDEN, "forbidden"),
+        (AppError::NotFound("missing".to_string()), StatusCode::NOT_FOUND, "not_found"),
+        (AppError::Conflict("taken".to_string()), StatusCode::CONFLICT, "conflict"),
+        (AppError::Locked(chrono::Local::now().naive_local()), StatusCode::LOCKED, "account_locked"),
+        (AppError::Busy, StatusCode::SERVICE_UNAVAILABLE, "database_busy"),
+        (AppError::Internal("boom".to_string()), StatusCode::INTERNAL_SERVER_ERROR, "internal_error"),
+    ];
+
+    for (err, status, code) in cases {
+        assert_eq!(err.error_response().status(), status);
+        assert_eq!(err.code(), code);
+    }
+    assert!(AppError::Busy.error_response().headers().contains_key(RETRY_AFTER));
+
+    let locked = AppError::Locked(chrono::Local::now().naive_local() + chrono::Duration::minutes(10)).error_response();
+    let retry_after: i64 = locked.headers().get(RETRY_AFTER).unwrap().to_str().unwrap().parse().unwrap();
+    assert!((590..=600).contains(&retry_after));
+}
+
+#[test]
+fn internal_errors_hide_details() {
+    let body = AppError::Internal("Database error: disk I/O error".to_string()).body();
+
+    assert_eq!(body, ErrorBody { code: "internal_error".to_string(), message: "Internal server error".to_string(), request_id: None });
+    assert_eq!(AppError::NotFound("Trade not found".to_string()).body().message, "Trade not found");
+}
+
+#[test]
+fn converts_database_errors() {
+    assert_eq!(AppError::from(DbError::Busy), AppError::Busy);
+    assert_eq!(AppError::from(Error::NotFound), AppError::NotFound("Record not found".to_string()));
+    assert!(matches!(AppError::from(violation(DatabaseErrorKind::UniqueViolation)), AppError::Conflict(_)));
+    assert!(matches!(AppError::from(violation(DatabaseErrorKind::ForeignKeyViolation)), AppError::Conflict(_)));
+    assert!(matches!(AppError::from(DbError::Query(Error::RollbackTransaction)), AppError::Internal(_)));
+    assert_eq!(violation(DatabaseErrorKind::UniqueViolation).status_code(), StatusCode::CONFLICT);
+}
@@ -0,0 +1,39 @@
+//! Trade Management System library.
+//!
+//! This crate exposes the modules that make up the Trade Management System so they can be shared between the
+//! HTTP server binary and auxiliary targets such as fuzzers.
+
+/// The diesel crate is used for interacting with databases.
+extern crate diesel;
+
+/// The diesel_migrations crate is used for handling database migrations.
+extern crate diesel_migrations;
+
+/// The serde_json crate is used for serializing and deserializing JSON data.
+extern crate serde_json;
+
+/// The utils module contains utility functions and structures.
+pub mod utils;
+
+/// The config module resolves secrets from the environment, mounted files and Vault.
+pub mod config;
+
+/// The error module contains the error type handlers turn into HTTP error responses.
+pub mod error;
+
+/// The db module contains functions and structures for database interaction.
+pub mod db;
+
+/// The services module contains the business logic of the application.
+pub mod services;
+
+/// The middleware module contains middleware functions for the application.
+pub mod middleware;
+
+// Import error tests (only included in test builds)
+#[cfg(test)]
+mod error_test;
+
+// Import config tests (only included in test builds)
+#[cfg(test)]
+mod config_test;
@@ -1,50 +1,137 @@
-/// The diesel crate is used for interacting with databases.
-extern crate diesel;
-
-/// The diesel_migrations crate is used for handling database migrations.
-extern crate diesel_migrations;
-
-/// The serde_json crate is used for serializing and deserializing JSON data.
-extern crate serde_json;
-
-/// The r2d2_diesel crate is used for managing database connections.
-extern crate r2d2_diesel;
+use std::time::Duration;
 
 /// Importing necessary components from the actix_web crate.
 use actix_web::{App, HttpServer, web::{JsonConfig, Data}};
-use env_logger;
-
-/// The utils module contains utility functions and structures.
-mod utils;
+use tracing_actix_web::TracingLogger;
+use tracing_subscriber::EnvFilter;
 
-/// The db module contains functions and structures for database interaction.
-mod db;
-
-/// The services module contains the business logic of the application.
-mod services;
-
-/// The middleware module contains middleware functions for the application.
-mod middleware;
+/// Importing the application modules from the library crate.
+use trade_management_system::{config, db, services};
+use trade_management_system::config::settings;
+use trade_management_system::middleware::cors::cors;
+use trade_management_system::middleware::request_log::RequestLog;
+use trade_management_system::services::journal::TradeJournal;
+use trade_management_system::services::mailer::{self, Mailer};
+use trade_management_system::services::prices::{self, PriceCache};
+use trade_management_system::utils::hash;
+use trade_management_system::utils::qr::QrCache;
 
 /// The main function of the application. It sets up the server and starts it.
 #[actix_rt::main]
 async fn main() -> std::io::Result<()> {
-    // Set the logging level and initialize the logger.
-    std::env::set_var("RUST_LOG", "debug");
-    env_logger::init();
+    // Read the bind address, worker tuning, pool size, token lifetimes and log level from the settings file and `APP_*` variables.
+    let settings = settings::load().expect("Failed to load settings");
+
+    // Set the logging level (`RUST_LOG` wins over the settings) and log as JSON lines, each carrying the span (and
+    // request ID) it was written in.
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(&settings.log.level));
+    tracing_subscriber::fmt()
+        .json()
+        .with_env_filter(filter)
+        .with_current_span(true)
+        .with_span_list(false)
+        .init();
+    log::info!("{}", services::version::banner());
     
-    // Establish a connection pool to the database.
+    // Resolve secrets from mounted files and Vault before anything reads them.
+    config::load().expect("Failed to load secrets");
+
+    // Refuse to generate wallet keys from a broken entropy source.
+    hash::entropy_self_test().expect("Entropy self-test failed");
+
+    // Establish a connection pool to the database, read-only on a replica.
     let conn_pool = db::establish_connection();
+    let read_only = settings.server.read_only;
+
+    // Replay trade requests that were journaled but not completed before the last shutdown. Replicas leave it to the
+    // writer.
+    let journal = Data::new(TradeJournal::from_env());
+    if !read_only {
+        let mut conn = conn_pool.get().expect("Failed to get a connection from the pool");
+        let replayed = journal.recover(&mut conn).expect("Failed to recover trade journal");
+        log::info!("Recovered {} journaled trade request(s)", replayed);
+    }
+
+    // Keep rendered QR codes in memory across requests.
+    let qr_cache = Data::new(QrCache::from_env());
+
+    // Keep current asset prices in memory, refreshed in the background, to mark positions to market.
+    let price_cache = Data::new(PriceCache::from_env());
+    prices::spawn_refresh(price_cache.clone());
+
+    // Deliver queued emails (confirmations, password resets) through the configured mail provider. Replicas leave it
+    // to the writer.
+    if read_only {
+        log::info!("Running as a read-only replica: only the replica routes are served");
+    } else {
+        match Mailer::from_env() {
+            Some(mailer) => mailer::spawn_delivery(mailer, conn_pool.clone()),
+            None => log::warn!("EMAIL_API_URL is not set: queued emails will not be delivered"),
+        }
+    }
+
+    // Log the effective capacity settings, including the ones derived from the machine.
+    let server = &settings.server;
+    log::info!(
+        "Starting {} worker(s) on {}:{} with up to {} connection(s) and {} blocking thread(s) each, a {}ms client timeout and {} database connection(s)",
+        server.workers(),
+        server.host,
+        server.port,
+        server.max_connections,
+        server.blocking_threads(),
+        server.client_timeout_ms,
+        settings.database.pool_size
+    );
 
     // Start the HTTP server.
     HttpServer::new(move || {
-        App::new()
+        let app = App::new()
             .app_data(Data::new(conn_pool.clone())) // Share the database connection pool across the application.
-            .app_data(JsonConfig::default().limit(4096)) // Configure JSON payload size limit.
+            .app_data(journal.clone()) // Share the trade journal across the application.
+            .app_data(qr_cache.clone()) // Share the QR code cache across the application.
+            .app_data(price_cache.clone()) // Share the price feed cache across the application.
+            .app_data(JsonConfig::default().limit(settings.server.json_limit)) // Configure JSON payload size limit.
+            .wrap(cors(&settings.cors)) // Answer CORS preflight requests and allow the configured origins.
+            .wrap(RequestLog) // Log every request and return its ID in `X-Request-Id` and error bodies.
+            .wrap(TracingLogger::default()); // Open a span with a request ID around every request.
+        if read_only {
+            return app.configure(services::replica::init_routes); // Configure the read-only replica routes only.
+        }
+        app
             .configure(services::user::init_routes) // Configure user-related routes.
+            .configure(services::auth::init_routes) // Configure token refresh and logout routes.
             .configure(services::trade::init_routes) // Configure trade-related routes.
+            .configure(services::wallet::init_routes) // Configure wallet-related routes.
+            .configure(services::export::init_routes) // Configure export-sharing routes.
+            .configure(services::email_in::init_routes) // Configure inbound email gateway routes.
+            .configure(services::summary::init_routes) // Configure the home-screen summary route.
+            .configure(services::changes::init_routes) // Configure the incremental sync route.
+            .configure(services::advisor::init_routes) // Configure advisor overview routes.
+            .configure(services::delegation::init_routes) // Configure delegated trade entry routes.
+            .configure(services::inbox::init_routes) // Configure trade request inbox routes.
+            .configure(services::metadata::init_routes) // Configure asset and chain metadata routes.
+            .configure(services::chain_registry::init_routes) // Configure the admin chain registry routes.
+            .configure(services::portfolio::init_routes) // Configure the portfolio route.
+            .configure(services::recompute::init_routes) // Configure the admin recompute job routes.
+            .configure(services::fee_schedule::init_routes) // Configure the admin fee schedule and fee override routes.
+            .configure(services::price_fixing::init_routes) // Configure the admin price fixing routes.
+            .configure(services::account_merge::init_routes) // Configure the admin account merge routes.
+            .configure(services::audit_export::init_routes) // Configure the admin audit log export route.
+            .configure(services::migrations::init_routes) // Configure the admin migration status route.
+            .configure(services::email_change::init_routes) // Configure the email change routes.
+            .configure(services::password::init_routes) // Configure the password reset routes.
+            .configure(services::openapi::init_routes) // Configure the OpenAPI specification and Swagger UI routes.
+            .configure(services::settings::init_routes) // Configure the user settings and device routes.
+            .configure(services::analytics::init_routes) // Configure the risk and trade statistics routes.
+            .configure(services::admin_analytics::init_routes) // Configure the admin platform analytics route.
+            .configure(services::metrics::init_routes) // Configure the business metrics route.
+            .configure(services::version::init_routes) // Configure the build information route.
     })
-    .bind(("127.0.0.1", 9000))? // Bind the server to a specific address and port.
+    .workers(server.workers()) // Set the number of worker threads.
+    .max_connections(server.max_connections) // Limit the concurrent connections per worker.
+    .client_request_timeout(Duration::from_millis(server.client_timeout_ms)) // Time out clients slow to send a request.
+    .worker_max_blocking_threads(server.blocking_threads()) // Size the blocking thread pool of each worker.
+    .bind((server.host.as_str(), server.port))? // Bind the server to the configured address and port.
     .run()
     .await    
 }
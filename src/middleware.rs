@@ -1 +1,16 @@
-pub mod jwt_guard;
\ No newline at end of file
+pub mod cors;
+pub mod jwt_guard;
+pub mod load_shed;
+pub mod request_log;
+
+#[cfg(test)]
+mod cors_test;
+
+#[cfg(test)]
+mod load_shed_test;
+
+#[cfg(test)]
+mod jwt_guard_test;
+
+#[cfg(test)]
+mod request_log_test;
@@ -0,0 +1,39 @@
+//! This module builds the CORS middleware that lets the single-page app call the API from another origin.
+//!
+//! `cors` turns the `cors` section of the settings (`config::settings`) into an `actix_cors::Cors`:
+//!
+//! - only the origins in `allowed_origins` are allowed, compared exactly. With an empty list, which is the default,
+//!   cross-origin requests are refused and same-origin clients are unaffected;
+//! - preflight (`OPTIONS`) requests are answered by the middleware itself with the allowed `allowed_methods` and
+//!   `allowed_headers`, cached by the browser for `max_age_seconds`. Since it wraps the whole application, preflight
+//!   requests never reach `JwtGuard`, which would otherwise reject them for lacking a token;
+//! - `X-Request-Id`, `X-Excluded-Trades` and `Retry-After` are exposed to scripts. Errors, including `401`s from
+//!   `JwtGuard`, carry the CORS headers too, so the app can read them.
+//!
+//! Tokens travel in the `Authorization` header rather than cookies, so credentials are not enabled.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use actix_web::App;
+//! use crate::config::settings;
+//! use crate::middleware::cors::cors;
+//!
+//! let app = App::new().wrap(cors(&settings::get().cors));
+//! ```
+
+use actix_cors::Cors;
+
+use crate::config::settings::CorsSettings;
+use crate::middleware::request_log::REQUEST_ID_HEADER;
+
+pub const EXPOSED_HEADERS: [&str; 3] = [REQUEST_ID_HEADER, "x-excluded-trades", "retry-after"];
+
+pub fn cors(settings: &CorsSettings) -> Cors {
+    let cors = settings.allowed_origins.iter().fold(Cors::default(), |cors, origin| cors.allowed_origin(origin));
+
+    cors.allowed_methods(settings.allowed_methods.iter().map(String::as_str))
+        .allowed_headers(settings.allowed_headers.iter().map(String::as_str))
+        .expose_headers(EXPOSED_HEADERS)
+        .max_age(settings.max_age_seconds)
+}
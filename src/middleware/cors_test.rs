@@ -0,0 +1,86 @@
+use actix_web::http::header::{
+    ACCESS_CONTROL_ALLOW_METHODS, ACCESS_CONTROL_ALLOW_ORIGIN, ACCESS_CONTROL_EXPOSE_HEADERS,
+    ACCESS_CONTROL_REQUEST_HEADERS, ACCESS_CONTROL_REQUEST_METHOD, ORIGIN,
+};
+use actix_web::{test, web, App, HttpResponse};
+
+use super::cors::cors;
+use super::jwt_guard::JwtGuard;
+use super::request_log::RequestLog;
+use crate::config::settings::CorsSettings;
+
+const APP_ORIGIN: &str = "https://app.example.com";
+
+fn settings() -> CorsSettings {
+    CorsSettings { allowed_origins: vec![APP_ORIGIN.to_string()], ..CorsSettings::default() }
+}
+
+async fn trades() -> HttpResponse {
+    HttpResponse::Ok().body("trades")
+}
+
+macro_rules! app {
+    () => {
+        test::init_service(
+            App::new()
+                .wrap(cors(&settings()))
+                .wrap(RequestLog)
+                .route("/trade", web::get().to(trades).wrap(JwtGuard))
+                .route("/open", web::get().to(trades)),
+        )
+        .await
+    };
+}
+
+#[actix_web::test]
+async fn answers_preflight_of_guarded_routes() {
+    let app = app!();
+
+    let req = test::TestRequest::default()
+        .method(actix_web::http::Method::OPTIONS)
+        .uri("/trade")
+        .insert_header((ORIGIN, APP_ORIGIN))
+        .insert_header((ACCESS_CONTROL_REQUEST_METHOD, "GET"))
+        .insert_header((ACCESS_CONTROL_REQUEST_HEADERS, "authorization"))
+        .to_request();
+    let res = test::call_service(&app, req).await;
+
+    assert_eq!(res.status(), 200);
+    assert_eq!(res.headers().get(ACCESS_CONTROL_ALLOW_ORIGIN).unwrap(), APP_ORIGIN);
+    assert!(res.headers().get(ACCESS_CONTROL_ALLOW_METHODS).unwrap().to_str().unwrap().contains("GET"));
+}
+
+#[actix_web::test]
+async fn adds_headers_to_allowed_origins() {
+    let app = app!();
+
+    let req = test::TestRequest::get().uri("/open").insert_header((ORIGIN, APP_ORIGIN)).to_request();
+    let res = test::call_service(&app, req).await;
+
+    assert_eq!(res.status(), 200);
+    assert_eq!(res.headers().get(ACCESS_CONTROL_ALLOW_ORIGIN).unwrap(), APP_ORIGIN);
+    let exposed = res.headers().get(ACCESS_CONTROL_EXPOSE_HEADERS).unwrap().to_str().unwrap().to_string();
+    assert!(exposed.contains("x-request-id"));
+}
+
+#[actix_web::test]
+async fn adds_headers_to_rejected_tokens() {
+    let app = app!();
+
+    let req = test::TestRequest::get().uri("/trade").insert_header((ORIGIN, APP_ORIGIN)).to_request();
+    let res = test::try_call_service(&app, req).await.err().unwrap().error_response();
+
+    assert_eq!(res.status(), 401);
+    assert_eq!(res.headers().get(ACCESS_CONTROL_ALLOW_ORIGIN).unwrap(), APP_ORIGIN);
+}
+
+#[actix_web::test]
+async fn refuses_other_origins() {
+    let app = app!();
+
+    let req = test::TestRequest::get().uri("/open").insert_header((ORIGIN, "https://evil.example.com")).to_request();
+    let res = test::call_service(&app, req).await;
+
+    // Served without CORS headers, so the browser does not hand the response to the other origin's script.
+    assert!(res.headers().get(ACCESS_CONTROL_ALLOW_ORIGIN).is_none());
+}
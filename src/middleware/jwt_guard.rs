@@ -7,6 +7,9 @@
 //! - `JwtGuard`: A transformer that wraps the provided service with JWT authentication logic.
 //! - `JwtGuardMiddleware`: The middleware that performs the actual JWT token verification and user authentication.
 //!
+//! Requests are authenticated before they reach the wrapped service. The caller's identity is inserted into the
+//! request extensions as a `services::jwt::AuthenticatedUser`, which handlers read with `services::jwt::user_id`.
+//!
 //! These components are designed to integrate seamlessly into the Actix Web middleware chain, providing a secure way to protect routes.
 //!
 //! # Examples
@@ -54,7 +57,7 @@
 //! middleware chain to secure the desired routes.
 
 use actix_service::{Service, Transform};
-use actix_web::{dev::ServiceRequest, dev::ServiceResponse, Error};
+use actix_web::{dev::ServiceRequest, dev::ServiceResponse, Error, HttpMessage};
 use futures::future::{ok, Ready};
 use std::task::{Context, Poll};
 use futures_util::future::LocalBoxFuture;
@@ -98,13 +101,15 @@ where
     }
 
     fn call(&self, req: ServiceRequest) -> Self::Future {
-        let fut = self.service.call(req);
-        Box::pin(async move {
-            let res = fut.await?;
-
-            authenticate(res.request().clone())?;
-
-            Ok(res)
-        })
+        // Authenticate before the handler runs, so rejected requests never reach it.
+        match authenticate(req.request()) {
+            Ok(user) => {
+                if let Some(user) = user {
+                    req.extensions_mut().insert(user);
+                }
+                Box::pin(self.service.call(req))
+            }
+            Err(err) => Box::pin(async move { Err(err) }),
+        }
     }
 }
\ No newline at end of file
@@ -0,0 +1,116 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use actix_web::http::header::AUTHORIZATION;
+use actix_web::{test, web, App, HttpRequest, HttpResponse};
+use jsonwebtoken::{encode, EncodingKey, Header};
+use serde_json::json;
+
+use super::jwt_guard::JwtGuard;
+use crate::config::settings;
+use crate::services::jwt::{self, create_jwt};
+
+async fn whoami(req: HttpRequest, calls: web::Data<Arc<AtomicUsize>>) -> HttpResponse {
+    calls.fetch_add(1, Ordering::SeqCst);
+    HttpResponse::Ok().body(jwt::user_id(&req).unwrap_or_default())
+}
+
+#[actix_web::test]
+async fn rejects_requests_before_the_handler_runs() {
+    std::env::set_var("JWT_SECRET", "test_secret");
+    let calls = Arc::new(AtomicUsize::new(0));
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(calls.clone()))
+            .route("/whoami", web::post().to(whoami).wrap(JwtGuard)),
+    )
+    .await;
+
+    let req = test::TestRequest::post().uri("/whoami").to_request();
+    assert!(test::try_call_service(&app, req).await.is_err());
+    assert_eq!(calls.load(Ordering::SeqCst), 0);
+}
+
+#[actix_web::test]
+async fn injects_the_authenticated_user() {
+    std::env::set_var("JWT_SECRET", "test_secret");
+    let calls = Arc::new(AtomicUsize::new(0));
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(calls.clone()))
+            .route("/whoami", web::post().to(whoami).wrap(JwtGuard)),
+    )
+    .await;
+
+    let token = create_jwt("user-1".to_string()).unwrap();
+    let req = test::TestRequest::post().uri("/whoami").insert_header((AUTHORIZATION, token)).to_request();
+    let body = test::call_and_read_body(&app, req).await;
+    assert_eq!(body, "user-1");
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+}
+
+// A token with the given claims, signed like `create_jwt` signs them. `iss` and `aud` default to this deployment's.
+fn token_with(mut claims: serde_json::Value) -> String {
+    let jwt = &settings::get().jwt;
+    for (claim, value) in [("iss", &jwt.issuer), ("aud", &jwt.audience)] {
+        if claims.get(claim).is_none() {
+            claims[claim] = json!(value);
+        }
+    }
+    encode(&Header::default(), &claims, &EncodingKey::from_secret(b"test_secret")).unwrap()
+}
+
+async fn status_with(token: String) -> Option<u16> {
+    std::env::set_var("JWT_SECRET", "test_secret");
+    let calls = Arc::new(AtomicUsize::new(0));
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(calls.clone()))
+            .route("/whoami", web::post().to(whoami).wrap(JwtGuard)),
+    )
+    .await;
+
+    let req = test::TestRequest::post().uri("/whoami").insert_header((AUTHORIZATION, token)).to_request();
+    test::try_call_service(&app, req).await.ok().map(|res| res.status().as_u16())
+}
+
+#[actix_web::test]
+async fn tolerates_clock_skew_within_the_leeway() {
+    let now = chrono::Utc::now().timestamp();
+
+    // Expired, or not yet valid, by less than the default leeway of 60 seconds.
+    let expired = token_with(json!({ "id": "user-1", "iat": now - 900, "nbf": now - 900, "exp": now - 30 }));
+    assert_eq!(status_with(expired).await, Some(200));
+    let early = token_with(json!({ "id": "user-1", "iat": now + 30, "nbf": now + 30, "exp": now + 900 }));
+    assert_eq!(status_with(early).await, Some(200));
+}
+
+#[actix_web::test]
+async fn rejects_clock_skew_beyond_the_leeway() {
+    let now = chrono::Utc::now().timestamp();
+
+    let expired = token_with(json!({ "id": "user-1", "iat": now - 900, "nbf": now - 900, "exp": now - 300 }));
+    assert_eq!(status_with(expired).await, None);
+    let early = token_with(json!({ "id": "user-1", "iat": now + 300, "nbf": now + 300, "exp": now + 900 }));
+    assert_eq!(status_with(early).await, None);
+}
+
+#[actix_web::test]
+async fn accepts_tokens_without_issued_at_claims() {
+    let now = chrono::Utc::now().timestamp();
+
+    let legacy = token_with(json!({ "id": "user-1", "exp": now + 900 }));
+    assert_eq!(status_with(legacy).await, Some(200));
+}
+
+#[actix_web::test]
+async fn rejects_tokens_of_other_deployments() {
+    let now = chrono::Utc::now().timestamp();
+
+    let other_issuer = token_with(json!({ "id": "user-1", "exp": now + 900, "iss": "trade-staging" }));
+    assert_eq!(status_with(other_issuer).await, None);
+    let other_audience = token_with(json!({ "id": "user-1", "exp": now + 900, "aud": "trade-staging" }));
+    assert_eq!(status_with(other_audience).await, None);
+    let unscoped = encode(&Header::default(), &json!({ "id": "user-1", "exp": now + 900 }), &EncodingKey::from_secret(b"test_secret")).unwrap();
+    assert_eq!(status_with(unscoped).await, None);
+}
@@ -0,0 +1,150 @@
+//! This module defines a middleware that sheds load from low-priority routes when the application is overloaded.
+//!
+//! Every route wrapped with `LoadShed` reports its latency to a shared moving average. Routes tagged as low priority
+//! (analytics, exports) are rejected early with `503 Service Unavailable` and a `Retry-After` header when either the
+//! database pool has no idle connections left or the average latency exceeds the configured threshold. High-priority
+//! routes (authentication, trade writes) are never shed, so they stay responsive while the backlog drains.
+//!
+//! The thresholds are read from the environment:
+//! - `LOAD_SHED_LATENCY_MS`: average latency, in milliseconds, above which low-priority requests are shed (default `500`).
+//! - `LOAD_SHED_RETRY_AFTER`: value of the `Retry-After` header, in seconds (default `1`).
+//!
+//! # Examples
+//!
+//! ```rust
+//! use actix_web::web;
+//! use crate::middleware::load_shed::LoadShed;
+//!
+//! // ... imports ...
+//!
+//! pub fn init_routes(cfg: &mut web::ServiceConfig) {
+//!     cfg.service(web::resource("/profit-loss").route(web::get().to(profit_loss).wrap(LoadShed::low_priority())))
+//!         .service(web::resource("/trade").route(web::post().to(create_trade).wrap(LoadShed::high_priority())));
+//! }
+//! ```
+//!
+//! # Note
+//! The pool is looked up from the application data (`web::Data<DbPool>`). When it is not registered, only the latency
+//! signal is used.
+
+use actix_service::{Service, Transform};
+use actix_web::body::EitherBody;
+use actix_web::http::header::RETRY_AFTER;
+use actix_web::{dev::ServiceRequest, dev::ServiceResponse, web::Data, Error, HttpResponse};
+use futures::future::{ok, Ready};
+use futures_util::future::LocalBoxFuture;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::task::{Context, Poll};
+use std::time::Instant;
+
+use crate::db::DbPool;
+
+static AVERAGE_LATENCY_MS: AtomicU64 = AtomicU64::new(0);
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Priority {
+    High,
+    Low,
+}
+
+pub struct LoadShed {
+    priority: Priority,
+}
+
+impl LoadShed {
+    pub fn high_priority() -> Self {
+        LoadShed { priority: Priority::High }
+    }
+
+    pub fn low_priority() -> Self {
+        LoadShed { priority: Priority::Low }
+    }
+}
+
+pub fn average_latency_ms() -> u64 {
+    AVERAGE_LATENCY_MS.load(Ordering::Relaxed)
+}
+
+fn record_latency(sample_ms: u64) {
+    let average = AVERAGE_LATENCY_MS.load(Ordering::Relaxed);
+    AVERAGE_LATENCY_MS.store((average * 7 + sample_ms) / 8, Ordering::Relaxed);
+}
+
+fn env_u64(key: &str, default: u64) -> u64 {
+    std::env::var(key)
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(default)
+}
+
+pub fn is_pool_exhausted(pool: &DbPool) -> bool {
+    let state = pool.state();
+    state.idle_connections == 0 && state.connections >= pool.max_size()
+}
+
+pub fn should_shed(priority: Priority, pool_exhausted: bool, latency_ms: u64, latency_threshold_ms: u64) -> bool {
+    priority == Priority::Low && (pool_exhausted || latency_ms > latency_threshold_ms)
+}
+
+impl<S, B> Transform<S, ServiceRequest> for LoadShed
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = LoadShedMiddleware<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ok(LoadShedMiddleware { service, priority: self.priority })
+    }
+}
+
+pub struct LoadShedMiddleware<S> {
+    service: S,
+    priority: Priority,
+}
+
+impl<S, B> Service<ServiceRequest> for LoadShedMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx)
+    }
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let pool_exhausted = req
+            .app_data::<Data<DbPool>>()
+            .map(|pool| is_pool_exhausted(pool))
+            .unwrap_or(false);
+        let latency_threshold_ms = env_u64("LOAD_SHED_LATENCY_MS", 500);
+
+        if should_shed(self.priority, pool_exhausted, average_latency_ms(), latency_threshold_ms) {
+            // Shed requests count as instant responses so the average decays and traffic is let through again.
+            record_latency(0);
+            let retry_after = env_u64("LOAD_SHED_RETRY_AFTER", 1);
+            let response = HttpResponse::ServiceUnavailable()
+                .insert_header((RETRY_AFTER, retry_after.to_string()))
+                .json("Service overloaded, please retry later");
+            return Box::pin(async move { Ok(req.into_response(response).map_into_right_body()) });
+        }
+
+        let started_at = Instant::now();
+        let fut = self.service.call(req);
+        Box::pin(async move {
+            let res = fut.await;
+            record_latency(started_at.elapsed().as_millis() as u64);
+            res.map(ServiceResponse::map_into_left_body)
+        })
+    }
+}
@@ -0,0 +1,17 @@
+use super::load_shed::{should_shed, Priority};
+
+#[test]
+fn high_priority_is_never_shed() {
+    assert!(!should_shed(Priority::High, true, 10_000, 500));
+}
+
+#[test]
+fn low_priority_is_shed_when_pool_exhausted() {
+    assert!(should_shed(Priority::Low, true, 0, 500));
+}
+
+#[test]
+fn low_priority_is_shed_when_latency_exceeds_threshold() {
+    assert!(should_shed(Priority::Low, false, 501, 500));
+    assert!(!should_shed(Priority::Low, false, 500, 500));
+}
@@ -0,0 +1,137 @@
+//! This module defines a middleware that logs every request as one structured line and tags it with a request ID.
+//!
+//! The application is wrapped with `tracing_actix_web::TracingLogger`, which opens a span per request carrying a
+//! generated `request_id`; `main` installs a JSON `tracing_subscriber` that prints the current span with every event,
+//! so all log lines written while a request is handled (including `log` macros) carry its ID. `RequestLog` runs inside
+//! that span and:
+//!
+//! - emits a `request completed` event with `request_id`, `method`, `path`, `status`, `latency_ms` and the `user_id`
+//!   authenticated by `JwtGuard` (empty for anonymous requests),
+//! - returns the ID in the `X-Request-Id` response header,
+//! - makes the ID available to `current_request_id` while the request is handled, which `AppError` uses to add
+//!   `request_id` to error bodies. Errors returned by inner middleware (e.g. a rejected token) are rendered here, so
+//!   they are logged and carry the header too.
+//!
+//! When `TracingLogger` is not installed, a fresh UUID is used as the request ID.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use actix_web::App;
+//! use tracing_actix_web::TracingLogger;
+//! use crate::middleware::request_log::RequestLog;
+//!
+//! // `wrap` calls apply inside out: `TracingLogger` must come last so `RequestLog` sees its request ID.
+//! let app = App::new()
+//!     .wrap(RequestLog)
+//!     .wrap(TracingLogger::default());
+//! ```
+
+use actix_service::{Service, Transform};
+use actix_web::error::InternalError;
+use actix_web::http::header::{HeaderMap, HeaderName, HeaderValue};
+use actix_web::{dev::ServiceRequest, dev::ServiceResponse, Error, HttpMessage};
+use futures::future::{ok, Ready};
+use futures_util::future::LocalBoxFuture;
+use std::task::{Context, Poll};
+use std::time::Instant;
+use tracing_actix_web::RequestId;
+use uuid::Uuid;
+
+use crate::services::jwt;
+
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+tokio::task_local! {
+    static REQUEST_ID: String;
+}
+
+// The ID of the request being handled, if any.
+pub fn current_request_id() -> Option<String> {
+    REQUEST_ID.try_with(|id| id.clone()).ok()
+}
+
+fn set_header(headers: &mut HeaderMap, request_id: &str) {
+    if let Ok(value) = HeaderValue::from_str(request_id) {
+        headers.insert(HeaderName::from_static(REQUEST_ID_HEADER), value);
+    }
+}
+
+pub struct RequestLog;
+
+impl<S, B> Transform<S, ServiceRequest> for RequestLog
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = RequestLogMiddleware<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ok(RequestLogMiddleware { service })
+    }
+}
+
+pub struct RequestLogMiddleware<S> {
+    service: S,
+}
+
+impl<S, B> Service<ServiceRequest> for RequestLogMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx)
+    }
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let request_id = req
+            .extensions()
+            .get::<RequestId>()
+            .map(|id| id.to_string())
+            .unwrap_or_else(|| Uuid::new_v4().as_hyphenated().to_string());
+        let (method, path) = (req.method().to_string(), req.path().to_string());
+        let started_at = Instant::now();
+        let fut = self.service.call(req);
+
+        Box::pin(REQUEST_ID.scope(request_id.clone(), async move {
+            let result = fut.await;
+            let (status, user_id) = match &result {
+                Ok(res) => (res.status(), jwt::user_id(res.request())),
+                Err(err) => (err.as_response_error().status_code(), None),
+            };
+            tracing::info!(
+                request_id = %request_id,
+                method = %method,
+                path = %path,
+                status = status.as_u16(),
+                latency_ms = started_at.elapsed().as_millis() as u64,
+                user_id = %user_id.unwrap_or_default(),
+                "request completed"
+            );
+
+            match result {
+                Ok(mut res) => {
+                    set_header(res.headers_mut(), &request_id);
+                    Ok(res)
+                }
+                // Render the error now, while the request ID is set, and keep it an error for the outer middleware.
+                Err(err) => {
+                    let mut response = err.error_response();
+                    set_header(response.headers_mut(), &request_id);
+                    Err(InternalError::from_response(err, response).into())
+                }
+            }
+        }))
+    }
+}
@@ -0,0 +1,49 @@
+use actix_web::{test, web, App, HttpResponse, ResponseError};
+use tracing_actix_web::TracingLogger;
+
+use super::jwt_guard::JwtGuard;
+use super::request_log::{current_request_id, RequestLog, REQUEST_ID_HEADER};
+use crate::error::{AppError, ErrorBody};
+
+async fn missing() -> HttpResponse {
+    AppError::NotFound("Trade not found".to_string()).error_response()
+}
+
+async fn ok() -> HttpResponse {
+    HttpResponse::Ok().body(current_request_id().unwrap_or_default())
+}
+
+#[actix_web::test]
+async fn error_bodies_carry_the_request_id() {
+    let app = test::init_service(
+        App::new()
+            .wrap(RequestLog)
+            .wrap(TracingLogger::default())
+            .route("/missing", web::get().to(missing))
+            .route("/ok", web::get().to(ok)),
+    )
+    .await;
+
+    let res = test::call_service(&app, test::TestRequest::get().uri("/missing").to_request()).await;
+    let header = res.headers().get(REQUEST_ID_HEADER).unwrap().to_str().unwrap().to_string();
+    let body: ErrorBody = test::read_body_json(res).await;
+    assert_eq!(body.code, "not_found");
+    assert_eq!(body.request_id, Some(header.clone()));
+
+    let res = test::call_service(&app, test::TestRequest::get().uri("/ok").to_request()).await;
+    let other = res.headers().get(REQUEST_ID_HEADER).unwrap().to_str().unwrap().to_string();
+    assert_ne!(other, header);
+    assert_eq!(test::read_body(res).await, other.as_bytes());
+}
+
+#[actix_web::test]
+async fn rejected_tokens_carry_the_request_id() {
+    std::env::set_var("JWT_SECRET", "test_secret");
+    let app = test::init_service(App::new().wrap(RequestLog).route("/ok", web::get().to(ok).wrap(JwtGuard))).await;
+
+    let err = test::try_call_service(&app, test::TestRequest::get().uri("/ok").to_request()).await.err().unwrap();
+    let res = err.error_response();
+    assert_eq!(res.status(), 401);
+    assert!(res.headers().contains_key(REQUEST_ID_HEADER));
+    assert!(current_request_id().is_none());
+}
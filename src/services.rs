@@ -4,5 +4,203 @@ pub mod user;
 /// The trade module contains services related to trade management.
 pub mod trade;
 
+/// The wallet module contains services related to wallet transfers and their approvals.
+pub mod wallet;
+
 /// The jwt module contains services related to JSON Web Token (JWT) management.
-pub mod jwt;
\ No newline at end of file
+pub mod jwt;
+
+/// The auth module contains the token refresh and logout endpoints.
+pub mod auth;
+
+/// The export module contains services related to sharing exports through signed URLs.
+pub mod export;
+
+/// The format module contains JSON/CSV content negotiation for responses.
+pub mod format;
+
+/// The journal module contains the write-ahead journal for incoming trade requests.
+pub mod journal;
+
+/// The quick_entry module contains the parser for compact text trade commands.
+pub mod quick_entry;
+
+/// The email_in module contains the inbound email gateway for broker trade confirmations.
+pub mod email_in;
+
+/// The summary module contains the mobile home-screen summary endpoint.
+pub mod summary;
+
+/// The changes module contains incremental sync for offline-first clients.
+pub mod changes;
+
+/// The advisor module contains the consolidated multi-account view for advisors.
+pub mod advisor;
+
+/// The delegation module contains the endpoints for delegated trade entry.
+pub mod delegation;
+
+/// The inbox module contains the trade requests assistants propose for owners to accept.
+pub mod inbox;
+
+/// The metadata module contains display names, icons and explorer links for assets and chains.
+pub mod metadata;
+
+/// The narration module contains the localized sentences describing a trade.
+pub mod narration;
+
+/// The chain_registry module contains the admin API for per-chain block explorer templates.
+pub mod chain_registry;
+
+/// The portfolio module contains the per-asset positions and P&L of a user.
+pub mod portfolio;
+
+/// The recompute module contains the admin jobs that re-derive stored fees, snapshots and positions.
+pub mod recompute;
+
+/// The fee_schedule module contains the admin routes for versioning the fee schedule and per-user fee overrides.
+pub mod fee_schedule;
+
+/// The price_fixing module contains the admin routes for configuring the end-of-day price fixing.
+pub mod price_fixing;
+
+/// The account_merge module contains the admin routes for merging duplicate user accounts.
+pub mod account_merge;
+
+/// The email_change module contains the verified email address change flow.
+pub mod email_change;
+
+/// The openapi module serves the OpenAPI specification and Swagger UI of the user and trade API.
+pub mod openapi;
+
+/// The settings module contains the endpoints for a user's settings and login devices.
+pub mod settings;
+
+/// The analytics module contains the risk metrics and win/loss statistics of a trader's trades.
+pub mod analytics;
+
+/// The version module contains the build information endpoint.
+pub mod version;
+
+/// The metrics module contains the business SLA metrics of the trade lifecycle.
+pub mod metrics;
+
+/// The prices module contains the price feed used to mark open positions to market.
+pub mod prices;
+
+/// The password module contains the forgotten password reset flow.
+pub mod password;
+
+/// The mailer module delivers queued emails through a pluggable mail provider.
+pub mod mailer;
+
+/// The admin analytics module aggregates trading across every trader for the operations team.
+pub mod admin_analytics;
+
+/// The pagination module implements keyset (cursor) pagination of growing listings.
+pub mod pagination;
+
+/// The audit export module exports the audit log as a hash-chained, signed NDJSON stream.
+pub mod audit_export;
+
+/// The replica module contains the read-only routes served by read-only replicas.
+pub mod replica;
+
+/// The migrations module reports the applied and pending database migrations to admins.
+pub mod migrations;
+
+// Import trade service tests (only included in test builds)
+#[cfg(test)]
+mod trade_test;
+
+// Import user service tests (only included in test builds)
+#[cfg(test)]
+mod user_test;
+
+// Import journal tests (only included in test builds)
+#[cfg(test)]
+mod journal_test;
+
+// Import format tests (only included in test builds)
+#[cfg(test)]
+mod format_test;
+
+// Import quick entry tests (only included in test builds)
+#[cfg(test)]
+mod quick_entry_test;
+
+// Import delegation tests (only included in test builds)
+#[cfg(test)]
+mod delegation_test;
+
+// Import trade request inbox tests (only included in test builds)
+#[cfg(test)]
+mod inbox_test;
+
+// Import business metrics tests (only included in test builds)
+#[cfg(test)]
+mod metrics_test;
+
+// Import inbound email tests (only included in test builds)
+#[cfg(test)]
+mod email_in_test;
+
+// Import change sync tests (only included in test builds)
+#[cfg(test)]
+mod changes_test;
+
+// Import advisor tests (only included in test builds)
+#[cfg(test)]
+mod advisor_test;
+
+// Import metadata tests (only included in test builds)
+#[cfg(test)]
+mod metadata_test;
+
+// Import narration tests (only included in test builds)
+#[cfg(test)]
+mod narration_test;
+
+// Import OpenAPI tests (only included in test builds)
+#[cfg(test)]
+mod openapi_test;
+
+// Import analytics tests (only included in test builds)
+#[cfg(test)]
+mod analytics_test;
+
+// Import HTTP fixtures for external services (only included in test builds)
+#[cfg(test)]
+pub mod fixtures;
+
+// Import price feed tests (only included in test builds)
+#[cfg(test)]
+mod prices_test;
+
+// Import mailer tests (only included in test builds)
+#[cfg(test)]
+mod mailer_test;
+
+// Import pagination tests (only included in test builds)
+#[cfg(test)]
+mod pagination_test;
+
+// Import audit export tests (only included in test builds)
+#[cfg(test)]
+mod audit_export_test;
+
+// Import read-only replica tests (only included in test builds)
+#[cfg(test)]
+mod replica_test;
+
+// Import wallet service tests (only included in test builds)
+#[cfg(test)]
+mod wallet_test;
+
+// Import export signing tests (only included in test builds)
+#[cfg(test)]
+mod export_test;
+
+// Import summary tests (only included in test builds)
+#[cfg(test)]
+mod summary_test;
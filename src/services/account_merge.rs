@@ -0,0 +1,74 @@
+//! This module defines the admin API for merging duplicate user accounts.
+//!
+//! The provided functions include:
+//!
+//! - `merge_accounts`: Folds one account into another (`POST /admin/users/merge` with
+//!   `{"source_user_id": "...", "target_user_id": "..."}`). The source user's trades, wallet ledger, transfers and
+//!   settings are reassigned to the target user, after which the source user and wallet are deleted.
+//! - `list_merges`: Lists past merges, newest first (`GET /admin/merges`).
+//! - `export_mapping`: Downloads the mapping of a merge as a JSON file (`GET /admin/merges/{merge_id}/mapping`). It
+//!   lists the source user and wallet, the reassigned row IDs per table and the rows that were dropped, so the merge
+//!   can be reversed by hand.
+//!
+//! # Note
+//! All routes are wrapped with the `JwtGuard` middleware and restricted to admins (`ADMIN_USER_IDS`).
+
+use actix_web::{web, HttpRequest, HttpResponse, ResponseError};
+use serde::{Deserialize, Serialize};
+
+use crate::db::models::account_merge::AccountMerge;
+use crate::db::{self, DbPool};
+use crate::error::AppError;
+use crate::middleware::jwt_guard::JwtGuard;
+use crate::services::jwt;
+
+#[derive(Serialize, Deserialize)]
+pub struct MergeForm {
+    pub source_user_id: String,
+    pub target_user_id: String,
+}
+
+pub async fn merge_accounts(req: HttpRequest, pool: web::Data<DbPool>, form: web::Json<MergeForm>) -> HttpResponse {
+    let admin_id = match jwt::require_admin(&req) {
+        Ok(admin_id) => admin_id,
+        Err(err) => return err.error_response(),
+    };
+
+    let form = form.into_inner();
+    match db::run(&pool, move |conn| Ok(AccountMerge::merge(conn, form.source_user_id, form.target_user_id, admin_id)?)).await {
+        Ok((Some(merge), None)) => HttpResponse::Ok().json(merge),
+        Ok((_, errors)) => AppError::Validation(errors.unwrap_or_default()).error_response(),
+        Err(err) => err.error_response(),
+    }
+}
+
+pub async fn list_merges(req: HttpRequest, pool: web::Data<DbPool>) -> HttpResponse {
+    if let Err(err) = jwt::require_admin(&req) {
+        return err.error_response();
+    }
+
+    match db::run(&pool, move |conn| Ok(AccountMerge::list(conn)?)).await {
+        Ok(merges) => HttpResponse::Ok().json(merges),
+        Err(err) => err.error_response(),
+    }
+}
+
+pub async fn export_mapping(req: HttpRequest, pool: web::Data<DbPool>, merge_id: web::Path<String>) -> HttpResponse {
+    if let Err(err) = jwt::require_admin(&req) {
+        return err.error_response();
+    }
+
+    match db::run(&pool, move |conn| Ok(AccountMerge::find_by_id(conn, merge_id.into_inner())?)).await {
+        Ok(Some(merge)) => HttpResponse::Ok()
+            .insert_header(("Content-Disposition", format!("attachment; filename=\"merge-{}.json\"", merge.id)))
+            .json(merge.mapping()),
+        Ok(None) => AppError::NotFound("Merge not found".to_string()).error_response(),
+        Err(err) => err.error_response(),
+    }
+}
+
+pub fn init_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(web::resource("/admin/users/merge").route(web::post().to(merge_accounts).wrap(JwtGuard)));
+    cfg.service(web::resource("/admin/merges").route(web::get().to(list_merges).wrap(JwtGuard)));
+    cfg.service(web::resource("/admin/merges/{merge_id}/mapping").route(web::get().to(export_mapping).wrap(JwtGuard)));
+}
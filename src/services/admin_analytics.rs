@@ -0,0 +1,58 @@
+//! This module defines the platform-wide analytics read by the operations team's revenue dashboard.
+//!
+//! `GET /admin/analytics/activity` aggregates the trades of every trader between `start_date` and `end_date` (the
+//! relative ranges of the other analytics endpoints are accepted too, in the timezone given by `tz`). It returns one
+//! row per day, asset and chain, oldest first, with:
+//!
+//! - `trades` and `traders`: the number of trades and of distinct traders;
+//! - `volume`: the notional traded (`traded_amount * execution_price`);
+//! - `pnl` and `fees`: the summed per-trade P&L (`Trade::calculate_trade_pnl`, net of fees) and the execution and
+//!   transaction fees charged, which are the platform's revenue;
+//! - `average_slippage`: the mean of `Trade::calculate_slippage` over the trades that have a price before execution,
+//!   `null` when none has.
+//!
+//! Simulated trades (`source = simulation`) are left out. Like the other analytics endpoints, the response is CSV with
+//! `Accept: text/csv` or `?format=csv` (see `services::format`).
+//!
+//! # Note
+//! The route is wrapped with the `JwtGuard` middleware and only answers admins (`ADMIN_USER_IDS`).
+
+use actix_web::{web, HttpRequest, HttpResponse, ResponseError};
+use serde::{Deserialize, Serialize};
+
+use crate::db::models::trade::Trade;
+use crate::db::{self, DbPool};
+use crate::error::AppError;
+use crate::middleware::{jwt_guard::JwtGuard, load_shed::LoadShed};
+use crate::services::format::respond;
+use crate::services::jwt;
+use crate::utils;
+
+#[derive(Serialize, Deserialize)]
+pub struct PlatformQuery {
+    pub start_date: String,
+    #[serde(default)]
+    pub end_date: String,
+    pub tz: Option<String>,
+    pub format: Option<String>,
+}
+
+pub async fn activity(req: HttpRequest, pool: web::Data<DbPool>, params: web::Query<PlatformQuery>) -> HttpResponse {
+    if let Err(err) = jwt::require_admin(&req) {
+        return err.error_response();
+    }
+
+    let (start_date, end_date) = match utils::date::parse_range(&params.start_date, &params.end_date, params.tz.as_deref()) {
+        Ok(range) => range,
+        Err(err) => return AppError::Validation(format!("Error: {}", err)).error_response(),
+    };
+
+    match db::run(&pool, move |conn| Ok(Trade::platform_activity(conn, start_date, end_date)?)).await {
+        Ok(rows) => respond(&req, params.format.as_deref(), &rows),
+        Err(err) => err.error_response(),
+    }
+}
+
+pub fn init_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(web::resource("/admin/analytics/activity").route(web::get().to(activity).wrap(JwtGuard).wrap(LoadShed::low_priority())));
+}
@@ -0,0 +1,202 @@
+//! This module defines the consolidated multi-account view for advisors.
+//!
+//! The provided functions include:
+//!
+//! - `grant_client`: Grants an advisor read-only access to a client account (`PUT /advisor/{advisor_id}/clients/{client_id}`).
+//! - `revoke_client`: Removes that access again (`DELETE /advisor/{advisor_id}/clients/{client_id}`).
+//! - `overview`: Aggregates P&L, exposure and fees across all linked clients (`GET /advisor/overview?advisor_id=`),
+//!   with per-client totals and drill-down links.
+//! - `client_detail`: Returns the metrics of a single linked client; clients that are not linked are not found.
+//!
+//! Only the client (or an admin, see `ADMIN_USER_IDS`) may grant or revoke an advisor's access, and only the advisor
+//! (or an admin) may read the overview and client details; other callers get `403`.
+//!
+//! The date range is given by `start_date`/`end_date` (absolute or relative, see `utils::date::parse_range`) in the
+//! timezone of the optional `tz` parameter, and defaults to the month to date.
+//!
+//! Errors are returned as `crate::error::AppError` JSON bodies with a machine-readable `code`.
+//!
+//! # Note
+//! All routes are wrapped with the `JwtGuard` middleware for secure access.
+
+use actix_web::{web, HttpRequest, HttpResponse, ResponseError};
+use serde::{Deserialize, Serialize};
+
+use crate::db::models::advisor::{AdvisorClient, ClientMetrics};
+use crate::db::{self, DbPool};
+use crate::error::AppError;
+use crate::middleware::{jwt_guard::JwtGuard, load_shed::LoadShed};
+use crate::services::jwt;
+use crate::utils;
+
+#[derive(Serialize, Deserialize)]
+pub struct AdvisorQuery {
+    pub advisor_id: String,
+    #[serde(default)]
+    pub start_date: String,
+    #[serde(default)]
+    pub end_date: String,
+    pub tz: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct RangeQuery {
+    #[serde(default)]
+    pub start_date: String,
+    #[serde(default)]
+    pub end_date: String,
+    pub tz: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct ClientLinks {
+    pub detail: String,
+    pub trades: String,
+    pub profit_loss: String,
+}
+
+#[derive(Serialize)]
+pub struct ClientOverview {
+    #[serde(flatten)]
+    pub metrics: ClientMetrics,
+    pub links: ClientLinks,
+}
+
+#[derive(Serialize)]
+pub struct AdvisorOverview {
+    pub advisor_id: String,
+    pub start_date: String,
+    pub end_date: String,
+    pub trade_count: i64,
+    pub pnl: f32,
+    pub exposure: f32,
+    pub fees: f32,
+    pub clients: Vec<ClientOverview>,
+}
+
+fn resolve_range(start_date: &str, end_date: &str, tz: Option<&str>) -> Result<(String, String), AppError> {
+    let start_date = if start_date.is_empty() { "mtd" } else { start_date };
+    utils::date::parse_range(start_date, end_date, tz).map_err(|err| AppError::Validation(format!("Error: {}", err)))
+}
+
+// Links are managed by the client and read by the advisor; admins may do both.
+fn ensure_caller(req: &HttpRequest, user_id: &str, message: &str) -> Result<(), AppError> {
+    match jwt::user_id(req) {
+        Some(caller_id) if caller_id != user_id && !jwt::is_admin(&caller_id) => Err(AppError::Forbidden(message.to_string())),
+        _ => Ok(()),
+    }
+}
+
+pub(crate) fn encode_query_value(value: &str) -> String {
+    value
+        .bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => (byte as char).to_string(),
+            _ => format!("%{:02X}", byte),
+        })
+        .collect()
+}
+
+pub(crate) fn client_links(advisor_id: &str, client_id: &str, start_date: &str, end_date: &str) -> ClientLinks {
+    let range = format!("start_date={}&end_date={}", encode_query_value(start_date), encode_query_value(end_date));
+    ClientLinks {
+        detail: format!("/advisor/{}/clients/{}?{}", advisor_id, client_id, range),
+        trades: format!("/trade?user_id={}&{}", client_id, range),
+        profit_loss: format!("/profit-loss?trader_id={}&{}", client_id, range),
+    }
+}
+
+pub async fn grant_client(req: HttpRequest, pool: web::Data<DbPool>, path: web::Path<(String, String)>) -> HttpResponse {
+    let (advisor_id, client_id) = path.into_inner();
+    if let Err(err) = ensure_caller(&req, &client_id, "Only the client can grant access to their account") {
+        return err.error_response();
+    }
+
+    match db::run(&pool, move |conn| Ok(AdvisorClient::grant(conn, advisor_id, client_id)?)).await {
+        Ok((Some(link), None)) => HttpResponse::Ok().json(link),
+        Ok((_, errors)) => AppError::Validation(errors.unwrap_or_default()).error_response(),
+        Err(err) => err.error_response(),
+    }
+}
+
+pub async fn revoke_client(req: HttpRequest, pool: web::Data<DbPool>, path: web::Path<(String, String)>) -> HttpResponse {
+    let (advisor_id, client_id) = path.into_inner();
+    if let Err(err) = ensure_caller(&req, &client_id, "Only the client can revoke access to their account") {
+        return err.error_response();
+    }
+
+    match db::run(&pool, move |conn| Ok(AdvisorClient::revoke(conn, advisor_id, client_id)?)).await {
+        Ok(true) => HttpResponse::Ok().json("Client access revoked"),
+        Ok(false) => AppError::NotFound("Client not linked to advisor".to_string()).error_response(),
+        Err(err) => err.error_response(),
+    }
+}
+
+pub async fn overview(req: HttpRequest, pool: web::Data<DbPool>, params: web::Query<AdvisorQuery>) -> HttpResponse {
+    if params.advisor_id.is_empty() {
+        return AppError::Validation("Error: Advisor ID is required".to_string()).error_response();
+    }
+    if let Err(err) = ensure_caller(&req, &params.advisor_id, "Only the advisor can view their clients") {
+        return err.error_response();
+    }
+
+    let (start_date, end_date) = match resolve_range(&params.start_date, &params.end_date, params.tz.as_deref()) {
+        Ok(range) => range,
+        Err(err) => return err.error_response(),
+    };
+
+    let (advisor_id, start, end) = (params.advisor_id.clone(), start_date.clone(), end_date.clone());
+    let metrics = match db::run(&pool, move |conn| Ok(ClientMetrics::for_advisor(conn, advisor_id, start, end)?)).await {
+        Ok(metrics) => metrics,
+        Err(err) => return err.error_response(),
+    };
+
+    HttpResponse::Ok().json(AdvisorOverview {
+        advisor_id: params.advisor_id.clone(),
+        trade_count: metrics.iter().map(|client| client.trade_count).sum(),
+        pnl: metrics.iter().map(|client| client.pnl).sum(),
+        exposure: metrics.iter().map(|client| client.exposure).sum(),
+        fees: metrics.iter().map(|client| client.fees).sum(),
+        clients: metrics
+            .into_iter()
+            .map(|metrics| ClientOverview {
+                links: client_links(&params.advisor_id, &metrics.client_id, &start_date, &end_date),
+                metrics,
+            })
+            .collect(),
+        start_date,
+        end_date,
+    })
+}
+
+pub async fn client_detail(req: HttpRequest, pool: web::Data<DbPool>, path: web::Path<(String, String)>, params: web::Query<RangeQuery>) -> HttpResponse {
+    let (advisor_id, client_id) = path.into_inner();
+    if let Err(err) = ensure_caller(&req, &advisor_id, "Only the advisor can view their clients") {
+        return err.error_response();
+    }
+
+    let (start_date, end_date) = match resolve_range(&params.start_date, &params.end_date, params.tz.as_deref()) {
+        Ok(range) => range,
+        Err(err) => return err.error_response(),
+    };
+
+    let (advisor, start, end) = (advisor_id.clone(), start_date.clone(), end_date.clone());
+    match db::run(&pool, move |conn| Ok(ClientMetrics::for_client(conn, advisor, client_id, start, end)?)).await {
+        Ok(Some(metrics)) => HttpResponse::Ok().json(ClientOverview {
+            links: client_links(&advisor_id, &metrics.client_id, &start_date, &end_date),
+            metrics,
+        }),
+        Ok(None) => AppError::NotFound("Client not linked to advisor".to_string()).error_response(),
+        Err(err) => err.error_response(),
+    }
+}
+
+pub fn init_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(web::resource("/advisor/overview").route(web::get().to(overview).wrap(JwtGuard).wrap(LoadShed::low_priority())))
+        .service(
+            web::resource("/advisor/{advisor_id}/clients/{client_id}")
+                .route(web::get().to(client_detail).wrap(JwtGuard).wrap(LoadShed::low_priority()))
+                .route(web::put().to(grant_client).wrap(JwtGuard))
+                .route(web::delete().to(revoke_client).wrap(JwtGuard)),
+        );
+}
@@ -0,0 +1,56 @@
+use actix_web::http::header::AUTHORIZATION;
+use actix_web::http::StatusCode;
+use actix_web::test::{call_service, init_service, TestRequest};
+use actix_web::{web, App};
+
+use crate::db::fixtures::{test_pool, user};
+use super::advisor::{client_links, encode_query_value, init_routes};
+use super::jwt::create_jwt;
+
+#[test]
+fn encodes_query_values() {
+    assert_eq!(encode_query_value("2023-08-01 00:00:00.5"), "2023-08-01%2000%3A00%3A00.5");
+    assert_eq!(encode_query_value("a+b&c"), "a%2Bb%26c");
+}
+
+#[test]
+fn builds_drill_down_links() {
+    let links = client_links("advisor", "client", "2023-08-01 00:00:00", "2023-08-31 23:59:59");
+
+    assert_eq!(links.detail, "/advisor/advisor/clients/client?start_date=2023-08-01%2000%3A00%3A00&end_date=2023-08-31%2023%3A59%3A59");
+    assert_eq!(links.trades, "/trade?user_id=client&start_date=2023-08-01%2000%3A00%3A00&end_date=2023-08-31%2023%3A59%3A59");
+    assert!(links.profit_loss.starts_with("/profit-loss?trader_id=client&start_date="));
+}
+
+#[actix_web::test]
+async fn clients_manage_access_and_only_their_advisor_reads_it() {
+    std::env::set_var("JWT_SECRET", "test_secret");
+    let pool = test_pool();
+    let conn = &mut pool.get().unwrap();
+    let (advisor, client) = (user(conn, "advisor"), user(conn, "client"));
+    let app = init_service(App::new().app_data(web::Data::new(pool.clone())).configure(init_routes)).await;
+    let (advisor_token, client_token) = (create_jwt(advisor.id.clone()).unwrap(), create_jwt(client.id.clone()).unwrap());
+    let stranger = create_jwt("stranger".to_string()).unwrap();
+
+    let link = format!("/advisor/{}/clients/{}", advisor.id, client.id);
+    let req = TestRequest::put().uri(&link).insert_header((AUTHORIZATION, advisor_token.clone())).to_request();
+    assert_eq!(call_service(&app, req).await.status(), StatusCode::FORBIDDEN);
+    let req = TestRequest::put().uri(&link).insert_header((AUTHORIZATION, client_token.clone())).to_request();
+    assert_eq!(call_service(&app, req).await.status(), StatusCode::OK);
+
+    let overview = format!("/advisor/overview?advisor_id={}", advisor.id);
+    for (uri, token, status) in [
+        (&overview, stranger.clone(), StatusCode::FORBIDDEN),
+        (&link, stranger.clone(), StatusCode::FORBIDDEN),
+        (&overview, advisor_token.clone(), StatusCode::OK),
+        (&link, advisor_token.clone(), StatusCode::OK),
+    ] {
+        let req = TestRequest::get().uri(uri).insert_header((AUTHORIZATION, token)).to_request();
+        assert_eq!(call_service(&app, req).await.status(), status);
+    }
+
+    for (token, status) in [(stranger, StatusCode::FORBIDDEN), (advisor_token, StatusCode::FORBIDDEN), (client_token.clone(), StatusCode::OK), (client_token, StatusCode::NOT_FOUND)] {
+        let req = TestRequest::delete().uri(&link).insert_header((AUTHORIZATION, token)).to_request();
+        assert_eq!(call_service(&app, req).await.status(), status);
+    }
+}
@@ -0,0 +1,405 @@
+//! This module defines risk and performance analytics computed from a trader's trade history.
+//!
+//! Both endpoints take `trader_id`, `start_date` and `end_date` and load the trades in the range with
+//! `Trade::get_bt_dates`.
+//!
+//! `GET /analytics/risk` returns:
+//!
+//! - `daily_returns`: one entry per day with trades, holding that day's P&L (`Trade::calculate_trade_pnl`, net of
+//!   fees), the notional traded (`traded_amount * execution_price`) and the return, P&L over notional;
+//! - `annualized_volatility`: the sample standard deviation of the daily returns, scaled by `sqrt(365)` since crypto
+//!   markets trade every day;
+//! - `sharpe_ratio`: the mean daily return in excess of the risk-free rate over its standard deviation, annualized the
+//!   same way. `risk_free_rate` is an optional annual rate (e.g. `0.04`), zero by default.
+//!
+//! Both figures need at least two daily returns and are `null` otherwise; the Sharpe ratio is also `null` when the
+//! returns do not vary.
+//!
+//! `GET /analytics/stats` groups the trades by asset and by trade type and returns, for each group and for all trades
+//! together, the trade count, win rate, average win and loss, profit factor (gross profit over gross loss) and largest
+//! win and loss, using the per-trade P&L of `Trade::calculate_trade_pnl`. Trades with zero P&L count as neither wins
+//! nor losses. Figures without any winning or losing trade to compute them from are `null`.
+//!
+//! `GET /stats/exposure` shows how concentrated a trader is on a single chain or venue. It takes `trader_id`, an optional
+//! `range` (a `utils::date::parse_span` expression, `last_30d` by default) and `tz`, and returns the trader's exposure
+//! grouped by chain (`by_chain`) and by venue (`by_venue`, see `TradeSource::venue`: the exchange of imported trades,
+//! otherwise `onchain`, `api_key` or `manual`). Each group lists:
+//!
+//! - `holdings`: the current quantity of each asset, replaying all of the group's trades with `Position::from_trades`
+//!   and marked at the last price, so a group's holdings are net of buys and sells in that group;
+//! - `market_value`: the gross value of the holdings (sum of absolute values) and `share`, its fraction of the gross
+//!   value over all groups;
+//! - `volume` and `trades`: the notional (`traded_amount * execution_price`) and number of trades within `range`, and
+//!   `volume_share`, the fraction of the volume within `range`.
+//!
+//! Shares are `null` when there is nothing to divide by. Simulated trades (`source = simulation`) carry no risk and are
+//! left out. Groups are ordered by market value, largest first.
+//!
+//! `start_date` and `end_date` accept the relative ranges of the other analytics endpoints, in the timezone given by
+//! `tz`.
+//!
+//! `GET /analytics/leaderboard` ranks every trader against each other (see `db::models::leaderboard`). It takes a
+//! `period` of `7d`, `30d` (the default) or `all`, counted back from now, and a `limit` (10 by default, at most
+//! `MAX_LEADERBOARD_SIZE`), and returns the top traders by realized P&L (`by_pnl`) and by volume (`by_volume`). Users
+//! who set `hide_from_leaderboard` in their settings are left out.
+//!
+//! # Note
+//! All routes are wrapped with the `JwtGuard` middleware; only the trader and admins (`ADMIN_USER_IDS`) can read them.
+//! The leaderboard is open to every logged-in user.
+
+use std::collections::BTreeMap;
+
+use actix_web::{web, HttpRequest, HttpResponse, ResponseError};
+use serde::{Deserialize, Serialize};
+
+use crate::db::models::leaderboard::Leaderboard;
+use crate::db::models::position::Position;
+use crate::db::models::trade::{Trade, TradeSource};
+use crate::db::{self, DbPool};
+use crate::error::AppError;
+use crate::middleware::{jwt_guard::JwtGuard, load_shed::LoadShed};
+use crate::services::jwt;
+use crate::utils;
+
+pub const TRADING_DAYS_PER_YEAR: f32 = 365.0;
+pub const DEFAULT_LEADERBOARD_SIZE: i64 = 10;
+pub const MAX_LEADERBOARD_SIZE: i64 = 100;
+
+#[derive(Serialize, Deserialize)]
+pub struct AnalyticsQuery {
+    pub trader_id: String,
+    pub start_date: String,
+    #[serde(default)]
+    pub end_date: String,
+    pub tz: Option<String>,
+    pub risk_free_rate: Option<f32>,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct DailyReturn {
+    pub date: String,
+    pub pnl: f32,
+    pub notional: f32,
+    #[serde(rename = "return")]
+    pub daily_return: f32,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RiskReport {
+    pub trader_id: String,
+    pub daily_returns: Vec<DailyReturn>,
+    pub annualized_volatility: Option<f32>,
+    pub sharpe_ratio: Option<f32>,
+}
+
+// Days without notional (e.g. only zero-sized trades) have no return and are left out.
+pub fn daily_returns(trades: &[Trade]) -> Vec<DailyReturn> {
+    let mut days: BTreeMap<String, (f32, f32)> = BTreeMap::new();
+    for trade in trades {
+        let day = days.entry(trade.created_at.date().to_string()).or_insert((0.0, 0.0));
+        day.0 += trade.calculate_trade_pnl();
+        day.1 += trade.traded_amount * trade.execution_price;
+    }
+
+    days.into_iter()
+        .filter(|(_, (_, notional))| *notional > 0.0)
+        .map(|(date, (pnl, notional))| DailyReturn { date, pnl, notional, daily_return: pnl / notional })
+        .collect()
+}
+
+fn mean_and_deviation(returns: &[f32]) -> Option<(f32, f32)> {
+    if returns.len() < 2 {
+        return None;
+    }
+    let count = returns.len() as f32;
+    let mean = returns.iter().sum::<f32>() / count;
+    let variance = returns.iter().map(|value| (value - mean).powi(2)).sum::<f32>() / (count - 1.0);
+    Some((mean, variance.sqrt()))
+}
+
+pub fn annualized_volatility(returns: &[f32]) -> Option<f32> {
+    mean_and_deviation(returns).map(|(_, deviation)| deviation * TRADING_DAYS_PER_YEAR.sqrt())
+}
+
+pub fn sharpe_ratio(returns: &[f32], risk_free_rate: f32) -> Option<f32> {
+    let (mean, deviation) = mean_and_deviation(returns)?;
+    if deviation == 0.0 {
+        return None;
+    }
+    let daily_risk_free = risk_free_rate / TRADING_DAYS_PER_YEAR;
+    Some((mean - daily_risk_free) / deviation * TRADING_DAYS_PER_YEAR.sqrt())
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct TradeStats {
+    pub group: String,
+    pub trades: usize,
+    pub win_rate: f32,
+    pub average_win: Option<f32>,
+    pub average_loss: Option<f32>,
+    pub profit_factor: Option<f32>,
+    pub largest_win: Option<f32>,
+    pub largest_loss: Option<f32>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct StatsReport {
+    pub trader_id: String,
+    pub overall: Option<TradeStats>,
+    pub by_asset: Vec<TradeStats>,
+    pub by_trade_type: Vec<TradeStats>,
+}
+
+fn average(values: &[f32]) -> Option<f32> {
+    (!values.is_empty()).then(|| values.iter().sum::<f32>() / values.len() as f32)
+}
+
+impl TradeStats {
+    // `None` for an empty group.
+    pub fn from_pnls(group: String, pnls: &[f32]) -> Option<Self> {
+        if pnls.is_empty() {
+            return None;
+        }
+        let wins: Vec<f32> = pnls.iter().copied().filter(|pnl| *pnl > 0.0).collect();
+        let losses: Vec<f32> = pnls.iter().copied().filter(|pnl| *pnl < 0.0).collect();
+        let gross_loss = -losses.iter().sum::<f32>();
+
+        Some(TradeStats {
+            group,
+            trades: pnls.len(),
+            win_rate: wins.len() as f32 / pnls.len() as f32,
+            average_win: average(&wins),
+            average_loss: average(&losses),
+            profit_factor: (gross_loss > 0.0).then(|| wins.iter().sum::<f32>() / gross_loss),
+            largest_win: wins.iter().copied().reduce(f32::max),
+            largest_loss: losses.iter().copied().reduce(f32::min),
+        })
+    }
+}
+
+fn grouped(trades: &[Trade], key: impl Fn(&Trade) -> &str) -> Vec<TradeStats> {
+    let mut groups: BTreeMap<String, Vec<f32>> = BTreeMap::new();
+    for trade in trades {
+        groups.entry(key(trade).to_string()).or_default().push(trade.calculate_trade_pnl());
+    }
+    groups.into_iter().filter_map(|(group, pnls)| TradeStats::from_pnls(group, &pnls)).collect()
+}
+
+impl StatsReport {
+    pub fn new(trader_id: String, trades: &[Trade]) -> Self {
+        let pnls: Vec<f32> = trades.iter().map(|trade| trade.calculate_trade_pnl()).collect();
+
+        StatsReport {
+            trader_id,
+            overall: TradeStats::from_pnls("all".to_string(), &pnls),
+            by_asset: grouped(trades, |trade| &trade.asset),
+            by_trade_type: grouped(trades, |trade| &trade.trade_type),
+        }
+    }
+}
+
+impl RiskReport {
+    pub fn new(trader_id: String, trades: &[Trade], risk_free_rate: f32) -> Self {
+        let daily_returns = daily_returns(trades);
+        let returns: Vec<f32> = daily_returns.iter().map(|day| day.daily_return).collect();
+
+        RiskReport {
+            trader_id,
+            annualized_volatility: annualized_volatility(&returns),
+            sharpe_ratio: sharpe_ratio(&returns, risk_free_rate),
+            daily_returns,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ExposureQuery {
+    pub trader_id: String,
+    pub range: Option<String>,
+    pub tz: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct LeaderboardQuery {
+    pub period: Option<String>,
+    pub limit: Option<i64>,
+}
+
+// The start of a leaderboard period ending at `now`; `None` for all time.
+pub fn leaderboard_since(period: &str, now: chrono::NaiveDateTime) -> Result<Option<chrono::NaiveDateTime>, String> {
+    match period {
+        "7d" => Ok(Some(now - chrono::Duration::days(7))),
+        "30d" => Ok(Some(now - chrono::Duration::days(30))),
+        "all" => Ok(None),
+        _ => Err(format!("Unknown period '{}', expected 7d, 30d or all", period)),
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct Holding {
+    pub asset: String,
+    pub quantity: f32,
+    pub market_value: f32,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct Exposure {
+    pub group: String,
+    pub holdings: Vec<Holding>,
+    pub market_value: f32,
+    pub share: Option<f32>,
+    pub volume: f32,
+    pub volume_share: Option<f32>,
+    pub trades: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ExposureReport {
+    pub trader_id: String,
+    pub range_start: chrono::NaiveDateTime,
+    pub range_end: chrono::NaiveDateTime,
+    pub by_chain: Vec<Exposure>,
+    pub by_venue: Vec<Exposure>,
+}
+
+// `history` is oldest first, as `Position::from_trades` expects.
+fn exposures(history: &[&Trade], range: (chrono::NaiveDateTime, chrono::NaiveDateTime), key: impl Fn(&Trade) -> &str) -> Vec<Exposure> {
+    let mut groups: BTreeMap<String, BTreeMap<String, Vec<Trade>>> = BTreeMap::new();
+    for trade in history {
+        groups.entry(key(trade).to_string()).or_default().entry(trade.asset.clone()).or_default().push((*trade).clone());
+    }
+
+    let mut exposures: Vec<Exposure> = groups
+        .into_iter()
+        .map(|(group, assets)| {
+            let recent: Vec<&Trade> = assets
+                .values()
+                .flatten()
+                .filter(|trade| trade.created_at >= range.0 && trade.created_at <= range.1)
+                .collect();
+            let holdings: Vec<Holding> = assets
+                .values()
+                .filter_map(|trades| Position::from_trades(trades))
+                .filter(|position| position.quantity != 0.0)
+                .map(|position| Holding { market_value: position.quantity * position.last_price, asset: position.asset, quantity: position.quantity })
+                .collect();
+
+            Exposure {
+                group,
+                market_value: holdings.iter().map(|holding| holding.market_value.abs()).sum(),
+                holdings,
+                share: None,
+                volume: recent.iter().map(|trade| trade.traded_amount * trade.execution_price).sum(),
+                volume_share: None,
+                trades: recent.len(),
+            }
+        })
+        .collect();
+
+    let total_value: f32 = exposures.iter().map(|exposure| exposure.market_value).sum();
+    let total_volume: f32 = exposures.iter().map(|exposure| exposure.volume).sum();
+    for exposure in &mut exposures {
+        exposure.share = (total_value > 0.0).then(|| exposure.market_value / total_value);
+        exposure.volume_share = (total_volume > 0.0).then(|| exposure.volume / total_volume);
+    }
+    exposures.sort_by(|a, b| b.market_value.total_cmp(&a.market_value).then_with(|| a.group.cmp(&b.group)));
+    exposures
+}
+
+impl ExposureReport {
+    pub fn new(trader_id: String, history: &[Trade], range: (chrono::NaiveDateTime, chrono::NaiveDateTime)) -> Self {
+        let history: Vec<&Trade> = history.iter().filter(|trade| trade.source != TradeSource::SIMULATION).collect();
+
+        ExposureReport {
+            trader_id,
+            range_start: range.0,
+            range_end: range.1,
+            by_chain: exposures(&history, range, |trade| &trade.chain),
+            by_venue: exposures(&history, range, |trade| TradeSource::venue(&trade.source)),
+        }
+    }
+}
+
+fn ensure_can_view(req: &HttpRequest, trader_id: &str) -> Result<(), AppError> {
+    match jwt::user_id(req) {
+        Some(caller_id) if caller_id != trader_id && !jwt::is_admin(&caller_id) => {
+            Err(AppError::Forbidden("Analytics belong to another user".to_string()))
+        }
+        _ => Ok(()),
+    }
+}
+
+// The trades of the requested trader and range, once the caller is allowed to see them.
+async fn load_trades(req: &HttpRequest, pool: &DbPool, params: &AnalyticsQuery) -> Result<Vec<Trade>, AppError> {
+    if params.trader_id.is_empty() {
+        return Err(AppError::Validation("Error: Trader ID is required".to_string()));
+    }
+    ensure_can_view(req, &params.trader_id)?;
+    let (start_date, end_date) = utils::date::parse_range(&params.start_date, &params.end_date, params.tz.as_deref())
+        .map_err(|err| AppError::Validation(format!("Error: {}", err)))?;
+
+    let trader_id = params.trader_id.clone();
+    db::run(pool, move |conn| Ok(Trade::get_bt_dates(conn, start_date, end_date, trader_id, &[])?)).await
+}
+
+pub async fn risk(req: HttpRequest, pool: web::Data<DbPool>, params: web::Query<AnalyticsQuery>) -> HttpResponse {
+    let risk_free_rate = params.risk_free_rate.unwrap_or(0.0);
+    if !risk_free_rate.is_finite() {
+        return AppError::Validation("Error: risk_free_rate must be a number".to_string()).error_response();
+    }
+
+    match load_trades(&req, &pool, &params).await {
+        Ok(trades) => HttpResponse::Ok().json(RiskReport::new(params.trader_id.clone(), &trades, risk_free_rate)),
+        Err(err) => err.error_response(),
+    }
+}
+
+pub async fn stats(req: HttpRequest, pool: web::Data<DbPool>, params: web::Query<AnalyticsQuery>) -> HttpResponse {
+    match load_trades(&req, &pool, &params).await {
+        Ok(trades) => HttpResponse::Ok().json(StatsReport::new(params.trader_id.clone(), &trades)),
+        Err(err) => err.error_response(),
+    }
+}
+
+pub async fn exposure(req: HttpRequest, pool: web::Data<DbPool>, params: web::Query<ExposureQuery>) -> HttpResponse {
+    if params.trader_id.is_empty() {
+        return AppError::Validation("Error: Trader ID is required".to_string()).error_response();
+    }
+    if let Err(err) = ensure_can_view(&req, &params.trader_id) {
+        return err.error_response();
+    }
+    let range = match utils::date::parse_span(params.range.as_deref().unwrap_or("last_30d"), params.tz.as_deref()) {
+        Ok(range) => range,
+        Err(err) => return AppError::Validation(format!("Error: {}", err)).error_response(),
+    };
+
+    let trader_id = params.into_inner().trader_id;
+    let owner_id = trader_id.clone();
+    match db::run(&pool, move |conn| Ok(Trade::history_for_user(conn, owner_id)?)).await {
+        Ok(history) => HttpResponse::Ok().json(ExposureReport::new(trader_id, &history, range)),
+        Err(err) => err.error_response(),
+    }
+}
+
+pub async fn leaderboard(pool: web::Data<DbPool>, params: web::Query<LeaderboardQuery>) -> HttpResponse {
+    let since = match leaderboard_since(params.period.as_deref().unwrap_or("30d"), chrono::Utc::now().naive_utc()) {
+        Ok(since) => since,
+        Err(err) => return AppError::Validation(format!("Error: {}", err)).error_response(),
+    };
+    let limit = params.limit.unwrap_or(DEFAULT_LEADERBOARD_SIZE);
+    if !(1..=MAX_LEADERBOARD_SIZE).contains(&limit) {
+        return AppError::Validation(format!("Error: limit must be between 1 and {}", MAX_LEADERBOARD_SIZE)).error_response();
+    }
+
+    match db::run(&pool, move |conn| Ok(Leaderboard::top(conn, since, limit)?)).await {
+        Ok(leaderboard) => HttpResponse::Ok().json(leaderboard),
+        Err(err) => err.error_response(),
+    }
+}
+
+pub fn init_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(web::resource("/analytics/risk").route(web::get().to(risk).wrap(JwtGuard).wrap(LoadShed::low_priority())))
+        .service(web::resource("/analytics/stats").route(web::get().to(stats).wrap(JwtGuard).wrap(LoadShed::low_priority())))
+        .service(web::resource("/stats/exposure").route(web::get().to(exposure).wrap(JwtGuard).wrap(LoadShed::low_priority())))
+        .service(web::resource("/analytics/leaderboard").route(web::get().to(leaderboard).wrap(JwtGuard).wrap(LoadShed::low_priority())));
+}
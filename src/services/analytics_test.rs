@@ -0,0 +1,142 @@
+use super::analytics::{annualized_volatility, daily_returns, leaderboard_since, sharpe_ratio, ExposureReport, RiskReport, StatsReport, TradeStats, TRADING_DAYS_PER_YEAR};
+use super::trade::{fill_optional_fields, TradeForm};
+use crate::db::fixtures;
+use crate::db::models::trade::Trade;
+
+fn trade(timestamp: i64, execution_price: f32, final_price: f32) -> Trade {
+    fill_optional_fields(&TradeForm {
+        amount: 1.0,
+        before_price: Some(execution_price),
+        execution_price: Some(execution_price),
+        final_price: Some(final_price),
+        traded_amount: Some(1.0),
+        timestamp: Some(timestamp),
+        ..fixtures::trade("user_id", "wallet_id")
+    })
+}
+
+fn assert_close(actual: Option<f32>, expected: f32) {
+    let actual = actual.unwrap();
+    assert!((actual - expected).abs() < 1e-3, "{} is not {}", actual, expected);
+}
+
+#[test]
+fn returns_are_pnl_over_notional_per_day() {
+    // 2022-01-01 and 2022-01-02, 14:00 UTC.
+    let trades = [trade(1641045600, 100.0, 110.0), trade(1641045600, 300.0, 290.0), trade(1641132000, 100.0, 95.0)];
+    let days = daily_returns(&trades);
+
+    assert_eq!(days.iter().map(|day| day.date.as_str()).collect::<Vec<_>>(), vec!["2022-01-01", "2022-01-02"]);
+    assert_eq!((days[0].notional, days[1].notional), (400.0, 100.0));
+    let expected_pnl: f32 = trades[..2].iter().map(|trade| trade.calculate_trade_pnl()).sum();
+    assert_eq!(days[0].daily_return, expected_pnl / 400.0);
+}
+
+#[test]
+fn volatility_and_sharpe_are_annualized() {
+    let returns = [0.01, -0.01, 0.02, 0.0];
+    let deviation = (0.0005_f32 / 3.0).sqrt();
+    assert_close(annualized_volatility(&returns), deviation * TRADING_DAYS_PER_YEAR.sqrt());
+    assert_close(sharpe_ratio(&returns, 0.0), 0.005 / deviation * TRADING_DAYS_PER_YEAR.sqrt());
+    assert!(sharpe_ratio(&returns, 0.05).unwrap() < sharpe_ratio(&returns, 0.0).unwrap());
+
+    assert_eq!(annualized_volatility(&[0.01]), None);
+    assert_eq!(sharpe_ratio(&[0.01, 0.01], 0.0), None);
+}
+
+#[test]
+fn report_without_trades_is_empty() {
+    let report = RiskReport::new("user_id".to_string(), &[], 0.0);
+    assert!(report.daily_returns.is_empty());
+    assert_eq!((report.annualized_volatility, report.sharpe_ratio), (None, None));
+}
+
+#[test]
+fn stats_summarize_wins_and_losses() {
+    let stats = TradeStats::from_pnls("ETH".to_string(), &[30.0, -10.0, 10.0, 0.0, -20.0]).unwrap();
+    assert_eq!(stats, TradeStats {
+        group: "ETH".to_string(),
+        trades: 5,
+        win_rate: 0.4,
+        average_win: Some(20.0),
+        average_loss: Some(-15.0),
+        profit_factor: Some(40.0 / 30.0),
+        largest_win: Some(30.0),
+        largest_loss: Some(-20.0),
+    });
+
+    let only_wins = TradeStats::from_pnls("BTC".to_string(), &[5.0]).unwrap();
+    assert_eq!((only_wins.win_rate, only_wins.average_loss, only_wins.profit_factor), (1.0, None, None));
+    assert_eq!(TradeStats::from_pnls("SOL".to_string(), &[]), None);
+}
+
+#[test]
+fn stats_are_grouped_by_asset_and_trade_type() {
+    let mut sell = trade(1641045600, 100.0, 90.0);
+    sell.trade_type = "LimitSell".to_string();
+    sell.asset = "BTC".to_string();
+    let trades = [trade(1641045600, 100.0, 110.0), trade(1641132000, 100.0, 95.0), sell];
+
+    let report = StatsReport::new("user_id".to_string(), &trades);
+    assert_eq!(report.overall.unwrap().trades, 3);
+    assert_eq!(report.by_asset.iter().map(|stats| (stats.group.as_str(), stats.trades)).collect::<Vec<_>>(), vec![("BTC", 1), ("ETH", 2)]);
+    assert_eq!(report.by_trade_type.iter().map(|stats| (stats.group.as_str(), stats.trades)).collect::<Vec<_>>(), vec![("LimitSell", 1), ("MarketBuy", 2)]);
+    assert!(StatsReport::new("user_id".to_string(), &[]).overall.is_none());
+}
+
+fn placed(chain: &str, source: &str, trade_type: &str, timestamp: i64, price: f32, quantity: f32) -> Trade {
+    let mut trade = trade(timestamp, price, price);
+    trade.chain = chain.to_string();
+    trade.source = source.to_string();
+    trade.trade_type = trade_type.to_string();
+    trade.traded_amount = quantity;
+    trade
+}
+
+#[test]
+fn exposure_is_grouped_by_chain_and_venue() {
+    // 2022-01-01 and 2022-02-01, 14:00 UTC; the range only covers February.
+    let (january, february) = (1641045600, 1643724000);
+    let range = (
+        chrono::NaiveDate::from_ymd_opt(2022, 2, 1).unwrap().and_hms_opt(0, 0, 0).unwrap(),
+        chrono::NaiveDate::from_ymd_opt(2022, 2, 28).unwrap().and_hms_opt(23, 59, 59).unwrap(),
+    );
+    let history = [
+        placed("Ethereum", "import:binance", "MarketBuy", january, 100.0, 3.0),
+        placed("Ethereum", "import:binance", "MarketSell", february, 100.0, 1.0),
+        placed("Arbitrum", "onchain:arbitrum", "MarketBuy", february, 100.0, 2.0),
+        placed("Arbitrum", "simulation", "MarketBuy", february, 100.0, 50.0),
+    ];
+    let report = ExposureReport::new("user_id".to_string(), &history, range);
+
+    let chains: Vec<(&str, f32, f32, usize)> =
+        report.by_chain.iter().map(|exposure| (exposure.group.as_str(), exposure.market_value, exposure.volume, exposure.trades)).collect();
+    assert_eq!(chains, vec![("Arbitrum", 200.0, 200.0, 1), ("Ethereum", 200.0, 100.0, 1)]);
+    assert_eq!(report.by_chain[0].share, Some(0.5));
+    assert_eq!(report.by_chain[1].volume_share, Some(100.0 / 300.0));
+    assert_eq!(report.by_chain[1].holdings[0].quantity, 2.0);
+
+    let venues: Vec<&str> = report.by_venue.iter().map(|exposure| exposure.group.as_str()).collect();
+    assert_eq!(venues, vec!["binance", "onchain"]);
+}
+
+#[test]
+fn exposure_without_holdings_has_no_shares() {
+    let history = [placed("Ethereum", "manual", "MarketBuy", 1641045600, 100.0, 1.0), placed("Ethereum", "manual", "MarketSell", 1641045600, 100.0, 1.0)];
+    let range = (chrono::NaiveDateTime::MIN, chrono::NaiveDateTime::MAX);
+    let report = ExposureReport::new("user_id".to_string(), &history, range);
+
+    assert!(report.by_chain[0].holdings.is_empty());
+    assert_eq!(report.by_chain[0].share, None);
+    assert_eq!(report.by_venue[0].group, "manual");
+    assert_eq!(report.by_venue[0].volume_share, Some(1.0));
+}
+
+#[test]
+fn leaderboard_periods_count_back_from_now() {
+    let now = chrono::NaiveDate::from_ymd_opt(2023, 9, 30).unwrap().and_hms_opt(12, 0, 0).unwrap();
+    assert_eq!(leaderboard_since("7d", now), Ok(Some(now - chrono::Duration::days(7))));
+    assert_eq!(leaderboard_since("30d", now), Ok(Some(now - chrono::Duration::days(30))));
+    assert_eq!(leaderboard_since("all", now), Ok(None));
+    assert!(leaderboard_since("90d", now).is_err());
+}
@@ -0,0 +1,246 @@
+//! This module exports the audit log (`db::models::audit`) in a tamper-evident format for compliance reviews.
+//!
+//! `GET /admin/audit/export` streams every audit entry, oldest first, as newline-delimited JSON (`application/x-ndjson`).
+//! Each line is one record, tagged by `type`:
+//!
+//! - `entry`: an audit entry with its position in the export (`seq`, from 1), the `hash` of the previous record
+//!   (`prev_hash`, 64 zeros for the first one) and its own `hash`, the hex SHA-256 of `prev_hash` followed by the JSON
+//!   of the entry. Altering, removing or reordering an entry breaks the chain from that point on.
+//! - `checkpoint`: written after every `CHECKPOINT_INTERVAL` entries and once at the end (`final`), even for an empty
+//!   log. It signs the `seq` and `hash` of the last entry with ECDSA over secp256k1, so a chain cannot be rebuilt
+//!   without the key. The signature is the compact hex form over the SHA-256 of `"{seq}:{hash}"`, and `public_key` is
+//!   the compressed hex key it verifies with.
+//!
+//! The signing key is the hex secret key `AUDIT_SIGNING_KEY` (see `config::secret`); without it the export answers
+//! `500`. `verify` checks an export against the public key: the chain, every checkpoint signature and the final
+//! checkpoint, which tells a complete export from a truncated one.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use crate::services::audit_export;
+//!
+//! let entries = audit_export::verify(&body, "02a1...")?;
+//! println!("{} entries, none altered", entries);
+//! ```
+
+use actix_web::{http::header::CONTENT_DISPOSITION, web, HttpRequest, HttpResponse, ResponseError};
+use secp256k1::{ecdsa::Signature, Message, PublicKey, Secp256k1, SecretKey};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::config;
+use crate::db::models::audit::AuditEntry;
+use crate::db::DbPool;
+use crate::error::AppError;
+use crate::services::jwt;
+
+pub const NDJSON_CONTENT_TYPE: &str = "application/x-ndjson";
+pub const CHECKPOINT_INTERVAL: u64 = 100;
+pub const GENESIS_HASH: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+const EXPORT_PAGE_SIZE: i64 = 500;
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ExportRecord {
+    Entry {
+        seq: u64,
+        prev_hash: String,
+        hash: String,
+        entry: AuditEntry,
+    },
+    Checkpoint {
+        seq: u64,
+        hash: String,
+        signed_at: chrono::NaiveDateTime,
+        public_key: String,
+        signature: String,
+        #[serde(rename = "final")]
+        is_final: bool,
+    },
+}
+
+impl ExportRecord {
+    pub fn to_line(&self) -> String {
+        let mut line = serde_json::to_string(self).expect("export records always serialize");
+        line.push('\n');
+        line
+    }
+}
+
+fn sha256(input: &[u8]) -> [u8; 32] {
+    Sha256::digest(input).into()
+}
+
+pub fn entry_hash(prev_hash: &str, entry: &AuditEntry) -> String {
+    let entry = serde_json::to_string(entry).expect("audit entries always serialize");
+    hex::encode(sha256(format!("{}{}", prev_hash, entry).as_bytes()))
+}
+
+fn checkpoint_message(seq: u64, hash: &str) -> Message {
+    Message::from_slice(&sha256(format!("{}:{}", seq, hash).as_bytes())).expect("a SHA-256 digest is 32 bytes")
+}
+
+#[derive(Clone)]
+pub struct Signer {
+    key: SecretKey,
+    public_key: String,
+}
+
+impl Signer {
+    pub fn new(secret_key: &str) -> Result<Self, String> {
+        let bytes = hex::decode(secret_key.trim()).map_err(|_| "The audit signing key is not hex".to_string())?;
+        let key = SecretKey::from_slice(&bytes).map_err(|_| "The audit signing key is not a secp256k1 secret key".to_string())?;
+        let public_key = hex::encode(PublicKey::from_secret_key(&Secp256k1::signing_only(), &key).serialize());
+        Ok(Signer { key, public_key })
+    }
+
+    pub fn from_env() -> Result<Self, String> {
+        Self::new(&config::secret("AUDIT_SIGNING_KEY").ok_or_else(|| "AUDIT_SIGNING_KEY is not set".to_string())?)
+    }
+
+    pub fn public_key(&self) -> &str {
+        &self.public_key
+    }
+
+    fn sign(&self, seq: u64, hash: &str) -> String {
+        let signature = Secp256k1::signing_only().sign_ecdsa(&checkpoint_message(seq, hash), &self.key);
+        hex::encode(signature.serialize_compact())
+    }
+}
+
+// The head of the chain while an export is written.
+pub struct HashChain {
+    seq: u64,
+    head: String,
+    signer: Signer,
+}
+
+impl HashChain {
+    pub fn new(signer: Signer) -> Self {
+        HashChain { seq: 0, head: GENESIS_HASH.to_string(), signer }
+    }
+
+    // The record of the next entry, followed by a checkpoint when it ends an interval.
+    pub fn append(&mut self, entry: AuditEntry) -> Vec<ExportRecord> {
+        let hash = entry_hash(&self.head, &entry);
+        self.seq += 1;
+        let prev_hash = std::mem::replace(&mut self.head, hash.clone());
+        let mut records = vec![ExportRecord::Entry { seq: self.seq, prev_hash, hash, entry }];
+        if self.seq.is_multiple_of(CHECKPOINT_INTERVAL) {
+            records.push(self.checkpoint(false));
+        }
+        records
+    }
+
+    pub fn checkpoint(&self, is_final: bool) -> ExportRecord {
+        ExportRecord::Checkpoint {
+            seq: self.seq,
+            hash: self.head.clone(),
+            signed_at: chrono::Local::now().naive_local(),
+            public_key: self.signer.public_key.clone(),
+            signature: self.signer.sign(self.seq, &self.head),
+            is_final,
+        }
+    }
+}
+
+// Checks an export against the public key of the signer and returns the number of entries.
+pub fn verify(ndjson: &str, public_key: &str) -> Result<u64, String> {
+    let secp = Secp256k1::verification_only();
+    let key = hex::decode(public_key)
+        .ok()
+        .and_then(|bytes| PublicKey::from_slice(&bytes).ok())
+        .ok_or_else(|| "Invalid public key".to_string())?;
+
+    let (mut seq, mut head, mut finished) = (0, GENESIS_HASH.to_string(), false);
+    for (number, line) in ndjson.lines().enumerate().filter(|(_, line)| !line.trim().is_empty()) {
+        let number = number + 1;
+        if finished {
+            return Err(format!("Line {}: records after the final checkpoint", number));
+        }
+        let record: ExportRecord = serde_json::from_str(line).map_err(|err| format!("Line {}: {}", number, err))?;
+        match record {
+            ExportRecord::Entry { seq: entry_seq, prev_hash, hash, entry } => {
+                if entry_seq != seq + 1 {
+                    return Err(format!("Line {}: expected entry {}, found {}", number, seq + 1, entry_seq));
+                }
+                if prev_hash != head || hash != entry_hash(&head, &entry) {
+                    return Err(format!("Line {}: entry {} does not match the chain", number, entry_seq));
+                }
+                seq = entry_seq;
+                head = hash;
+            }
+            ExportRecord::Checkpoint { seq: checkpoint_seq, hash, public_key: signed_by, signature, is_final, .. } => {
+                if checkpoint_seq != seq || hash != head {
+                    return Err(format!("Line {}: checkpoint {} does not match the chain", number, checkpoint_seq));
+                }
+                if signed_by != public_key {
+                    return Err(format!("Line {}: checkpoint signed by another key", number));
+                }
+                let signature = hex::decode(&signature)
+                    .ok()
+                    .and_then(|bytes| Signature::from_compact(&bytes).ok())
+                    .ok_or_else(|| format!("Line {}: invalid signature", number))?;
+                secp.verify_ecdsa(&checkpoint_message(checkpoint_seq, &hash), &signature, &key)
+                    .map_err(|_| format!("Line {}: checkpoint {} has a bad signature", number, checkpoint_seq))?;
+                finished = is_final;
+            }
+        }
+    }
+
+    if !finished {
+        return Err("The export has no final checkpoint".to_string());
+    }
+    Ok(seq)
+}
+
+pub async fn export(req: HttpRequest, pool: web::Data<DbPool>) -> HttpResponse {
+    if let Err(err) = jwt::require_admin(&req) {
+        return err.error_response();
+    }
+    let signer = match Signer::from_env() {
+        Ok(signer) => signer,
+        Err(err) => return AppError::Internal(err).error_response(),
+    };
+
+    // The state is the chain and the cursor of the next page: `Some(None)` for the first one, `None` once the final
+    // checkpoint was sent.
+    type Cursor = Option<Option<(chrono::NaiveDateTime, String)>>;
+    let pages = futures::stream::unfold((HashChain::new(signer), Some(None)), move |(mut chain, cursor): (HashChain, Cursor)| {
+        let pool = pool.clone();
+        async move {
+            let after = cursor?;
+            let page = pool
+                .get()
+                .map_err(|err| AppError::Internal(err.to_string()))
+                .and_then(|mut conn| AuditEntry::export_page(&mut conn, after.as_ref(), EXPORT_PAGE_SIZE).map_err(AppError::from));
+            let page = match page {
+                Ok(page) => page,
+                Err(err) => return Some((Err(err.into()), (chain, None))),
+            };
+            let next = match page.last() {
+                Some(last) if page.len() as i64 == EXPORT_PAGE_SIZE => Some(Some((last.created_at, last.id.clone()))),
+                _ => None,
+            };
+
+            let mut chunk = String::new();
+            for entry in page {
+                chain.append(entry).iter().for_each(|record| chunk.push_str(&record.to_line()));
+            }
+            if next.is_none() {
+                chunk.push_str(&chain.checkpoint(true).to_line());
+            }
+            Some((Ok(web::Bytes::from(chunk)), (chain, next)))
+        }
+    });
+
+    HttpResponse::Ok()
+        .content_type(NDJSON_CONTENT_TYPE)
+        .insert_header((CONTENT_DISPOSITION, "attachment; filename=\"audit-log.ndjson\""))
+        .streaming::<_, actix_web::Error>(pages)
+}
+
+pub fn init_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(web::resource("/admin/audit/export").route(web::get().to(export)));
+}
@@ -0,0 +1,87 @@
+use crate::db::fixtures::test_connection;
+use crate::db::models::audit::AuditEntry;
+use super::audit_export::{verify, ExportRecord, HashChain, Signer, CHECKPOINT_INTERVAL};
+
+const SIGNING_KEY: &str = "0101010101010101010101010101010101010101010101010101010101010101";
+
+fn entry(index: usize) -> AuditEntry {
+    AuditEntry {
+        id: format!("entry-{:04}", index),
+        actor_id: "admin".to_string(),
+        action: "recompute".to_string(),
+        target_id: format!("job-{}", index),
+        details: serde_json::json!({"what": "fees"}).to_string(),
+        created_at: chrono::NaiveDate::from_ymd_opt(2023, 9, 1).unwrap().and_hms_opt(12, 0, 0).unwrap() + chrono::Duration::seconds(index as i64),
+    }
+}
+
+fn export(entries: usize) -> (Vec<String>, String) {
+    let signer = Signer::new(SIGNING_KEY).unwrap();
+    let public_key = signer.public_key().to_string();
+    let mut chain = HashChain::new(signer);
+    let mut lines: Vec<String> = (0..entries).flat_map(|index| chain.append(entry(index))).map(|record| record.to_line()).collect();
+    lines.push(chain.checkpoint(true).to_line());
+    (lines, public_key)
+}
+
+#[test]
+fn exports_verify_with_a_checkpoint_every_interval() {
+    let (lines, public_key) = export(250);
+    assert_eq!(verify(&lines.concat(), &public_key), Ok(250));
+
+    let checkpoints: Vec<(u64, bool)> = lines
+        .iter()
+        .filter_map(|line| match serde_json::from_str(line).unwrap() {
+            ExportRecord::Checkpoint { seq, is_final, .. } => Some((seq, is_final)),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(checkpoints, vec![(CHECKPOINT_INTERVAL, false), (2 * CHECKPOINT_INTERVAL, false), (250, true)]);
+
+    let (empty, _) = export(0);
+    assert_eq!(verify(&empty.concat(), &public_key), Ok(0));
+}
+
+#[test]
+fn altered_removed_or_truncated_exports_fail_verification() {
+    let (lines, public_key) = export(120);
+
+    let mut altered = lines.clone();
+    altered[5] = altered[5].replace("job-5", "job-6");
+    assert_eq!(verify(&altered.concat(), &public_key), Err("Line 6: entry 6 does not match the chain".to_string()));
+
+    let mut removed = lines.clone();
+    removed.remove(110);
+    assert!(verify(&removed.concat(), &public_key).is_err());
+
+    let truncated = &lines[..lines.len() - 1];
+    assert_eq!(verify(&truncated.concat(), &public_key), Err("The export has no final checkpoint".to_string()));
+
+    let other = Signer::new(&"02".repeat(32)).unwrap();
+    assert_eq!(verify(&lines.concat(), other.public_key()), Err("Line 101: checkpoint signed by another key".to_string()));
+
+    // A chain rebuilt after removing an entry cannot be signed without the key.
+    let forged = lines[100].replace(&public_key, other.public_key());
+    let mut forged_lines = lines.clone();
+    forged_lines[100] = forged;
+    assert!(verify(&forged_lines.concat(), other.public_key()).unwrap_err().contains("bad signature"));
+}
+
+#[test]
+fn export_pages_follow_the_cursor_oldest_first() {
+    let conn = &mut test_connection();
+    for index in 0..5 {
+        AuditEntry::record(conn, "admin", "recompute", &format!("job-{}", index), serde_json::json!({})).unwrap();
+    }
+
+    let first = AuditEntry::export_page(conn, None, 3).unwrap();
+    let last = first.last().unwrap();
+    let second = AuditEntry::export_page(conn, Some(&(last.created_at, last.id.clone())), 3).unwrap();
+    assert_eq!((first.len(), second.len()), (3, 2));
+
+    let exported: Vec<AuditEntry> = first.into_iter().chain(second).collect();
+    let mut expected = AuditEntry::list(conn, 10, 0).unwrap();
+    expected.reverse();
+    expected.sort_by(|a, b| (a.created_at, &a.id).cmp(&(b.created_at, &b.id)));
+    assert_eq!(exported, expected);
+}
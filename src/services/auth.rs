@@ -0,0 +1,79 @@
+//! This module defines the token refresh and logout endpoints.
+//!
+//! Logging in (`POST /login`, see `services::user`) returns a `TokenPair`: a short-lived JWT access token and a
+//! long-lived refresh token. The provided functions include:
+//!
+//! - `issue_tokens`: Creates a new access token and refresh token for a user.
+//! - `refresh`: Exchanges a refresh token for a new `TokenPair` (`POST /auth/refresh`). The presented refresh token is
+//!   revoked, so each refresh token works once; an unknown, expired or revoked token is a `401`.
+//! - `logout`: Revokes a refresh token (`POST /auth/logout`), ending the session once the current access token
+//!   expires. Revoking a token that is unknown or already revoked is not an error.
+//!
+//! Both endpoints take a JSON body of the form `{"refresh_token": "..."}`.
+//!
+//! # Note
+//! These routes are not wrapped with the `JwtGuard` middleware: the refresh token is the credential, and the access
+//! token is usually already expired when a client refreshes.
+
+use actix_web::{web, HttpResponse, ResponseError};
+use diesel::SqliteConnection;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::db::models::refresh_token::RefreshToken;
+use crate::db::{self, DbPool};
+use crate::error::AppError;
+use crate::middleware::load_shed::LoadShed;
+use crate::config::settings;
+use crate::services::jwt::create_jwt;
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct TokenPair {
+    pub access_token: String,
+    pub refresh_token: String,
+    pub token_type: String,
+    pub expires_in: i64,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct RefreshForm {
+    pub refresh_token: String,
+}
+
+fn token_pair(user_id: String, refresh_token: String) -> Result<TokenPair, AppError> {
+    let access_token = create_jwt(user_id).map_err(|err| AppError::Internal(format!("Error creating token: {}", err)))?;
+    Ok(TokenPair {
+        access_token,
+        refresh_token,
+        token_type: "Bearer".to_string(),
+        expires_in: settings::get().jwt.access_token_minutes * 60,
+    })
+}
+
+pub fn issue_tokens(conn: &mut SqliteConnection, user_id: String) -> Result<TokenPair, AppError> {
+    let (refresh_token, _) = RefreshToken::issue(conn, user_id.clone())?;
+    token_pair(user_id, refresh_token)
+}
+
+pub async fn refresh(pool: web::Data<DbPool>, form: web::Json<RefreshForm>) -> HttpResponse {
+    match db::run(&pool, move |conn| Ok(RefreshToken::rotate(conn, &form.refresh_token)?)).await {
+        Ok(Some((refresh_token, record))) => match token_pair(record.user_id, refresh_token) {
+            Ok(tokens) => HttpResponse::Ok().json(tokens),
+            Err(err) => err.error_response(),
+        },
+        Ok(None) => AppError::Unauthorized("Invalid or expired refresh token".to_string()).error_response(),
+        Err(err) => err.error_response(),
+    }
+}
+
+pub async fn logout(pool: web::Data<DbPool>, form: web::Json<RefreshForm>) -> HttpResponse {
+    match db::run(&pool, move |conn| Ok(RefreshToken::revoke(conn, &form.refresh_token)?)).await {
+        Ok(_) => HttpResponse::Ok().json("Logged out"),
+        Err(err) => err.error_response(),
+    }
+}
+
+pub fn init_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(web::resource("/auth/refresh").route(web::post().to(refresh).wrap(LoadShed::high_priority())))
+        .service(web::resource("/auth/logout").route(web::post().to(logout).wrap(LoadShed::high_priority())));
+}
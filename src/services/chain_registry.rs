@@ -0,0 +1,90 @@
+//! This module defines the admin API of the chain registry.
+//!
+//! The provided functions include:
+//!
+//! - `list_chains`: Lists every supported chain with its effective transaction URL template and whether it is
+//!   overridden (`GET /admin/chains`).
+//! - `set_explorer`: Overrides the transaction URL template of a chain (`PUT /admin/chains/{chain}/explorer` with
+//!   `{"tx_url_template": "https://.../tx/{hash}"}`).
+//! - `reset_explorer`: Restores the built-in template of a chain (`DELETE /admin/chains/{chain}/explorer`).
+//!
+//! The templates are used for `explorer_url` on trade responses and by `GET /metadata`.
+//!
+//! # Note
+//! All routes are wrapped with the `JwtGuard` middleware and restricted to admins (`ADMIN_USER_IDS`).
+
+use actix_web::{web, HttpRequest, HttpResponse, ResponseError};
+use serde::{Deserialize, Serialize};
+
+use crate::db::models::chain_explorer::ChainExplorer;
+use crate::db::models::trade::Chain;
+use crate::db::{self, DbPool};
+use crate::error::AppError;
+use crate::middleware::jwt_guard::JwtGuard;
+use crate::services::{jwt, metadata};
+
+#[derive(Serialize, Deserialize)]
+pub struct ExplorerForm {
+    pub tx_url_template: String,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ChainEntry {
+    pub chain: String,
+    pub tx_url_template: String,
+    pub overridden: bool,
+}
+
+pub async fn list_chains(req: HttpRequest, pool: web::Data<DbPool>) -> HttpResponse {
+    if let Err(err) = jwt::require_admin(&req) {
+        return err.error_response();
+    }
+
+    let overrides = match db::run(&pool, move |conn| Ok(ChainExplorer::list(conn)?)).await {
+        Ok(overrides) => overrides,
+        Err(err) => return err.error_response(),
+    };
+    let defaults = metadata::default_tx_url_templates();
+
+    let chains: Vec<ChainEntry> = Chain::ALL
+        .iter()
+        .map(|chain| match overrides.iter().find(|explorer| explorer.chain == *chain) {
+            Some(explorer) => ChainEntry { chain: chain.to_string(), tx_url_template: explorer.tx_url_template.clone(), overridden: true },
+            None => ChainEntry { chain: chain.to_string(), tx_url_template: defaults[*chain].clone(), overridden: false },
+        })
+        .collect();
+    HttpResponse::Ok().json(chains)
+}
+
+pub async fn set_explorer(req: HttpRequest, pool: web::Data<DbPool>, chain: web::Path<String>, form: web::Json<ExplorerForm>) -> HttpResponse {
+    if let Err(err) = jwt::require_admin(&req) {
+        return err.error_response();
+    }
+
+    match db::run(&pool, move |conn| Ok(ChainExplorer::set(conn, chain.into_inner(), form.into_inner().tx_url_template)?)).await {
+        Ok((Some(explorer), None)) => HttpResponse::Ok().json(explorer),
+        Ok((_, errors)) => AppError::Validation(errors.unwrap_or_default()).error_response(),
+        Err(err) => err.error_response(),
+    }
+}
+
+pub async fn reset_explorer(req: HttpRequest, pool: web::Data<DbPool>, chain: web::Path<String>) -> HttpResponse {
+    if let Err(err) = jwt::require_admin(&req) {
+        return err.error_response();
+    }
+
+    match db::run(&pool, move |conn| Ok(ChainExplorer::reset(conn, chain.into_inner())?)).await {
+        Ok(true) => HttpResponse::Ok().json("Explorer template reset"),
+        Ok(false) => AppError::NotFound("Chain has no explorer override".to_string()).error_response(),
+        Err(err) => err.error_response(),
+    }
+}
+
+pub fn init_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(web::resource("/admin/chains").route(web::get().to(list_chains).wrap(JwtGuard)))
+        .service(
+            web::resource("/admin/chains/{chain}/explorer")
+                .route(web::put().to(set_explorer).wrap(JwtGuard))
+                .route(web::delete().to(reset_explorer).wrap(JwtGuard)),
+        );
+}
@@ -0,0 +1,128 @@
+//! This module implements incremental sync for offline-first clients.
+//!
+//! `GET /changes?trader_id=&since=` returns every entity of the trader that changed after the `since` cursor: trades,
+//! each of the trader's wallets (`UserWallet::list_for_user`) and their approval policies (the wallets' settings),
+//! plus tombstones for deleted trades. Callers sync their own changes; admins may sync anyone's. Omitting
+//! `since` returns a full snapshot. The response carries the `cursor` to pass as `since` on the next call; it is an
+//! opaque token that points at the newest change included, so nothing committed after it is skipped.
+//!
+//! # Examples
+//!
+//! ```text
+//! GET /changes?trader_id=abc
+//! { "cursor": "323032...", "trades": [...], "wallets": [...], "settings": [...], "tombstones": [] }
+//!
+//! GET /changes?trader_id=abc&since=323032...
+//! { "cursor": "323032...", "trades": [], "wallets": [], "settings": [], "tombstones": [{ "entity": "trade", ... }] }
+//! ```
+//!
+//! # Note
+//! The route is wrapped with the `JwtGuard` middleware for secure access.
+
+use actix_web::{web, HttpRequest, HttpResponse, ResponseError};
+use chrono::NaiveDateTime;
+use diesel::SqliteConnection;
+use serde::{Deserialize, Serialize};
+
+use crate::db::models::tombstone::Tombstone;
+use crate::db::models::trade::Trade;
+use crate::db::models::transfer::ApprovalPolicy;
+use crate::db::models::user::User;
+use crate::db::models::user_wallet::UserWallet;
+use crate::db::models::wallet::Wallet;
+use crate::db::{self, DbPool};
+use crate::error::AppError;
+use crate::middleware::jwt_guard::JwtGuard;
+use crate::services::jwt;
+
+const CURSOR_FORMAT: &str = "%Y-%m-%d %H:%M:%S%.f";
+
+#[derive(Serialize, Deserialize)]
+pub struct ChangesQuery {
+    pub trader_id: String,
+    pub since: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct Changes {
+    pub cursor: Option<String>,
+    pub trades: Vec<Trade>,
+    pub wallets: Vec<Wallet>,
+    pub settings: Vec<ApprovalPolicy>,
+    pub tombstones: Vec<Tombstone>,
+}
+
+pub fn encode_cursor(timestamp: NaiveDateTime) -> String {
+    hex::encode(timestamp.format(CURSOR_FORMAT).to_string())
+}
+
+pub fn decode_cursor(cursor: &str) -> Option<NaiveDateTime> {
+    let decoded = String::from_utf8(hex::decode(cursor).ok()?).ok()?;
+    NaiveDateTime::parse_from_str(&decoded, CURSOR_FORMAT).ok()
+}
+
+// Every entity of the trader that changed after `since`, with the cursor of the newest one.
+fn collect(conn: &mut SqliteConnection, trader_id: String, since: Option<NaiveDateTime>) -> Result<Changes, AppError> {
+    let user = match User::find_by_id(conn, trader_id)? {
+        Some(user) => user,
+        None => return Err(AppError::NotFound("User not found".to_string())),
+    };
+    let changed = |updated_at: NaiveDateTime| since.is_none_or(|since| updated_at > since);
+
+    let trades = Trade::changed_since(conn, user.id.clone(), since)?;
+    let (mut wallets, mut settings) = (Vec::new(), Vec::new());
+    for owned in UserWallet::list_for_user(conn, &user.id)? {
+        wallets.extend(Wallet::find_by_id(conn, owned.id.clone())?.into_iter().filter(|wallet| changed(wallet.updated_at)));
+        settings.extend(ApprovalPolicy::find_by_wallet(conn, owned.id)?.into_iter().filter(|policy| changed(policy.updated_at)));
+    }
+    let tombstones = Tombstone::since(conn, user.id, since)?;
+
+    let latest = trades
+        .iter()
+        .map(|trade| trade.updated_at)
+        .chain(wallets.iter().map(|wallet| wallet.updated_at))
+        .chain(settings.iter().map(|policy| policy.updated_at))
+        .chain(tombstones.iter().map(|tombstone| tombstone.deleted_at))
+        .max()
+        .or(since);
+
+    Ok(Changes {
+        cursor: latest.map(encode_cursor),
+        trades,
+        wallets,
+        settings,
+        tombstones,
+    })
+}
+
+fn ensure_can_view(req: &HttpRequest, trader_id: &str) -> Result<(), AppError> {
+    match jwt::user_id(req) {
+        Some(caller_id) if caller_id != trader_id && !jwt::is_admin(&caller_id) => {
+            Err(AppError::Forbidden("Changes belong to another user".to_string()))
+        }
+        _ => Ok(()),
+    }
+}
+
+pub async fn changes(req: HttpRequest, pool: web::Data<DbPool>, params: web::Query<ChangesQuery>) -> HttpResponse {
+    if let Err(err) = ensure_can_view(&req, &params.trader_id) {
+        return err.error_response();
+    }
+    let since = match params.since.as_deref() {
+        Some(cursor) if !cursor.is_empty() => match decode_cursor(cursor) {
+            Some(since) => Some(since),
+            None => return HttpResponse::BadRequest().json("Error: Invalid cursor"),
+        },
+        _ => None,
+    };
+
+    let trader_id = params.into_inner().trader_id;
+    match db::run(&pool, move |conn| collect(conn, trader_id, since)).await {
+        Ok(changes) => HttpResponse::Ok().json(changes),
+        Err(err) => err.error_response(),
+    }
+}
+
+pub fn init_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(web::resource("/changes").route(web::get().to(changes).wrap(JwtGuard)));
+}
@@ -0,0 +1,45 @@
+use actix_web::http::header::AUTHORIZATION;
+use actix_web::http::StatusCode;
+use actix_web::test::{call_service, init_service, read_body_json, TestRequest};
+use actix_web::{web, App};
+use chrono::NaiveDate;
+
+use crate::db::fixtures::{test_pool, user};
+use crate::db::models::user_wallet::UserWallet;
+use super::changes::{decode_cursor, encode_cursor, init_routes};
+use super::jwt::create_jwt;
+
+#[test]
+fn cursor_round_trips() {
+    let timestamp = NaiveDate::from_ymd_opt(2023, 8, 1).unwrap().and_hms_micro_opt(12, 30, 15, 250).unwrap();
+
+    assert_eq!(decode_cursor(&encode_cursor(timestamp)), Some(timestamp));
+}
+
+#[test]
+fn rejects_malformed_cursors() {
+    assert_eq!(decode_cursor("not-hex"), None);
+    assert_eq!(decode_cursor(&hex::encode("yesterday")), None);
+}
+
+#[actix_web::test]
+async fn owners_sync_every_wallet_and_strangers_are_refused() {
+    std::env::set_var("JWT_SECRET", "test_secret");
+    let pool = test_pool();
+    let conn = &mut pool.get().unwrap();
+    let owner = user(conn, "owner");
+    let savings = UserWallet::create(conn, &owner.id, "Savings").unwrap().0.unwrap();
+    let app = init_service(App::new().app_data(web::Data::new(pool.clone())).configure(init_routes)).await;
+    let uri = format!("/changes?trader_id={}", owner.id);
+
+    let token = create_jwt(owner.id.clone()).unwrap();
+    let response = call_service(&app, TestRequest::get().uri(&uri).insert_header((AUTHORIZATION, token)).to_request()).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let body: serde_json::Value = read_body_json(response).await;
+    let wallets: Vec<&str> = body["wallets"].as_array().unwrap().iter().map(|wallet| wallet["id"].as_str().unwrap()).collect();
+    assert_eq!(wallets, vec![owner.wallet_id.as_str(), savings.id.as_str()]);
+
+    let stranger = create_jwt("stranger".to_string()).unwrap();
+    let response = call_service(&app, TestRequest::get().uri(&uri).insert_header((AUTHORIZATION, stranger)).to_request()).await;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+}
@@ -0,0 +1,77 @@
+//! This module defines the endpoints for managing delegated trade entry.
+//!
+//! The provided functions include:
+//!
+//! - `list_delegates`: Lists the active delegations of an owner (`GET /user/{owner_id}/delegates`).
+//! - `grant_delegate`: Allows a delegate to act on the owner's trades within one scope (`create`, `update`, `delete`
+//!   or `propose`) (`PUT /user/{owner_id}/delegates/{delegate_id}/{scope}`).
+//! - `revoke_delegate`: Revokes that permission again (`DELETE /user/{owner_id}/delegates/{delegate_id}/{scope}`).
+//!
+//! Only the owner may grant or revoke delegations, and only the owner and admins may list them; requests whose token
+//! belongs to another user are rejected with a `403`. The permissions themselves are enforced by the trade service.
+//!
+//! # Note
+//! All routes are wrapped with the `JwtGuard` middleware for secure access.
+
+use actix_web::{web, HttpRequest, HttpResponse, ResponseError};
+
+use crate::db::models::delegation::TradeDelegation;
+use crate::db::{self, DbPool};
+use crate::error::AppError;
+use crate::middleware::jwt_guard::JwtGuard;
+use crate::services::jwt;
+
+fn ensure_owner(req: &HttpRequest, owner_id: &str) -> Result<(), AppError> {
+    match jwt::user_id(req) {
+        Some(actor_id) if actor_id != owner_id => Err(AppError::Forbidden("Only the owner can manage delegations".to_string())),
+        _ => Ok(()),
+    }
+}
+
+pub async fn list_delegates(req: HttpRequest, pool: web::Data<DbPool>, owner_id: web::Path<String>) -> HttpResponse {
+    let owner_id = owner_id.into_inner();
+    let is_admin = jwt::user_id(&req).is_some_and(|caller_id| jwt::is_admin(&caller_id));
+    if let (false, Err(err)) = (is_admin, ensure_owner(&req, &owner_id)) {
+        return err.error_response();
+    }
+
+    match db::run(&pool, move |conn| Ok(TradeDelegation::list_for_owner(conn, owner_id)?)).await {
+        Ok(delegations) => HttpResponse::Ok().json(delegations),
+        Err(err) => err.error_response(),
+    }
+}
+
+pub async fn grant_delegate(req: HttpRequest, pool: web::Data<DbPool>, path: web::Path<(String, String, String)>) -> HttpResponse {
+    let (owner_id, delegate_id, scope) = path.into_inner();
+    if let Err(err) = ensure_owner(&req, &owner_id) {
+        return err.error_response();
+    }
+
+    match db::run(&pool, move |conn| Ok(TradeDelegation::grant(conn, owner_id, delegate_id, scope)?)).await {
+        Ok((Some(delegation), None)) => HttpResponse::Ok().json(delegation),
+        Ok((_, errors)) => AppError::Validation(errors.unwrap_or_default()).error_response(),
+        Err(err) => err.error_response(),
+    }
+}
+
+pub async fn revoke_delegate(req: HttpRequest, pool: web::Data<DbPool>, path: web::Path<(String, String, String)>) -> HttpResponse {
+    let (owner_id, delegate_id, scope) = path.into_inner();
+    if let Err(err) = ensure_owner(&req, &owner_id) {
+        return err.error_response();
+    }
+
+    match db::run(&pool, move |conn| Ok(TradeDelegation::revoke(conn, owner_id, delegate_id, scope)?)).await {
+        Ok(true) => HttpResponse::Ok().json("Delegation revoked"),
+        Ok(false) => AppError::NotFound("Delegation not found".to_string()).error_response(),
+        Err(err) => err.error_response(),
+    }
+}
+
+pub fn init_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(web::resource("/user/{owner_id}/delegates").route(web::get().to(list_delegates).wrap(JwtGuard)))
+        .service(
+            web::resource("/user/{owner_id}/delegates/{delegate_id}/{scope}")
+                .route(web::put().to(grant_delegate).wrap(JwtGuard))
+                .route(web::delete().to(revoke_delegate).wrap(JwtGuard)),
+        );
+}
@@ -0,0 +1,30 @@
+use actix_web::http::header::AUTHORIZATION;
+use actix_web::http::StatusCode;
+use actix_web::test::{call_service, init_service, read_body_json, TestRequest};
+use actix_web::{web, App};
+
+use crate::db::fixtures::{test_pool, user};
+use crate::db::models::delegation::{DelegationScope, TradeDelegation};
+use super::delegation::init_routes;
+use super::jwt::create_jwt;
+
+#[actix_web::test]
+async fn delegates_are_listed_to_their_owner() {
+    std::env::set_var("JWT_SECRET", "test_secret");
+    let pool = test_pool();
+    let conn = &mut pool.get().unwrap();
+    let (owner, delegate) = (user(conn, "owner"), user(conn, "delegate"));
+    TradeDelegation::grant(conn, owner.id.clone(), delegate.id.clone(), DelegationScope::PROPOSE.to_string()).unwrap();
+    let app = init_service(App::new().app_data(web::Data::new(pool.clone())).configure(init_routes)).await;
+    let uri = format!("/user/{}/delegates", owner.id);
+
+    // Being a delegate does not reveal the owner's other delegations.
+    for stranger in [delegate.id, "stranger".to_string()] {
+        let req = TestRequest::get().uri(&uri).insert_header((AUTHORIZATION, create_jwt(stranger).unwrap())).to_request();
+        assert_eq!(call_service(&app, req).await.status(), StatusCode::FORBIDDEN);
+    }
+
+    let req = TestRequest::get().uri(&uri).insert_header((AUTHORIZATION, create_jwt(owner.id).unwrap())).to_request();
+    let delegations: serde_json::Value = read_body_json(call_service(&app, req).await).await;
+    assert_eq!(delegations.as_array().unwrap().len(), 1);
+}
@@ -0,0 +1,110 @@
+//! This module defines the endpoints for changing a user's email address with re-verification.
+//!
+//! The provided functions include:
+//!
+//! - `request_change`: Starts a change to the address in `{"email": "..."}` (`POST /user/{user_id}/email`). A
+//!   confirmation token is emailed to the new address and the current one is notified; the account keeps its current
+//!   address until the change is confirmed. Requesting again replaces the pending change.
+//! - `pending_change`: Shows the pending change, if any (`GET /user/{user_id}/email`).
+//! - `cancel_change`: Cancels the pending change (`DELETE /user/{user_id}/email`).
+//! - `confirm_change`: Applies a change from `{"token": "..."}` (`POST /user/email/confirm`). An unknown, expired,
+//!   cancelled or already used token is a `401`; an address taken in the meantime is a `409`.
+//!
+//! # Note
+//! The per-user routes are wrapped with the `JwtGuard` middleware and only serve the user themselves. The confirmation
+//! route is not: the token is the credential, and it is usually opened from the mailbox of the new address.
+
+use actix_web::{web, HttpRequest, HttpResponse, ResponseError};
+use serde::{Deserialize, Serialize};
+
+use crate::db::models::email_change::{EmailChange, INVALID_TOKEN};
+use crate::db::models::user::EMAIL_EXISTS;
+use crate::db::{self, DbPool};
+use crate::error::AppError;
+use crate::middleware::{jwt_guard::JwtGuard, load_shed::LoadShed};
+use crate::services::jwt;
+
+#[derive(Serialize, Deserialize)]
+pub struct EmailChangeForm {
+    pub email: String,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ConfirmForm {
+    pub token: String,
+}
+
+fn ensure_self(req: &HttpRequest, user_id: &str) -> Result<(), AppError> {
+    match jwt::user_id(req) {
+        Some(actor_id) if actor_id != user_id => Err(AppError::Forbidden("Only the user can change their email".to_string())),
+        _ => Ok(()),
+    }
+}
+
+fn error_response(error: String) -> HttpResponse {
+    match error.as_str() {
+        EMAIL_EXISTS => AppError::Conflict(error).error_response(),
+        INVALID_TOKEN => AppError::Unauthorized(error).error_response(),
+        _ => AppError::Validation(error).error_response(),
+    }
+}
+
+pub async fn request_change(req: HttpRequest, pool: web::Data<DbPool>, user_id: web::Path<String>, form: web::Json<EmailChangeForm>) -> HttpResponse {
+    let user_id = user_id.into_inner();
+    if let Err(err) = ensure_self(&req, &user_id) {
+        return err.error_response();
+    }
+
+    match db::run(&pool, move |conn| Ok(EmailChange::request(conn, user_id, form.into_inner().email)?)).await {
+        Ok((Some(change), None)) => HttpResponse::Ok().json(change),
+        Ok((_, errors)) => error_response(errors.unwrap_or_default()),
+        Err(err) => err.error_response(),
+    }
+}
+
+pub async fn pending_change(req: HttpRequest, pool: web::Data<DbPool>, user_id: web::Path<String>) -> HttpResponse {
+    let user_id = user_id.into_inner();
+    if let Err(err) = ensure_self(&req, &user_id) {
+        return err.error_response();
+    }
+
+    match db::run(&pool, move |conn| Ok(EmailChange::find_pending(conn, user_id)?)).await {
+        Ok(Some(change)) => HttpResponse::Ok().json(change),
+        Ok(None) => AppError::NotFound("No pending email change".to_string()).error_response(),
+        Err(err) => err.error_response(),
+    }
+}
+
+pub async fn cancel_change(req: HttpRequest, pool: web::Data<DbPool>, user_id: web::Path<String>) -> HttpResponse {
+    let user_id = user_id.into_inner();
+    if let Err(err) = ensure_self(&req, &user_id) {
+        return err.error_response();
+    }
+
+    match db::run(&pool, move |conn| Ok(EmailChange::cancel(conn, user_id)?)).await {
+        Ok(true) => HttpResponse::Ok().json("cancelled"),
+        Ok(false) => AppError::NotFound("No pending email change".to_string()).error_response(),
+        Err(err) => err.error_response(),
+    }
+}
+
+pub async fn confirm_change(pool: web::Data<DbPool>, form: web::Json<ConfirmForm>) -> HttpResponse {
+    match db::run(&pool, move |conn| Ok(EmailChange::confirm(conn, &form.token)?)).await {
+        Ok((Some(user), None)) => HttpResponse::Ok().json(user),
+        Ok((_, errors)) => error_response(errors.unwrap_or_default()),
+        Err(err) => err.error_response(),
+    }
+}
+
+pub fn init_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::resource("/user/email/confirm")
+            .route(web::post().to(confirm_change).wrap(LoadShed::high_priority())),
+    );
+    cfg.service(
+        web::resource("/user/{user_id}/email")
+            .route(web::get().to(pending_change).wrap(JwtGuard))
+            .route(web::post().to(request_change).wrap(JwtGuard))
+            .route(web::delete().to(cancel_change).wrap(JwtGuard)),
+    );
+}
@@ -0,0 +1,359 @@
+//! This module implements the inbound email gateway that turns broker trade confirmations into trades.
+//!
+//! Mail providers forward inbound messages to `POST /inbound/email` as JSON (`from`, `subject`, `text`), authenticated
+//! with the shared secret from the `INBOUND_EMAIL_SECRET` environment variable in the `X-Inbound-Secret` header. The
+//! sender address is matched against registered users; mail from unknown senders is dropped.
+//!
+//! Supported confirmations use the `Label: value` layout common to broker emails. Labels are matched
+//! case-insensitively against known aliases (`Side`/`Action`, `Quantity`/`Qty`/`Filled Quantity`, `Symbol`/`Asset`,
+//! `Price`/`Fill Price`/`Average Price`, `Order Type`, `Chain`/`Network`, `Date`/`Executed At`); other lines are ignored.
+//! Pairs such as `ETH/USD` or `ETHUSDT` are reduced to the base asset.
+//!
+//! A confirmation that resolves to a valid trade is created through the trade journal for the user's wallet, with the
+//! source `import:email`, as are trades accepted from the review queue. Anything ambiguous — a missing or conflicting
+//! field, an unknown asset or chain — lands in the user's review queue instead, where it can be accepted with
+//! corrected values or rejected:
+//!
+//! - `receive`: Webhook endpoint for inbound mail.
+//! - `list_reviews`: Lists the caller's pending reviews (`GET /email-reviews`).
+//! - `accept_review`: Creates the trade from a corrected `TradeForm` and resolves the review. The trade must be for the
+//!   review's user and one of their wallets; its metadata records the review in `email_review_id`.
+//! - `reject_review`: Discards the review.
+//!
+//! Reviews hold the original email, so only the review's user (or an admin, see `ADMIN_USER_IDS`) may accept or reject
+//! it; anyone else gets `403 Forbidden`.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use crate::services::email_in::parse;
+//!
+//! let parsed = parse("Side: Buy\nQuantity: 1.5\nSymbol: ETH/USD\nFill Price: $1,850.00\nNetwork: Arbitrum");
+//! let form = parsed.to_trade_form("user_id".to_string(), "wallet_id".to_string()).unwrap();
+//! assert_eq!(form.trade_type, "LimitBuy");
+//! ```
+
+use actix_web::{web, HttpRequest, HttpResponse, ResponseError};
+use chrono::{DateTime, NaiveDateTime};
+use diesel::SqliteConnection;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::config;
+use crate::db::models::email_review::{EmailReview, ReviewStatus};
+use crate::db::models::trade::{Asset, TradeSource};
+use crate::db::models::user::User;
+use crate::db::{self, DbPool};
+use crate::error::AppError;
+use crate::middleware::jwt_guard::JwtGuard;
+use crate::services::journal::TradeJournal;
+use crate::services::jwt;
+use crate::services::quick_entry::normalize_chain;
+use crate::services::trade::{ensure_user_wallet, with_explorer_url, TradeForm, TradeResponse};
+
+#[derive(Debug, Default, Serialize, Deserialize, PartialEq)]
+pub struct ParsedConfirmation {
+    pub side: Option<String>,
+    pub order_type: Option<String>,
+    pub quantity: Option<String>,
+    pub asset: Option<String>,
+    pub price: Option<String>,
+    pub chain: Option<String>,
+    pub executed_at: Option<String>,
+    pub conflicts: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct InboundEmail {
+    pub from: String,
+    pub subject: String,
+    pub text: String,
+}
+
+// What became of an inbound email: ignored, booked as a trade or queued for review.
+enum Received {
+    Ignored,
+    Trade(TradeResponse),
+    Review(Option<EmailReview>),
+}
+
+const QUOTE_CURRENCIES: [&str; 4] = ["USDT", "USDC", "USD", "EUR"];
+const EMAIL_SOURCE: &str = "email";
+
+fn field_for(label: &str) -> Option<&'static str> {
+    let label: String = label.chars().filter(|c| c.is_ascii_alphanumeric()).collect::<String>().to_ascii_lowercase();
+    match label.as_str() {
+        "side" | "action" | "direction" => Some("side"),
+        "ordertype" | "type" => Some("order_type"),
+        "quantity" | "qty" | "filledquantity" | "size" => Some("quantity"),
+        "symbol" | "asset" | "instrument" | "coin" => Some("asset"),
+        "price" | "fillprice" | "executionprice" | "averageprice" | "avgprice" => Some("price"),
+        "chain" | "network" => Some("chain"),
+        "date" | "time" | "executedat" | "executiontime" => Some("executed_at"),
+        _ => None,
+    }
+}
+
+pub fn parse(text: &str) -> ParsedConfirmation {
+    let mut parsed = ParsedConfirmation::default();
+
+    for line in text.lines() {
+        let (label, value) = match line.split_once(':') {
+            Some((label, value)) => (label.trim(), value.trim()),
+            None => continue,
+        };
+        let field = match field_for(label) {
+            Some(field) if !value.is_empty() => field,
+            _ => continue,
+        };
+        let slot = match field {
+            "side" => &mut parsed.side,
+            "order_type" => &mut parsed.order_type,
+            "quantity" => &mut parsed.quantity,
+            "asset" => &mut parsed.asset,
+            "price" => &mut parsed.price,
+            "chain" => &mut parsed.chain,
+            _ => &mut parsed.executed_at,
+        };
+        match slot {
+            Some(existing) if existing != value => parsed.conflicts.push(field.to_string()),
+            Some(_) => (),
+            None => *slot = Some(value.to_string()),
+        }
+    }
+
+    parsed
+}
+
+fn parse_amount(value: &str, field: &str) -> Result<f32, String> {
+    let cleaned: String = value.chars().filter(|c| !matches!(c, '$' | ',' | ' ')).collect();
+    match cleaned.parse::<f32>() {
+        Ok(amount) if amount.is_finite() && amount > 0.0 => Ok(amount),
+        _ => Err(format!("Invalid {}: {}", field, value)),
+    }
+}
+
+fn base_asset(symbol: &str) -> Option<String> {
+    let symbol = symbol.to_ascii_uppercase();
+    let base = symbol.split(['/', '-']).next().unwrap_or_default().to_string();
+    if Asset::is_valid(&base) {
+        return Some(base);
+    }
+    QUOTE_CURRENCIES
+        .iter()
+        .filter_map(|quote| base.strip_suffix(quote))
+        .find(|base| Asset::is_valid(base))
+        .map(|base| base.to_string())
+}
+
+fn parse_executed_at(value: &str) -> Option<i64> {
+    if let Ok(datetime) = DateTime::parse_from_rfc3339(value) {
+        return Some(datetime.timestamp());
+    }
+    NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S")
+        .ok()
+        .map(|datetime| datetime.and_utc().timestamp())
+}
+
+impl ParsedConfirmation {
+    pub fn to_trade_form(&self, user_id: String, wallet_id: String) -> Result<TradeForm, String> {
+        if let Some(field) = self.conflicts.first() {
+            return Err(format!("Conflicting values for {}", field));
+        }
+
+        let side = match self.side.as_deref().map(|side| side.to_ascii_lowercase()) {
+            Some(side) if side == "buy" || side == "bought" => "Buy",
+            Some(side) if side == "sell" || side == "sold" => "Sell",
+            Some(side) => return Err(format!("Unknown side: {}", side)),
+            None => return Err("Missing side".to_string()),
+        };
+
+        let quantity = parse_amount(self.quantity.as_deref().ok_or("Missing quantity")?, "quantity")?;
+
+        let symbol = self.asset.as_deref().ok_or("Missing asset")?;
+        let asset = base_asset(symbol).ok_or_else(|| format!("Unknown asset: {}", symbol))?;
+
+        let price = match self.price.as_deref() {
+            Some(price) => Some(parse_amount(price, "price")?),
+            None => None,
+        };
+
+        let order = match (self.order_type.as_deref().map(|order| order.to_ascii_lowercase()), price) {
+            (Some(order), Some(_)) if order == "limit" => "Limit",
+            (Some(order), _) if order == "market" => "Market",
+            (Some(order), None) if order == "limit" => return Err("Limit order without price".to_string()),
+            (Some(order), _) => return Err(format!("Unknown order type: {}", order)),
+            (None, Some(_)) => "Limit",
+            (None, None) => "Market",
+        };
+
+        let chain = match self.chain.as_deref() {
+            Some(chain) => normalize_chain(chain)?,
+            None => "Ethereum".to_string(),
+        };
+
+        let timestamp = match self.executed_at.as_deref() {
+            Some(value) => Some(parse_executed_at(value).ok_or_else(|| format!("Invalid date: {}", value))?),
+            None => None,
+        };
+
+        Ok(TradeForm {
+            user_id,
+            wallet_id,
+            amount: quantity,
+            chain,
+            trade_type: format!("{}{}", order, side),
+            asset,
+            before_price: None,
+            execution_price: price,
+            final_price: None,
+            traded_amount: Some(quantity),
+            timestamp,
+            entered_by: None,
+            tx_hash: None,
+            source: Some(TradeSource::import(EMAIL_SOURCE)),
+            metadata: None,
+        })
+    }
+}
+
+pub fn sender_address(from: &str) -> String {
+    let address = match (from.rfind('<'), from.rfind('>')) {
+        (Some(start), Some(end)) if start < end => &from[start + 1..end],
+        _ => from,
+    };
+    address.trim().to_ascii_lowercase()
+}
+
+fn authorized(req: &HttpRequest) -> bool {
+    let secret = match config::secret("INBOUND_EMAIL_SECRET") {
+        Some(secret) => secret,
+        _ => return false,
+    };
+    match req.headers().get("X-Inbound-Secret").and_then(|value| value.to_str().ok()) {
+        Some(provided) => Sha256::digest(provided.as_bytes()) == Sha256::digest(secret.as_bytes()),
+        None => false,
+    }
+}
+
+pub async fn receive(req: HttpRequest, pool: web::Data<DbPool>, journal: web::Data<TradeJournal>, email: web::Json<InboundEmail>) -> HttpResponse {
+    if !authorized(&req) {
+        return HttpResponse::Unauthorized().json("Invalid inbound secret");
+    }
+
+    let (sender, email) = (sender_address(&email.from), email.into_inner());
+    let result = db::run(&pool, move |conn| {
+        let user = match User::find_by_email(conn, sender.clone())? {
+            Some(user) => user,
+            None => return Ok(Received::Ignored),
+        };
+
+        let parsed = parse(&email.text);
+        let result = parsed
+            .to_trade_form(user.id.clone(), user.wallet_id.clone())
+            .and_then(|form| form.validate().map(|_| form));
+
+        let reason = match result {
+            Ok(form) => match journal.record(conn, &form)? {
+                (Some(trade), None) => return Ok(Received::Trade(with_explorer_url(conn, trade)?)),
+                (_, errors) => errors.unwrap_or_else(|| "Trade was rejected by validation".to_string()),
+            },
+            Err(reason) => reason,
+        };
+
+        let parsed = serde_json::to_string(&parsed).expect("parsed confirmation serializes");
+        Ok(Received::Review(EmailReview::create(conn, user.id, sender, email.subject, email.text, parsed, reason)?))
+    });
+    match result.await {
+        Ok(Received::Ignored) => HttpResponse::Ok().json("Ignored: unknown sender"),
+        Ok(Received::Trade(trade)) => HttpResponse::Ok().json(trade),
+        Ok(Received::Review(Some(review))) => HttpResponse::Accepted().json(review),
+        Ok(Received::Review(None)) => HttpResponse::InternalServerError().into(),
+        Err(err) => err.error_response(),
+    }
+}
+
+// The pending review, once the caller is its user or an admin.
+fn pending_review(conn: &mut SqliteConnection, caller_id: &str, review_id: String) -> Result<EmailReview, AppError> {
+    let review = match EmailReview::find_by_id(conn, review_id)? {
+        Some(review) => review,
+        None => return Err(AppError::NotFound("Review not found".to_string())),
+    };
+    if review.user_id != caller_id && !jwt::is_admin(caller_id) {
+        return Err(AppError::Forbidden("Review belongs to another user".to_string()));
+    }
+    if review.status != ReviewStatus::PENDING {
+        return Err(AppError::Conflict("Review already resolved".to_string()));
+    }
+    Ok(review)
+}
+
+pub async fn list_reviews(req: HttpRequest, pool: web::Data<DbPool>) -> HttpResponse {
+    let caller_id = match jwt::user_id(&req) {
+        Some(caller_id) => caller_id,
+        None => return AppError::Unauthorized("missing token".to_string()).error_response(),
+    };
+
+    match db::run(&pool, move |conn| Ok(EmailReview::list_pending(conn, caller_id)?)).await {
+        Ok(reviews) => HttpResponse::Ok().json(reviews),
+        Err(err) => err.error_response(),
+    }
+}
+
+pub async fn accept_review(req: HttpRequest, pool: web::Data<DbPool>, journal: web::Data<TradeJournal>, review_id: web::Path<String>, trade: web::Json<TradeForm>) -> HttpResponse {
+    let caller_id = match jwt::user_id(&req) {
+        Some(caller_id) => caller_id,
+        None => return AppError::Unauthorized("missing token".to_string()).error_response(),
+    };
+    if let Err(err) = trade.validate() {
+        return AppError::Validation(err).error_response();
+    }
+
+    let (review_id, mut trade) = (review_id.into_inner(), trade.into_inner());
+    let result = db::run(&pool, move |conn| {
+        let review = pending_review(conn, &caller_id, review_id)?;
+        if trade.user_id != review.user_id {
+            return Err(AppError::Validation("Trade must belong to the review's user".to_string()));
+        }
+        ensure_user_wallet(conn, &trade.user_id, &trade.wallet_id)?;
+
+        trade.source = Some(TradeSource::import(EMAIL_SOURCE));
+        trade.metadata.get_or_insert_with(Default::default).insert("email_review_id".to_string(), review.id.clone());
+        let trade = match journal.record(conn, &trade)? {
+            (Some(trade), None) => trade,
+            (_, errors) => return Err(AppError::Validation(errors.unwrap_or_default())),
+        };
+        EmailReview::resolve(conn, review.id, ReviewStatus::ACCEPTED, Some(trade.id.clone()))?;
+        with_explorer_url(conn, trade)
+    });
+    match result.await {
+        Ok(trade) => HttpResponse::Ok().json(trade),
+        Err(err) => err.error_response(),
+    }
+}
+
+pub async fn reject_review(req: HttpRequest, pool: web::Data<DbPool>, review_id: web::Path<String>) -> HttpResponse {
+    let caller_id = match jwt::user_id(&req) {
+        Some(caller_id) => caller_id,
+        None => return AppError::Unauthorized("missing token".to_string()).error_response(),
+    };
+
+    let result = db::run(&pool, move |conn| {
+        let review = pending_review(conn, &caller_id, review_id.into_inner())?;
+        match EmailReview::resolve(conn, review.id, ReviewStatus::REJECTED, None)? {
+            Some(review) => Ok(review),
+            None => Err(AppError::Conflict("Review already resolved".to_string())),
+        }
+    });
+    match result.await {
+        Ok(review) => HttpResponse::Ok().json(review),
+        Err(err) => err.error_response(),
+    }
+}
+
+pub fn init_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(web::resource("/inbound/email").route(web::post().to(receive)))
+        .service(web::resource("/email-reviews").route(web::get().to(list_reviews).wrap(JwtGuard)))
+        .service(web::resource("/email-reviews/{review_id}/accept").route(web::post().to(accept_review).wrap(JwtGuard)))
+        .service(web::resource("/email-reviews/{review_id}/reject").route(web::post().to(reject_review).wrap(JwtGuard)));
+}
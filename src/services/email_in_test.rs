@@ -0,0 +1,97 @@
+use actix_web::http::header::AUTHORIZATION;
+use actix_web::http::StatusCode;
+use actix_web::test::{call_service, init_service, read_body_json, TestRequest};
+use actix_web::{web, App};
+use diesel::SqliteConnection;
+
+use crate::db::fixtures::{test_pool, trade, user};
+use crate::db::models::email_review::EmailReview;
+use super::email_in::{init_routes, parse, sender_address};
+use super::journal::TradeJournal;
+use super::jwt::create_jwt;
+
+#[test]
+fn parses_broker_confirmation() {
+    let parsed = parse(
+        "Hello,\n\nYour order was filled.\n\nAction: BOUGHT\nQty: 1.5\nInstrument: ETHUSDT\nAverage Price: $1,850.25\nNetwork: arbitrum\nExecuted At: 2023-08-01T12:00:00Z\n",
+    );
+
+    let form = parsed.to_trade_form("user".to_string(), "wallet".to_string()).unwrap();
+
+    assert_eq!(form.trade_type, "LimitBuy");
+    assert_eq!(form.asset, "ETH");
+    assert_eq!(form.amount, 1.5);
+    assert_eq!(form.execution_price, Some(1850.25));
+    assert_eq!(form.chain, "Arbitrum");
+    assert_eq!(form.timestamp, Some(1690891200));
+}
+
+#[test]
+fn market_order_without_price() {
+    let parsed = parse("Side: Sell\nQuantity: 2\nSymbol: BTC/USD\nOrder Type: Market");
+
+    let form = parsed.to_trade_form("user".to_string(), "wallet".to_string()).unwrap();
+
+    assert_eq!(form.trade_type, "MarketSell");
+    assert_eq!(form.execution_price, None);
+    assert_eq!(form.chain, "Ethereum");
+}
+
+#[test]
+fn ambiguous_confirmations_need_review() {
+    let missing = parse("Side: Buy\nSymbol: ETH");
+    assert_eq!(missing.to_trade_form("u".to_string(), "w".to_string()).err(), Some("Missing quantity".to_string()));
+
+    let conflicting = parse("Side: Buy\nQuantity: 1\nQuantity: 2\nSymbol: ETH");
+    assert!(conflicting.to_trade_form("u".to_string(), "w".to_string()).is_err());
+
+    let unknown = parse("Side: Buy\nQuantity: 1\nSymbol: SHIB/USD");
+    assert!(unknown.to_trade_form("u".to_string(), "w".to_string()).is_err());
+}
+
+#[test]
+fn extracts_sender_address() {
+    assert_eq!(sender_address("Jane Doe <Jane@Example.com>"), "jane@example.com");
+    assert_eq!(sender_address(" jane@example.com "), "jane@example.com");
+}
+
+fn review(conn: &mut SqliteConnection, user_id: &str) -> EmailReview {
+    EmailReview::create(conn, user_id.to_string(), "broker@example.com".to_string(), "Fill".to_string(), "Side: Buy".to_string(), "{}".to_string(), "Missing quantity".to_string())
+        .unwrap()
+        .unwrap()
+}
+
+#[actix_web::test]
+async fn reviews_are_only_read_and_resolved_by_their_user() {
+    std::env::set_var("JWT_SECRET", "test_secret");
+    let pool = test_pool();
+    let conn = &mut pool.get().unwrap();
+    let (owner, stranger) = (user(conn, "owner"), user(conn, "stranger"));
+    let (accepted, rejected) = (review(conn, &owner.id), review(conn, &owner.id));
+
+    let journal = TradeJournal::new(std::env::temp_dir().join(format!("trade_journal-{}.log", uuid::Uuid::new_v4())));
+    let app = init_service(App::new().app_data(web::Data::new(pool.clone())).app_data(web::Data::new(journal)).configure(init_routes)).await;
+    let (owner_token, stranger_token) = (create_jwt(owner.id.clone()).unwrap(), create_jwt(stranger.id.clone()).unwrap());
+    let post = |uri: String, token: &String, wallet_id: &str| {
+        TestRequest::post().uri(&uri).insert_header((AUTHORIZATION, token.clone())).set_json(trade(&owner.id, wallet_id)).to_request()
+    };
+
+    let req = TestRequest::get().uri("/email-reviews").insert_header((AUTHORIZATION, stranger_token.clone())).to_request();
+    let body: serde_json::Value = read_body_json(call_service(&app, req).await).await;
+    assert!(body.as_array().unwrap().is_empty());
+    let req = TestRequest::get().uri("/email-reviews").insert_header((AUTHORIZATION, owner_token.clone())).to_request();
+    let body: serde_json::Value = read_body_json(call_service(&app, req).await).await;
+    assert_eq!(body.as_array().unwrap().len(), 2);
+
+    let accept = format!("/email-reviews/{}/accept", accepted.id);
+    assert_eq!(call_service(&app, post(accept.clone(), &stranger_token, &owner.wallet_id)).await.status(), StatusCode::FORBIDDEN);
+    assert_eq!(call_service(&app, post(accept.clone(), &owner_token, &stranger.wallet_id)).await.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(call_service(&app, post(accept.clone(), &owner_token, &owner.wallet_id)).await.status(), StatusCode::OK);
+    assert_eq!(call_service(&app, post(accept, &owner_token, &owner.wallet_id)).await.status(), StatusCode::CONFLICT);
+
+    let reject = format!("/email-reviews/{}/reject", rejected.id);
+    let req = TestRequest::post().uri(&reject).insert_header((AUTHORIZATION, stranger_token)).to_request();
+    assert_eq!(call_service(&app, req).await.status(), StatusCode::FORBIDDEN);
+    let req = TestRequest::post().uri(&reject).insert_header((AUTHORIZATION, owner_token)).to_request();
+    assert_eq!(call_service(&app, req).await.status(), StatusCode::OK);
+}
@@ -0,0 +1,79 @@
+//! This module defines the endpoint that turns export URLs into short-lived signed links.
+//!
+//! `POST /export/sign` takes the path and query of an export (one of the analytics endpoints, typically with
+//! `format=csv`) and returns a URL that can be opened without a JWT until it expires. The caller must be
+//! authenticated, and the link lifetime is capped at `MAX_TTL_SECONDS`.
+//!
+//! Because a signed link is opened without a JWT, the endpoint it points at cannot check who is reading. The
+//! check happens here instead: the URL must name a `trader_id`, and it must be the caller's own unless the caller
+//! is an admin. Otherwise the request is rejected with `403 Forbidden`.
+//!
+//! # Examples
+//!
+//! ```text
+//! POST /export/sign
+//! { "url": "/profit-loss?start_date=2023-01-01&end_date=2023-02-01&trader_id=abc&format=csv", "ttl_seconds": 600 }
+//!
+//! 200 OK
+//! { "url": "/profit-loss?start_date=...&format=csv&expires=1690000600&signature=5f2c...", "expires_in": 600 }
+//! ```
+
+use actix_web::{web, HttpRequest, HttpResponse, ResponseError};
+use serde::{Deserialize, Serialize};
+
+use crate::error::AppError;
+use crate::middleware::jwt_guard::JwtGuard;
+use crate::services::jwt;
+use crate::utils::signed_url::create_signed_url;
+
+pub const DEFAULT_TTL_SECONDS: i64 = 900;
+pub const MAX_TTL_SECONDS: i64 = 86400;
+
+#[derive(Serialize, Deserialize)]
+pub struct SignForm {
+    pub url: String,
+    pub ttl_seconds: Option<i64>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct SignedUrlResponse {
+    pub url: String,
+    pub expires_in: i64,
+}
+
+/// Checks that every `trader_id` in the URL's query belongs to the caller, unless the caller is an admin.
+fn ensure_own_trader(req: &HttpRequest, url: &str) -> Result<(), AppError> {
+    let caller_id = jwt::user_id(req).ok_or_else(|| AppError::Unauthorized("missing token".to_string()))?;
+    let query = url.split_once('?').map(|(_, query)| query).unwrap_or("");
+    let params = web::Query::<Vec<(String, String)>>::from_query(query)
+        .map_err(|_| AppError::Validation("URL query cannot be parsed".to_string()))?;
+    let trader_ids: Vec<&String> = params.iter().filter(|(key, _)| key == "trader_id").map(|(_, value)| value).collect();
+
+    if trader_ids.is_empty() {
+        return Err(AppError::Validation("URL must include a trader_id".to_string()));
+    }
+    if trader_ids.iter().any(|trader_id| **trader_id != caller_id) && !jwt::is_admin(&caller_id) {
+        return Err(AppError::Forbidden("Cannot share another user's export".to_string()));
+    }
+    Ok(())
+}
+
+pub async fn sign(req: HttpRequest, form: web::Json<SignForm>) -> HttpResponse {
+    if let Err(error) = ensure_own_trader(&req, &form.url) {
+        return error.error_response();
+    }
+
+    let ttl_seconds = form.ttl_seconds.unwrap_or(DEFAULT_TTL_SECONDS);
+    if ttl_seconds <= 0 || ttl_seconds > MAX_TTL_SECONDS {
+        return AppError::Validation(format!("Error: ttl_seconds must be between 1 and {}", MAX_TTL_SECONDS)).error_response();
+    }
+
+    match create_signed_url(&form.url, ttl_seconds) {
+        Some(url) => HttpResponse::Ok().json(SignedUrlResponse { url, expires_in: ttl_seconds }),
+        None => AppError::Validation("Error: URL cannot be shared".to_string()).error_response(),
+    }
+}
+
+pub fn init_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(web::resource("/export/sign").route(web::post().to(sign).wrap(JwtGuard)));
+}
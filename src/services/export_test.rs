@@ -0,0 +1,54 @@
+use actix_web::http::header::AUTHORIZATION;
+use actix_web::http::StatusCode;
+use actix_web::test::{call_service, init_service, read_body_json, TestRequest};
+use actix_web::App;
+use serde_json::json;
+
+use super::export::init_routes;
+use super::jwt::create_jwt;
+
+#[actix_web::test]
+async fn only_the_callers_own_exports_are_signed() {
+    std::env::set_var("JWT_SECRET", "test_secret");
+    let app = init_service(App::new().configure(init_routes)).await;
+    let token = create_jwt("trader".to_string()).unwrap();
+    let sign = |url: &str| {
+        TestRequest::post()
+            .uri("/export/sign")
+            .insert_header((AUTHORIZATION, token.clone()))
+            .set_json(json!({ "url": url }))
+            .to_request()
+    };
+
+    let response = call_service(&app, sign("/profit-loss?start_date=2023-01-01&trader_id=trader&format=csv")).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let body: serde_json::Value = read_body_json(response).await;
+    assert!(body["url"].as_str().unwrap().contains("signature="));
+
+    let response = call_service(&app, sign("/profit-loss?start_date=2023-01-01&trader_id=someone-else&format=csv")).await;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    let response = call_service(&app, sign("/profit-loss?trader_id=trader&trader_id=someone-else")).await;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    let response = call_service(&app, sign("/profit-loss?start_date=2023-01-01&format=csv")).await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
+
+#[actix_web::test]
+async fn invalid_signing_requests_are_validation_errors() {
+    std::env::set_var("JWT_SECRET", "test_secret");
+    let app = init_service(App::new().configure(init_routes)).await;
+    let token = create_jwt("trader".to_string()).unwrap();
+
+    for form in [
+        json!({ "url": "/profit-loss?trader_id=trader", "ttl_seconds": 0 }),
+        json!({ "url": "/user/trader?trader_id=trader" }),
+    ] {
+        let req = TestRequest::post().uri("/export/sign").insert_header((AUTHORIZATION, token.clone())).set_json(form).to_request();
+        let response = call_service(&app, req).await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let body: serde_json::Value = read_body_json(response).await;
+        assert_eq!(body["code"], "validation_error");
+    }
+}
@@ -0,0 +1,143 @@
+//! This module defines the admin API of the versioned fee schedule.
+//!
+//! The provided functions include:
+//!
+//! - `list_schedules`: Lists every fee schedule version, newest first (`GET /admin/fee-schedules`).
+//! - `create_schedule`: Adds a version taking effect at a Unix timestamp (`POST /admin/fee-schedules` with
+//!   `{"execution_rate": 0.002, "transaction_rate": 0.004, "effective_from": 1693526400}`). It closes the current
+//!   version; trades executed before `effective_from` keep their fees.
+//!
+//! - `list_overrides`: Lists every fee override version of a user, newest first
+//!   (`GET /admin/users/{user_id}/fee-overrides`).
+//! - `set_override`: Gives a user negotiated rates on one venue, or on every venue when `venue` is omitted
+//!   (`POST /admin/users/{user_id}/fee-overrides` with `{"venue": "binance", "execution_rate": 0.001,
+//!   "transaction_rate": 0.002}`). They take effect at `effective_from` (a Unix timestamp), or now when it is omitted,
+//!   and replace the user's current override for the same venue.
+//! - `remove_override`: Ends the user's override for `?venue=` (or the one for every venue) now, so their later trades
+//!   pay the schedule again (`DELETE /admin/users/{user_id}/fee-overrides`).
+//!
+//! A version may take effect in the past, as long as it starts after the latest one; trades already stored in that
+//! period are only re-charged by a `fees` recompute job (`POST /admin/recompute`). The same goes for overrides.
+//!
+//! # Note
+//! All routes are wrapped with the `JwtGuard` middleware and restricted to admins (`ADMIN_USER_IDS`).
+
+use actix_web::{web, HttpRequest, HttpResponse, ResponseError};
+use serde::{Deserialize, Serialize};
+
+use crate::db::models::fee_override::FeeOverride;
+use crate::db::models::fee_schedule::FeeSchedule;
+use crate::db::{self, DbPool};
+use crate::error::AppError;
+use crate::middleware::jwt_guard::JwtGuard;
+use crate::services::jwt;
+use crate::utils;
+
+#[derive(Serialize, Deserialize)]
+pub struct FeeScheduleForm {
+    pub execution_rate: f32,
+    pub transaction_rate: f32,
+    pub effective_from: i64,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct FeeOverrideForm {
+    pub venue: Option<String>,
+    pub execution_rate: f32,
+    pub transaction_rate: f32,
+    pub effective_from: Option<i64>,
+}
+
+#[derive(Deserialize)]
+pub struct VenueQuery {
+    pub venue: Option<String>,
+}
+
+pub async fn list_schedules(req: HttpRequest, pool: web::Data<DbPool>) -> HttpResponse {
+    if let Err(err) = jwt::require_admin(&req) {
+        return err.error_response();
+    }
+
+    match db::run(&pool, move |conn| Ok(FeeSchedule::list(conn)?)).await {
+        Ok(schedules) => HttpResponse::Ok().json(schedules),
+        Err(err) => err.error_response(),
+    }
+}
+
+pub async fn create_schedule(req: HttpRequest, pool: web::Data<DbPool>, form: web::Json<FeeScheduleForm>) -> HttpResponse {
+    let admin_id = match jwt::require_admin(&req) {
+        Ok(admin_id) => admin_id,
+        Err(err) => return err.error_response(),
+    };
+
+    let effective_from = match utils::date::timestamp_to_naive_date_time(form.effective_from) {
+        Some(effective_from) => effective_from,
+        None => return AppError::Validation("Invalid effective_from timestamp".to_string()).error_response(),
+    };
+
+    match db::run(&pool, move |conn| Ok(FeeSchedule::create(conn, form.execution_rate, form.transaction_rate, effective_from, admin_id)?)).await {
+        Ok((Some(schedule), None)) => HttpResponse::Ok().json(schedule),
+        Ok((_, errors)) => AppError::Validation(errors.unwrap_or_default()).error_response(),
+        Err(err) => err.error_response(),
+    }
+}
+
+pub async fn list_overrides(req: HttpRequest, pool: web::Data<DbPool>, user_id: web::Path<String>) -> HttpResponse {
+    if let Err(err) = jwt::require_admin(&req) {
+        return err.error_response();
+    }
+
+    match db::run(&pool, move |conn| Ok(FeeOverride::list_for_user(conn, &user_id)?)).await {
+        Ok(overrides) => HttpResponse::Ok().json(overrides),
+        Err(err) => err.error_response(),
+    }
+}
+
+pub async fn set_override(req: HttpRequest, pool: web::Data<DbPool>, user_id: web::Path<String>, form: web::Json<FeeOverrideForm>) -> HttpResponse {
+    let admin_id = match jwt::require_admin(&req) {
+        Ok(admin_id) => admin_id,
+        Err(err) => return err.error_response(),
+    };
+
+    let form = form.into_inner();
+    let effective_from = match form.effective_from {
+        Some(timestamp) => match utils::date::timestamp_to_naive_date_time(timestamp) {
+            Some(effective_from) => effective_from,
+            None => return AppError::Validation("Invalid effective_from timestamp".to_string()).error_response(),
+        },
+        None => chrono::Local::now().naive_local(),
+    };
+
+    match db::run(&pool, move |conn| Ok(FeeOverride::set(conn, &user_id, form.venue, form.execution_rate, form.transaction_rate, effective_from, admin_id)?)).await {
+        Ok((Some(fee_override), None)) => HttpResponse::Ok().json(fee_override),
+        Ok((_, errors)) => AppError::Validation(errors.unwrap_or_default()).error_response(),
+        Err(err) => err.error_response(),
+    }
+}
+
+pub async fn remove_override(req: HttpRequest, pool: web::Data<DbPool>, user_id: web::Path<String>, params: web::Query<VenueQuery>) -> HttpResponse {
+    let admin_id = match jwt::require_admin(&req) {
+        Ok(admin_id) => admin_id,
+        Err(err) => return err.error_response(),
+    };
+
+    match db::run(&pool, move |conn| Ok(FeeOverride::remove(conn, &user_id, params.into_inner().venue, chrono::Local::now().naive_local(), &admin_id)?)).await {
+        Ok(true) => HttpResponse::Ok().json("Fee override removed"),
+        Ok(false) => AppError::NotFound("User has no fee override for this venue".to_string()).error_response(),
+        Err(err) => err.error_response(),
+    }
+}
+
+pub fn init_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::resource("/admin/fee-schedules")
+            .route(web::get().to(list_schedules).wrap(JwtGuard))
+            .route(web::post().to(create_schedule).wrap(JwtGuard)),
+    )
+    .service(
+        web::resource("/admin/users/{user_id}/fee-overrides")
+            .route(web::get().to(list_overrides).wrap(JwtGuard))
+            .route(web::post().to(set_override).wrap(JwtGuard))
+            .route(web::delete().to(remove_override).wrap(JwtGuard)),
+    );
+}
@@ -0,0 +1,65 @@
+//! This module provides HTTP fixtures for tests of the services that call external APIs.
+//!
+//! Each fixture starts a local `wiremock` server that answers like the real service, with responses recorded from it
+//! and stored in `testdata/http/{service}/{name}.json`. Point the client under test at `MockServer::uri()` instead of
+//! the real base URL, so the tests run without network access, in CI and locally alike.
+//!
+//! - `price_feed`: the CoinGecko `/simple/price` endpoint (`services::prices`), answering `coingecko/simple_price`.
+//! - `email_provider`: the send endpoint of the transactional email service (`services::mailer`), answering
+//!   `email/accepted` to any `POST`.
+//! - `failing`: any request answered with a status and a recorded error body, e.g. `coingecko/rate_limited`.
+//!
+//! Fixtures only mount the happy path. Tests that check what was sent mount their own `Mock` with matchers and
+//! `expect`, or inspect `MockServer::received_requests`.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use crate::services::fixtures;
+//!
+//! let server = fixtures::price_feed().await;
+//! let prices = CoinGecko::new(&server.uri(), None).fetch(&["BTC"])?;
+//! ```
+
+use std::fs;
+use std::path::PathBuf;
+
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+pub const RECORDINGS_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/testdata/http");
+
+// The recorded JSON body `{service}/{name}`.
+pub fn recording(name: &str) -> serde_json::Value {
+    let file = PathBuf::from(RECORDINGS_DIR).join(format!("{}.json", name));
+    let contents = fs::read_to_string(&file).unwrap_or_else(|err| panic!("Error reading {}: {}", file.display(), err));
+    serde_json::from_str(&contents).unwrap_or_else(|err| panic!("Error parsing {}: {}", file.display(), err))
+}
+
+pub async fn price_feed() -> MockServer {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/simple/price"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(recording("coingecko/simple_price")))
+        .mount(&server)
+        .await;
+    server
+}
+
+pub async fn email_provider() -> MockServer {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(recording("email/accepted")))
+        .mount(&server)
+        .await;
+    server
+}
+
+pub async fn failing(status: u16, name: &str) -> MockServer {
+    let server = MockServer::start().await;
+    Mock::given(wiremock::matchers::any())
+        .respond_with(ResponseTemplate::new(status).set_body_json(recording(name)))
+        .mount(&server)
+        .await;
+    server
+}
@@ -0,0 +1,108 @@
+//! This module implements content negotiation between JSON and CSV responses.
+//!
+//! Handlers pass their rows to `respond` (or a single record to `respond_one`), which picks the response format from
+//! the `format` query parameter (`json` or `csv`) when present, and otherwise from the request's `Accept` header. JSON
+//! remains the default when neither asks for CSV. CSV bodies are produced by a single shared serializer, `to_csv`, so every endpoint emits the
+//! same header row and quoting rules. CSV responses are sent as an attachment named after the endpoint
+//! (`profit-loss.csv` for `/profit-loss`).
+//!
+//! Exports too large to build in memory are streamed with `csv_stream`, which sends each page of rows as it is
+//! serialized by `to_csv_chunk`; only the first page carries the header row.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use crate::services::format::respond;
+//!
+//! pub async fn profit_loss(req: HttpRequest, params: web::Query<TradeQuery>) -> HttpResponse {
+//!     let rows = Trade::profit_loss(/* ... */);
+//!     respond(&req, params.format.as_deref(), &rows)
+//! }
+//! ```
+
+use actix_web::http::header::{Accept, Header, CONTENT_DISPOSITION};
+use actix_web::web::Bytes;
+use actix_web::{HttpRequest, HttpResponse};
+use futures::Stream;
+use serde::Serialize;
+
+pub const CSV_CONTENT_TYPE: &str = "text/csv; charset=utf-8";
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ResponseFormat {
+    Json,
+    Csv,
+}
+
+pub fn negotiate(req: &HttpRequest, format: Option<&str>) -> ResponseFormat {
+    match format.map(|format| format.to_ascii_lowercase()) {
+        Some(format) if format == "csv" => return ResponseFormat::Csv,
+        Some(format) if format == "json" => return ResponseFormat::Json,
+        _ => (),
+    }
+
+    if let Ok(accept) = Accept::parse(req) {
+        for mime in accept.ranked() {
+            match (mime.type_().as_str(), mime.subtype().as_str()) {
+                ("text", "csv") => return ResponseFormat::Csv,
+                ("application", "json") | ("*", "*") => return ResponseFormat::Json,
+                _ => continue,
+            }
+        }
+    }
+
+    ResponseFormat::Json
+}
+
+pub fn to_csv_chunk<T: Serialize>(rows: &[T], with_header: bool) -> Result<Bytes, csv::Error> {
+    let mut writer = csv::WriterBuilder::new().has_headers(with_header).from_writer(Vec::new());
+    for row in rows {
+        writer.serialize(row)?;
+    }
+    Ok(Bytes::from(writer.into_inner().map_err(|err| err.into_error())?))
+}
+
+pub fn to_csv<T: Serialize>(rows: &[T]) -> Result<String, csv::Error> {
+    let bytes = to_csv_chunk(rows, true)?;
+    Ok(String::from_utf8(bytes.to_vec()).expect("CSV output is valid UTF-8"))
+}
+
+// `attachment; filename="profit-loss.csv"` for `/profit-loss`.
+pub fn csv_disposition(req: &HttpRequest) -> String {
+    let name = req.path().trim_matches('/').replace('/', "-");
+    format!("attachment; filename=\"{}.csv\"", if name.is_empty() { "export" } else { &name })
+}
+
+fn csv_response<T: Serialize>(req: &HttpRequest, rows: &[T]) -> HttpResponse {
+    match to_csv(rows) {
+        Ok(body) => HttpResponse::Ok()
+            .content_type(CSV_CONTENT_TYPE)
+            .insert_header((CONTENT_DISPOSITION, csv_disposition(req)))
+            .body(body),
+        Err(_) => HttpResponse::InternalServerError().json("Failed to serialize CSV"),
+    }
+}
+
+pub fn csv_stream<S>(req: &HttpRequest, chunks: S) -> HttpResponse
+where
+    S: Stream<Item = Result<Bytes, actix_web::Error>> + 'static,
+{
+    HttpResponse::Ok()
+        .content_type(CSV_CONTENT_TYPE)
+        .insert_header((CONTENT_DISPOSITION, csv_disposition(req)))
+        .streaming(chunks)
+}
+
+pub fn respond<T: Serialize>(req: &HttpRequest, format: Option<&str>, rows: &[T]) -> HttpResponse {
+    match negotiate(req, format) {
+        ResponseFormat::Json => HttpResponse::Ok().json(rows),
+        ResponseFormat::Csv => csv_response(req, rows),
+    }
+}
+
+pub fn respond_one<T: Serialize>(req: &HttpRequest, format: Option<&str>, row: &T) -> HttpResponse {
+    match negotiate(req, format) {
+        ResponseFormat::Json => HttpResponse::Ok().json(row),
+        ResponseFormat::Csv => csv_response(req, std::slice::from_ref(row)),
+    }
+}
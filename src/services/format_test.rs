@@ -0,0 +1,55 @@
+use actix_web::http::header::ACCEPT;
+use actix_web::test::TestRequest;
+
+use super::format::{csv_disposition, negotiate, to_csv, to_csv_chunk, ResponseFormat};
+use crate::db::models::trade::DailyProfitLoss;
+
+#[test]
+fn defaults_to_json() {
+    let req = TestRequest::default().to_http_request();
+    assert_eq!(negotiate(&req, None), ResponseFormat::Json);
+}
+
+#[test]
+fn accept_header_selects_csv() {
+    let req = TestRequest::default().insert_header((ACCEPT, "text/csv")).to_http_request();
+    assert_eq!(negotiate(&req, None), ResponseFormat::Csv);
+
+    let req = TestRequest::default()
+        .insert_header((ACCEPT, "application/json, text/csv;q=0.5"))
+        .to_http_request();
+    assert_eq!(negotiate(&req, None), ResponseFormat::Json);
+}
+
+#[test]
+fn format_parameter_overrides_accept_header() {
+    let req = TestRequest::default().insert_header((ACCEPT, "application/json")).to_http_request();
+    assert_eq!(negotiate(&req, Some("csv")), ResponseFormat::Csv);
+}
+
+#[test]
+fn serializes_rows_with_header() {
+    let rows = vec![
+        DailyProfitLoss { date: "2022-01-01".to_string(), profit: 10.0, loss: -2.0 },
+        DailyProfitLoss { date: "2022-01-02".to_string(), profit: 0.0, loss: -1.0 },
+    ];
+
+    assert_eq!(to_csv(&rows).unwrap(), "date,profit,loss\n2022-01-01,10.0,-2.0\n2022-01-02,0.0,-1.0\n");
+}
+
+#[test]
+fn only_first_chunk_has_header() {
+    let rows = vec![DailyProfitLoss { date: "2022-01-03".to_string(), profit: 1.0, loss: 0.0 }];
+
+    assert_eq!(to_csv_chunk(&rows, true).unwrap(), "date,profit,loss\n2022-01-03,1.0,0.0\n");
+    assert_eq!(to_csv_chunk(&rows, false).unwrap(), "2022-01-03,1.0,0.0\n");
+}
+
+#[test]
+fn attachment_is_named_after_path() {
+    let req = TestRequest::with_uri("/profit-loss?format=csv").to_http_request();
+    assert_eq!(csv_disposition(&req), "attachment; filename=\"profit-loss.csv\"");
+
+    let req = TestRequest::with_uri("/trade/export").to_http_request();
+    assert_eq!(csv_disposition(&req), "attachment; filename=\"trade-export.csv\"");
+}
@@ -0,0 +1,136 @@
+//! This module defines the trade request inbox, where assistants propose trades for the owner to accept.
+//!
+//! The provided functions include:
+//!
+//! - `propose`: Queues a trade form in the owner's inbox (`POST /inbox`). The caller must be the owner (`user_id` of
+//!   the form) or hold a `propose` delegation from them; the form is validated up front and its `wallet_id` must be
+//!   one of the owner's wallets.
+//! - `list_requests`: Lists the pending requests in the caller's own inbox (`GET /inbox`).
+//! - `accept_request`: Creates the proposed trade through the trade journal and marks the request as accepted
+//!   (`POST /inbox/{request_id}/accept`). The trade records the proposing assistant in `entered_by`. The wallet is
+//!   checked again, since it may have been unlinked while the request was pending.
+//! - `reject_request`: Marks the request as rejected without creating a trade (`POST /inbox/{request_id}/reject`).
+//!
+//! Only the owner may accept or reject a request, and a request can only be resolved once (`409` afterwards).
+//!
+//! # Note
+//! All routes are wrapped with the `JwtGuard` middleware for secure access.
+
+use actix_web::{web, HttpRequest, HttpResponse, ResponseError};
+use diesel::SqliteConnection;
+use crate::db::models::delegation::{DelegationScope, TradeDelegation};
+use crate::db::models::trade_request::{RequestStatus, TradeRequest};
+use crate::db::{self, DbPool};
+use crate::error::AppError;
+use crate::middleware::jwt_guard::JwtGuard;
+use crate::services::journal::TradeJournal;
+use crate::services::jwt;
+use crate::services::metrics;
+use crate::services::trade::{ensure_user_wallet, with_explorer_url, TradeForm};
+
+fn ensure_owner(caller_id: Option<&str>, request: &TradeRequest) -> Result<(), AppError> {
+    match caller_id {
+        Some(actor_id) if actor_id != request.owner_id => Err(AppError::Forbidden("Only the owner can resolve this request".to_string())),
+        _ => Ok(()),
+    }
+}
+
+fn find_pending(conn: &mut SqliteConnection, caller_id: Option<&str>, request_id: String) -> Result<TradeRequest, AppError> {
+    let request = match TradeRequest::find_by_id(conn, request_id)? {
+        Some(request) => request,
+        None => return Err(AppError::NotFound("Request not found".to_string())),
+    };
+    ensure_owner(caller_id, &request)?;
+    if request.status != RequestStatus::PENDING {
+        return Err(AppError::Conflict("Request already resolved".to_string()));
+    }
+    Ok(request)
+}
+
+pub async fn propose(req: HttpRequest, pool: web::Data<DbPool>, trade: web::Json<TradeForm>) -> HttpResponse {
+    let actor_id = match jwt::user_id(&req) {
+        Some(actor_id) => actor_id,
+        None => return AppError::Unauthorized("missing token".to_string()).error_response(),
+    };
+
+    let mut trade = trade.into_inner();
+    if let Err(err) = trade.validate() {
+        return AppError::Validation(err).error_response();
+    }
+
+    let result = db::run(&pool, move |conn| {
+        if !TradeDelegation::allows(conn, &trade.user_id, &actor_id, DelegationScope::PROPOSE)? {
+            return Err(AppError::Forbidden("Not allowed to propose trades for this user".to_string()));
+        }
+        ensure_user_wallet(conn, &trade.user_id, &trade.wallet_id)?;
+
+        trade.entered_by = Some(actor_id.clone());
+        let payload = serde_json::to_string(&trade).expect("trade form serializes");
+        Ok(TradeRequest::create(conn, trade.user_id, actor_id, payload)?)
+    });
+    match result.await {
+        Ok(Some(request)) => HttpResponse::Accepted().json(request),
+        Ok(None) => AppError::Internal("Failed to queue trade request".to_string()).error_response(),
+        Err(err) => err.error_response(),
+    }
+}
+
+pub async fn list_requests(req: HttpRequest, pool: web::Data<DbPool>) -> HttpResponse {
+    let caller_id = match jwt::user_id(&req) {
+        Some(caller_id) => caller_id,
+        None => return AppError::Unauthorized("missing token".to_string()).error_response(),
+    };
+    match db::run(&pool, move |conn| Ok(TradeRequest::list_pending(conn, caller_id)?)).await {
+        Ok(requests) => HttpResponse::Ok().json(requests),
+        Err(err) => err.error_response(),
+    }
+}
+
+pub async fn accept_request(req: HttpRequest, pool: web::Data<DbPool>, journal: web::Data<TradeJournal>, request_id: web::Path<String>) -> HttpResponse {
+    let (caller_id, request_id) = (jwt::user_id(&req), request_id.into_inner());
+    let result = db::run(&pool, move |conn| {
+        let request = find_pending(conn, caller_id.as_deref(), request_id)?;
+        let trade: TradeForm = serde_json::from_str(&request.payload)
+            .map_err(|err| AppError::Internal(format!("Invalid trade request payload: {}", err)))?;
+        trade.validate().map_err(AppError::Validation)?;
+        ensure_user_wallet(conn, &trade.user_id, &trade.wallet_id)?;
+
+        let trade = match journal.record(conn, &trade)? {
+            (Some(trade), None) => trade,
+            (_, errors) => return Err(AppError::Validation(errors.unwrap_or_default())),
+        };
+        TradeRequest::resolve(conn, request.id, RequestStatus::ACCEPTED, Some(trade.id.clone()))?;
+        metrics::trade_request_resolved(RequestStatus::ACCEPTED, request.created_at);
+        with_explorer_url(conn, trade)
+    });
+    match result.await {
+        Ok(trade) => HttpResponse::Ok().json(trade),
+        Err(err) => err.error_response(),
+    }
+}
+
+pub async fn reject_request(req: HttpRequest, pool: web::Data<DbPool>, request_id: web::Path<String>) -> HttpResponse {
+    let (caller_id, request_id) = (jwt::user_id(&req), request_id.into_inner());
+    let result = db::run(&pool, move |conn| {
+        let request = find_pending(conn, caller_id.as_deref(), request_id)?;
+        Ok(TradeRequest::resolve(conn, request.id, RequestStatus::REJECTED, None)?)
+    });
+    match result.await {
+        Ok(Some(request)) => {
+            metrics::trade_request_resolved(RequestStatus::REJECTED, request.created_at);
+            HttpResponse::Ok().json(request)
+        }
+        Ok(None) => AppError::Conflict("Request already resolved".to_string()).error_response(),
+        Err(err) => err.error_response(),
+    }
+}
+
+pub fn init_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::resource("/inbox")
+            .route(web::post().to(propose).wrap(JwtGuard))
+            .route(web::get().to(list_requests).wrap(JwtGuard)),
+    )
+    .service(web::resource("/inbox/{request_id}/accept").route(web::post().to(accept_request).wrap(JwtGuard)))
+    .service(web::resource("/inbox/{request_id}/reject").route(web::post().to(reject_request).wrap(JwtGuard)));
+}
@@ -0,0 +1,52 @@
+use actix_web::http::header::AUTHORIZATION;
+use actix_web::http::StatusCode;
+use actix_web::test::{call_service, init_service, read_body_json, TestRequest};
+use actix_web::{web, App};
+
+use crate::db::fixtures::{test_pool, trade, user};
+use crate::db::models::delegation::{DelegationScope, TradeDelegation};
+use crate::db::models::trade_request::TradeRequest;
+use super::inbox::init_routes;
+use super::journal::TradeJournal;
+use super::jwt::create_jwt;
+
+#[actix_web::test]
+async fn requests_stay_in_their_owners_inbox_and_wallets() {
+    std::env::set_var("JWT_SECRET", "test_secret");
+    let pool = test_pool();
+    let conn = &mut pool.get().unwrap();
+    let (owner, assistant, stranger) = (user(conn, "owner"), user(conn, "assistant"), user(conn, "stranger"));
+    TradeDelegation::grant(conn, owner.id.clone(), assistant.id.clone(), DelegationScope::PROPOSE.to_string()).unwrap();
+
+    let journal = TradeJournal::new(std::env::temp_dir().join(format!("trade_journal-{}.log", uuid::Uuid::new_v4())));
+    let app = init_service(App::new().app_data(web::Data::new(pool.clone())).app_data(web::Data::new(journal)).configure(init_routes)).await;
+    let (owner_token, assistant_token, stranger_token) =
+        (create_jwt(owner.id.clone()).unwrap(), create_jwt(assistant.id.clone()).unwrap(), create_jwt(stranger.id.clone()).unwrap());
+    let propose = |wallet_id: &str| {
+        TestRequest::post().uri("/inbox").insert_header((AUTHORIZATION, assistant_token.clone())).set_json(trade(&owner.id, wallet_id)).to_request()
+    };
+
+    // Proposals must use one of the owner's wallets.
+    assert_eq!(call_service(&app, propose(&stranger.wallet_id)).await.status(), StatusCode::BAD_REQUEST);
+    let resp = call_service(&app, propose(&owner.wallet_id)).await;
+    assert_eq!(resp.status(), StatusCode::ACCEPTED);
+    let request: serde_json::Value = read_body_json(resp).await;
+
+    for (token, pending) in [(&stranger_token, 0), (&assistant_token, 0), (&owner_token, 1)] {
+        let req = TestRequest::get().uri("/inbox").insert_header((AUTHORIZATION, token.clone())).to_request();
+        let body: serde_json::Value = read_body_json(call_service(&app, req).await).await;
+        assert_eq!(body.as_array().unwrap().len(), pending);
+    }
+
+    let accept = |request_id: &str, token: &String| {
+        TestRequest::post().uri(&format!("/inbox/{}/accept", request_id)).insert_header((AUTHORIZATION, token.clone())).to_request()
+    };
+    let request_id = request["id"].as_str().unwrap();
+    assert_eq!(call_service(&app, accept(request_id, &stranger_token)).await.status(), StatusCode::FORBIDDEN);
+    assert_eq!(call_service(&app, accept(request_id, &owner_token)).await.status(), StatusCode::OK);
+
+    // A request queued with a foreign wallet is refused again when accepted.
+    let payload = serde_json::to_string(&trade(&owner.id, &stranger.wallet_id)).unwrap();
+    let foreign = TradeRequest::create(conn, owner.id.clone(), assistant.id.clone(), payload).unwrap().unwrap();
+    assert_eq!(call_service(&app, accept(&foreign.id, &owner_token)).await.status(), StatusCode::BAD_REQUEST);
+}
@@ -0,0 +1,216 @@
+//! This module implements a write-ahead journal for incoming trade requests.
+//!
+//! Every accepted trade payload is appended to an append-only, newline-delimited JSON file (and flushed to disk)
+//! before it is written to the database. Once the request has been handled a completion record is appended for the
+//! same entry. If the process crashes in between, `TradeJournal::recover` replays every entry that has no completion
+//! record when the server starts again. A replayed trade the wallet can no longer pay for is rejected like a new one
+//! and not retried.
+//!
+//! The journal entry id doubles as the trade id, so replaying an entry whose trade already reached the database does
+//! not create a duplicate.
+//!
+//! The journal location is read from the `TRADE_JOURNAL_PATH` environment variable (default `trade_journal.log`).
+//!
+//! # Examples
+//!
+//! ```rust
+//! use crate::services::journal::TradeJournal;
+//!
+//! let journal = TradeJournal::from_env();
+//!
+//! // On startup, replay requests that were accepted but never completed.
+//! let replayed = journal.recover(&mut conn)?;
+//!
+//! // While handling a request.
+//! let id = journal.append(&trade_form)?;
+//! // ... write the trade with `id` ...
+//! journal.complete(&id)?;
+//!
+//! // Or let the journal wrap the whole write.
+//! let (trade, errors) = journal.record(&mut conn, &trade_form)?;
+//! ```
+
+use std::collections::HashSet;
+use std::fmt;
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use actix_web::{HttpResponse, ResponseError};
+use diesel::SqliteConnection;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::db::error::DbError;
+use crate::db::models::fee_schedule::FeeSchedule;
+use crate::db::models::trade::Trade;
+use crate::error::AppError;
+use crate::services::metrics;
+use crate::services::trade::{fill_optional_fields, TradeForm};
+
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum JournalRecord {
+    Accepted { id: String, payload: Box<TradeForm> },
+    Completed { id: String },
+}
+
+pub struct JournalEntry {
+    pub id: String,
+    pub payload: TradeForm,
+}
+
+#[derive(Debug)]
+pub enum JournalError {
+    Io(io::Error),
+    Db(DbError),
+}
+
+impl fmt::Display for JournalError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            JournalError::Io(err) => write!(f, "trade journal error: {}", err),
+            JournalError::Db(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl std::error::Error for JournalError {}
+
+impl From<io::Error> for JournalError {
+    fn from(err: io::Error) -> Self {
+        JournalError::Io(err)
+    }
+}
+
+impl From<DbError> for JournalError {
+    fn from(err: DbError) -> Self {
+        JournalError::Db(err)
+    }
+}
+
+impl ResponseError for JournalError {
+    fn error_response(&self) -> HttpResponse {
+        match self {
+            JournalError::Io(_) => HttpResponse::InternalServerError().json("Failed to journal trade"),
+            JournalError::Db(err) => err.error_response(),
+        }
+    }
+}
+
+impl From<JournalError> for AppError {
+    fn from(err: JournalError) -> Self {
+        match err {
+            JournalError::Io(_) => AppError::Internal("Failed to journal trade".to_string()),
+            JournalError::Db(err) => AppError::from(err),
+        }
+    }
+}
+
+pub struct TradeJournal {
+    path: PathBuf,
+    lock: Mutex<()>,
+}
+
+impl TradeJournal {
+    pub fn new(path: PathBuf) -> Self {
+        TradeJournal { path, lock: Mutex::new(()) }
+    }
+
+    pub fn from_env() -> Self {
+        let path = std::env::var("TRADE_JOURNAL_PATH").unwrap_or_else(|_| "trade_journal.log".to_string());
+        Self::new(PathBuf::from(path))
+    }
+
+    pub fn append(&self, payload: &TradeForm) -> io::Result<String> {
+        let id = Uuid::new_v4().as_hyphenated().to_string();
+        self.write_record(&JournalRecord::Accepted { id: id.clone(), payload: Box::new(payload.clone()) })?;
+        Ok(id)
+    }
+
+    pub fn complete(&self, id: &str) -> io::Result<()> {
+        self.write_record(&JournalRecord::Completed { id: id.to_string() })
+    }
+
+    pub fn record(&self, conn: &mut SqliteConnection, payload: &TradeForm) -> Result<(Option<Trade>, Option<String>), JournalError> {
+        let id = self.append(payload)?;
+
+        let mut trade = fill_optional_fields(payload);
+        trade.id = id.clone();
+        let created = FeeSchedule::apply(conn, &mut trade)
+            .map_err(DbError::from)
+            .and_then(|_| Trade::create(conn, &mut trade));
+
+        self.complete(&id)?;
+        let created = created?;
+        if let (Some(trade), _) = &created {
+            metrics::trade_recorded(&trade.source, payload.timestamp);
+        }
+        Ok(created)
+    }
+
+    pub fn pending(&self) -> io::Result<Vec<JournalEntry>> {
+        let _guard = self.lock.lock().unwrap();
+        self.read_pending()
+    }
+
+    pub fn recover(&self, conn: &mut SqliteConnection) -> Result<usize, DbError> {
+        let entries = self.pending().expect("Error reading trade journal");
+        let mut replayed = 0;
+
+        for entry in entries {
+            if Trade::find_including_deleted(conn, entry.id.clone())?.is_none() && entry.payload.validate().is_ok() {
+                let mut trade = fill_optional_fields(&entry.payload);
+                trade.id = entry.id.clone();
+                FeeSchedule::apply(conn, &mut trade)?;
+                if let (Some(_), _) = Trade::create(conn, &mut trade)? {
+                    replayed += 1;
+                }
+            }
+            self.complete(&entry.id).expect("Error writing trade journal");
+        }
+
+        self.compact().expect("Error compacting trade journal");
+        Ok(replayed)
+    }
+
+    fn compact(&self) -> io::Result<()> {
+        let _guard = self.lock.lock().unwrap();
+        if self.read_pending()?.is_empty() && self.path.exists() {
+            File::create(&self.path)?.sync_all()?;
+        }
+        Ok(())
+    }
+
+    fn write_record(&self, record: &JournalRecord) -> io::Result<()> {
+        let mut line = serde_json::to_string(record)?;
+        line.push('\n');
+
+        let _guard = self.lock.lock().unwrap();
+        let mut file = OpenOptions::new().create(true).append(true).open(&self.path)?;
+        file.write_all(line.as_bytes())?;
+        file.sync_data()
+    }
+
+    fn read_pending(&self) -> io::Result<Vec<JournalEntry>> {
+        if !self.path.exists() {
+            return Ok(Vec::new());
+        }
+
+        let mut accepted = Vec::new();
+        let mut completed = HashSet::new();
+        for line in BufReader::new(File::open(&self.path)?).lines() {
+            // A torn final line from a crash mid-write is skipped rather than failing recovery.
+            match serde_json::from_str::<JournalRecord>(&line?) {
+                Ok(JournalRecord::Accepted { id, payload }) => accepted.push(JournalEntry { id, payload: *payload }),
+                Ok(JournalRecord::Completed { id }) => {
+                    completed.insert(id);
+                }
+                Err(_) => continue,
+            }
+        }
+
+        Ok(accepted.into_iter().filter(|entry| !completed.contains(&entry.id)).collect())
+    }
+}
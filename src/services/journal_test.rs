@@ -0,0 +1,62 @@
+use std::path::PathBuf;
+
+use uuid::Uuid;
+
+use super::journal::TradeJournal;
+use crate::db::fixtures::{test_connection, trade, user};
+use crate::db::models::trade::Trade;
+
+fn journal_path() -> PathBuf {
+    std::env::temp_dir().join(format!("trade_journal-{}.log", Uuid::new_v4()))
+}
+
+#[test]
+fn completed_entries_are_not_pending() {
+    let journal = TradeJournal::new(journal_path());
+
+    let first = journal.append(&trade("user", "wallet")).unwrap();
+    let second = journal.append(&trade("user", "wallet")).unwrap();
+    journal.complete(&first).unwrap();
+
+    let pending = journal.pending().unwrap();
+    assert_eq!(pending.len(), 1);
+    assert_eq!(pending[0].id, second);
+}
+
+#[test]
+fn recover_replays_pending_entries_once() {
+    let conn = &mut test_connection();
+    let user = user(conn, "journal");
+
+    let path = journal_path();
+    let journal = TradeJournal::new(path.clone());
+    let id = journal.append(&trade(&user.id, &user.wallet_id)).unwrap();
+
+    assert_eq!(journal.recover(conn).unwrap(), 1);
+    assert!(Trade::find_by_id(conn, id).unwrap().is_some());
+    assert!(journal.pending().unwrap().is_empty());
+
+    assert_eq!(journal.recover(conn).unwrap(), 0);
+    assert_eq!(Trade::list(conn).unwrap().len(), 1);
+
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn trades_on_another_users_wallet_are_rejected() {
+    let conn = &mut test_connection();
+    let (owner, stranger) = (user(conn, "owner"), user(conn, "stranger"));
+
+    let path = journal_path();
+    let journal = TradeJournal::new(path.clone());
+    let (created, errors) = journal.record(conn, &trade(&owner.id, &stranger.wallet_id)).unwrap();
+    assert!(created.is_none());
+    assert_eq!(errors.as_deref(), Some("Wallet does not belong to this user"));
+
+    // Replays are checked the same way.
+    journal.append(&trade(&owner.id, &stranger.wallet_id)).unwrap();
+    assert_eq!(journal.recover(conn).unwrap(), 0);
+    assert!(Trade::list(conn).unwrap().is_empty());
+
+    std::fs::remove_file(path).unwrap();
+}
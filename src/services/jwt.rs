@@ -1,6 +1,12 @@
 //! This module defines utility functions for JSON Web Token (JWT) creation and authentication in Actix Web applications.
 //!
 //! It includes functions to create JWT tokens with custom claims and to authenticate incoming requests based on JWT tokens.
+//! Tokens are short-lived access tokens (`jwt.access_token_minutes` in the settings, `ACCESS_TOKEN_MINUTES` by default); clients renew them with a refresh token through
+//! `services::auth`.
+//!
+//! Tokens carry `iat`, `nbf` and `exp` claims. `exp` and `nbf` are checked with a leeway of `jwt.leeway_seconds` (60 by
+//! default), so a client or server whose clock is slightly off does not see spurious "token expired" errors. `iss` and `aud`
+//! must match `jwt.issuer` and `jwt.audience`, so a token issued by one environment (staging) is refused by another.
 //!
 //! # Examples
 //!
@@ -21,7 +27,12 @@
 //! }
 //!
 //! // Authenticate a request using a JWT token.
-//! pub fn authenticate(req: HttpRequest) -> Result<(), Error> {
+//! pub fn authenticate(req: &HttpRequest) -> Result<Option<AuthenticatedUser>, Error> {
+//!     // ... implementation details ...
+//! }
+//!
+//! // Read the ID of the authenticated caller, as stored by the `JwtGuard` middleware.
+//! pub fn user_id(req: &HttpRequest) -> Option<String> {
 //!     // ... implementation details ...
 //! }
 //! ```
@@ -29,29 +40,62 @@
 //! # Note
 //! Ensure that you have the necessary JWT library (e.g., `jsonwebtoken`) and the required secret set in your environment
 //! variables (`JWT_SECRET`) for proper token creation and authentication. Additionally, use the `create_jwt` function to generate
-//! JWT tokens and the `authenticate` function to verify and authenticate incoming requests.
+//! JWT tokens and the `authenticate` function to verify and authenticate incoming requests. `authenticate` returns the user the
+//! token was issued to, which the `JwtGuard` middleware stores in the request extensions. It also accepts `GET` requests
+//! carrying a valid signature from `utils::signed_url` in place of a token; those have no authenticated user.
+//! `user_id` returns the ID of the authenticated caller, or `None` for signed links and unguarded routes. `is_admin`
+//! checks a user ID against the comma-separated `ADMIN_USER_IDS` environment variable.
 
 use actix_web::error::ErrorUnauthorized;
 use jsonwebtoken::errors::ErrorKind;
 use jsonwebtoken::{encode, Header, EncodingKey, Validation, Algorithm, decode, DecodingKey};
 use serde::{Deserialize, Serialize};
-use actix_web::{HttpRequest, Error};
+use actix_web::{HttpMessage, HttpRequest, Error};
 use actix_web::http::header::AUTHORIZATION;
+use actix_web::http::Method;
+
+use crate::config::{self, settings};
+use crate::error::AppError;
+use crate::utils::signed_url;
 
 #[derive(Debug, Serialize, Deserialize)]
 struct Claims {
     id: String,
+    // Tokens issued before `iat` and `nbf` were added carry neither.
+    #[serde(default)]
+    iat: i64,
+    #[serde(default)]
+    nbf: i64,
     exp: i64,
+    iss: String,
+    aud: String,
 }
 
+#[derive(Debug, Clone, PartialEq)]
+pub struct AuthenticatedUser {
+    pub id: String,
+}
+
+// Default access token lifetime; see `jwt.access_token_minutes` in the settings.
+pub const ACCESS_TOKEN_MINUTES: i64 = 15;
+
 pub fn create_jwt(id: String) -> Result<String, jsonwebtoken::errors::Error> {
-    let expiration = chrono::Utc::now()
-        .checked_add_signed(chrono::Duration::hours(3))
+    let now = chrono::Utc::now();
+    let expiration = now
+        .checked_add_signed(chrono::Duration::minutes(settings::get().jwt.access_token_minutes))
         .expect("valid timestamp")
         .timestamp();
-    let claims = Claims { id, exp: expiration.clone() };
+    let jwt = &settings::get().jwt;
+    let claims = Claims {
+        id,
+        iat: now.timestamp(),
+        nbf: now.timestamp(),
+        exp: expiration,
+        iss: jwt.issuer.clone(),
+        aud: jwt.audience.clone(),
+    };
 
-    let secret = std::env::var("JWT_SECRET").expect("JWT_SECRET must be set");
+    let secret = config::secret("JWT_SECRET").expect("JWT_SECRET must be set");
     let key = secret.as_bytes();
 
     let token = encode(
@@ -63,7 +107,15 @@ pub fn create_jwt(id: String) -> Result<String, jsonwebtoken::errors::Error> {
     Ok(token)
 }
 
-pub fn authenticate(req: HttpRequest) -> Result<(), Error> {
+pub fn authenticate(req: &HttpRequest) -> Result<Option<AuthenticatedUser>, Error> {
+    // Signed export links stand in for a token on read-only requests.
+    if req.headers().get(AUTHORIZATION).is_none()
+        && req.method() == Method::GET
+        && signed_url::verify(req.path(), req.query_string(), chrono::Utc::now().timestamp())
+    {
+        return Ok(None);
+    }
+
     let token = match req.headers().get(AUTHORIZATION) {
         Some(value) => match value.to_str() {
             Ok(value) => value,
@@ -72,19 +124,44 @@ pub fn authenticate(req: HttpRequest) -> Result<(), Error> {
         None => return Err(ErrorUnauthorized("missing token")),
     };
 
-    let validation = Validation::new(Algorithm::HS256);
+    // Tolerate small clock differences between the clients, the servers and the issuer.
+    let jwt = &settings::get().jwt;
+    let mut validation = Validation::new(Algorithm::HS256);
+    validation.leeway = jwt.leeway_seconds;
+    validation.validate_nbf = true;
+    // Only accept tokens issued by and for this deployment.
+    validation.set_issuer(&[&jwt.issuer]);
+    validation.set_audience(&[&jwt.audience]);
+    validation.set_required_spec_claims(&["exp", "iss", "aud"]);
 
-    let secret = std::env::var("JWT_SECRET").expect("JWT_SECRET must be set");
+    let secret = config::secret("JWT_SECRET").expect("JWT_SECRET must be set");
     let key = secret.as_bytes();
 
     match decode::<Claims>(token, &DecodingKey::from_secret(key), &validation) {
-        Ok(_token_data) => (),
+        Ok(token_data) => Ok(Some(AuthenticatedUser { id: token_data.claims.id })),
         Err(err) => match *err.kind() {
-            ErrorKind::ExpiredSignature => return Err(ErrorUnauthorized("token expired")),
-            ErrorKind::InvalidToken => return Err(ErrorUnauthorized("invalid token")),
-            _ => return Err(ErrorUnauthorized("invalid token")),
+            ErrorKind::ExpiredSignature => Err(ErrorUnauthorized("token expired")),
+            ErrorKind::ImmatureSignature => Err(ErrorUnauthorized("token not yet valid")),
+            ErrorKind::InvalidToken => Err(ErrorUnauthorized("invalid token")),
+            _ => Err(ErrorUnauthorized("invalid token")),
         },
-    };
+    }
+}
+
+pub fn user_id(req: &HttpRequest) -> Option<String> {
+    req.extensions().get::<AuthenticatedUser>().map(|user| user.id.clone())
+}
+
+pub fn is_admin(user_id: &str) -> bool {
+    std::env::var("ADMIN_USER_IDS")
+        .map(|ids| ids.split(',').any(|id| id.trim() == user_id))
+        .unwrap_or(false)
+}
 
-    Ok(())
-}
\ No newline at end of file
+// The ID of the authenticated caller, if they are an admin.
+pub fn require_admin(req: &HttpRequest) -> Result<String, AppError> {
+    match user_id(req) {
+        Some(user_id) if is_admin(&user_id) => Ok(user_id),
+        _ => Err(AppError::Forbidden("Admin access required".to_string())),
+    }
+}
@@ -0,0 +1,138 @@
+//! This module delivers the emails queued in the outbox (`outbound_email`) to users.
+//!
+//! An `EmailSender` hands one message to a mail provider. `HttpEmailSender` is the built-in sender: it posts each
+//! message as JSON (`{"from", "to", "subject", "text"}`) to `EMAIL_API_URL`, the send endpoint of a transactional
+//! email service, with `EMAIL_API_KEY` (see `config::secret`) as a bearer token and `EMAIL_FROM` as the sender
+//! address. Calls go through a circuit breaker named `email` (reported by `/metrics/business`): after 5 consecutive
+//! failures the provider is left alone for a minute and sends fail immediately. Other providers can be plugged in by
+//! implementing the trait.
+//!
+//! `Mailer::deliver_pending` sends the unsent messages oldest first and marks each one sent once the provider accepted
+//! it; a failure stops the round so the remaining messages keep their order and are retried. `spawn_delivery`, started
+//! from `main` when `EMAIL_API_URL` is set, runs a round every `EMAIL_DELIVERY_SECS` seconds (default 10) on a
+//! background thread. Without it, messages stay queued.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use crate::services::mailer::{self, Mailer};
+//!
+//! if let Some(mailer) = Mailer::from_env() {
+//!     mailer::spawn_delivery(mailer, pool.clone());
+//! }
+//! ```
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use diesel::SqliteConnection;
+
+use crate::config;
+use crate::db::models::outbound_email::OutboundEmail;
+use crate::db::DbPool;
+use crate::utils::circuit_breaker::{self, CircuitBreaker, CircuitError};
+
+// Messages sent per delivery round.
+pub const BATCH_SIZE: i64 = 50;
+
+pub trait EmailSender: Send + Sync {
+    fn name(&self) -> &str;
+
+    fn send(&self, email: &OutboundEmail) -> Result<(), String>;
+}
+
+pub struct HttpEmailSender {
+    url: String,
+    api_key: Option<String>,
+    from: String,
+    agent: ureq::Agent,
+    breaker: Arc<CircuitBreaker>,
+}
+
+impl HttpEmailSender {
+    pub fn new(url: &str, api_key: Option<String>, from: &str) -> Self {
+        HttpEmailSender {
+            url: url.to_string(),
+            api_key,
+            from: from.to_string(),
+            agent: ureq::AgentBuilder::new().timeout(Duration::from_secs(10)).build(),
+            breaker: circuit_breaker::register(CircuitBreaker::new("email", 5, Duration::from_secs(60))),
+        }
+    }
+}
+
+impl EmailSender for HttpEmailSender {
+    fn name(&self) -> &str {
+        "http"
+    }
+
+    fn send(&self, email: &OutboundEmail) -> Result<(), String> {
+        let mut request = self.agent.post(&self.url);
+        if let Some(api_key) = &self.api_key {
+            request = request.set("Authorization", &format!("Bearer {}", api_key));
+        }
+        let sent = self.breaker.call(|| {
+            request
+                .send_json(serde_json::json!({
+                    "from": self.from,
+                    "to": email.recipient,
+                    "subject": email.subject,
+                    "text": email.body,
+                }))
+                .map_err(|err| format!("Email request to {} failed: {}", self.url, err))
+        });
+        match sent {
+            Ok(_) => Ok(()),
+            Err(CircuitError::Open) => Err(format!("Email provider at {} is unavailable", self.url)),
+            Err(CircuitError::Inner(err)) => Err(err),
+        }
+    }
+}
+
+pub struct Mailer {
+    sender: Box<dyn EmailSender>,
+    interval: Duration,
+}
+
+impl Mailer {
+    pub fn new(sender: Box<dyn EmailSender>, interval: Duration) -> Self {
+        Mailer { sender, interval }
+    }
+
+    // `None` when no mail provider is configured.
+    pub fn from_env() -> Option<Self> {
+        let url = std::env::var("EMAIL_API_URL").ok()?;
+        let from = std::env::var("EMAIL_FROM").unwrap_or_else(|_| "no-reply@localhost".to_string());
+        let interval = std::env::var("EMAIL_DELIVERY_SECS").ok().and_then(|value| value.parse().ok()).unwrap_or(10);
+        Some(Self::new(
+            Box::new(HttpEmailSender::new(&url, config::secret("EMAIL_API_KEY"), &from)),
+            Duration::from_secs(interval.max(1)),
+        ))
+    }
+
+    // Sends up to `limit` queued messages and returns how many were sent.
+    pub fn deliver_pending(&self, conn: &mut SqliteConnection, limit: i64) -> Result<usize, String> {
+        let pending = OutboundEmail::pending(conn, limit).map_err(|err| format!("Error reading the outbox: {:?}", err))?;
+        let mut sent = 0;
+        for email in pending {
+            self.sender.send(&email).map_err(|err| format!("{} (after {} sent)", err, sent))?;
+            OutboundEmail::mark_sent(conn, email.id).map_err(|err| format!("Error marking an email sent: {:?}", err))?;
+            sent += 1;
+        }
+        Ok(sent)
+    }
+}
+
+pub fn spawn_delivery(mailer: Mailer, pool: DbPool) {
+    std::thread::spawn(move || loop {
+        match pool.get() {
+            Ok(mut conn) => match mailer.deliver_pending(&mut conn, BATCH_SIZE) {
+                Ok(0) => (),
+                Ok(count) => log::debug!("Delivered {} email(s) through {}", count, mailer.sender.name()),
+                Err(err) => log::warn!("Email delivery failed: {}", err),
+            },
+            Err(err) => log::warn!("Email delivery could not get a connection: {}", err),
+        }
+        std::thread::sleep(mailer.interval);
+    });
+}
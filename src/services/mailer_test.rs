@@ -0,0 +1,87 @@
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::db::fixtures::test_connection;
+use crate::db::models::outbound_email::OutboundEmail;
+use super::fixtures;
+use super::mailer::{EmailSender, HttpEmailSender, Mailer};
+
+// Records what it sends, and fails once `fail_after` messages were sent.
+struct Recording {
+    sent: Arc<Mutex<Vec<String>>>,
+    fail_after: usize,
+}
+
+impl EmailSender for Recording {
+    fn name(&self) -> &str {
+        "recording"
+    }
+
+    fn send(&self, email: &OutboundEmail) -> Result<(), String> {
+        let mut sent = self.sent.lock().unwrap();
+        if sent.len() >= self.fail_after {
+            return Err("provider unavailable".to_string());
+        }
+        sent.push(email.recipient.clone());
+        Ok(())
+    }
+}
+
+fn mailer(fail_after: usize) -> (Mailer, Arc<Mutex<Vec<String>>>) {
+    let sent = Arc::new(Mutex::new(Vec::new()));
+    (Mailer::new(Box::new(Recording { sent: sent.clone(), fail_after }), Duration::from_secs(1)), sent)
+}
+
+#[test]
+fn delivers_queued_emails_once() {
+    let conn = &mut test_connection();
+    OutboundEmail::queue(conn, "first@example.com", "Subject", "Body".to_string()).unwrap();
+    OutboundEmail::queue(conn, "second@example.com", "Subject", "Body".to_string()).unwrap();
+    let (mailer, sent) = mailer(usize::MAX);
+
+    assert_eq!(mailer.deliver_pending(conn, 10).unwrap(), 2);
+    assert_eq!(mailer.deliver_pending(conn, 10).unwrap(), 0);
+    assert_eq!(*sent.lock().unwrap(), vec!["first@example.com", "second@example.com"]);
+    assert!(OutboundEmail::pending(conn, 10).unwrap().is_empty());
+}
+
+#[test]
+fn failed_deliveries_stay_queued() {
+    let conn = &mut test_connection();
+    OutboundEmail::queue(conn, "first@example.com", "Subject", "Body".to_string()).unwrap();
+    OutboundEmail::queue(conn, "second@example.com", "Subject", "Body".to_string()).unwrap();
+    let (mailer, _sent) = mailer(1);
+
+    assert!(mailer.deliver_pending(conn, 10).is_err());
+    let pending = OutboundEmail::pending(conn, 10).unwrap();
+    assert_eq!(pending.iter().map(|email| email.recipient.as_str()).collect::<Vec<_>>(), vec!["second@example.com"]);
+}
+
+#[actix_web::test]
+async fn posts_emails_to_the_provider() {
+    let server = fixtures::email_provider().await;
+    let conn = &mut test_connection();
+    OutboundEmail::queue(conn, "first@example.com", "Subject", "Body".to_string()).unwrap();
+    let sender = HttpEmailSender::new(&server.uri(), Some("api-key".to_string()), "no-reply@example.com");
+
+    assert_eq!(Mailer::new(Box::new(sender), Duration::from_secs(1)).deliver_pending(conn, 10).unwrap(), 1);
+    assert!(OutboundEmail::pending(conn, 10).unwrap().is_empty());
+
+    let requests = server.received_requests().await.unwrap();
+    assert_eq!(requests.len(), 1);
+    assert_eq!(requests[0].headers.get("Authorization").unwrap(), "Bearer api-key");
+    let body: serde_json::Value = requests[0].body_json().unwrap();
+    assert_eq!(body, serde_json::json!({"from": "no-reply@example.com", "to": "first@example.com", "subject": "Subject", "text": "Body"}));
+}
+
+#[actix_web::test]
+async fn rejected_api_key_keeps_emails_queued() {
+    let server = fixtures::failing(401, "email/unauthorized").await;
+    let conn = &mut test_connection();
+    OutboundEmail::queue(conn, "first@example.com", "Subject", "Body".to_string()).unwrap();
+    let sender = HttpEmailSender::new(&server.uri(), Some("wrong-key".to_string()), "no-reply@example.com");
+
+    let error = Mailer::new(Box::new(sender), Duration::from_secs(1)).deliver_pending(conn, 10).unwrap_err();
+    assert!(error.contains("401"), "{}", error);
+    assert_eq!(OutboundEmail::pending(conn, 10).unwrap().len(), 1);
+}
@@ -0,0 +1,239 @@
+//! This module exposes display metadata for the supported assets and chains, so frontends don't hardcode it.
+//!
+//! For every asset and chain accepted by `Asset::is_valid`/`Chain::is_valid`, `GET /metadata` returns a localized
+//! display name, an icon URL and block explorer URL templates (`{hash}` and `{address}` are the placeholders). The
+//! locale is taken from the `locale` query parameter, falling back to the `Accept-Language` header and then to
+//! English. Supported locales are `en`, `pt`, `es` and `de`. Icon URLs are relative to `ICON_BASE_URL` (default
+//! `/static/icons`).
+//!
+//! Chain transaction templates can be overridden through the admin chain registry (`services::chain_registry`);
+//! `tx_url_templates` returns the effective template of every chain. `explorer_url` fills a chain's template with a
+//! transaction hash, and `GET /metadata/tx-link?chain=&hash=` exposes it; hashes must be `0x` followed by 64 hex
+//! digits. Trade responses use the same templates for their `explorer_url`.
+//!
+//! # Examples
+//!
+//! ```text
+//! GET /metadata?locale=pt
+//!
+//! 200 OK
+//! {
+//!     "locale": "pt",
+//!     "assets": [{"code": "BTC", "name": "Bitcoin", "icon_url": "/static/icons/assets/btc.svg", ...}, ...],
+//!     "chains": [{"code": "Ethereum", "name": "Ethereum", "tx_url_template": "https://etherscan.io/tx/{hash}", ...}, ...]
+//! }
+//! ```
+
+use std::collections::HashMap;
+
+use actix_web::http::header::ACCEPT_LANGUAGE;
+use actix_web::{web, HttpRequest, HttpResponse, ResponseError};
+use diesel::SqliteConnection;
+use serde::{Deserialize, Serialize};
+
+use crate::db::error::DbError;
+use crate::db::models::chain_explorer::ChainExplorer;
+use crate::db::models::trade::{Asset, Chain};
+use crate::db::{self, DbPool};
+use crate::error::AppError;
+
+pub const LOCALES: [&str; 4] = ["en", "pt", "es", "de"];
+const DEFAULT_LOCALE: &str = "en";
+
+// Display names in the order of `LOCALES`.
+struct Entry {
+    code: &'static str,
+    names: [&'static str; 4],
+    tx_url_template: &'static str,
+    address_url_template: &'static str,
+}
+
+const ASSETS: [Entry; 5] = [
+    Entry {
+        code: "BTC",
+        names: ["Bitcoin", "Bitcoin", "Bitcoin", "Bitcoin"],
+        tx_url_template: "https://mempool.space/tx/{hash}",
+        address_url_template: "https://mempool.space/address/{address}",
+    },
+    Entry {
+        code: "ETH",
+        names: ["Ether", "Ether", "Ether", "Ether"],
+        tx_url_template: "https://etherscan.io/tx/{hash}",
+        address_url_template: "https://etherscan.io/address/{address}",
+    },
+    Entry {
+        code: "XRP",
+        names: ["XRP", "XRP", "XRP", "XRP"],
+        tx_url_template: "https://livenet.xrpl.org/transactions/{hash}",
+        address_url_template: "https://livenet.xrpl.org/accounts/{address}",
+    },
+    Entry {
+        code: "XLM",
+        names: ["Stellar Lumens", "Stellar Lumens", "Stellar Lumens", "Stellar Lumens"],
+        tx_url_template: "https://stellar.expert/explorer/public/tx/{hash}",
+        address_url_template: "https://stellar.expert/explorer/public/account/{address}",
+    },
+    Entry {
+        code: "DOGE",
+        names: ["Dogecoin", "Dogecoin", "Dogecoin", "Dogecoin"],
+        tx_url_template: "https://blockchair.com/dogecoin/transaction/{hash}",
+        address_url_template: "https://blockchair.com/dogecoin/address/{address}",
+    },
+];
+
+const CHAINS: [Entry; 4] = [
+    Entry {
+        code: "Ethereum",
+        names: ["Ethereum", "Ethereum", "Ethereum", "Ethereum"],
+        tx_url_template: "https://etherscan.io/tx/{hash}",
+        address_url_template: "https://etherscan.io/address/{address}",
+    },
+    Entry {
+        code: "Arbitrum",
+        names: ["Arbitrum One", "Arbitrum One", "Arbitrum One", "Arbitrum One"],
+        tx_url_template: "https://arbiscan.io/tx/{hash}",
+        address_url_template: "https://arbiscan.io/address/{address}",
+    },
+    Entry {
+        code: "Optimism",
+        names: ["OP Mainnet", "OP Mainnet", "OP Mainnet", "OP Mainnet"],
+        tx_url_template: "https://optimistic.etherscan.io/tx/{hash}",
+        address_url_template: "https://optimistic.etherscan.io/address/{address}",
+    },
+    Entry {
+        code: "Polygon",
+        names: ["Polygon PoS", "Polygon PoS", "Polygon PoS", "Polygon PoS"],
+        tx_url_template: "https://polygonscan.com/tx/{hash}",
+        address_url_template: "https://polygonscan.com/address/{address}",
+    },
+];
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct DisplayMetadata {
+    pub code: String,
+    pub name: String,
+    pub icon_url: String,
+    pub tx_url_template: String,
+    pub address_url_template: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Metadata {
+    pub locale: String,
+    pub assets: Vec<DisplayMetadata>,
+    pub chains: Vec<DisplayMetadata>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct MetadataQuery {
+    pub locale: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct TxLinkQuery {
+    pub chain: String,
+    pub hash: String,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct TxLink {
+    pub chain: String,
+    pub hash: String,
+    pub url: String,
+}
+
+// Picks the first supported language of a locale list such as `pt-BR,pt;q=0.9,en;q=0.8`.
+pub fn resolve_locale(requested: Option<&str>, accept_language: Option<&str>) -> &'static str {
+    requested
+        .into_iter()
+        .chain(accept_language)
+        .flat_map(|value| value.split(','))
+        .map(|tag| tag.split(';').next().unwrap_or("").trim())
+        .filter_map(|tag| tag.split(['-', '_']).next())
+        .find_map(|language| LOCALES.iter().find(|locale| locale.eq_ignore_ascii_case(language)))
+        .copied()
+        .unwrap_or(DEFAULT_LOCALE)
+}
+
+fn icon_base_url() -> String {
+    std::env::var("ICON_BASE_URL").unwrap_or_else(|_| "/static/icons".to_string())
+}
+
+fn display(entry: &Entry, kind: &str, locale: &str, tx_url_template: Option<&String>) -> DisplayMetadata {
+    let index = LOCALES.iter().position(|candidate| *candidate == locale).unwrap_or(0);
+    DisplayMetadata {
+        code: entry.code.to_string(),
+        name: entry.names[index].to_string(),
+        icon_url: format!("{}/{}/{}.svg", icon_base_url().trim_end_matches('/'), kind, entry.code.to_lowercase()),
+        tx_url_template: tx_url_template.map(String::as_str).unwrap_or(entry.tx_url_template).to_string(),
+        address_url_template: entry.address_url_template.to_string(),
+    }
+}
+
+pub fn metadata(locale: &str, tx_url_templates: &HashMap<String, String>) -> Metadata {
+    Metadata {
+        locale: locale.to_string(),
+        assets: ASSETS
+            .iter()
+            .filter(|entry| Asset::is_valid(entry.code))
+            .map(|entry| display(entry, "assets", locale, None))
+            .collect(),
+        chains: CHAINS
+            .iter()
+            .filter(|entry| Chain::is_valid(entry.code))
+            .map(|entry| display(entry, "chains", locale, tx_url_templates.get(entry.code)))
+            .collect(),
+    }
+}
+
+pub fn is_tx_hash(hash: &str) -> bool {
+    hash.len() == 66 && hash.starts_with("0x") && hash[2..].chars().all(|c| c.is_ascii_hexdigit())
+}
+
+pub fn default_tx_url_templates() -> HashMap<String, String> {
+    CHAINS
+        .iter()
+        .map(|entry| (entry.code.to_string(), entry.tx_url_template.to_string()))
+        .collect()
+}
+
+pub fn tx_url_templates(conn: &mut SqliteConnection) -> Result<HashMap<String, String>, DbError> {
+    let mut templates = default_tx_url_templates();
+    for explorer in ChainExplorer::list(conn)? {
+        templates.insert(explorer.chain, explorer.tx_url_template);
+    }
+    Ok(templates)
+}
+
+pub fn explorer_url(tx_url_templates: &HashMap<String, String>, chain: &str, hash: &str) -> Option<String> {
+    if !is_tx_hash(hash) || !Chain::is_valid(chain) {
+        return None;
+    }
+
+    tx_url_templates.get(chain).map(|template| template.replace("{hash}", hash))
+}
+
+pub async fn get_metadata(req: HttpRequest, pool: web::Data<DbPool>, params: web::Query<MetadataQuery>) -> HttpResponse {
+    let accept_language = req.headers().get(ACCEPT_LANGUAGE).and_then(|value| value.to_str().ok());
+    match db::run(&pool, move |conn| Ok(tx_url_templates(conn)?)).await {
+        Ok(templates) => HttpResponse::Ok().json(metadata(resolve_locale(params.locale.as_deref(), accept_language), &templates)),
+        Err(err) => err.error_response(),
+    }
+}
+
+pub async fn tx_link(pool: web::Data<DbPool>, params: web::Query<TxLinkQuery>) -> HttpResponse {
+    let templates = match db::run(&pool, move |conn| Ok(tx_url_templates(conn)?)).await {
+        Ok(templates) => templates,
+        Err(err) => return err.error_response(),
+    };
+
+    match explorer_url(&templates, &params.chain, &params.hash) {
+        Some(url) => HttpResponse::Ok().json(TxLink { chain: params.chain.clone(), hash: params.hash.clone(), url }),
+        None => AppError::Validation("Unknown chain or invalid transaction hash".to_string()).error_response(),
+    }
+}
+
+pub fn init_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(web::resource("/metadata").route(web::get().to(get_metadata)))
+        .service(web::resource("/metadata/tx-link").route(web::get().to(tx_link)));
+}
@@ -0,0 +1,35 @@
+use super::metadata::{default_tx_url_templates, explorer_url, metadata, resolve_locale};
+use crate::db::models::trade::{Asset, Chain};
+
+#[test]
+fn resolves_locale_from_query_then_header() {
+    assert_eq!(resolve_locale(Some("de"), Some("pt-BR")), "de");
+    assert_eq!(resolve_locale(None, Some("fr-FR,pt-BR;q=0.9,en;q=0.8")), "pt");
+    assert_eq!(resolve_locale(Some("fr"), None), "en");
+    assert_eq!(resolve_locale(None, None), "en");
+}
+
+#[test]
+fn covers_every_registered_asset_and_chain() {
+    let mut templates = default_tx_url_templates();
+    templates.insert("Polygon".to_string(), "https://polygon.blockscout.com/tx/{hash}".to_string());
+    let metadata = metadata("en", &templates);
+
+    assert_eq!(metadata.assets.len(), Asset::ALL.len());
+    assert_eq!(metadata.chains.len(), Chain::ALL.len());
+    let arbitrum = metadata.chains.iter().find(|chain| chain.code == "Arbitrum").unwrap();
+    assert_eq!(arbitrum.tx_url_template, "https://arbiscan.io/tx/{hash}");
+    assert!(arbitrum.icon_url.ends_with("/chains/arbitrum.svg"));
+    let polygon = metadata.chains.iter().find(|chain| chain.code == "Polygon").unwrap();
+    assert_eq!(polygon.tx_url_template, "https://polygon.blockscout.com/tx/{hash}");
+}
+
+#[test]
+fn builds_transaction_links() {
+    let templates = default_tx_url_templates();
+    let hash = format!("0x{}", "ab".repeat(32));
+
+    assert_eq!(explorer_url(&templates, "Polygon", &hash), Some(format!("https://polygonscan.com/tx/{}", hash)));
+    assert_eq!(explorer_url(&templates, "Solana", &hash), None);
+    assert_eq!(explorer_url(&templates, "Ethereum", "0x1234"), None);
+}
@@ -0,0 +1,127 @@
+//! This module defines the business SLA metrics of the trade lifecycle and the endpoint that exposes them.
+//!
+//! These metrics describe the trades themselves rather than the HTTP requests that carry them, so operations can
+//! alert when trades reach the books late even while every request succeeds quickly:
+//!
+//! - `trades_recorded_total{source}`: trades written through the trade journal, by entry path (`manual`,
+//!   `import:email`, ...). API key sources are reported as `api_key`, without the key ID.
+//! - `trade_record_lag_seconds{source}`: for trades that carry their execution time (`TradeForm::timestamp`), how long
+//!   after execution the trade was recorded. For `import:*` sources this is the import lag of the broker feed.
+//! - `trade_request_resolution_seconds{status}`: the time from an assistant proposing a trade in the owner's inbox to
+//!   the owner accepting (the trade is placed) or rejecting it.
+//!
+//! - `circuit_breaker_state{name}`: the state of each registered circuit breaker of an external integration (see
+//!   `utils::circuit_breaker`): `0` closed, `1` half-open, `2` open.
+//! - `circuit_breaker_consecutive_failures{name}`: the failures of each breaker since its last successful call.
+//!
+//! The histograms share `LAG_BUCKETS`, from one second to a week. Every trade goes through `TradeJournal::record`,
+//! which calls `trade_recorded`; the inbox calls `trade_request_resolved`. The breaker gauges are read when the
+//! endpoint is scraped.
+//!
+//! `GET /metrics/business` returns the metrics in the OpenMetrics text format. Scrapers authenticate with the shared
+//! secret `METRICS_TOKEN` (see `config::secret`) as a bearer token; without it configured the endpoint answers `401`.
+
+use actix_web::http::header::AUTHORIZATION;
+use actix_web::{web, HttpRequest, HttpResponse, ResponseError};
+use chrono::NaiveDateTime;
+use sha2::{Digest, Sha256};
+
+use crate::config;
+use crate::error::AppError;
+use crate::utils::circuit_breaker::{self, CircuitState};
+use crate::utils::metrics::{self, Metric, MetricKind, CONTENT_TYPE};
+
+pub const LAG_BUCKETS: [f64; 10] = [1.0, 5.0, 30.0, 60.0, 300.0, 900.0, 3600.0, 21600.0, 86400.0, 604800.0];
+
+pub const TRADES_RECORDED: Metric = Metric {
+    name: "trades_recorded",
+    help: "Trades written through the trade journal, by source.",
+    kind: MetricKind::Counter,
+    buckets: &[],
+};
+
+pub const TRADE_RECORD_LAG: Metric = Metric {
+    name: "trade_record_lag_seconds",
+    help: "Time from a trade's execution to it being recorded, by source.",
+    kind: MetricKind::Histogram,
+    buckets: &LAG_BUCKETS,
+};
+
+pub const TRADE_REQUEST_RESOLUTION: Metric = Metric {
+    name: "trade_request_resolution_seconds",
+    help: "Time from a trade being proposed in the inbox to it being accepted or rejected, by status.",
+    kind: MetricKind::Histogram,
+    buckets: &LAG_BUCKETS,
+};
+
+pub const CIRCUIT_BREAKER_STATE: Metric = Metric {
+    name: "circuit_breaker_state",
+    help: "State of a circuit breaker: 0 closed, 1 half-open, 2 open.",
+    kind: MetricKind::Gauge,
+    buckets: &[],
+};
+
+pub const CIRCUIT_BREAKER_FAILURES: Metric = Metric {
+    name: "circuit_breaker_consecutive_failures",
+    help: "Consecutive failures of the integration behind a circuit breaker.",
+    kind: MetricKind::Gauge,
+    buckets: &[],
+};
+
+// Sources qualified by an ID would create a series per ID.
+pub fn source_label(source: &str) -> &str {
+    match source.split_once(':') {
+        Some(("api_key", _)) => "api_key",
+        _ => source,
+    }
+}
+
+pub fn trade_recorded(source: &str, executed_at: Option<i64>) {
+    let labels = [("source", source_label(source))];
+    metrics::increment(&TRADES_RECORDED, &labels);
+    if let Some(executed_at) = executed_at {
+        let lag = chrono::Utc::now().timestamp() - executed_at;
+        metrics::observe(&TRADE_RECORD_LAG, &labels, lag.max(0) as f64);
+    }
+}
+
+pub fn trade_request_resolved(status: &str, proposed_at: NaiveDateTime) {
+    let elapsed = chrono::Local::now().naive_local() - proposed_at;
+    metrics::observe(&TRADE_REQUEST_RESOLUTION, &[("status", status)], elapsed.num_milliseconds().max(0) as f64 / 1000.0);
+}
+
+pub fn record_circuit_breakers() {
+    for status in circuit_breaker::statuses() {
+        let state = match status.state {
+            CircuitState::Closed => 0.0,
+            CircuitState::HalfOpen => 1.0,
+            CircuitState::Open => 2.0,
+        };
+        let labels = [("name", status.name.as_str())];
+        metrics::set(&CIRCUIT_BREAKER_STATE, &labels, state);
+        metrics::set(&CIRCUIT_BREAKER_FAILURES, &labels, status.consecutive_failures as f64);
+    }
+}
+
+fn authorized(req: &HttpRequest) -> bool {
+    let token = match config::secret("METRICS_TOKEN") {
+        Some(token) => token,
+        _ => return false,
+    };
+    match req.headers().get(AUTHORIZATION).and_then(|value| value.to_str().ok()).and_then(|value| value.strip_prefix("Bearer ")) {
+        Some(provided) => Sha256::digest(provided.as_bytes()) == Sha256::digest(token.as_bytes()),
+        None => false,
+    }
+}
+
+pub async fn business(req: HttpRequest) -> HttpResponse {
+    if !authorized(&req) {
+        return AppError::Unauthorized("Invalid metrics token".to_string()).error_response();
+    }
+    record_circuit_breakers();
+    HttpResponse::Ok().content_type(CONTENT_TYPE).body(metrics::render())
+}
+
+pub fn init_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(web::resource("/metrics/business").route(web::get().to(business)));
+}
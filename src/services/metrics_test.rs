@@ -0,0 +1,28 @@
+use std::time::Duration;
+
+use actix_web::http::header::AUTHORIZATION;
+use actix_web::http::StatusCode;
+use actix_web::test::{call_service, init_service, read_body, TestRequest};
+use actix_web::App;
+
+use super::metrics::init_routes;
+use crate::utils::circuit_breaker::{self, CircuitBreaker};
+
+#[actix_web::test]
+async fn business_metrics_report_circuit_breakers() {
+    std::env::set_var("METRICS_TOKEN", "metrics_secret");
+    let breaker = circuit_breaker::register(CircuitBreaker::new("metrics_test_feed", 2, Duration::from_secs(60)));
+    for _ in 0..2 {
+        let _ = breaker.call(|| Err::<(), _>("unavailable"));
+    }
+    let app = init_service(App::new().configure(init_routes)).await;
+
+    let req = TestRequest::get().uri("/metrics/business").to_request();
+    assert_eq!(call_service(&app, req).await.status(), StatusCode::UNAUTHORIZED);
+
+    let req = TestRequest::get().uri("/metrics/business").insert_header((AUTHORIZATION, "Bearer metrics_secret")).to_request();
+    let body = String::from_utf8(read_body(call_service(&app, req).await).await.to_vec()).unwrap();
+    assert!(body.contains("# TYPE circuit_breaker_state gauge\n"));
+    assert!(body.contains("circuit_breaker_state{name=\"metrics_test_feed\"} 2\n"));
+    assert!(body.contains("circuit_breaker_consecutive_failures{name=\"metrics_test_feed\"} 2\n"));
+}
@@ -0,0 +1,46 @@
+//! This module defines the database migration status route for operators.
+//!
+//! `GET /admin/migrations` lists the migrations applied to the database, oldest first, and the migrations embedded in
+//! this build that are not applied yet, by name (e.g. `2023-09-16-000000_foreign_keys`):
+//!
+//! ```text
+//! GET /admin/migrations
+//!
+//! 200 OK
+//! {
+//!     "applied": ["2023-08-13-014914_create", "..."],
+//!     "pending": []
+//! }
+//! ```
+//!
+//! `pending` is empty after startup unless `database.auto_migrate` is off (see `db::establish_connection`), so a
+//! non-empty list tells that the schema is behind the build. Migrations applied by a newer build are listed by their
+//! bare version.
+//!
+//! # Note
+//! The route is wrapped with the `JwtGuard` middleware and only answers admins (`ADMIN_USER_IDS`).
+
+use actix_web::{web, HttpRequest, HttpResponse, ResponseError};
+
+use crate::db::{self, DbPool};
+use crate::error::AppError;
+use crate::middleware::jwt_guard::JwtGuard;
+use crate::services::jwt;
+
+pub async fn status(req: HttpRequest, pool: web::Data<DbPool>) -> HttpResponse {
+    if let Err(err) = jwt::require_admin(&req) {
+        return err.error_response();
+    }
+
+    let status = db::run(&pool, |conn| {
+        db::migration_status(conn).map_err(|err| AppError::Internal(format!("Failed to read the migrations: {}", err)))
+    });
+    match status.await {
+        Ok(status) => HttpResponse::Ok().json(status),
+        Err(err) => err.error_response(),
+    }
+}
+
+pub fn init_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(web::resource("/admin/migrations").route(web::get().to(status).wrap(JwtGuard)));
+}
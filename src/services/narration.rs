@@ -0,0 +1,129 @@
+//! This module renders trades as short human-readable sentences, e.g.
+//! `Bought 1.5 ETH at $1,850 on Arbitrum, fees $8.30, PnL +$120`.
+//!
+//! Each locale of `services::metadata::LOCALES` has its own template (see `utils::template`) and number format: English
+//! groups thousands with `,` and uses `.` for decimals, the other locales the reverse. Prices and fees are shown in US
+//! dollars with two decimals, dropped when they are zero; quantities keep up to eight significant decimals. The P&L
+//! section is left out while the trade has no `final_price`.
+//!
+//! Trade responses include the sentence as `summary` when requested with `?include=summary` (see `services::trade`),
+//! and messages sent to users about a trade should use `describe` so they read the same.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use crate::services::narration::describe;
+//!
+//! let sentence = describe(&trade, "pt");
+//! // "Comprou 1,5 ETH a $1.850 em Arbitrum, taxas $17,58, resultado +$120"
+//! ```
+
+use std::collections::HashMap;
+
+use crate::db::models::trade::Trade;
+use crate::services::metadata::LOCALES;
+use crate::utils::template;
+
+struct Phrases {
+    bought: &'static str,
+    sold: &'static str,
+    template: &'static str,
+}
+
+// Phrases in the order of `LOCALES`.
+const PHRASES: [Phrases; 4] = [
+    Phrases {
+        bought: "Bought",
+        sold: "Sold",
+        template: "{action} {quantity} {asset} at {price} on {chain}, fees {fees}[, PnL {pnl}]",
+    },
+    Phrases {
+        bought: "Comprou",
+        sold: "Vendeu",
+        template: "{action} {quantity} {asset} a {price} em {chain}, taxas {fees}[, resultado {pnl}]",
+    },
+    Phrases {
+        bought: "Compró",
+        sold: "Vendió",
+        template: "{action} {quantity} {asset} a {price} en {chain}, comisiones {fees}[, PyG {pnl}]",
+    },
+    Phrases {
+        bought: "Gekauft:",
+        sold: "Verkauft:",
+        template: "{action} {quantity} {asset} zu {price} auf {chain}, Gebühren {fees}[, GuV {pnl}]",
+    },
+];
+
+fn separators(locale: &str) -> (char, char) {
+    if locale == "en" {
+        (',', '.')
+    } else {
+        ('.', ',')
+    }
+}
+
+fn format_number(value: f64, decimals: usize, locale: &str) -> String {
+    let (thousands, decimal) = separators(locale);
+    let formatted = format!("{:.*}", decimals, value.abs());
+    let (integer, fraction) = formatted.split_once('.').unwrap_or((&formatted, ""));
+
+    let mut grouped = String::with_capacity(integer.len() + integer.len() / 3);
+    for (index, digit) in integer.chars().enumerate() {
+        if index > 0 && (integer.len() - index) % 3 == 0 {
+            grouped.push(thousands);
+        }
+        grouped.push(digit);
+    }
+
+    if fraction.is_empty() {
+        grouped
+    } else {
+        format!("{}{}{}", grouped, decimal, fraction)
+    }
+}
+
+pub fn format_quantity(value: f32, locale: &str) -> String {
+    let (_, decimal) = separators(locale);
+    let formatted = format_number(value as f64, 8, locale);
+    let formatted = formatted.trim_end_matches('0').trim_end_matches(decimal);
+    if value < 0.0 {
+        format!("-{}", formatted)
+    } else {
+        formatted.to_string()
+    }
+}
+
+pub fn format_money(value: f32, locale: &str, signed: bool) -> String {
+    let (_, decimal) = separators(locale);
+    let formatted = format_number(value as f64, 2, locale);
+    let zero_cents = format!("{}00", decimal);
+    let formatted = formatted.strip_suffix(&zero_cents).unwrap_or(&formatted);
+    let sign = match (value < 0.0, signed) {
+        (true, _) => "-",
+        (false, true) => "+",
+        (false, false) => "",
+    };
+    format!("{}${}", sign, formatted)
+}
+
+pub fn describe(trade: &Trade, locale: &str) -> String {
+    let index = LOCALES.iter().position(|candidate| *candidate == locale).unwrap_or(0);
+    let (locale, phrases) = (LOCALES[index], &PHRASES[index]);
+
+    let mut values = HashMap::new();
+    let action = match trade.trade_type.as_str() {
+        "LimitSell" | "MarketSell" => phrases.sold,
+        _ => phrases.bought,
+    };
+    values.insert("action", action.to_string());
+    values.insert("quantity", format_quantity(trade.traded_amount, locale));
+    values.insert("asset", trade.asset.clone());
+    values.insert("price", format_money(trade.execution_price, locale, false));
+    values.insert("chain", trade.chain.clone());
+    values.insert("fees", format_money(trade.execution_fee + trade.transaction_fee, locale, false));
+    if trade.final_price > 0.0 {
+        values.insert("pnl", format_money(trade.calculate_trade_pnl(), locale, true));
+    }
+
+    template::render(phrases.template, &values)
+}
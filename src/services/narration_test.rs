@@ -0,0 +1,45 @@
+use super::narration::{describe, format_money, format_quantity};
+use crate::db::models::trade::Trade;
+
+fn trade(trade_type: &str, final_price: f32) -> Trade {
+    let now = chrono::Local::now().naive_local();
+    Trade {
+        id: "trade_id".to_string(),
+        user_id: "user_id".to_string(),
+        wallet_id: "wallet_id".to_string(),
+        amount: 2775.0,
+        chain: "Arbitrum".to_string(),
+        trade_type: trade_type.to_string(),
+        asset: "ETH".to_string(),
+        before_price: 1850.0,
+        execution_price: 1850.0,
+        final_price,
+        traded_amount: 1.5,
+        execution_fee: 5.0,
+        transaction_fee: 4.0,
+        created_at: now,
+        updated_at: now,
+        entered_by: None,
+        tx_hash: None,
+        source: "manual".to_string(),
+        deleted_at: None,
+        metadata: None,
+    }
+}
+
+#[test]
+fn describes_trades_per_locale() {
+    assert_eq!(describe(&trade("MarketBuy", 1936.0), "en"), "Bought 1.5 ETH at $1,850 on Arbitrum, fees $9, PnL +$120");
+    assert_eq!(describe(&trade("LimitBuy", 1936.0), "pt"), "Comprou 1,5 ETH a $1.850 em Arbitrum, taxas $9, resultado +$120");
+    assert_eq!(describe(&trade("MarketSell", 0.0), "en"), "Sold 1.5 ETH at $1,850 on Arbitrum, fees $9");
+    assert_eq!(describe(&trade("MarketSell", 0.0), "fr"), "Sold 1.5 ETH at $1,850 on Arbitrum, fees $9");
+}
+
+#[test]
+fn formats_numbers_for_the_locale() {
+    assert_eq!(format_money(8.3, "en", false), "$8.30");
+    assert_eq!(format_money(1234567.0, "de", false), "$1.234.567");
+    assert_eq!(format_money(-12.5, "es", true), "-$12,50");
+    assert_eq!(format_quantity(0.00012, "en"), "0.00012");
+    assert_eq!(format_quantity(1000.0, "pt"), "1.000");
+}
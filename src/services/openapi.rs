@@ -0,0 +1,70 @@
+//! This module serves the OpenAPI specification of the user and trade API.
+//!
+//! The specification is derived at compile time from the `#[utoipa::path]` annotations on the handlers in
+//! `services::user` and `services::trade` and from the `ToSchema`/`IntoParams` derives on their request and response
+//! types, so it stays in step with the code. Routes that require a JWT are marked with the `bearer_auth` scheme.
+//!
+//! - `GET /api-doc/openapi.json`: The specification as JSON.
+//! - `GET /swagger-ui/`: Swagger UI for browsing and trying out the API.
+//!
+//! # Note
+//! Neither route is wrapped with the `JwtGuard` middleware: the specification only describes the API.
+
+use actix_web::web;
+use utoipa::openapi::security::{HttpAuthScheme, HttpBuilder, SecurityScheme};
+use utoipa::{Modify, OpenApi};
+use utoipa_swagger_ui::SwaggerUi;
+
+use crate::services::{trade, user};
+
+struct BearerAuth;
+
+impl Modify for BearerAuth {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+        components.add_security_scheme(
+            "bearer_auth",
+            SecurityScheme::Http(HttpBuilder::new().scheme(HttpAuthScheme::Bearer).bearer_format("JWT").build()),
+        );
+    }
+}
+
+#[derive(OpenApi)]
+#[openapi(
+    info(title = "Trade Management System"),
+    paths(
+        user::create_user,
+        user::index,
+        user::get,
+        user::update,
+        user::delete,
+        user::login,
+        user::confirm_login,
+        trade::create_trade,
+        trade::quick_trade,
+        trade::index,
+        trade::export,
+        trade::get,
+        trade::update,
+        trade::patch,
+        trade::delete,
+        trade::history,
+        trade::profit_loss,
+        trade::cumulative_fee,
+        trade::fee_breakdown,
+        trade::slippage,
+        trade::execution_quality,
+        trade::trade_clusters,
+    ),
+    modifiers(&BearerAuth),
+    tags(
+        (name = "users", description = "Registration, login and user management"),
+        (name = "trades", description = "Trade entry and retrieval"),
+        (name = "analytics", description = "Aggregates over a trader's trades, as JSON or CSV"),
+    )
+)]
+pub struct ApiDoc;
+
+pub fn init_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(SwaggerUi::new("/swagger-ui/{_:.*}").url("/api-doc/openapi.json", ApiDoc::openapi()));
+}
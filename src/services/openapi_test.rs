@@ -0,0 +1,30 @@
+use actix_web::{test, App};
+use utoipa::OpenApi;
+
+use super::openapi::{init_routes, ApiDoc};
+
+#[actix_web::test]
+async fn documents_every_user_and_trade_route() {
+    let spec = ApiDoc::openapi();
+    for path in ["/user", "/user/{user_id}", "/login", "/trade", "/trade/quick", "/trade/{trade_id}", "/profit-loss", "/cumulative-fees", "/slippage", "/execution-quality", "/trade-clusters"] {
+        assert!(spec.paths.paths.contains_key(path), "{} is not documented", path);
+    }
+
+    let schemas = spec.components.unwrap().schemas;
+    for schema in ["TradeForm", "TradeResponse", "Trade", "User", "DailyProfitLoss", "ErrorBody"] {
+        assert!(schemas.contains_key(schema), "{} has no schema", schema);
+    }
+}
+
+#[actix_web::test]
+async fn serves_the_specification_and_swagger_ui() {
+    let app = test::init_service(App::new().configure(init_routes)).await;
+
+    let req = test::TestRequest::get().uri("/api-doc/openapi.json").to_request();
+    let spec: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    assert!(spec["paths"]["/trade/{trade_id}"]["put"].is_object());
+    assert!(spec["components"]["securitySchemes"]["bearer_auth"].is_object());
+
+    let req = test::TestRequest::get().uri("/swagger-ui/").to_request();
+    assert!(test::call_service(&app, req).await.status().is_success());
+}
@@ -0,0 +1,66 @@
+//! This module implements keyset pagination for listings that keep growing, such as trades and wallet ledgers.
+//!
+//! Offset paging has to skip every row before the page, so deep pages get slower as a table grows, and rows inserted
+//! while a client pages through shift the pages under it. A keyset page instead continues after the `(created_at, id)`
+//! of the last row of the previous page, which an index on those columns finds directly.
+//!
+//! Listings that support it switch to keyset paging when the request has a `cursor` parameter: empty for the first
+//! page, then the `next_cursor` of the previous response. The rows are then wrapped in a `Page` envelope. `next_cursor`
+//! is `null` once a page comes back short, which means there is nothing left. Cursors are opaque tokens (`hex` of the
+//! key) and cannot be combined with `offset`.
+//!
+//! # Examples
+//!
+//! ```text
+//! GET /trade?cursor=&limit=2
+//! { "data": [{ "id": "b7...", ... }, { "id": "4c...", ... }], "next_cursor": "323032..." }
+//!
+//! GET /trade?cursor=323032...&limit=2
+//! { "data": [{ "id": "91...", ... }], "next_cursor": null }
+//! ```
+
+use chrono::NaiveDateTime;
+use serde::Serialize;
+
+use crate::error::AppError;
+
+const CURSOR_FORMAT: &str = "%Y-%m-%d %H:%M:%S%.f";
+
+// The `(created_at, id)` of a row, which orders a listing.
+pub type Keyset = (NaiveDateTime, String);
+
+#[derive(Debug, Serialize)]
+pub struct Page<T> {
+    pub data: Vec<T>,
+    pub next_cursor: Option<String>,
+}
+
+pub fn encode_cursor(key: &Keyset) -> String {
+    hex::encode(format!("{}|{}", key.0.format(CURSOR_FORMAT), key.1))
+}
+
+pub fn decode_cursor(cursor: &str) -> Option<Keyset> {
+    let decoded = String::from_utf8(hex::decode(cursor).ok()?).ok()?;
+    let (created_at, id) = decoded.split_once('|')?;
+    Some((NaiveDateTime::parse_from_str(created_at, CURSOR_FORMAT).ok()?, id.to_string()))
+}
+
+// `None` for offset paging, `Some(None)` for the first keyset page and `Some(Some(key))` for the next ones.
+pub fn parse_cursor(cursor: Option<&str>, offset: Option<i64>) -> Result<Option<Option<Keyset>>, AppError> {
+    match cursor {
+        None => Ok(None),
+        Some(_) if offset.is_some() => Err(AppError::Validation("Error: cursor and offset cannot be combined".to_string())),
+        Some("") => Ok(Some(None)),
+        Some(cursor) => decode_cursor(cursor)
+            .map(|key| Some(Some(key)))
+            .ok_or_else(|| AppError::Validation("Error: Invalid cursor".to_string())),
+    }
+}
+
+// The cursor of the page after `rows`, unless `rows` is the last page.
+pub fn next_cursor<T>(rows: &[T], limit: i64, key: impl Fn(&T) -> Keyset) -> Option<String> {
+    match rows.last() {
+        Some(last) if rows.len() as i64 == limit => Some(encode_cursor(&key(last))),
+        _ => None,
+    }
+}
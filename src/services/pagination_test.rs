@@ -0,0 +1,49 @@
+use diesel::prelude::*;
+
+use crate::db::fixtures::{funded_wallet, test_connection};
+use crate::db::models::wallet_transaction::WalletTransaction;
+use crate::db::schema::wallet_transactions;
+use super::pagination::{decode_cursor, encode_cursor, next_cursor, parse_cursor};
+
+#[test]
+fn cursors_round_trip_and_reject_garbage() {
+    let created_at = chrono::NaiveDate::from_ymd_opt(2023, 9, 1).unwrap().and_hms_micro_opt(12, 30, 0, 250).unwrap();
+    let key = (created_at, "4c2f-id".to_string());
+    assert_eq!(decode_cursor(&encode_cursor(&key)), Some(key.clone()));
+
+    assert_eq!(decode_cursor("not-hex"), None);
+    assert_eq!(decode_cursor(&hex::encode("2023-09-01")), None);
+    assert!(parse_cursor(Some("not-hex"), None).is_err());
+    assert!(parse_cursor(Some(""), Some(10)).is_err());
+    assert_eq!(parse_cursor(None, Some(10)).unwrap(), None);
+    assert_eq!(parse_cursor(Some(""), None).unwrap(), Some(None));
+    assert_eq!(parse_cursor(Some(&encode_cursor(&key)), None).unwrap(), Some(Some(key)));
+}
+
+#[test]
+fn keyset_pages_cover_the_ledger_once() {
+    let conn = &mut test_connection();
+    let wallet = funded_wallet(conn);
+    for amount in 1..=4 {
+        WalletTransaction::deposit(conn, wallet.id.clone(), amount as f32, None).unwrap();
+    }
+    // Entries sharing a timestamp are ordered by ID.
+    let created_at = chrono::Local::now().naive_local();
+    diesel::update(wallet_transactions::table).set(wallet_transactions::created_at.eq(created_at)).execute(conn).unwrap();
+
+    let mut seen = Vec::new();
+    let mut before = None;
+    loop {
+        let page = WalletTransaction::list_before(conn, wallet.id.clone(), before.as_ref(), 2).unwrap();
+        seen.extend(page.iter().map(|entry| entry.id.clone()));
+        match next_cursor(&page, 2, |entry| (entry.created_at, entry.id.clone())) {
+            Some(cursor) => before = decode_cursor(&cursor),
+            None => break,
+        }
+    }
+
+    let mut expected: Vec<String> = WalletTransaction::list_for_wallet(conn, wallet.id, 10, 0).unwrap().into_iter().map(|entry| entry.id).collect();
+    expected.sort_by(|a, b| b.cmp(a));
+    assert_eq!(seen, expected);
+    assert_eq!(seen.len(), 5);
+}
@@ -0,0 +1,61 @@
+//! This module defines the endpoints for resetting a forgotten password.
+//!
+//! The provided functions include:
+//!
+//! - `forgot_password`: Emails a single-use reset token to the account with the address in `{"email": "..."}`
+//!   (`POST /password/forgot`). It answers `202` whether or not an account has that address, so it cannot be used to
+//!   find out which emails are registered.
+//! - `reset_password`: Sets a new password from `{"token": "...", "password": "..."}` (`POST /password/reset`) and ends
+//!   every session of the user. An unknown, expired or already used token is a `401`, a missing password a `400`.
+//!
+//! The tokens and their lifetime are described in `db::models::password_reset`; the emails are delivered by
+//! `services::mailer`.
+//!
+//! # Note
+//! Neither route is wrapped with the `JwtGuard` middleware: the user cannot log in, and the token is the credential.
+
+use actix_web::{web, HttpResponse, ResponseError};
+use serde::{Deserialize, Serialize};
+
+use crate::db::models::password_reset::{PasswordReset, INVALID_TOKEN};
+use crate::db::{self, DbPool};
+use crate::error::AppError;
+use crate::middleware::load_shed::LoadShed;
+
+#[derive(Serialize, Deserialize)]
+pub struct ForgotPasswordForm {
+    pub email: String,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ResetPasswordForm {
+    pub token: String,
+    pub password: String,
+}
+
+pub async fn forgot_password(pool: web::Data<DbPool>, form: web::Json<ForgotPasswordForm>) -> HttpResponse {
+    match db::run(&pool, move |conn| Ok(PasswordReset::request(conn, form.into_inner().email)?)).await {
+        Ok(_) => HttpResponse::Accepted().json("If an account uses this address, a reset token was emailed to it"),
+        Err(err) => err.error_response(),
+    }
+}
+
+pub async fn reset_password(pool: web::Data<DbPool>, form: web::Json<ResetPasswordForm>) -> HttpResponse {
+    let form = form.into_inner();
+    match db::run(&pool, move |conn| Ok(PasswordReset::reset(conn, &form.token, form.password)?)).await {
+        Ok((Some(user), None)) => HttpResponse::Ok().json(user),
+        Ok((_, errors)) => {
+            let error = errors.unwrap_or_default();
+            match error.as_str() {
+                INVALID_TOKEN => AppError::Unauthorized(error).error_response(),
+                _ => AppError::Validation(error).error_response(),
+            }
+        }
+        Err(err) => err.error_response(),
+    }
+}
+
+pub fn init_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(web::resource("/password/forgot").route(web::post().to(forgot_password).wrap(LoadShed::high_priority())))
+        .service(web::resource("/password/reset").route(web::post().to(reset_password).wrap(LoadShed::high_priority())));
+}
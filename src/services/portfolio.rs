@@ -0,0 +1,100 @@
+//! This module defines the portfolio endpoint built on the positions derived from trades.
+//!
+//! `GET /portfolio/{user_id}` returns one entry per asset the user has traded, with the current quantity (negative
+//! for a short), the weighted average entry price, the mark price (`last_price`), the realized P&L and the unrealized
+//! P&L at that mark, plus the total unrealized P&L. Positions are maintained by the trade model on every create, update
+//! and delete (see `db::models::position`), so this endpoint only reads them.
+//!
+//! The mark is the current price from the price feed when one is available (`marked_to_market` is then `true`, see
+//! `services::prices`), and the price of the asset's most recent trade otherwise.
+//!
+//! # Note
+//! The route is wrapped with the `JwtGuard` middleware; only the user and admins (`ADMIN_USER_IDS`) can read a
+//! portfolio.
+
+use std::collections::BTreeMap;
+
+use actix_web::{web, HttpRequest, HttpResponse, ResponseError};
+use serde::{Deserialize, Serialize};
+
+use crate::db::models::position::Position;
+use crate::db::{self, DbPool};
+use crate::error::AppError;
+use crate::middleware::jwt_guard::JwtGuard;
+use crate::services::jwt;
+use crate::services::prices::PriceCache;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PortfolioEntry {
+    pub asset: String,
+    pub quantity: f32,
+    pub average_entry_price: f32,
+    pub last_price: f32,
+    pub realized_pnl: f32,
+    pub unrealized_pnl: f32,
+    pub marked_to_market: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Portfolio {
+    pub user_id: String,
+    pub positions: Vec<PortfolioEntry>,
+    pub unrealized_pnl: f32,
+}
+
+impl Portfolio {
+    // `marks` are feed prices by asset; they replace the last trade price of the positions they cover.
+    pub fn new(user_id: String, positions: Vec<Position>, marks: &BTreeMap<String, f32>) -> Self {
+        let positions: Vec<PortfolioEntry> = positions
+            .into_iter()
+            .map(|mut position| {
+                let mark = marks.get(&position.asset).copied();
+                if let Some(mark) = mark {
+                    position.last_price = mark;
+                }
+                (position, mark.is_some())
+            })
+            .map(|(position, marked_to_market)| PortfolioEntry {
+                unrealized_pnl: position.unrealized_pnl(),
+                marked_to_market,
+                asset: position.asset,
+                quantity: position.quantity,
+                average_entry_price: position.average_entry_price,
+                last_price: position.last_price,
+                realized_pnl: position.realized_pnl,
+            })
+            .collect();
+        let unrealized_pnl = positions.iter().map(|entry| entry.unrealized_pnl).sum();
+
+        Portfolio { user_id, positions, unrealized_pnl }
+    }
+}
+
+fn ensure_can_view(req: &HttpRequest, user_id: &str) -> Result<(), AppError> {
+    match jwt::user_id(req) {
+        Some(caller_id) if caller_id != user_id && !jwt::is_admin(&caller_id) => {
+            Err(AppError::Forbidden("Portfolio belongs to another user".to_string()))
+        }
+        _ => Ok(()),
+    }
+}
+
+pub async fn portfolio(req: HttpRequest, pool: web::Data<DbPool>, user_id: web::Path<String>) -> HttpResponse {
+    let user_id = user_id.into_inner();
+    if let Err(err) = ensure_can_view(&req, &user_id) {
+        return err.error_response();
+    }
+
+    let owner_id = user_id.clone();
+    match db::run(&pool, move |conn| Ok(Position::list_for_user(conn, owner_id)?)).await {
+        Ok(positions) => {
+            let marks = req.app_data::<web::Data<PriceCache>>().map(|prices| prices.marks()).unwrap_or_default();
+            HttpResponse::Ok().json(Portfolio::new(user_id, positions, &marks))
+        }
+        Err(err) => err.error_response(),
+    }
+}
+
+pub fn init_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(web::resource("/portfolio/{user_id}").route(web::get().to(portfolio).wrap(JwtGuard)));
+}
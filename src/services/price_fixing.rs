@@ -0,0 +1,74 @@
+//! This module defines the admin API of the end-of-day price fixing (see `db::models::price_fixing`).
+//!
+//! The provided functions include:
+//!
+//! - `list_fixings`: Lists every fixing version, newest first, for auditing which source and fixing time applied to a
+//!   day (`GET /admin/price-fixings`). Daily snapshots refer to these versions by `fixing_id`.
+//! - `create_fixing`: Adds a version taking effect at a Unix timestamp (`POST /admin/price-fixings` with
+//!   `{"source": "coingecko", "fixing_time": "16:00", "effective_from": 1693526400}`). `fixing_time` is `HH:MM` in UTC.
+//!   It closes the current version; days that started before `effective_from` keep their fixing.
+//!
+//! Snapshots already stored for days covered by a new version only pick it up when they are rebuilt, e.g. by a
+//! `snapshots` recompute job (`POST /admin/recompute`).
+//!
+//! # Note
+//! All routes are wrapped with the `JwtGuard` middleware and restricted to admins (`ADMIN_USER_IDS`).
+
+use actix_web::{web, HttpRequest, HttpResponse, ResponseError};
+use serde::{Deserialize, Serialize};
+
+use crate::db::models::price_fixing::PriceFixing;
+use crate::db::{self, DbPool};
+use crate::error::AppError;
+use crate::middleware::jwt_guard::JwtGuard;
+use crate::services::jwt;
+use crate::utils;
+
+#[derive(Serialize, Deserialize)]
+pub struct PriceFixingForm {
+    pub source: String,
+    pub fixing_time: String,
+    pub effective_from: i64,
+}
+
+pub async fn list_fixings(req: HttpRequest, pool: web::Data<DbPool>) -> HttpResponse {
+    if let Err(err) = jwt::require_admin(&req) {
+        return err.error_response();
+    }
+
+    match db::run(&pool, move |conn| Ok(PriceFixing::list(conn)?)).await {
+        Ok(fixings) => HttpResponse::Ok().json(fixings),
+        Err(err) => err.error_response(),
+    }
+}
+
+pub async fn create_fixing(req: HttpRequest, pool: web::Data<DbPool>, form: web::Json<PriceFixingForm>) -> HttpResponse {
+    let admin_id = match jwt::require_admin(&req) {
+        Ok(admin_id) => admin_id,
+        Err(err) => return err.error_response(),
+    };
+
+    let form = form.into_inner();
+    let fixing_time = match chrono::NaiveTime::parse_from_str(&form.fixing_time, "%H:%M") {
+        Ok(fixing_time) => fixing_time,
+        Err(_) => return AppError::Validation("Invalid fixing_time, expected HH:MM".to_string()).error_response(),
+    };
+    let effective_from = match utils::date::timestamp_to_naive_date_time(form.effective_from) {
+        Some(effective_from) => effective_from,
+        None => return AppError::Validation("Invalid effective_from timestamp".to_string()).error_response(),
+    };
+
+    match db::run(&pool, move |conn| Ok(PriceFixing::create(conn, form.source, fixing_time, effective_from, admin_id)?)).await {
+        Ok((Some(fixing), None)) => HttpResponse::Ok().json(fixing),
+        Ok((_, errors)) => AppError::Validation(errors.unwrap_or_default()).error_response(),
+        Err(err) => err.error_response(),
+    }
+}
+
+pub fn init_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::resource("/admin/price-fixings")
+            .route(web::get().to(list_fixings).wrap(JwtGuard))
+            .route(web::post().to(create_fixing).wrap(JwtGuard)),
+    );
+}
@@ -0,0 +1,199 @@
+//! This module marks open positions to market with current prices from an external price feed.
+//!
+//! A `PriceProvider` fetches the current USD price of a set of assets; `CoinGecko` is the built-in provider, reading
+//! the `/simple/price` endpoint of `PRICE_FEED_URL` (default `https://api.coingecko.com/api/v3`) with the optional
+//! `COINGECKO_API_KEY` (see `config::secret`). Other feeds can be plugged in by implementing the trait.
+//!
+//! `PriceCache` keeps the last price of every asset in `Asset::ALL`. `spawn_refresh`, started from `main`, refreshes it
+//! every `PRICE_REFRESH_SECS` seconds (default 60) on a background thread, through a circuit breaker so an unavailable
+//! feed is not hammered; the breaker is named after the provider and reported by `/metrics/business`. A price is used for `PRICE_MAX_AGE_SECS` seconds after it was fetched (default five refresh
+//! intervals); after that it is considered stale and dropped from `marks`.
+//!
+//! Marks are used by:
+//!
+//! - `GET /portfolio/{user_id}`: `last_price` and `unrealized_pnl` of a position use the feed price when one is
+//!   available (`marked_to_market`), and the price of the asset's most recent trade otherwise.
+//! - `GET /profit-loss?cost_basis=fifo`: when the range reaches today, the open lots of the latest row of each asset
+//!   are re-marked at the feed price (`mark_to_market`).
+//!
+//! # Examples
+//!
+//! ```rust
+//! use actix_web::web::Data;
+//! use crate::services::prices::{self, PriceCache};
+//!
+//! let prices = Data::new(PriceCache::from_env());
+//! prices::spawn_refresh(prices.clone());
+//!
+//! let marks = prices.marks();
+//! println!("ETH: {:?}", marks.get("ETH"));
+//! ```
+
+use std::collections::BTreeMap;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+
+use actix_web::web::Data;
+
+use crate::config;
+use crate::db::models::trade::{Asset, DailyCostBasisPnl};
+use crate::utils::circuit_breaker::{self, CircuitBreaker, CircuitError};
+
+pub const DEFAULT_FEED_URL: &str = "https://api.coingecko.com/api/v3";
+pub const DEFAULT_REFRESH_SECS: u64 = 60;
+
+// CoinGecko identifies coins by ID rather than ticker.
+pub const COINGECKO_IDS: [(&str, &str); 5] = [
+    ("BTC", "bitcoin"),
+    ("ETH", "ethereum"),
+    ("XRP", "ripple"),
+    ("XLM", "stellar"),
+    ("DOGE", "dogecoin"),
+];
+
+pub trait PriceProvider: Send + Sync {
+    fn name(&self) -> &str;
+
+    // USD prices by asset ticker; assets the feed does not know are left out.
+    fn fetch(&self, assets: &[&str]) -> Result<BTreeMap<String, f32>, String>;
+}
+
+pub struct CoinGecko {
+    base_url: String,
+    api_key: Option<String>,
+    agent: ureq::Agent,
+}
+
+// The prices of a `/simple/price?vs_currencies=usd` response, by asset ticker.
+pub fn coingecko_prices(response: &serde_json::Value) -> BTreeMap<String, f32> {
+    COINGECKO_IDS
+        .iter()
+        .filter_map(|(asset, id)| {
+            let price = response[*id]["usd"].as_f64()? as f32;
+            (price.is_finite() && price > 0.0).then(|| (asset.to_string(), price))
+        })
+        .collect()
+}
+
+impl CoinGecko {
+    pub fn new(base_url: &str, api_key: Option<String>) -> Self {
+        CoinGecko {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            api_key,
+            agent: ureq::AgentBuilder::new().timeout(Duration::from_secs(10)).build(),
+        }
+    }
+}
+
+impl PriceProvider for CoinGecko {
+    fn name(&self) -> &str {
+        "coingecko"
+    }
+
+    fn fetch(&self, assets: &[&str]) -> Result<BTreeMap<String, f32>, String> {
+        let ids: Vec<&str> = COINGECKO_IDS.iter().filter(|(asset, _)| assets.contains(asset)).map(|(_, id)| *id).collect();
+        let url = format!("{}/simple/price", self.base_url);
+        let mut request = self.agent.get(&url).query("ids", &ids.join(",")).query("vs_currencies", "usd");
+        if let Some(api_key) = &self.api_key {
+            request = request.set("x-cg-demo-api-key", api_key);
+        }
+        let response: serde_json::Value = request
+            .call()
+            .map_err(|err| format!("Price request to {} failed: {}", url, err))?
+            .into_json()
+            .map_err(|err| format!("Price response from {} is not JSON: {}", url, err))?;
+        Ok(coingecko_prices(&response))
+    }
+}
+
+struct Quote {
+    price: f32,
+    fetched_at: Instant,
+}
+
+pub struct PriceCache {
+    provider: Box<dyn PriceProvider>,
+    breaker: Arc<CircuitBreaker>,
+    quotes: RwLock<BTreeMap<String, Quote>>,
+    refresh_interval: Duration,
+    max_age: Duration,
+}
+
+fn env_secs(name: &str, default: u64) -> u64 {
+    std::env::var(name).ok().and_then(|value| value.parse().ok()).unwrap_or(default)
+}
+
+impl PriceCache {
+    pub fn new(provider: Box<dyn PriceProvider>, refresh_interval: Duration, max_age: Duration) -> Self {
+        PriceCache {
+            breaker: circuit_breaker::register(CircuitBreaker::new(provider.name(), 3, Duration::from_secs(300))),
+            provider,
+            quotes: RwLock::new(BTreeMap::new()),
+            refresh_interval,
+            max_age,
+        }
+    }
+
+    pub fn from_env() -> Self {
+        let url = std::env::var("PRICE_FEED_URL").unwrap_or_else(|_| DEFAULT_FEED_URL.to_string());
+        let refresh_secs = env_secs("PRICE_REFRESH_SECS", DEFAULT_REFRESH_SECS).max(1);
+        let max_age_secs = env_secs("PRICE_MAX_AGE_SECS", refresh_secs * 5);
+        Self::new(
+            Box::new(CoinGecko::new(&url, config::secret("COINGECKO_API_KEY"))),
+            Duration::from_secs(refresh_secs),
+            Duration::from_secs(max_age_secs),
+        )
+    }
+
+    // Fetches every supported asset and returns how many prices were updated.
+    pub fn refresh(&self) -> Result<usize, String> {
+        let prices = match self.breaker.call(|| self.provider.fetch(&Asset::ALL)) {
+            Ok(prices) => prices,
+            Err(CircuitError::Open) => return Err(format!("{} price feed is unavailable", self.provider.name())),
+            Err(CircuitError::Inner(err)) => return Err(err),
+        };
+
+        let fetched_at = Instant::now();
+        let mut quotes = self.quotes.write().unwrap();
+        for (asset, price) in &prices {
+            quotes.insert(asset.clone(), Quote { price: *price, fetched_at });
+        }
+        Ok(prices.len())
+    }
+
+    // The prices that are not stale, by asset.
+    pub fn marks(&self) -> BTreeMap<String, f32> {
+        self.quotes
+            .read()
+            .unwrap()
+            .iter()
+            .filter(|(_, quote)| quote.fetched_at.elapsed() <= self.max_age)
+            .map(|(asset, quote)| (asset.clone(), quote.price))
+            .collect()
+    }
+}
+
+pub fn spawn_refresh(cache: Data<PriceCache>) {
+    std::thread::spawn(move || loop {
+        match cache.refresh() {
+            Ok(count) => log::debug!("Refreshed {} price(s) from {}", count, cache.provider.name()),
+            Err(err) => log::warn!("Price refresh failed: {}", err),
+        }
+        std::thread::sleep(cache.refresh_interval);
+    });
+}
+
+// Re-marks the open lots of the latest row of each asset at its feed price. Rows are ordered by date.
+pub fn mark_to_market(rows: &mut [DailyCostBasisPnl], marks: &BTreeMap<String, f32>) {
+    let mut marked: Vec<String> = Vec::new();
+    for row in rows.iter_mut().rev() {
+        if marked.contains(&row.asset) {
+            continue;
+        }
+        marked.push(row.asset.clone());
+        if let Some(mark) = marks.get(&row.asset) {
+            row.unrealized = (row.unrealized + row.open_quantity * (mark - row.mark)).round();
+            row.mark = *mark;
+        }
+    }
+}
@@ -0,0 +1,123 @@
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use serde_json::json;
+
+use crate::db::models::position::Position;
+use crate::db::models::trade::DailyCostBasisPnl;
+use super::portfolio::Portfolio;
+use super::fixtures;
+use super::prices::{coingecko_prices, mark_to_market, CoinGecko, PriceCache, PriceProvider};
+
+struct FixedPrices(Result<Vec<(&'static str, f32)>, &'static str>);
+
+impl PriceProvider for FixedPrices {
+    fn name(&self) -> &str {
+        "fixed"
+    }
+
+    fn fetch(&self, _assets: &[&str]) -> Result<BTreeMap<String, f32>, String> {
+        self.0.clone().map(|prices| prices.into_iter().map(|(asset, price)| (asset.to_string(), price)).collect()).map_err(str::to_string)
+    }
+}
+
+fn row(date: &str, asset: &str, unrealized: f32, open_quantity: f32, mark: f32) -> DailyCostBasisPnl {
+    DailyCostBasisPnl { date: date.to_string(), asset: asset.to_string(), realized: 0.0, unrealized, open_quantity, mark }
+}
+
+#[test]
+fn parses_coingecko_response() {
+    let response = json!({
+        "bitcoin": {"usd": 64000.5},
+        "ethereum": {"usd": 3100},
+        "ripple": {"usd": 0},
+        "dogecoin": {"eur": 0.1}
+    });
+
+    let prices = coingecko_prices(&response);
+
+    assert_eq!(prices, BTreeMap::from([("BTC".to_string(), 64000.5), ("ETH".to_string(), 3100.0)]));
+}
+
+#[test]
+fn refresh_caches_prices_until_stale() {
+    let cache = PriceCache::new(Box::new(FixedPrices(Ok(vec![("ETH", 2000.0)]))), Duration::from_secs(60), Duration::from_secs(300));
+    assert!(cache.marks().is_empty());
+
+    assert_eq!(cache.refresh(), Ok(1));
+    assert_eq!(cache.marks().get("ETH"), Some(&2000.0));
+
+    let stale = PriceCache::new(Box::new(FixedPrices(Ok(vec![("ETH", 2000.0)]))), Duration::from_secs(60), Duration::ZERO);
+    stale.refresh().unwrap();
+    std::thread::sleep(Duration::from_millis(5));
+    assert!(stale.marks().is_empty());
+}
+
+#[test]
+fn failed_refresh_keeps_cache_empty() {
+    let cache = PriceCache::new(Box::new(FixedPrices(Err("timeout"))), Duration::from_secs(60), Duration::from_secs(300));
+
+    assert_eq!(cache.refresh(), Err("timeout".to_string()));
+    assert!(cache.marks().is_empty());
+}
+
+#[test]
+fn marks_latest_row_of_each_asset() {
+    let mut rows = vec![
+        row("2024-01-01", "BTC", 100.0, 1.0, 30100.0),
+        row("2024-01-01", "ETH", 50.0, 2.0, 2025.0),
+        row("2024-01-02", "BTC", 200.0, 1.0, 30200.0),
+    ];
+    let marks = BTreeMap::from([("BTC".to_string(), 31000.0)]);
+
+    mark_to_market(&mut rows, &marks);
+
+    assert_eq!((rows[0].unrealized, rows[0].mark), (100.0, 30100.0));
+    assert_eq!((rows[1].unrealized, rows[1].mark), (50.0, 2025.0));
+    assert_eq!((rows[2].unrealized, rows[2].mark), (1000.0, 31000.0));
+}
+
+#[test]
+fn portfolio_uses_feed_price() {
+    let position = |asset: &str| Position {
+        user_id: "user".to_string(),
+        asset: asset.to_string(),
+        quantity: 2.0,
+        average_entry_price: 100.0,
+        realized_pnl: 0.0,
+        last_price: 110.0,
+        updated_at: chrono::Local::now().naive_local(),
+    };
+    let marks = BTreeMap::from([("ETH".to_string(), 150.0)]);
+
+    let portfolio = Portfolio::new("user".to_string(), vec![position("BTC"), position("ETH")], &marks);
+
+    assert_eq!((portfolio.positions[0].last_price, portfolio.positions[0].unrealized_pnl, portfolio.positions[0].marked_to_market), (110.0, 20.0, false));
+    assert_eq!((portfolio.positions[1].last_price, portfolio.positions[1].unrealized_pnl, portfolio.positions[1].marked_to_market), (150.0, 100.0, true));
+    assert_eq!(portfolio.unrealized_pnl, 120.0);
+}
+
+#[actix_web::test]
+async fn refreshes_from_the_recorded_feed() {
+    let server = fixtures::price_feed().await;
+    let cache = PriceCache::new(Box::new(CoinGecko::new(&server.uri(), Some("demo-key".to_string()))), Duration::from_secs(60), Duration::from_secs(300));
+
+    assert_eq!(cache.refresh().unwrap(), 5);
+    assert_eq!(cache.marks()["BTC"], 26089.51);
+    assert_eq!(cache.marks()["XLM"], 0.124719);
+
+    let requests = server.received_requests().await.unwrap();
+    assert_eq!(requests.len(), 1);
+    assert_eq!(requests[0].headers.get("x-cg-demo-api-key").unwrap(), "demo-key");
+    let query: BTreeMap<_, _> = requests[0].url.query_pairs().into_owned().collect();
+    assert_eq!(query["vs_currencies"], "usd");
+    assert_eq!(query["ids"], "bitcoin,ethereum,ripple,stellar,dogecoin");
+}
+
+#[actix_web::test]
+async fn rate_limited_feed_is_an_error() {
+    let server = fixtures::failing(429, "coingecko/rate_limited").await;
+    let error = CoinGecko::new(&server.uri(), None).fetch(&["BTC"]).unwrap_err();
+
+    assert!(error.contains("429"), "{}", error);
+}
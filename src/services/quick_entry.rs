@@ -0,0 +1,140 @@
+//! This module parses compact, keyboard-style trade commands into trade forms.
+//!
+//! A command has the shape `<buy|sell> [market|limit] <quantity> <ASSET> [@<price>] [on <Chain>] [#tag ...]`,
+//! for example `buy 1.5 ETH @1850 on Arbitrum #scalp`. Keywords, assets and chains are case-insensitive.
+//! A price makes the trade a limit order unless `market` is given explicitly; the chain defaults to Ethereum.
+//!
+//! `parse` returns a `QuickEntry` describing how the command was interpreted, which `to_trade_form` turns into a
+//! regular `TradeForm` that still goes through `TradeForm::validate`.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use crate::services::quick_entry::parse;
+//!
+//! let entry = parse("sell 2 btc @42000 on optimism #swing").unwrap();
+//! assert_eq!(entry.trade_type, "LimitSell");
+//! assert_eq!(entry.chain, "Optimism");
+//! assert_eq!(entry.tags, vec!["swing"]);
+//! ```
+
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::db::models::trade::{Asset, Chain};
+use crate::services::trade::TradeForm;
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, ToSchema)]
+pub struct QuickEntry {
+    pub trade_type: String,
+    pub quantity: f32,
+    pub asset: String,
+    pub price: Option<f32>,
+    pub chain: String,
+    pub tags: Vec<String>,
+}
+
+const CHAINS: [&str; 4] = ["Ethereum", "Arbitrum", "Optimism", "Polygon"];
+
+fn parse_number(token: &str, field: &str) -> Result<f32, String> {
+    match token.parse::<f32>() {
+        Ok(value) if value.is_finite() && value > 0.0 => Ok(value),
+        _ => Err(format!("Invalid {}: {}", field, token)),
+    }
+}
+
+pub(crate) fn normalize_chain(token: &str) -> Result<String, String> {
+    CHAINS
+        .iter()
+        .find(|chain| chain.eq_ignore_ascii_case(token))
+        .map(|chain| chain.to_string())
+        .filter(|chain| Chain::is_valid(chain))
+        .ok_or_else(|| format!("Unknown chain: {}", token))
+}
+
+pub fn parse(command: &str) -> Result<QuickEntry, String> {
+    let mut tokens = command.split_whitespace().peekable();
+
+    let side = match tokens.next().map(|token| token.to_ascii_lowercase()) {
+        Some(side) if side == "buy" => "Buy",
+        Some(side) if side == "sell" => "Sell",
+        _ => return Err("Command must start with buy or sell".to_string()),
+    };
+
+    let mut order = None;
+    if let Some(token) = tokens.peek() {
+        match token.to_ascii_lowercase().as_str() {
+            "market" => order = Some("Market"),
+            "limit" => order = Some("Limit"),
+            _ => (),
+        }
+    }
+    if order.is_some() {
+        tokens.next();
+    }
+
+    let quantity = parse_number(tokens.next().ok_or("Missing quantity")?, "quantity")?;
+
+    let asset = tokens.next().ok_or("Missing asset")?.to_ascii_uppercase();
+    if !Asset::is_valid(&asset) {
+        return Err(format!("Unknown asset: {}", asset));
+    }
+
+    let mut price = None;
+    let mut chain = "Ethereum".to_string();
+    let mut tags = Vec::new();
+    while let Some(token) = tokens.next() {
+        if let Some(value) = token.strip_prefix('@') {
+            let value = if value.is_empty() { tokens.next().ok_or("Missing price")? } else { value };
+            price = Some(parse_number(value, "price")?);
+        } else if token.eq_ignore_ascii_case("at") {
+            price = Some(parse_number(tokens.next().ok_or("Missing price")?, "price")?);
+        } else if token.eq_ignore_ascii_case("on") {
+            chain = normalize_chain(tokens.next().ok_or("Missing chain")?)?;
+        } else if let Some(tag) = token.strip_prefix('#') {
+            if !tag.is_empty() {
+                tags.push(tag.to_string());
+            }
+        } else {
+            return Err(format!("Unexpected token: {}", token));
+        }
+    }
+
+    let order = match (order, price) {
+        (Some("Limit"), None) => return Err("Limit orders require a price".to_string()),
+        (Some(order), _) => order,
+        (None, Some(_)) => "Limit",
+        (None, None) => "Market",
+    };
+
+    Ok(QuickEntry {
+        trade_type: format!("{}{}", order, side),
+        quantity,
+        asset,
+        price,
+        chain,
+        tags,
+    })
+}
+
+impl QuickEntry {
+    pub fn to_trade_form(&self, user_id: String, wallet_id: String) -> TradeForm {
+        TradeForm {
+            user_id,
+            wallet_id,
+            amount: self.quantity,
+            chain: self.chain.clone(),
+            trade_type: self.trade_type.clone(),
+            asset: self.asset.clone(),
+            before_price: None,
+            execution_price: self.price,
+            final_price: None,
+            traded_amount: Some(self.quantity),
+            timestamp: None,
+            entered_by: None,
+            tx_hash: None,
+            source: None,
+            metadata: None,
+        }
+    }
+}
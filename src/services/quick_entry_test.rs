@@ -0,0 +1,52 @@
+use super::quick_entry::parse;
+
+#[test]
+fn parses_limit_buy_with_chain_and_tags() {
+    let entry = parse("buy 1.5 ETH @1850 on Arbitrum #scalp").unwrap();
+
+    assert_eq!(entry.trade_type, "LimitBuy");
+    assert_eq!(entry.quantity, 1.5);
+    assert_eq!(entry.asset, "ETH");
+    assert_eq!(entry.price, Some(1850.0));
+    assert_eq!(entry.chain, "Arbitrum");
+    assert_eq!(entry.tags, vec!["scalp"]);
+}
+
+#[test]
+fn defaults_to_market_order_on_ethereum() {
+    let entry = parse("SELL 2 btc").unwrap();
+
+    assert_eq!(entry.trade_type, "MarketSell");
+    assert_eq!(entry.asset, "BTC");
+    assert_eq!(entry.price, None);
+    assert_eq!(entry.chain, "Ethereum");
+}
+
+#[test]
+fn accepts_explicit_order_type_and_spaced_price() {
+    let entry = parse("buy market 3 xrp @ 0.5 on polygon").unwrap();
+
+    assert_eq!(entry.trade_type, "MarketBuy");
+    assert_eq!(entry.price, Some(0.5));
+    assert_eq!(entry.chain, "Polygon");
+}
+
+#[test]
+fn rejects_invalid_commands() {
+    assert!(parse("").is_err());
+    assert!(parse("hold 1 ETH").is_err());
+    assert!(parse("buy -1 ETH").is_err());
+    assert!(parse("buy 1 SHIB").is_err());
+    assert!(parse("buy 1 ETH on Solana").is_err());
+    assert!(parse("buy limit 1 ETH").is_err());
+    assert!(parse("buy 1 ETH tomorrow").is_err());
+}
+
+#[test]
+fn converts_to_valid_trade_form() {
+    let form = parse("buy 1.5 ETH @1850").unwrap().to_trade_form("user".to_string(), "wallet".to_string());
+
+    assert!(form.validate().is_ok());
+    assert_eq!(form.execution_price, Some(1850.0));
+    assert_eq!(form.traded_amount, Some(1.5));
+}
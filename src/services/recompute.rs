@@ -0,0 +1,107 @@
+//! This module defines the admin API for recomputing stored values after a formula change.
+//!
+//! The provided functions include:
+//!
+//! - `start_recompute`: Queues a recompute job and runs it in the background on the blocking thread pool
+//!   (`POST /admin/recompute?what=fees|snapshots|positions&range=`). A job that fails is logged and marked `Failed`. `range` limits the job to trades created within
+//!   it: a relative range such as `last_30d` or `<from>..<to>` with `YYYY-MM-DD` dates (see `utils::date::parse_span`),
+//!   in the timezone of the optional `tz` parameter. Without a range every trade is covered. Responds `202` with the
+//!   job; the request is recorded in the audit log.
+//! - `list_jobs`: Lists the most recent jobs (`GET /admin/recompute`).
+//! - `get_job`: Returns the status and `processed`/`total` progress of a job (`GET /admin/recompute/{job_id}`).
+//!
+//! See `db::models::recompute` for what each kind of job re-derives.
+//!
+//! # Note
+//! All routes are wrapped with the `JwtGuard` middleware and restricted to admins (`ADMIN_USER_IDS`).
+
+use actix_web::{web, HttpRequest, HttpResponse, ResponseError};
+use serde::{Deserialize, Serialize};
+
+use crate::db::models::recompute::RecomputeJob;
+use crate::db::{self, DbPool};
+use crate::error::AppError;
+use crate::middleware::jwt_guard::JwtGuard;
+use crate::services::jwt;
+use crate::utils;
+
+const RECENT_JOBS: i64 = 50;
+
+#[derive(Serialize, Deserialize)]
+pub struct RecomputeQuery {
+    pub what: String,
+    pub range: Option<String>,
+    pub tz: Option<String>,
+}
+
+pub async fn start_recompute(req: HttpRequest, pool: web::Data<DbPool>, params: web::Query<RecomputeQuery>) -> HttpResponse {
+    let admin_id = match jwt::require_admin(&req) {
+        Ok(admin_id) => admin_id,
+        Err(err) => return err.error_response(),
+    };
+
+    let range = match params.range.as_deref().filter(|range| !range.is_empty()) {
+        Some(range) => match utils::date::parse_span(range, params.tz.as_deref()) {
+            Ok(range) => Some(range),
+            Err(err) => return AppError::Validation(format!("Error: {}", err)).error_response(),
+        },
+        None => None,
+    };
+
+    let what = params.into_inner().what;
+    let job = match db::run(&pool, move |conn| Ok(RecomputeJob::create(conn, what, range, admin_id)?)).await {
+        Ok((Some(job), None)) => job,
+        Ok((_, errors)) => return AppError::Validation(errors.unwrap_or_default()).error_response(),
+        Err(err) => return err.error_response(),
+    };
+
+    let pool = pool.get_ref().clone();
+    let job_id = job.id.clone();
+    actix_web::rt::spawn(async move {
+        let id = job_id.clone();
+        match db::run(&pool, move |conn| Ok(RecomputeJob::run(conn, id)?)).await {
+            Ok(_) => log::info!("Recompute job {} completed", job_id),
+            Err(err) => {
+                log::error!("Recompute job {} failed: {}", job_id, err);
+                let (id, error) = (job_id.clone(), err.to_string());
+                if let Err(err) = db::run(&pool, move |conn| Ok(RecomputeJob::fail(conn, &id, error)?)).await {
+                    log::error!("Recompute job {} could not be marked as failed: {}", job_id, err);
+                }
+            }
+        }
+    });
+
+    HttpResponse::Accepted().json(job)
+}
+
+pub async fn list_jobs(req: HttpRequest, pool: web::Data<DbPool>) -> HttpResponse {
+    if let Err(err) = jwt::require_admin(&req) {
+        return err.error_response();
+    }
+
+    match db::run(&pool, move |conn| Ok(RecomputeJob::list(conn, RECENT_JOBS)?)).await {
+        Ok(jobs) => HttpResponse::Ok().json(jobs),
+        Err(err) => err.error_response(),
+    }
+}
+
+pub async fn get_job(req: HttpRequest, pool: web::Data<DbPool>, job_id: web::Path<String>) -> HttpResponse {
+    if let Err(err) = jwt::require_admin(&req) {
+        return err.error_response();
+    }
+
+    match db::run(&pool, move |conn| Ok(RecomputeJob::find_by_id(conn, job_id.into_inner())?)).await {
+        Ok(Some(job)) => HttpResponse::Ok().json(job),
+        Ok(None) => AppError::NotFound("Recompute job not found".to_string()).error_response(),
+        Err(err) => err.error_response(),
+    }
+}
+
+pub fn init_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::resource("/admin/recompute")
+            .route(web::post().to(start_recompute).wrap(JwtGuard))
+            .route(web::get().to(list_jobs).wrap(JwtGuard)),
+    )
+    .service(web::resource("/admin/recompute/{job_id}").route(web::get().to(get_job).wrap(JwtGuard)));
+}
@@ -0,0 +1,44 @@
+//! This module defines the routes served by a read-only replica.
+//!
+//! Dashboards mostly read analytics, which are expensive to compute but never write. With `server.read_only` set (see
+//! `config::settings`), an instance serves only the routes registered by `init_routes` below, from a database opened
+//! read-only (see `db::read_only_url`). Replicas can then be scaled out behind a load balancer that sends these paths
+//! to them, while a single writer instance serves every route, writes included.
+//!
+//! A replica serves:
+//!
+//! - the trade analytics: `/profit-loss`, `/cumulative-fees`, `/cumulative-fees/breakdown`, `/slippage`,
+//!   `/execution-quality` and `/trade-clusters` (`trade::init_analytics_routes`);
+//! - `/analytics/risk`, `/analytics/stats`, `/analytics/leaderboard` and `/stats/exposure` (`analytics`);
+//! - `/admin/analytics/activity` (`admin_analytics`);
+//! - `/summary`, `/portfolio/{user_id}`, `/metadata` and `/metadata/tx-link`;
+//! - `/version`, to check which build a replica runs.
+//!
+//! No write route is registered, so any other request is answered `404`. Tokens are checked with the shared
+//! `JWT_SECRET` like on the writer, but logins and token refreshes have to go to the writer. A replica does not replay
+//! the trade journal or deliver queued emails at startup, since both write; it still refreshes the price feed, which
+//! is kept in memory. `/metrics/business` is not served: its counters are kept in memory by the instance that records
+//! the trades.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use actix_web::App;
+//! use crate::services::replica;
+//!
+//! let app = App::new().app_data(Data::new(read_only_pool)).configure(replica::init_routes);
+//! ```
+
+use actix_web::web;
+
+use crate::services::{admin_analytics, analytics, metadata, portfolio, summary, trade, version};
+
+pub fn init_routes(cfg: &mut web::ServiceConfig) {
+    cfg.configure(trade::init_analytics_routes)
+        .configure(analytics::init_routes)
+        .configure(admin_analytics::init_routes)
+        .configure(summary::init_routes)
+        .configure(portfolio::init_routes)
+        .configure(metadata::init_routes)
+        .configure(version::init_routes);
+}
@@ -0,0 +1,31 @@
+use actix_web::http::header::AUTHORIZATION;
+use actix_web::http::StatusCode;
+use actix_web::test::{call_service, init_service, TestRequest};
+use actix_web::{web, App};
+
+use crate::db::fixtures::test_pool;
+use super::jwt::create_jwt;
+use super::replica::init_routes;
+
+#[actix_web::test]
+async fn replicas_serve_analytics_but_no_write_routes() {
+    std::env::set_var("JWT_SECRET", "test_secret");
+    let app = init_service(App::new().app_data(web::Data::new(test_pool())).configure(init_routes)).await;
+    let token = create_jwt("reader".to_string()).unwrap();
+
+    for uri in ["/version", "/analytics/leaderboard?period=7d", "/cumulative-fees?trader_id=reader&start_date=last_7d"] {
+        let req = TestRequest::get().uri(uri).insert_header((AUTHORIZATION, token.clone())).to_request();
+        assert_eq!(call_service(&app, req).await.status(), StatusCode::OK, "{}", uri);
+    }
+
+    let writes = [
+        TestRequest::post().uri("/trade").set_json(serde_json::json!({})),
+        TestRequest::put().uri("/user/reader/settings").set_json(serde_json::json!({"confirm_new_devices": true})),
+        TestRequest::post().uri("/admin/price-fixings").set_json(serde_json::json!({})),
+        TestRequest::delete().uri("/trade/any"),
+    ];
+    for request in writes {
+        let req = request.insert_header((AUTHORIZATION, token.clone())).to_request();
+        assert_eq!(call_service(&app, req).await.status(), StatusCode::NOT_FOUND);
+    }
+}
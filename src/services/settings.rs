@@ -0,0 +1,86 @@
+//! This module defines the endpoints for a user's own settings and login devices.
+//!
+//! The provided functions include:
+//!
+//! - `get_settings`: Returns the user's settings, or the defaults if they never changed them
+//!   (`GET /user/{user_id}/settings`).
+//! - `update_settings`: Replaces the user's settings (`PUT /user/{user_id}/settings` with
+//!   `{"confirm_new_devices": true, "hide_from_leaderboard": false}`). With `confirm_new_devices`, logins from unseen
+//!   devices must be confirmed from the account's email before tokens are issued (see `services::user::login`). With
+//!   `hide_from_leaderboard`, the user no longer appears in `GET /analytics/leaderboard`; it is off when omitted.
+//! - `list_devices`: Lists the devices the user logged in from, most recently seen first (`GET /user/{user_id}/devices`).
+//!   Devices without `confirmed_at` are awaiting confirmation.
+//!
+//! # Note
+//! All routes are wrapped with the `JwtGuard` middleware and only serve the user themselves.
+
+use actix_web::{web, HttpRequest, HttpResponse, ResponseError};
+use serde::{Deserialize, Serialize};
+
+use crate::db::models::device::KnownDevice;
+use crate::db::models::user_settings::UserSettings;
+use crate::db::{self, DbPool};
+use crate::error::AppError;
+use crate::middleware::jwt_guard::JwtGuard;
+use crate::services::jwt;
+
+#[derive(Serialize, Deserialize)]
+pub struct SettingsForm {
+    pub confirm_new_devices: bool,
+    #[serde(default)]
+    pub hide_from_leaderboard: bool,
+}
+
+fn ensure_self(req: &HttpRequest, user_id: &str) -> Result<(), AppError> {
+    match jwt::user_id(req) {
+        Some(actor_id) if actor_id != user_id => Err(AppError::Forbidden("Only the user can manage their settings".to_string())),
+        _ => Ok(()),
+    }
+}
+
+pub async fn get_settings(req: HttpRequest, pool: web::Data<DbPool>, user_id: web::Path<String>) -> HttpResponse {
+    let user_id = user_id.into_inner();
+    if let Err(err) = ensure_self(&req, &user_id) {
+        return err.error_response();
+    }
+
+    match db::run(&pool, move |conn| Ok(UserSettings::for_user(conn, user_id)?)).await {
+        Ok(settings) => HttpResponse::Ok().json(settings),
+        Err(err) => err.error_response(),
+    }
+}
+
+pub async fn update_settings(req: HttpRequest, pool: web::Data<DbPool>, user_id: web::Path<String>, form: web::Json<SettingsForm>) -> HttpResponse {
+    let user_id = user_id.into_inner();
+    if let Err(err) = ensure_self(&req, &user_id) {
+        return err.error_response();
+    }
+
+    let form = form.into_inner();
+    match db::run(&pool, move |conn| Ok(UserSettings::update(conn, user_id, form.confirm_new_devices, form.hide_from_leaderboard)?)).await {
+        Ok((Some(settings), None)) => HttpResponse::Ok().json(settings),
+        Ok((_, errors)) => AppError::Validation(errors.unwrap_or_default()).error_response(),
+        Err(err) => err.error_response(),
+    }
+}
+
+pub async fn list_devices(req: HttpRequest, pool: web::Data<DbPool>, user_id: web::Path<String>) -> HttpResponse {
+    let user_id = user_id.into_inner();
+    if let Err(err) = ensure_self(&req, &user_id) {
+        return err.error_response();
+    }
+
+    match db::run(&pool, move |conn| Ok(KnownDevice::list_for_user(conn, user_id)?)).await {
+        Ok(devices) => HttpResponse::Ok().json(devices),
+        Err(err) => err.error_response(),
+    }
+}
+
+pub fn init_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::resource("/user/{user_id}/settings")
+            .route(web::get().to(get_settings).wrap(JwtGuard))
+            .route(web::put().to(update_settings).wrap(JwtGuard)),
+    );
+    cfg.service(web::resource("/user/{user_id}/devices").route(web::get().to(list_devices).wrap(JwtGuard)));
+}
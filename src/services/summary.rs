@@ -0,0 +1,54 @@
+//! This module defines the mobile home-screen summary endpoint.
+//!
+//! `GET /summary?trader_id=` returns `db::models::summary::Summary` in a single round trip. "Today" is resolved with
+//! `utils::date::parse_range` in the timezone given by the optional `tz` parameter (UTC by default). A summary can be
+//! read by whoever can read the trader's trades: the trader, their delegates, their advisors and admins (see
+//! `services::trade::ensure_can_view`).
+//!
+//! # Note
+//! The route is wrapped with the `JwtGuard` middleware for secure access.
+
+use actix_web::{web, HttpRequest, HttpResponse, ResponseError};
+use serde::{Deserialize, Serialize};
+
+use crate::db::models::summary::Summary;
+use crate::db::{self, DbPool};
+use crate::error::AppError;
+use crate::middleware::{jwt_guard::JwtGuard, load_shed::LoadShed};
+use crate::services::jwt;
+use crate::services::trade::ensure_can_view;
+use crate::utils;
+
+#[derive(Serialize, Deserialize)]
+pub struct SummaryQuery {
+    pub trader_id: String,
+    pub tz: Option<String>,
+}
+
+pub async fn summary(req: HttpRequest, pool: web::Data<DbPool>, params: web::Query<SummaryQuery>) -> HttpResponse {
+    if params.trader_id.is_empty() {
+        return AppError::Validation("Error: Trader ID is required".to_string()).error_response();
+    }
+
+    let (start_date, end_date) = match utils::date::parse_range("today", "", params.tz.as_deref()) {
+        Ok(range) => range,
+        Err(err) => return AppError::Validation(format!("Error: {}", err)).error_response(),
+    };
+
+    let (caller_id, trader_id) = (jwt::user_id(&req), params.into_inner().trader_id);
+    let result = db::run(&pool, move |conn| {
+        ensure_can_view(conn, caller_id.as_deref(), &trader_id)?;
+        Ok(Summary::for_user(conn, trader_id, start_date, end_date)?)
+    });
+    match result.await {
+        Ok(summary) => HttpResponse::Ok().json(summary),
+        Err(err) => err.error_response(),
+    }
+}
+
+pub fn init_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::resource("/summary")
+            .route(web::get().to(summary).wrap(JwtGuard).wrap(LoadShed::high_priority())),
+    );
+}
@@ -0,0 +1,55 @@
+use actix_web::http::header::AUTHORIZATION;
+use actix_web::http::StatusCode;
+use actix_web::test::{call_service, init_service, read_body_json, TestRequest};
+use actix_web::{web, App};
+
+use crate::db::fixtures::{test_pool, user};
+use crate::db::models::advisor::AdvisorClient;
+use crate::db::models::delegation::{DelegationScope, TradeDelegation};
+use super::jwt::create_jwt;
+use super::summary::init_routes;
+
+#[actix_web::test]
+async fn summaries_are_read_by_their_owner() {
+    std::env::set_var("JWT_SECRET", "test_secret");
+    let pool = test_pool();
+    let owner = user(&mut pool.get().unwrap(), "owner");
+    let app = init_service(App::new().app_data(web::Data::new(pool.clone())).configure(init_routes)).await;
+    let uri = format!("/summary?trader_id={}", owner.id);
+
+    let token = create_jwt(owner.id.clone()).unwrap();
+    let response = call_service(&app, TestRequest::get().uri(&uri).insert_header((AUTHORIZATION, token)).to_request()).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let body: serde_json::Value = read_body_json(response).await;
+    assert_eq!(body["wallets"][0]["wallet_id"], owner.wallet_id);
+
+    let stranger = create_jwt("stranger".to_string()).unwrap();
+    let response = call_service(&app, TestRequest::get().uri(&uri).insert_header((AUTHORIZATION, stranger)).to_request()).await;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+}
+
+#[actix_web::test]
+async fn summaries_are_read_by_delegates_and_advisors() {
+    std::env::set_var("JWT_SECRET", "test_secret");
+    let pool = test_pool();
+    let conn = &mut pool.get().unwrap();
+    let (owner, delegate, advisor) = (user(conn, "owner"), user(conn, "delegate"), user(conn, "advisor"));
+    TradeDelegation::grant(conn, owner.id.clone(), delegate.id.clone(), DelegationScope::CREATE.to_string()).unwrap();
+    AdvisorClient::grant(conn, advisor.id.clone(), owner.id.clone()).unwrap();
+    let app = init_service(App::new().app_data(web::Data::new(pool.clone())).configure(init_routes)).await;
+    let uri = format!("/summary?trader_id={}", owner.id);
+
+    for reader in [delegate.id, advisor.id] {
+        let token = create_jwt(reader).unwrap();
+        let response = call_service(&app, TestRequest::get().uri(&uri).insert_header((AUTHORIZATION, token)).to_request()).await;
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    let token = create_jwt(owner.id.clone()).unwrap();
+    for uri in ["/summary?trader_id=".to_string(), format!("{}&tz=Mars/Olympus", uri)] {
+        let response = call_service(&app, TestRequest::get().uri(&uri).insert_header((AUTHORIZATION, token.clone())).to_request()).await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let body: serde_json::Value = read_body_json(response).await;
+        assert_eq!(body["code"], "validation_error");
+    }
+}
@@ -3,20 +3,83 @@
 //!
 //! The provided functions include:
 //!
-//! - `create_trade`: Handles the creation of a new trade entry in the database.
-//! - `index`: Retrieves a list of all trades from the database.
+//! - `TradeForm::validate`: Rejects trade forms with non-finite or negative values, an unknown chain, trade type or
+//!   asset, out-of-range timestamps and malformed transaction hashes.
+//! - `create_trade`: Journals the request and handles the creation of a new trade entry in the database. The wallet
+//!   must be one of the trade user's wallets (see `db::models::user_wallet`), or the trade is rejected with `400`. With
+//!   `verify_holdings=true`, a sell is rejected with `409` when the wallet could not have held enough of the asset at
+//!   the trade's timestamp, or when backdating it would leave a later sell short (see `Trade::holdings_conflict`).
+//! - `quick_trade`: Parses a compact text command (e.g. `buy 1.5 ETH @1850 on Arbitrum`) into a trade, previewing it
+//!   in dry-run mode (the default) or creating it when `dry_run` is `false`.
+//! - `index`: Retrieves a page of trades (`limit`/`offset`, default 100, at most 1000), filtered by `user_id`, `asset`,
+//!   `chain`, `trade_type`, `source`, a `start_date`/`end_date` range and `metadata.{key}=value` pairs (e.g.
+//!   `?metadata.exchange_order_id=8123`), which must all match. Trades are sorted newest first, or by `sort`
+//!   (`created_at`, `amount`, `asset` or `pnl`, the trade's own P&L) in `order` (`asc` or `desc`). The total number of
+//!   matches is sent in `X-Total-Count`. With `cursor`, pages are keyset pages in a `{"data", "next_cursor"}` envelope,
+//!   always newest first, and the matches are not counted (see `services::pagination`).
+//! - `export`: Streams every trade of the caller as CSV, oldest first (`GET /trade/export?format=csv`), as an
+//!   attachment. Trades are read and sent `EXPORT_PAGE_SIZE` at a time, so long histories are never held in memory.
 //! - `get`: Retrieves a specific trade entry by its ID.
-//! - `update`: Updates a specific trade entry with new information.
-//! - `delete`: Deletes a specific trade entry from the database.
-//! - `profit_loss`: Calculates and retrieves profit and loss data for trades within a specified date range.
+//! - `update`: Updates a specific trade entry with new information (`PUT /trade/{trade_id}` with a full `TradeForm`;
+//!   omitted prices are stored as `0`). The trade keeps its user and wallet; `user_id` and `wallet_id` in the form are
+//!   not applied.
+//! - `patch`: Updates only the fields present in a `TradePatch` (`PATCH /trade/{trade_id}`); the others, and the fees
+//!   the trade was charged, keep their values. A chain, trade type or asset that is given must be a known one.
+//! - `delete`: Soft-deletes a specific trade entry: it disappears from every listing and aggregate, but the row is kept.
+//! - `history`: Lists every create, update and delete of a trade, oldest first, with the acting user and the trade's
+//!   previous values (`GET /trade/{trade_id}/history`). Deleted trades keep their history, readable like the trade.
+//! - `profit_loss`: Calculates and retrieves profit and loss data for trades within a specified date range. With
+//!   `cost_basis=fifo`, returns realized P&L from closed lots and unrealized P&L from open lots separately, per day
+//!   and asset (see `db::models::trade::CostBasis`); when the range reaches today, open lots are marked at the price
+//!   feed (see `services::prices`). With `breakdown=asset`, each day also lists the profit and loss of every asset
+//!   traded that day in `assets`; CSV responses have one row per day and asset instead.
 //! - `cumulative_fee`: Calculates and retrieves cumulative fee data for trades within a specified date range.
+//! - `fee_breakdown`: Splits those fees into execution and transaction fees per asset, chain and trade type, most
+//!   expensive group first (`GET /cumulative-fees/breakdown`). `overridden_trades` counts the trades of a group charged
+//!   with the trader's negotiated rates (see `db::models::fee_override`) instead of the fee schedule.
 //! - `slippage`: Retrieves slippage data for trades within a specified date range.
+//! - `execution_quality`: Retrieves the per-asset slippage distribution (p50/p90/p99) within a specified date range.
+//! - `trade_clusters`: Groups a trader's trades into `clusters` groups (default 4, at most 10) by size, outcome and
+//!   time of day using k-means, and describes each group (e.g. "large losing late-night trades"). JSON only.
+//!
+//! The analytics endpoints respond in CSV instead of JSON when the request sends `Accept: text/csv` or `?format=csv`,
+//! as an attachment named after the endpoint (e.g. `profit-loss.csv`).
+//! Their `start_date`/`end_date` parameters also accept relative ranges (`last_7d`, `mtd`, `ytd`, `prev_month`),
+//! resolved by `utils::date::parse_range` in the timezone given by the optional `tz` parameter (e.g. `Europe/Berlin`).
+//! With `exclude_outliers=N`, trades more than N standard deviations from the mean P&L or slippage are left out of
+//! the aggregate, and their IDs are listed (comma-separated) in the `X-Excluded-Trades` response header. With
+//! `source=...` (except for `trade_clusters`), only trades from that entry path are aggregated.
+//!
 //! - `init_routes`: Initializes routes for handling trade-related HTTP requests.
 //!
+//! Trades may carry `metadata`, a map of external references with string values set by importers and API clients
+//! (see `db::models::trade::TradeMetadata`). `update` keeps the trade's metadata when the form has none, and `patch`
+//! merges its keys into it, removing those given an empty value.
+//!
+//! Trades may carry the `tx_hash` of their on-chain transaction. Trade responses then include an `explorer_url` built
+//! from the chain's transaction template (see `services::metadata` and the admin chain registry). With
+//! `?include=summary`, `index` and `get` also return each trade as a sentence in `summary` (see `services::narration`),
+//! in the language of the `locale` parameter or the `Accept-Language` header.
+//!
+//! A user's trades, listings and analytics can be read by the user, their delegates (any scope), their advisors (see
+//! `db::models::advisor`) and admins; `index` without `user_id` lists the caller's own trades, or every trade for
+//! admins. Creating, updating and deleting trades is allowed to the trade's owner and to users holding a delegation
+//! for that scope (see `db::models::delegation`). Admins (`ADMIN_USER_IDS`) may do all of these. The caller is the
+//! user the `JwtGuard` middleware authenticated; only signed links (see `utils::signed_url`) read without one. Trades
+//! created by a delegate record them in `entered_by`, trades created by the owner record the owner.
+//!
+//! Each entry path stamps the trade's `source` (see `db::models::trade::TradeSource`): trades created through
+//! `create_trade` and `quick_trade` are `manual` whatever the request says. Listings and analytics accept either a
+//! full source (`import:binance`) or a kind (`import`).
+//!
+//! Errors are returned as `crate::error::AppError` JSON bodies (`{"code": ..., "message": ...}`): invalid input is a
+//! `400`, acting on another user's trade without permission a `403`, a missing trade a `404` and database failures a
+//! `500` (or `503` while the database is busy).
+//!
 //! # Examples
 //!
 //! ```
-//! use actix_web::{web, HttpResponse};
+//! use actix_web::{web, HttpRequest, HttpResponse, ResponseError};
 //! use serde::{Deserialize, Serialize};
 //!
 //! // ... imports ...
@@ -50,17 +113,25 @@
 //! # Note
 //!
 //! Some of the functions in this module require authentication through JSON Web Tokens (JWT),
-//! and they are wrapped with the `JwtGuard` middleware for secure access.
+//! and they are wrapped with the `JwtGuard` middleware for secure access. Analytics routes are tagged
+//! as low priority with the `LoadShed` middleware so they are shed first when the server is overloaded.
+
+use std::collections::{BTreeMap, HashMap};
 
-use actix_web::{web, HttpResponse};
+use actix_web::http::header::{HeaderName, HeaderValue, ACCEPT_LANGUAGE};
+use actix_web::{web, HttpRequest, HttpResponse, ResponseError};
+use diesel::SqliteConnection;
 use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
 
 use crate::{
-    db::{models::trade::Trade, DbPool},
-    middleware::jwt_guard::JwtGuard, utils,
+    db::{self, error::DbError, models::{advisor::AdvisorClient, delegation::{DelegationScope, TradeDelegation}, fee_schedule::FeeSchedule, trade_audit::TradeAudit, trade::{Asset, Chain, CumulativeFeesResponse, DailyProfitLoss, DailyProfitLossBreakdown, FeeBreakdown, ExecutionQuality, SlippageByTrader, Trade, TradeCluster, TradeFilter, TradeMetadata, TradeSort, TradeSource, TradeType}, user_wallet::UserWallet}, DbPool},
+    error::{AppError, ErrorBody},
+    services::{pagination::{self, Page}, format::{csv_stream, negotiate, respond, respond_one, to_csv_chunk, ResponseFormat}, journal::TradeJournal, jwt, metadata, narration, prices::{self, PriceCache}, quick_entry::{self, QuickEntry}},
+    middleware::{jwt_guard::JwtGuard, load_shed::LoadShed}, utils,
 };
 
-#[derive(Serialize, Deserialize)]
+#[derive(Clone, Serialize, Deserialize, ToSchema)]
 pub struct TradeForm {
     pub user_id: String,
     pub wallet_id: String,
@@ -73,18 +144,247 @@ pub struct TradeForm {
     pub final_price: Option<f32>,
     pub traded_amount: Option<f32>,
     pub timestamp: Option<i64>,
+    #[serde(default)]
+    pub entered_by: Option<String>,
+    #[serde(default)]
+    pub tx_hash: Option<String>,
+    #[serde(default)]
+    pub source: Option<String>,
+    // External references such as `{"exchange_order_id": "8123"}`, see `TradeMetadata`.
+    #[serde(default)]
+    pub metadata: Option<BTreeMap<String, String>>,
 }
 
-#[derive(Serialize, Deserialize)]
+// The fields to change in `patch`; missing ones are kept.
+#[derive(Serialize, Deserialize, Default, ToSchema)]
+pub struct TradePatch {
+    pub amount: Option<f32>,
+    pub chain: Option<String>,
+    pub trade_type: Option<String>,
+    pub asset: Option<String>,
+    pub before_price: Option<f32>,
+    pub execution_price: Option<f32>,
+    pub final_price: Option<f32>,
+    pub traded_amount: Option<f32>,
+    pub tx_hash: Option<String>,
+    // Merged into the trade's metadata; an empty value removes the key.
+    pub metadata: Option<BTreeMap<String, String>>,
+}
+
+#[derive(Clone, Serialize, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
 pub struct TradeQuery {
     pub start_date: String,
+    #[serde(default)]
     pub end_date: String,
     pub trader_id: String,
     pub asset: Option<String>,
     pub trade_type: Option<String>,
+    pub format: Option<String>,
+    pub tz: Option<String>,
+    pub clusters: Option<usize>,
+    pub exclude_outliers: Option<f32>,
+    pub source: Option<String>,
+    pub cost_basis: Option<String>,
+    pub breakdown: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct TradeListQuery {
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+    pub cursor: Option<String>,
+    // `created_at` (default), `amount`, `asset` or `pnl`.
+    pub sort: Option<String>,
+    // `desc` (default) or `asc`.
+    pub order: Option<String>,
+    pub user_id: Option<String>,
+    pub asset: Option<String>,
+    pub chain: Option<String>,
+    pub trade_type: Option<String>,
+    pub start_date: Option<String>,
+    pub end_date: Option<String>,
+    pub tz: Option<String>,
+    pub source: Option<String>,
+    pub include: Option<String>,
+    pub locale: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct IncludeQuery {
+    pub include: Option<String>,
+    pub locale: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct CreateTradeQuery {
+    /// Reject sells the wallet could not have covered at the trade's timestamp.
+    #[serde(default)]
+    pub verify_holdings: bool,
+}
+
+#[derive(Serialize, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ExportQuery {
+    /// Only `csv` is supported, and is the default.
+    pub format: Option<String>,
+}
+
+const DEFAULT_PAGE_SIZE: i64 = 100;
+const MAX_PAGE_SIZE: i64 = 1000;
+const DEFAULT_CLUSTERS: usize = 4;
+const MAX_CLUSTERS: usize = 10;
+const EXPORT_PAGE_SIZE: i64 = 500;
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct QuickTradeForm {
+    pub user_id: String,
+    pub wallet_id: String,
+    pub command: String,
+    pub dry_run: Option<bool>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct TradeResponse {
+    #[serde(flatten)]
+    pub trade: Trade,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub explorer_url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub summary: Option<String>,
+}
+
+impl TradeResponse {
+    pub fn new(trade: Trade, tx_url_templates: &HashMap<String, String>) -> Self {
+        let explorer_url = trade
+            .tx_hash
+            .as_deref()
+            .and_then(|tx_hash| metadata::explorer_url(tx_url_templates, &trade.chain, tx_hash));
+        TradeResponse { trade, explorer_url, summary: None }
+    }
+
+    pub fn with_summary(mut self, locale: Option<&str>) -> Self {
+        self.summary = locale.map(|locale| narration::describe(&self.trade, locale));
+        self
+    }
+}
+
+// The locale of `summary` when it was requested with `?include=summary`.
+fn summary_locale(req: &HttpRequest, include: Option<&str>, locale: Option<&str>) -> Option<&'static str> {
+    if !include.unwrap_or("").split(',').any(|field| field.trim() == "summary") {
+        return None;
+    }
+    let accept_language = req.headers().get(ACCEPT_LANGUAGE).and_then(|value| value.to_str().ok());
+    Some(metadata::resolve_locale(locale, accept_language))
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct QuickTradePreview {
+    pub dry_run: bool,
+    pub interpretation: QuickEntry,
+    pub trade: TradeForm,
+}
+
+impl TradeForm {
+    pub fn validate(&self) -> Result<(), String> {
+        let values = [
+            Some(self.amount),
+            self.before_price,
+            self.execution_price,
+            self.final_price,
+            self.traded_amount,
+        ];
+        if values.iter().flatten().any(|value| !value.is_finite() || *value < 0.0) {
+            return Err("Amounts and prices must be non-negative numbers".to_string());
+        }
+
+        if !Chain::is_valid(&self.chain) || !TradeType::is_valid(&self.trade_type) || !Asset::is_valid(&self.asset) {
+            return Err("Invalid chain, trade type or asset".to_string());
+        }
+
+        if let Some(timestamp) = self.timestamp {
+            if utils::date::timestamp_to_naive_date_time(timestamp).is_none() {
+                return Err("Invalid timestamp".to_string());
+            }
+        }
+
+        if let Some(tx_hash) = &self.tx_hash {
+            if !metadata::is_tx_hash(tx_hash) {
+                return Err("Transaction hash must be 0x followed by 64 hex digits".to_string());
+            }
+        }
+
+        if let Some(source) = &self.source {
+            if !TradeSource::is_valid(source) {
+                return Err("Invalid trade source".to_string());
+            }
+        }
+
+        if let Some(metadata) = &self.metadata {
+            TradeMetadata::validate(metadata)?;
+        }
+
+        Ok(())
+    }
 }
 
+impl TradePatch {
+    pub fn validate(&self) -> Result<(), String> {
+        let values = [self.amount, self.before_price, self.execution_price, self.final_price, self.traded_amount];
+        if values.iter().flatten().any(|value| !value.is_finite() || *value < 0.0) {
+            return Err("Amounts and prices must be non-negative numbers".to_string());
+        }
+
+        if self.chain.as_ref().is_some_and(|chain| !Chain::is_valid(chain))
+            || self.trade_type.as_ref().is_some_and(|trade_type| !TradeType::is_valid(trade_type))
+            || self.asset.as_ref().is_some_and(|asset| !Asset::is_valid(asset))
+        {
+            return Err("Invalid chain, trade type or asset".to_string());
+        }
+
+        if let Some(tx_hash) = &self.tx_hash {
+            if !metadata::is_tx_hash(tx_hash) {
+                return Err("Transaction hash must be 0x followed by 64 hex digits".to_string());
+            }
+        }
+
+        if let Some(metadata) = &self.metadata {
+            TradeMetadata::validate(metadata)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn apply_to(&self, trade: &mut Trade) {
+        trade.amount = self.amount.unwrap_or(trade.amount);
+        trade.chain = self.chain.clone().unwrap_or_else(|| trade.chain.clone());
+        trade.trade_type = self.trade_type.clone().unwrap_or_else(|| trade.trade_type.clone());
+        trade.asset = self.asset.clone().unwrap_or_else(|| trade.asset.clone());
+        trade.before_price = self.before_price.unwrap_or(trade.before_price);
+        trade.execution_price = self.execution_price.unwrap_or(trade.execution_price);
+        trade.final_price = self.final_price.unwrap_or(trade.final_price);
+        trade.traded_amount = self.traded_amount.unwrap_or(trade.traded_amount);
+        trade.tx_hash = self.tx_hash.clone().or_else(|| trade.tx_hash.clone());
+        if let Some(changes) = &self.metadata {
+            let mut metadata = TradeMetadata::parse(trade.metadata.as_deref());
+            for (key, value) in changes {
+                if value.is_empty() {
+                    metadata.remove(key);
+                } else {
+                    metadata.insert(key.clone(), value.clone());
+                }
+            }
+            trade.metadata = Some(TradeMetadata::to_json(&metadata));
+        }
+    }
+}
+
+// Fees use the built-in rates; `FeeSchedule::apply` charges the schedule in force once a connection is at hand.
 pub fn fill_optional_fields(trade: &TradeForm) -> Trade {
+    let (execution_fee, transaction_fee) = FeeSchedule::default_fees(trade.execution_price.unwrap_or(0.0), trade.traded_amount.unwrap_or(0.0));
     Trade {
         user_id: trade.user_id.clone(),
         wallet_id: trade.wallet_id.clone(),
@@ -92,158 +392,834 @@ pub fn fill_optional_fields(trade: &TradeForm) -> Trade {
         chain: trade.chain.clone(),
         trade_type: trade.trade_type.clone(),
         asset: trade.asset.clone(),
-        before_price: if trade.before_price.is_none() {
-            0.0
-        } else {
-            trade.before_price.unwrap()
-        },
-        execution_price: if trade.execution_price.is_none() {
-            0.0
-        } else {
-            trade.execution_price.unwrap()
-        },
-        final_price: if trade.final_price.is_none() {
-            0.0
-        } else {
-            trade.final_price.unwrap()
-        },
-        traded_amount: if trade.traded_amount.is_none() {
-            0.0
-        } else {
-            trade.traded_amount.unwrap()
-        },
-        execution_fee: (trade.execution_price.unwrap_or(0.0) * trade.traded_amount.unwrap_or(0.0)) * 0.003,
-        transaction_fee: trade.execution_price.unwrap_or(0.0) * 0.005,
+        before_price: trade.before_price.unwrap_or(0.0),
+        execution_price: trade.execution_price.unwrap_or(0.0),
+        final_price: trade.final_price.unwrap_or(0.0),
+        traded_amount: trade.traded_amount.unwrap_or(0.0),
+        execution_fee,
+        transaction_fee,
         id: "".to_string(),
-        created_at: if trade.timestamp.is_none() {
-            chrono::Local::now().naive_local()
-        } else {
-            utils::date::timestamp_to_naive_date_time(trade.timestamp.unwrap())
+        created_at: match trade.timestamp.and_then(utils::date::timestamp_to_naive_date_time) {
+            Some(created_at) => created_at,
+            None => chrono::Local::now().naive_local(),
         },
         updated_at: chrono::Local::now().naive_local(),
+        entered_by: Some(trade.entered_by.clone().unwrap_or_else(|| trade.user_id.clone())),
+        tx_hash: trade.tx_hash.clone(),
+        source: trade.source.clone().unwrap_or_else(|| TradeSource::MANUAL.to_string()),
+        deleted_at: None,
+        metadata: trade.metadata.as_ref().map(TradeMetadata::to_json),
     }
 }
 
-pub async fn create_trade(trade: web::Json<TradeForm>, pool: web::Data<DbPool>) -> HttpResponse {
-    let conn = &mut pool.get().unwrap();
-    
-    let mut trade = fill_optional_fields(&trade.0);
-    match Trade::create(conn, &mut trade) {
-        Some(trade) => HttpResponse::Ok().json(trade),
-        None => HttpResponse::InternalServerError().into(),
+// Returns the user acting on the owner's trades, after checking the caller is the owner, an admin or holds a
+// delegation for `scope`. Signed links only cover reads, so writes always need a caller.
+fn authorize(conn: &mut SqliteConnection, caller_id: Option<String>, owner_id: &str, scope: &str) -> Result<String, AppError> {
+    let actor_id = match caller_id {
+        Some(actor_id) => actor_id,
+        None => return Err(AppError::Unauthorized("missing token".to_string())),
+    };
+
+    if jwt::is_admin(&actor_id) || TradeDelegation::allows(conn, owner_id, &actor_id, scope)? {
+        Ok(actor_id)
+    } else {
+        Err(AppError::Forbidden(format!("Not allowed to {} trades for this user", scope)))
     }
 }
 
-pub async fn index(pool: web::Data<DbPool>) -> HttpResponse {
-    let conn = &mut pool.get().unwrap();
-    let trades = Trade::list(conn);
-    if trades.is_empty() {
-        HttpResponse::InternalServerError().into()
+// Returns the user recorded in the trade's history.
+fn authorize_existing(conn: &mut SqliteConnection, caller_id: Option<String>, trade_id: &str, scope: &str) -> Result<String, AppError> {
+    match Trade::find_by_id(conn, trade_id.to_string())? {
+        Some(trade) => authorize(conn, caller_id, &trade.user_id, scope),
+        None => Err(AppError::NotFound("Trade not found".to_string())),
+    }
+}
+
+// The owner's trades can be read by the owner, an admin, any of the owner's delegates and their advisors. Signed
+// links carry no caller; they were issued by someone who could already read the data.
+pub fn ensure_can_view(conn: &mut SqliteConnection, caller_id: Option<&str>, owner_id: &str) -> Result<(), AppError> {
+    let caller_id = match caller_id {
+        Some(caller_id) => caller_id,
+        None => return Ok(()),
+    };
+
+    if caller_id == owner_id
+        || jwt::is_admin(caller_id)
+        || TradeDelegation::list_for_owner(conn, owner_id.to_string())?.iter().any(|delegation| delegation.delegate_id == caller_id)
+        || AdvisorClient::find(conn, caller_id.to_string(), owner_id.to_string())?.is_some()
+    {
+        Ok(())
     } else {
-        HttpResponse::Ok().json(trades)
+        Err(AppError::Forbidden("Trades belong to another user".to_string()))
+    }
+}
+
+// Limits a listing to the trades the caller can read: those of the requested user, or the caller's own when no user
+// is given (admins see every trade).
+fn scope_to_caller(conn: &mut SqliteConnection, caller_id: Option<&str>, filter: &mut TradeFilter) -> Result<(), AppError> {
+    match (caller_id, filter.user_id.as_deref()) {
+        (caller_id, Some(owner_id)) => ensure_can_view(conn, caller_id, owner_id),
+        (Some(caller_id), None) if !jwt::is_admin(caller_id) => {
+            filter.user_id = Some(caller_id.to_string());
+            Ok(())
+        }
+        _ => Ok(()),
+    }
+}
+
+pub fn ensure_user_wallet(conn: &mut SqliteConnection, user_id: &str, wallet_id: &str) -> Result<(), AppError> {
+    if UserWallet::owns(conn, user_id, wallet_id)? {
+        Ok(())
+    } else {
+        Err(AppError::Validation("Wallet does not belong to this user".to_string()))
+    }
+}
+
+pub fn with_explorer_urls(conn: &mut SqliteConnection, trades: Vec<Trade>) -> Result<Vec<TradeResponse>, AppError> {
+    let templates = metadata::tx_url_templates(conn)?;
+    Ok(trades.into_iter().map(|trade| TradeResponse::new(trade, &templates)).collect())
+}
+
+pub fn with_explorer_url(conn: &mut SqliteConnection, trade: Trade) -> Result<TradeResponse, AppError> {
+    let templates = metadata::tx_url_templates(conn)?;
+    Ok(TradeResponse::new(trade, &templates))
+}
+
+fn create_journaled(conn: &mut SqliteConnection, caller_id: Option<String>, journal: &TradeJournal, mut trade: TradeForm, verify_holdings: bool) -> Result<TradeResponse, AppError> {
+    trade.validate().map_err(AppError::Validation)?;
+    trade.entered_by = Some(authorize(conn, caller_id, &trade.user_id, DelegationScope::CREATE)?);
+    trade.source = Some(TradeSource::MANUAL.to_string());
+
+    if verify_holdings && matches!(trade.trade_type.as_str(), "LimitSell" | "MarketSell") {
+        if let Some(conflict) = Trade::holdings_conflict(conn, &fill_optional_fields(&trade))? {
+            return Err(AppError::Conflict(conflict));
+        }
+    }
+
+    match journal.record(conn, &trade)? {
+        (Some(trade), None) => with_explorer_url(conn, trade),
+        (_, errors) => Err(AppError::Validation(errors.unwrap_or_default())),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/trade",
+    tag = "trades",
+    params(CreateTradeQuery),
+    request_body = TradeForm,
+    responses(
+        (status = 200, description = "The created trade", body = TradeResponse),
+        (status = 400, description = "Invalid trade, a wallet of another user or insufficient balance", body = ErrorBody),
+        (status = 403, description = "No delegation to create trades for this user", body = ErrorBody),
+        (status = 409, description = "With `verify_holdings`, the wallet could not have covered this sell or a later one", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn create_trade(
+    req: HttpRequest,
+    trade: web::Json<TradeForm>,
+    pool: web::Data<DbPool>,
+    journal: web::Data<TradeJournal>,
+    params: web::Query<CreateTradeQuery>,
+) -> HttpResponse {
+    let (caller_id, trade, verify_holdings) = (jwt::user_id(&req), trade.into_inner(), params.verify_holdings);
+    match db::run(&pool, move |conn| create_journaled(conn, caller_id, &journal, trade, verify_holdings)).await {
+        Ok(trade) => HttpResponse::Ok().json(trade),
+        Err(err) => err.error_response(),
     }
 }
 
-pub async fn get(pool: web::Data<DbPool>, trade_id: web::Path<String>) -> HttpResponse {
-    let conn = &mut pool.get().unwrap();
-    match Trade::find_by_id(conn, trade_id.into_inner()) {
-        Some(trade) => HttpResponse::Ok().json(trade),
-        None => HttpResponse::InternalServerError().into(),
+#[utoipa::path(
+    post,
+    path = "/trade/quick",
+    tag = "trades",
+    request_body = QuickTradeForm,
+    responses(
+        (status = 200, description = "The interpreted trade in dry-run mode; the created trade (`TradeResponse`) otherwise", body = QuickTradePreview),
+        (status = 400, description = "The command cannot be parsed", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn quick_trade(req: HttpRequest, trade: web::Json<QuickTradeForm>, pool: web::Data<DbPool>, journal: web::Data<TradeJournal>) -> HttpResponse {
+    let interpretation = match quick_entry::parse(&trade.command) {
+        Ok(interpretation) => interpretation,
+        Err(err) => return AppError::Validation(err).error_response(),
+    };
+    let form = interpretation.to_trade_form(trade.user_id.clone(), trade.wallet_id.clone());
+    if let Err(err) = form.validate() {
+        return AppError::Validation(err).error_response();
+    }
+
+    if trade.dry_run.unwrap_or(true) {
+        return HttpResponse::Ok().json(QuickTradePreview { dry_run: true, interpretation, trade: form });
     }
+
+    let caller_id = jwt::user_id(&req);
+    match db::run(&pool, move |conn| create_journaled(conn, caller_id, &journal, form, false)).await {
+        Ok(trade) => HttpResponse::Ok().json(trade),
+        Err(err) => err.error_response(),
+    }
+}
+
+// The `metadata.{key}=value` pairs of a query string.
+pub fn metadata_filter(query: &str) -> Result<Vec<(String, String)>, String> {
+    let pairs = web::Query::<Vec<(String, String)>>::from_query(query).map_err(|err| err.to_string())?.into_inner();
+    pairs
+        .into_iter()
+        .filter_map(|(name, value)| name.strip_prefix("metadata.").map(|key| (key.to_string(), value)))
+        .map(|(key, value)| match TradeMetadata::is_valid_key(&key) {
+            true => Ok((key, value)),
+            false => Err(format!("Invalid metadata filter 'metadata.{}'", key)),
+        })
+        .collect()
 }
 
+// The `created_at` bounds of the optional `start_date` and `end_date` of a listing.
+pub fn date_filter(start_date: &Option<String>, end_date: &Option<String>, tz: Option<&str>) -> Result<(Option<String>, Option<String>), String> {
+    let non_empty = |value: &Option<String>| value.clone().filter(|value| !value.is_empty());
+    utils::date::parse_timezone(tz)?;
+
+    Ok(match (non_empty(start_date), non_empty(end_date)) {
+        (None, None) => (None, None),
+        // A relative start also bounds the end; an absolute start leaves the range open-ended.
+        (Some(start_date), None) => match utils::date::parse_range(&start_date, "", tz) {
+            Ok((start_date, end_date)) => (Some(start_date), Some(end_date)),
+            Err(_) => (Some(start_date), None),
+        },
+        (start_date, Some(end_date)) => {
+            let (start, end) = utils::date::parse_range(start_date.as_deref().unwrap_or("0000-01-01"), &end_date, tz)?;
+            (start_date.map(|_| start), Some(end))
+        }
+    })
+}
+
+fn list_filter(params: &TradeListQuery, query: &str) -> Result<TradeFilter, String> {
+    let non_empty = |value: &Option<String>| value.clone().filter(|value| !value.is_empty());
+    let (start_date, end_date) = date_filter(&params.start_date, &params.end_date, params.tz.as_deref())?;
+
+    Ok(TradeFilter {
+        user_id: non_empty(&params.user_id),
+        wallet_id: None,
+        asset: non_empty(&params.asset),
+        chain: non_empty(&params.chain),
+        trade_type: non_empty(&params.trade_type),
+        start_date,
+        end_date,
+        source: non_empty(&params.source),
+        metadata: metadata_filter(query)?,
+    })
+}
+
+#[utoipa::path(
+    get,
+    path = "/trade",
+    tag = "trades",
+    params(TradeListQuery),
+    responses(
+        (status = 200, description = "A page of trades, newest first; the total number of matches is in `X-Total-Count`. With `cursor`, the trades are wrapped in `{\"data\", \"next_cursor\"}` and not counted", body = [TradeResponse]),
+        (status = 400, description = "Invalid paging or filters", body = ErrorBody),
+        (status = 403, description = "Trades of a user the caller cannot read", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn index(req: HttpRequest, pool: web::Data<DbPool>, params: web::Query<TradeListQuery>) -> HttpResponse {
+    let limit = params.limit.unwrap_or(DEFAULT_PAGE_SIZE);
+    let offset = params.offset.unwrap_or(0);
+    if !(1..=MAX_PAGE_SIZE).contains(&limit) || offset < 0 {
+        return AppError::Validation(format!("Error: limit must be between 1 and {} and offset non-negative", MAX_PAGE_SIZE)).error_response();
+    }
+    let cursor = match pagination::parse_cursor(params.cursor.as_deref(), params.offset) {
+        Ok(cursor) => cursor,
+        Err(err) => return err.error_response(),
+    };
+    let sort = match TradeSort::parse(params.sort.as_deref(), params.order.as_deref()) {
+        Ok(sort) => sort,
+        Err(err) => return AppError::Validation(format!("Error: {}", err)).error_response(),
+    };
+    if cursor.is_some() && sort != TradeSort::default() {
+        return AppError::Validation("Error: cursor pages are always sorted by created_at, newest first".to_string()).error_response();
+    }
+
+    let mut filter = match list_filter(&params, req.query_string()) {
+        Ok(filter) => filter,
+        Err(err) => return AppError::Validation(format!("Error: {}", err)).error_response(),
+    };
+    let caller_id = jwt::user_id(&req);
+
+    let locale = summary_locale(&req, params.include.as_deref(), params.locale.as_deref());
+    if let Some(before) = cursor {
+        // Keyset pages skip the count, which would scan every match.
+        let page = db::run(&pool, move |conn| {
+            scope_to_caller(conn, caller_id.as_deref(), &mut filter)?;
+            let trades = Trade::search_before(conn, &filter, before.as_ref(), limit)?;
+            let next_cursor = pagination::next_cursor(&trades, limit, |trade| (trade.created_at, trade.id.clone()));
+            Ok((with_explorer_urls(conn, trades)?, next_cursor))
+        });
+        return match page.await {
+            Ok((trades, next_cursor)) => HttpResponse::Ok().json(Page {
+                data: trades.into_iter().map(|trade| trade.with_summary(locale)).collect(),
+                next_cursor,
+            }),
+            Err(err) => err.error_response(),
+        };
+    }
+
+    let page = db::run(&pool, move |conn| {
+        scope_to_caller(conn, caller_id.as_deref(), &mut filter)?;
+        let total = Trade::count(conn, &filter)?;
+        let trades = Trade::search(conn, &filter, sort, limit, offset)?;
+        Ok((total, with_explorer_urls(conn, trades)?))
+    });
+    let (total, trades) = match page.await {
+        Ok(page) => page,
+        Err(err) => return err.error_response(),
+    };
+    let trades: Vec<TradeResponse> = trades.into_iter().map(|trade| trade.with_summary(locale)).collect();
+    HttpResponse::Ok()
+        .insert_header(("X-Total-Count", total.to_string()))
+        .json(trades)
+}
+
+#[utoipa::path(
+    get,
+    path = "/trade/export",
+    tag = "trades",
+    params(ExportQuery),
+    responses(
+        (status = 200, description = "Every trade of the caller, oldest first", body = String, content_type = "text/csv"),
+        (status = 400, description = "Unsupported format", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn export(req: HttpRequest, pool: web::Data<DbPool>, params: web::Query<ExportQuery>) -> HttpResponse {
+    if params.format.as_deref().is_some_and(|format| !format.eq_ignore_ascii_case("csv")) {
+        return AppError::Validation("Error: trades can only be exported as csv".to_string()).error_response();
+    }
+    let user_id = match jwt::user_id(&req) {
+        Some(user_id) => user_id,
+        None => return AppError::Unauthorized("Exports require an authenticated user".to_string()).error_response(),
+    };
+
+    // The state is the cursor of the next page: `Some(None)` for the first one, `None` once the last was sent.
+    let pages = futures::stream::unfold(Some(None), move |cursor: Option<Option<(chrono::NaiveDateTime, String)>>| {
+        let (pool, user_id) = (pool.clone(), user_id.clone());
+        async move {
+            let after = cursor?;
+            let from = after.clone();
+            let page = db::run(&pool, move |conn| Ok(Trade::export_page(conn, &user_id, from.as_ref(), EXPORT_PAGE_SIZE)?)).await;
+            let page = match page {
+                Ok(page) => page,
+                Err(err) => return Some((Err(err.into()), None)),
+            };
+            let next = match page.last() {
+                Some(last) if page.len() as i64 == EXPORT_PAGE_SIZE => Some(Some((last.created_at, last.id.clone()))),
+                _ => None,
+            };
+            let chunk = to_csv_chunk(&page, after.is_none()).map_err(|err| AppError::Internal(err.to_string()).into());
+            Some((chunk, next))
+        }
+    });
+
+    csv_stream(&req, pages)
+}
+
+#[utoipa::path(
+    get,
+    path = "/trade/{trade_id}",
+    tag = "trades",
+    params(("trade_id" = String, Path, description = "Trade ID"), IncludeQuery),
+    responses(
+        (status = 200, description = "The trade", body = TradeResponse),
+        (status = 403, description = "Trade belongs to another user", body = ErrorBody),
+        (status = 404, description = "Trade not found", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn get(req: HttpRequest, pool: web::Data<DbPool>, trade_id: web::Path<String>, params: web::Query<IncludeQuery>) -> HttpResponse {
+    let caller_id = jwt::user_id(&req);
+    let result = db::run(&pool, move |conn| match Trade::find_by_id(conn, trade_id.into_inner())? {
+        Some(trade) => {
+            ensure_can_view(conn, caller_id.as_deref(), &trade.user_id)?;
+            Ok(Some(with_explorer_url(conn, trade)?))
+        }
+        None => Ok(None),
+    });
+    match result.await {
+        Ok(Some(trade)) => {
+            let locale = summary_locale(&req, params.include.as_deref(), params.locale.as_deref());
+            HttpResponse::Ok().json(trade.with_summary(locale))
+        }
+        Ok(None) => AppError::NotFound("Trade not found".to_string()).error_response(),
+        Err(err) => err.error_response(),
+    }
+}
+
+#[utoipa::path(
+    put,
+    path = "/trade/{trade_id}",
+    tag = "trades",
+    params(("trade_id" = String, Path, description = "Trade ID")),
+    request_body = TradeForm,
+    responses(
+        (status = 200, description = "The updated trade", body = TradeResponse),
+        (status = 400, description = "Invalid trade or insufficient balance", body = ErrorBody),
+        (status = 403, description = "No delegation to update this trade", body = ErrorBody),
+        (status = 404, description = "Trade not found", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
 pub async fn update(
+    req: HttpRequest,
     pool: web::Data<DbPool>,
     trade_id: web::Path<String>,
     trade: web::Json<TradeForm>,
 ) -> HttpResponse {
-    let conn = &mut pool.get().unwrap();
-    let mut trade = fill_optional_fields(&trade.0);
-    match Trade::update(conn, trade_id.into_inner(), &mut trade) {
-        Some(trade) => HttpResponse::Ok().json(trade),
-        None => HttpResponse::InternalServerError().into(),
+    if let Err(err) = trade.validate() {
+        return AppError::Validation(err).error_response();
+    }
+
+    let (caller_id, trade_id, form) = (jwt::user_id(&req), trade_id.into_inner(), trade.into_inner());
+    let result = db::run(&pool, move |conn| {
+        let actor_id = authorize_existing(conn, caller_id, &trade_id, DelegationScope::UPDATE)?;
+
+        let mut trade = fill_optional_fields(&form);
+        match Trade::update(conn, trade_id, &mut trade, &actor_id)? {
+            (Some(trade), None) => with_explorer_url(conn, trade),
+            (_, Some(error)) => Err(AppError::Validation(error)),
+            (None, None) => Err(AppError::NotFound("Trade not found".to_string())),
+        }
+    });
+    match result.await {
+        Ok(trade) => HttpResponse::Ok().json(trade),
+        Err(err) => err.error_response(),
+    }
+}
+
+#[utoipa::path(
+    patch,
+    path = "/trade/{trade_id}",
+    tag = "trades",
+    params(("trade_id" = String, Path, description = "Trade ID")),
+    request_body = TradePatch,
+    responses(
+        (status = 200, description = "The updated trade", body = TradeResponse),
+        (status = 400, description = "Invalid field or insufficient balance", body = ErrorBody),
+        (status = 403, description = "No delegation to update this trade", body = ErrorBody),
+        (status = 404, description = "Trade not found", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn patch(
+    req: HttpRequest,
+    pool: web::Data<DbPool>,
+    trade_id: web::Path<String>,
+    changes: web::Json<TradePatch>,
+) -> HttpResponse {
+    if let Err(err) = changes.validate() {
+        return AppError::Validation(err).error_response();
+    }
+
+    let (caller_id, trade_id, changes) = (jwt::user_id(&req), trade_id.into_inner(), changes.into_inner());
+    let result = db::run(&pool, move |conn| {
+        let actor_id = authorize_existing(conn, caller_id, &trade_id, DelegationScope::UPDATE)?;
+
+        let mut trade = Trade::find_by_id(conn, trade_id.clone())?.ok_or_else(|| AppError::NotFound("Trade not found".to_string()))?;
+        changes.apply_to(&mut trade);
+        match Trade::update(conn, trade_id, &mut trade, &actor_id)? {
+            (Some(trade), None) => with_explorer_url(conn, trade),
+            (_, Some(error)) => Err(AppError::Validation(error)),
+            (None, None) => Err(AppError::NotFound("Trade not found".to_string())),
+        }
+    });
+    match result.await {
+        Ok(trade) => HttpResponse::Ok().json(trade),
+        Err(err) => err.error_response(),
     }
 }
 
-pub async fn delete(pool: web::Data<DbPool>, trade_id: web::Path<String>) -> HttpResponse {
-    let conn = &mut pool.get().unwrap();
-    match Trade::delete(conn, trade_id.into_inner()) {
-        true => HttpResponse::Ok().into(),
-        false => HttpResponse::InternalServerError().into(),
+#[utoipa::path(
+    delete,
+    path = "/trade/{trade_id}",
+    tag = "trades",
+    params(("trade_id" = String, Path, description = "Trade ID")),
+    responses(
+        (status = 200, description = "The trade was deleted"),
+        (status = 400, description = "The wallet cannot cover the reversal of a sell", body = ErrorBody),
+        (status = 403, description = "No delegation to delete this trade", body = ErrorBody),
+        (status = 404, description = "Trade not found", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn delete(req: HttpRequest, pool: web::Data<DbPool>, trade_id: web::Path<String>) -> HttpResponse {
+    let (caller_id, trade_id) = (jwt::user_id(&req), trade_id.into_inner());
+    let result = db::run(&pool, move |conn| {
+        let actor_id = authorize_existing(conn, caller_id, &trade_id, DelegationScope::DELETE)?;
+        Ok(Trade::delete(conn, trade_id, &actor_id)?)
+    });
+    match result.await {
+        Ok((true, _)) => HttpResponse::Ok().into(),
+        Ok((false, Some(error))) => AppError::Validation(error).error_response(),
+        Ok((false, None)) => AppError::NotFound("Trade not found".to_string()).error_response(),
+        Err(err) => err.error_response(),
     }
 }
 
-pub async fn profit_loss(pool: web::Data<DbPool>, params: web::Query<TradeQuery>) -> HttpResponse {
-    let conn = &mut pool.get().unwrap();
+#[derive(Serialize, ToSchema)]
+pub struct TradeHistoryEntry {
+    pub action: String,
+    pub actor_id: String,
+    // The trade as it was before an update or delete.
+    pub previous: Option<serde_json::Value>,
+    pub created_at: chrono::NaiveDateTime,
+}
+
+impl From<TradeAudit> for TradeHistoryEntry {
+    fn from(entry: TradeAudit) -> Self {
+        TradeHistoryEntry {
+            action: entry.action,
+            actor_id: entry.actor_id,
+            previous: entry.previous.and_then(|previous| serde_json::from_str(&previous).ok()),
+            created_at: entry.created_at,
+        }
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/trade/{trade_id}/history",
+    tag = "trades",
+    params(("trade_id" = String, Path, description = "Trade ID")),
+    responses(
+        (status = 200, description = "Every change made to the trade, oldest first", body = [TradeHistoryEntry]),
+        (status = 403, description = "Trade belongs to another user", body = ErrorBody),
+        (status = 404, description = "Trade not found", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn history(req: HttpRequest, pool: web::Data<DbPool>, trade_id: web::Path<String>) -> HttpResponse {
+    let caller_id = jwt::user_id(&req);
+    let result = db::run(&pool, move |conn| match Trade::find_including_deleted(conn, trade_id.into_inner())? {
+        Some(trade) => {
+            ensure_can_view(conn, caller_id.as_deref(), &trade.user_id)?;
+            Ok(Some(TradeAudit::history(conn, &trade.id)?))
+        }
+        None => Ok(None),
+    });
+    match result.await {
+        Ok(Some(entries)) => HttpResponse::Ok().json(entries.into_iter().map(TradeHistoryEntry::from).collect::<Vec<_>>()),
+        Ok(None) => AppError::NotFound("Trade not found".to_string()).error_response(),
+        Err(err) => err.error_response(),
+    }
+}
 
-    if params.start_date.is_empty() || params.end_date.is_empty() || params.trader_id.is_empty() {
-        return HttpResponse::BadRequest()
-            .json("Error: Start date, End date and Trader ID are required");
+fn resolve_range(params: &TradeQuery) -> Result<(String, String), String> {
+    if params.start_date.is_empty() || params.trader_id.is_empty() {
+        return Err("Error: Start date, End date and Trader ID are required".to_string());
     }
 
-    let trades = Trade::profit_loss(
-        conn,
-        params.start_date.clone(),
-        params.end_date.clone(),
-        params.trader_id.clone(),
-        params.asset.clone(),
-        params.trade_type.clone(),
-    );
+    utils::date::parse_range(&params.start_date, &params.end_date, params.tz.as_deref())
+        .map_err(|err| format!("Error: {}", err))
+}
+
+fn excluded_trades(conn: &mut SqliteConnection, params: &TradeQuery, start_date: &str, end_date: &str) -> Result<Vec<String>, AppError> {
+    match params.exclude_outliers {
+        None => Ok(Vec::new()),
+        Some(deviations) if deviations.is_finite() && deviations > 0.0 => Ok(Trade::outliers(
+            conn,
+            start_date.to_string(),
+            end_date.to_string(),
+            params.trader_id.clone(),
+            deviations,
+        )?),
+        Some(_) => Err(AppError::Validation("Error: exclude_outliers must be a positive number of standard deviations".to_string())),
+    }
+}
 
-    HttpResponse::Ok().json(trades)
+// The trades left out of an aggregate: the outliers, plus the trades from other sources when `source` is given.
+fn out_of_scope(conn: &mut SqliteConnection, params: &TradeQuery, start_date: &str, end_date: &str, excluded: &[String]) -> Result<Vec<String>, AppError> {
+    let mut out_of_scope = excluded.to_vec();
+    if let Some(source) = params.source.clone().filter(|source| !source.is_empty()) {
+        out_of_scope.extend(Trade::other_sources(conn, start_date.to_string(), end_date.to_string(), params.trader_id.clone(), source)?);
+    }
+    Ok(out_of_scope)
 }
 
+// Runs an aggregate on the trades in scope, once the caller is allowed to read the trader's trades, after leaving out
+// the outliers and the trades from other sources. Returns it with the outliers, for `with_excluded`.
+async fn aggregate<T, F>(req: &HttpRequest, pool: &DbPool, params: &TradeQuery, start_date: String, end_date: String, f: F) -> Result<(T, Vec<String>), AppError>
+where
+    F: FnOnce(&mut SqliteConnection, String, String, &[String]) -> Result<T, DbError> + Send + 'static,
+    T: Send + 'static,
+{
+    let (caller_id, params) = (jwt::user_id(req), params.clone());
+    db::run(pool, move |conn| {
+        ensure_can_view(conn, caller_id.as_deref(), &params.trader_id)?;
+        let excluded = excluded_trades(conn, &params, &start_date, &end_date)?;
+        let out_of_scope = out_of_scope(conn, &params, &start_date, &end_date, &excluded)?;
+        Ok((f(conn, start_date, end_date, &out_of_scope)?, excluded))
+    })
+    .await
+}
+
+fn with_excluded(mut response: HttpResponse, excluded: &[String]) -> HttpResponse {
+    if let Ok(value) = HeaderValue::from_str(&excluded.join(",")) {
+        response.headers_mut().insert(HeaderName::from_static("x-excluded-trades"), value);
+    }
+    response
+}
+
+#[utoipa::path(
+    get,
+    path = "/profit-loss",
+    tag = "analytics",
+    params(TradeQuery),
+    responses(
+        (status = 200, description = "Daily profit and loss, with `breakdown=asset` split per asset within each day (`DailyProfitLossBreakdown`), or with `cost_basis=fifo` realized and unrealized P&L per day and asset (`DailyCostBasisPnl`); excluded outliers are listed in `X-Excluded-Trades`", content((Vec<DailyProfitLoss> = "application/json"), (String = "text/csv"))),
+        (status = 400, description = "Missing or invalid parameters", body = ErrorBody),
+        (status = 403, description = "Trades of a user the caller cannot read", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn profit_loss(req: HttpRequest, pool: web::Data<DbPool>, params: web::Query<TradeQuery>) -> HttpResponse {
+    match params.cost_basis.as_deref() {
+        None | Some("fifo") => (),
+        Some(_) => return AppError::Validation("Error: cost_basis must be fifo".to_string()).error_response(),
+    }
+    if params.cost_basis.is_some() && params.trade_type.is_some() {
+        return AppError::Validation("Error: cost_basis cannot be combined with trade_type".to_string()).error_response();
+    }
+    match params.breakdown.as_deref() {
+        None | Some("asset") => (),
+        Some(_) => return AppError::Validation("Error: breakdown must be asset".to_string()).error_response(),
+    }
+    if params.breakdown.is_some() && params.cost_basis.is_some() {
+        return AppError::Validation("Error: cost_basis results are already per asset; breakdown cannot be combined with it".to_string()).error_response();
+    }
+
+    let (start_date, end_date) = match resolve_range(&params) {
+        Ok(range) => range,
+        Err(err) => return AppError::Validation(err).error_response(),
+    };
+    let (trader_id, asset, trade_type) = (params.trader_id.clone(), params.asset.clone(), params.trade_type.clone());
+
+    if params.cost_basis.is_some() {
+        let reaches_today = end_date >= chrono::Local::now().naive_local().format("%Y-%m-%d").to_string();
+        let days = aggregate(&req, &pool, &params, start_date, end_date, move |conn, start_date, end_date, out_of_scope| {
+            Trade::profit_loss_fifo(conn, start_date, end_date, trader_id, asset, out_of_scope)
+        });
+        return match days.await {
+            Ok((mut days, excluded)) => {
+                if let Some(prices) = req.app_data::<web::Data<PriceCache>>().filter(|_| reaches_today) {
+                    prices::mark_to_market(&mut days, &prices.marks());
+                }
+                with_excluded(respond(&req, params.format.as_deref(), &days), &excluded)
+            }
+            Err(err) => err.error_response(),
+        };
+    }
+
+    if params.breakdown.is_some() {
+        let format = params.format.as_deref();
+        let days = aggregate(&req, &pool, &params, start_date, end_date, move |conn, start_date, end_date, out_of_scope| {
+            Trade::profit_loss_by_asset(conn, start_date, end_date, trader_id, asset, trade_type, out_of_scope)
+        });
+        return match days.await {
+            Ok((days, excluded)) if negotiate(&req, format) == ResponseFormat::Csv => {
+                with_excluded(respond(&req, format, &DailyProfitLossBreakdown::flatten(days)), &excluded)
+            }
+            Ok((days, excluded)) => with_excluded(respond(&req, format, &days), &excluded),
+            Err(err) => err.error_response(),
+        };
+    }
+
+    let days = aggregate(&req, &pool, &params, start_date, end_date, move |conn, start_date, end_date, out_of_scope| {
+        Trade::profit_loss(conn, start_date, end_date, trader_id, asset, trade_type, out_of_scope)
+    });
+    match days.await {
+        Ok((trades, excluded)) => with_excluded(respond(&req, params.format.as_deref(), &trades), &excluded),
+        Err(err) => err.error_response(),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/cumulative-fees",
+    tag = "analytics",
+    params(TradeQuery),
+    responses(
+        (status = 200, description = "Total fees paid; excluded outliers are listed in `X-Excluded-Trades`", content((CumulativeFeesResponse = "application/json"), (String = "text/csv"))),
+        (status = 400, description = "Missing or invalid parameters", body = ErrorBody),
+        (status = 403, description = "Trades of a user the caller cannot read", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
 pub async fn cumulative_fee(
+    req: HttpRequest,
     pool: web::Data<DbPool>,
     params: web::Query<TradeQuery>,
 ) -> HttpResponse {
-    let conn = &mut pool.get().unwrap();
+    let (start_date, end_date) = match resolve_range(&params) {
+        Ok(range) => range,
+        Err(err) => return AppError::Validation(err).error_response(),
+    };
+
+    let trader_id = params.trader_id.clone();
+    let fees = aggregate(&req, &pool, &params, start_date, end_date, move |conn, start_date, end_date, out_of_scope| {
+        Trade::cumulative_fees(conn, start_date, end_date, trader_id, out_of_scope)
+    });
+    match fees.await {
+        Ok((fees, excluded)) => with_excluded(respond_one(&req, params.format.as_deref(), &fees), &excluded),
+        Err(err) => err.error_response(),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/cumulative-fees/breakdown",
+    tag = "analytics",
+    params(TradeQuery),
+    responses(
+        (status = 200, description = "Execution and transaction fees per asset, chain and trade type, most expensive first; excluded outliers are listed in `X-Excluded-Trades`", content((Vec<FeeBreakdown> = "application/json"), (String = "text/csv"))),
+        (status = 400, description = "Missing or invalid parameters", body = ErrorBody),
+        (status = 403, description = "Trades of a user the caller cannot read", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn fee_breakdown(req: HttpRequest, pool: web::Data<DbPool>, params: web::Query<TradeQuery>) -> HttpResponse {
+    let (start_date, end_date) = match resolve_range(&params) {
+        Ok(range) => range,
+        Err(err) => return AppError::Validation(err).error_response(),
+    };
 
-    if params.start_date.is_empty() || params.end_date.is_empty() || params.trader_id.is_empty() {
-        return HttpResponse::BadRequest().json("Error: Start date, End date and Trader ID are required")
+    let trader_id = params.trader_id.clone();
+    let breakdown = aggregate(&req, &pool, &params, start_date, end_date, move |conn, start_date, end_date, out_of_scope| {
+        Trade::fee_breakdown(conn, start_date, end_date, trader_id, out_of_scope)
+    });
+    match breakdown.await {
+        Ok((breakdown, excluded)) => with_excluded(respond(&req, params.format.as_deref(), &breakdown), &excluded),
+        Err(err) => err.error_response(),
     }
+}
 
-    let fees = Trade::cumulative_fees(
-        conn,
-        params.start_date.clone(),
-        params.end_date.clone(),
-        params.trader_id.clone(),
-    );
+#[utoipa::path(
+    get,
+    path = "/slippage",
+    tag = "analytics",
+    params(TradeQuery),
+    responses(
+        (status = 200, description = "Slippage totals and averages; excluded outliers are listed in `X-Excluded-Trades`", content((SlippageByTrader = "application/json"), (String = "text/csv"))),
+        (status = 400, description = "Missing or invalid parameters", body = ErrorBody),
+        (status = 403, description = "Trades of a user the caller cannot read", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn slippage(req: HttpRequest, pool: web::Data<DbPool>, params: web::Query<TradeQuery>) -> HttpResponse {
+    let (start_date, end_date) = match resolve_range(&params) {
+        Ok(range) => range,
+        Err(err) => return AppError::Validation(err).error_response(),
+    };
 
-    HttpResponse::Ok().json(fees)
+    let trader_id = params.trader_id.clone();
+    let slippage = aggregate(&req, &pool, &params, start_date, end_date, move |conn, start_date, end_date, out_of_scope| {
+        Trade::get_slippage_bt_dates(conn, start_date, end_date, trader_id, out_of_scope)
+    });
+    match slippage.await {
+        Ok((slippage, excluded)) => with_excluded(respond_one(&req, params.format.as_deref(), &slippage), &excluded),
+        Err(err) => err.error_response(),
+    }
 }
 
-pub async fn slippage(pool: web::Data<DbPool>, params: web::Query<TradeQuery>) -> HttpResponse {
-    let conn = &mut pool.get().unwrap();
-    
-    if params.start_date.is_empty() || params.end_date.is_empty() || params.trader_id.is_empty() {
-        return HttpResponse::BadRequest()
-            .json("Error: Start date, End date and Trader ID are required");
+#[utoipa::path(
+    get,
+    path = "/execution-quality",
+    tag = "analytics",
+    params(TradeQuery),
+    responses(
+        (status = 200, description = "Per-asset slippage percentiles; excluded outliers are listed in `X-Excluded-Trades`", content((Vec<ExecutionQuality> = "application/json"), (String = "text/csv"))),
+        (status = 400, description = "Missing or invalid parameters", body = ErrorBody),
+        (status = 403, description = "Trades of a user the caller cannot read", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn execution_quality(req: HttpRequest, pool: web::Data<DbPool>, params: web::Query<TradeQuery>) -> HttpResponse {
+    let (start_date, end_date) = match resolve_range(&params) {
+        Ok(range) => range,
+        Err(err) => return AppError::Validation(err).error_response(),
+    };
+
+    let trader_id = params.trader_id.clone();
+    let quality = aggregate(&req, &pool, &params, start_date, end_date, move |conn, start_date, end_date, out_of_scope| {
+        Trade::execution_quality(conn, start_date, end_date, trader_id, out_of_scope)
+    });
+    match quality.await {
+        Ok((quality, excluded)) => with_excluded(respond(&req, params.format.as_deref(), &quality), &excluded),
+        Err(err) => err.error_response(),
     }
+}
+
+#[utoipa::path(
+    get,
+    path = "/trade-clusters",
+    tag = "analytics",
+    params(TradeQuery),
+    responses(
+        (status = 200, description = "Groups of similar trades", body = [TradeCluster]),
+        (status = 400, description = "Missing or invalid parameters", body = ErrorBody),
+        (status = 403, description = "Trades of a user the caller cannot read", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn trade_clusters(req: HttpRequest, pool: web::Data<DbPool>, params: web::Query<TradeQuery>) -> HttpResponse {
+    let (start_date, end_date) = match resolve_range(&params) {
+        Ok(range) => range,
+        Err(err) => return AppError::Validation(err).error_response(),
+    };
 
-    let slippage = Trade::get_slippage_bt_dates(
-        conn,
-        params.start_date.clone(),
-        params.end_date.clone(),
-        params.trader_id.clone(),
-    );
+    let k = params.clusters.unwrap_or(DEFAULT_CLUSTERS);
+    if !(1..=MAX_CLUSTERS).contains(&k) {
+        return AppError::Validation(format!("Error: clusters must be between 1 and {}", MAX_CLUSTERS)).error_response();
+    }
 
-    HttpResponse::Ok().json(slippage)
+    let (caller_id, trader_id) = (jwt::user_id(&req), params.into_inner().trader_id);
+    let clusters = db::run(&pool, move |conn| {
+        ensure_can_view(conn, caller_id.as_deref(), &trader_id)?;
+        Ok(Trade::clusters(conn, start_date, end_date, trader_id, k)?)
+    });
+    match clusters.await {
+        Ok(clusters) => HttpResponse::Ok().json(clusters),
+        Err(err) => err.error_response(),
+    }
 }
 
 pub fn init_routes(cfg: &mut web::ServiceConfig) {
     cfg.service(
         web::resource("/trade")
-            .route(web::post().to(create_trade).wrap(JwtGuard))
-            .route(web::get().to(index).wrap(JwtGuard)),
+            .route(web::post().to(create_trade).wrap(JwtGuard).wrap(LoadShed::high_priority()))
+            .route(web::get().to(index).wrap(JwtGuard).wrap(LoadShed::high_priority())),
+    )
+    .service(
+        web::resource("/trade/quick")
+            .route(web::post().to(quick_trade).wrap(JwtGuard).wrap(LoadShed::high_priority())),
     )
+    .service(web::resource("/trade/export").route(web::get().to(export).wrap(JwtGuard).wrap(LoadShed::low_priority())))
     .service(
         web::resource("/trade/{trade_id}")
-            .route(web::get().to(get).wrap(JwtGuard))
-            .route(web::put().to(update).wrap(JwtGuard))
-            .route(web::delete().to(delete).wrap(JwtGuard)),
+            .route(web::get().to(get).wrap(JwtGuard).wrap(LoadShed::high_priority()))
+            .route(web::put().to(update).wrap(JwtGuard).wrap(LoadShed::high_priority()))
+            .route(web::patch().to(patch).wrap(JwtGuard).wrap(LoadShed::high_priority()))
+            .route(web::delete().to(delete).wrap(JwtGuard).wrap(LoadShed::high_priority())),
     )
-    .service(web::resource("/profit-loss").route(web::get().to(profit_loss).wrap(JwtGuard)))
-    .service(web::resource("/cumulative-fees").route(web::get().to(cumulative_fee).wrap(JwtGuard)))
-    .service(web::resource("/slippage").route(web::get().to(slippage).wrap(JwtGuard)));
+    .service(web::resource("/trade/{trade_id}/history").route(web::get().to(history).wrap(JwtGuard).wrap(LoadShed::high_priority())))
+    .configure(init_analytics_routes);
+}
+
+// The read-only analytics routes, which read-only replicas serve too (see `services::replica`).
+pub fn init_analytics_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(web::resource("/profit-loss").route(web::get().to(profit_loss).wrap(JwtGuard).wrap(LoadShed::low_priority())))
+    .service(web::resource("/cumulative-fees").route(web::get().to(cumulative_fee).wrap(JwtGuard).wrap(LoadShed::low_priority())))
+    .service(web::resource("/cumulative-fees/breakdown").route(web::get().to(fee_breakdown).wrap(JwtGuard).wrap(LoadShed::low_priority())))
+    .service(web::resource("/slippage").route(web::get().to(slippage).wrap(JwtGuard).wrap(LoadShed::low_priority())))
+    .service(web::resource("/execution-quality").route(web::get().to(execution_quality).wrap(JwtGuard).wrap(LoadShed::low_priority())))
+    .service(web::resource("/trade-clusters").route(web::get().to(trade_clusters).wrap(JwtGuard).wrap(LoadShed::low_priority())));
 }
@@ -0,0 +1,246 @@
+use actix_web::http::header::AUTHORIZATION;
+use actix_web::http::StatusCode;
+use actix_web::test::{call_service, init_service, read_body_json, TestRequest};
+use actix_web::{web, App};
+
+use crate::db::fixtures::{create_trade, test_pool, trade, user};
+use crate::db::models::advisor::AdvisorClient;
+use crate::db::models::user_wallet::UserWallet;
+use crate::db::models::wallet_transaction::WalletTransaction;
+use crate::error::ErrorBody;
+use super::journal::TradeJournal;
+use super::jwt::create_jwt;
+use super::metadata::default_tx_url_templates;
+use super::trade::{fill_optional_fields, init_routes, metadata_filter, TradeForm, TradePatch, TradeResponse};
+
+fn trade_form() -> TradeForm {
+    TradeForm {
+        amount: 10.0,
+        trade_type: "LimitBuy".to_string(),
+        before_price: Some(10.0),
+        execution_price: Some(11.0),
+        final_price: Some(12.0),
+        timestamp: Some(1641045600),
+        ..trade("user_id", "wallet_id")
+    }
+}
+
+#[test]
+fn validate_accepts_valid_form() {
+    assert!(trade_form().validate().is_ok());
+}
+
+#[test]
+fn validate_rejects_out_of_range_timestamp() {
+    let mut form = trade_form();
+    form.timestamp = Some(-1);
+    assert!(form.validate().is_err());
+
+    form.timestamp = Some(i64::MAX);
+    assert!(form.validate().is_err());
+}
+
+#[test]
+fn validate_rejects_non_finite_values() {
+    let mut form = trade_form();
+    form.execution_price = Some(f32::INFINITY);
+    assert!(form.validate().is_err());
+
+    let mut form = trade_form();
+    form.amount = -1.0;
+    assert!(form.validate().is_err());
+}
+
+#[test]
+fn fill_optional_fields_uses_timestamp() {
+    let trade = fill_optional_fields(&trade_form());
+    assert_eq!(trade.created_at.to_string(), "2022-01-01 14:00:00");
+}
+
+#[test]
+fn validate_rejects_malformed_tx_hash() {
+    let mut form = trade_form();
+    form.tx_hash = Some("0x1234".to_string());
+    assert!(form.validate().is_err());
+
+    form.tx_hash = Some(format!("0x{}", "0f".repeat(32)));
+    assert!(form.validate().is_ok());
+}
+
+#[test]
+fn validate_checks_source_and_defaults_to_manual() {
+    assert_eq!(fill_optional_fields(&trade_form()).source, "manual");
+
+    let mut form = trade_form();
+    for source in ["simulation", "import:binance", "onchain:arbitrum", "api_key:42"] {
+        form.source = Some(source.to_string());
+        assert!(form.validate().is_ok(), "{}", source);
+    }
+    for source in ["", "import", "import:", "robot:1"] {
+        form.source = Some(source.to_string());
+        assert!(form.validate().is_err(), "{}", source);
+    }
+}
+
+#[test]
+fn responses_link_trades_with_tx_hash_to_explorer() {
+    let templates = default_tx_url_templates();
+    let response = TradeResponse::new(fill_optional_fields(&trade_form()), &templates);
+    let json = serde_json::to_value(&response).unwrap();
+    assert!(json.get("explorer_url").is_none());
+    assert_eq!(json["asset"], "ETH");
+
+    let mut form = trade_form();
+    form.tx_hash = Some(format!("0x{}", "0f".repeat(32)));
+    let response = TradeResponse::new(fill_optional_fields(&form), &templates);
+    assert_eq!(response.explorer_url, Some(format!("https://etherscan.io/tx/0x{}", "0f".repeat(32))));
+}
+
+#[test]
+fn patch_only_changes_the_given_fields() {
+    let mut trade = fill_optional_fields(&trade_form());
+    let fees = (trade.execution_fee, trade.transaction_fee);
+    let patch = TradePatch { final_price: Some(15.0), chain: Some("Arbitrum".to_string()), ..Default::default() };
+    assert!(patch.validate().is_ok());
+    patch.apply_to(&mut trade);
+
+    assert_eq!((trade.final_price, trade.chain.as_str()), (15.0, "Arbitrum"));
+    assert_eq!((trade.before_price, trade.execution_price, trade.amount, trade.asset.as_str()), (10.0, 11.0, 10.0, "ETH"));
+    assert_eq!((trade.execution_fee, trade.transaction_fee), fees);
+}
+
+#[test]
+fn patch_validates_the_given_fields() {
+    assert!(TradePatch::default().validate().is_ok());
+    assert!(TradePatch { asset: Some("NOPE".to_string()), ..Default::default() }.validate().is_err());
+    assert!(TradePatch { trade_type: Some("Buy".to_string()), ..Default::default() }.validate().is_err());
+    assert!(TradePatch { chain: Some(String::new()), ..Default::default() }.validate().is_err());
+    assert!(TradePatch { traded_amount: Some(-1.0), ..Default::default() }.validate().is_err());
+}
+
+#[actix_web::test]
+async fn missing_trades_are_not_found() {
+    std::env::set_var("JWT_SECRET", "test_secret");
+    let app = init_service(App::new().app_data(web::Data::new(test_pool())).configure(init_routes)).await;
+    let token = create_jwt("user_id".to_string()).unwrap();
+
+    let requests = [
+        TestRequest::get(),
+        TestRequest::put().set_json(trade_form()),
+        TestRequest::patch().set_json(TradePatch::default()),
+        TestRequest::delete(),
+    ];
+    for request in requests {
+        let req = request.uri("/trade/missing").insert_header((AUTHORIZATION, token.clone())).to_request();
+        let response = call_service(&app, req).await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        let body: ErrorBody = read_body_json(response).await;
+        assert_eq!((body.code.as_str(), body.message.as_str()), ("not_found", "Trade not found"));
+    }
+
+    // Invalid fields are rejected before the trade is looked up.
+    let form = TradeForm { chain: String::new(), ..trade_form() };
+    let req = TestRequest::put().uri("/trade/missing").insert_header((AUTHORIZATION, token)).set_json(form).to_request();
+    assert_eq!(call_service(&app, req).await.status(), StatusCode::BAD_REQUEST);
+}
+
+#[test]
+fn metadata_is_validated_and_merged_by_patches() {
+    let metadata = |pairs: &[(&str, &str)]| Some(pairs.iter().map(|(key, value)| (key.to_string(), value.to_string())).collect());
+    let form = TradeForm { metadata: metadata(&[("exchange_order_id", "8123"), ("client_tag", "grid-bot")]), ..trade_form() };
+    assert!(form.validate().is_ok());
+    assert!(TradeForm { metadata: metadata(&[("order id", "8123")]), ..trade_form() }.validate().is_err());
+    assert!(TradeForm { metadata: metadata(&[("note", &"x".repeat(257))]), ..trade_form() }.validate().is_err());
+
+    let mut trade = fill_optional_fields(&form);
+    let patch = TradePatch { metadata: metadata(&[("client_tag", ""), ("bot_run_id", "42")]), ..Default::default() };
+    assert!(patch.validate().is_ok());
+    patch.apply_to(&mut trade);
+    assert_eq!(trade.metadata.as_deref(), Some(r#"{"bot_run_id":"42","exchange_order_id":"8123"}"#));
+}
+
+#[test]
+fn metadata_filters_are_read_from_the_query_string() {
+    let filters = metadata_filter("limit=10&metadata.exchange_order_id=8123&metadata.client_tag=grid%20bot").unwrap();
+    assert_eq!(filters, vec![("exchange_order_id".to_string(), "8123".to_string()), ("client_tag".to_string(), "grid bot".to_string())]);
+    assert!(metadata_filter("metadata.a%22b=1").is_err());
+    assert!(metadata_filter("asset=ETH").unwrap().is_empty());
+}
+
+#[actix_web::test]
+async fn trades_are_only_created_on_the_users_own_wallets() {
+    std::env::set_var("JWT_SECRET", "test_secret");
+    let pool = test_pool();
+    let conn = &mut pool.get().unwrap();
+    let (alice, bob) = (user(conn, "alice"), user(conn, "bob"));
+    let savings = UserWallet::create(conn, &alice.id, "Savings").unwrap().0.unwrap();
+    WalletTransaction::deposit(conn, savings.id.clone(), 1_000.0, None).unwrap();
+
+    let journal = TradeJournal::new(std::env::temp_dir().join(format!("trade_journal-{}.log", uuid::Uuid::new_v4())));
+    let app = init_service(App::new().app_data(web::Data::new(pool.clone())).app_data(web::Data::new(journal)).configure(init_routes)).await;
+    let token = create_jwt(alice.id.clone()).unwrap();
+
+    for (wallet_id, status) in [(&bob.wallet_id, StatusCode::BAD_REQUEST), (&savings.id, StatusCode::OK), (&alice.wallet_id, StatusCode::OK)] {
+        let form = TradeForm { user_id: alice.id.clone(), wallet_id: wallet_id.clone(), ..trade_form() };
+        let req = TestRequest::post().uri("/trade").insert_header((AUTHORIZATION, token.clone())).set_json(form).to_request();
+        assert_eq!(call_service(&app, req).await.status(), status);
+    }
+}
+
+#[actix_web::test]
+async fn updates_keep_the_trades_user_and_wallet() {
+    std::env::set_var("JWT_SECRET", "test_secret");
+    let pool = test_pool();
+    let conn = &mut pool.get().unwrap();
+    let (alice, bob) = (user(conn, "alice"), user(conn, "bob"));
+    let created = create_trade(conn, &trade(&alice.id, &alice.wallet_id));
+
+    let app = init_service(App::new().app_data(web::Data::new(pool.clone())).configure(init_routes)).await;
+    let form = TradeForm { user_id: bob.id.clone(), wallet_id: bob.wallet_id.clone(), ..trade(&alice.id, &alice.wallet_id) };
+    let req = TestRequest::put()
+        .uri(&format!("/trade/{}", created.id))
+        .insert_header((AUTHORIZATION, create_jwt(alice.id.clone()).unwrap()))
+        .set_json(form)
+        .to_request();
+    let response = call_service(&app, req).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let body: serde_json::Value = read_body_json(response).await;
+    assert_eq!((body["user_id"].as_str(), body["wallet_id"].as_str()), (Some(alice.id.as_str()), Some(alice.wallet_id.as_str())));
+}
+
+#[actix_web::test]
+async fn trades_and_analytics_are_read_by_the_owner_and_their_advisors() {
+    std::env::set_var("JWT_SECRET", "test_secret");
+    let pool = test_pool();
+    let conn = &mut pool.get().unwrap();
+    let (alice, bob, advisor) = (user(conn, "alice"), user(conn, "bob"), user(conn, "advisor"));
+    let alice_trade = create_trade(conn, &trade(&alice.id, &alice.wallet_id));
+    create_trade(conn, &trade(&bob.id, &bob.wallet_id));
+    AdvisorClient::grant(conn, advisor.id.clone(), alice.id.clone()).unwrap();
+
+    let app = init_service(App::new().app_data(web::Data::new(pool.clone())).configure(init_routes)).await;
+    let (alice_token, bob_token, advisor_token) = (create_jwt(alice.id.clone()).unwrap(), create_jwt(bob.id.clone()).unwrap(), create_jwt(advisor.id.clone()).unwrap());
+
+    let req = TestRequest::get().uri("/trade").insert_header((AUTHORIZATION, alice_token)).to_request();
+    let listed: Vec<serde_json::Value> = read_body_json(call_service(&app, req).await).await;
+    assert_eq!(listed.iter().map(|trade| trade["id"].as_str().unwrap()).collect::<Vec<_>>(), vec![alice_trade.id.as_str()]);
+
+    let range = format!("trader_id={}&start_date=2023-08-01&end_date=2023-08-31", alice.id);
+    let uris = [
+        format!("/trade?user_id={}", alice.id),
+        format!("/trade/{}", alice_trade.id),
+        format!("/trade/{}/history", alice_trade.id),
+        format!("/profit-loss?{}", range),
+        format!("/cumulative-fees?{}", range),
+        format!("/cumulative-fees/breakdown?{}", range),
+        format!("/slippage?{}", range),
+        format!("/execution-quality?{}", range),
+        format!("/trade-clusters?{}", range),
+    ];
+    for uri in &uris {
+        for (token, status) in [(bob_token.clone(), StatusCode::FORBIDDEN), (advisor_token.clone(), StatusCode::OK)] {
+            let req = TestRequest::get().uri(uri).insert_header((AUTHORIZATION, token)).to_request();
+            assert_eq!(call_service(&app, req).await.status(), status, "{}", uri);
+        }
+    }
+}
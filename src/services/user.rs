@@ -1,7 +1,12 @@
 //! This module defines functions and structs related to user management and authentication using the Actix Web framework.
 //!
-//! The provided functions handle user creation, retrieval, deletion, and login. Additionally, they include routes for
-//! registering users, retrieving user information, deleting users, and logging in.
+//! The provided functions handle user creation, retrieval, update, deletion, and login. Additionally, they include routes
+//! for registering users, retrieving and updating user information, deleting users, and logging in.
+//!
+//! `PATCH /user/{user_id}` changes only the fields present in `{"name", "email", "password", "current_password"}` and
+//! only for the user themselves. A password change needs `current_password` (a wrong one is a `403`). An email change is
+//! not applied at once: it is confirmed from the new address (see `services::email_change`), and the response carries
+//! it as `pending_email`; an address that is already registered is a `409`.
 //!
 //! Key features of this module include:
 //! - `UserForm`: A struct representing the user registration form.
@@ -10,10 +15,23 @@
 //! The `init_routes` function configures routes for these user-related operations and wraps certain routes with the `JwtGuard`
 //! middleware to ensure secure authentication.
 //!
+//! A successful login returns a short-lived access token together with a refresh token (see `services::auth`), as
+//! `{"access_token": ..., "refresh_token": ..., "token_type": "Bearer", "expires_in": ...}`.
+//!
+//! Logins are checked against the devices the user logged in from before (see `db::models::device`). A login from an
+//! unseen device or IP address sends a security email to the account; if the user enabled `confirm_new_devices` in
+//! their settings, `login` answers `202` with a `LoginPending` body instead of tokens, and the tokens are returned by
+//! `confirm_login` (`POST /login/confirm` with `{"token": "..."}`) once the token from the email is presented.
+//!
+//! Errors are returned as `crate::error::AppError` JSON bodies: invalid registrations are a `400`, an email that is
+//! already registered a `409`, unknown users a `404` and failed logins a `401` telling how many attempts are left. After
+//! too many failures in a row the account is locked for a while (see `db::models::login_attempt`), and logins answer
+//! `423` with a `Retry-After` header until it is unlocked.
+//!
 //! # Examples
 //!
 //! ```rust
-//! use actix_web::{HttpResponse, web};
+//! use actix_web::{HttpResponse, ResponseError, web};
 //! use serde::{Deserialize, Serialize};
 //!
 //! // ... imports ...
@@ -51,89 +69,237 @@
 //! Ensure that your database schema and models are properly configured to work with the provided methods.
 //! Properly validate and handle user input to prevent security vulnerabilities.
 
-use actix_web::{HttpResponse, web};
+use actix_web::http::header::USER_AGENT;
+use actix_web::{HttpRequest, HttpResponse, ResponseError, web};
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 
-use crate::middleware::jwt_guard::JwtGuard;
+use crate::middleware::{jwt_guard::JwtGuard, load_shed::LoadShed};
 
-use crate::db::{DbPool, models::user::User, models::wallet::Wallet};
+use crate::db::{self, DbPool, models::device::{DeviceCheck, KnownDevice}, models::user::{LoginOutcome, User, UserChanges, UserUpdate, EMAIL_EXISTS, USER_NOT_FOUND, WRONG_PASSWORD}, models::wallet::Wallet};
+use crate::error::{AppError, ErrorBody};
+use crate::services::auth::{issue_tokens, TokenPair};
+use crate::services::jwt;
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, ToSchema)]
 pub struct UserForm {
     pub name: String,
     pub email: String,
     pub password: String,
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, ToSchema)]
 pub struct LoginForm {
     pub email: String,
     pub password: String,
 }
 
-pub async fn create_user(user: web::Json<UserForm>, pool: web::Data<DbPool>) -> HttpResponse {
-    let conn = &mut pool.get().unwrap();
-    let wallet = Wallet::create(conn);
-    if wallet.is_none() {
-        return HttpResponse::InternalServerError().json("Failed to create wallet");
-    }
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct LoginPending {
+    pub message: String,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct ConfirmLoginForm {
+    pub token: String,
+}
 
-    let (user, errors) = User::create(conn, user.0.name.clone(), user.0.email.clone(), wallet.unwrap().id, user.0.password.clone());
-    if errors.is_some() {
-        return HttpResponse::InternalServerError().json(errors.unwrap());
-    } else {
-        return HttpResponse::Ok().json(user);
+#[utoipa::path(
+    post,
+    path = "/user",
+    tag = "users",
+    request_body = UserForm,
+    responses(
+        (status = 200, description = "The created user", body = User),
+        (status = 400, description = "Missing required fields", body = ErrorBody),
+        (status = 409, description = "Email already exists", body = ErrorBody),
+    )
+)]
+pub async fn create_user(user: web::Json<UserForm>, pool: web::Data<DbPool>) -> HttpResponse {
+    let user = user.into_inner();
+    let result = db::run(&pool, move |conn| {
+        let wallet = Wallet::create(conn)?.ok_or_else(|| AppError::Internal("Failed to create wallet".to_string()))?;
+        Ok(User::create(conn, user.name, user.email, wallet.id, user.password)?)
+    });
+    match result.await {
+        Ok((Some(user), None)) => HttpResponse::Ok().json(user),
+        Ok((_, Some(error))) if error == EMAIL_EXISTS => AppError::Conflict(error).error_response(),
+        Ok((_, Some(error))) => AppError::Validation(error).error_response(),
+        Ok((None, None)) => AppError::Internal("Failed to create user".to_string()).error_response(),
+        Err(err) => err.error_response(),
     }
-    
 }
 
+#[utoipa::path(
+    get,
+    path = "/user",
+    tag = "users",
+    responses((status = 200, description = "Every user", body = [User])),
+    security(("bearer_auth" = []))
+)]
 pub async fn index(pool: web::Data<DbPool>) -> HttpResponse {
-    let conn = &mut pool.get().unwrap();
-    let users = User::list(conn);
-    if users.is_empty() {
-        HttpResponse::InternalServerError().json("Failed to get users")
-    } else {
-        HttpResponse::Ok().json(users)
+    match db::run(&pool, move |conn| Ok(User::list(conn)?)).await {
+        Ok(users) => HttpResponse::Ok().json(users),
+        Err(err) => err.error_response(),
     }
 }
 
+#[utoipa::path(
+    get,
+    path = "/user/{user_id}",
+    tag = "users",
+    params(("user_id" = String, Path, description = "User ID")),
+    responses(
+        (status = 200, description = "The user", body = User),
+        (status = 404, description = "User not found", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
 pub async fn get(pool: web::Data<DbPool>, user_id: web::Path<String>) -> HttpResponse {
-    let conn = &mut pool.get().unwrap();
-    match User::find_by_id(conn, user_id.into_inner()) {
-        Some(user) => HttpResponse::Ok().json(user),
-        None => HttpResponse::InternalServerError().json("Failed to get user")
+    match db::run(&pool, move |conn| Ok(User::find_by_id(conn, user_id.into_inner())?)).await {
+        Ok(Some(user)) => HttpResponse::Ok().json(user),
+        Ok(None) => AppError::NotFound("User not found".to_string()).error_response(),
+        Err(err) => err.error_response(),
+    }
+}
+
+#[utoipa::path(
+    patch,
+    path = "/user/{user_id}",
+    tag = "users",
+    params(("user_id" = String, Path, description = "User ID")),
+    request_body = UserChanges,
+    responses(
+        (status = 200, description = "The updated user, and the email waiting to be confirmed if it was changed", body = UserUpdate),
+        (status = 400, description = "Invalid name, email or password", body = ErrorBody),
+        (status = 403, description = "Another user's profile, or a wrong current password", body = ErrorBody),
+        (status = 404, description = "User not found", body = ErrorBody),
+        (status = 409, description = "Email already exists", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn update(req: HttpRequest, pool: web::Data<DbPool>, user_id: web::Path<String>, changes: web::Json<UserChanges>) -> HttpResponse {
+    let user_id = user_id.into_inner();
+    if jwt::user_id(&req).is_some_and(|actor_id| actor_id != user_id) {
+        return AppError::Forbidden("Only the user can update their profile".to_string()).error_response();
+    }
+
+    match db::run(&pool, move |conn| Ok(User::update(conn, user_id, changes.into_inner())?)).await {
+        Ok((Some(update), None)) => HttpResponse::Ok().json(update),
+        Ok((_, errors)) => {
+            let error = errors.unwrap_or_default();
+            match error.as_str() {
+                USER_NOT_FOUND => AppError::NotFound(error).error_response(),
+                WRONG_PASSWORD => AppError::Forbidden(error).error_response(),
+                EMAIL_EXISTS => AppError::Conflict(error).error_response(),
+                _ => AppError::Validation(error).error_response(),
+            }
+        }
+        Err(err) => err.error_response(),
     }
 }
 
+#[utoipa::path(
+    delete,
+    path = "/user/{user_id}",
+    tag = "users",
+    params(("user_id" = String, Path, description = "User ID")),
+    responses(
+        (status = 200, description = "The user was deleted", body = String),
+        (status = 404, description = "User not found", body = ErrorBody),
+        (status = 409, description = "Trades or other records still refer to the user", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
 pub async fn delete(pool: web::Data<DbPool>, user_id: web::Path<String>) -> HttpResponse {
-    let conn = &mut pool.get().unwrap();
-    match User::delete(conn, user_id.into_inner()) {
-        true => HttpResponse::Ok().json("deleted"),
-        false => HttpResponse::InternalServerError().json("Failed to delete user")
+    match db::run(&pool, move |conn| Ok(User::delete(conn, user_id.into_inner())?)).await {
+        Ok(true) => HttpResponse::Ok().json("deleted"),
+        Ok(false) => AppError::NotFound("User not found".to_string()).error_response(),
+        Err(err) => err.error_response(),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/login",
+    tag = "users",
+    request_body = LoginForm,
+    responses(
+        (status = 200, description = "Access and refresh tokens", body = TokenPair),
+        (status = 202, description = "The login is from a new device and must be confirmed from the account's email", body = LoginPending),
+        (status = 401, description = "Invalid email or password, with the attempts left before the account is locked", body = ErrorBody),
+        (status = 423, description = "Too many failed attempts: the account is locked, see `Retry-After`", body = ErrorBody),
+    )
+)]
+pub async fn login(req: HttpRequest, pool: web::Data<DbPool>, user: web::Json<LoginForm>) -> HttpResponse {
+    let user_agent = req.headers().get(USER_AGENT).and_then(|value| value.to_str().ok()).unwrap_or("unknown").to_string();
+    let ip_address = req.connection_info().realip_remote_addr().unwrap_or("unknown").to_string();
+    let form = user.into_inner();
+    let result = db::run(&pool, move |conn| {
+        let user = match User::login(conn, form.email, form.password)? {
+            LoginOutcome::Success(user) => user,
+            LoginOutcome::Failed { attempts_left } => {
+                let message = format!("Invalid email or password; {} attempt(s) left before the account is locked", attempts_left);
+                return Err(AppError::Unauthorized(message));
+            }
+            LoginOutcome::Locked { until } => return Err(AppError::Locked(until)),
+        };
+
+        // No tokens until a new device is confirmed.
+        match KnownDevice::check_in(conn, &user, &user_agent, &ip_address)? {
+            DeviceCheck::Trusted(_) => Ok(Some(issue_tokens(conn, user.id)?)),
+            DeviceCheck::NeedsConfirmation => Ok(None),
+        }
+    });
+    match result.await {
+        Ok(Some(tokens)) => HttpResponse::Ok().json(tokens),
+        Ok(None) => HttpResponse::Accepted().json(LoginPending {
+            message: "This device is new: confirm the login with the token emailed to you".to_string(),
+        }),
+        Err(err) => err.error_response(),
     }
 }
 
-pub async fn login(pool: web::Data<DbPool>, user: web::Json<LoginForm>) -> HttpResponse {
-    let conn = &mut pool.get().unwrap();
-    match User::login(conn, user.0.email.clone(), user.0.password.clone()) {
-        Some(user) => HttpResponse::Ok().json(user),
-        None => HttpResponse::InternalServerError().json("Failed to login")
+#[utoipa::path(
+    post,
+    path = "/login/confirm",
+    tag = "users",
+    request_body = ConfirmLoginForm,
+    responses(
+        (status = 200, description = "Access and refresh tokens", body = TokenPair),
+        (status = 401, description = "Invalid or expired confirmation token", body = ErrorBody),
+    )
+)]
+pub async fn confirm_login(pool: web::Data<DbPool>, form: web::Json<ConfirmLoginForm>) -> HttpResponse {
+    let result = db::run(&pool, move |conn| match KnownDevice::confirm(conn, &form.token)? {
+        Some(device) => Ok(Some(issue_tokens(conn, device.user_id)?)),
+        None => Ok(None),
+    });
+    match result.await {
+        Ok(Some(tokens)) => HttpResponse::Ok().json(tokens),
+        Ok(None) => AppError::Unauthorized("Invalid or expired confirmation token".to_string()).error_response(),
+        Err(err) => err.error_response(),
     }
 }
 
 pub fn init_routes(cfg: &mut web::ServiceConfig) {
     cfg.service(
         web::resource("/user")
-            .route(web::post().to(create_user))
+            .route(web::post().to(create_user).wrap(LoadShed::high_priority()))
             .route(web::get().to(index).wrap(JwtGuard))
     )
     .service(
         web::resource("/user/{user_id}")
             .route(web::get().to(get)).wrap(JwtGuard)
+            .route(web::patch().to(update).wrap(JwtGuard))
             .route(web::delete().to(delete).wrap(JwtGuard))
     )
     .service(
         web::resource("/login")
-            .route(web::post().to(login))
+            .route(web::post().to(login).wrap(LoadShed::high_priority()))
+    )
+    .service(
+        web::resource("/login/confirm")
+            .route(web::post().to(confirm_login).wrap(LoadShed::high_priority()))
     );
 }
\ No newline at end of file
@@ -0,0 +1,28 @@
+use actix_web::http::header::AUTHORIZATION;
+use actix_web::http::StatusCode;
+use actix_web::{test, web, App};
+
+use crate::db::fixtures::test_pool;
+use crate::error::ErrorBody;
+use super::jwt::create_jwt;
+use super::user::init_routes;
+
+#[actix_web::test]
+async fn missing_users_are_not_found() {
+    std::env::set_var("JWT_SECRET", "test_secret");
+    let app = test::init_service(App::new().app_data(web::Data::new(test_pool())).configure(init_routes)).await;
+    let token = create_jwt("missing".to_string()).unwrap();
+
+    let requests = [
+        test::TestRequest::get(),
+        test::TestRequest::patch().set_json(serde_json::json!({"name": "Renamed"})),
+        test::TestRequest::delete(),
+    ];
+    for request in requests {
+        let req = request.uri("/user/missing").insert_header((AUTHORIZATION, token.clone())).to_request();
+        let response = test::call_service(&app, req).await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        let body: ErrorBody = test::read_body_json(response).await;
+        assert_eq!((body.code.as_str(), body.message.as_str()), ("not_found", "User not found"));
+    }
+}
@@ -0,0 +1,75 @@
+//! This module exposes the build information of the running server.
+//!
+//! The values are embedded at compile time by the build script: the crate version, the git commit, the build
+//! timestamp, the enabled cargo features and the most recent database migration. `GET /version` returns them as
+//! JSON, and `banner` renders the same information for the startup log, so operators can verify what is deployed.
+//!
+//! # Examples
+//!
+//! ```text
+//! GET /version
+//!
+//! 200 OK
+//! {
+//!     "version": "0.1.0",
+//!     "git_sha": "f11315a",
+//!     "build_timestamp": "2023-08-20T12:00:00Z",
+//!     "features": [],
+//!     "migrations_revision": "2023-08-13-014914_create"
+//! }
+//! ```
+
+use actix_web::{web, HttpResponse};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BuildInfo {
+    pub version: String,
+    pub git_sha: String,
+    pub build_timestamp: String,
+    pub features: Vec<String>,
+    pub migrations_revision: String,
+}
+
+impl BuildInfo {
+    pub fn current() -> Self {
+        let build_timestamp = env!("BUILD_TIMESTAMP")
+            .parse::<i64>()
+            .ok()
+            .and_then(|timestamp| chrono::DateTime::<chrono::Utc>::from_timestamp(timestamp, 0))
+            .map(|datetime| datetime.to_rfc3339_opts(chrono::SecondsFormat::Secs, true))
+            .unwrap_or_default();
+
+        BuildInfo {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            git_sha: env!("BUILD_GIT_SHA").to_string(),
+            build_timestamp,
+            features: env!("BUILD_FEATURES")
+                .split(',')
+                .filter(|feature| !feature.is_empty())
+                .map(|feature| feature.to_string())
+                .collect(),
+            migrations_revision: env!("BUILD_MIGRATIONS_REVISION").to_string(),
+        }
+    }
+}
+
+pub fn banner() -> String {
+    let info = BuildInfo::current();
+    format!(
+        "Trade Management System v{} ({}) built {} | migrations: {} | features: [{}]",
+        info.version,
+        info.git_sha,
+        info.build_timestamp,
+        info.migrations_revision,
+        info.features.join(", ")
+    )
+}
+
+pub async fn version() -> HttpResponse {
+    HttpResponse::Ok().json(BuildInfo::current())
+}
+
+pub fn init_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(web::resource("/version").route(web::get().to(version)));
+}
@@ -0,0 +1,393 @@
+//! This module defines wallet-related endpoints, including transfers guarded by multi-signature approvals.
+//!
+//! The provided functions include:
+//!
+//! - `set_policy`: Configures the approval threshold, quorum and approvers of a wallet. Once a wallet has a policy, only
+//!   admins can change it.
+//! - `request_transfer`: Requests a transfer out of a wallet on behalf of the caller; large transfers stay pending
+//!   until approved.
+//! - `get_transfer`: Retrieves a transfer together with the approvals collected so far.
+//! - `approve_transfer`: Records the caller's approval and executes the transfer once the quorum is met. Callers who
+//!   are not approvers of the wallet get `403`.
+//! - `deposit`: Credits a wallet (`POST /wallet/{wallet_id}/deposit` with `{"amount": ..., "reference": ...}`).
+//! - `withdraw`: Debits a wallet (`POST /wallet/{wallet_id}/withdraw`). Amounts above the wallet's approval threshold
+//!   are refused with `409`; they must be requested as transfers.
+//! - `list_transactions`: Lists the wallet ledger, newest first (`GET /wallet/{wallet_id}/transactions`, paged with
+//!   `limit`/`offset`, default 100, at most 1000, or with `cursor` as described in `services::pagination`).
+//! - `list_trades`: Lists the trades settled against a wallet, newest first (`GET /wallet/{wallet_id}/trades`), paged
+//!   like the ledger and optionally limited to `start_date`/`end_date` (the ranges of `GET /trade`, in the timezone
+//!   `tz`). The trades are wrapped in `{"data", "next_cursor", "totals"}`, where `totals` holds the number of trades,
+//!   the volume (`traded_amount * execution_price`) and the fees of every trade in the range, not only the page.
+//!   `next_cursor` is only set on cursor pages.
+//! - `address_qr`: Renders a QR code of a wallet's deposit address (`GET /wallet/{wallet_id}/addresses/{address}/qr.png`
+//!   or `qr.svg`, with an optional `size` in pixels). Images come from the in-memory `utils::qr::QrCache` and are sent
+//!   with a `Cache-Control` header so clients keep them too.
+//! - `regenerate_hash`: Gives a wallet a new hash drawn from OS entropy (`POST /admin/wallets/{wallet_id}/regenerate-hash`),
+//!   recording the previous hash in the audit log. Admins only.
+//! - `list_user_wallets`: Lists a user's named wallets with their balance, oldest first (`GET /user/{user_id}/wallets`).
+//!   The wallet created at signup has `primary` set.
+//! - `create_user_wallet`: Opens a new, empty wallet for a user (`POST /user/{user_id}/wallets` with `{"name": ...}`).
+//!   Names must be unique per user; see `db::models::user_wallet`.
+//! - `init_routes`: Initializes routes for handling wallet-related HTTP requests.
+//!
+//! Policies, transfer requests and reads, deposits, withdrawals, the ledger and the trades are limited to the wallet's
+//! owner and admins (`ADMIN_USER_IDS`), as are a user's wallets: any of them may be used, not only the one created at
+//! signup.
+//!
+//! Errors are returned as `crate::error::AppError` JSON bodies with a machine-readable `code`.
+//!
+//! # Note
+//! All routes are wrapped with the `JwtGuard` middleware for secure access.
+
+use actix_web::http::header::CACHE_CONTROL;
+use actix_web::{web, HttpRequest, HttpResponse, ResponseError};
+use diesel::SqliteConnection;
+use serde::{Deserialize, Serialize};
+
+use crate::db::models::trade::{Trade, TradeFilter, TradeSort, TradeTotals};
+use crate::db::models::transfer::{ApprovalPolicy, Transfer, TransferApproval, NOT_AN_APPROVER};
+use crate::db::models::user::{User, USER_NOT_FOUND};
+use crate::db::models::user_wallet::UserWallet;
+use crate::db::models::wallet::Wallet;
+use crate::db::models::wallet_transaction::WalletTransaction;
+use crate::db::{self, DbPool};
+use crate::error::AppError;
+use crate::middleware::jwt_guard::JwtGuard;
+use crate::services::jwt;
+use crate::services::pagination::{self, Page};
+use crate::services::trade::date_filter;
+use crate::utils::qr::{self, QrCache, QrFormat};
+
+#[derive(Serialize, Deserialize)]
+pub struct PolicyForm {
+    pub threshold: f32,
+    pub required_approvals: i32,
+    pub approvers: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct TransferForm {
+    pub amount: f32,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct LedgerForm {
+    pub amount: f32,
+    pub reference: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct LedgerQuery {
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+    pub cursor: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct WalletTradesQuery {
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+    pub cursor: Option<String>,
+    pub start_date: Option<String>,
+    pub end_date: Option<String>,
+    pub tz: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct WalletTrades {
+    pub data: Vec<Trade>,
+    pub next_cursor: Option<String>,
+    pub totals: TradeTotals,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct WalletNameForm {
+    pub name: String,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct QrQuery {
+    pub size: Option<u32>,
+}
+
+#[derive(Serialize)]
+pub struct TransferResponse {
+    pub transfer: Transfer,
+    pub approvals: Vec<TransferApproval>,
+}
+
+const DEFAULT_PAGE_SIZE: i64 = 100;
+const MAX_PAGE_SIZE: i64 = 1000;
+
+fn ensure_wallet_owner(conn: &mut SqliteConnection, caller_id: Option<&str>, wallet_id: &str) -> Result<(), AppError> {
+    let caller_id = match caller_id {
+        Some(caller_id) => caller_id,
+        None => return Ok(()),
+    };
+    if jwt::is_admin(caller_id) {
+        return Ok(());
+    }
+    if UserWallet::owns(conn, caller_id, wallet_id)? {
+        Ok(())
+    } else {
+        Err(AppError::Forbidden("Wallet belongs to another user".to_string()))
+    }
+}
+
+fn ensure_self_or_admin(req: &HttpRequest, user_id: &str) -> Result<(), AppError> {
+    match jwt::user_id(req) {
+        Some(caller_id) if caller_id != user_id && !jwt::is_admin(&caller_id) => {
+            Err(AppError::Forbidden("Only the user can manage their wallets".to_string()))
+        }
+        _ => Ok(()),
+    }
+}
+
+pub async fn set_policy(req: HttpRequest, pool: web::Data<DbPool>, wallet_id: web::Path<String>, policy: web::Json<PolicyForm>) -> HttpResponse {
+    let (caller_id, wallet_id, policy) = (jwt::user_id(&req), wallet_id.into_inner(), policy.into_inner());
+    let result = db::run(&pool, move |conn| {
+        ensure_wallet_owner(conn, caller_id.as_deref(), &wallet_id)?;
+        // Otherwise the owner could swap the approvers for themselves and skip the quorum.
+        if ApprovalPolicy::find_by_wallet(conn, wallet_id.clone())?.is_some() && !caller_id.as_deref().is_some_and(jwt::is_admin) {
+            return Err(AppError::Forbidden("Only an admin can change an existing approval policy".to_string()));
+        }
+        Ok(ApprovalPolicy::set(conn, wallet_id, policy.threshold, policy.required_approvals, policy.approvers)?)
+    });
+    match result.await {
+        Ok((Some(policy), None)) => HttpResponse::Ok().json(policy),
+        Ok((_, errors)) => AppError::Validation(errors.unwrap_or_default()).error_response(),
+        Err(err) => err.error_response(),
+    }
+}
+
+pub async fn request_transfer(req: HttpRequest, pool: web::Data<DbPool>, wallet_id: web::Path<String>, transfer: web::Json<TransferForm>) -> HttpResponse {
+    let requested_by = match jwt::user_id(&req) {
+        Some(requested_by) => requested_by,
+        None => return AppError::Unauthorized("missing token".to_string()).error_response(),
+    };
+    let (wallet_id, amount) = (wallet_id.into_inner(), transfer.amount);
+    let result = db::run(&pool, move |conn| {
+        ensure_wallet_owner(conn, Some(requested_by.as_str()), &wallet_id)?;
+        Ok(Transfer::request(conn, wallet_id, requested_by, amount)?)
+    });
+    match result.await {
+        Ok((Some(transfer), None)) => HttpResponse::Ok().json(transfer),
+        Ok((_, errors)) => AppError::Validation(errors.unwrap_or_default()).error_response(),
+        Err(err) => err.error_response(),
+    }
+}
+
+pub async fn get_transfer(req: HttpRequest, pool: web::Data<DbPool>, transfer_id: web::Path<String>) -> HttpResponse {
+    let (caller_id, transfer_id) = (jwt::user_id(&req), transfer_id.into_inner());
+    let result = db::run(&pool, move |conn| {
+        let transfer = match Transfer::find_by_id(conn, transfer_id.clone())? {
+            Some(transfer) => transfer,
+            None => return Err(AppError::NotFound("Transfer not found".to_string())),
+        };
+        ensure_wallet_owner(conn, caller_id.as_deref(), &transfer.wallet_id)?;
+        Ok(TransferResponse { transfer, approvals: Transfer::approvals(conn, transfer_id)? })
+    });
+    match result.await {
+        Ok(transfer) => HttpResponse::Ok().json(transfer),
+        Err(err) => err.error_response(),
+    }
+}
+
+pub async fn approve_transfer(req: HttpRequest, pool: web::Data<DbPool>, transfer_id: web::Path<String>) -> HttpResponse {
+    let approver_id = match jwt::user_id(&req) {
+        Some(approver_id) => approver_id,
+        None => return AppError::Unauthorized("missing token".to_string()).error_response(),
+    };
+    match db::run(&pool, move |conn| Ok(Transfer::approve(conn, transfer_id.into_inner(), approver_id)?)).await {
+        Ok((Some(transfer), None)) => HttpResponse::Ok().json(transfer),
+        Ok((_, Some(error))) if error == NOT_AN_APPROVER => AppError::Forbidden(error).error_response(),
+        Ok((_, errors)) => AppError::Validation(errors.unwrap_or_default()).error_response(),
+        Err(err) => err.error_response(),
+    }
+}
+
+pub async fn deposit(req: HttpRequest, pool: web::Data<DbPool>, wallet_id: web::Path<String>, form: web::Json<LedgerForm>) -> HttpResponse {
+    let (caller_id, wallet_id, form) = (jwt::user_id(&req), wallet_id.into_inner(), form.into_inner());
+    let result = db::run(&pool, move |conn| {
+        ensure_wallet_owner(conn, caller_id.as_deref(), &wallet_id)?;
+        Ok(WalletTransaction::deposit(conn, wallet_id, form.amount, form.reference)?)
+    });
+    match result.await {
+        Ok((Some(entry), None)) => HttpResponse::Ok().json(entry),
+        Ok((_, errors)) => AppError::Validation(errors.unwrap_or_default()).error_response(),
+        Err(err) => err.error_response(),
+    }
+}
+
+pub async fn withdraw(req: HttpRequest, pool: web::Data<DbPool>, wallet_id: web::Path<String>, form: web::Json<LedgerForm>) -> HttpResponse {
+    let (caller_id, wallet_id, form) = (jwt::user_id(&req), wallet_id.into_inner(), form.into_inner());
+    let result = db::run(&pool, move |conn| {
+        ensure_wallet_owner(conn, caller_id.as_deref(), &wallet_id)?;
+        if ApprovalPolicy::find_by_wallet(conn, wallet_id.clone())?.is_some_and(|policy| policy.requires_approval(form.amount)) {
+            return Err(AppError::Conflict("Withdrawals above the approval threshold must be requested as transfers".to_string()));
+        }
+        Ok(WalletTransaction::withdraw(conn, wallet_id, form.amount, form.reference)?)
+    });
+    match result.await {
+        Ok((Some(entry), None)) => HttpResponse::Ok().json(entry),
+        Ok((_, errors)) => AppError::Validation(errors.unwrap_or_default()).error_response(),
+        Err(err) => err.error_response(),
+    }
+}
+
+pub async fn list_transactions(req: HttpRequest, pool: web::Data<DbPool>, wallet_id: web::Path<String>, params: web::Query<LedgerQuery>) -> HttpResponse {
+    let limit = params.limit.unwrap_or(DEFAULT_PAGE_SIZE);
+    let offset = params.offset.unwrap_or(0);
+    if !(1..=MAX_PAGE_SIZE).contains(&limit) || offset < 0 {
+        return AppError::Validation(format!("Error: limit must be between 1 and {} and offset non-negative", MAX_PAGE_SIZE)).error_response();
+    }
+    let cursor = match pagination::parse_cursor(params.cursor.as_deref(), params.offset) {
+        Ok(cursor) => cursor,
+        Err(err) => return err.error_response(),
+    };
+
+    let (caller_id, wallet_id) = (jwt::user_id(&req), wallet_id.into_inner());
+    let paged = cursor.is_some();
+    let result = db::run(&pool, move |conn| {
+        ensure_wallet_owner(conn, caller_id.as_deref(), &wallet_id)?;
+        Ok(match cursor {
+            Some(before) => WalletTransaction::list_before(conn, wallet_id, before.as_ref(), limit)?,
+            None => WalletTransaction::list_for_wallet(conn, wallet_id, limit, offset)?,
+        })
+    });
+    match result.await {
+        Ok(entries) if paged => HttpResponse::Ok().json(Page {
+            next_cursor: pagination::next_cursor(&entries, limit, |entry| (entry.created_at, entry.id.clone())),
+            data: entries,
+        }),
+        Ok(entries) => HttpResponse::Ok().json(entries),
+        Err(err) => err.error_response(),
+    }
+}
+
+pub async fn list_trades(req: HttpRequest, pool: web::Data<DbPool>, wallet_id: web::Path<String>, params: web::Query<WalletTradesQuery>) -> HttpResponse {
+    let limit = params.limit.unwrap_or(DEFAULT_PAGE_SIZE);
+    let offset = params.offset.unwrap_or(0);
+    if !(1..=MAX_PAGE_SIZE).contains(&limit) || offset < 0 {
+        return AppError::Validation(format!("Error: limit must be between 1 and {} and offset non-negative", MAX_PAGE_SIZE)).error_response();
+    }
+    let cursor = match pagination::parse_cursor(params.cursor.as_deref(), params.offset) {
+        Ok(cursor) => cursor,
+        Err(err) => return err.error_response(),
+    };
+    let (start_date, end_date) = match date_filter(&params.start_date, &params.end_date, params.tz.as_deref()) {
+        Ok(range) => range,
+        Err(err) => return AppError::Validation(format!("Error: {}", err)).error_response(),
+    };
+
+    let (caller_id, wallet_id) = (jwt::user_id(&req), wallet_id.into_inner());
+    let result = db::run(&pool, move |conn| {
+        ensure_wallet_owner(conn, caller_id.as_deref(), &wallet_id)?;
+        if Wallet::find_by_id(conn, wallet_id.clone())?.is_none() {
+            return Err(AppError::NotFound("Wallet not found".to_string()));
+        }
+
+        let filter = TradeFilter { wallet_id: Some(wallet_id), start_date, end_date, ..Default::default() };
+        let totals = Trade::totals(conn, &filter)?;
+        let (data, next_cursor) = match cursor {
+            Some(before) => {
+                let trades = Trade::search_before(conn, &filter, before.as_ref(), limit)?;
+                let next_cursor = pagination::next_cursor(&trades, limit, |trade| (trade.created_at, trade.id.clone()));
+                (trades, next_cursor)
+            }
+            None => (Trade::search(conn, &filter, TradeSort::default(), limit, offset)?, None),
+        };
+        Ok(WalletTrades { data, next_cursor, totals })
+    });
+    match result.await {
+        Ok(trades) => HttpResponse::Ok().json(trades),
+        Err(err) => err.error_response(),
+    }
+}
+
+pub async fn list_user_wallets(req: HttpRequest, pool: web::Data<DbPool>, user_id: web::Path<String>) -> HttpResponse {
+    let user_id = user_id.into_inner();
+    if let Err(err) = ensure_self_or_admin(&req, &user_id) {
+        return err.error_response();
+    }
+
+    let result = db::run(&pool, move |conn| {
+        if User::find_by_id(conn, user_id.clone())?.is_none() {
+            return Err(AppError::NotFound(USER_NOT_FOUND.to_string()));
+        }
+        Ok(UserWallet::list_for_user(conn, &user_id)?)
+    });
+    match result.await {
+        Ok(wallets) => HttpResponse::Ok().json(wallets),
+        Err(err) => err.error_response(),
+    }
+}
+
+pub async fn create_user_wallet(req: HttpRequest, pool: web::Data<DbPool>, user_id: web::Path<String>, form: web::Json<WalletNameForm>) -> HttpResponse {
+    let user_id = user_id.into_inner();
+    if let Err(err) = ensure_self_or_admin(&req, &user_id) {
+        return err.error_response();
+    }
+
+    match db::run(&pool, move |conn| Ok(UserWallet::create(conn, &user_id, &form.name)?)).await {
+        Ok((Some(wallet), None)) => HttpResponse::Ok().json(wallet),
+        Ok((_, errors)) => match errors.unwrap_or_default() {
+            error if error == USER_NOT_FOUND => AppError::NotFound(error).error_response(),
+            error => AppError::Validation(error).error_response(),
+        },
+        Err(err) => err.error_response(),
+    }
+}
+
+pub async fn address_qr(pool: web::Data<DbPool>, cache: web::Data<QrCache>, path: web::Path<(String, String, String)>, params: web::Query<QrQuery>) -> HttpResponse {
+    let (wallet_id, address, format) = path.into_inner();
+    let format = match QrFormat::parse(&format) {
+        Some(format) => format,
+        None => return AppError::NotFound("Unsupported image format".to_string()).error_response(),
+    };
+
+    match db::run(&pool, move |conn| Ok(Wallet::find_by_id(conn, wallet_id)?)).await {
+        Ok(Some(wallet)) if wallet.hash == address => (),
+        Ok(_) => return AppError::NotFound("Address not found for this wallet".to_string()).error_response(),
+        Err(err) => return err.error_response(),
+    }
+
+    match cache.get_or_render(&address, params.size.unwrap_or(qr::DEFAULT_SIZE), format) {
+        Ok(image) => HttpResponse::Ok()
+            .content_type(format.content_type())
+            .insert_header((CACHE_CONTROL, "private, max-age=86400"))
+            .body(image.as_ref().clone()),
+        Err(err) => AppError::Validation(err).error_response(),
+    }
+}
+
+pub async fn regenerate_hash(req: HttpRequest, pool: web::Data<DbPool>, wallet_id: web::Path<String>) -> HttpResponse {
+    let admin_id = match jwt::require_admin(&req) {
+        Ok(admin_id) => admin_id,
+        Err(err) => return err.error_response(),
+    };
+
+    match db::run(&pool, move |conn| Ok(Wallet::regenerate_hash(conn, wallet_id.into_inner(), &admin_id)?)).await {
+        Ok(Some(wallet)) => HttpResponse::Ok().json(wallet),
+        Ok(None) => AppError::NotFound("Wallet not found".to_string()).error_response(),
+        Err(err) => err.error_response(),
+    }
+}
+
+pub fn init_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(web::resource("/wallet/{wallet_id}/policy").route(web::put().to(set_policy).wrap(JwtGuard)))
+        .service(web::resource("/wallet/{wallet_id}/transfer").route(web::post().to(request_transfer).wrap(JwtGuard)))
+        .service(web::resource("/transfer/{transfer_id}").route(web::get().to(get_transfer).wrap(JwtGuard)))
+        .service(web::resource("/transfer/{transfer_id}/approve").route(web::post().to(approve_transfer).wrap(JwtGuard)))
+        .service(web::resource("/wallet/{wallet_id}/deposit").route(web::post().to(deposit).wrap(JwtGuard)))
+        .service(web::resource("/wallet/{wallet_id}/withdraw").route(web::post().to(withdraw).wrap(JwtGuard)))
+        .service(web::resource("/wallet/{wallet_id}/transactions").route(web::get().to(list_transactions).wrap(JwtGuard)))
+        .service(web::resource("/wallet/{wallet_id}/trades").route(web::get().to(list_trades).wrap(JwtGuard)))
+        .service(
+            web::resource("/user/{user_id}/wallets")
+                .route(web::get().to(list_user_wallets).wrap(JwtGuard))
+                .route(web::post().to(create_user_wallet).wrap(JwtGuard)),
+        )
+        .service(web::resource("/wallet/{wallet_id}/addresses/{address}/qr.{format}").route(web::get().to(address_qr).wrap(JwtGuard)))
+        .service(web::resource("/admin/wallets/{wallet_id}/regenerate-hash").route(web::post().to(regenerate_hash).wrap(JwtGuard)));
+}
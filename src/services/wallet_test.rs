@@ -0,0 +1,116 @@
+use actix_web::http::header::AUTHORIZATION;
+use actix_web::http::StatusCode;
+use actix_web::test::{call_service, init_service, read_body_json, TestRequest};
+use actix_web::{web, App};
+
+use crate::db::fixtures::{create_trade, test_pool, trade, user};
+use super::jwt::create_jwt;
+use super::trade::TradeForm;
+use super::wallet::init_routes;
+
+#[actix_web::test]
+async fn wallet_trades_are_paged_with_totals_for_the_owner() {
+    std::env::set_var("JWT_SECRET", "test_secret");
+    let pool = test_pool();
+    let conn = &mut pool.get().unwrap();
+    let user = user(conn, "owner");
+    // 2023-09-01, 2023-09-02 and 2023-09-03 at noon UTC.
+    for timestamp in [1_693_569_600, 1_693_656_000, 1_693_742_400] {
+        create_trade(conn, &TradeForm { final_price: Some(110.0), timestamp: Some(timestamp), ..trade(&user.id, &user.wallet_id) });
+    }
+
+    let app = init_service(App::new().app_data(web::Data::new(pool.clone())).configure(init_routes)).await;
+    let token = create_jwt(user.id.clone()).unwrap();
+    let uri = format!("/wallet/{}/trades?cursor=&limit=2&start_date=2023-09-02", user.wallet_id);
+    let req = TestRequest::get().uri(&uri).insert_header((AUTHORIZATION, token)).to_request();
+    let response = call_service(&app, req).await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let body: serde_json::Value = read_body_json(response).await;
+    assert_eq!(body["data"].as_array().unwrap().len(), 2);
+    assert!(body["next_cursor"].is_string());
+    assert_eq!(body["totals"]["trades"], 2);
+    assert_eq!(body["totals"]["volume"], 200.0);
+
+    let stranger = create_jwt("stranger".to_string()).unwrap();
+    let req = TestRequest::get().uri(&format!("/wallet/{}/trades", user.wallet_id)).insert_header((AUTHORIZATION, stranger)).to_request();
+    assert_eq!(call_service(&app, req).await.status(), StatusCode::FORBIDDEN);
+}
+
+#[actix_web::test]
+async fn users_list_and_open_their_named_wallets() {
+    std::env::set_var("JWT_SECRET", "test_secret");
+    let pool = test_pool();
+    let conn = &mut pool.get().unwrap();
+    let user = user(conn, "owner");
+
+    let app = init_service(App::new().app_data(web::Data::new(pool.clone())).configure(init_routes)).await;
+    let token = create_jwt(user.id.clone()).unwrap();
+    let uri = format!("/user/{}/wallets", user.id);
+    let req = TestRequest::post().uri(&uri).insert_header((AUTHORIZATION, token.clone())).set_json(serde_json::json!({"name": "Savings"})).to_request();
+    let response = call_service(&app, req).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let created: serde_json::Value = read_body_json(response).await;
+    assert_eq!((created["name"].as_str(), created["primary"].as_bool()), (Some("Savings"), Some(false)));
+
+    let req = TestRequest::post().uri(&uri).insert_header((AUTHORIZATION, token.clone())).set_json(serde_json::json!({"name": "savings"})).to_request();
+    assert_eq!(call_service(&app, req).await.status(), StatusCode::BAD_REQUEST);
+
+    let req = TestRequest::get().uri(&uri).insert_header((AUTHORIZATION, token.clone())).to_request();
+    let wallets: serde_json::Value = read_body_json(call_service(&app, req).await).await;
+    let names: Vec<&str> = wallets.as_array().unwrap().iter().map(|wallet| wallet["name"].as_str().unwrap()).collect();
+    assert_eq!(names, vec!["Main", "Savings"]);
+
+    // The new wallet is the user's own.
+    let req = TestRequest::get().uri(&format!("/wallet/{}/trades", created["id"].as_str().unwrap())).insert_header((AUTHORIZATION, token)).to_request();
+    assert_eq!(call_service(&app, req).await.status(), StatusCode::OK);
+
+    let stranger = create_jwt("stranger".to_string()).unwrap();
+    let req = TestRequest::get().uri(&uri).insert_header((AUTHORIZATION, stranger)).to_request();
+    assert_eq!(call_service(&app, req).await.status(), StatusCode::FORBIDDEN);
+}
+
+#[actix_web::test]
+async fn transfers_are_managed_by_the_owner_and_approved_by_approvers() {
+    std::env::set_var("JWT_SECRET", "test_secret");
+    let pool = test_pool();
+    let conn = &mut pool.get().unwrap();
+    let (owner, approver) = (user(conn, "owner"), user(conn, "approver"));
+    let app = init_service(App::new().app_data(web::Data::new(pool.clone())).configure(init_routes)).await;
+    let (token, approver_token) = (create_jwt(owner.id.clone()).unwrap(), create_jwt(approver.id.clone()).unwrap());
+    let stranger = create_jwt("stranger".to_string()).unwrap();
+
+    let policy = serde_json::json!({"threshold": 50.0, "required_approvals": 1, "approvers": [approver.id]});
+    let uri = format!("/wallet/{}/policy", owner.wallet_id);
+    let req = TestRequest::put().uri(&uri).insert_header((AUTHORIZATION, stranger.clone())).set_json(&policy).to_request();
+    assert_eq!(call_service(&app, req).await.status(), StatusCode::FORBIDDEN);
+    let req = TestRequest::put().uri(&uri).insert_header((AUTHORIZATION, token.clone())).set_json(&policy).to_request();
+    assert_eq!(call_service(&app, req).await.status(), StatusCode::OK);
+
+    // The owner cannot replace the approvers once the policy is set.
+    let takeover = serde_json::json!({"threshold": 50.0, "required_approvals": 1, "approvers": [owner.id]});
+    let req = TestRequest::put().uri(&uri).insert_header((AUTHORIZATION, token.clone())).set_json(&takeover).to_request();
+    assert_eq!(call_service(&app, req).await.status(), StatusCode::FORBIDDEN);
+
+    let uri = format!("/wallet/{}/transfer", owner.wallet_id);
+    let req = TestRequest::post().uri(&uri).insert_header((AUTHORIZATION, stranger.clone())).set_json(serde_json::json!({"amount": 100.0})).to_request();
+    assert_eq!(call_service(&app, req).await.status(), StatusCode::FORBIDDEN);
+    let req = TestRequest::post().uri(&uri).insert_header((AUTHORIZATION, token.clone())).set_json(serde_json::json!({"amount": 100.0})).to_request();
+    let transfer: serde_json::Value = read_body_json(call_service(&app, req).await).await;
+    assert_eq!((transfer["requested_by"].as_str(), transfer["status"].as_str()), (Some(owner.id.as_str()), Some("Pending")));
+
+    let uri = format!("/transfer/{}", transfer["id"].as_str().unwrap());
+    let req = TestRequest::get().uri(&uri).insert_header((AUTHORIZATION, stranger.clone())).to_request();
+    assert_eq!(call_service(&app, req).await.status(), StatusCode::FORBIDDEN);
+    let req = TestRequest::get().uri(&uri).insert_header((AUTHORIZATION, token.clone())).to_request();
+    assert_eq!(call_service(&app, req).await.status(), StatusCode::OK);
+
+    // Owning the wallet does not make the owner an approver.
+    for caller in [stranger, token] {
+        let req = TestRequest::post().uri(&format!("{}/approve", uri)).insert_header((AUTHORIZATION, caller)).to_request();
+        assert_eq!(call_service(&app, req).await.status(), StatusCode::FORBIDDEN);
+    }
+    let req = TestRequest::post().uri(&format!("{}/approve", uri)).insert_header((AUTHORIZATION, approver_token)).to_request();
+    let approved: serde_json::Value = read_body_json(call_service(&app, req).await).await;
+    assert_eq!(approved["status"], "Executed");
+}
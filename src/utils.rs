@@ -2,4 +2,61 @@
 pub mod hash;
 
 /// The date module contains utility functions for handling dates.
-pub mod date;
\ No newline at end of file
+pub mod date;
+
+/// The circuit_breaker module contains a circuit breaker for calls to external integrations.
+pub mod circuit_breaker;
+
+/// The kmeans module contains k-means clustering over feature vectors.
+pub mod kmeans;
+
+/// The quantile module contains a streaming quantile estimator.
+pub mod quantile;
+
+/// The signed_url module contains utility functions for signing and verifying shareable URLs.
+pub mod signed_url;
+
+/// The qr module contains QR code rendering and an in-memory cache of rendered images.
+pub mod qr;
+
+/// The template module contains a minimal placeholder template engine for user-facing text.
+pub mod template;
+
+/// The metrics module contains a registry of counters and histograms rendered as OpenMetrics text.
+pub mod metrics;
+
+// Import date tests (only included in test builds)
+#[cfg(test)]
+mod date_test;
+
+// Import circuit breaker tests (only included in test builds)
+#[cfg(test)]
+mod circuit_breaker_test;
+
+// Import signed URL tests (only included in test builds)
+#[cfg(test)]
+mod signed_url_test;
+
+// Import quantile sketch tests (only included in test builds)
+#[cfg(test)]
+mod quantile_test;
+
+// Import k-means tests (only included in test builds)
+#[cfg(test)]
+mod kmeans_test;
+
+// Import QR code tests (only included in test builds)
+#[cfg(test)]
+mod qr_test;
+
+// Import template tests (only included in test builds)
+#[cfg(test)]
+mod template_test;
+
+// Import metrics registry tests (only included in test builds)
+#[cfg(test)]
+mod metrics_test;
+
+// Import hash tests (only included in test builds)
+#[cfg(test)]
+mod hash_test;
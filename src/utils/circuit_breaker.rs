@@ -0,0 +1,180 @@
+//! This module provides a circuit breaker for calls to external integrations.
+//!
+//! A `CircuitBreaker` counts consecutive failures of the wrapped integration. Once the failure threshold is reached the
+//! breaker opens and rejects calls immediately, so a slow or failing third party cannot hold request handlers or
+//! background jobs hostage. After the reset timeout elapses a single probe call is let through (half-open): a success
+//! closes the breaker again, a failure re-opens it for another timeout.
+//!
+//! The breaker can be driven directly with `call` for synchronous work, or with `allow_request`, `record_success` and
+//! `record_failure` around async calls. `status` returns a serializable snapshot suitable for metrics endpoints.
+//!
+//! Breakers created with `register` are also tracked in a process-wide registry, and `statuses` returns the snapshot of
+//! each of them that is still alive; `/metrics/business` reports them (see `services::metrics`). The registry only holds
+//! weak references, so dropping the owner of a breaker removes it.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use std::time::Duration;
+//! use crate::utils::circuit_breaker::{self, CircuitBreaker, CircuitError};
+//!
+//! let breaker = circuit_breaker::register(CircuitBreaker::new("price_feed", 5, Duration::from_secs(30)));
+//!
+//! match breaker.call(|| fetch_price("ETH")) {
+//!     Ok(price) => println!("ETH price: {}", price),
+//!     Err(CircuitError::Open) => println!("Price feed unavailable, using cached price"),
+//!     Err(CircuitError::Inner(err)) => println!("Price feed error: {}", err),
+//! }
+//! ```
+
+use serde::Serialize;
+use std::sync::{Arc, Mutex, Weak};
+use std::time::{Duration, Instant};
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+pub enum CircuitState {
+    Closed,
+    Open,
+    HalfOpen,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum CircuitError<E> {
+    Open,
+    Inner(E),
+}
+
+#[derive(Debug, Serialize)]
+pub struct CircuitBreakerStatus {
+    pub name: String,
+    pub state: CircuitState,
+    pub consecutive_failures: u32,
+}
+
+struct Inner {
+    state: CircuitState,
+    consecutive_failures: u32,
+    opened_at: Option<Instant>,
+    probe_in_flight: bool,
+}
+
+pub struct CircuitBreaker {
+    name: String,
+    failure_threshold: u32,
+    reset_timeout: Duration,
+    inner: Mutex<Inner>,
+}
+
+static REGISTRY: Mutex<Vec<Weak<CircuitBreaker>>> = Mutex::new(Vec::new());
+
+pub fn register(breaker: CircuitBreaker) -> Arc<CircuitBreaker> {
+    let breaker = Arc::new(breaker);
+    let mut registry = REGISTRY.lock().unwrap();
+    registry.retain(|registered| registered.strong_count() > 0);
+    registry.push(Arc::downgrade(&breaker));
+    breaker
+}
+
+pub fn statuses() -> Vec<CircuitBreakerStatus> {
+    let registry = REGISTRY.lock().unwrap();
+    registry.iter().filter_map(Weak::upgrade).map(|breaker| breaker.status()).collect()
+}
+
+impl CircuitBreaker {
+    pub fn new(name: &str, failure_threshold: u32, reset_timeout: Duration) -> Self {
+        CircuitBreaker {
+            name: name.to_string(),
+            failure_threshold: failure_threshold.max(1),
+            reset_timeout,
+            inner: Mutex::new(Inner {
+                state: CircuitState::Closed,
+                consecutive_failures: 0,
+                opened_at: None,
+                probe_in_flight: false,
+            }),
+        }
+    }
+
+    pub fn state(&self) -> CircuitState {
+        let mut inner = self.inner.lock().unwrap();
+        self.refresh(&mut inner);
+        inner.state
+    }
+
+    pub fn status(&self) -> CircuitBreakerStatus {
+        let mut inner = self.inner.lock().unwrap();
+        self.refresh(&mut inner);
+        CircuitBreakerStatus {
+            name: self.name.clone(),
+            state: inner.state,
+            consecutive_failures: inner.consecutive_failures,
+        }
+    }
+
+    pub fn allow_request(&self) -> bool {
+        let mut inner = self.inner.lock().unwrap();
+        self.refresh(&mut inner);
+        match inner.state {
+            CircuitState::Closed => true,
+            CircuitState::Open => false,
+            CircuitState::HalfOpen => {
+                if inner.probe_in_flight {
+                    false
+                } else {
+                    inner.probe_in_flight = true;
+                    true
+                }
+            }
+        }
+    }
+
+    pub fn record_success(&self) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.state = CircuitState::Closed;
+        inner.consecutive_failures = 0;
+        inner.opened_at = None;
+        inner.probe_in_flight = false;
+    }
+
+    pub fn record_failure(&self) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.consecutive_failures += 1;
+        inner.probe_in_flight = false;
+        if inner.state == CircuitState::HalfOpen || inner.consecutive_failures >= self.failure_threshold {
+            inner.state = CircuitState::Open;
+            inner.opened_at = Some(Instant::now());
+        }
+    }
+
+    pub fn call<T, E, F>(&self, f: F) -> Result<T, CircuitError<E>>
+    where
+        F: FnOnce() -> Result<T, E>,
+    {
+        if !self.allow_request() {
+            return Err(CircuitError::Open);
+        }
+
+        match f() {
+            Ok(value) => {
+                self.record_success();
+                Ok(value)
+            }
+            Err(err) => {
+                self.record_failure();
+                Err(CircuitError::Inner(err))
+            }
+        }
+    }
+
+    fn refresh(&self, inner: &mut Inner) {
+        if inner.state != CircuitState::Open {
+            return;
+        }
+        if let Some(opened_at) = inner.opened_at {
+            if opened_at.elapsed() >= self.reset_timeout {
+                inner.state = CircuitState::HalfOpen;
+                inner.probe_in_flight = false;
+            }
+        }
+    }
+}
@@ -0,0 +1,73 @@
+use std::thread::sleep;
+use std::time::Duration;
+
+use super::circuit_breaker::{self, CircuitBreaker, CircuitError, CircuitState};
+
+fn fail() -> Result<(), &'static str> {
+    Err("unavailable")
+}
+
+fn succeed() -> Result<(), &'static str> {
+    Ok(())
+}
+
+#[test]
+fn opens_after_threshold() {
+    let breaker = CircuitBreaker::new("test", 2, Duration::from_secs(60));
+
+    assert_eq!(breaker.call(fail), Err(CircuitError::Inner("unavailable")));
+    assert_eq!(breaker.state(), CircuitState::Closed);
+    assert_eq!(breaker.call(fail), Err(CircuitError::Inner("unavailable")));
+    assert_eq!(breaker.state(), CircuitState::Open);
+    assert_eq!(breaker.call(succeed), Err(CircuitError::Open));
+}
+
+#[test]
+fn success_resets_failures() {
+    let breaker = CircuitBreaker::new("test", 2, Duration::from_secs(60));
+
+    let _ = breaker.call(fail);
+    assert_eq!(breaker.call(succeed), Ok(()));
+    let _ = breaker.call(fail);
+
+    assert_eq!(breaker.state(), CircuitState::Closed);
+    assert_eq!(breaker.status().consecutive_failures, 1);
+}
+
+#[test]
+fn half_open_allows_single_probe() {
+    let breaker = CircuitBreaker::new("test", 1, Duration::from_millis(10));
+
+    let _ = breaker.call(fail);
+    sleep(Duration::from_millis(20));
+
+    assert_eq!(breaker.state(), CircuitState::HalfOpen);
+    assert!(breaker.allow_request());
+    assert!(!breaker.allow_request());
+
+    breaker.record_success();
+    assert_eq!(breaker.state(), CircuitState::Closed);
+}
+
+#[test]
+fn failed_probe_reopens() {
+    let breaker = CircuitBreaker::new("test", 1, Duration::from_millis(10));
+
+    let _ = breaker.call(fail);
+    sleep(Duration::from_millis(20));
+    let _ = breaker.call(fail);
+
+    assert_eq!(breaker.state(), CircuitState::Open);
+}
+
+#[test]
+fn registered_breakers_are_reported_while_alive() {
+    let breaker = circuit_breaker::register(CircuitBreaker::new("registered_test", 2, Duration::from_secs(60)));
+    let _ = breaker.call(fail);
+
+    let status = circuit_breaker::statuses().into_iter().find(|status| status.name == "registered_test").unwrap();
+    assert_eq!((status.state, status.consecutive_failures), (CircuitState::Closed, 1));
+
+    drop(breaker);
+    assert!(!circuit_breaker::statuses().iter().any(|status| status.name == "registered_test"));
+}
@@ -1,34 +1,156 @@
-//! This module provides a function to convert a Unix timestamp to a NaiveDateTime
+//! This module provides helpers for converting timestamps and resolving date ranges.
 //!
-//! The `timestamp_to_naive_date_time` function takes a Unix timestamp as input and returns a `NaiveDateTime` object.
-//! The function utilizes the `chrono` crate to perform the conversion.
+//! The `timestamp_to_naive_date_time` function takes a Unix timestamp as input and returns a `NaiveDateTime` object,
+//! or `None` when the timestamp falls outside the range that can be represented.
+//!
+//! The `parse_range` function resolves the `start_date`/`end_date` parameters of the analytics endpoints. Besides
+//! absolute dates, which are passed through unchanged, either bound may be a relative range expression:
+//!
+//! - `today`: from midnight today until now.
+//! - `last_<N>d` (e.g. `last_7d`): the last N days including today, until now.
+//! - `mtd`: month to date.
+//! - `ytd`: year to date.
+//! - `prev_month`: the whole previous calendar month.
+//!
+//! A relative `start_date` resolves to the start of its range and a relative `end_date` to the end of its range. When
+//! `end_date` is omitted, the end of the `start_date` range is used. Day and month boundaries are computed in the
+//! given IANA timezone (UTC by default) and converted back to UTC, the timezone trades are stored in.
+//!
+//! `parse_span` resolves a single `range` expression into a pair of `NaiveDateTime`s: either a relative range or
+//! `<from>..<to>`, where each side is a `YYYY-MM-DD` date (the whole day) or a relative range.
+//! The functions utilize the `chrono` and `chrono-tz` crates to perform the conversions.
 //!
 //! # Examples
 //!
 //! ```
-//! use chrono::{prelude::DateTime, NaiveDateTime};
-//! use chrono::Utc;
-//! use std::time::{UNIX_EPOCH, Duration};
-//!
-//! pub fn timestamp_to_naive_date_time(time: i64) -> NaiveDateTime {
-//!     let d = UNIX_EPOCH + Duration::from_secs(time as u64);
-//!     let datetime = DateTime::<Utc>::from(d);
-//!     datetime.naive_utc()
+//! use chrono::{DateTime, NaiveDateTime, Utc};
+//!
+//! pub fn timestamp_to_naive_date_time(time: i64) -> Option<NaiveDateTime> {
+//!     if time < 0 {
+//!         return None;
+//!     }
+//!     DateTime::<Utc>::from_timestamp(time, 0).map(|datetime| datetime.naive_utc())
 //! }
 //!
 //! let unix_timestamp = 1629620736; // Example Unix timestamp
-//! let naive_date_time = timestamp_to_naive_date_time(unix_timestamp);
+//! let naive_date_time = timestamp_to_naive_date_time(unix_timestamp).unwrap();
 //!
 //! println!("Unix Timestamp: {}", unix_timestamp);
 //! println!("Converted NaiveDateTime: {}", naive_date_time);
+//!
+//! // Resolve "the previous month" for a trader in São Paulo.
+//! let (start_date, end_date) = parse_range("prev_month", "", Some("America/Sao_Paulo")).unwrap();
 //! ```
 
-use chrono::{prelude::DateTime, NaiveDateTime};
-use chrono::Utc;
-use std::time::{UNIX_EPOCH, Duration};
+use chrono::{DateTime, Datelike, Days, Duration, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use chrono_tz::Tz;
+
+const DATE_TIME_FORMAT: &str = "%Y-%m-%d %H:%M:%S%.f";
+
+pub fn timestamp_to_naive_date_time(time: i64) -> Option<NaiveDateTime> {
+    if time < 0 {
+        return None;
+    }
+    DateTime::<Utc>::from_timestamp(time, 0).map(|datetime| datetime.naive_utc())
+}
+
+pub fn parse_timezone(tz: Option<&str>) -> Result<Tz, String> {
+    match tz {
+        Some(tz) if !tz.is_empty() => tz.parse::<Tz>().map_err(|_| format!("Unknown timezone: {}", tz)),
+        _ => Ok(Tz::UTC),
+    }
+}
+
+fn start_of_day(tz: Tz, date: NaiveDate) -> NaiveDateTime {
+    let midnight = date.and_hms_opt(0, 0, 0).expect("midnight is a valid time");
+    // Some zones skip midnight when entering daylight saving time; the day then starts an hour later.
+    tz.from_local_datetime(&midnight)
+        .earliest()
+        .or_else(|| tz.from_local_datetime(&(midnight + Duration::hours(1))).earliest())
+        .map(|datetime| datetime.naive_utc())
+        .unwrap_or(midnight)
+}
+
+fn first_of_month(year: i32, month: u32) -> NaiveDate {
+    NaiveDate::from_ymd_opt(year, month, 1).expect("first day of month is a valid date")
+}
+
+fn resolve_relative(expression: &str, tz: Tz, now: DateTime<Utc>) -> Option<(NaiveDateTime, NaiveDateTime)> {
+    let expression = expression.to_ascii_lowercase();
+    let today = now.with_timezone(&tz).date_naive();
+    let now = now.naive_utc();
+
+    match expression.as_str() {
+        "today" => Some((start_of_day(tz, today), now)),
+        "mtd" => Some((start_of_day(tz, first_of_month(today.year(), today.month())), now)),
+        "ytd" => Some((start_of_day(tz, first_of_month(today.year(), 1)), now)),
+        "prev_month" => {
+            let this_month = first_of_month(today.year(), today.month());
+            let prev_month = match today.month() {
+                1 => first_of_month(today.year() - 1, 12),
+                month => first_of_month(today.year(), month - 1),
+            };
+            Some((start_of_day(tz, prev_month), start_of_day(tz, this_month) - Duration::nanoseconds(1)))
+        }
+        _ => {
+            let days = expression.strip_prefix("last_")?.strip_suffix('d')?.parse::<u64>().ok()?;
+            let first_day = today.checked_sub_days(Days::new(days.checked_sub(1)?))?;
+            Some((start_of_day(tz, first_day), now))
+        }
+    }
+}
+
+pub fn parse_range_at(start_date: &str, end_date: &str, tz: Option<&str>, now: DateTime<Utc>) -> Result<(String, String), String> {
+    let tz = parse_timezone(tz)?;
+    if start_date.is_empty() {
+        return Err("Start date is required".to_string());
+    }
+
+    let start_range = resolve_relative(start_date, tz, now);
+    let start = match start_range {
+        Some((start, _)) => start.format(DATE_TIME_FORMAT).to_string(),
+        None => start_date.to_string(),
+    };
+
+    let end = match (end_date.is_empty(), start_range) {
+        (true, Some((_, end))) => end.format(DATE_TIME_FORMAT).to_string(),
+        (true, None) => return Err("End date is required unless start date is a relative range".to_string()),
+        (false, _) => match resolve_relative(end_date, tz, now) {
+            Some((_, end)) => end.format(DATE_TIME_FORMAT).to_string(),
+            None => end_date.to_string(),
+        },
+    };
+
+    Ok((start, end))
+}
+
+pub fn parse_range(start_date: &str, end_date: &str, tz: Option<&str>) -> Result<(String, String), String> {
+    parse_range_at(start_date, end_date, tz, Utc::now())
+}
+
+fn resolve_bound(expression: &str, tz: Tz, now: DateTime<Utc>) -> Result<(NaiveDateTime, NaiveDateTime), String> {
+    if let Some(range) = resolve_relative(expression, tz, now) {
+        return Ok(range);
+    }
+
+    let date = NaiveDate::parse_from_str(expression, "%Y-%m-%d").map_err(|_| format!("Invalid date: {}", expression))?;
+    let next_day = date.succ_opt().ok_or_else(|| format!("Invalid date: {}", expression))?;
+    Ok((start_of_day(tz, date), start_of_day(tz, next_day) - Duration::nanoseconds(1)))
+}
+
+pub fn parse_span_at(expression: &str, tz: Option<&str>, now: DateTime<Utc>) -> Result<(NaiveDateTime, NaiveDateTime), String> {
+    let tz = parse_timezone(tz)?;
+    let (start, end) = match expression.split_once("..") {
+        Some((from, to)) => (resolve_bound(from.trim(), tz, now)?.0, resolve_bound(to.trim(), tz, now)?.1),
+        None => resolve_bound(expression.trim(), tz, now)?,
+    };
+
+    if start > end {
+        return Err("Range start must not be after its end".to_string());
+    }
+    Ok((start, end))
+}
 
-pub fn timestamp_to_naive_date_time(time: i64) -> NaiveDateTime {
-    let d = UNIX_EPOCH + Duration::from_secs(time as u64);
-    let datetime = DateTime::<Utc>::from(d);
-    datetime.naive_utc()
-}
\ No newline at end of file
+pub fn parse_span(expression: &str, tz: Option<&str>) -> Result<(NaiveDateTime, NaiveDateTime), String> {
+    parse_span_at(expression, tz, Utc::now())
+}
@@ -0,0 +1,76 @@
+use chrono::{DateTime, TimeZone, Utc};
+
+use super::date::{parse_range_at, parse_span_at, parse_timezone};
+
+fn now() -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2023, 3, 15, 12, 30, 0).unwrap()
+}
+
+#[test]
+fn absolute_dates_pass_through() {
+    let range = parse_range_at("2023-01-01", "2023-02-01", None, now()).unwrap();
+
+    assert_eq!(range, ("2023-01-01".to_string(), "2023-02-01".to_string()));
+}
+
+#[test]
+fn resolves_relative_ranges_in_utc() {
+    assert_eq!(
+        parse_range_at("last_7d", "", None, now()).unwrap(),
+        ("2023-03-09 00:00:00".to_string(), "2023-03-15 12:30:00".to_string())
+    );
+    assert_eq!(parse_range_at("mtd", "", None, now()).unwrap().0, "2023-03-01 00:00:00");
+    assert_eq!(parse_range_at("YTD", "", None, now()).unwrap().0, "2023-01-01 00:00:00");
+    assert_eq!(parse_range_at("today", "", None, now()).unwrap().0, "2023-03-15 00:00:00");
+}
+
+#[test]
+fn previous_month_covers_the_whole_month() {
+    let (start, end) = parse_range_at("prev_month", "", None, now()).unwrap();
+    assert_eq!(start, "2023-02-01 00:00:00");
+    assert_eq!(end, "2023-02-28 23:59:59.999999999");
+
+    let january = Utc.with_ymd_and_hms(2023, 1, 10, 0, 0, 0).unwrap();
+    assert_eq!(parse_range_at("prev_month", "", None, january).unwrap().0, "2022-12-01 00:00:00");
+}
+
+#[test]
+fn resolves_boundaries_in_the_given_timezone() {
+    // 2023-03-01 00:30 UTC is still February in São Paulo (UTC-3).
+    let now = Utc.with_ymd_and_hms(2023, 3, 1, 0, 30, 0).unwrap();
+
+    let (start, _) = parse_range_at("mtd", "", Some("America/Sao_Paulo"), now).unwrap();
+
+    assert_eq!(start, "2023-02-01 03:00:00");
+}
+
+#[test]
+fn mixes_relative_and_absolute_bounds() {
+    let (start, end) = parse_range_at("2023-01-01", "prev_month", None, now()).unwrap();
+
+    assert_eq!(start, "2023-01-01");
+    assert_eq!(end, "2023-02-28 23:59:59.999999999");
+}
+
+#[test]
+fn rejects_invalid_input() {
+    assert!(parse_range_at("", "2023-01-01", None, now()).is_err());
+    assert!(parse_range_at("2023-01-01", "", None, now()).is_err());
+    assert!(parse_range_at("last_0d", "", None, now()).is_err());
+    assert!(parse_timezone(Some("Mars/Olympus_Mons")).is_err());
+    assert!(parse_timezone(None).is_ok());
+}
+
+#[test]
+fn resolves_spans_of_dates_and_relative_ranges() {
+    let (start, end) = parse_span_at("2023-01-01..2023-01-31", None, now()).unwrap();
+    assert_eq!(start.to_string(), "2023-01-01 00:00:00");
+    assert_eq!(end.to_string(), "2023-01-31 23:59:59.999999999");
+
+    let (start, end) = parse_span_at("last_7d", None, now()).unwrap();
+    assert_eq!((start.to_string(), end.to_string()), ("2023-03-09 00:00:00".to_string(), "2023-03-15 12:30:00".to_string()));
+
+    assert_eq!(parse_span_at("2023-02-01..mtd", None, now()).unwrap().1.to_string(), "2023-03-15 12:30:00");
+    assert!(parse_span_at("2023-02-01..2023-01-01", None, now()).is_err());
+    assert!(parse_span_at("yesterday", None, now()).is_err());
+}
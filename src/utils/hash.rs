@@ -3,14 +3,18 @@
 //! The provided functions include:
 //!
 //! - `generate_keypair`: Generates a new pair of secret and public keys using the `secp256k1` elliptic curve algorithm.
+//!   Keys are drawn directly from the operating system's entropy source (`OsRng`), never from a seeded generator.
 //! - `generate_hash`: Generates a SHA-256 hash from the provided input data.
 //! - `new_hash`: Generates a new SHA-256 hash using a randomly generated public key.
+//! - `entropy_self_test`: Checks the OS entropy source before any key is generated; `main` refuses to start when it
+//!   fails. It reads two blocks, which must differ and not be constant, and a 20,000-bit sample that must pass the
+//!   FIPS 140-2 monobit test (`monobit`).
 //!
 //! # Examples
 //!
 //! ```
 //! use secp256k1::{
-//!     rand::{self, rngs, SeedableRng},
+//!     rand::{rngs::OsRng, RngCore},
 //!     PublicKey, SecretKey,
 //! };
 //! use sha2::{Digest, Sha256};
@@ -32,21 +36,24 @@
 //! }
 //!
 //! // Example usage
+//! entropy_self_test().expect("Entropy self-test failed");
 //! let hash = new_hash();
 //! println!("Generated Hash: {}", hash);
 //! ```
 
 use secp256k1::{
-    rand::{self, rngs, SeedableRng},
+    rand::{rngs::OsRng, RngCore},
     PublicKey, SecretKey,
 };
 use sha2::{Digest, Sha256};
 use hex::encode;
 
+// 20,000 bits, the sample size of the FIPS 140-2 monobit test.
+pub const MONOBIT_SAMPLE_BYTES: usize = 2500;
+
 fn generate_keypair() -> (SecretKey, PublicKey) {
     let secp = secp256k1::Secp256k1::new();
-    let mut rng = rngs::StdRng::seed_from_u64(rand::random::<u64>());
-    secp.generate_keypair(&mut rng)
+    secp.generate_keypair(&mut OsRng)
 }
 
 fn generate_hash(input: &[u8]) -> String {
@@ -56,12 +63,34 @@ fn generate_hash(input: &[u8]) -> String {
     encode(result)
 }
 
+// A hex-encoded SHA-256 digest is always 64 characters.
 pub fn new_hash() -> String {
     let (_secret_key, public_key) = generate_keypair();
-    let mut hash = generate_hash(&public_key.serialize());
-    while hash.len() != 64 {
-        let (_secret_key, public_key) = generate_keypair();
-        hash = generate_hash(&public_key.serialize());
+    generate_hash(&public_key.serialize())
+}
+
+// FIPS 140-2: the number of ones in a 20,000-bit sample must be strictly between 9725 and 10275.
+pub fn monobit(sample: &[u8]) -> bool {
+    let ones: u32 = sample.iter().map(|byte| byte.count_ones()).sum();
+    sample.len() == MONOBIT_SAMPLE_BYTES && ones > 9725 && ones < 10275
+}
+
+pub fn entropy_self_test() -> Result<(), String> {
+    let mut first = [0u8; 32];
+    let mut second = [0u8; 32];
+    let mut sample = [0u8; MONOBIT_SAMPLE_BYTES];
+    for buffer in [&mut first[..], &mut second[..], &mut sample[..]] {
+        OsRng.try_fill_bytes(buffer).map_err(|err| format!("The OS entropy source failed: {}", err))?;
     }
-    hash
-}
\ No newline at end of file
+
+    if first == second {
+        return Err("The OS entropy source returned the same block twice".to_string());
+    }
+    if [&first, &second].iter().any(|block| block.iter().all(|byte| *byte == block[0])) {
+        return Err("The OS entropy source returned a constant block".to_string());
+    }
+    if !monobit(&sample) {
+        return Err("The OS entropy source failed the monobit test".to_string());
+    }
+    Ok(())
+}
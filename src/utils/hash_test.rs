@@ -0,0 +1,22 @@
+use super::hash::{entropy_self_test, monobit, new_hash, MONOBIT_SAMPLE_BYTES};
+
+#[test]
+fn hashes_are_unique_hex_digests() {
+    let (first, second) = (new_hash(), new_hash());
+    assert_eq!(first.len(), 64);
+    assert!(first.chars().all(|c| c.is_ascii_hexdigit()));
+    assert_ne!(first, second);
+}
+
+#[test]
+fn monobit_rejects_biased_samples() {
+    assert!(!monobit(&[0u8; MONOBIT_SAMPLE_BYTES]));
+    assert!(!monobit(&[0xffu8; MONOBIT_SAMPLE_BYTES]));
+    assert!(monobit(&[0x5au8; MONOBIT_SAMPLE_BYTES]));
+    assert!(!monobit(&[0x5au8; 16]));
+}
+
+#[test]
+fn os_entropy_passes_the_self_test() {
+    assert_eq!(entropy_self_test(), Ok(()));
+}
@@ -0,0 +1,112 @@
+//! This module provides a small k-means implementation for clustering feature vectors.
+//!
+//! `standardize` rescales every dimension to zero mean and unit variance so that features measured in different
+//! units (notional, P&L, time of day) contribute equally to distances. `kmeans` then runs Lloyd's algorithm from a
+//! deterministic farthest-point initialisation, so the same input always produces the same clusters.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use crate::utils::kmeans::{kmeans, standardize};
+//!
+//! let mut points = vec![vec![1.0, 1.0], vec![1.2, 0.9], vec![9.0, 8.5], vec![8.8, 9.1]];
+//! standardize(&mut points);
+//! let clusters = kmeans(&points, 2, 50);
+//! assert_eq!(clusters.assignments[0], clusters.assignments[1]);
+//! assert_ne!(clusters.assignments[0], clusters.assignments[2]);
+//! ```
+
+pub struct Clusters {
+    pub centroids: Vec<Vec<f64>>,
+    pub assignments: Vec<usize>,
+}
+
+fn distance(a: &[f64], b: &[f64]) -> f64 {
+    a.iter().zip(b).map(|(x, y)| (x - y) * (x - y)).sum()
+}
+
+fn nearest(point: &[f64], centroids: &[Vec<f64>]) -> usize {
+    centroids
+        .iter()
+        .enumerate()
+        .min_by(|(_, a), (_, b)| distance(point, a).total_cmp(&distance(point, b)))
+        .map(|(index, _)| index)
+        .unwrap_or(0)
+}
+
+fn mean(points: &[&Vec<f64>], dimensions: usize) -> Vec<f64> {
+    let mut mean = vec![0.0; dimensions];
+    for point in points {
+        for (total, value) in mean.iter_mut().zip(point.iter()) {
+            *total += value;
+        }
+    }
+    mean.iter_mut().for_each(|total| *total /= points.len() as f64);
+    mean
+}
+
+pub fn standardize(points: &mut [Vec<f64>]) {
+    let dimensions = match points.first() {
+        Some(point) => point.len(),
+        None => return,
+    };
+    let count = points.len() as f64;
+
+    for dimension in 0..dimensions {
+        let mean = points.iter().map(|point| point[dimension]).sum::<f64>() / count;
+        let variance = points.iter().map(|point| (point[dimension] - mean).powi(2)).sum::<f64>() / count;
+        let deviation = variance.sqrt();
+        for point in points.iter_mut() {
+            point[dimension] = if deviation > 0.0 { (point[dimension] - mean) / deviation } else { 0.0 };
+        }
+    }
+}
+
+pub fn kmeans(points: &[Vec<f64>], k: usize, max_iterations: usize) -> Clusters {
+    let k = k.min(points.len());
+    if k == 0 {
+        return Clusters { centroids: Vec::new(), assignments: vec![0; points.len()] };
+    }
+    let dimensions = points[0].len();
+
+    // Start from the point closest to the overall mean, then repeatedly add the point farthest from all centroids.
+    let overall = mean(&points.iter().collect::<Vec<_>>(), dimensions);
+    let mut centroids = vec![points[nearest(&overall, points)].clone()];
+    while centroids.len() < k {
+        let farthest = points
+            .iter()
+            .max_by(|a, b| {
+                let a = distance(a, &centroids[nearest(a, &centroids)]);
+                let b = distance(b, &centroids[nearest(b, &centroids)]);
+                a.total_cmp(&b)
+            })
+            .expect("points is not empty");
+        centroids.push(farthest.clone());
+    }
+
+    let mut assignments = vec![0; points.len()];
+    for _ in 0..max_iterations {
+        let next: Vec<usize> = points.iter().map(|point| nearest(point, &centroids)).collect();
+        let converged = next == assignments;
+        assignments = next;
+
+        for (cluster, centroid) in centroids.iter_mut().enumerate() {
+            let members: Vec<&Vec<f64>> = points
+                .iter()
+                .zip(&assignments)
+                .filter(|(_, assigned)| **assigned == cluster)
+                .map(|(point, _)| point)
+                .collect();
+            // An empty cluster keeps its previous centroid.
+            if !members.is_empty() {
+                *centroid = mean(&members, dimensions);
+            }
+        }
+
+        if converged {
+            break;
+        }
+    }
+
+    Clusters { centroids, assignments }
+}
@@ -0,0 +1,40 @@
+use super::kmeans::{kmeans, standardize};
+
+#[test]
+fn separates_distinct_groups() {
+    let points = vec![
+        vec![1.0, 1.0],
+        vec![1.1, 0.9],
+        vec![0.9, 1.2],
+        vec![10.0, 10.0],
+        vec![10.2, 9.8],
+        vec![-5.0, 8.0],
+    ];
+
+    let clusters = kmeans(&points, 3, 50);
+
+    assert_eq!(clusters.centroids.len(), 3);
+    assert_eq!(clusters.assignments[0], clusters.assignments[1]);
+    assert_eq!(clusters.assignments[0], clusters.assignments[2]);
+    assert_eq!(clusters.assignments[3], clusters.assignments[4]);
+    assert_ne!(clusters.assignments[0], clusters.assignments[3]);
+    assert_ne!(clusters.assignments[5], clusters.assignments[0]);
+    assert_ne!(clusters.assignments[5], clusters.assignments[3]);
+}
+
+#[test]
+fn caps_clusters_at_number_of_points() {
+    let points = vec![vec![1.0], vec![2.0]];
+
+    assert_eq!(kmeans(&points, 5, 10).centroids.len(), 2);
+    assert!(kmeans(&[], 3, 10).centroids.is_empty());
+}
+
+#[test]
+fn standardizes_dimensions() {
+    let mut points = vec![vec![1.0, 5.0], vec![3.0, 5.0]];
+
+    standardize(&mut points);
+
+    assert_eq!(points, vec![vec![-1.0, 0.0], vec![1.0, 0.0]]);
+}
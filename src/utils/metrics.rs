@@ -0,0 +1,180 @@
+//! This module provides an in-process registry of counters and histograms rendered in the OpenMetrics text format.
+//!
+//! A `Metric` describes a family (name, help text and kind); each distinct set of labels recorded against it is its
+//! own series. `increment` adds one to a counter, `set` replaces the value of a gauge and `observe` records a value
+//! in a histogram with the `buckets` of its metric. `render` writes every family recorded so far, in name order, ending with the `# EOF` marker, so the
+//! output can be scraped by Prometheus or any OpenMetrics collector.
+//!
+//! The process-wide registry behind the free functions lives for the lifetime of the server and starts empty on
+//! every restart; collectors are expected to handle counter resets. Label values should come from small, fixed sets
+//! (sources, statuses), never from IDs, or the number of series grows without bound.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use crate::utils::metrics::{self, Metric, MetricKind};
+//!
+//! const IMPORTS: Metric = Metric { name: "imports", help: "Imported trades.", kind: MetricKind::Counter, buckets: &[] };
+//!
+//! metrics::increment(&IMPORTS, &[("source", "import:email")]);
+//! assert!(metrics::render().contains("imports_total{source=\"import:email\"} 1"));
+//! ```
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::Mutex;
+
+pub const CONTENT_TYPE: &str = "application/openmetrics-text; version=1.0.0; charset=utf-8";
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MetricKind {
+    Counter,
+    Gauge,
+    Histogram,
+}
+
+#[derive(Debug)]
+pub struct Metric {
+    pub name: &'static str,
+    pub help: &'static str,
+    pub kind: MetricKind,
+    // Upper bounds of the histogram buckets, ascending; the `+Inf` bucket is implied.
+    pub buckets: &'static [f64],
+}
+
+enum Series {
+    Counter(u64),
+    Gauge(f64),
+    Histogram { counts: Vec<u64>, sum: f64, count: u64 },
+}
+
+type Labels = Vec<(String, String)>;
+
+struct Family {
+    metric: &'static Metric,
+    series: BTreeMap<Labels, Series>,
+}
+
+#[derive(Default)]
+pub struct Registry {
+    families: BTreeMap<&'static str, Family>,
+}
+
+static REGISTRY: Mutex<Registry> = Mutex::new(Registry::new());
+
+fn labels(labels: &[(&str, &str)]) -> Labels {
+    labels.iter().map(|(key, value)| (key.to_string(), value.to_string())).collect()
+}
+
+fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+fn label_set(labels: &Labels, le: Option<&str>) -> String {
+    let mut pairs: Vec<String> = labels.iter().map(|(key, value)| format!("{}=\"{}\"", key, escape(value))).collect();
+    if let Some(le) = le {
+        pairs.push(format!("le=\"{}\"", le));
+    }
+    if pairs.is_empty() {
+        String::new()
+    } else {
+        format!("{{{}}}", pairs.join(","))
+    }
+}
+
+impl Registry {
+    pub const fn new() -> Self {
+        Registry { families: BTreeMap::new() }
+    }
+
+    fn series(&mut self, metric: &'static Metric, labels: Labels) -> &mut Series {
+        let family = self.families.entry(metric.name).or_insert_with(|| Family { metric, series: BTreeMap::new() });
+        family.series.entry(labels).or_insert_with(|| match metric.kind {
+            MetricKind::Counter => Series::Counter(0),
+            MetricKind::Gauge => Series::Gauge(0.0),
+            MetricKind::Histogram => Series::Histogram { counts: vec![0; metric.buckets.len()], sum: 0.0, count: 0 },
+        })
+    }
+
+    pub fn increment(&mut self, metric: &'static Metric, labels: &[(&str, &str)]) {
+        if let Series::Counter(value) = self.series(metric, self::labels(labels)) {
+            *value += 1;
+        }
+    }
+
+    pub fn set(&mut self, metric: &'static Metric, labels: &[(&str, &str)], value: f64) {
+        if let Series::Gauge(current) = self.series(metric, self::labels(labels)) {
+            *current = value;
+        }
+    }
+
+    // Values that are not finite are dropped, since they would poison the sum.
+    pub fn observe(&mut self, metric: &'static Metric, labels: &[(&str, &str)], value: f64) {
+        if !value.is_finite() {
+            return;
+        }
+        if let Series::Histogram { counts, sum, count } = self.series(metric, self::labels(labels)) {
+            for (bound, bucket) in metric.buckets.iter().zip(counts.iter_mut()) {
+                if value <= *bound {
+                    *bucket += 1;
+                }
+            }
+            *sum += value;
+            *count += 1;
+        }
+    }
+
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        for family in self.families.values() {
+            let metric = family.metric;
+            let kind = match metric.kind {
+                MetricKind::Counter => "counter",
+                MetricKind::Gauge => "gauge",
+                MetricKind::Histogram => "histogram",
+            };
+            let _ = writeln!(out, "# TYPE {} {}", metric.name, kind);
+            if metric.name.ends_with("_seconds") {
+                let _ = writeln!(out, "# UNIT {} seconds", metric.name);
+            }
+            let _ = writeln!(out, "# HELP {} {}", metric.name, metric.help);
+
+            for (labels, series) in &family.series {
+                match series {
+                    Series::Counter(value) => {
+                        let _ = writeln!(out, "{}_total{} {}", metric.name, label_set(labels, None), value);
+                    }
+                    Series::Gauge(value) => {
+                        let _ = writeln!(out, "{}{} {}", metric.name, label_set(labels, None), value);
+                    }
+                    Series::Histogram { counts, sum, count } => {
+                        for (bound, bucket) in metric.buckets.iter().zip(counts) {
+                            let _ = writeln!(out, "{}_bucket{} {}", metric.name, label_set(labels, Some(&format!("{:?}", bound))), bucket);
+                        }
+                        let _ = writeln!(out, "{}_bucket{} {}", metric.name, label_set(labels, Some("+Inf")), count);
+                        let _ = writeln!(out, "{}_sum{} {:?}", metric.name, label_set(labels, None), sum);
+                        let _ = writeln!(out, "{}_count{} {}", metric.name, label_set(labels, None), count);
+                    }
+                }
+            }
+        }
+        out.push_str("# EOF\n");
+        out
+    }
+}
+
+pub fn increment(metric: &'static Metric, labels: &[(&str, &str)]) {
+    REGISTRY.lock().unwrap().increment(metric, labels);
+}
+
+pub fn set(metric: &'static Metric, labels: &[(&str, &str)], value: f64) {
+    REGISTRY.lock().unwrap().set(metric, labels, value);
+}
+
+pub fn observe(metric: &'static Metric, labels: &[(&str, &str)], value: f64) {
+    REGISTRY.lock().unwrap().observe(metric, labels, value);
+}
+
+pub fn render() -> String {
+    REGISTRY.lock().unwrap().render()
+}
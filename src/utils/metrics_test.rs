@@ -0,0 +1,70 @@
+use super::metrics::{Metric, MetricKind, Registry};
+
+const REQUESTS: Metric = Metric { name: "requests", help: "Handled requests.", kind: MetricKind::Counter, buckets: &[] };
+
+const LATENCY: Metric = Metric {
+    name: "latency_seconds",
+    help: "Request latency.",
+    kind: MetricKind::Histogram,
+    buckets: &[1.0, 10.0],
+};
+
+#[test]
+fn empty_registry_renders_eof() {
+    assert_eq!(Registry::new().render(), "# EOF\n");
+}
+
+#[test]
+fn counters_are_labelled() {
+    let mut registry = Registry::new();
+    registry.increment(&REQUESTS, &[("source", "manual")]);
+    registry.increment(&REQUESTS, &[("source", "manual")]);
+    registry.increment(&REQUESTS, &[("source", "say \"hi\"")]);
+
+    assert_eq!(
+        registry.render(),
+        "# TYPE requests counter\n\
+         # HELP requests Handled requests.\n\
+         requests_total{source=\"manual\"} 2\n\
+         requests_total{source=\"say \\\"hi\\\"\"} 1\n\
+         # EOF\n"
+    );
+}
+
+#[test]
+fn histograms_are_cumulative() {
+    let mut registry = Registry::new();
+    registry.observe(&LATENCY, &[], 0.5);
+    registry.observe(&LATENCY, &[], 4.0);
+    registry.observe(&LATENCY, &[], 60.0);
+    registry.observe(&LATENCY, &[], f64::NAN);
+
+    assert_eq!(
+        registry.render(),
+        "# TYPE latency_seconds histogram\n\
+         # UNIT latency_seconds seconds\n\
+         # HELP latency_seconds Request latency.\n\
+         latency_seconds_bucket{le=\"1.0\"} 1\n\
+         latency_seconds_bucket{le=\"10.0\"} 2\n\
+         latency_seconds_bucket{le=\"+Inf\"} 3\n\
+         latency_seconds_sum 64.5\n\
+         latency_seconds_count 3\n\
+         # EOF\n"
+    );
+}
+
+#[test]
+fn gauges_keep_the_last_value() {
+    const QUEUE: Metric = Metric { name: "queue_depth", help: "Queued jobs.", kind: MetricKind::Gauge, buckets: &[] };
+    let mut registry = Registry::new();
+    registry.set(&QUEUE, &[("queue", "email")], 3.0);
+    registry.set(&QUEUE, &[("queue", "email")], 1.0);
+
+    assert_eq!(
+        registry.render(),
+        "# TYPE queue_depth gauge\n\
+         # HELP queue_depth Queued jobs.\n\
+         queue_depth{queue=\"email\"} 1\n\
+         # EOF\n"
+    );
+}
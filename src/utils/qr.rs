@@ -0,0 +1,136 @@
+//! This module renders QR codes for deposit addresses and keeps recently rendered images in memory.
+//!
+//! The provided functions include:
+//!
+//! - `render`: Encodes a string as a QR code and renders it as PNG or SVG, no larger than `size` pixels per side
+//!   (rounded down to a whole number of pixels per module, including the quiet zone).
+//! - `QrCache`: A bounded in-memory cache of rendered images keyed by content, size and format. When it is full the
+//!   oldest entry is evicted. Rendering is deterministic, so entries never go stale.
+//!
+//! Sizes outside `MIN_SIZE..=MAX_SIZE` are rejected.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use crate::utils::qr::{render, QrCache, QrFormat};
+//!
+//! let png = render("3f1a...c9", 256, QrFormat::Png)?;
+//!
+//! let cache = QrCache::new(256);
+//! let svg = cache.get_or_render("3f1a...c9", 512, QrFormat::Svg)?;
+//! ```
+
+use std::collections::{HashMap, VecDeque};
+use std::io::Cursor;
+use std::sync::{Arc, Mutex};
+
+use image::{DynamicImage, ImageFormat, Luma};
+use qrcode::render::svg;
+use qrcode::QrCode;
+
+pub const MIN_SIZE: u32 = 64;
+pub const MAX_SIZE: u32 = 1024;
+pub const DEFAULT_SIZE: u32 = 256;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum QrFormat {
+    Png,
+    Svg,
+}
+
+impl QrFormat {
+    pub fn parse(format: &str) -> Option<Self> {
+        match format.to_ascii_lowercase().as_str() {
+            "png" => Some(QrFormat::Png),
+            "svg" => Some(QrFormat::Svg),
+            _ => None,
+        }
+    }
+
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            QrFormat::Png => "image/png",
+            QrFormat::Svg => "image/svg+xml",
+        }
+    }
+}
+
+pub fn render(content: &str, size: u32, format: QrFormat) -> Result<Vec<u8>, String> {
+    if !(MIN_SIZE..=MAX_SIZE).contains(&size) {
+        return Err(format!("Size must be between {} and {} pixels", MIN_SIZE, MAX_SIZE));
+    }
+
+    let code = QrCode::new(content.as_bytes()).map_err(|err| err.to_string())?;
+    match format {
+        QrFormat::Png => {
+            let image = code.render::<Luma<u8>>().max_dimensions(size, size).build();
+            let mut bytes = Vec::new();
+            DynamicImage::ImageLuma8(image)
+                .write_to(&mut Cursor::new(&mut bytes), ImageFormat::Png)
+                .map_err(|err| err.to_string())?;
+            Ok(bytes)
+        }
+        QrFormat::Svg => Ok(code
+            .render::<svg::Color>()
+            .max_dimensions(size, size)
+            .build()
+            .into_bytes()),
+    }
+}
+
+type CacheKey = (String, u32, QrFormat);
+
+#[derive(Default)]
+struct CacheState {
+    images: HashMap<CacheKey, Arc<Vec<u8>>>,
+    order: VecDeque<CacheKey>,
+}
+
+pub struct QrCache {
+    capacity: usize,
+    state: Mutex<CacheState>,
+}
+
+impl QrCache {
+    pub fn new(capacity: usize) -> Self {
+        QrCache { capacity, state: Mutex::new(CacheState::default()) }
+    }
+
+    pub fn from_env() -> Self {
+        let capacity = std::env::var("QR_CACHE_SIZE")
+            .ok()
+            .and_then(|value| value.parse().ok())
+            .unwrap_or(256);
+        Self::new(capacity)
+    }
+
+    pub fn len(&self) -> usize {
+        self.state.lock().unwrap().images.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn get_or_render(&self, content: &str, size: u32, format: QrFormat) -> Result<Arc<Vec<u8>>, String> {
+        let key = (content.to_string(), size, format);
+        if let Some(image) = self.state.lock().unwrap().images.get(&key) {
+            return Ok(image.clone());
+        }
+
+        let image = Arc::new(render(content, size, format)?);
+        if self.capacity > 0 {
+            let mut state = self.state.lock().unwrap();
+            if !state.images.contains_key(&key) {
+                while state.order.len() >= self.capacity {
+                    if let Some(oldest) = state.order.pop_front() {
+                        state.images.remove(&oldest);
+                    }
+                }
+                state.order.push_back(key.clone());
+                state.images.insert(key, image.clone());
+            }
+        }
+        Ok(image)
+    }
+}
@@ -0,0 +1,33 @@
+use super::qr::{render, QrCache, QrFormat, MAX_SIZE};
+
+const ADDRESS: &str = "3f1a9be0c2d4e6f8a0b2c4d6e8f0a2b4c6d8e0f2a4b6c8d0e2f4a6b8c0d2e4f6";
+
+#[test]
+fn renders_png_and_svg_within_size() {
+    let png = render(ADDRESS, 256, QrFormat::Png).unwrap();
+    assert!(png.starts_with(b"\x89PNG"));
+    let width = u32::from_be_bytes(png[16..20].try_into().unwrap());
+    assert!(width > 0 && width <= 256);
+
+    let svg = String::from_utf8(render(ADDRESS, 256, QrFormat::Svg).unwrap()).unwrap();
+    assert!(svg.contains("<svg"));
+
+    assert!(render(ADDRESS, 16, QrFormat::Png).is_err());
+    assert!(render(ADDRESS, MAX_SIZE + 1, QrFormat::Png).is_err());
+    assert_eq!(QrFormat::parse("PNG"), Some(QrFormat::Png));
+    assert_eq!(QrFormat::parse("gif"), None);
+}
+
+#[test]
+fn cache_reuses_and_evicts_images() {
+    let cache = QrCache::new(2);
+    let first = cache.get_or_render(ADDRESS, 128, QrFormat::Png).unwrap();
+    let again = cache.get_or_render(ADDRESS, 128, QrFormat::Png).unwrap();
+    assert!(std::sync::Arc::ptr_eq(&first, &again));
+
+    cache.get_or_render(ADDRESS, 256, QrFormat::Png).unwrap();
+    cache.get_or_render(ADDRESS, 128, QrFormat::Svg).unwrap();
+    assert_eq!(cache.len(), 2);
+    let rendered = cache.get_or_render(ADDRESS, 128, QrFormat::Png).unwrap();
+    assert!(!std::sync::Arc::ptr_eq(&first, &rendered));
+}
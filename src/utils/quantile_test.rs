@@ -0,0 +1,42 @@
+use rand::seq::SliceRandom;
+
+use super::quantile::QuantileSketch;
+
+#[test]
+fn empty_sketch_has_no_quantile() {
+    assert_eq!(QuantileSketch::new(0.5).quantile(), None);
+}
+
+#[test]
+fn small_samples_are_exact() {
+    let mut sketch = QuantileSketch::new(0.5);
+    for value in [3.0, 1.0, 2.0] {
+        sketch.observe(value);
+    }
+
+    assert_eq!(sketch.quantile(), Some(2.0));
+    assert_eq!(sketch.count(), 3);
+}
+
+#[test]
+fn estimates_quantiles_of_shuffled_stream() {
+    let mut values: Vec<f64> = (1..=10_000).map(|value| value as f64).collect();
+    values.shuffle(&mut rand::thread_rng());
+
+    for (p, expected) in [(0.5, 5_000.0), (0.9, 9_000.0), (0.99, 9_900.0)] {
+        let mut sketch = QuantileSketch::new(p);
+        values.iter().for_each(|value| sketch.observe(*value));
+
+        let estimate = sketch.quantile().unwrap();
+        assert!((estimate - expected).abs() < 200.0, "p{} estimated {}", p, estimate);
+    }
+}
+
+#[test]
+fn ignores_non_finite_values() {
+    let mut sketch = QuantileSketch::new(0.5);
+    sketch.observe(f64::NAN);
+    sketch.observe(f64::INFINITY);
+
+    assert_eq!(sketch.count(), 0);
+}
@@ -0,0 +1,45 @@
+use super::signed_url::{create_signed_url, sign, verify};
+
+fn set_secret() {
+    std::env::set_var("SIGNED_URL_SECRET", "test_secret");
+}
+
+fn split(url: &str) -> (&str, &str) {
+    url.split_once('?').unwrap()
+}
+
+#[test]
+fn signed_url_verifies() {
+    set_secret();
+    let url = create_signed_url("/profit-loss?start_date=2023-01-01&format=csv", 60).unwrap();
+    let (path, query) = split(&url);
+
+    assert!(verify(path, query, chrono::Utc::now().timestamp()));
+}
+
+#[test]
+fn tampered_query_is_rejected() {
+    set_secret();
+    let url = create_signed_url("/profit-loss?trader_id=abc", 60).unwrap();
+    let (path, query) = split(&url);
+    let query = query.replace("trader_id=abc", "trader_id=xyz");
+
+    assert!(!verify(path, &query, chrono::Utc::now().timestamp()));
+}
+
+#[test]
+fn expired_url_is_rejected() {
+    set_secret();
+    let signature = sign("/slippage", "trader_id=abc", 100);
+    let query = format!("trader_id=abc&expires=100&signature={}", signature);
+
+    assert!(verify("/slippage", &query, 100));
+    assert!(!verify("/slippage", &query, 101));
+}
+
+#[test]
+fn only_export_paths_can_be_signed() {
+    set_secret();
+    assert!(create_signed_url("/trade", 60).is_none());
+    assert!(create_signed_url("/cumulative-fees", 60).is_some());
+}
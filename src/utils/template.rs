@@ -0,0 +1,75 @@
+//! This module provides a minimal text template engine for user-facing messages.
+//!
+//! Placeholders are written as `{name}` and replaced with the value of `name`. A placeholder without a value is left
+//! as is, so a missing translation key is visible instead of silently dropped. Text inside square brackets is an
+//! optional section: it is rendered only when every placeholder in it has a value, and removed otherwise. Sections do
+//! not nest, and there is no escaping for literal braces or brackets.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use std::collections::HashMap;
+//! use crate::utils::template::render;
+//!
+//! let mut values = HashMap::new();
+//! values.insert("asset", "ETH".to_string());
+//!
+//! assert_eq!(render("Bought {asset}[, PnL {pnl}]", &values), "Bought ETH");
+//! ```
+
+use std::collections::HashMap;
+
+fn substitute(text: &str, values: &HashMap<&str, String>) -> (String, bool) {
+    let mut output = String::with_capacity(text.len());
+    let mut complete = true;
+    let mut rest = text;
+
+    while let Some(start) = rest.find('{') {
+        output.push_str(&rest[..start]);
+        match rest[start..].find('}') {
+            Some(end) => {
+                let name = &rest[start + 1..start + end];
+                match values.get(name) {
+                    Some(value) => output.push_str(value),
+                    None => {
+                        complete = false;
+                        output.push_str(&rest[start..=start + end]);
+                    }
+                }
+                rest = &rest[start + end + 1..];
+            }
+            None => {
+                output.push_str(&rest[start..]);
+                rest = "";
+            }
+        }
+    }
+    output.push_str(rest);
+
+    (output, complete)
+}
+
+pub fn render(template: &str, values: &HashMap<&str, String>) -> String {
+    let mut output = String::with_capacity(template.len());
+    let mut rest = template;
+
+    while let Some(start) = rest.find('[') {
+        output.push_str(&substitute(&rest[..start], values).0);
+        match rest[start..].find(']') {
+            Some(end) => {
+                let (section, complete) = substitute(&rest[start + 1..start + end], values);
+                if complete {
+                    output.push_str(&section);
+                }
+                rest = &rest[start + end + 1..];
+            }
+            None => {
+                output.push_str(&substitute(&rest[start..], values).0);
+                rest = "";
+            }
+        }
+    }
+    output.push_str(&substitute(rest, values).0);
+
+    output
+}
@@ -0,0 +1,25 @@
+use std::collections::HashMap;
+
+use super::template::render;
+
+#[test]
+fn replaces_placeholders_and_keeps_unknown_ones() {
+    let mut values = HashMap::new();
+    values.insert("asset", "ETH".to_string());
+    values.insert("price", "$1,850".to_string());
+
+    assert_eq!(render("{asset} at {price}", &values), "ETH at $1,850");
+    assert_eq!(render("{asset} on {chain}", &values), "ETH on {chain}");
+    assert_eq!(render("unterminated {asset", &values), "unterminated {asset");
+}
+
+#[test]
+fn optional_sections_need_every_value() {
+    let mut values = HashMap::new();
+    values.insert("asset", "ETH".to_string());
+
+    assert_eq!(render("Bought {asset}[, PnL {pnl}]", &values), "Bought ETH");
+    values.insert("pnl", "+$120".to_string());
+    assert_eq!(render("Bought {asset}[, PnL {pnl}]", &values), "Bought ETH, PnL +$120");
+    assert_eq!(render("[{asset}] [{missing}]!", &values), "ETH !");
+}